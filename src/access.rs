@@ -0,0 +1,237 @@
+// Until Every Cage is Empty
+// Copyright (C) 2025 Eli Perez
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+// Contact the developer directly at untileverycageproject@protonmail.com
+//! Rate limits for /api/ routes, in two tiers.
+//!
+//! Anonymous clients get `RATE_LIMIT` requests a minute per address (120 by
+//! default, 0 for no limit). Trusted frontends send a key from `API_KEYS`
+//! (`site:key1,app:key2`, like `ADMIN_TOKENS`) in `X-API-Key` and get
+//! `API_KEY_RATE_LIMIT` a minute per key instead, unlimited by default. An
+//! unknown key is refused rather than treated as anonymous.
+//!
+//! A client's address is the connection's, or the last `X-Forwarded-For`
+//! entry (the one the proxy in front added) with `TRUST_PROXY=1` or when the
+//! server doesn't know the connection's (on Shuttle). Limited responses say
+//! where the client stands in `RateLimit-Limit` and `RateLimit-Remaining`.
+
+use std::collections::HashMap;
+use std::error::Error;
+use std::net::SocketAddr;
+use std::sync::Mutex;
+use std::time::Instant;
+
+use axum::extract::{ConnectInfo, Request, State};
+use axum::http::{HeaderName, HeaderValue, StatusCode, header};
+use axum::middleware::Next;
+use axum::response::{IntoResponse, Response};
+
+use crate::AppState;
+use crate::settings::Settings;
+
+pub const API_KEY_HEADER: HeaderName = HeaderName::from_static("x-api-key");
+pub const RATE_LIMIT_HEADER: HeaderName = HeaderName::from_static("ratelimit-limit");
+pub const RATE_REMAINING_HEADER: HeaderName = HeaderName::from_static("ratelimit-remaining");
+
+const DEFAULT_ANONYMOUS_LIMIT: u32 = 120;
+/// Clients tracked at once. Past it those with a full allowance are
+/// forgotten, and failing that the one nearest to it, since forgetting a
+/// client only refills its allowance early.
+const MAX_CLIENTS: usize = 10_000;
+
+/// A client's allowance, refilled continuously up to its limit a minute.
+struct Bucket {
+    limit: f64,
+    tokens: f64,
+    updated: Instant,
+}
+
+impl Bucket {
+    fn refill(&mut self, now: Instant) {
+        let elapsed = now.duration_since(self.updated).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * self.limit / 60.0).min(self.limit);
+        self.updated = now;
+    }
+}
+
+/// Who a request is counted against. Handlers behind `rate_limit` find it
+/// in the request's extensions.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Client {
+    Address(String),
+    /// The name `API_KEYS` gives the key.
+    Key(String),
+}
+
+impl Client {
+    /// `key:<name>` or `address:<address>`, for records of who did what.
+    pub(crate) fn id(&self) -> String {
+        match self {
+            Client::Address(address) => format!("address:{}", address),
+            Client::Key(name) => format!("key:{}", name),
+        }
+    }
+}
+
+/// The keys and limits, and every client's allowance.
+#[derive(Default)]
+pub struct Access {
+    /// Names by key.
+    keys: HashMap<String, String>,
+    /// Requests a minute; None for no limit.
+    anonymous_limit: Option<u32>,
+    key_limit: Option<u32>,
+    trust_proxy: bool,
+    buckets: Mutex<HashMap<Client, Bucket>>,
+}
+
+/// A limit a minute from `name`, None when it's 0.
+fn limit_var(settings: &Settings, name: &str, default: Option<u32>) -> Result<Option<u32>, String> {
+    match settings.get(name) {
+        Some(value) if !value.trim().is_empty() => {
+            let limit: u32 = value
+                .trim()
+                .parse()
+                .map_err(|_| format!("Invalid {} {:?}, expected requests a minute", name, value))?;
+            Ok((limit > 0).then_some(limit))
+        }
+        _ => Ok(default),
+    }
+}
+
+impl Access {
+    pub fn from_settings(settings: &Settings) -> Result<Self, Box<dyn Error>> {
+        let keys = settings
+            .get("API_KEYS")
+            .unwrap_or_default()
+            .split(',')
+            .filter_map(|entry| entry.split_once(':'))
+            .map(|(name, key)| (key.trim().to_string(), name.trim().to_string()))
+            .filter(|(key, name)| !key.is_empty() && !name.is_empty())
+            .collect();
+        Ok(Access {
+            keys,
+            anonymous_limit: limit_var(settings, "RATE_LIMIT", Some(DEFAULT_ANONYMOUS_LIMIT))?,
+            key_limit: limit_var(settings, "API_KEY_RATE_LIMIT", None)?,
+            trust_proxy: matches!(settings.get("TRUST_PROXY"), Some("1") | Some("true")),
+            ..Default::default()
+        })
+    }
+
+    /// The client and its limit, or why the request is refused.
+    fn identify(&self, request: &Request) -> Result<(Client, Option<u32>), String> {
+        if let Some(key) = request.headers().get(API_KEY_HEADER) {
+            let name = key
+                .to_str()
+                .ok()
+                .and_then(|key| self.keys.get(key.trim()))
+                .ok_or("Unknown API key")?;
+            return Ok((Client::Key(name.clone()), self.key_limit));
+        }
+        let peer = request
+            .extensions()
+            .get::<ConnectInfo<SocketAddr>>()
+            .map(|ConnectInfo(addr)| addr.ip().to_string());
+        let forwarded = || {
+            request
+                .headers()
+                .get("x-forwarded-for")
+                .and_then(|value| value.to_str().ok())
+                .and_then(|value| value.rsplit(',').next())
+                .map(|address| address.trim().to_string())
+                .filter(|address| !address.is_empty())
+        };
+        let address = match peer {
+            Some(peer) if !self.trust_proxy => peer,
+            peer => forwarded().or(peer).unwrap_or_default(),
+        };
+        Ok((Client::Address(address), self.anonymous_limit))
+    }
+
+    /// Takes one request from the client's allowance: what's left, or None
+    /// when nothing is.
+    fn take(&self, client: Client, limit: u32) -> Option<u32> {
+        let now = Instant::now();
+        let mut buckets = self.buckets.lock().unwrap();
+        if buckets.len() >= MAX_CLIENTS && !buckets.contains_key(&client) {
+            buckets.retain(|_, bucket| {
+                bucket.refill(now);
+                bucket.tokens < bucket.limit
+            });
+            if buckets.len() >= MAX_CLIENTS {
+                let fullest = buckets
+                    .iter()
+                    .max_by(|(_, a), (_, b)| (a.tokens / a.limit).total_cmp(&(b.tokens / b.limit)))
+                    .map(|(client, _)| client.clone());
+                if let Some(fullest) = fullest {
+                    buckets.remove(&fullest);
+                }
+            }
+        }
+        let bucket = buckets.entry(client).or_insert(Bucket {
+            limit: f64::from(limit),
+            tokens: f64::from(limit),
+            updated: now,
+        });
+        bucket.refill(now);
+        if bucket.tokens < 1.0 {
+            return None;
+        }
+        bucket.tokens -= 1.0;
+        Some(bucket.tokens as u32)
+    }
+}
+
+/// Counts /api/ requests against their client's limit, refusing them with
+/// 429 and `Retry-After` once it's reached.
+pub async fn rate_limit(
+    State(state): State<AppState>,
+    mut request: Request,
+    next: Next,
+) -> Response {
+    if !request.uri().path().starts_with("/api/") {
+        return next.run(request).await;
+    }
+    let (client, limit) = match state.access.identify(&request) {
+        Ok(identified) => identified,
+        Err(e) => return (StatusCode::UNAUTHORIZED, e).into_response(),
+    };
+    request.extensions_mut().insert(client.clone());
+    let Some(limit) = limit else {
+        return next.run(request).await;
+    };
+    let remaining = state.access.take(client, limit);
+    let mut response = match remaining {
+        Some(_) => next.run(request).await,
+        None => {
+            let mut response =
+                (StatusCode::TOO_MANY_REQUESTS, "Rate limit reached").into_response();
+            // Until one request's worth has refilled.
+            let retry_after = (60.0 / f64::from(limit)).ceil() as u64;
+            response
+                .headers_mut()
+                .insert(header::RETRY_AFTER, HeaderValue::from(retry_after));
+            response
+        }
+    };
+    let headers = response.headers_mut();
+    headers.insert(RATE_LIMIT_HEADER, HeaderValue::from(limit));
+    headers.insert(
+        RATE_REMAINING_HEADER,
+        HeaderValue::from(remaining.unwrap_or(0)),
+    );
+    response
+}
@@ -0,0 +1,540 @@
+// Until Every Cage is Empty
+// Copyright (C) 2025 Eli Perez
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+// Contact the developer directly at untileverycageproject@protonmail.com
+//! The kinds of activity a facility carries out. Datasets describe them in
+//! their own words; the `type` column keeps that description and `Activity`
+//! gives the same information in a fixed set of values for filtering and
+//! map legends.
+
+use axum::Json;
+use axum::extract::{Query, State};
+use axum::http::StatusCode;
+use axum::response::IntoResponse;
+use serde::{Deserialize, Serialize};
+
+use crate::{AppState, i18n};
+
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum Activity {
+    MeatSlaughter,
+    MeatProcessing,
+    PoultrySlaughter,
+    PoultryProcessing,
+    EggProducts,
+    FishProcessing,
+    DairyProcessing,
+    ColdStorage,
+    AnimalProduction,
+    DairyFarm,
+    PigFarm,
+    PigBreedingFarm,
+    PoultryFarm,
+    EggFarm,
+    Hatchery,
+    Aquaculture,
+    FurFarm,
+    Cafo,
+    EquineExport,
+    HuntingGame,
+    Exhibition,
+    Laboratory,
+    Breeder,
+    Dealer,
+    Other,
+}
+
+/// Each activity with its API ID and the label importers write in the `type`
+/// column, in legend order.
+const ACTIVITIES: &[(Activity, &str, &str)] = &[
+    (Activity::MeatSlaughter, "meat_slaughter", "Meat Slaughter"),
+    (
+        Activity::MeatProcessing,
+        "meat_processing",
+        "Meat Processing",
+    ),
+    (
+        Activity::PoultrySlaughter,
+        "poultry_slaughter",
+        "Poultry Slaughter",
+    ),
+    (
+        Activity::PoultryProcessing,
+        "poultry_processing",
+        "Poultry Processing",
+    ),
+    (Activity::EggProducts, "egg_products", "Egg Product"),
+    (
+        Activity::FishProcessing,
+        "fish_processing",
+        "Fish Processing",
+    ),
+    (
+        Activity::DairyProcessing,
+        "dairy_processing",
+        "Dairy Processing",
+    ),
+    (Activity::ColdStorage, "cold_storage", "Cold Storage"),
+    (
+        Activity::AnimalProduction,
+        "animal_production",
+        "Animal Production",
+    ),
+    (Activity::DairyFarm, "dairy_farm", "Dairy Farm"),
+    (Activity::PigFarm, "pig_farm", "Pig Farm"),
+    (
+        Activity::PigBreedingFarm,
+        "pig_breeding_farm",
+        "Pig Breeding Farm",
+    ),
+    (Activity::PoultryFarm, "poultry_farm", "Poultry Farm"),
+    (Activity::EggFarm, "egg_farm", "Egg Farm"),
+    (Activity::Hatchery, "hatchery", "Hatchery"),
+    (Activity::Aquaculture, "aquaculture", "Aquaculture"),
+    (Activity::FurFarm, "fur_farm", "Fur Farm"),
+    (Activity::Cafo, "cafo", "CAFO"),
+    (Activity::EquineExport, "equine_export", "Equine Export"),
+    (Activity::HuntingGame, "hunting_game", "Hunting/Game"),
+    (Activity::Exhibition, "exhibition", "Exhibition"),
+    (Activity::Laboratory, "laboratory", "Laboratory"),
+    (Activity::Breeder, "breeder", "Breeder"),
+    (Activity::Dealer, "dealer", "Dealer"),
+    (Activity::Other, "other", "Other"),
+];
+
+/// Labels in the other languages the site is offered in, as `(language,
+/// label)` pairs in the order of `i18n::LANGUAGES`.
+const TRANSLATIONS: &[(Activity, [(&str, &str); 5])] = &[
+    (
+        Activity::MeatSlaughter,
+        [
+            ("es", "Sacrificio de ganado"),
+            ("de", "Schlachtung"),
+            ("fr", "Abattage"),
+            ("it", "Macellazione"),
+            ("da", "Slagtning"),
+        ],
+    ),
+    (
+        Activity::MeatProcessing,
+        [
+            ("es", "Procesado de carne"),
+            ("de", "Fleischverarbeitung"),
+            ("fr", "Transformation de viande"),
+            ("it", "Lavorazione carni"),
+            ("da", "Kødforarbejdning"),
+        ],
+    ),
+    (
+        Activity::PoultrySlaughter,
+        [
+            ("es", "Sacrificio de aves"),
+            ("de", "Geflügelschlachtung"),
+            ("fr", "Abattage de volailles"),
+            ("it", "Macellazione avicola"),
+            ("da", "Fjerkræslagtning"),
+        ],
+    ),
+    (
+        Activity::PoultryProcessing,
+        [
+            ("es", "Procesado de aves"),
+            ("de", "Geflügelverarbeitung"),
+            ("fr", "Transformation de volailles"),
+            ("it", "Lavorazione avicola"),
+            ("da", "Fjerkræforarbejdning"),
+        ],
+    ),
+    (
+        Activity::EggProducts,
+        [
+            ("es", "Ovoproductos"),
+            ("de", "Eiprodukte"),
+            ("fr", "Ovoproduits"),
+            ("it", "Ovoprodotti"),
+            ("da", "Ægprodukter"),
+        ],
+    ),
+    (
+        Activity::FishProcessing,
+        [
+            ("es", "Procesado de pescado"),
+            ("de", "Fischverarbeitung"),
+            ("fr", "Transformation du poisson"),
+            ("it", "Lavorazione del pesce"),
+            ("da", "Fiskeforarbejdning"),
+        ],
+    ),
+    (
+        Activity::DairyProcessing,
+        [
+            ("es", "Procesado lácteo"),
+            ("de", "Milchverarbeitung"),
+            ("fr", "Transformation laitière"),
+            ("it", "Lavorazione lattiero-casearia"),
+            ("da", "Mejeriforarbejdning"),
+        ],
+    ),
+    (
+        Activity::ColdStorage,
+        [
+            ("es", "Almacén frigorífico"),
+            ("de", "Kühlhaus"),
+            ("fr", "Entrepôt frigorifique"),
+            ("it", "Deposito frigorifero"),
+            ("da", "Kølehus"),
+        ],
+    ),
+    (
+        Activity::AnimalProduction,
+        [
+            ("es", "Producción animal"),
+            ("de", "Tierhaltung"),
+            ("fr", "Élevage"),
+            ("it", "Allevamento"),
+            ("da", "Husdyrproduktion"),
+        ],
+    ),
+    (
+        Activity::DairyFarm,
+        [
+            ("es", "Granja lechera"),
+            ("de", "Milchviehbetrieb"),
+            ("fr", "Élevage laitier"),
+            ("it", "Allevamento da latte"),
+            ("da", "Malkekvægbrug"),
+        ],
+    ),
+    (
+        Activity::PigFarm,
+        [
+            ("es", "Granja porcina"),
+            ("de", "Schweinehaltung"),
+            ("fr", "Élevage porcin"),
+            ("it", "Allevamento suino"),
+            ("da", "Svinebrug"),
+        ],
+    ),
+    (
+        Activity::PigBreedingFarm,
+        [
+            ("es", "Granja de cerdas reproductoras"),
+            ("de", "Sauenhaltung"),
+            ("fr", "Élevage de truies"),
+            ("it", "Allevamento di scrofe"),
+            ("da", "Sohold"),
+        ],
+    ),
+    (
+        Activity::PoultryFarm,
+        [
+            ("es", "Granja avícola"),
+            ("de", "Geflügelhaltung"),
+            ("fr", "Élevage de volailles"),
+            ("it", "Allevamento avicolo"),
+            ("da", "Fjerkræbrug"),
+        ],
+    ),
+    (
+        Activity::EggFarm,
+        [
+            ("es", "Granja de gallinas ponedoras"),
+            ("de", "Legehennenhaltung"),
+            ("fr", "Élevage de poules pondeuses"),
+            ("it", "Allevamento di galline ovaiole"),
+            ("da", "Ægproduktion"),
+        ],
+    ),
+    (
+        Activity::Hatchery,
+        [
+            ("es", "Incubadora"),
+            ("de", "Brüterei"),
+            ("fr", "Couvoir"),
+            ("it", "Incubatoio"),
+            ("da", "Rugeri"),
+        ],
+    ),
+    (
+        Activity::Aquaculture,
+        [
+            ("es", "Acuicultura"),
+            ("de", "Aquakultur"),
+            ("fr", "Aquaculture"),
+            ("it", "Acquacoltura"),
+            ("da", "Akvakultur"),
+        ],
+    ),
+    (
+        Activity::FurFarm,
+        [
+            ("es", "Granja peletera"),
+            ("de", "Pelztierfarm"),
+            ("fr", "Élevage d'animaux à fourrure"),
+            ("it", "Allevamento di animali da pelliccia"),
+            ("da", "Pelsdyrfarm"),
+        ],
+    ),
+    (
+        Activity::Cafo,
+        [
+            ("es", "Explotación ganadera intensiva"),
+            ("de", "Massentierhaltung"),
+            ("fr", "Élevage intensif"),
+            ("it", "Allevamento intensivo"),
+            ("da", "Intensivt husdyrbrug"),
+        ],
+    ),
+    (
+        Activity::EquineExport,
+        [
+            ("es", "Exportación de équidos"),
+            ("de", "Equidenexport"),
+            ("fr", "Exportation d'équidés"),
+            ("it", "Esportazione di equini"),
+            ("da", "Eksport af heste"),
+        ],
+    ),
+    (
+        Activity::HuntingGame,
+        [
+            ("es", "Caza"),
+            ("de", "Wild"),
+            ("fr", "Gibier"),
+            ("it", "Selvaggina"),
+            ("da", "Vildt"),
+        ],
+    ),
+    (
+        Activity::Exhibition,
+        [
+            ("es", "Exhibición"),
+            ("de", "Zurschaustellung"),
+            ("fr", "Exposition"),
+            ("it", "Esposizione"),
+            ("da", "Udstilling"),
+        ],
+    ),
+    (
+        Activity::Laboratory,
+        [
+            ("es", "Laboratorio"),
+            ("de", "Labor"),
+            ("fr", "Laboratoire"),
+            ("it", "Laboratorio"),
+            ("da", "Laboratorium"),
+        ],
+    ),
+    (
+        Activity::Breeder,
+        [
+            ("es", "Criador"),
+            ("de", "Züchter"),
+            ("fr", "Éleveur"),
+            ("it", "Allevatore"),
+            ("da", "Opdrætter"),
+        ],
+    ),
+    (
+        Activity::Dealer,
+        [
+            ("es", "Comerciante"),
+            ("de", "Händler"),
+            ("fr", "Négociant"),
+            ("it", "Commerciante"),
+            ("da", "Forhandler"),
+        ],
+    ),
+    (
+        Activity::Other,
+        [
+            ("es", "Otro"),
+            ("de", "Sonstiges"),
+            ("fr", "Autre"),
+            ("it", "Altro"),
+            ("da", "Andet"),
+        ],
+    ),
+];
+
+impl Activity {
+    pub fn all() -> impl Iterator<Item = Activity> {
+        ACTIVITIES.iter().map(|(activity, _, _)| *activity)
+    }
+
+    fn entry(self) -> &'static (Activity, &'static str, &'static str) {
+        ACTIVITIES
+            .iter()
+            .find(|(activity, _, _)| *activity == self)
+            .expect("every activity is listed")
+    }
+
+    /// The ID used in the API (`meat_slaughter`).
+    pub fn id(self) -> &'static str {
+        self.entry().1
+    }
+
+    /// The label used in the `type` column and mapping tables ("Meat Slaughter").
+    pub fn label(self) -> &'static str {
+        self.entry().2
+    }
+
+    /// The label in a language code, falling back to English.
+    pub fn label_in(self, lang: &str) -> &'static str {
+        TRANSLATIONS
+            .iter()
+            .find(|(activity, _)| *activity == self)
+            .and_then(|(_, labels)| labels.iter().find(|(code, _)| *code == lang))
+            .map_or(self.label(), |(_, label)| *label)
+    }
+
+    pub fn from_label(label: &str) -> Option<Activity> {
+        ACTIVITIES
+            .iter()
+            .find(|(_, _, known)| label.trim().eq_ignore_ascii_case(known))
+            .map(|(activity, _, _)| *activity)
+    }
+
+    /// Finds an activity by ID or label, ignoring case.
+    pub fn parse(value: &str) -> Option<Activity> {
+        let id = value.trim().to_lowercase().replace([' ', '-', '/'], "_");
+        Activity::all()
+            .find(|activity| activity.id() == id)
+            .or_else(|| Activity::from_label(value))
+    }
+
+    /// The activity one phrase of a source description stands for, such as
+    /// FSIS's "Voluntary Slaughter - Poultry" or the UK's "Intensive Sow Pig
+    /// Farm". None for phrases naming no activity, like FSIS's inspection
+    /// programs ("Certification - Export").
+    fn classify(phrase: &str) -> Option<Activity> {
+        if let Some(activity) = Activity::from_label(phrase) {
+            return Some(activity);
+        }
+        let phrase = phrase.to_lowercase();
+        let has = |text: &str| phrase.contains(text);
+        let has_word = |word: &str| {
+            phrase
+                .split(|c: char| !c.is_alphabetic())
+                .any(|w| w == word)
+        };
+        let poultry = has("poultry") || has("bird");
+        let activity = if has("freezing") || has("cold stor") {
+            Activity::ColdStorage
+        } else if has("hatcher") {
+            Activity::Hatchery
+        } else if has("laying") || has_word("layer") || has_word("layers") {
+            Activity::EggFarm
+        } else if has("egg") {
+            Activity::EggProducts
+        } else if has("slaughter") {
+            if poultry {
+                Activity::PoultrySlaughter
+            } else {
+                Activity::MeatSlaughter
+            }
+        } else if has("processing") {
+            if poultry {
+                Activity::PoultryProcessing
+            } else if has("fish") || has("siluriformes") {
+                Activity::FishProcessing
+            } else if has("dairy") || has("milk") {
+                Activity::DairyProcessing
+            } else {
+                Activity::MeatProcessing
+            }
+        } else if has_word("fur") {
+            Activity::FurFarm
+        } else if has("aqua") {
+            Activity::Aquaculture
+        } else if has_word("sow") {
+            Activity::PigBreedingFarm
+        } else if has("pig farm") || has("finishing unit") {
+            Activity::PigFarm
+        } else if has("poultry farm") {
+            Activity::PoultryFarm
+        } else if has("dairy") {
+            Activity::DairyFarm
+        } else if has("research") || has("laborator") {
+            Activity::Laboratory
+        } else if has("breeder") {
+            Activity::Breeder
+        } else if has("dealer") {
+            Activity::Dealer
+        } else if has("exhibit") {
+            Activity::Exhibition
+        } else {
+            return None;
+        };
+        Some(activity)
+    }
+}
+
+/// The activities in a `type` description ("Meat Processing; Meat Slaughter",
+/// "Mixed Slaughterhouse (Cattle Slaughterhouse, Pig Slaughterhouse)"), in
+/// legend order. Descriptions naming no known activity give `Other`.
+pub fn parse_description(description: &str) -> Vec<Activity> {
+    let mut activities: Vec<Activity> = description
+        .split([';', ',', '(', ')'])
+        .filter_map(Activity::classify)
+        .collect();
+    activities.sort();
+    activities.dedup();
+    if activities.is_empty() && !description.trim().is_empty() {
+        activities.push(Activity::Other);
+    }
+    activities
+}
+
+#[derive(Serialize)]
+struct LegendEntry {
+    id: &'static str,
+    label: &'static str,
+    /// Locations carrying out the activity.
+    locations: usize,
+}
+
+#[derive(Deserialize)]
+pub struct LegendParams {
+    /// Language of the labels, one of `i18n::LANGUAGES`.
+    lang: Option<String>,
+}
+
+/// GET /api/activities: every activity with its label and how many locations
+/// carry it out, for filters and map legends.
+pub async fn get_activities_handler(
+    State(state): State<AppState>,
+    Query(params): Query<LegendParams>,
+) -> impl IntoResponse {
+    let lang = match i18n::language(params.lang.as_deref()) {
+        Ok(lang) => lang,
+        Err(e) => return (StatusCode::BAD_REQUEST, e).into_response(),
+    };
+    let index = state.location_index();
+    let legend: Vec<LegendEntry> = Activity::all()
+        .map(|activity| LegendEntry {
+            id: activity.id(),
+            label: activity.label_in(lang),
+            locations: index
+                .locations
+                .iter()
+                .filter(|loc| loc.activities.contains(&activity))
+                .count(),
+        })
+        .collect();
+    Json(legend).into_response()
+}
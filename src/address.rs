@@ -0,0 +1,116 @@
+// Until Every Cage is Empty
+// Copyright (C) 2025 Eli Perez
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+// Contact the developer directly at untileverycageproject@protonmail.com
+//! City, state and ZIP of the APHIS records. APHIS writes them as one
+//! `City-State-Zip` column ("SALT LAKE CITY, UT 84112"), in capitals or
+//! not, with ZIP+4s spaced out ("74005 - 1647") and the leading zeros of
+//! Puerto Rico's ZIPs lost ("ARECIBO, PR 614"). They are split and checked
+//! as the records load, so the reports have `City`, `State` and `Zip` like
+//! the licensee list's geocoded columns, and can be filtered by them.
+//!
+//! A part that doesn't check out (a state that isn't a USPS code, a ZIP
+//! that isn't 5 or 9 digits) is left empty rather than served wrong.
+
+/// USPS codes of the states, DC, the territories and military post offices.
+const STATES: &[&str] = &[
+    "AL", "AK", "AZ", "AR", "CA", "CO", "CT", "DE", "DC", "FL", "GA", "HI", "ID", "IL", "IN", "IA",
+    "KS", "KY", "LA", "ME", "MD", "MA", "MI", "MN", "MS", "MO", "MT", "NE", "NV", "NH", "NJ", "NM",
+    "NY", "NC", "ND", "OH", "OK", "OR", "PA", "RI", "SC", "SD", "TN", "TX", "UT", "VT", "VA", "WA",
+    "WV", "WI", "WY", "AS", "GU", "MP", "PR", "VI", "AA", "AE", "AP",
+];
+
+#[derive(Debug, Default, Clone, PartialEq)]
+pub(crate) struct CityStateZip {
+    pub(crate) city: String,
+    pub(crate) state: String,
+    pub(crate) zip: String,
+}
+
+impl CityStateZip {
+    /// Splits APHIS's "AUBURN, AL 36849".
+    pub(crate) fn parse(value: &str) -> Self {
+        let Some((city_part, rest)) = value.rsplit_once(',') else {
+            return CityStateZip {
+                city: city(value),
+                ..Default::default()
+            };
+        };
+        let rest = rest.trim();
+        let (state_part, zip_part) = rest.split_once(char::is_whitespace).unwrap_or((rest, ""));
+        CityStateZip::checked(city_part, state_part, zip_part)
+    }
+
+    /// The parts given as separate fields, standardized and checked.
+    pub(crate) fn checked(city_part: &str, state_part: &str, zip_part: &str) -> Self {
+        CityStateZip {
+            city: city(city_part),
+            state: state(state_part),
+            zip: zip(zip_part),
+        }
+    }
+
+    /// Each part of `self`, or of `fallback` where `self`'s is empty.
+    pub(crate) fn or(self, fallback: CityStateZip) -> Self {
+        let pick = |part: String, fallback: String| if part.is_empty() { fallback } else { part };
+        CityStateZip {
+            city: pick(self.city, fallback.city),
+            state: pick(self.state, fallback.state),
+            zip: pick(self.zip, fallback.zip),
+        }
+    }
+}
+
+/// Single-spaced, and in title case where the source shouts ("SALT LAKE
+/// CITY" is "Salt Lake City", "WINSTON-SALEM" "Winston-Salem").
+fn city(value: &str) -> String {
+    let city = value.split_whitespace().collect::<Vec<_>>().join(" ");
+    if city.chars().any(char::is_lowercase) {
+        return city;
+    }
+    let mut title = String::with_capacity(city.len());
+    let mut start = true;
+    for c in city.chars() {
+        if start {
+            title.push(c);
+        } else {
+            title.extend(c.to_lowercase());
+        }
+        start = c == ' ' || c == '-' || c == '.';
+    }
+    title
+}
+
+/// The USPS code, uppercased, or empty for anything else.
+pub(crate) fn state(value: &str) -> String {
+    let code = value.trim().to_ascii_uppercase();
+    if STATES.contains(&code.as_str()) {
+        code
+    } else {
+        String::new()
+    }
+}
+
+/// A ZIP or ZIP+4 as `12345` or `12345-6789`, or empty for anything else.
+/// Three and four digits are a ZIP whose leading zeros a spreadsheet dropped.
+pub(crate) fn zip(value: &str) -> String {
+    let digits: String = value.chars().filter(char::is_ascii_digit).collect();
+    match digits.len() {
+        3..=5 => format!("{:0>5}", digits),
+        9 => format!("{}-{}", &digits[..5], &digits[5..]),
+        _ => String::new(),
+    }
+}
@@ -0,0 +1,215 @@
+// Until Every Cage is Empty
+// Copyright (C) 2025 Eli Perez
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+// Contact the developer directly at untileverycageproject@protonmail.com
+use std::collections::HashSet;
+
+use axum::extract::State;
+use axum::http::{HeaderMap, StatusCode, header};
+use axum::{Json, response::IntoResponse};
+use serde::Deserialize;
+
+use crate::AppState;
+use crate::errors::ApiError;
+use crate::geocode::{self, address_query, country_name};
+use crate::jobs::JobState;
+
+/// Checks the `Authorization: Bearer <token>` header against `ADMIN_TOKEN`
+/// and `ADMIN_TOKENS`, and returns who the token belongs to for the audit log.
+///
+/// `ADMIN_TOKENS` gives each moderator their own token
+/// (`alice:token1,bob:token2`); `ADMIN_TOKEN` is recorded as `admin`. Admin
+/// routes are disabled entirely when no token is configured.
+pub(crate) fn authorize(
+    state: &AppState,
+    headers: &HeaderMap,
+) -> Result<String, (StatusCode, String)> {
+    let mut tokens: Vec<(String, String)> = state
+        .settings
+        .get("ADMIN_TOKENS")
+        .unwrap_or_default()
+        .split(',')
+        .filter_map(|entry| entry.split_once(':'))
+        .map(|(name, token)| (name.trim().to_string(), token.trim().to_string()))
+        .collect();
+    if let Some(token) = state.settings.get("ADMIN_TOKEN") {
+        tokens.push(("admin".to_string(), token.to_string()));
+    }
+    tokens.retain(|(name, token)| !name.is_empty() && !token.is_empty());
+    if tokens.is_empty() {
+        return Err((
+            StatusCode::SERVICE_UNAVAILABLE,
+            "Admin routes are disabled, set ADMIN_TOKEN or ADMIN_TOKENS to enable them".to_string(),
+        ));
+    }
+
+    let provided = headers
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "));
+
+    provided
+        .and_then(|provided| tokens.into_iter().find(|(_, token)| token == provided))
+        .map(|(name, _)| name)
+        .ok_or((StatusCode::UNAUTHORIZED, "Invalid admin token".to_string()))
+}
+
+#[derive(Deserialize)]
+pub struct GeocodeRequest {
+    /// Establishment IDs to geocode.
+    #[serde(default)]
+    ids: Vec<String>,
+    /// Geocode every location that has no coordinates yet.
+    #[serde(default)]
+    all_missing: bool,
+}
+
+/// POST /admin/geocode
+///
+/// Queues a background job geocoding the requested locations with the
+/// configured provider. Results are applied to the served data as coordinate
+/// overrides; progress is available at /api/jobs/{id}.
+pub async fn post_geocode_handler(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Json(request): Json<GeocodeRequest>,
+) -> impl IntoResponse {
+    if let Err(e) = authorize(&state, &headers) {
+        return e.into_response();
+    }
+
+    if request.ids.is_empty() && !request.all_missing {
+        return (
+            StatusCode::BAD_REQUEST,
+            "Provide a list of ids or set all_missing".to_string(),
+        )
+            .into_response();
+    }
+
+    let geocoder = match geocode::provider_from_settings(&state.settings) {
+        Ok(geocoder) => geocoder,
+        Err(e) => return (StatusCode::SERVICE_UNAVAILABLE, e).into_response(),
+    };
+
+    let index = state.location_index();
+    let ids: HashSet<&str> = request.ids.iter().map(String::as_str).collect();
+    let targets: Vec<_> = index
+        .locations
+        .iter()
+        .filter(|loc| {
+            ids.contains(loc.establishment_id.as_str())
+                || (request.all_missing && loc.latitude == 0.0 && loc.longitude == 0.0)
+        })
+        .map(|loc| {
+            let query = address_query(&[
+                &loc.street,
+                &loc.city,
+                &loc.state,
+                &loc.zip,
+                country_name(&loc.country),
+            ]);
+            ((loc.country.clone(), loc.establishment_id.clone()), query)
+        })
+        .collect();
+
+    let job = state.jobs.create("geocode", targets.len());
+    let jobs = state.jobs.clone();
+    let job_id = job.id;
+
+    tokio::spawn(async move {
+        jobs.update(job_id, |job| job.state = JobState::Running);
+
+        let total = targets.len();
+        let mut last_error = None;
+        let mut errors = 0;
+
+        for (key, query) in targets {
+            let found = match geocoder.geocode(&query).await {
+                Ok(Some(coordinates)) => {
+                    state
+                        .coordinate_overrides
+                        .write()
+                        .unwrap()
+                        .insert(key, coordinates);
+                    true
+                }
+                Ok(None) => false,
+                Err(e) => {
+                    errors += 1;
+                    last_error = Some(e.to_string());
+                    false
+                }
+            };
+
+            jobs.update(job_id, |job| {
+                job.processed += 1;
+                if found {
+                    job.succeeded += 1;
+                } else {
+                    job.failed += 1;
+                }
+            });
+            tokio::time::sleep(geocoder.min_interval()).await;
+        }
+
+        // Every request erroring means the provider is unreachable or misconfigured.
+        if total > 0 && errors == total {
+            jobs.finish(job_id, JobState::Failed, last_error);
+        } else if let Err(e) = state.rebuild_location_index().await {
+            jobs.finish(
+                job_id,
+                JobState::Failed,
+                Some(format!("Failed to reindex locations: {}", e)),
+            );
+        } else {
+            jobs.finish(
+                job_id,
+                JobState::Completed,
+                Some(format!("Geocoded with {}", geocoder.name())),
+            );
+        }
+    });
+
+    (
+        StatusCode::ACCEPTED,
+        [(header::LOCATION, format!("/api/jobs/{}", job_id))],
+        Json(job),
+    )
+        .into_response()
+}
+
+/// POST /api/admin/reload
+///
+/// Reloads every dataset from the configured `DATA_SOURCE` and swaps it in
+/// without a restart. On failure the previous data keeps being served, and a
+/// dataset that fails to load keeps its previous copy (see /readyz).
+pub async fn post_reload_handler(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> impl IntoResponse {
+    if let Err(e) = authorize(&state, &headers) {
+        return e.into_response();
+    }
+
+    let summary = match state.store.reload().await {
+        Ok(summary) => summary,
+        Err(e) => {
+            return ApiError::internal(format!("Failed to reload data: {}", e)).into_response();
+        }
+    };
+    state.rebuild().await;
+    Json(summary).into_response()
+}
@@ -0,0 +1,400 @@
+// Until Every Cage is Empty
+// Copyright (C) 2025 Eli Perez
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+// Contact the developer directly at untileverycageproject@protonmail.com
+//! The Router the Shuttle deployment and the self-hosted `serve` binary both
+//! run.
+
+use std::collections::BTreeMap;
+use std::time::Duration;
+
+use axum::Router;
+use axum::handler::Handler;
+use axum::http::{HeaderValue, Method, header};
+use axum::routing::{MethodFilter, MethodRouter, on};
+use tower_http::compression::CompressionLayer;
+use tower_http::cors::{AllowHeaders, AllowOrigin, CorsLayer};
+
+use crate::AppState;
+use crate::settings::Settings;
+
+/// How long browsers may cache a preflight response.
+const CORS_MAX_AGE: Duration = Duration::from_secs(3600);
+
+/// `cors` configured by the environment.
+pub fn cors_from_env() -> Result<CorsLayer, String> {
+    cors(&Settings::from_env())
+}
+
+/// CORS for the API. `CORS_ORIGINS` takes a comma-separated list of origins
+/// (`https://example.org,https://mirror.example.org`); unset or `*` allows any.
+/// Only the methods the API has are allowed, without credentials: keys go in
+/// `X-API-Key` and admin tokens in `Authorization`.
+pub fn cors(settings: &Settings) -> Result<CorsLayer, String> {
+    let cors = CorsLayer::new()
+        .allow_methods([
+            Method::GET,
+            Method::HEAD,
+            Method::POST,
+            Method::PUT,
+            Method::DELETE,
+        ])
+        .allow_headers(AllowHeaders::mirror_request())
+        .expose_headers([
+            crate::TOTAL_COUNT_HEADER,
+            header::WARNING,
+            header::RETRY_AFTER,
+            crate::access::RATE_LIMIT_HEADER,
+            crate::access::RATE_REMAINING_HEADER,
+        ])
+        .max_age(CORS_MAX_AGE);
+    let origins = match settings.get("CORS_ORIGINS") {
+        Some(origins) if !origins.trim().is_empty() && origins.trim() != "*" => origins,
+        _ => return Ok(cors.allow_origin(AllowOrigin::any())),
+    };
+    let origins = origins
+        .split(',')
+        .map(str::trim)
+        .filter(|origin| !origin.is_empty())
+        .map(|origin| {
+            HeaderValue::from_str(origin).map_err(|_| format!("Invalid CORS origin {:?}", origin))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(cors.allow_origin(AllowOrigin::list(origins)))
+}
+
+/// A method and path the API answers, and its handler.
+pub struct Route {
+    pub method: Method,
+    pub path: &'static str,
+    handler: MethodRouter<AppState>,
+}
+
+fn route<H, T>(method: Method, path: &'static str, handler: H) -> Route
+where
+    H: Handler<T, AppState>,
+    T: 'static,
+{
+    let filter = MethodFilter::try_from(method.clone()).expect("axum routes every method used");
+    Route {
+        method,
+        path,
+        handler: on(filter, handler),
+    }
+}
+
+/// Every route `router` serves. The OpenAPI description (see `openapi`)
+/// lists the same ones.
+pub fn routes() -> Vec<Route> {
+    vec![
+        route(Method::GET, "/api/locations", crate::get_locations_handler),
+        route(
+            Method::GET,
+            "/api/locations/bbox",
+            crate::get_locations_in_bbox_handler,
+        ),
+        route(
+            Method::GET,
+            "/api/locations/near",
+            crate::get_locations_near_handler,
+        ),
+        route(
+            Method::GET,
+            "/api/locations/along-route",
+            crate::get_locations_along_route_handler,
+        ),
+        route(
+            Method::GET,
+            "/api/locations/by-fips/{code}",
+            crate::get_locations_by_fips_handler,
+        ),
+        route(
+            Method::GET,
+            "/api/locations/by-zip/{zip}",
+            crate::get_locations_by_zip_handler,
+        ),
+        route(
+            Method::GET,
+            "/api/locations/slug/{slug}",
+            crate::get_location_by_slug_handler,
+        ),
+        route(
+            Method::POST,
+            "/api/locations/batch",
+            crate::post_batch_handler,
+        ),
+        route(
+            Method::POST,
+            "/api/locations/nearest",
+            crate::post_locations_nearest_handler,
+        ),
+        route(
+            Method::GET,
+            "/api/locations/changes",
+            crate::get_location_changes_handler,
+        ),
+        route(
+            Method::GET,
+            "/api/locations/delta",
+            crate::get_location_delta_handler,
+        ),
+        route(
+            Method::GET,
+            "/api/locations/{establishment_id}",
+            crate::get_location_handler,
+        ),
+        route(
+            Method::GET,
+            "/api/locations/{establishment_id}/card",
+            crate::get_location_card_handler,
+        ),
+        route(
+            Method::GET,
+            "/api/locations/{establishment_id}/enforcement",
+            crate::get_location_enforcement_handler,
+        ),
+        route(Method::GET, "/tiles/{z}/{x}/{y}", crate::get_tile_handler),
+        route(
+            Method::GET,
+            "/api/facilities",
+            crate::get_facilities_handler,
+        ),
+        route(
+            Method::GET,
+            "/api/facilities/{id}",
+            crate::get_facility_handler,
+        ),
+        route(
+            Method::GET,
+            "/api/facilities/{id}/related",
+            crate::get_related_facilities_handler,
+        ),
+        route(Method::GET, "/api/graphql", crate::get_graphql_handler),
+        route(Method::POST, "/api/graphql", crate::post_graphql_handler),
+        route(
+            Method::GET,
+            "/api/aquaculture",
+            crate::get_aquaculture_handler,
+        ),
+        route(Method::GET, "/api/fur-farms", crate::get_fur_farms_handler),
+        route(Method::GET, "/api/egg-farms", crate::get_egg_farms_handler),
+        route(Method::GET, "/api/cafos", crate::get_cafos_handler),
+        route(Method::GET, "/api/equine", crate::get_equine_handler),
+        route(Method::GET, "/api/citations", crate::get_citations_handler),
+        route(
+            Method::GET,
+            "/api/enforcement",
+            crate::get_enforcement_handler,
+        ),
+        route(
+            Method::GET,
+            "/api/export/kml",
+            crate::get_kml_export_handler,
+        ),
+        route(
+            Method::GET,
+            "/api/export/kmz",
+            crate::get_kmz_export_handler,
+        ),
+        route(
+            Method::GET,
+            "/api/export/sqlite",
+            crate::get_sqlite_export_handler,
+        ),
+        route(Method::GET, "/api/search", crate::get_search_handler),
+        route(
+            Method::GET,
+            "/api/autocomplete",
+            crate::get_autocomplete_handler,
+        ),
+        route(Method::GET, "/api/match", crate::get_match_handler),
+        route(
+            Method::GET,
+            "/api/aphis-reports",
+            crate::get_aphis_reports_handler,
+        ),
+        route(
+            Method::GET,
+            "/api/aphis-reports/timeseries",
+            crate::get_aphis_timeseries_handler,
+        ),
+        route(
+            Method::GET,
+            "/api/aphis-reports/{certificate_number}",
+            crate::get_aphis_report_handler,
+        ),
+        route(
+            Method::GET,
+            "/api/aphis-reports/{certificate_number}/inspections",
+            crate::get_aphis_inspections_handler,
+        ),
+        route(
+            Method::GET,
+            "/api/inspection-reports",
+            crate::get_inspection_reports_handler,
+        ),
+        route(
+            Method::GET,
+            "/api/inspection-reports/{certificate_number}",
+            crate::get_inspection_report_handler,
+        ),
+        route(Method::GET, "/api/stats", crate::get_stats_handler),
+        route(
+            Method::GET,
+            "/api/choropleth",
+            crate::get_choropleth_handler,
+        ),
+        route(Method::GET, "/api/heatmap", crate::get_heatmap_handler),
+        route(
+            Method::GET,
+            "/api/data-quality",
+            crate::get_data_quality_handler,
+        ),
+        route(
+            Method::GET,
+            "/api/activities",
+            crate::get_activities_handler,
+        ),
+        route(Method::GET, "/api/i18n/{lang}", crate::get_i18n_handler),
+        route(Method::GET, "/api/changelog", crate::get_changelog_handler),
+        route(Method::GET, "/api/importers", crate::get_importers_handler),
+        route(Method::GET, "/api/meta", crate::get_meta_handler),
+        route(
+            Method::GET,
+            "/api/deprecations",
+            crate::get_deprecations_handler,
+        ),
+        route(Method::GET, "/sitemap.xml", crate::get_sitemap_handler),
+        route(Method::GET, "/healthz", crate::get_healthz_handler),
+        route(Method::GET, "/readyz", crate::get_readyz_handler),
+        route(Method::GET, "/api/openapi.json", crate::get_openapi_handler),
+        route(Method::GET, "/api/docs", crate::get_docs_handler),
+        route(
+            Method::POST,
+            "/api/submissions",
+            crate::post_submission_handler,
+        ),
+        route(
+            Method::GET,
+            "/api/submissions/pending",
+            crate::get_pending_submissions_handler,
+        ),
+        route(
+            Method::POST,
+            "/api/subscriptions",
+            crate::post_subscription_handler,
+        ),
+        route(
+            Method::DELETE,
+            "/api/subscriptions/{id}",
+            crate::delete_subscription_handler,
+        ),
+        route(
+            Method::GET,
+            "/api/admin/subscriptions",
+            crate::get_subscriptions_handler,
+        ),
+        route(Method::POST, "/api/feedback", crate::post_feedback_handler),
+        route(
+            Method::GET,
+            "/api/admin/feedback",
+            crate::get_feedback_handler,
+        ),
+        route(
+            Method::PUT,
+            "/api/admin/submissions/{id}",
+            crate::put_submission_handler,
+        ),
+        route(
+            Method::POST,
+            "/api/admin/submissions/{id}/review",
+            crate::post_review_handler,
+        ),
+        route(
+            Method::PUT,
+            "/api/admin/flags/{country}/{establishment_id}",
+            crate::put_flag_handler,
+        ),
+        route(
+            Method::DELETE,
+            "/api/admin/flags/{country}/{establishment_id}",
+            crate::delete_flag_handler,
+        ),
+        route(
+            Method::GET,
+            "/api/admin/residences",
+            crate::get_residences_handler,
+        ),
+        route(
+            Method::PUT,
+            "/api/admin/residences/{facility_id}",
+            crate::put_residence_handler,
+        ),
+        route(
+            Method::DELETE,
+            "/api/admin/residences/{facility_id}",
+            crate::delete_residence_handler,
+        ),
+        route(Method::GET, "/api/admin/audit", crate::get_audit_handler),
+        route(Method::GET, "/api/jobs", crate::get_jobs_handler),
+        route(Method::GET, "/api/jobs/{id}", crate::get_job_handler),
+        route(
+            Method::POST,
+            "/api/admin/reload",
+            crate::post_reload_handler,
+        ),
+        route(Method::POST, "/admin/geocode", crate::post_geocode_handler),
+    ]
+}
+
+/// Every one of `routes`, with rate limits (see `access`), caching, deprecation
+/// headers, compression, `cors` and request tracing (see `telemetry`).
+pub fn router(state: AppState, cors: CorsLayer) -> Router {
+    let mut paths: BTreeMap<&'static str, MethodRouter<AppState>> = BTreeMap::new();
+    for route in routes() {
+        let handler = match paths.remove(route.path) {
+            Some(handler) => handler.merge(route.handler),
+            None => route.handler,
+        };
+        paths.insert(route.path, handler);
+    }
+    paths
+        .into_iter()
+        .fold(Router::new(), |router, (path, handler)| {
+            router.route(path, handler)
+        })
+        .layer(axum::middleware::from_fn_with_state(
+            state.clone(),
+            crate::precompress,
+        ))
+        .layer(axum::middleware::from_fn_with_state(
+            state.clone(),
+            crate::cache_headers,
+        ))
+        .layer(axum::middleware::from_fn_with_state(
+            state.clone(),
+            crate::load_warning,
+        ))
+        .layer(axum::middleware::from_fn_with_state(
+            state.clone(),
+            crate::rate_limit,
+        ))
+        .with_state(state)
+        .layer(axum::middleware::from_fn(crate::deprecation_headers))
+        .layer(CompressionLayer::new().br(true).zstd(true).gzip(true))
+        .layer(cors)
+        .layer(crate::telemetry::trace_layer())
+}
@@ -0,0 +1,193 @@
+// Until Every Cage is Empty
+// Copyright (C) 2025 Eli Perez
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+// Contact the developer directly at untileverycageproject@protonmail.com
+//! Fish farms, hatcheries and seafood processors, which the slaughter and
+//! processing directories leave out. Each country's sites are CSV files in
+//! `<country>/aquaculture/`, one per source, written by the aquaculture
+//! importers.
+
+use axum::extract::{Query, State};
+use axum::http::{HeaderMap, StatusCode};
+use axum::response::IntoResponse;
+use serde::{Deserialize, Serialize};
+
+use crate::activities::Activity;
+use crate::geocode;
+use crate::ids;
+use crate::paging::Page;
+use crate::schema;
+use crate::species::{self, Taxon};
+use crate::store::Dataset;
+use crate::{AppState, geojson};
+
+/// A row of an aquaculture CSV.
+#[derive(Serialize, Deserialize, Debug, Default, Clone)]
+#[serde(default)]
+pub struct AquacultureSite {
+    /// The permit or registration number, or an `ids::content_id` when the
+    /// source has none.
+    pub site_id: String,
+    pub name: String,
+    /// As the source words it: "Processor", "Hatchery", "Net pen farm"...
+    pub operation: String,
+    /// The dataset the row came from, e.g. "NOAA seafood processor permits".
+    pub source: String,
+    pub street: String,
+    pub city: String,
+    pub state: String,
+    pub zip: String,
+    /// Species as the source names them, separated by "; ".
+    pub species: String,
+    pub latitude: f64,
+    pub longitude: f64,
+}
+
+/// A site as served, with the species and activity worked out.
+#[derive(Serialize, Debug, Clone)]
+pub(crate) struct AquacultureFacility {
+    /// The site's facility ID (see `ids`).
+    pub(crate) facility_id: String,
+    pub(crate) country: String,
+    #[serde(flatten)]
+    pub(crate) site: AquacultureSite,
+    /// Species group IDs from the ontology (`salmon`, `shrimp`...).
+    pub(crate) species_ids: Vec<&'static str>,
+    pub(crate) activity: Activity,
+}
+
+impl geojson::Feature for AquacultureFacility {}
+
+impl AquacultureFacility {
+    fn new(country: &str, site: AquacultureSite) -> Self {
+        let species_ids = species::groups(
+            species::mentioned_in(&site.species)
+                .iter()
+                .map(|taxon| taxon.id),
+        );
+        let activity = if site.operation.to_lowercase().contains("process") {
+            Activity::FishProcessing
+        } else {
+            Activity::Aquaculture
+        };
+        AquacultureFacility {
+            facility_id: ids::facility_id("aquaculture", country, &site.site_id),
+            country: geocode::country_code(country),
+            site,
+            species_ids,
+            activity,
+        }
+    }
+}
+
+/// Every country's aquaculture sites.
+pub(crate) fn read_aquaculture(
+    dataset: &Dataset,
+) -> Result<Vec<AquacultureFacility>, Box<dyn std::error::Error>> {
+    let mut facilities = Vec::new();
+    for country in dataset.countries() {
+        for (name, contents) in dataset.files_in(&format!("{}/aquaculture", country)) {
+            if !name.ends_with(".csv") {
+                continue;
+            }
+            schema::check::<AquacultureSite>(
+                &format!("{}/aquaculture/{}", country, name),
+                contents,
+            )?;
+            let mut reader = csv::Reader::from_reader(contents);
+            for result in reader.deserialize() {
+                let site: AquacultureSite =
+                    result.map_err(|e| format!("{}/aquaculture/{}: {}", country, name, e))?;
+                facilities.push(AquacultureFacility::new(country, site));
+            }
+        }
+    }
+    Ok(facilities)
+}
+
+#[derive(Deserialize)]
+pub struct AquacultureParams {
+    country_code: Option<String>,
+    state: Option<String>,
+    /// Comma-separated species, e.g. `salmon,shrimp` or `crustaceans`.
+    species: Option<String>,
+    format: Option<String>,
+    limit: Option<usize>,
+    offset: Option<usize>,
+    fields: Option<String>,
+}
+
+/// GET /api/aquaculture: fish farms, hatcheries and seafood processors.
+pub async fn get_aquaculture_handler(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Query(params): Query<AquacultureParams>,
+) -> impl IntoResponse {
+    let parse = || -> Result<(Vec<&'static Taxon>, Page), String> {
+        let species = params
+            .species
+            .as_deref()
+            .unwrap_or_default()
+            .split(',')
+            .map(str::trim)
+            .filter(|term| !term.is_empty())
+            .map(|term| species::lookup(term).ok_or_else(|| format!("Unknown species {}", term)))
+            .collect::<Result<_, _>>()?;
+        let page = Page::parse(params.limit, params.offset, params.fields.as_deref())?;
+        Ok((species, page))
+    };
+    let (species, page) = match parse() {
+        Ok(parsed) => parsed,
+        Err(e) => return (StatusCode::BAD_REQUEST, e).into_response(),
+    };
+
+    let reports = state.reports();
+    if species.is_empty() && params.country_code.is_none() && params.state.is_none() {
+        return geojson::respond_all(
+            &reports.aquaculture,
+            &headers,
+            params.format.as_deref(),
+            &page,
+        );
+    }
+
+    let selected: Vec<&AquacultureFacility> = reports
+        .aquaculture
+        .iter()
+        .filter(|facility| {
+            params
+                .country_code
+                .as_ref()
+                .is_none_or(|country| facility.country == geocode::country_code(country))
+        })
+        .filter(|facility| {
+            params
+                .state
+                .as_ref()
+                .is_none_or(|state| facility.site.state.eq_ignore_ascii_case(state))
+        })
+        .filter(|facility| {
+            species.is_empty()
+                || species.iter().any(|wanted| {
+                    facility
+                        .species_ids
+                        .iter()
+                        .any(|id| species::is_within(id, wanted.id))
+                })
+        })
+        .collect();
+    geojson::respond(&selected, &headers, params.format.as_deref(), &page)
+}
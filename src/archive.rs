@@ -0,0 +1,128 @@
+// Until Every Cage is Empty
+// Copyright (C) 2025 Eli Perez
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+// Contact the developer directly at untileverycageproject@protonmail.com
+use std::error::Error;
+use std::io::{Read, Seek};
+
+use encoding_rs::{Encoding, UTF_8, WINDOWS_1252};
+
+/// What a member of a source bundle contains, decided from its contents.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MemberKind {
+    /// Already in the shared `Location` schema.
+    Locations,
+    AphisReports,
+    InspectionReports,
+    /// Danish Fødevarestyrelsen smiley export, read with `smiley::Smiley`.
+    SmileyXml,
+    Unknown,
+}
+
+impl MemberKind {
+    /// Path the member is written to under a data directory laid out like
+    /// static_data: the FSIS directory and the APHIS datasets under `us/`,
+    /// smiley exports under `dk/`.
+    pub fn file_name(&self) -> Option<&'static str> {
+        match self {
+            MemberKind::Locations => Some("us/locations.csv"),
+            MemberKind::AphisReports => Some("us/aphis_data_final.csv"),
+            MemberKind::InspectionReports => Some("us/inspection_reports.csv"),
+            MemberKind::SmileyXml => Some("dk/locations.csv"),
+            MemberKind::Unknown => None,
+        }
+    }
+}
+
+pub struct ArchiveMember {
+    pub name: String,
+    /// Name of the encoding the member was decoded from.
+    pub encoding: &'static str,
+    /// Contents, re-encoded as UTF-8.
+    pub text: String,
+}
+
+impl ArchiveMember {
+    pub fn kind(&self) -> MemberKind {
+        let lower = self.name.to_lowercase();
+        if lower.ends_with(".xml") {
+            return if self.text.contains("<navnelbnr>") {
+                MemberKind::SmileyXml
+            } else {
+                MemberKind::Unknown
+            };
+        }
+        if !(lower.ends_with(".csv") || lower.ends_with(".txt")) {
+            return MemberKind::Unknown;
+        }
+
+        let header = self.text.lines().next().unwrap_or_default();
+        let has = |column: &str| header.split(',').any(|h| h.trim_matches('"') == column);
+
+        if has("establishment_id") && has("establishment_name") && has("latitude") {
+            MemberKind::Locations
+        } else if has("Certificate Number") && has("Registration Type") {
+            MemberKind::AphisReports
+        } else if has("Certificate Number") && has("License Type") {
+            MemberKind::InspectionReports
+        } else {
+            MemberKind::Unknown
+        }
+    }
+}
+
+/// Decodes raw bytes, honouring a BOM and falling back to Windows-1252 when
+/// the data isn't valid UTF-8 (common for exports from older Excel versions).
+pub fn decode(bytes: &[u8]) -> (String, &'static str) {
+    if let Some((encoding, bom_len)) = Encoding::for_bom(bytes) {
+        let (text, _) = encoding.decode_without_bom_handling(&bytes[bom_len..]);
+        return (text.into_owned(), encoding.name());
+    }
+
+    match std::str::from_utf8(bytes) {
+        Ok(text) => (text.to_string(), UTF_8.name()),
+        Err(_) => {
+            let (text, _) = WINDOWS_1252.decode_without_bom_handling(bytes);
+            (text.into_owned(), WINDOWS_1252.name())
+        }
+    }
+}
+
+/// Extracts and decodes every file in a ZIP archive. Directories and
+/// macOS resource-fork entries are skipped.
+pub fn extract_members<R: Read + Seek>(reader: R) -> Result<Vec<ArchiveMember>, Box<dyn Error>> {
+    let mut archive = zip::ZipArchive::new(reader)?;
+    let mut members = Vec::new();
+
+    for i in 0..archive.len() {
+        let mut file = archive.by_index(i)?;
+        if file.is_dir() || file.name().starts_with("__MACOSX/") {
+            continue;
+        }
+
+        let mut bytes = Vec::with_capacity(file.size() as usize);
+        file.read_to_end(&mut bytes)?;
+        let (text, encoding) = decode(&bytes);
+
+        members.push(ArchiveMember {
+            name: file.name().to_string(),
+            encoding,
+            text,
+        });
+    }
+
+    Ok(members)
+}
@@ -0,0 +1,165 @@
+// Until Every Cage is Empty
+// Copyright (C) 2025 Eli Perez
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+// Contact the developer directly at untileverycageproject@protonmail.com
+//! POST /api/locations/batch: many records by id in one request, for
+//! clients rehydrating bookmarks.
+//!
+//! An id is a facility ID (see `ids`) of any dataset, an establishment id or
+//! an APHIS certificate number. An establishment id used in several
+//! countries returns each of its locations.
+
+use std::collections::HashSet;
+
+use axum::Json;
+use axum::extract::State;
+use axum::http::StatusCode;
+use axum::response::IntoResponse;
+use serde::Serialize;
+
+use crate::aquaculture::AquacultureFacility;
+use crate::cafos::CafoFacility;
+use crate::egg_farms::EggFarmFacility;
+use crate::equine::EquineFacility;
+use crate::fur_farms::FurFarmFacility;
+use crate::location::{AphisReport, InspectionReport};
+use crate::{AppState, LocationResponse};
+
+/// Most ids in one request.
+const MAX_IDS: usize = 1000;
+
+#[derive(Serialize, Default)]
+struct Batch<'a> {
+    locations: Vec<&'a LocationResponse>,
+    aphis_reports: Vec<&'a AphisReport>,
+    inspection_reports: Vec<&'a InspectionReport>,
+    aquaculture: Vec<&'a AquacultureFacility>,
+    fur_farms: Vec<&'a FurFarmFacility>,
+    egg_farms: Vec<&'a EggFarmFacility>,
+    cafos: Vec<&'a CafoFacility>,
+    equine: Vec<&'a EquineFacility>,
+    /// The ids that matched nothing, in the order given.
+    not_found: Vec<&'a str>,
+}
+
+/// Keeps the items one of whose ids is wanted, noting the ids found.
+fn select<'a, T>(
+    items: &'a [T],
+    ids: impl Fn(&'a T) -> [&'a str; 2],
+    wanted: &HashSet<&str>,
+    found: &mut HashSet<&'a str>,
+) -> Vec<&'a T> {
+    items
+        .iter()
+        .filter(|item| {
+            let mut matched = false;
+            for id in ids(item) {
+                if !id.is_empty() && wanted.contains(id) {
+                    found.insert(id);
+                    matched = true;
+                }
+            }
+            matched
+        })
+        .collect()
+}
+
+/// POST /api/locations/batch with a JSON array of ids.
+pub async fn post_batch_handler(
+    State(state): State<AppState>,
+    Json(ids): Json<Vec<String>>,
+) -> impl IntoResponse {
+    if ids.len() > MAX_IDS {
+        return (
+            StatusCode::BAD_REQUEST,
+            format!("At most {} ids can be looked up at once", MAX_IDS),
+        )
+            .into_response();
+    }
+    let wanted: HashSet<&str> = ids.iter().map(|id| id.trim()).collect();
+
+    let index = state.location_index();
+    let reports = state.reports();
+    let mut found: HashSet<&str> = HashSet::new();
+    let mut batch = Batch {
+        locations: select(
+            &index.locations,
+            |loc| [&loc.facility_id, &loc.establishment_id],
+            &wanted,
+            &mut found,
+        ),
+        aphis_reports: select(
+            &reports.aphis,
+            |report| {
+                [
+                    report.facility_id.as_deref().unwrap_or_default(),
+                    &report.certificate_number,
+                ]
+            },
+            &wanted,
+            &mut found,
+        ),
+        inspection_reports: select(
+            &reports.inspections,
+            |report| {
+                [
+                    report.facility_id.as_deref().unwrap_or_default(),
+                    &report.certificate_number,
+                ]
+            },
+            &wanted,
+            &mut found,
+        ),
+        aquaculture: select(
+            &reports.aquaculture,
+            |site| [&site.facility_id, ""],
+            &wanted,
+            &mut found,
+        ),
+        fur_farms: select(
+            &reports.fur_farms,
+            |farm| [&farm.facility_id, ""],
+            &wanted,
+            &mut found,
+        ),
+        egg_farms: select(
+            &reports.egg_farms,
+            |farm| [&farm.facility_id, ""],
+            &wanted,
+            &mut found,
+        ),
+        cafos: select(
+            &reports.cafos,
+            |permit| [&permit.facility_id, ""],
+            &wanted,
+            &mut found,
+        ),
+        equine: select(
+            &reports.equine,
+            |site| [&site.facility_id, ""],
+            &wanted,
+            &mut found,
+        ),
+        ..Default::default()
+    };
+    let mut listed = HashSet::new();
+    batch.not_found = ids
+        .iter()
+        .map(|id| id.trim())
+        .filter(|id| !found.contains(id) && listed.insert(*id))
+        .collect();
+    Json(batch).into_response()
+}
@@ -0,0 +1,264 @@
+//! Converts the Department of Agriculture, Fisheries and Forestry's lists of
+//! export registered establishments into the standard locations CSV.
+//!
+//! Usage: `au-daff [--commodity meat] [--mapping FILE] < establishments.csv
+//! > au/locations.csv`
+//!
+//! The lists are published per commodity (meat, poultry, dairy, fish,
+//! eggs...); `--commodity` names it for files without a commodity column.
+//! Each entry's operations ("Abattoir, Boning room, Cold store") pick its
+//! activities through `operations.toml`, which `--mapping` extends in the
+//! same format, and the species it is registered for (cattle, sheep, goats...)
+//! are kept in the description the way the other countries' `type` column
+//! records them. Rows without coordinates are kept at 0, 0 for the geocode
+//! job.
+
+use std::collections::BTreeMap;
+use std::io::{self, Read};
+use std::path::PathBuf;
+
+use heatmap_backend::Location;
+use heatmap_backend::archive;
+use heatmap_backend::importer::{self, Options, Report, SourceAdapter};
+use heatmap_backend::mapping::MappingTable;
+use heatmap_backend::species;
+
+const DEFAULT_OPERATIONS: &str = include_str!("operations.toml");
+
+/// Header spellings seen in the lists, after normalization.
+const COLUMNS: &[(&str, &[&str])] = &[
+    (
+        "number",
+        &[
+            "establishment number",
+            "est no",
+            "est number",
+            "establishment no",
+            "registration number",
+            "export registration number",
+        ],
+    ),
+    (
+        "name",
+        &[
+            "establishment name",
+            "establishment",
+            "trading name",
+            "company name",
+            "name",
+        ],
+    ),
+    (
+        "street",
+        &["street address", "address", "site address", "street"],
+    ),
+    ("city", &["suburb", "town", "locality", "city"]),
+    ("state", &["state", "state territory"]),
+    ("zip", &["postcode", "post code"]),
+    ("commodity", &["commodity", "commodities", "sector"]),
+    (
+        "operations",
+        &[
+            "operations",
+            "operation",
+            "activities",
+            "activity",
+            "establishment type",
+            "type",
+        ],
+    ),
+    ("species", &["species", "approved species"]),
+    ("latitude", &["latitude", "lat"]),
+    ("longitude", &["longitude", "lon", "lng", "long"]),
+];
+
+/// Words naming each commodity key of `operations.toml`, most specific first
+/// ("poultry meat" is poultry).
+const COMMODITIES: &[(&str, &str)] = &[
+    ("poultry", "poultry"),
+    ("rabbit", "rabbit"),
+    ("game", "game"),
+    ("dairy", "dairy"),
+    ("milk", "dairy"),
+    ("fish", "fish"),
+    ("seafood", "fish"),
+    ("egg", "egg"),
+    ("eggs", "egg"),
+    ("meat", "meat"),
+];
+
+/// Column positions by canonical name.
+fn map_columns(headers: &[String]) -> BTreeMap<&'static str, usize> {
+    importer::map_columns(COLUMNS, headers)
+}
+
+/// The `operations.toml` commodity a commodity cell names.
+fn commodity_of(text: &str) -> Option<&'static str> {
+    let text = format!(" {} ", importer::normalize(text));
+    COMMODITIES
+        .iter()
+        .find(|(words, _)| text.contains(&format!(" {} ", words)))
+        .map(|(_, key)| *key)
+}
+
+/// The activities for one operation within a commodity: the longest
+/// `"<commodity> <words>"` key whose words appear in it, or the commodity's
+/// own key.
+fn activities_for<'a>(
+    operations: &'a MappingTable,
+    commodity: &str,
+    text: &str,
+) -> Option<&'a Vec<String>> {
+    let text = format!(" {} ", importer::normalize(text));
+    operations
+        .mappings
+        .iter()
+        .filter_map(|(key, activities)| {
+            let words = key.strip_prefix(commodity)?.strip_prefix(' ')?;
+            text.contains(&format!(" {} ", importer::normalize(words)))
+                .then_some((words.len(), activities))
+        })
+        .max_by_key(|(len, _)| *len)
+        .map(|(_, activities)| activities)
+        .or_else(|| operations.mappings.get(commodity))
+}
+
+struct Daff {
+    operations: MappingTable,
+    default_commodity: Option<&'static str>,
+    columns: BTreeMap<&'static str, usize>,
+}
+
+impl SourceAdapter for Daff {
+    type Row = Vec<String>;
+
+    fn name(&self) -> &'static str {
+        "au-daff"
+    }
+
+    fn parse(&mut self, input: &[u8]) -> Result<Vec<Vec<String>>, Box<dyn std::error::Error>> {
+        let (text, _) = archive::decode(input);
+        let mut rows = importer::csv_rows(&text)?;
+
+        // The header is the first row naming an establishment number column.
+        let header_index = rows
+            .iter()
+            .position(|row| map_columns(row).contains_key("number"))
+            .ok_or("no number column found")?;
+        self.columns = map_columns(&rows[header_index]);
+        if !self.columns.contains_key("name") {
+            return Err("no name column found".into());
+        }
+        if !self.columns.contains_key("commodity") && self.default_commodity.is_none() {
+            return Err("no commodity column found, pass --commodity".into());
+        }
+        Ok(rows.split_off(header_index + 1))
+    }
+
+    fn normalize(&mut self, row: Vec<String>, report: &mut Report) -> Option<Location> {
+        let cell = |name: &str| {
+            self.columns
+                .get(name)
+                .and_then(|i| row.get(*i))
+                .map(|value| value.trim())
+                .unwrap_or_default()
+        };
+        let number = cell("number");
+        if number.is_empty() {
+            report.skip("no establishment number");
+            return None;
+        }
+        let commodity = match cell("commodity") {
+            "" => self.default_commodity,
+            text => commodity_of(text),
+        };
+        let Some(commodity) = commodity.filter(|key| self.operations.mappings.contains_key(*key))
+        else {
+            report.unmapped(format!("commodity {}", cell("commodity")));
+            return None;
+        };
+
+        let mut activities: Vec<String> = Vec::new();
+        let mut mapped: Vec<&String> = cell("operations")
+            .split([',', ';', '/'])
+            .filter(|part| !part.trim().is_empty())
+            .filter_map(|part| activities_for(&self.operations, commodity, part))
+            .flatten()
+            .collect();
+        if mapped.is_empty() {
+            mapped.extend(self.operations.mappings[commodity].iter());
+        }
+        for activity in mapped {
+            if !activities.contains(activity) {
+                activities.push(activity.clone());
+            }
+        }
+
+        let mut species: Vec<String> = Vec::new();
+        for name in cell("species")
+            .split([',', ';', '/', '&'])
+            .map(str::trim)
+            .filter(|name| !name.is_empty())
+        {
+            match species::lookup(name) {
+                Some(taxon) => {
+                    if !species.iter().any(|known| known == taxon.singular) {
+                        species.push(taxon.singular.to_string());
+                    }
+                }
+                None => report.unmapped(format!("species {}", name)),
+            }
+        }
+        let activities = importer::describe(activities, &species);
+
+        // An establishment is listed once per commodity; `importer::run`
+        // merges them.
+        let coordinate = |name: &str| importer::parse_coordinate(cell(name)).unwrap_or(0.0);
+        Some(Location {
+            country: "AU".to_string(),
+            establishment_id: number.to_string(),
+            establishment_number: number.to_string(),
+            establishment_name: cell("name").to_string(),
+            street: cell("street").to_string(),
+            city: cell("city").to_string(),
+            state: cell("state").to_uppercase(),
+            zip: cell("zip").to_string(),
+            latitude: coordinate("latitude"),
+            longitude: coordinate("longitude"),
+            slaughter: activities.contains("Slaughter"),
+            activities,
+            ..Default::default()
+        })
+    }
+}
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let mut operations = MappingTable::parse(DEFAULT_OPERATIONS, "operations.toml")?;
+    let mut default_commodity = None;
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--mapping" => {
+                let path: PathBuf = args.next().ok_or("--mapping needs a file")?.into();
+                operations.merge(MappingTable::load(&path)?);
+            }
+            "--commodity" => {
+                let value = args.next().ok_or("--commodity needs a value")?;
+                default_commodity =
+                    Some(commodity_of(&value).ok_or(format!("unknown commodity {value:?}"))?);
+            }
+            _ => return Err(format!("unexpected argument {arg:?}").into()),
+        }
+    }
+
+    let mut input = Vec::new();
+    io::stdin().read_to_end(&mut input)?;
+    let mut daff = Daff {
+        operations,
+        default_commodity,
+        columns: BTreeMap::new(),
+    };
+    importer::run(&mut daff, &input, io::stdout(), &Options::default())?;
+
+    Ok(())
+}
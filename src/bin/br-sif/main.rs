@@ -0,0 +1,304 @@
+//! Converts the Ministério da Agricultura e Pecuária's list of establishments
+//! registered with the Serviço de Inspeção Federal (SIF) into the standard
+//! locations CSV.
+//!
+//! Usage: `br-sif [--mapping FILE] < sif.csv > br/locations.csv`
+//!
+//! Each row's class ("Abatedouro Frigorífico", "Unidade de Beneficiamento
+//! de Pescado"...), area and species pick its activities through
+//! `classes.toml`, which `--mapping` extends in the same format; rows matching
+//! no class (honey, wax) are skipped. Species are given in Portuguese
+//! ("Bovinos", "Suínos", "Aves"), translated with `SPECIES` and kept in the
+//! description the way the other countries' `type` column records them. Rows
+//! without coordinates are kept at 0, 0 for the geocode job.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::io::{self, Read};
+use std::path::PathBuf;
+
+use heatmap_backend::Location;
+use heatmap_backend::archive;
+use heatmap_backend::importer::{self, Options, Report, SourceAdapter};
+use heatmap_backend::mapping::MappingTable;
+use heatmap_backend::species;
+
+const DEFAULT_CLASSES: &str = include_str!("classes.toml");
+
+/// Header spellings seen in MAPA's exports, after normalization.
+const COLUMNS: &[(&str, &[&str])] = &[
+    (
+        "number",
+        &[
+            "sif",
+            "numero sif",
+            "n sif",
+            "no sif",
+            "numero do sif",
+            "registro",
+            "numero de registro",
+        ],
+    ),
+    (
+        "name",
+        &["razao social", "nome empresarial", "empresa", "nome"],
+    ),
+    ("dba", &["nome fantasia", "fantasia"]),
+    ("street", &["endereco", "logradouro"]),
+    ("city", &["municipio", "cidade"]),
+    ("state", &["uf", "estado"]),
+    ("zip", &["cep"]),
+    (
+        "class",
+        &["classe", "classificacao", "categoria", "atividade"],
+    ),
+    ("area", &["area", "area de atuacao", "setor"]),
+    ("species", &["especie", "especies", "especie animal"]),
+    ("latitude", &["latitude", "lat"]),
+    ("longitude", &["longitude", "lon", "lng"]),
+];
+
+/// Portuguese species names, singular and plural, as ontology IDs.
+const SPECIES: &[(&str, &str)] = &[
+    ("bovino", "cattle"),
+    ("bovinos", "cattle"),
+    ("bubalino", "water_buffalo"),
+    ("bubalinos", "water_buffalo"),
+    ("suino", "pig"),
+    ("suinos", "pig"),
+    ("javali", "feral_swine"),
+    ("javalis", "feral_swine"),
+    ("ovino", "sheep"),
+    ("ovinos", "sheep"),
+    ("caprino", "goat"),
+    ("caprinos", "goat"),
+    ("equideo", "horse"),
+    ("equideos", "horse"),
+    ("equino", "horse"),
+    ("equinos", "horse"),
+    ("coelho", "rabbit"),
+    ("coelhos", "rabbit"),
+    ("cervo", "deer"),
+    ("cervos", "deer"),
+    ("ave", "poultry"),
+    ("aves", "poultry"),
+    ("galinha", "chicken"),
+    ("galinhas", "chicken"),
+    ("frango", "chicken"),
+    ("frangos", "chicken"),
+    ("galinaceos", "chicken"),
+    ("peru", "turkey"),
+    ("perus", "turkey"),
+    ("pato", "duck"),
+    ("patos", "duck"),
+    ("ganso", "goose"),
+    ("gansos", "goose"),
+    ("codorna", "quail"),
+    ("codornas", "quail"),
+    ("avestruz", "ostrich"),
+    ("avestruzes", "ostrich"),
+    ("ema", "rhea"),
+    ("emas", "rhea"),
+    ("peixe", "fish"),
+    ("peixes", "fish"),
+    ("pescado", "fish"),
+    ("tilapia", "tilapia"),
+    ("salmao", "salmon"),
+    ("truta", "trout"),
+    ("camarao", "shrimp"),
+    ("camaroes", "shrimp"),
+    ("caranguejo", "crab"),
+    ("caranguejos", "crab"),
+    ("lagosta", "lobster"),
+    ("lagostas", "lobster"),
+    ("ostra", "oyster"),
+    ("ostras", "oyster"),
+    ("mexilhao", "mussel"),
+    ("mexilhoes", "mussel"),
+    ("marisco", "clam"),
+    ("mariscos", "clam"),
+    ("vieira", "scallop"),
+    ("vieiras", "scallop"),
+];
+
+/// Column positions by canonical name.
+fn map_columns(headers: &[String]) -> BTreeMap<&'static str, usize> {
+    importer::map_columns(COLUMNS, headers)
+}
+
+/// The activities of the key with the most words that all appear in `text`.
+fn activities_for<'a>(classes: &'a MappingTable, text: &str) -> Option<&'a Vec<String>> {
+    let words: BTreeSet<String> = importer::normalize(text)
+        .split(' ')
+        .map(str::to_string)
+        .collect();
+    classes
+        .mappings
+        .iter()
+        .filter_map(|(key, activities)| {
+            let key_words: Vec<String> = importer::normalize(key)
+                .split(' ')
+                .map(str::to_string)
+                .collect();
+            key_words
+                .iter()
+                .all(|word| words.contains(word))
+                .then_some((key_words.len(), activities))
+        })
+        .max_by_key(|(len, _)| *len)
+        .map(|(_, activities)| activities)
+}
+
+/// Meat slaughter and processing as poultry slaughter and processing for
+/// poultry species, keeping the meat ones for the other species (or when
+/// the row names none). Abatedouros handle both under the same class.
+fn for_species(activities: &[String], taxa: &[&species::Taxon]) -> Vec<String> {
+    let poultry = taxa.iter().any(|t| species::is_within(t.id, "poultry"));
+    let other = taxa.is_empty() || taxa.iter().any(|t| !species::is_within(t.id, "poultry"));
+    let mut result = Vec::new();
+    for activity in activities {
+        let poultry_activity = match activity.as_str() {
+            "Meat Slaughter" => Some("Poultry Slaughter"),
+            "Meat Processing" => Some("Poultry Processing"),
+            _ => None,
+        };
+        match poultry_activity {
+            Some(poultry_activity) => {
+                if poultry {
+                    result.push(poultry_activity.to_string());
+                }
+                if other {
+                    result.push(activity.clone());
+                }
+            }
+            None => result.push(activity.clone()),
+        }
+    }
+    result
+}
+
+struct Sif {
+    classes: MappingTable,
+    columns: BTreeMap<&'static str, usize>,
+}
+
+impl SourceAdapter for Sif {
+    type Row = Vec<String>;
+
+    fn name(&self) -> &'static str {
+        "br-sif"
+    }
+
+    fn parse(&mut self, input: &[u8]) -> Result<Vec<Vec<String>>, Box<dyn std::error::Error>> {
+        let (text, _) = archive::decode(input);
+        let mut rows = importer::csv_rows(&text)?;
+
+        // Exports sometimes start with a title line; the header is the first
+        // row naming a SIF number column.
+        let header_index = rows
+            .iter()
+            .position(|row| map_columns(row).contains_key("number"))
+            .ok_or("no SIF number column found")?;
+        self.columns = map_columns(&rows[header_index]);
+        for required in ["name", "class"] {
+            if !self.columns.contains_key(required) {
+                return Err(format!("no {} column found", required).into());
+            }
+        }
+        Ok(rows.split_off(header_index + 1))
+    }
+
+    fn normalize(&mut self, row: Vec<String>, report: &mut Report) -> Option<Location> {
+        let cell = |name: &str| {
+            self.columns
+                .get(name)
+                .and_then(|i| row.get(*i))
+                .map(|value| value.trim())
+                .unwrap_or_default()
+        };
+        let number = cell("number");
+        if number.is_empty() {
+            report.skip("no SIF number");
+            return None;
+        }
+
+        let mut taxa = Vec::new();
+        for name in cell("species")
+            .split([',', ';', '/', '|'])
+            .map(importer::normalize)
+            .filter(|name| !name.is_empty())
+        {
+            let found = SPECIES
+                .iter()
+                .find(|(portuguese, _)| *portuguese == name)
+                .and_then(|(_, id)| species::taxon(id))
+                .or_else(|| species::lookup(&name));
+            match found {
+                Some(taxon) => taxa.push(taxon),
+                None => report.unmapped(format!("species {}", name)),
+            }
+        }
+
+        let description = format!("{} {}", cell("class"), cell("area"));
+        let Some(mapped) = activities_for(&self.classes, &description) else {
+            report.unmapped(format!("class {}", cell("class")));
+            return None;
+        };
+        let mut activities: Vec<String> = Vec::new();
+        for activity in for_species(mapped, &taxa) {
+            if !activities.contains(&activity) {
+                activities.push(activity);
+            }
+        }
+        let mut species: Vec<String> = Vec::new();
+        for taxon in taxa {
+            if !species.iter().any(|known| known == taxon.singular) {
+                species.push(taxon.singular.to_string());
+            }
+        }
+        let activities = importer::describe(activities, &species);
+
+        // An establishment is listed once per class and species;
+        // `importer::run` merges them.
+        let coordinate = |name: &str| importer::parse_coordinate(cell(name)).unwrap_or(0.0);
+        Some(Location {
+            country: "BR".to_string(),
+            establishment_id: number.to_string(),
+            establishment_number: format!("SIF {}", number),
+            establishment_name: cell("name").to_string(),
+            dbas: cell("dba").to_string(),
+            street: cell("street").to_string(),
+            city: cell("city").to_string(),
+            state: cell("state").to_uppercase(),
+            zip: cell("zip").to_string(),
+            latitude: coordinate("latitude"),
+            longitude: coordinate("longitude"),
+            slaughter: activities.contains("Slaughter"),
+            activities,
+            ..Default::default()
+        })
+    }
+}
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let mut classes = MappingTable::parse(DEFAULT_CLASSES, "classes.toml")?;
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--mapping" => {
+                let path: PathBuf = args.next().ok_or("--mapping needs a file")?.into();
+                classes.merge(MappingTable::load(&path)?);
+            }
+            _ => return Err(format!("unexpected argument {arg:?}").into()),
+        }
+    }
+
+    let mut input = Vec::new();
+    io::stdin().read_to_end(&mut input)?;
+    let mut sif = Sif {
+        classes,
+        columns: BTreeMap::new(),
+    };
+    importer::run(&mut sif, &input, io::stdout(), &Options::default())?;
+
+    Ok(())
+}
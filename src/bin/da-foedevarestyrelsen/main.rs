@@ -1,100 +1,66 @@
-use std::io;
+//! Converts Fødevarestyrelsen's smiley export (Smiley_xml.xml) into the
+//! standard locations CSV.
+//!
+//! Usage: `da-foedevarestyrelsen [--mapping FILE] [--skip-missing-coordinates]
+//! [--report FILE] < Smiley_xml.xml > dk/locations.csv`
+//!
+//! Animal product industries without an entry in the mapping table are left
+//! out and listed at the end, with `--report` writing them as a mapping file
+//! to fill in and pass back with `--mapping`. Rows without usable coordinates
+//! are kept at 0, 0 so the geocode job picks them up, unless
+//! `--skip-missing-coordinates` is given.
 
-use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+use std::io::{self, Read};
+use std::path::PathBuf;
 
-use heatmap_backend::Location;
+use heatmap_backend::importer::{self, Options};
+use heatmap_backend::mapping::MappingTable;
+use heatmap_backend::smiley::Smiley;
 
-#[derive(Debug, Serialize, Deserialize, PartialEq)]
-struct Document(Vec<Row>);
-
-#[derive(Debug, Serialize, Deserialize, PartialEq)]
-struct Row {
-    navnelbnr: usize,
-    cvrnr: String,
-    pnr: String,
-    // region: (),
-    #[serde(rename = "brancheKode")]
-    industry_code: String,
-    #[serde(rename = "branche")]
-    industry: String,
-    #[serde(rename = "virksomhedstype")]
-    company_type: String,
-    #[serde(rename = "navn1")]
-    name: String,
-    #[serde(rename = "adresse1")]
-    address: String,
-    #[serde(rename = "postnr")]
-    zip: usize,
-    #[serde(rename = "By")]
-    city: String,
-    // <seneste_kontrol>1</seneste_kontrol>
-    // <seneste_kontrol_dato>27-11-2024 00:00:00</seneste_kontrol_dato>
-    // <naestseneste_kontrol>1</naestseneste_kontrol>
-    // <naestseneste_kontrol_dato>01-11-2023 00:00:00</naestseneste_kontrol_dato>
-    // <tredjeseneste_kontrol>1</tredjeseneste_kontrol>
-    // <tredjeseneste_kontrol_dato>02-11-2022 00:00:00</tredjeseneste_kontrol_dato>
-    // <fjerdeseneste_kontrol>1</fjerdeseneste_kontrol>
-    // <fjerdeseneste_kontrol_dato>09-08-2022 00:00:00</fjerdeseneste_kontrol_dato>
-    // <URL>http://www.findsmiley.dk/da-DK/Searching/DetailsView.htm?virk=921228</URL>
-    // <reklame_beskyttelse>0</reklame_beskyttelse>
-    // <Elite_Smiley>0</Elite_Smiley>
-    // <Kaedenavn></Kaedenavn>
-    #[serde(rename = "Geo_Lng")]
-    lng: String,
-    #[serde(rename = "Geo_Lat")]
-    lat: String,
-    // <Pixibranche>Fiske- og vildtforretninger, fiskeafdelinger</Pixibranche>
+/// A mapping file listing the unmapped industries, commented out until
+/// someone fills in their activities.
+fn mapping_stub(unmapped: &BTreeMap<String, usize>) -> String {
+    let mut stub = String::from(
+        "# Industries da-foedevarestyrelsen could not map. Fill in the activities,\n\
+         # uncomment, and pass this file back with --mapping.\n\n[mappings]\n",
+    );
+    for (industry, rows) in unmapped {
+        let _ = writeln!(stub, "# {:?} = []  # {} rows", industry, rows);
+    }
+    stub
 }
 
 fn main() -> Result<(), Box<dyn std::error::Error>> {
-    let stdin = io::stdin();
-    let doc: Document = serde_xml_rs::from_reader(stdin).unwrap();
-    // for row in doc.0 {
-    //     println!("{} {}", row.branche, row.branche_kode);
-    // }
-    // return Ok(());
-    let locs: Vec<_> = doc
-        .0
-        .into_iter()
-        .filter(|row| {
-            row.industry
-                .to_lowercase()
-                .starts_with("fremstilling af animalske produkter")
-                || row.industry.to_lowercase().contains("slagter")
-        })
-        .enumerate()
-        .map(|(i, row)| Location {
-            county: "Denmark".to_string(),
-            establishment_id: i.to_string(),
-            establishment_name: row.name,
-            city: row.city,
-            street: row.address,
-            zip: row.zip.to_string(),
-            activities: match &row.industry[..] {
-                "Fremstilling af animalske produkter - Fisk og muslinger m.v."
-                | "Fremstilling af animalske produkter - Kød"
-                | "Slagterier"
-                | "Specialforretning - Slagter m.v."
-                | "Virksomhed, foreløbig AUT: Slagteri, slagteri med fremstilli"
-                | "Virksomhed, foreløbig: Slagter, slagterafdeling" => {
-                    "Meat Processing; Meat Slaughter"
-                }
-                "Fremstilling af animalske produkter - Andre produkter"
-                | "Fremstilling af animalske produkter - Mælk og ost"
-                | "Fremstilling af animalske produkter - Æg" => "Meat Processing",
-                b => todo!("{b:?}"),
+    let mut smiley = Smiley::new()?;
+    let mut skip_missing_coordinates = false;
+    let mut report_path: Option<PathBuf> = None;
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--mapping" => {
+                let path: PathBuf = args.next().ok_or("--mapping needs a file")?.into();
+                smiley.industries.merge(MappingTable::load(&path)?);
             }
-            .to_string(),
-            latitude: row.lat.parse().unwrap_or(0.0),
-            longitude: row.lng.parse().unwrap_or(0.0),
-            ..Default::default()
-        })
-        .collect();
+            "--skip-missing-coordinates" => skip_missing_coordinates = true,
+            "--report" => {
+                report_path = Some(args.next().ok_or("--report needs a file")?.into());
+            }
+            _ => return Err(format!("unexpected argument {arg:?}").into()),
+        }
+    }
 
-    let mut wtr = csv::Writer::from_writer(io::stdout());
+    let mut input = Vec::new();
+    io::stdin().read_to_end(&mut input)?;
+    let options = Options {
+        skip_missing_coordinates,
+    };
+    let report = importer::run(&mut smiley, &input, io::stdout(), &options)?;
 
-    for loc in locs {
-        wtr.serialize(loc).unwrap();
+    if let Some(path) = report_path {
+        std::fs::write(&path, mapping_stub(&report.unmapped))
+            .map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
     }
 
     Ok(())
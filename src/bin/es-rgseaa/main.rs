@@ -0,0 +1,258 @@
+//! Converts exports of Spain's Registro General Sanitario de Empresas
+//! Alimentarias y Alimentos (RGSEAA) into the standard locations CSV.
+//!
+//! Usage: `es-rgseaa [--mapping FILE] [--merge es/locations.csv] < rgseaa.csv
+//! > es/locations.new.csv`
+//!
+//! Registration numbers look like `10.012345/M`: the clave (sector), the
+//! order number and the province. Only the sectors in `claves.toml` are kept
+//! (carnes, pescados, huevos, leche), each entry's category and activity
+//! picking the activities within its sector. `--mapping` adds or overrides
+//! entries in the same format. es/locations.csv also holds the REGA farm
+//! register; `--merge` copies its rows that aren't RGSEAA entries into the
+//! output, so write to a new file and move it over the old one. Rows without
+//! coordinates are kept at 0, 0 for the geocode job.
+
+use std::collections::BTreeMap;
+use std::io::{self, Read};
+use std::path::PathBuf;
+
+use heatmap_backend::Location;
+use heatmap_backend::archive;
+use heatmap_backend::importer::{self, Options, Report, SourceAdapter};
+use heatmap_backend::mapping::MappingTable;
+
+const DEFAULT_CLAVES: &str = include_str!("claves.toml");
+
+/// Header spellings seen in AESAN's exports, after normalization.
+const COLUMNS: &[(&str, &[&str])] = &[
+    (
+        "number",
+        &[
+            "n rgseaa",
+            "no rgseaa",
+            "numero rgseaa",
+            "rgseaa",
+            "numero de registro",
+            "n registro",
+            "registro sanitario",
+            "numero registro sanitario",
+        ],
+    ),
+    (
+        "name",
+        &[
+            "razon social",
+            "nombre",
+            "titular",
+            "empresa",
+            "denominacion",
+        ],
+    ),
+    (
+        "street",
+        &["domicilio", "direccion", "domicilio industrial", "calle"],
+    ),
+    ("city", &["localidad", "municipio", "poblacion"]),
+    ("province", &["provincia"]),
+    ("region", &["comunidad autonoma", "ccaa", "comunidad"]),
+    ("zip", &["codigo postal", "cp", "c p"]),
+    ("clave", &["clave"]),
+    ("category", &["categoria", "categorias"]),
+    ("activity", &["actividad", "actividades"]),
+    ("latitude", &["latitud", "latitude", "lat"]),
+    ("longitude", &["longitud", "longitude", "lon", "lng"]),
+];
+
+/// Column positions by canonical name.
+fn map_columns(headers: &[String]) -> BTreeMap<&'static str, usize> {
+    importer::map_columns(COLUMNS, headers)
+}
+
+/// Whether `id` is an RGSEAA registration number ("10.012345/M").
+fn is_rgseaa_number(id: &str) -> bool {
+    let Some((clave, rest)) = id.split_once('.') else {
+        return false;
+    };
+    clave.len() == 2 && clave.chars().all(|c| c.is_ascii_digit()) && rest.contains('/')
+}
+
+/// The activities for one part of an entry's description within its sector:
+/// the longest `"<clave> <words>"` key whose words appear in it, or the
+/// sector's own key.
+fn activities_for<'a>(
+    claves: &'a MappingTable,
+    clave: &str,
+    text: &str,
+) -> Option<&'a Vec<String>> {
+    let text = format!(" {} ", importer::normalize(text));
+    claves
+        .mappings
+        .iter()
+        .filter_map(|(key, activities)| {
+            let words = key.strip_prefix(clave)?.strip_prefix(' ')?;
+            text.contains(&format!(" {} ", importer::normalize(words)))
+                .then_some((words.len(), activities))
+        })
+        .max_by_key(|(len, _)| *len)
+        .map(|(_, activities)| activities)
+        .or_else(|| claves.mappings.get(clave))
+}
+
+struct Rgseaa {
+    claves: MappingTable,
+    columns: BTreeMap<&'static str, usize>,
+    /// Rows kept, by clave, for the summary.
+    by_clave: BTreeMap<String, usize>,
+}
+
+impl SourceAdapter for Rgseaa {
+    type Row = Vec<String>;
+
+    fn name(&self) -> &'static str {
+        "es-rgseaa"
+    }
+
+    fn parse(&mut self, input: &[u8]) -> Result<Vec<Vec<String>>, Box<dyn std::error::Error>> {
+        let (text, _) = archive::decode(input);
+        let mut rows = importer::csv_rows(&text)?;
+
+        // Exports sometimes start with a title line; the header is the first
+        // row naming a registration number column.
+        let header_index = rows
+            .iter()
+            .position(|row| map_columns(row).contains_key("number"))
+            .ok_or("no RGSEAA number column found")?;
+        self.columns = map_columns(&rows[header_index]);
+        if !self.columns.contains_key("name") {
+            return Err("no name column found".into());
+        }
+        Ok(rows.split_off(header_index + 1))
+    }
+
+    fn normalize(&mut self, row: Vec<String>, report: &mut Report) -> Option<Location> {
+        let cell = |name: &str| {
+            self.columns
+                .get(name)
+                .and_then(|i| row.get(*i))
+                .map(|value| value.trim())
+                .unwrap_or_default()
+        };
+        let number = cell("number");
+        if number.is_empty() {
+            report.skip("no RGSEAA number");
+            return None;
+        }
+        let clave = match cell("clave") {
+            "" => number.split('.').next().unwrap_or_default(),
+            clave => clave,
+        };
+        let clave = format!("{:0>2}", clave.trim());
+        if !self.claves.mappings.contains_key(&clave) {
+            report.skip("outside the mapped sectors");
+            return None;
+        }
+
+        let mut activities: Vec<String> = Vec::new();
+        let description = format!("{}; {}", cell("category"), cell("activity"));
+        for part in description.split([';', ',', '/']) {
+            if part.trim().is_empty() {
+                continue;
+            }
+            for activity in activities_for(&self.claves, &clave, part)
+                .into_iter()
+                .flatten()
+            {
+                if !activities.contains(activity) {
+                    activities.push(activity.clone());
+                }
+            }
+        }
+        if activities.is_empty()
+            && let Some(defaults) = self.claves.mappings.get(&clave)
+        {
+            activities.extend(defaults.iter().cloned());
+        }
+        let activities = importer::describe(activities, &[]);
+
+        // An establishment is listed once per category it is registered for;
+        // `importer::run` merges them.
+        let coordinate = |name: &str| importer::parse_coordinate(cell(name)).unwrap_or(0.0);
+        let location = Location {
+            country: "ES".to_string(),
+            establishment_id: number.to_string(),
+            establishment_number: number.to_string(),
+            establishment_name: cell("name").to_string(),
+            street: cell("street").to_string(),
+            city: cell("city").to_string(),
+            county: cell("province").to_string(),
+            state: cell("region").to_string(),
+            zip: cell("zip").to_string(),
+            latitude: coordinate("latitude"),
+            longitude: coordinate("longitude"),
+            slaughter: activities.contains("Slaughter"),
+            activities,
+            ..Default::default()
+        };
+        *self.by_clave.entry(clave).or_default() += 1;
+        Some(location)
+    }
+}
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let mut claves = MappingTable::parse(DEFAULT_CLAVES, "claves.toml")?;
+    let mut merge_path: Option<PathBuf> = None;
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--mapping" => {
+                let path: PathBuf = args.next().ok_or("--mapping needs a file")?.into();
+                claves.merge(MappingTable::load(&path)?);
+            }
+            "--merge" => merge_path = Some(args.next().ok_or("--merge needs a file")?.into()),
+            _ => return Err(format!("unexpected argument {arg:?}").into()),
+        }
+    }
+
+    let mut kept = Vec::new();
+    if let Some(path) = &merge_path {
+        let mut reader = csv::Reader::from_path(path)
+            .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+        for result in reader.deserialize() {
+            let location: Location = result?;
+            if !is_rgseaa_number(&location.establishment_id) {
+                kept.push(location);
+            }
+        }
+    }
+
+    let mut input = Vec::new();
+    io::stdin().read_to_end(&mut input)?;
+    let mut rgseaa = Rgseaa {
+        claves,
+        columns: BTreeMap::new(),
+        by_clave: BTreeMap::new(),
+    };
+    // The kept rows go first, so the import is written after them.
+    let mut imported = Vec::new();
+    importer::run(&mut rgseaa, &input, &mut imported, &Options::default())?;
+
+    let mut wtr = csv::Writer::from_writer(io::stdout());
+    for location in &kept {
+        wtr.serialize(location)?;
+    }
+    for result in csv::Reader::from_reader(imported.as_slice()).deserialize() {
+        let location: Location = result?;
+        wtr.serialize(location)?;
+    }
+    wtr.flush()?;
+
+    if merge_path.is_some() {
+        eprintln!("kept {} rows that aren't RGSEAA entries", kept.len());
+    }
+    for (clave, rows) in &rgseaa.by_clave {
+        eprintln!("  clave {} on {} rows", clave, rows);
+    }
+
+    Ok(())
+}
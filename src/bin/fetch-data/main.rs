@@ -0,0 +1,311 @@
+//! Downloads the upstream datasets and runs each through its importer into
+//! static_data, so updating the data is one reproducible command.
+//!
+//! Usage: `fetch-data [--data DIR] [--config FILE] [--input NAME=FILE]...
+//! [--only NAME,...] [--dry-run]`
+//!
+//! The sources and their importers are listed in `sources.toml`, which
+//! `--config` extends in the same format. `--input` uses a local file instead
+//! of downloading, for sources published only through search tools. Each
+//! output replaces the old file only once its importer has succeeded; one
+//! source failing doesn't stop the others, but makes the run fail. New
+//! establishments are left at 0, 0 for the geocode job. Every location list
+//! that changed is then saved as a dated snapshot for the changelog.
+
+use std::collections::BTreeMap;
+use std::fs::{self, File};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::time::Duration;
+
+use serde::Deserialize;
+
+const DEFAULT_SOURCES: &str = include_str!("sources.toml");
+const DEFAULT_DATA_DIR: &str = "static_data";
+
+#[derive(Deserialize)]
+struct Config {
+    #[serde(default)]
+    source: Vec<Source>,
+}
+
+#[derive(Deserialize, Clone)]
+struct Source {
+    name: String,
+    url: Option<String>,
+    page: Option<String>,
+    link: Option<String>,
+    command: Vec<String>,
+    #[serde(default)]
+    stdin: bool,
+    result: Option<String>,
+    output: String,
+}
+
+fn parse_config(text: &str, origin: &str) -> Result<Vec<Source>, Box<dyn std::error::Error>> {
+    let config: Config =
+        toml::from_str(text).map_err(|e| format!("Failed to parse {}: {}", origin, e))?;
+    for source in &config.source {
+        if source.command.is_empty() {
+            return Err(format!("{}: source {} has no command", origin, source.name).into());
+        }
+    }
+    Ok(config.source)
+}
+
+/// Values for the placeholders in commands and paths.
+struct Placeholders {
+    input: PathBuf,
+    work: PathBuf,
+    data: PathBuf,
+    date: String,
+}
+
+impl Placeholders {
+    fn fill(&self, template: &str) -> String {
+        template
+            .replace("{input}", &self.input.display().to_string())
+            .replace("{work}", &self.work.display().to_string())
+            .replace("{data}", &self.data.display().to_string())
+            .replace("{date}", &self.date)
+            .replace("{year}", &self.date[..4])
+    }
+}
+
+fn client() -> Result<reqwest::blocking::Client, reqwest::Error> {
+    reqwest::blocking::Client::builder()
+        .user_agent("UntilEveryCage fetch-data")
+        .timeout(Duration::from_secs(300))
+        .build()
+}
+
+fn download(url: &str, dest: &Path) -> Result<(), Box<dyn std::error::Error>> {
+    let bytes = client()?
+        .get(url)
+        .send()?
+        .error_for_status()
+        .map_err(|e| format!("Failed to download {}: {}", url, e))?
+        .bytes()?;
+    fs::write(dest, &bytes)?;
+    Ok(())
+}
+
+/// The first link on `page` whose address contains every word of `link`.
+fn find_link(page: &str, link: &str) -> Result<String, Box<dyn std::error::Error>> {
+    let html = client()?
+        .get(page)
+        .send()?
+        .error_for_status()
+        .map_err(|e| format!("Failed to fetch {}: {}", page, e))?
+        .text()?;
+    let base = reqwest::Url::parse(page)?;
+    let words: Vec<String> = link.split_whitespace().map(str::to_lowercase).collect();
+    for chunk in html.split("href=").skip(1) {
+        let Some(quote) = chunk.chars().next().filter(|c| *c == '"' || *c == '\'') else {
+            continue;
+        };
+        let Some(href) = chunk[1..].split(quote).next() else {
+            continue;
+        };
+        let lower = href.to_lowercase();
+        if words.iter().all(|word| lower.contains(word.as_str())) {
+            return Ok(base.join(&href.replace("&amp;", "&"))?.to_string());
+        }
+    }
+    Err(format!("No link matching {:?} on {}", link, page).into())
+}
+
+/// An importer built alongside this binary.
+fn importer(name: &str) -> Result<PathBuf, Box<dyn std::error::Error>> {
+    let path = std::env::current_exe()?.with_file_name(format!(
+        "{}{}",
+        name,
+        std::env::consts::EXE_SUFFIX
+    ));
+    if !path.exists() {
+        return Err(format!(
+            "importer {} not found next to fetch-data, build it with `cargo build --release --bins`",
+            name
+        )
+        .into());
+    }
+    Ok(path)
+}
+
+enum Outcome {
+    Written(PathBuf),
+    Skipped(String),
+}
+
+fn fetch(
+    source: &Source,
+    input: Option<&PathBuf>,
+    data: &Path,
+    work: &Path,
+    dry_run: bool,
+) -> Result<Outcome, Box<dyn std::error::Error>> {
+    let work = work.join(&source.name);
+    fs::create_dir_all(&work)?;
+    let placeholders = Placeholders {
+        input: input.cloned().unwrap_or_else(|| work.join("input")),
+        work: work.clone(),
+        data: data.to_path_buf(),
+        date: chrono::Utc::now().format("%Y-%m-%d").to_string(),
+    };
+    let output = data.join(placeholders.fill(&source.output));
+
+    let url = match (input, &source.url, &source.page, &source.link) {
+        (Some(_), _, _, _) => None,
+        (None, Some(url), _, _) => Some(url.clone()),
+        (None, None, Some(page), Some(link)) if !dry_run => Some(find_link(page, link)?),
+        (None, None, Some(page), Some(_)) => Some(format!("a link on {}", page)),
+        _ => {
+            return Ok(Outcome::Skipped(format!(
+                "no url configured, pass --input {}=<file>",
+                source.name
+            )));
+        }
+    };
+    let args: Vec<String> = source.command[1..]
+        .iter()
+        .map(|arg| placeholders.fill(arg))
+        .collect();
+    if dry_run {
+        eprintln!(
+            "{}: {} | {} {} -> {}",
+            source.name,
+            url.unwrap_or_else(|| placeholders.input.display().to_string()),
+            source.command[0],
+            args.join(" "),
+            output.display()
+        );
+        return Ok(Outcome::Skipped("dry run".to_string()));
+    }
+    if let Some(url) = url {
+        eprintln!("{}: downloading {}", source.name, url);
+        download(&url, &placeholders.input)?;
+    }
+
+    let stdout_path = work.join("stdout");
+    let mut command = Command::new(importer(&source.command[0])?);
+    command
+        .args(&args)
+        .stdout(File::create(&stdout_path)?)
+        .stderr(Stdio::inherit());
+    // Importers record their runs in the data directory being written.
+    if std::env::var_os("IMPORT_RUNS_FILE").is_none() {
+        command.env("IMPORT_RUNS_FILE", data.join("import_runs.toml"));
+    }
+    if source.stdin {
+        command.stdin(File::open(&placeholders.input)?);
+    } else {
+        command.stdin(Stdio::null());
+    }
+    let status = command.status()?;
+    if !status.success() {
+        return Err(format!("{} exited with {}", source.command[0], status).into());
+    }
+
+    let result = match &source.result {
+        Some(result) => PathBuf::from(placeholders.fill(result)),
+        None => stdout_path,
+    };
+    if fs::metadata(&result).map(|m| m.len()).unwrap_or_default() == 0 {
+        return Err(format!(
+            "{} wrote nothing to {}",
+            source.command[0],
+            result.display()
+        )
+        .into());
+    }
+    if let Some(dir) = output.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    // Copied next to the output first, so the old file is only replaced whole.
+    let tmp = output.with_extension("fetching");
+    fs::copy(&result, &tmp)?;
+    fs::rename(&tmp, &output)?;
+    Ok(Outcome::Written(output))
+}
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let mut sources = parse_config(DEFAULT_SOURCES, "sources.toml")?;
+    let mut data = PathBuf::from(DEFAULT_DATA_DIR);
+    let mut inputs: BTreeMap<String, PathBuf> = BTreeMap::new();
+    let mut only: Option<Vec<String>> = None;
+    let mut dry_run = false;
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--data" => data = args.next().ok_or("--data needs a directory")?.into(),
+            "--config" => {
+                let path = args.next().ok_or("--config needs a file")?;
+                let text = fs::read_to_string(&path)
+                    .map_err(|e| format!("Failed to read {}: {}", path, e))?;
+                for source in parse_config(&text, &path)? {
+                    sources.retain(|known| known.name != source.name);
+                    sources.push(source);
+                }
+            }
+            "--input" => {
+                let value = args.next().ok_or("--input needs NAME=FILE")?;
+                let (name, path) = value
+                    .split_once('=')
+                    .ok_or_else(|| format!("expected NAME=FILE, got {value:?}"))?;
+                inputs.insert(name.to_string(), PathBuf::from(path));
+            }
+            "--only" => {
+                let names = args.next().ok_or("--only needs source names")?;
+                only = Some(
+                    names
+                        .split(',')
+                        .map(|name| name.trim().to_string())
+                        .collect(),
+                );
+            }
+            "--dry-run" => dry_run = true,
+            _ => return Err(format!("unexpected argument {arg:?}").into()),
+        }
+    }
+    for name in inputs.keys().chain(only.iter().flatten()) {
+        if !sources.iter().any(|source| source.name == *name) {
+            return Err(format!("unknown source {name:?}").into());
+        }
+    }
+    if !data.is_dir() {
+        return Err(format!("data directory {} not found", data.display()).into());
+    }
+
+    let work = std::env::temp_dir().join(format!("fetch-data-{}", std::process::id()));
+    let mut failed = 0;
+    for source in &sources {
+        if only
+            .as_ref()
+            .is_some_and(|names| !names.contains(&source.name))
+        {
+            continue;
+        }
+        match fetch(source, inputs.get(&source.name), &data, &work, dry_run) {
+            Ok(Outcome::Written(path)) => eprintln!("{}: wrote {}", source.name, path.display()),
+            Ok(Outcome::Skipped(reason)) => eprintln!("{}: skipped, {}", source.name, reason),
+            Err(e) => {
+                eprintln!("{}: failed, {}", source.name, e);
+                failed += 1;
+            }
+        }
+    }
+    let _ = fs::remove_dir_all(&work);
+
+    // Saved even when a source failed, for the lists that were refreshed.
+    if !dry_run {
+        let today = chrono::Utc::now().format("%Y-%m-%d").to_string();
+        for country in heatmap_backend::snapshots::save_snapshots(&data, &today)? {
+            eprintln!("{}: saved snapshot {}", country, today);
+        }
+    }
+
+    if failed > 0 {
+        return Err(format!("{} sources failed", failed).into());
+    }
+    Ok(())
+}
@@ -0,0 +1,196 @@
+//! Geocodes the rows of static_data CSVs that have no coordinates and writes
+//! the coordinates back into the files.
+//!
+//! Usage: `geocode [--provider nominatim|photon|geocodio] [--cache FILE]
+//! [--limit N] [--all] static_data/it/locations.csv ...`
+//!
+//! Works on any CSV with `latitude` and `longitude` columns and some of
+//! `street`, `city`, `county`, `state` and `zip` (locations, aquaculture
+//! sites, fur farms, egg farms); the country is the file's country
+//! directory. Only rows at 0, 0 are geocoded unless `--all` is given. Every
+//! answer, including "not found", is kept in the cache (`geocode_cache.csv`
+//! by default) so reruns and interrupted runs only query the provider for new
+//! addresses, and requests are spaced to the provider's rate limit. `--limit`
+//! stops after N provider requests. The provider defaults to `GEOCODER` (see `geocode::provider`).
+
+use std::path::{Path, PathBuf};
+use std::time::Instant;
+
+use heatmap_backend::geocode::{self, GeocodeCache, address_query, country_name};
+use heatmap_backend::settings::Settings;
+
+const ADDRESS_COLUMNS: &[&str] = &["street", "city", "county", "state", "zip"];
+
+/// The cache is written every this many provider requests.
+const SAVE_EVERY: usize = 50;
+
+/// The country directory a file is in: `static_data/it/locations.csv` or
+/// `static_data/us/fur_farms/wi.csv`.
+fn country_of(path: &Path) -> Option<String> {
+    path.ancestors()
+        .skip(1)
+        .filter_map(|dir| dir.file_name())
+        .map(|name| name.to_string_lossy().to_string())
+        .find(|name| name.len() == 2 && name.chars().all(|c| c.is_ascii_lowercase()))
+}
+
+#[derive(Default)]
+struct Totals {
+    rows: usize,
+    from_cache: usize,
+    geocoded: usize,
+    not_found: usize,
+    errors: usize,
+}
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let mut provider_name = None;
+    let mut cache_path = PathBuf::from("geocode_cache.csv");
+    let mut limit = usize::MAX;
+    let mut all = false;
+    let mut files = Vec::new();
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--provider" => provider_name = Some(args.next().ok_or("--provider needs a name")?),
+            "--cache" => cache_path = args.next().ok_or("--cache needs a file")?.into(),
+            "--limit" => {
+                limit = args
+                    .next()
+                    .and_then(|value| value.parse().ok())
+                    .ok_or("--limit needs a number")?
+            }
+            "--all" => all = true,
+            _ if arg.starts_with("--") => return Err(format!("unexpected argument {arg:?}").into()),
+            _ => files.push(PathBuf::from(arg)),
+        }
+    }
+    if files.is_empty() {
+        return Err("no CSV files given".into());
+    }
+
+    let geocoder = match provider_name {
+        Some(name) => geocode::provider(&name, &Settings::from_env())?,
+        None => geocode::provider_from_env()?,
+    };
+    let mut cache = GeocodeCache::load(&cache_path)?;
+    let mut requests = 0;
+    let mut last_request: Option<Instant> = None;
+
+    for path in &files {
+        let country = country_of(path)
+            .ok_or_else(|| format!("{}: not inside a country directory", path.display()))?;
+        let mut reader = csv::Reader::from_path(path)
+            .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+        let headers = reader.headers()?.clone();
+        let position = |name: &str| headers.iter().position(|header| header == name);
+        let (Some(lat_column), Some(lng_column)) = (position("latitude"), position("longitude"))
+        else {
+            return Err(format!("{}: no latitude and longitude columns", path.display()).into());
+        };
+        let address_columns: Vec<usize> = ADDRESS_COLUMNS
+            .iter()
+            .filter_map(|name| position(name))
+            .collect();
+        let mut records: Vec<csv::StringRecord> = reader.records().collect::<Result<_, _>>()?;
+
+        let mut totals = Totals::default();
+        for record in &mut records {
+            let missing = record[lat_column].parse::<f64>().unwrap_or(0.0) == 0.0
+                && record[lng_column].parse::<f64>().unwrap_or(0.0) == 0.0;
+            if !missing && !all {
+                continue;
+            }
+            let mut parts: Vec<&str> = address_columns.iter().map(|i| &record[*i]).collect();
+            if parts.iter().all(|part| part.trim().is_empty()) {
+                continue;
+            }
+            parts.push(country_name(&country));
+            let query = address_query(&parts);
+            totals.rows += 1;
+
+            let coordinates = match cache.get(geocoder.name(), &query) {
+                Some(cached) => {
+                    match cached {
+                        Some(_) => totals.from_cache += 1,
+                        None => totals.not_found += 1,
+                    }
+                    cached
+                }
+                None if requests >= limit => continue,
+                None => {
+                    if let Some(last) = last_request {
+                        let wait = geocoder.min_interval().saturating_sub(last.elapsed());
+                        tokio::time::sleep(wait).await;
+                    }
+                    let result = geocoder.geocode(&query).await;
+                    last_request = Some(Instant::now());
+                    requests += 1;
+                    match result {
+                        Ok(found) => {
+                            cache.insert(geocoder.name(), &query, found);
+                            if requests % SAVE_EVERY == 0 {
+                                cache.save()?;
+                            }
+                            if found.is_none() {
+                                totals.not_found += 1;
+                            }
+                            found
+                        }
+                        // Errors aren't cached, so the next run retries them.
+                        Err(e) => {
+                            totals.errors += 1;
+                            eprintln!("  {:?}: {}", query, e);
+                            None
+                        }
+                    }
+                }
+            };
+            if let Some((latitude, longitude)) = coordinates {
+                let mut fields: Vec<String> = record.iter().map(str::to_string).collect();
+                fields[lat_column] = latitude.to_string();
+                fields[lng_column] = longitude.to_string();
+                *record = csv::StringRecord::from(fields);
+                totals.geocoded += 1;
+            }
+        }
+
+        // Write next to the file and rename, so an interrupted run can't
+        // leave it truncated.
+        let tmp = path.with_extension("csv.tmp");
+        let mut wtr = csv::Writer::from_path(&tmp)
+            .map_err(|e| format!("Failed to write {}: {}", tmp.display(), e))?;
+        wtr.write_record(&headers)?;
+        for record in &records {
+            wtr.write_record(record)?;
+        }
+        wtr.flush()?;
+        drop(wtr);
+        std::fs::rename(&tmp, path)?;
+
+        eprintln!(
+            "{}: {} rows to geocode, {} located ({} from the cache), {} not found, {} errors",
+            path.display(),
+            totals.rows,
+            totals.geocoded,
+            totals.from_cache,
+            totals.not_found,
+            totals.errors
+        );
+    }
+
+    cache.save()?;
+    eprintln!(
+        "{} requests to {}, {} answers cached in {}",
+        requests,
+        geocoder.name(),
+        cache.len(),
+        cache_path.display()
+    );
+    if requests >= limit {
+        eprintln!("stopped at --limit {}, run again to continue", limit);
+    }
+
+    Ok(())
+}
@@ -0,0 +1,102 @@
+//! Ingests a zipped bundle of source files in one run.
+//!
+//! Some registries publish a single ZIP holding several CSVs, often in mixed
+//! encodings. Every member is decoded to UTF-8, identified from its contents and
+//! routed to the matching parser; the validated records are written to the
+//! output directory at their static_data paths (`us/locations.csv`), so it can
+//! be served as a data directory. Members of the same
+//! kind are written to one file together, smiley exports through the Danish
+//! importer's adapter so establishments in more than one are merged.
+//!
+//! Usage: `ingest-archive <bundle.zip> <out_dir>`
+
+use std::fs::{self, File};
+use std::path::Path;
+
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+
+use heatmap_backend::archive::{self, ArchiveMember, MemberKind};
+use heatmap_backend::importer::{self, Options};
+use heatmap_backend::smiley::Smiley;
+use heatmap_backend::{AphisReport, InspectionReport, Location};
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let [bundle, out_dir] = args.as_slice() else {
+        return Err("usage: ingest-archive <bundle.zip> <out_dir>".into());
+    };
+
+    let members = archive::extract_members(File::open(bundle)?)?;
+    let out_dir = Path::new(out_dir);
+    fs::create_dir_all(out_dir)?;
+
+    let kinds = [
+        MemberKind::Locations,
+        MemberKind::AphisReports,
+        MemberKind::InspectionReports,
+        MemberKind::SmileyXml,
+    ];
+    for kind in kinds {
+        let routed: Vec<&ArchiveMember> = members.iter().filter(|m| m.kind() == kind).collect();
+        if routed.is_empty() {
+            continue;
+        }
+
+        let path = out_dir.join(kind.file_name().unwrap_or_default());
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let count = match kind {
+            MemberKind::Locations => write_records::<Location>(&routed, &path)?,
+            MemberKind::AphisReports => write_records::<AphisReport>(&routed, &path)?,
+            MemberKind::InspectionReports => write_records::<InspectionReport>(&routed, &path)?,
+            MemberKind::SmileyXml => {
+                let inputs: Vec<&[u8]> = routed.iter().map(|m| m.text.as_bytes()).collect();
+                let report = importer::convert(
+                    &mut Smiley::new()?,
+                    &inputs,
+                    File::create(&path)?,
+                    &Options::default(),
+                )?;
+                report.written
+            }
+            MemberKind::Unknown => continue,
+        };
+
+        for member in &routed {
+            eprintln!("{} ({}) -> {:?}", member.name, member.encoding, kind);
+        }
+        eprintln!("wrote {} records to {}", count, path.display());
+    }
+
+    for member in members.iter().filter(|m| m.kind() == MemberKind::Unknown) {
+        eprintln!(
+            "skipped {} ({}): unrecognised contents",
+            member.name, member.encoding
+        );
+    }
+
+    Ok(())
+}
+
+/// Parses every routed member as `T` and writes them to a single CSV.
+fn write_records<T: DeserializeOwned + Serialize>(
+    members: &[&ArchiveMember],
+    path: &Path,
+) -> Result<usize, Box<dyn std::error::Error>> {
+    let mut wtr = csv::Writer::from_path(path)?;
+    let mut count = 0;
+
+    for member in members {
+        let mut reader = csv::Reader::from_reader(member.text.as_bytes());
+        for (line, result) in reader.deserialize::<T>().enumerate() {
+            let record = result.map_err(|e| format!("{} row {}: {}", member.name, line + 2, e))?;
+            wtr.serialize(record)?;
+            count += 1;
+        }
+    }
+
+    wtr.flush()?;
+    Ok(count)
+}
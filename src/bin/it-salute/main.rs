@@ -0,0 +1,297 @@
+//! Converts the Ministero della Salute lists of approved establishments
+//! (Regulation (EC) No 853/2004) into the standard locations CSV.
+//!
+//! Reads a CSV or HTML-table export on stdin and writes static_data's
+//! locations.csv format to stdout. Each row's section and approval codes are
+//! mapped with `eu_codes`; rows approved only for activities outside the map's
+//! scope (dairies, wholesale markets...) are skipped.
+//!
+//! Usage: `it-salute [--section I] [--mapping FILE] < export.csv > it/locations.csv`
+//!
+//! `--section` gives the section for exports covering a single one, when the
+//! file has no section column. `--mapping` takes a TOML table keyed
+//! `"<section> <code>"` (e.g. `"VIII AH" = ["Fish Processing"]`) overriding the
+//! default mapping.
+
+use std::collections::BTreeMap;
+use std::io::{self, Read};
+use std::path::PathBuf;
+
+use heatmap_backend::Location;
+use heatmap_backend::archive;
+use heatmap_backend::eu_codes::{self, Section};
+use heatmap_backend::importer::{self, Options, Report, SourceAdapter};
+use heatmap_backend::mapping::MappingTable;
+
+/// Header spellings seen across the ministry's exports, after normalization.
+const COLUMNS: &[(&str, &[&str])] = &[
+    (
+        "approval_number",
+        &[
+            "approval number",
+            "num riconoscimento",
+            "numero riconoscimento",
+            "n riconoscimento",
+            "riconoscimento",
+            "approval no",
+        ],
+    ),
+    ("name", &["ragione sociale", "denominazione", "name"]),
+    ("street", &["indirizzo", "address"]),
+    ("city", &["comune", "citta", "town", "town region"]),
+    ("province", &["provincia", "prov"]),
+    ("region", &["regione", "region"]),
+    ("zip", &["cap"]),
+    ("section", &["sezione", "section"]),
+    (
+        "activities",
+        &[
+            "attivita",
+            "categoria",
+            "categorie",
+            "activities",
+            "category",
+        ],
+    ),
+    ("latitude", &["latitudine", "latitude", "lat"]),
+    ("longitude", &["longitudine", "longitude", "lon", "lng"]),
+];
+
+fn decode_entities(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find('&') {
+        out.push_str(&rest[..start]);
+        rest = &rest[start..];
+        let Some(end) = rest.find(';').filter(|end| *end <= 10) else {
+            out.push('&');
+            rest = &rest[1..];
+            continue;
+        };
+        let entity = &rest[1..end];
+        let decoded = match entity {
+            "amp" => Some('&'),
+            "lt" => Some('<'),
+            "gt" => Some('>'),
+            "quot" => Some('"'),
+            "apos" => Some('\''),
+            "nbsp" => Some(' '),
+            _ => entity
+                .strip_prefix("#x")
+                .and_then(|hex| u32::from_str_radix(hex, 16).ok())
+                .or_else(|| entity.strip_prefix('#').and_then(|dec| dec.parse().ok()))
+                .and_then(char::from_u32),
+        };
+        match decoded {
+            Some(c) => {
+                out.push(c);
+                rest = &rest[end + 1..];
+            }
+            None => {
+                out.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+fn strip_tags(html: &str) -> String {
+    let mut text = String::new();
+    let mut in_tag = false;
+    for c in html.chars() {
+        match c {
+            '<' => in_tag = true,
+            '>' => in_tag = false,
+            _ if !in_tag => text.push(c),
+            _ => {}
+        }
+    }
+    decode_entities(&text)
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Rows of the first HTML table, header row included.
+fn html_rows(html: &str) -> Vec<Vec<String>> {
+    let lower = html.to_lowercase();
+    let mut rows = Vec::new();
+    for (start, _) in lower.match_indices("<tr") {
+        let end = lower[start..]
+            .find("</tr")
+            .map(|end| start + end)
+            .unwrap_or(html.len());
+        let row_lower = &lower[start..end];
+        let row = &html[start..end];
+
+        let mut cells = Vec::new();
+        let mut offset = 0;
+        while let Some(cell_start) = row_lower[offset..]
+            .find("<td")
+            .into_iter()
+            .chain(row_lower[offset..].find("<th"))
+            .min()
+        {
+            let cell_start = offset + cell_start;
+            let Some(content_start) = row_lower[cell_start..].find('>') else {
+                break;
+            };
+            let content_start = cell_start + content_start + 1;
+            let content_end = row_lower[content_start..]
+                .find("</t")
+                .map(|end| content_start + end)
+                .unwrap_or(row.len());
+            cells.push(strip_tags(&row[content_start..content_end]));
+            offset = content_end;
+        }
+        if !cells.is_empty() {
+            rows.push(cells);
+        }
+    }
+    rows
+}
+
+/// Column positions by canonical name.
+fn map_columns(headers: &[String]) -> BTreeMap<&'static str, usize> {
+    importer::map_columns(COLUMNS, headers)
+}
+
+struct Salute {
+    overrides: MappingTable,
+    default_section: Option<Section>,
+    columns: BTreeMap<&'static str, usize>,
+}
+
+impl SourceAdapter for Salute {
+    type Row = Vec<String>;
+
+    fn name(&self) -> &'static str {
+        "it-salute"
+    }
+
+    fn parse(&mut self, input: &[u8]) -> Result<Vec<Vec<String>>, Box<dyn std::error::Error>> {
+        let (text, _) = archive::decode(input);
+        let mut rows = if text.to_lowercase().contains("<table") {
+            html_rows(&text)
+        } else {
+            importer::csv_rows(&text)?
+        };
+
+        // Exports sometimes start with a title line; the header is the first
+        // row naming an approval number column.
+        let header_index = rows
+            .iter()
+            .position(|row| map_columns(row).contains_key("approval_number"))
+            .ok_or("no approval number column found")?;
+        self.columns = map_columns(&rows[header_index]);
+        for required in ["name", "activities"] {
+            if !self.columns.contains_key(required) {
+                return Err(format!("no {} column found", required).into());
+            }
+        }
+        if !self.columns.contains_key("section") && self.default_section.is_none() {
+            return Err("no section column found, pass --section".into());
+        }
+        Ok(rows.split_off(header_index + 1))
+    }
+
+    fn normalize(&mut self, row: Vec<String>, report: &mut Report) -> Option<Location> {
+        let cell = |name: &str| {
+            self.columns
+                .get(name)
+                .and_then(|i| row.get(*i))
+                .map(|value| value.trim())
+                .unwrap_or_default()
+        };
+        let approval_number = cell("approval_number");
+        if approval_number.is_empty() {
+            report.skip("no approval number");
+            return None;
+        }
+
+        let Some(section) = Section::parse(cell("section")).or(self.default_section) else {
+            report.unmapped(format!("? {}", cell("activities")));
+            return None;
+        };
+        let (codes, unknown) = eu_codes::parse_codes(cell("activities"));
+        for token in unknown {
+            report.unmapped(format!("{} {}", section.numeral(), token));
+        }
+
+        let mut activities: Vec<String> = Vec::new();
+        for code in codes {
+            let key = eu_codes::mapping_key(section, code);
+            let mapped: Vec<String> = match self.overrides.mappings.get(&key) {
+                Some(mapped) => mapped.clone(),
+                None => eu_codes::activities(section, code)
+                    .iter()
+                    .map(|activity| activity.label().to_string())
+                    .collect(),
+            };
+            for activity in mapped {
+                if !activities.contains(&activity) {
+                    activities.push(activity);
+                }
+            }
+        }
+        if activities.is_empty() {
+            report.skip("approved only for activities outside the map's scope");
+            return None;
+        }
+        activities.sort();
+        let activities = activities.join("; ");
+
+        // A facility approved under several sections is listed once per
+        // section; `importer::run` merges them.
+        let coordinate = |name: &str| importer::parse_coordinate(cell(name)).unwrap_or(0.0);
+        Some(Location {
+            country: "IT".to_string(),
+            establishment_id: approval_number.to_string(),
+            establishment_number: approval_number.to_string(),
+            establishment_name: cell("name").to_string(),
+            street: cell("street").to_string(),
+            city: cell("city").to_string(),
+            county: cell("province").to_string(),
+            state: cell("region").to_string(),
+            zip: cell("zip").to_string(),
+            latitude: coordinate("latitude"),
+            longitude: coordinate("longitude"),
+            slaughter: activities.contains("Slaughter"),
+            activities,
+            ..Default::default()
+        })
+    }
+}
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let mut overrides = MappingTable::default();
+    let mut default_section = None;
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--mapping" => {
+                let path: PathBuf = args.next().ok_or("--mapping needs a file")?.into();
+                overrides.merge(MappingTable::load(&path)?);
+            }
+            "--section" => {
+                let value = args.next().ok_or("--section needs a value")?;
+                default_section =
+                    Some(Section::parse(&value).ok_or(format!("unknown section {value:?}"))?);
+            }
+            _ => return Err(format!("unexpected argument {arg:?}").into()),
+        }
+    }
+
+    let mut input = Vec::new();
+    io::stdin().read_to_end(&mut input)?;
+    let mut salute = Salute {
+        overrides,
+        default_section,
+        columns: BTreeMap::new(),
+    };
+    importer::run(&mut salute, &input, io::stdout(), &Options::default())?;
+
+    Ok(())
+}
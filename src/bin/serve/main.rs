@@ -0,0 +1,51 @@
+//! Runs the API on a plain tokio listener, for mirrors hosted outside Shuttle.
+//!
+//! Usage: `serve`
+//!
+//! Configured through the environment:
+//!
+//! - `PORT` (default 8000) and `HOST` (default 0.0.0.0) to listen on
+//! - `DATA_SOURCE`, a static_data-like directory or a ZIP URL (default: the
+//!   data embedded at build time)
+//! - `CORS_ORIGINS`, comma-separated allowed origins (default: any)
+//! - `RUST_LOG` and `LOG_FORMAT` (`text` or `json`) for logging
+//! - `RATE_LIMIT`, `API_KEYS`, `API_KEY_RATE_LIMIT` and `TRUST_PROXY` for
+//!   rate limits (see `access`)
+//! - everything `AppState::from_env` reads (`ADMIN_TOKEN`, `ADMIN_TOKENS`,
+//!   `DEPLOYMENT_REGIONS`, ...)
+
+use std::net::SocketAddr;
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    if let Some(arg) = std::env::args().nth(1) {
+        return Err(format!("unexpected argument {arg:?}").into());
+    }
+    let host = std::env::var("HOST").unwrap_or_else(|_| "0.0.0.0".to_string());
+    let port: u16 = match std::env::var("PORT") {
+        Ok(port) => port
+            .parse()
+            .map_err(|_| format!("Invalid PORT {:?}", port))?,
+        Err(_) => 8000,
+    };
+    let addr: SocketAddr = format!("{}:{}", host, port)
+        .parse()
+        .map_err(|_| format!("Invalid HOST {:?}", host))?;
+    let cors = heatmap_backend::app::cors_from_env()?;
+    heatmap_backend::telemetry::init_from_env()?;
+
+    let state = heatmap_backend::AppState::from_env().await?;
+    let app = heatmap_backend::app::router(state, cors);
+    let listener = tokio::net::TcpListener::bind(addr).await?;
+    tracing::info!("listening on http://{}", listener.local_addr()?);
+    // Anonymous rate limits count by the connection's address.
+    axum::serve(
+        listener,
+        app.into_make_service_with_connect_info::<SocketAddr>(),
+    )
+    .with_graceful_shutdown(async {
+        let _ = tokio::signal::ctrl_c().await;
+    })
+    .await?;
+    Ok(())
+}
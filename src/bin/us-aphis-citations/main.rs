@@ -0,0 +1,281 @@
+//! Converts APHIS inspection report exports into static_data's citations CSV
+//! format, one row per citation.
+//!
+//! Usage: `us-aphis-citations --inspections inspections.csv
+//! [--citations citations.csv] > us/citations/2025.csv`
+//!
+//! The inspections file has a row per inspection (the Public Search Tool
+//! export, or a re-publication such as the Data Liberation Project's); the
+//! citations file has a row per citation, joined to its inspection by the
+//! inspection id. Without `--citations` the inspections file is expected to
+//! carry the citation columns itself, one row per citation. Inspections
+//! without citations are kept as a row without a kind, so clean inspections
+//! show up in the history. Columns are recognized by the spellings in
+//! `COLUMNS`.
+
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use heatmap_backend::archive;
+use heatmap_backend::ids;
+use heatmap_backend::{CitationKind, InspectionCitation};
+
+/// Header spellings seen in the exports, after normalization.
+const COLUMNS: &[(&str, &[&str])] = &[
+    (
+        "inspection_id",
+        &[
+            "inspection id",
+            "hash id",
+            "report id",
+            "inspection number",
+            "id",
+        ],
+    ),
+    (
+        "certificate_number",
+        &[
+            "certificate number",
+            "certificate",
+            "certificate id",
+            "cert id",
+            "license number",
+        ],
+    ),
+    (
+        "customer_number",
+        &["customer number", "customer id", "customer"],
+    ),
+    ("inspection_date", &["inspection date", "date"]),
+    ("inspection_type", &["inspection type", "type"]),
+    (
+        "report_url",
+        &["report url", "report link", "web url", "pdf url", "url"],
+    ),
+    (
+        "code",
+        &["code", "citation code", "section", "regulation", "cfr"],
+    ),
+    (
+        "kind",
+        &[
+            "kind",
+            "severity",
+            "citation type",
+            "nci type",
+            "noncompliance",
+        ],
+    ),
+    ("repeat", &["repeat", "repeat citation", "is repeat"]),
+    (
+        "description",
+        &[
+            "description",
+            "desc",
+            "citation description",
+            "regulation title",
+        ],
+    ),
+    (
+        "narrative",
+        &["narrative", "citation narrative", "citation text", "text"],
+    ),
+];
+
+fn normalize_header(header: &str) -> String {
+    deunicode::deunicode(header)
+        .to_lowercase()
+        .split(|c: char| !c.is_ascii_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// A CSV file as rows of canonical column name to value.
+fn read_rows(
+    path: &Path,
+) -> Result<Vec<BTreeMap<&'static str, String>>, Box<dyn std::error::Error>> {
+    let bytes =
+        std::fs::read(path).map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    let (text, _) = archive::decode(&bytes);
+    let mut reader = csv::ReaderBuilder::new()
+        .flexible(true)
+        .from_reader(text.as_bytes());
+
+    let mut columns = BTreeMap::new();
+    for (i, header) in reader.headers()?.iter().enumerate() {
+        let header = normalize_header(header);
+        if let Some((name, _)) = COLUMNS
+            .iter()
+            .find(|(_, aliases)| aliases.contains(&header.as_str()))
+        {
+            columns.entry(*name).or_insert(i);
+        }
+    }
+
+    let mut rows = Vec::new();
+    for record in reader.records() {
+        let record = record?;
+        rows.push(
+            columns
+                .iter()
+                .map(|(name, i)| (*name, record.get(*i).unwrap_or_default().trim().to_string()))
+                .collect(),
+        );
+    }
+    Ok(rows)
+}
+
+/// "03/14/2024" or "2024-03-14T00:00:00" as "2024-03-14".
+fn normalize_date(value: &str) -> String {
+    let value = value.split(['T', ' ']).next().unwrap_or_default();
+    ["%Y-%m-%d", "%m/%d/%Y", "%m/%d/%y", "%d-%b-%Y"]
+        .iter()
+        .find_map(|format| chrono::NaiveDate::parse_from_str(value, format).ok())
+        .map(|date| date.format("%Y-%m-%d").to_string())
+        .unwrap_or_else(|| value.to_string())
+}
+
+fn is_yes(value: &str) -> bool {
+    matches!(
+        value.to_lowercase().as_str(),
+        "true" | "yes" | "y" | "1" | "repeat"
+    )
+}
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let mut inspections_path = None;
+    let mut citations_path = None;
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--inspections" => {
+                inspections_path = Some(args.next().ok_or("--inspections needs a file")?)
+            }
+            "--citations" => citations_path = Some(args.next().ok_or("--citations needs a file")?),
+            _ => return Err(format!("unexpected argument {arg:?}").into()),
+        }
+    }
+    let inspections_path = inspections_path.ok_or("--inspections is required")?;
+
+    let inspection_rows = read_rows(Path::new(&inspections_path))?;
+    let citation_rows = match &citations_path {
+        Some(path) => read_rows(Path::new(path))?,
+        None => inspection_rows.clone(),
+    };
+
+    let get = |row: &BTreeMap<&str, String>, name: &str| row.get(name).cloned().unwrap_or_default();
+
+    // Inspections by id, without their citations.
+    let mut inspections: BTreeMap<String, InspectionCitation> = BTreeMap::new();
+    let mut skipped = 0;
+    for row in &inspection_rows {
+        let certificate_number = get(row, "certificate_number");
+        if certificate_number.is_empty() {
+            skipped += 1;
+            continue;
+        }
+        let inspection_id = match get(row, "inspection_id") {
+            id if !id.is_empty() => id,
+            // Without an id, the certificate and date name the inspection.
+            _ if !get(row, "inspection_date").is_empty() => {
+                format!(
+                    "{}:{}",
+                    certificate_number,
+                    normalize_date(&get(row, "inspection_date"))
+                )
+            }
+            _ => ids::content_id(&[
+                &certificate_number,
+                &get(row, "inspection_type"),
+                &get(row, "report_url"),
+            ]),
+        };
+        inspections
+            .entry(inspection_id.clone())
+            .or_insert_with(|| InspectionCitation {
+                certificate_number,
+                customer_number: get(row, "customer_number"),
+                inspection_id,
+                inspection_date: normalize_date(&get(row, "inspection_date")),
+                inspection_type: get(row, "inspection_type"),
+                report_url: get(row, "report_url"),
+                ..Default::default()
+            });
+    }
+
+    let mut citations: BTreeMap<String, Vec<InspectionCitation>> = BTreeMap::new();
+    let mut unmatched = 0;
+    let mut unknown_kinds: BTreeMap<String, usize> = BTreeMap::new();
+    for row in &citation_rows {
+        let kind_text = get(row, "kind");
+        let code = get(row, "code");
+        if kind_text.is_empty() && code.is_empty() {
+            continue;
+        }
+        let inspection_id = match get(row, "inspection_id") {
+            id if !id.is_empty() => id,
+            _ => format!(
+                "{}:{}",
+                get(row, "certificate_number"),
+                normalize_date(&get(row, "inspection_date"))
+            ),
+        };
+        let Some(inspection) = inspections.get(&inspection_id) else {
+            unmatched += 1;
+            continue;
+        };
+        // A citation with an unrecognized severity still counts as cited.
+        let kind = CitationKind::parse(&kind_text).unwrap_or_else(|| {
+            if !kind_text.is_empty() {
+                *unknown_kinds.entry(kind_text.clone()).or_default() += 1;
+            }
+            CitationKind::NonCritical
+        });
+        citations
+            .entry(inspection_id)
+            .or_default()
+            .push(InspectionCitation {
+                code,
+                kind: Some(kind),
+                repeat: is_yes(&get(row, "repeat")),
+                description: get(row, "description"),
+                narrative: get(row, "narrative"),
+                ..inspection.clone()
+            });
+    }
+
+    let mut wtr = csv::Writer::from_writer(std::io::stdout());
+    let mut count = 0;
+    let mut clean = 0;
+    for (id, inspection) in inspections {
+        match citations.remove(&id) {
+            Some(rows) => {
+                for row in rows {
+                    wtr.serialize(row)?;
+                    count += 1;
+                }
+            }
+            None => {
+                wtr.serialize(inspection)?;
+                clean += 1;
+            }
+        }
+    }
+    wtr.flush()?;
+
+    eprintln!(
+        "wrote {} citations and {} inspections without citations, skipped {} inspections without a certificate and {} citations without a matching inspection",
+        count, clean, skipped, unmatched
+    );
+    for (kind, rows) in &unknown_kinds {
+        eprintln!(
+            "  unrecognized kind {:?} on {} rows, kept as non_critical",
+            kind, rows
+        );
+    }
+
+    heatmap_backend::importers::record_run("us-aphis-citations", count)?;
+
+    Ok(())
+}
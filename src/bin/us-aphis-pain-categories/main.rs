@@ -0,0 +1,252 @@
+//! Reads the pain category counts out of APHIS annual report PDFs (form
+//! 7023) into static_data's pain categories CSV, one row per species line.
+//!
+//! Usage: `us-aphis-pain-categories [--year YYYY] report.pdf... | dir...
+//! > us/aphis_pain_categories.csv`
+//!
+//! Directories are read for their `.pdf` files. The registration number and
+//! fiscal year are taken from the report's text, `--year` standing in for
+//! reports that don't print the year. A species line is its label followed
+//! by up to five counts, columns B to F. Blank cells print nothing, so a
+//! line of four counts whose last is the sum of the other three is read as
+//! C to F without B; any other four are B to E. Lines without counts (the
+//! form's instructions name the species too) are left out.
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::PathBuf;
+
+use heatmap_backend::PainCategories;
+use heatmap_backend::importer::Report;
+use heatmap_backend::pdf;
+
+/// The form's species labels, lowercased, with the `animal_count_columns`
+/// key of each.
+const LABELS: &[(&str, &str)] = &[
+    ("dogs", "dog"),
+    ("cats", "cat"),
+    ("guinea pigs", "guinea_pig"),
+    ("hamsters", "hamster"),
+    ("rabbits", "rabbit"),
+    ("non-human primates", "non_human_primate"),
+    ("nonhuman primates", "non_human_primate"),
+    ("non human primates", "non_human_primate"),
+    ("sheep", "sheep"),
+    ("pigs", "pig"),
+    ("other farm animals", "other_livestock"),
+    ("all other covered species", "other_animals"),
+    ("all other animals", "other_animals"),
+];
+
+/// Words allowed between a label and its counts, e.g. the species an "All
+/// Other Covered Species" line names.
+const MAX_WORDS_BEFORE_COUNTS: usize = 6;
+
+/// A count such as "1,440". None for words, dates and registration numbers.
+fn parse_count(token: &str) -> Option<u64> {
+    let token = token.trim_end_matches(['.', ';']);
+    if token.is_empty() || !token.chars().all(|c| c.is_ascii_digit() || c == ',') {
+        return None;
+    }
+    token.replace(',', "").parse().ok()
+}
+
+/// A registration number such as "87-R-0002".
+fn is_registration_number(token: &str) -> bool {
+    let parts: Vec<&str> = token.split('-').collect();
+    matches!(parts.as_slice(), [state, class, number]
+        if state.len() == 2
+            && state.chars().all(|c| c.is_ascii_digit())
+            && class.len() == 1
+            && class.chars().all(|c| c.is_ascii_uppercase())
+            && number.len() == 4
+            && number.chars().all(|c| c.is_ascii_digit()))
+}
+
+/// The year after "fiscal year" or "FY".
+fn fiscal_year(words: &[&str]) -> Option<String> {
+    words.windows(4).find_map(|window| {
+        let after = match window {
+            [fiscal, year, ..]
+                if fiscal.eq_ignore_ascii_case("fiscal")
+                    && year.to_lowercase().starts_with("year") =>
+            {
+                &window[2..]
+            }
+            [fy, ..] if fy.trim_end_matches(':').eq_ignore_ascii_case("fy") => &window[1..],
+            _ => return None,
+        };
+        after.iter().take(2).find_map(|word| {
+            let word = word.trim_matches(|c: char| !c.is_ascii_digit());
+            (word.len() == 4 && word.starts_with("20")).then(|| word.to_string())
+        })
+    })
+}
+
+/// Columns B to E of a line from its counts.
+fn columns(counts: &[u64]) -> Option<[Option<u64>; 4]> {
+    match *counts {
+        [b, c, d, e, _f] => Some([Some(b), Some(c), Some(d), Some(e)]),
+        [c, d, e, f] if c + d + e == f => Some([None, Some(c), Some(d), Some(e)]),
+        [b, c, d, e] => Some([Some(b), Some(c), Some(d), Some(e)]),
+        _ => None,
+    }
+}
+
+/// The species lines of a report's text.
+fn species_lines(text: &str) -> Vec<(&'static str, [Option<u64>; 4])> {
+    let lower = text.to_lowercase();
+    // Every label occurrence, the longest winning where they overlap so the
+    // "pigs" of "guinea pigs" isn't a line of its own.
+    let mut found: Vec<(usize, usize, &'static str)> = Vec::new();
+    for (label, key) in LABELS {
+        let mut from = 0;
+        while let Some(i) = lower[from..].find(label) {
+            let start = from + i;
+            let end = start + label.len();
+            let at_word = |i: usize| {
+                lower[..i]
+                    .chars()
+                    .next_back()
+                    .is_none_or(|c| !c.is_alphanumeric())
+            };
+            let ends_word = lower[end..]
+                .chars()
+                .next()
+                .is_none_or(|c| !c.is_alphanumeric());
+            if at_word(start) && ends_word {
+                found.push((start, end, key));
+            }
+            from = end;
+        }
+    }
+    found.sort_by_key(|(start, end, _)| (*start, std::cmp::Reverse(*end)));
+    let mut labels: Vec<(usize, usize, &'static str)> = Vec::new();
+    for label in found {
+        if labels.last().is_none_or(|last| label.0 >= last.1) {
+            labels.push(label);
+        }
+    }
+
+    let mut lines: Vec<(&'static str, [Option<u64>; 4])> = Vec::new();
+    for (i, (_, end, key)) in labels.iter().enumerate() {
+        let next = labels.get(i + 1).map_or(lower.len(), |label| label.0);
+        let mut counts = Vec::new();
+        for (n, word) in lower[*end..next].split_whitespace().enumerate() {
+            match parse_count(word) {
+                Some(count) if counts.len() < 5 => counts.push(count),
+                Some(_) => break,
+                None if counts.is_empty() && n < MAX_WORDS_BEFORE_COUNTS => {}
+                None => break,
+            }
+        }
+        if let Some(columns) = columns(&counts)
+            && !lines.iter().any(|(known, _)| known == key)
+        {
+            lines.push((key, columns));
+        }
+    }
+    lines
+}
+
+fn pdf_files(paths: &[PathBuf]) -> Result<Vec<PathBuf>, Box<dyn std::error::Error>> {
+    let mut files = Vec::new();
+    for path in paths {
+        if path.is_dir() {
+            let mut entries: Vec<PathBuf> = fs::read_dir(path)?
+                .filter_map(|entry| Some(entry.ok()?.path()))
+                .filter(|path| {
+                    path.extension()
+                        .is_some_and(|extension| extension.eq_ignore_ascii_case("pdf"))
+                })
+                .collect();
+            entries.sort();
+            files.extend(entries);
+        } else {
+            files.push(path.clone());
+        }
+    }
+    Ok(files)
+}
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let mut year = None;
+    let mut paths = Vec::new();
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--year" => year = Some(args.next().ok_or("--year needs a value")?),
+            _ => paths.push(PathBuf::from(arg)),
+        }
+    }
+    if paths.is_empty() {
+        return Err("usage: us-aphis-pain-categories [--year YYYY] report.pdf... | dir...".into());
+    }
+
+    let mut report = Report::default();
+    let mut rows: BTreeMap<(String, String, &'static str), PainCategories> = BTreeMap::new();
+    for file in pdf_files(&paths)? {
+        let name = file
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_default();
+        let text = match pdf::extract_text(&fs::read(&file)?) {
+            Ok(lines) => lines.join("\n"),
+            Err(e) => {
+                eprintln!("{}: {}", file.display(), e);
+                report.skip("unreadable PDF");
+                continue;
+            }
+        };
+        let words: Vec<&str> = text.split_whitespace().collect();
+        let Some(certificate_number) = words
+            .iter()
+            .map(|word| word.trim_matches(|c: char| !c.is_alphanumeric()))
+            .find(|word| is_registration_number(word))
+        else {
+            report.skip("no registration number (scanned, or not an annual report)");
+            continue;
+        };
+        let Some(report_year) = fiscal_year(&words).or_else(|| year.clone()) else {
+            report.skip("no fiscal year; pass --year");
+            continue;
+        };
+        let lines = species_lines(&text);
+        if lines.is_empty() {
+            report.skip("no species lines");
+            continue;
+        }
+        for (species, [column_b, column_c, column_d, column_e]) in lines {
+            // A later amendment of the same report replaces the earlier.
+            rows.insert(
+                (certificate_number.to_string(), report_year.clone(), species),
+                PainCategories {
+                    certificate_number: certificate_number.to_string(),
+                    year: report_year.clone(),
+                    species: species.to_string(),
+                    column_b,
+                    column_c,
+                    column_d,
+                    column_e,
+                    source_file: name.clone(),
+                },
+            );
+        }
+    }
+
+    let mut wtr = csv::Writer::from_writer(std::io::stdout());
+    for row in rows.values() {
+        wtr.serialize(row)?;
+    }
+    wtr.flush()?;
+    report.written = rows.len();
+
+    eprintln!("wrote {} species lines", report.written);
+    for (reason, files) in &report.skipped {
+        eprintln!("  skipped {} files: {}", files, reason);
+    }
+
+    heatmap_backend::importers::record_run("us-aphis-pain-categories", report.written)?;
+
+    Ok(())
+}
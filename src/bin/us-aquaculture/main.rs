@@ -0,0 +1,223 @@
+//! Converts NOAA seafood processor permit lists and state aquaculture
+//! registries into static_data's aquaculture CSV format.
+//!
+//! Usage: `us-aquaculture --source noaa < permits.csv > us/aquaculture/noaa.csv`
+//! or `us-aquaculture --source state --state WA < registry.csv > us/aquaculture/wa.csv`
+//!
+//! Both kinds of list come as CSV exports whose headers vary by agency and
+//! year; columns are recognized by the spellings in `COLUMNS`. `--state`
+//! fills in the state for registries that don't have a state column. Rows
+//! without coordinates are kept at 0, 0.
+
+use std::collections::BTreeMap;
+use std::io::{self, Read};
+
+use heatmap_backend::AquacultureSite;
+use heatmap_backend::archive;
+use heatmap_backend::ids;
+
+/// Header spellings seen in the permit and registry exports, after
+/// normalization.
+const COLUMNS: &[(&str, &[&str])] = &[
+    (
+        "site_id",
+        &[
+            "permit number",
+            "permit no",
+            "permit",
+            "license number",
+            "license no",
+            "registration number",
+            "certificate number",
+            "processor code",
+            "id",
+        ],
+    ),
+    (
+        "name",
+        &[
+            "business name",
+            "company name",
+            "facility name",
+            "permit holder",
+            "licensee",
+            "operator",
+            "name",
+        ],
+    ),
+    (
+        "operation",
+        &[
+            "permit type",
+            "operation type",
+            "facility type",
+            "license type",
+            "activity",
+            "type",
+        ],
+    ),
+    (
+        "street",
+        &[
+            "street address",
+            "address line 1",
+            "address 1",
+            "address",
+            "street",
+        ],
+    ),
+    ("city", &["city", "town"]),
+    ("state", &["state", "st"]),
+    ("zip", &["zip code", "zipcode", "zip", "postal code"]),
+    (
+        "species",
+        &[
+            "species cultured",
+            "culture species",
+            "species list",
+            "species",
+            "products",
+        ],
+    ),
+    ("latitude", &["latitude", "lat"]),
+    ("longitude", &["longitude", "lon", "lng", "long"]),
+];
+
+fn normalize_header(header: &str) -> String {
+    deunicode::deunicode(header)
+        .to_lowercase()
+        .split(|c: char| !c.is_ascii_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Column positions by canonical name.
+fn map_columns(headers: &csv::StringRecord) -> BTreeMap<&'static str, usize> {
+    let mut columns = BTreeMap::new();
+    for (i, header) in headers.iter().enumerate() {
+        let header = normalize_header(header);
+        if let Some((name, _)) = COLUMNS
+            .iter()
+            .find(|(_, aliases)| aliases.contains(&header.as_str()))
+        {
+            columns.entry(*name).or_insert(i);
+        }
+    }
+    columns
+}
+
+/// "Salmon, Trout / Oysters" as "Salmon; Trout; Oysters".
+fn species_list(value: &str) -> Vec<String> {
+    value
+        .split([',', ';', '/', '|'])
+        .map(str::trim)
+        .filter(|name| !name.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let mut source = None;
+    let mut state = None;
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--source" => source = Some(args.next().ok_or("--source needs noaa or state")?),
+            "--state" => state = Some(args.next().ok_or("--state needs a value")?.to_uppercase()),
+            _ => return Err(format!("unexpected argument {arg:?}").into()),
+        }
+    }
+    let (source_name, default_operation) = match source.as_deref() {
+        Some("noaa") => ("NOAA seafood processor permits".to_string(), "Processor"),
+        Some("state") => (
+            format!(
+                "{} aquaculture registry",
+                state.as_deref().ok_or("--source state needs --state")?
+            ),
+            "Farm",
+        ),
+        Some(other) => {
+            return Err(format!("unknown source {other:?}, expected noaa or state").into());
+        }
+        None => return Err("--source is required (noaa or state)".into()),
+    };
+
+    let mut bytes = Vec::new();
+    io::stdin().read_to_end(&mut bytes)?;
+    let (text, _) = archive::decode(&bytes);
+    let mut reader = csv::ReaderBuilder::new()
+        .flexible(true)
+        .from_reader(text.as_bytes());
+    let columns = map_columns(reader.headers()?);
+    if !columns.contains_key("name") {
+        return Err("no name column found".into());
+    }
+
+    let mut sites: BTreeMap<String, AquacultureSite> = BTreeMap::new();
+    let mut skipped = 0;
+    for record in reader.records() {
+        let record = record?;
+        let cell = |name: &str| {
+            columns
+                .get(name)
+                .and_then(|i| record.get(*i))
+                .map(str::trim)
+                .unwrap_or_default()
+        };
+        if cell("name").is_empty() {
+            skipped += 1;
+            continue;
+        }
+        let site_id = match cell("site_id") {
+            "" => ids::content_id(&[cell("name"), cell("street"), cell("zip")]),
+            id => id.to_string(),
+        };
+
+        // Registries list a site once per species or permit type.
+        let site = sites
+            .entry(site_id.clone())
+            .or_insert_with(|| AquacultureSite {
+                site_id,
+                name: cell("name").to_string(),
+                operation: match cell("operation") {
+                    "" => default_operation.to_string(),
+                    operation => operation.to_string(),
+                },
+                source: source_name.clone(),
+                street: cell("street").to_string(),
+                city: cell("city").to_string(),
+                state: match cell("state") {
+                    "" => state.clone().unwrap_or_default(),
+                    value => value.to_uppercase(),
+                },
+                zip: cell("zip").to_string(),
+                latitude: cell("latitude").parse().unwrap_or(0.0),
+                longitude: cell("longitude").parse().unwrap_or(0.0),
+                ..Default::default()
+            });
+        let mut species = species_list(&site.species);
+        for name in species_list(cell("species")) {
+            if !species.contains(&name) {
+                species.push(name);
+            }
+        }
+        site.species = species.join("; ");
+    }
+
+    let mut wtr = csv::Writer::from_writer(io::stdout());
+    let count = sites.len();
+    for site in sites.into_values() {
+        wtr.serialize(site)?;
+    }
+    wtr.flush()?;
+
+    eprintln!(
+        "wrote {} sites, skipped {} rows without a name",
+        count, skipped
+    );
+
+    heatmap_backend::importers::record_run("us-aquaculture", count)?;
+
+    Ok(())
+}
@@ -0,0 +1,289 @@
+//! Converts EPA and state CAFO permit lists (NPDES) into static_data's CAFO
+//! CSV format.
+//!
+//! Usage: `us-cafos --source epa < echo_cafos.csv > us/cafos/epa.csv`
+//! or `us-cafos --source state --state WI < permits.csv > us/cafos/wi.csv`
+//!
+//! EPA's ECHO facility search exports the NPDES permits with a CAFO
+//! component; the state lists add what ECHO leaves out, most often the
+//! animal counts. Headers vary by state and are recognized by the spellings
+//! in `COLUMNS`. Counts come either one column per animal type ("Mature
+//! Dairy Cows", "Swine 55 lbs or more"), which are summed per type, or as an
+//! animal type column with a count column. A permit listed once per animal
+//! type is merged into one row. Rows without coordinates are kept at 0, 0
+//! for the geocode job.
+
+use std::collections::BTreeMap;
+use std::io::{self, Read};
+
+use heatmap_backend::CafoPermit;
+use heatmap_backend::archive;
+use heatmap_backend::importer::{self, Report};
+
+/// Header spellings seen in the lists, after normalization.
+const COLUMNS: &[(&str, &[&str])] = &[
+    (
+        "permit_id",
+        &[
+            "npdes permit id",
+            "npdes id",
+            "npdes permit number",
+            "permit number",
+            "permit no",
+            "permit id",
+            "sourceid",
+            "source id",
+        ],
+    ),
+    (
+        "name",
+        &[
+            "facility name",
+            "cwpname",
+            "operation name",
+            "farm name",
+            "permittee",
+            "name",
+        ],
+    ),
+    (
+        "permit_status",
+        &["permit status", "cwppermitstatusdesc", "status"],
+    ),
+    (
+        "street",
+        &[
+            "street address",
+            "location address",
+            "cwpstreet",
+            "address",
+            "street",
+        ],
+    ),
+    ("city", &["city", "cwpcity", "town"]),
+    ("county", &["county", "county name", "cwpcounty"]),
+    ("state", &["state", "cwpstate", "st"]),
+    ("zip", &["zip", "zip code", "cwpzip", "postal code"]),
+    (
+        "animal_types",
+        &[
+            "animal type",
+            "animal types",
+            "livestock type",
+            "species",
+            "animal",
+        ],
+    ),
+    (
+        "animals",
+        &[
+            "number of animals",
+            "animal count",
+            "head count",
+            "headcount",
+            "maximum number of animals",
+            "permitted animals",
+        ],
+    ),
+    ("latitude", &["latitude", "lat", "faclat"]),
+    ("longitude", &["longitude", "lon", "lng", "long", "faclong"]),
+];
+
+/// The count field an animal type or count column belongs to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Count {
+    AnimalUnits,
+    DairyCattle,
+    BeefCattle,
+    Swine,
+    Chickens,
+    Turkeys,
+    Other,
+}
+
+impl Count {
+    /// By the words of a normalized column header or animal type.
+    fn of(text: &str) -> Option<Count> {
+        let has = |word: &str| text.contains(word);
+        let count = if has("animal unit") {
+            Count::AnimalUnits
+        } else if has("dairy") || has("milk") {
+            Count::DairyCattle
+        } else if has("cattle") || has("beef") || has("heifer") || has("steer") || has("calves") {
+            Count::BeefCattle
+        } else if has("swine") || has("hog") || has("pig") {
+            Count::Swine
+        } else if has("chicken") || has("layer") || has("broiler") || has("pullet") {
+            Count::Chickens
+        } else if has("turkey") {
+            Count::Turkeys
+        } else if has("horse") || has("sheep") || has("lamb") || has("goat") || has("duck") {
+            Count::Other
+        } else {
+            return None;
+        };
+        Some(count)
+    }
+
+    fn add(self, permit: &mut CafoPermit, count: u64) {
+        let field = match self {
+            Count::AnimalUnits => &mut permit.animal_units,
+            Count::DairyCattle => &mut permit.dairy_cattle,
+            Count::BeefCattle => &mut permit.beef_cattle,
+            Count::Swine => &mut permit.swine,
+            Count::Chickens => &mut permit.chickens,
+            Count::Turkeys => &mut permit.turkeys,
+            Count::Other => &mut permit.other_animals,
+        };
+        *field = Some(field.unwrap_or(0) + count);
+    }
+}
+
+/// A headcount such as "2,500" or "approx. 700". None for blanks and
+/// redactions.
+fn parse_count(value: &str) -> Option<u64> {
+    let digits: String = value.chars().filter(char::is_ascii_digit).collect();
+    digits.parse().ok()
+}
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let mut source = None;
+    let mut state = None;
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--source" => source = Some(args.next().ok_or("--source needs epa or state")?),
+            "--state" => state = Some(args.next().ok_or("--state needs a value")?.to_uppercase()),
+            _ => return Err(format!("unexpected argument {arg:?}").into()),
+        }
+    }
+    let (name, source_name) = match source.as_deref() {
+        Some("epa") => ("us-cafos-epa", "EPA ICIS-NPDES".to_string()),
+        Some("state") => (
+            "us-cafos-state",
+            format!(
+                "{} CAFO permit list",
+                state.as_deref().ok_or("--source state needs --state")?
+            ),
+        ),
+        Some(other) => {
+            return Err(format!("unknown source {other:?}, expected epa or state").into());
+        }
+        None => return Err("--source is required (epa or state)".into()),
+    };
+
+    let mut bytes = Vec::new();
+    io::stdin().read_to_end(&mut bytes)?;
+    let (text, _) = archive::decode(&bytes);
+    let mut rows = importer::csv_rows(&text)?;
+    let header_index = rows
+        .iter()
+        .position(|row| importer::map_columns(COLUMNS, row).contains_key("permit_id"))
+        .ok_or("no permit number column found")?;
+    let headers = &rows[header_index];
+    let columns = importer::map_columns(COLUMNS, headers);
+    // Any other column naming an animal type is a count of that type.
+    let count_columns: Vec<(usize, Count)> = headers
+        .iter()
+        .enumerate()
+        .filter(|(i, _)| !columns.values().any(|column| column == i))
+        .filter_map(|(i, header)| Count::of(&importer::normalize(header)).map(|count| (i, count)))
+        .collect();
+    let rows = rows.split_off(header_index + 1);
+
+    let mut report = Report::default();
+    let mut permits: BTreeMap<String, CafoPermit> = BTreeMap::new();
+    for row in &rows {
+        let cell = |name: &str| {
+            columns
+                .get(name)
+                .and_then(|i| row.get(*i))
+                .map(|value| value.trim())
+                .unwrap_or_default()
+        };
+        let permit_id = cell("permit_id");
+        if permit_id.is_empty() {
+            report.skip("no permit number");
+            continue;
+        }
+
+        let permit = permits
+            .entry(permit_id.to_string())
+            .or_insert_with(|| CafoPermit {
+                permit_id: permit_id.to_string(),
+                name: cell("name").to_string(),
+                source: source_name.clone(),
+                permit_status: cell("permit_status").to_string(),
+                street: cell("street").to_string(),
+                city: cell("city").to_string(),
+                county: cell("county").to_string(),
+                state: match cell("state") {
+                    "" => state.clone().unwrap_or_default(),
+                    value => value.to_uppercase(),
+                },
+                zip: cell("zip").to_string(),
+                latitude: importer::parse_coordinate(cell("latitude")).unwrap_or(0.0),
+                longitude: importer::parse_coordinate(cell("longitude")).unwrap_or(0.0),
+                ..Default::default()
+            });
+        if permit.name.is_empty() {
+            permit.name = cell("name").to_string();
+        }
+
+        let mut animal_types = importer::split_activities(&permit.animal_types);
+        for animal_type in cell("animal_types")
+            .split([',', ';', '/', '|'])
+            .map(str::trim)
+            .filter(|animal_type| !animal_type.is_empty())
+        {
+            if !animal_types.iter().any(|known| known == animal_type) {
+                animal_types.push(animal_type.to_string());
+            }
+        }
+        for &(i, count) in &count_columns {
+            if let Some(value) = row.get(i).and_then(|value| parse_count(value)) {
+                count.add(permit, value);
+            }
+        }
+        if let Some(value) = parse_count(cell("animals")) {
+            match Count::of(&importer::normalize(cell("animal_types"))) {
+                Some(count) => count.add(permit, value),
+                None => {
+                    report.unmapped(cell("animal_types"));
+                    Count::Other.add(permit, value);
+                }
+            }
+        }
+        permit.animal_types = animal_types.join("; ");
+    }
+
+    let mut wtr = csv::Writer::from_writer(io::stdout());
+    for permit in permits.values() {
+        if importer::coordinates(permit.latitude, permit.longitude).is_none() {
+            report.missing_coordinates += 1;
+        }
+        wtr.serialize(permit)?;
+    }
+    wtr.flush()?;
+    report.written = permits.len();
+
+    eprintln!(
+        "wrote {} permits, {} with animal counts, {} without coordinates (kept at 0, 0 for geocoding)",
+        report.written,
+        permits
+            .values()
+            .filter(|permit| permit.total_animals().is_some())
+            .count(),
+        report.missing_coordinates
+    );
+    for (reason, rows) in &report.skipped {
+        eprintln!("  skipped {} rows: {}", rows, reason);
+    }
+    for (value, rows) in &report.unmapped {
+        eprintln!("  counted {:?} as other animals on {} rows", value, rows);
+    }
+
+    heatmap_backend::importers::record_run(name, report.written)?;
+
+    Ok(())
+}
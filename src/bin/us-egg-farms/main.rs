@@ -0,0 +1,251 @@
+//! Converts FDA shell egg producer registrations and NPIP participant lists
+//! into static_data's egg farm CSV format.
+//!
+//! Usage: `us-egg-farms --source fda < registrations.csv > us/egg_farms/fda.csv`
+//! or `us-egg-farms --source npip [--state IA] < participants.csv > us/egg_farms/npip.csv`
+//!
+//! The FDA registrations (producers with 3,000 or more laying hens, released
+//! through FOIA) list only egg farms, so every row is one. The National
+//! Poultry Improvement Plan's participant lists cover hatcheries and breeding
+//! flocks of every kind; hatcheries and egg-type flocks are kept and
+//! meat-type flocks skipped. Headers vary between releases and states and are
+//! recognized by the spellings in `COLUMNS`. Rows without coordinates are
+//! kept at 0, 0 for the geocode job.
+
+use std::collections::BTreeMap;
+use std::io::{self, Read};
+
+use heatmap_backend::EggFarm;
+use heatmap_backend::archive;
+use heatmap_backend::ids;
+use heatmap_backend::importer::{self, Report};
+
+/// Header spellings seen in the registrations and lists, after normalization.
+const COLUMNS: &[(&str, &[&str])] = &[
+    (
+        "farm_id",
+        &[
+            "fei number",
+            "fei",
+            "registration number",
+            "npip number",
+            "npip no",
+            "official number",
+            "plant number",
+            "hatchery number",
+            "participant number",
+            "id",
+        ],
+    ),
+    (
+        "name",
+        &[
+            "firm name",
+            "facility name",
+            "business name",
+            "participant",
+            "participant name",
+            "hatchery name",
+            "flock owner",
+            "name",
+        ],
+    ),
+    (
+        "street",
+        &[
+            "street address",
+            "address line 1",
+            "address 1",
+            "address",
+            "street",
+        ],
+    ),
+    ("city", &["city", "town"]),
+    ("county", &["county", "county name"]),
+    ("state", &["state", "st"]),
+    ("zip", &["zip code", "zipcode", "zip", "postal code"]),
+    (
+        "operation",
+        &[
+            "type",
+            "participant type",
+            "operation",
+            "operation type",
+            "flock type",
+            "program",
+        ],
+    ),
+    (
+        "species",
+        &[
+            "species",
+            "classification",
+            "classifications",
+            "kind of poultry",
+            "poultry type",
+        ],
+    ),
+    (
+        "birds",
+        &[
+            "number of hens",
+            "number of laying hens",
+            "laying hens",
+            "flock size",
+            "birds",
+            "capacity",
+            "hatching capacity",
+        ],
+    ),
+    ("latitude", &["latitude", "lat"]),
+    ("longitude", &["longitude", "lon", "lng", "long"]),
+];
+
+/// A headcount such as "1,250,000" or "approx. 30000".
+fn parse_count(value: &str) -> Option<u64> {
+    let digits: String = value.chars().filter(char::is_ascii_digit).collect();
+    digits.parse().ok()
+}
+
+/// The operation an NPIP row describes, or None for meat-type flocks. The
+/// type and classification columns are read together, as states put
+/// "Hatchery" or "Egg-type chickens" in either.
+fn npip_operation(kind: &str, classification: &str) -> Option<&'static str> {
+    let text = importer::normalize(&format!("{} {}", kind, classification));
+    if text.contains("hatch") {
+        Some("Hatchery")
+    } else if text.contains("egg") || text.contains("layer") || text.contains("laying") {
+        Some("Egg-type breeding flock")
+    } else {
+        None
+    }
+}
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let mut source = None;
+    let mut state = None;
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--source" => source = Some(args.next().ok_or("--source needs fda or npip")?),
+            "--state" => state = Some(args.next().ok_or("--state needs a value")?.to_uppercase()),
+            _ => return Err(format!("unexpected argument {arg:?}").into()),
+        }
+    }
+    let (name, source_name) = match source.as_deref() {
+        Some("fda") => ("us-egg-farms-fda", "FDA shell egg producer registry"),
+        Some("npip") => ("us-egg-farms-npip", "NPIP participant list"),
+        Some(other) => {
+            return Err(format!("unknown source {other:?}, expected fda or npip").into());
+        }
+        None => return Err("--source is required (fda or npip)".into()),
+    };
+
+    let mut bytes = Vec::new();
+    io::stdin().read_to_end(&mut bytes)?;
+    let (text, _) = archive::decode(&bytes);
+    let mut rows = importer::csv_rows(&text)?;
+    // Lists saved from the published PDFs start with title lines.
+    let header_index = rows
+        .iter()
+        .position(|row| importer::map_columns(COLUMNS, row).contains_key("name"))
+        .ok_or("no name column found")?;
+    let columns = importer::map_columns(COLUMNS, &rows[header_index]);
+    let rows = rows.split_off(header_index + 1);
+
+    let mut report = Report::default();
+    let mut farms: BTreeMap<String, EggFarm> = BTreeMap::new();
+    for row in &rows {
+        let cell = |name: &str| {
+            columns
+                .get(name)
+                .and_then(|i| row.get(*i))
+                .map(|value| value.trim())
+                .unwrap_or_default()
+        };
+        if cell("name").is_empty() {
+            report.skip("no name");
+            continue;
+        }
+        let operation = match source.as_deref() {
+            Some("npip") => match npip_operation(cell("operation"), cell("species")) {
+                Some(operation) => operation.to_string(),
+                None => {
+                    report.skip("meat-type or unclassified flock");
+                    continue;
+                }
+            },
+            _ => match cell("operation") {
+                "" => "Shell egg producer".to_string(),
+                value => value.to_string(),
+            },
+        };
+        let farm_id = match cell("farm_id") {
+            "" => ids::content_id(&[cell("name"), cell("street"), cell("zip")]),
+            id => id.to_string(),
+        };
+
+        // NPIP lists a participant once per classification.
+        let farm = farms.entry(farm_id.clone()).or_insert_with(|| EggFarm {
+            farm_id,
+            name: cell("name").to_string(),
+            operation: operation.clone(),
+            source: source_name.to_string(),
+            street: cell("street").to_string(),
+            city: cell("city").to_string(),
+            county: cell("county").to_string(),
+            state: match cell("state") {
+                "" => state.clone().unwrap_or_default(),
+                value => value.to_uppercase(),
+            },
+            zip: cell("zip").to_string(),
+            latitude: importer::parse_coordinate(cell("latitude")).unwrap_or(0.0),
+            longitude: importer::parse_coordinate(cell("longitude")).unwrap_or(0.0),
+            ..Default::default()
+        });
+        // A participant with a hatchery and flocks is listed as the hatchery.
+        if operation == "Hatchery" {
+            farm.operation = operation;
+        }
+        let mut species = importer::split_activities(&farm.species);
+        let species_cell = match cell("species") {
+            "" if source.as_deref() == Some("fda") => "Laying hens",
+            value => value,
+        };
+        for name in species_cell
+            .split([',', ';', '/', '|'])
+            .map(str::trim)
+            .filter(|name| !name.is_empty())
+        {
+            if !species.iter().any(|known| known == name) {
+                species.push(name.to_string());
+            }
+        }
+        farm.species = species.join("; ");
+        if let Some(count) = parse_count(cell("birds")) {
+            farm.birds = Some(farm.birds.unwrap_or(0) + count);
+        }
+    }
+
+    let mut wtr = csv::Writer::from_writer(io::stdout());
+    for farm in farms.values() {
+        if importer::coordinates(farm.latitude, farm.longitude).is_none() {
+            report.missing_coordinates += 1;
+        }
+        wtr.serialize(farm)?;
+    }
+    wtr.flush()?;
+    report.written = farms.len();
+
+    eprintln!(
+        "wrote {} farms, {} without coordinates (kept at 0, 0 for geocoding)",
+        report.written, report.missing_coordinates
+    );
+    for (reason, rows) in &report.skipped {
+        eprintln!("  skipped {} rows: {}", rows, reason);
+    }
+
+    heatmap_backend::importers::record_run(name, report.written)?;
+
+    Ok(())
+}
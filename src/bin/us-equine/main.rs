@@ -0,0 +1,431 @@
+//! Converts USDA's horse export records into static_data's equine CSV format.
+//!
+//! Usage: `us-equine --source pens < export_facilities.csv > us/equine/pens.csv`
+//! or `us-equine --source shipments [--country mx] < certificates.csv > mx/equine/shipments.csv`
+//!
+//! - `pens` is APHIS's list of facilities approved to hold and inspect
+//!   horses for export, one row per facility.
+//! - `shipments` is the Slaughter Horse Transport Program's owner/shipper
+//!   certificates (VS 10-13, released through FOIA), one row per load. Each
+//!   names the assembly point the load left from and the plant it was
+//!   consigned to. With `--country us` (the default) the assembly points are
+//!   written; with `--country mx` or `ca`, that country's plants. Both are
+//!   written with the horses they shipped or received and the years the
+//!   certificates span.
+//!
+//! Headers vary between releases; columns are recognized by the spellings in
+//! `PEN_COLUMNS` and `SHIPMENT_COLUMNS`. Rows without coordinates are kept at
+//! 0, 0 for the geocode job.
+
+use std::collections::BTreeMap;
+use std::io::{self, Read};
+
+use heatmap_backend::EquineSite;
+use heatmap_backend::archive;
+use heatmap_backend::ids;
+
+/// Header spellings seen in the export facility lists, after normalization.
+const PEN_COLUMNS: &[(&str, &[&str])] = &[
+    (
+        "site_id",
+        &[
+            "approval number",
+            "facility number",
+            "facility id",
+            "export facility number",
+            "id",
+        ],
+    ),
+    (
+        "name",
+        &["facility name", "business name", "facility", "name"],
+    ),
+    (
+        "street",
+        &["street address", "address line 1", "address", "street"],
+    ),
+    ("city", &["city", "town"]),
+    ("county", &["county", "county name"]),
+    ("state", &["state", "st"]),
+    ("zip", &["zip code", "zipcode", "zip", "postal code"]),
+    (
+        "port_of_exit",
+        &[
+            "port of exit",
+            "port of embarkation",
+            "border crossing",
+            "port",
+        ],
+    ),
+    ("species", &["species", "animal type", "animals"]),
+    ("latitude", &["latitude", "lat"]),
+    ("longitude", &["longitude", "lon", "lng", "long"]),
+];
+
+/// Header spellings seen in the owner/shipper certificates.
+const SHIPMENT_COLUMNS: &[(&str, &[&str])] = &[
+    (
+        "origin_name",
+        &[
+            "origin",
+            "origin name",
+            "loading facility",
+            "assembly point",
+            "shipper",
+            "owner shipper",
+        ],
+    ),
+    (
+        "origin_street",
+        &["origin address", "origin street", "shipper address"],
+    ),
+    ("origin_city", &["origin city", "shipper city"]),
+    ("origin_state", &["origin state", "shipper state"]),
+    ("origin_zip", &["origin zip", "shipper zip"]),
+    (
+        "plant_id",
+        &[
+            "establishment number",
+            "plant number",
+            "destination establishment",
+            "tif",
+            "est no",
+        ],
+    ),
+    (
+        "plant_name",
+        &[
+            "destination",
+            "destination name",
+            "slaughter facility",
+            "slaughter plant",
+            "consignee",
+            "plant",
+        ],
+    ),
+    (
+        "plant_street",
+        &["destination address", "plant address", "consignee address"],
+    ),
+    (
+        "plant_city",
+        &["destination city", "plant city", "consignee city"],
+    ),
+    (
+        "plant_state",
+        &[
+            "destination state",
+            "destination province",
+            "plant state",
+            "province",
+        ],
+    ),
+    (
+        "plant_country",
+        &["destination country", "plant country", "country"],
+    ),
+    (
+        "port_of_exit",
+        &[
+            "port of exit",
+            "port of embarkation",
+            "border crossing",
+            "port",
+        ],
+    ),
+    (
+        "horses",
+        &[
+            "number of horses",
+            "horses",
+            "number of animals",
+            "head",
+            "head count",
+            "count",
+        ],
+    ),
+    ("species", &["species", "animal type"]),
+    (
+        "date",
+        &["date", "shipment date", "date of shipment", "loading date"],
+    ),
+];
+
+fn normalize_header(header: &str) -> String {
+    deunicode::deunicode(header)
+        .to_lowercase()
+        .split(|c: char| !c.is_ascii_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Column positions by canonical name.
+fn map_columns(
+    headers: &csv::StringRecord,
+    known: &[(&'static str, &[&str])],
+) -> BTreeMap<&'static str, usize> {
+    let mut columns = BTreeMap::new();
+    for (i, header) in headers.iter().enumerate() {
+        let header = normalize_header(header);
+        if let Some((name, _)) = known
+            .iter()
+            .find(|(_, aliases)| aliases.contains(&header.as_str()))
+        {
+            columns.entry(*name).or_insert(i);
+        }
+    }
+    columns
+}
+
+/// The ISO code of a destination country as the certificates write it.
+fn country_code(value: &str) -> String {
+    match normalize_header(value).as_str() {
+        "mexico" | "mex" | "mx" => "mx".to_string(),
+        "canada" | "can" | "ca" => "ca".to_string(),
+        "" | "united states" | "usa" | "us" => "us".to_string(),
+        other => other.to_string(),
+    }
+}
+
+/// A headcount such as "38" or "38 hd".
+fn parse_count(value: &str) -> Option<u64> {
+    let digits: String = value.chars().filter(char::is_ascii_digit).collect();
+    digits.parse().ok()
+}
+
+/// The year of a date such as "2023-04-11" or "4/11/2023".
+fn year(value: &str) -> Option<u16> {
+    value
+        .split(|c: char| !c.is_ascii_digit())
+        .filter(|part| part.len() == 4)
+        .find_map(|part| part.parse().ok())
+        .filter(|year| (1900..2100).contains(year))
+}
+
+/// Adds `name` to a "; "-separated list without repeating it.
+fn add_to_list(list: &mut String, name: &str) {
+    let name = name.trim();
+    if name.is_empty()
+        || list
+            .split("; ")
+            .any(|known| known.eq_ignore_ascii_case(name))
+    {
+        return;
+    }
+    if !list.is_empty() {
+        list.push_str("; ");
+    }
+    list.push_str(name);
+}
+
+fn read_input() -> io::Result<String> {
+    let mut bytes = Vec::new();
+    io::stdin().read_to_end(&mut bytes)?;
+    let (text, _) = archive::decode(&bytes);
+    Ok(text)
+}
+
+fn read_pens(text: &str) -> Result<Vec<EquineSite>, Box<dyn std::error::Error>> {
+    let mut reader = csv::ReaderBuilder::new()
+        .flexible(true)
+        .from_reader(text.as_bytes());
+    let columns = map_columns(reader.headers()?, PEN_COLUMNS);
+    if !columns.contains_key("name") {
+        return Err("no facility name column found".into());
+    }
+
+    let mut pens: BTreeMap<String, EquineSite> = BTreeMap::new();
+    for record in reader.records() {
+        let record = record?;
+        let cell = |name: &str| {
+            columns
+                .get(name)
+                .and_then(|i| record.get(*i))
+                .map(str::trim)
+                .unwrap_or_default()
+        };
+        if cell("name").is_empty() {
+            continue;
+        }
+        let site_id = match cell("site_id") {
+            "" => ids::content_id(&[cell("name"), cell("street"), cell("zip")]),
+            id => id.to_string(),
+        };
+        // The lists repeat a facility for each port it is approved for.
+        let pen = pens.entry(site_id.clone()).or_insert_with(|| EquineSite {
+            site_id,
+            name: cell("name").to_string(),
+            operation: "Approved export facility".to_string(),
+            source: "USDA approved export facilities".to_string(),
+            street: cell("street").to_string(),
+            city: cell("city").to_string(),
+            county: cell("county").to_string(),
+            state: cell("state").to_uppercase(),
+            zip: cell("zip").to_string(),
+            latitude: cell("latitude").parse().unwrap_or(0.0),
+            longitude: cell("longitude").parse().unwrap_or(0.0),
+            ..Default::default()
+        });
+        add_to_list(&mut pen.port_of_exit, cell("port_of_exit"));
+        add_to_list(
+            &mut pen.species,
+            match cell("species") {
+                "" => "Horse",
+                value => value,
+            },
+        );
+    }
+    Ok(pens.into_values().collect())
+}
+
+fn read_shipments(
+    text: &str,
+    country: &str,
+) -> Result<(Vec<EquineSite>, usize), Box<dyn std::error::Error>> {
+    let mut reader = csv::ReaderBuilder::new()
+        .flexible(true)
+        .from_reader(text.as_bytes());
+    let columns = map_columns(reader.headers()?, SHIPMENT_COLUMNS);
+    let wanted = if country == "us" {
+        "origin_name"
+    } else {
+        "plant_name"
+    };
+    if !columns.contains_key(wanted) {
+        return Err(format!("no {} column found", wanted.replace('_', " ")).into());
+    }
+
+    let mut sites: BTreeMap<String, EquineSite> = BTreeMap::new();
+    let mut years: BTreeMap<String, (u16, u16)> = BTreeMap::new();
+    let mut skipped = 0;
+    for record in reader.records() {
+        let record = record?;
+        let cell = |name: &str| {
+            columns
+                .get(name)
+                .and_then(|i| record.get(*i))
+                .map(str::trim)
+                .unwrap_or_default()
+        };
+        let site = if country == "us" {
+            if cell("origin_name").is_empty() {
+                skipped += 1;
+                continue;
+            }
+            EquineSite {
+                site_id: ids::content_id(&[
+                    cell("origin_name"),
+                    cell("origin_street"),
+                    cell("origin_zip"),
+                ]),
+                name: cell("origin_name").to_string(),
+                operation: "Assembly point".to_string(),
+                street: cell("origin_street").to_string(),
+                city: cell("origin_city").to_string(),
+                state: cell("origin_state").to_uppercase(),
+                zip: cell("origin_zip").to_string(),
+                ..Default::default()
+            }
+        } else {
+            if cell("plant_name").is_empty() {
+                skipped += 1;
+                continue;
+            }
+            // Releases covering one country's plants leave the column out.
+            if columns.contains_key("plant_country")
+                && country_code(cell("plant_country")) != country
+            {
+                continue;
+            }
+            EquineSite {
+                site_id: match cell("plant_id") {
+                    "" => ids::content_id(&[cell("plant_name"), cell("plant_city")]),
+                    id => id.to_string(),
+                },
+                name: cell("plant_name").to_string(),
+                operation: "Horse slaughter plant".to_string(),
+                street: cell("plant_street").to_string(),
+                city: cell("plant_city").to_string(),
+                state: cell("plant_state").to_string(),
+                ..Default::default()
+            }
+        };
+
+        let entry = sites.entry(site.site_id.clone()).or_insert(EquineSite {
+            source: "USDA owner/shipper certificates".to_string(),
+            ..site
+        });
+        add_to_list(&mut entry.port_of_exit, cell("port_of_exit"));
+        add_to_list(
+            &mut entry.species,
+            match cell("species") {
+                "" => "Horse",
+                value => value,
+            },
+        );
+        if let Some(count) = parse_count(cell("horses")) {
+            entry.horses = Some(entry.horses.unwrap_or(0) + count);
+        }
+        if let Some(year) = year(cell("date")) {
+            let span = years.entry(entry.site_id.clone()).or_insert((year, year));
+            *span = (span.0.min(year), span.1.max(year));
+        }
+    }
+
+    let sites = sites
+        .into_values()
+        .map(|mut site| {
+            site.period = match years.get(&site.site_id) {
+                Some((first, last)) if first == last => first.to_string(),
+                Some((first, last)) => format!("{}-{}", first, last),
+                None => String::new(),
+            };
+            site
+        })
+        .collect();
+    Ok((sites, skipped))
+}
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let mut source = None;
+    let mut country = "us".to_string();
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--source" => source = Some(args.next().ok_or("--source needs pens or shipments")?),
+            "--country" => country = args.next().ok_or("--country needs a value")?.to_lowercase(),
+            _ => return Err(format!("unexpected argument {arg:?}").into()),
+        }
+    }
+
+    let text = read_input()?;
+    let (name, (sites, skipped)) = match source.as_deref() {
+        Some("pens") if country == "us" => ("us-equine-pens".to_string(), (read_pens(&text)?, 0)),
+        Some("pens") => return Err("the export facilities are all in the us".into()),
+        Some("shipments") => (
+            format!("{}-equine-shipments", country),
+            read_shipments(&text, &country)?,
+        ),
+        Some(other) => {
+            return Err(format!("unknown source {other:?}, expected pens or shipments").into());
+        }
+        None => return Err("--source is required (pens or shipments)".into()),
+    };
+
+    let mut wtr = csv::Writer::from_writer(io::stdout());
+    let count = sites.len();
+    for site in sites {
+        wtr.serialize(site)?;
+    }
+    wtr.flush()?;
+
+    eprintln!(
+        "wrote {} {} sites, skipped {} rows without a name",
+        count, country, skipped
+    );
+
+    heatmap_backend::importers::record_run(&name, count)?;
+
+    Ok(())
+}
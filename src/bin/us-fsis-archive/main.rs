@@ -0,0 +1,362 @@
+//! Converts releases of the FSIS MPI directory into dated snapshots.
+//!
+//! Older releases of the directory (kept by the Internet Archive or saved from
+//! previous data refreshes) use a handful of different column layouts. This
+//! converts each of them into the shared `Location` schema and writes one CSV
+//! per release date to `static_data/us/snapshots/<date>.csv`. No archived
+//! releases have been converted into static_data yet, so until someone runs
+//! this against the captures, /api/locations/changes and /api/changelog only
+//! reach back to the first snapshot a refresh saved.
+//!
+//! Usage:
+//!
+//! ```text
+//! us-fsis-archive [--out DIR] [--current FILE] [--keep-columns] DATE=SOURCE...
+//! ```
+//!
+//! `SOURCE` is either a local file or an http(s) URL, e.g. a Wayback Machine
+//! capture such as
+//! `https://web.archive.org/web/20190301000000id_/https://www.fsis.usda.gov/.../MPI_Directory_by_Establishment_Number.csv`.
+//!
+//! A release only has the directory's demographic columns. Everything else
+//! (coordinates, the species and volume columns from the demographic dataset,
+//! and any column `Location` doesn't know) is carried over from the current
+//! directory (`--current`) wherever the establishment still exists. With
+//! `--keep-columns` the run fails rather than write a file in which a column
+//! filled in the current directory is blank throughout, so the release can
+//! replace it safely; old releases predate some columns, so backfills leave
+//! it out.
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+
+use heatmap_backend::archive;
+use heatmap_backend::{Location, fields};
+
+const DEFAULT_OUT_DIR: &str = "static_data/us/snapshots";
+const DEFAULT_CURRENT: &str = "static_data/us/locations.csv";
+
+struct Release {
+    date: String,
+    source: String,
+}
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let mut out_dir = PathBuf::from(DEFAULT_OUT_DIR);
+    let mut current = PathBuf::from(DEFAULT_CURRENT);
+    let mut keep_columns = false;
+    let mut releases = Vec::new();
+
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--out" => out_dir = args.next().ok_or("--out needs a directory")?.into(),
+            "--current" => current = args.next().ok_or("--current needs a file")?.into(),
+            "--keep-columns" => keep_columns = true,
+            _ => {
+                let (date, source) = arg
+                    .split_once('=')
+                    .ok_or_else(|| format!("expected DATE=SOURCE, got {arg:?}"))?;
+                if !is_iso_date(date) {
+                    return Err(format!("release date must be YYYY-MM-DD, got {date:?}").into());
+                }
+                releases.push(Release {
+                    date: date.to_string(),
+                    source: source.to_string(),
+                });
+            }
+        }
+    }
+
+    if releases.is_empty() {
+        return Err(
+            "usage: us-fsis-archive [--out DIR] [--current FILE] [--keep-columns] DATE=SOURCE..."
+                .into(),
+        );
+    }
+
+    let known = load_current(&current)?;
+    let current_path = current;
+    fs::create_dir_all(&out_dir)?;
+
+    let mut total = 0;
+    for release in releases {
+        let raw = fetch(&release.source)?;
+        let locations = normalize_release(&raw, &known)?;
+        if locations.is_empty() {
+            return Err(format!("{}: the release has no establishments", release.source).into());
+        }
+        let (headers, records) = with_current_columns(&locations, &known)?;
+        let dropped = dropped_column(&known, &headers, &records).filter(|_| keep_columns);
+        if let Some(column) = dropped {
+            return Err(format!(
+                "{}: {} is filled in in {} but would be blank in every row, \
+                 leaving the snapshot unwritten",
+                release.date,
+                column,
+                current_path.display()
+            )
+            .into());
+        }
+
+        let path = out_dir.join(format!("{}.csv", release.date));
+        let mut wtr = csv::Writer::from_path(&path)?;
+        wtr.write_record(&headers)?;
+        for record in &records {
+            wtr.write_record(record)?;
+        }
+        wtr.flush()?;
+
+        eprintln!(
+            "{}: {} establishments -> {}",
+            release.date,
+            locations.len(),
+            path.display()
+        );
+        total += locations.len();
+    }
+
+    heatmap_backend::importers::record_run("us-fsis-archive", total)?;
+    Ok(())
+}
+
+fn is_iso_date(date: &str) -> bool {
+    let parts: Vec<&str> = date.split('-').collect();
+    parts.len() == 3
+        && [4, 2, 2]
+            .iter()
+            .zip(&parts)
+            .all(|(len, part)| part.len() == *len && part.chars().all(|c| c.is_ascii_digit()))
+}
+
+fn fetch(source: &str) -> Result<String, Box<dyn std::error::Error>> {
+    let bytes = if source.starts_with("http://") || source.starts_with("https://") {
+        reqwest::blocking::get(source)?
+            .error_for_status()?
+            .bytes()?
+            .to_vec()
+    } else {
+        fs::read(source)?
+    };
+    // Old exports are frequently Windows-1252, which isn't valid UTF-8.
+    let (text, encoding) = archive::decode(&bytes);
+    if encoding != "UTF-8" {
+        eprintln!("{}: read as {}", source, encoding);
+    }
+    Ok(text)
+}
+
+/// The current directory, to carry over what releases don't have.
+#[derive(Default)]
+struct Current {
+    headers: csv::StringRecord,
+    /// Each row as read and as a `Location`.
+    rows: Vec<(csv::StringRecord, Location)>,
+    /// Indexes into `rows` by both establishment ID and number.
+    keys: HashMap<String, usize>,
+}
+
+impl Current {
+    fn get(&self, key: &str) -> Option<&(csv::StringRecord, Location)> {
+        self.keys.get(key).map(|&row| &self.rows[row])
+    }
+}
+
+fn load_current(path: &PathBuf) -> Result<Current, Box<dyn std::error::Error>> {
+    let mut known = Current::default();
+    if !path.exists() {
+        eprintln!(
+            "warning: {} not found, snapshots will have no coordinates",
+            path.display()
+        );
+        return Ok(known);
+    }
+
+    let mut reader = csv::Reader::from_path(path)?;
+    known.headers = reader.headers()?.clone();
+    for result in reader.records() {
+        let record = result?;
+        let loc: Location = record.deserialize(Some(&known.headers))?;
+        for key in [&loc.establishment_id, &loc.establishment_number] {
+            if !key.is_empty() {
+                known.keys.insert(key.clone(), known.rows.len());
+            }
+        }
+        known.rows.push((record, loc));
+    }
+    Ok(known)
+}
+
+/// `locations` as CSV records: the `Location` columns, then every other
+/// column of the current directory, copied from an establishment's current
+/// row.
+fn with_current_columns(
+    locations: &[Location],
+    known: &Current,
+) -> Result<(csv::StringRecord, Vec<csv::StringRecord>), Box<dyn std::error::Error>> {
+    let mut buffer = csv::Writer::from_writer(Vec::new());
+    for loc in locations {
+        buffer.serialize(loc)?;
+    }
+    let buffer = buffer.into_inner().map_err(|e| e.to_string())?;
+    let mut reader = csv::Reader::from_reader(buffer.as_slice());
+    let mut headers = reader.headers()?.clone();
+    let extra: Vec<usize> = (0..known.headers.len())
+        .filter(|&column| {
+            !headers
+                .iter()
+                .any(|header| header == &known.headers[column])
+        })
+        .collect();
+    for &column in &extra {
+        headers.push_field(&known.headers[column]);
+    }
+
+    let mut records = Vec::new();
+    for (record, loc) in reader.records().zip(locations) {
+        let mut record = record?;
+        let current = known.get(&loc.establishment_id).map(|(row, _)| row);
+        for &column in &extra {
+            record.push_field(current.and_then(|row| row.get(column)).unwrap_or(""));
+        }
+        records.push(record);
+    }
+    Ok((headers, records))
+}
+
+/// A column filled in somewhere in the current directory and blank in every
+/// one of `records`.
+fn dropped_column(
+    known: &Current,
+    headers: &csv::StringRecord,
+    records: &[csv::StringRecord],
+) -> Option<String> {
+    known.headers.iter().enumerate().find_map(|(column, name)| {
+        if !filled(known.rows.iter().map(|(row, _)| row), column) {
+            return None;
+        }
+        let written = headers.iter().position(|header| header == name);
+        match written {
+            Some(written) if filled(records.iter(), written) => None,
+            _ => Some(name.to_string()),
+        }
+    })
+}
+
+fn filled<'a>(mut rows: impl Iterator<Item = &'a csv::StringRecord>, column: usize) -> bool {
+    rows.any(|row| {
+        row.get(column)
+            .is_some_and(|value| !value.trim().is_empty())
+    })
+}
+
+/// Maps the header names used across MPI directory releases onto `Location` fields.
+fn canonical_column(header: &str) -> Option<&'static str> {
+    let key: String = header
+        .chars()
+        .filter(|c| c.is_ascii_alphanumeric())
+        .collect::<String>()
+        .to_lowercase();
+
+    Some(match key.as_str() {
+        "estid" | "establishmentid" => "establishment_id",
+        "estnumber" | "establishmentnumber" | "estnbr" => "establishment_number",
+        "company" | "establishmentname" | "name" => "establishment_name",
+        "street" | "address" | "address1" => "street",
+        "city" => "city",
+        "state" => "state",
+        "zip" | "zipcode" => "zip",
+        "phone" => "phone",
+        "grantdate" => "grant_date",
+        "activities" | "type" => "activities",
+        "dbas" | "dba" => "dbas",
+        "district" => "district",
+        "circuit" => "circuit",
+        "size" => "size",
+        "county" => "county",
+        "fipscode" | "fips" => "fips_code",
+        "latitude" | "lat" => "latitude",
+        "longitude" | "lng" | "lon" => "longitude",
+        _ => return None,
+    })
+}
+
+fn normalize_release(
+    raw: &str,
+    known: &Current,
+) -> Result<Vec<Location>, Box<dyn std::error::Error>> {
+    let mut reader = csv::ReaderBuilder::new()
+        .flexible(true)
+        .from_reader(raw.as_bytes());
+
+    let columns: Vec<Option<&'static str>> = reader
+        .headers()?
+        .iter()
+        .map(|h| canonical_column(h.trim_start_matches('\u{feff}')))
+        .collect();
+
+    if !columns.contains(&Some("establishment_name")) {
+        return Err("release has no establishment name column, is this an MPI directory?".into());
+    }
+
+    let mut locations = Vec::new();
+    for record in reader.records() {
+        let record = record?;
+        let values: Vec<(&'static str, String)> = columns
+            .iter()
+            .zip(record.iter())
+            .filter_map(|(column, value)| Some(((*column)?, value.trim().to_string())))
+            .collect();
+        let value = |wanted: &str| {
+            values
+                .iter()
+                .find(|(column, _)| *column == wanted)
+                .map_or("", |(_, value)| value.as_str())
+        };
+        if value("establishment_name").is_empty() {
+            continue;
+        }
+
+        // Start from the establishment's current row so the columns the
+        // release doesn't have are kept.
+        let mut loc = known
+            .get(value("establishment_id"))
+            .or_else(|| known.get(value("establishment_number")))
+            .map(|(_, current)| current.clone())
+            .unwrap_or_default();
+        for (column, value) in values {
+            match column {
+                // Blank where the current directory may know better.
+                "establishment_id" | "county" | "fips_code" if value.is_empty() => {}
+                "latitude" | "longitude" if value.parse().unwrap_or(0.0) == 0.0 => {}
+                "establishment_id" => loc.establishment_id = value,
+                "establishment_number" => loc.establishment_number = value,
+                "establishment_name" => loc.establishment_name = value,
+                "street" => loc.street = value,
+                "city" => loc.city = value,
+                "state" => loc.state = value,
+                "zip" => loc.zip = value,
+                "phone" => loc.phone = value,
+                "grant_date" => loc.grant_date = fields::parse_date(&value),
+                "activities" => loc.activities = value,
+                "dbas" => loc.dbas = value,
+                "district" => loc.district = value,
+                "circuit" => loc.circuit = value,
+                "size" => loc.size = value,
+                "county" => loc.county = value,
+                "fips_code" => loc.fips_code = value,
+                "latitude" => loc.latitude = value.parse().unwrap_or_default(),
+                "longitude" => loc.longitude = value.parse().unwrap_or_default(),
+                _ => {}
+            }
+        }
+        if loc.establishment_id.is_empty() {
+            loc.establishment_id = loc.establishment_number.clone();
+        }
+
+        locations.push(loc);
+    }
+
+    Ok(locations)
+}
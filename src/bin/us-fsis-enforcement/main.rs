@@ -0,0 +1,262 @@
+//! Converts FSIS quarterly enforcement reports and noncompliance records into
+//! static_data's enforcement CSV format, one row per action or record.
+//!
+//! Usage: `us-fsis-enforcement [--locations FILE] [--kind KIND] FILE... >
+//! us/enforcement/2025-q1.csv`
+//!
+//! Each file is a CSV export of a report's tables (the quarterly reports'
+//! appendices, or noncompliance records released under FOIA). Columns are
+//! recognized by the spellings in `COLUMNS`. `--kind` sets the kind of rows
+//! without an action column, e.g. `noncompliance_record` for a file of NRs.
+//! Establishment numbers are resolved to IDs through the current directory
+//! (`--locations`); rows naming an establishment not in it are kept without
+//! an ID and reported.
+
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+
+use heatmap_backend::archive;
+use heatmap_backend::{EnforcementKind, EnforcementRecord, Location};
+
+const DEFAULT_LOCATIONS: &str = "static_data/us/locations.csv";
+
+/// Header spellings seen in the exports, after normalization.
+const COLUMNS: &[(&str, &[&str])] = &[
+    (
+        "establishment_number",
+        &[
+            "establishment number",
+            "est number",
+            "est no",
+            "establishment",
+            "est",
+        ],
+    ),
+    (
+        "establishment_name",
+        &["establishment name", "est name", "name", "company"],
+    ),
+    (
+        "date",
+        &[
+            "date",
+            "action date",
+            "date of action",
+            "nr date",
+            "issue date",
+        ],
+    ),
+    (
+        "kind",
+        &["action", "action type", "enforcement action", "type"],
+    ),
+    (
+        "regulation",
+        &["regulation", "regulations", "cfr", "citation", "code"],
+    ),
+    (
+        "description",
+        &[
+            "description",
+            "basis",
+            "basis for action",
+            "reason",
+            "noncompliance",
+            "narrative",
+        ],
+    ),
+    ("report_url", &["report url", "url", "link"]),
+];
+
+fn normalize_header(header: &str) -> String {
+    deunicode::deunicode(header)
+        .to_lowercase()
+        .split(|c: char| !c.is_ascii_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// A CSV file as rows of canonical column name to value.
+fn read_rows(
+    path: &Path,
+) -> Result<Vec<BTreeMap<&'static str, String>>, Box<dyn std::error::Error>> {
+    let bytes =
+        std::fs::read(path).map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    let (text, _) = archive::decode(&bytes);
+    let mut reader = csv::ReaderBuilder::new()
+        .flexible(true)
+        .from_reader(text.as_bytes());
+
+    let mut columns = BTreeMap::new();
+    for (i, header) in reader.headers()?.iter().enumerate() {
+        let header = normalize_header(header);
+        if let Some((name, _)) = COLUMNS
+            .iter()
+            .find(|(_, aliases)| aliases.contains(&header.as_str()))
+        {
+            columns.entry(*name).or_insert(i);
+        }
+    }
+
+    let mut rows = Vec::new();
+    for record in reader.records() {
+        let record = record?;
+        rows.push(
+            columns
+                .iter()
+                .map(|(name, i)| (*name, record.get(*i).unwrap_or_default().trim().to_string()))
+                .collect(),
+        );
+    }
+    Ok(rows)
+}
+
+/// "03/14/2024" or "2024-03-14T00:00:00" as "2024-03-14".
+fn normalize_date(value: &str) -> String {
+    let value = value.split(['T', ' ']).next().unwrap_or_default();
+    ["%Y-%m-%d", "%m/%d/%Y", "%m/%d/%y", "%d-%b-%Y"]
+        .iter()
+        .find_map(|format| chrono::NaiveDate::parse_from_str(value, format).ok())
+        .map(|date| date.format("%Y-%m-%d").to_string())
+        .unwrap_or_else(|| value.to_string())
+}
+
+/// The grants in an establishment number: "M267+P267", "Est. 267 M" and
+/// "M-267" all give "M267" (and "P267").
+fn grant_numbers(number: &str) -> Vec<String> {
+    number
+        .to_uppercase()
+        .replace("EST.", "")
+        .split(['+', '/', ','])
+        .map(|part| {
+            let part: String = part.chars().filter(char::is_ascii_alphanumeric).collect();
+            // "267M" as written in some reports.
+            let digits = part.trim_end_matches(|c: char| c.is_ascii_alphabetic());
+            match part.strip_prefix(digits) {
+                Some(suffix) if !suffix.is_empty() && !digits.is_empty() => {
+                    format!("{}{}", suffix, digits)
+                }
+                _ => part,
+            }
+        })
+        .filter(|part| !part.is_empty())
+        .collect()
+}
+
+/// Current establishments' IDs keyed by each grant number.
+fn load_locations(path: &PathBuf) -> Result<HashMap<String, String>, Box<dyn std::error::Error>> {
+    let mut ids = HashMap::new();
+    let mut reader = csv::Reader::from_path(path)
+        .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    for result in reader.deserialize() {
+        let loc: Location = result?;
+        for number in grant_numbers(&loc.establishment_number) {
+            ids.entry(number)
+                .or_insert_with(|| loc.establishment_id.clone());
+        }
+    }
+    Ok(ids)
+}
+
+/// Humane handling is 9 CFR 313 and the Humane Methods of Slaughter Act for
+/// livestock, and good commercial practices (9 CFR 381.65(b)) for poultry.
+fn is_humane_handling(regulation: &str, description: &str) -> bool {
+    let regulation = regulation.to_lowercase().replace("9 cfr", "");
+    let description = description.to_lowercase();
+    regulation.trim_start().starts_with("313")
+        || regulation.contains("381.65(b)")
+        || ["humane", "hmsa", "good commercial practice", "stunning"]
+            .iter()
+            .any(|term| description.contains(term))
+}
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let mut locations = PathBuf::from(DEFAULT_LOCATIONS);
+    let mut default_kind = None;
+    let mut files = Vec::new();
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--locations" => locations = args.next().ok_or("--locations needs a file")?.into(),
+            "--kind" => {
+                let kind = args.next().ok_or("--kind needs a kind")?;
+                default_kind = Some(
+                    EnforcementKind::parse(&kind)
+                        .ok_or_else(|| format!("unknown kind {kind:?}"))?,
+                );
+            }
+            _ => files.push(PathBuf::from(arg)),
+        }
+    }
+    if files.is_empty() {
+        return Err("usage: us-fsis-enforcement [--locations FILE] [--kind KIND] FILE...".into());
+    }
+
+    let ids = load_locations(&locations)?;
+    let get = |row: &BTreeMap<&str, String>, name: &str| row.get(name).cloned().unwrap_or_default();
+
+    let mut wtr = csv::Writer::from_writer(std::io::stdout());
+    let mut count = 0;
+    let mut unlinked: BTreeMap<String, usize> = BTreeMap::new();
+    let mut unknown_kinds: BTreeMap<String, usize> = BTreeMap::new();
+    for file in &files {
+        for row in read_rows(file)? {
+            let establishment_number = get(&row, "establishment_number");
+            if establishment_number.is_empty() {
+                continue;
+            }
+            let kind_text = get(&row, "kind");
+            let kind = match EnforcementKind::parse(&kind_text).or(default_kind) {
+                Some(kind) => kind,
+                None => {
+                    *unknown_kinds.entry(kind_text).or_default() += 1;
+                    EnforcementKind::Other
+                }
+            };
+            let establishment_id = grant_numbers(&establishment_number)
+                .iter()
+                .find_map(|number| ids.get(number))
+                .cloned()
+                .unwrap_or_default();
+            if establishment_id.is_empty() {
+                *unlinked.entry(establishment_number.clone()).or_default() += 1;
+            }
+            let regulation = get(&row, "regulation");
+            let description = get(&row, "description");
+            wtr.serialize(EnforcementRecord {
+                establishment_id,
+                establishment_number,
+                establishment_name: get(&row, "establishment_name"),
+                date: normalize_date(&get(&row, "date")),
+                kind,
+                humane_handling: is_humane_handling(&regulation, &description),
+                regulation,
+                description,
+                report_url: get(&row, "report_url"),
+            })?;
+            count += 1;
+        }
+    }
+    wtr.flush()?;
+
+    eprintln!(
+        "wrote {} records, {} of them naming establishments not in {}",
+        count,
+        unlinked.values().sum::<usize>(),
+        locations.display()
+    );
+    for (number, rows) in &unlinked {
+        eprintln!("  establishment {:?} not found on {} rows", number, rows);
+    }
+    for (kind, rows) in &unknown_kinds {
+        eprintln!(
+            "  unrecognized action {:?} on {} rows, kept as other",
+            kind, rows
+        );
+    }
+
+    heatmap_backend::importers::record_run("us-fsis-enforcement", count)?;
+
+    Ok(())
+}
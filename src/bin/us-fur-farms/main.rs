@@ -0,0 +1,233 @@
+//! Converts USDA mink farm lists and state fur farm registries into
+//! static_data's fur farm CSV format.
+//!
+//! Usage: `us-fur-farms --source usda < mink_farms.csv > us/fur_farms/usda.csv`
+//! or `us-fur-farms --source state --state WI < registry.csv > us/fur_farms/wi.csv`
+//!
+//! The USDA lists (premises registered for SARS-CoV-2 surveillance and
+//! released through FOIA) and the state registries come as CSV exports whose
+//! headers vary; columns are recognized by the spellings in `COLUMNS`. The
+//! USDA lists only farm mink, so rows without a species column are recorded
+//! as mink. Rows without coordinates are kept at 0, 0 for the geocode job.
+
+use std::collections::BTreeMap;
+use std::io::{self, Read};
+
+use heatmap_backend::FurFarm;
+use heatmap_backend::archive;
+use heatmap_backend::ids;
+
+/// Header spellings seen in the lists and registries, after normalization.
+const COLUMNS: &[(&str, &[&str])] = &[
+    (
+        "farm_id",
+        &[
+            "premises id",
+            "premises number",
+            "pin",
+            "license number",
+            "license no",
+            "permit number",
+            "registration number",
+            "id",
+        ],
+    ),
+    (
+        "name",
+        &[
+            "farm name",
+            "business name",
+            "premises name",
+            "licensee",
+            "owner",
+            "operator",
+            "name",
+        ],
+    ),
+    (
+        "street",
+        &[
+            "street address",
+            "address line 1",
+            "address 1",
+            "address",
+            "street",
+        ],
+    ),
+    ("city", &["city", "town"]),
+    ("county", &["county", "county name"]),
+    ("state", &["state", "st"]),
+    ("zip", &["zip code", "zipcode", "zip", "postal code"]),
+    (
+        "species",
+        &["species", "animal type", "animals raised", "fur species"],
+    ),
+    (
+        "animals",
+        &[
+            "number of animals",
+            "number of mink",
+            "inventory",
+            "head count",
+            "headcount",
+            "animals",
+        ],
+    ),
+    ("latitude", &["latitude", "lat"]),
+    ("longitude", &["longitude", "lon", "lng", "long"]),
+];
+
+fn normalize_header(header: &str) -> String {
+    deunicode::deunicode(header)
+        .to_lowercase()
+        .split(|c: char| !c.is_ascii_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Column positions by canonical name.
+fn map_columns(headers: &csv::StringRecord) -> BTreeMap<&'static str, usize> {
+    let mut columns = BTreeMap::new();
+    for (i, header) in headers.iter().enumerate() {
+        let header = normalize_header(header);
+        if let Some((name, _)) = COLUMNS
+            .iter()
+            .find(|(_, aliases)| aliases.contains(&header.as_str()))
+        {
+            columns.entry(*name).or_insert(i);
+        }
+    }
+    columns
+}
+
+/// "Mink, Fox / Chinchilla" as "Mink; Fox; Chinchilla".
+fn species_list(value: &str) -> Vec<String> {
+    value
+        .split([',', ';', '/', '|'])
+        .map(str::trim)
+        .filter(|name| !name.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+/// A headcount such as "12,500" or "approx. 3000".
+fn parse_count(value: &str) -> Option<u64> {
+    let digits: String = value.chars().filter(char::is_ascii_digit).collect();
+    digits.parse().ok()
+}
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let mut source = None;
+    let mut state = None;
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--source" => source = Some(args.next().ok_or("--source needs usda or state")?),
+            "--state" => state = Some(args.next().ok_or("--state needs a value")?.to_uppercase()),
+            _ => return Err(format!("unexpected argument {arg:?}").into()),
+        }
+    }
+    let (source_name, default_species) = match source.as_deref() {
+        Some("usda") => ("USDA mink farm list".to_string(), "Mink"),
+        Some("state") => (
+            format!(
+                "{} fur farm registry",
+                state.as_deref().ok_or("--source state needs --state")?
+            ),
+            "",
+        ),
+        Some(other) => {
+            return Err(format!("unknown source {other:?}, expected usda or state").into());
+        }
+        None => return Err("--source is required (usda or state)".into()),
+    };
+
+    let mut bytes = Vec::new();
+    io::stdin().read_to_end(&mut bytes)?;
+    let (text, _) = archive::decode(&bytes);
+    let mut reader = csv::ReaderBuilder::new()
+        .flexible(true)
+        .from_reader(text.as_bytes());
+    let columns = map_columns(reader.headers()?);
+    if !columns.contains_key("name") && !columns.contains_key("farm_id") {
+        return Err("no name or premises id column found".into());
+    }
+
+    let mut farms: BTreeMap<String, FurFarm> = BTreeMap::new();
+    let mut redacted: BTreeMap<String, usize> = BTreeMap::new();
+    let mut skipped = 0;
+    for record in reader.records() {
+        let record = record?;
+        let cell = |name: &str| {
+            columns
+                .get(name)
+                .and_then(|i| record.get(*i))
+                .map(str::trim)
+                .unwrap_or_default()
+        };
+        // FOIA releases often redact names but keep the premises and county.
+        if cell("name").is_empty() && cell("farm_id").is_empty() && cell("county").is_empty() {
+            skipped += 1;
+            continue;
+        }
+        let farm_id = match cell("farm_id") {
+            // Redacted rows differ only by county, so are numbered within it.
+            "" if cell("name").is_empty() && cell("street").is_empty() => {
+                let nth = redacted.entry(cell("county").to_string()).or_insert(0);
+                *nth += 1;
+                ids::content_id(&[cell("county"), &nth.to_string()])
+            }
+            "" => ids::content_id(&[cell("name"), cell("street"), cell("zip")]),
+            id => id.to_string(),
+        };
+
+        // Registries list a farm once per species.
+        let farm = farms.entry(farm_id.clone()).or_insert_with(|| FurFarm {
+            farm_id,
+            name: cell("name").to_string(),
+            source: source_name.clone(),
+            street: cell("street").to_string(),
+            city: cell("city").to_string(),
+            county: cell("county").to_string(),
+            state: match cell("state") {
+                "" => state.clone().unwrap_or_default(),
+                value => value.to_uppercase(),
+            },
+            zip: cell("zip").to_string(),
+            latitude: cell("latitude").parse().unwrap_or(0.0),
+            longitude: cell("longitude").parse().unwrap_or(0.0),
+            ..Default::default()
+        });
+        let mut species = species_list(&farm.species);
+        let cell_species = match cell("species") {
+            "" => default_species,
+            value => value,
+        };
+        for name in species_list(cell_species) {
+            if !species.contains(&name) {
+                species.push(name);
+            }
+        }
+        farm.species = species.join("; ");
+        if let Some(count) = parse_count(cell("animals")) {
+            farm.animals = Some(farm.animals.unwrap_or(0) + count);
+        }
+    }
+
+    let mut wtr = csv::Writer::from_writer(io::stdout());
+    let count = farms.len();
+    for farm in farms.into_values() {
+        wtr.serialize(farm)?;
+    }
+    wtr.flush()?;
+
+    eprintln!(
+        "wrote {} farms, skipped {} rows without a name, id or county",
+        count, skipped
+    );
+
+    heatmap_backend::importers::record_run("us-fur-farms", count)?;
+
+    Ok(())
+}
@@ -0,0 +1,103 @@
+// Until Every Cage is Empty
+// Copyright (C) 2025 Eli Perez
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+// Contact the developer directly at untileverycageproject@protonmail.com
+//! Converts the Census Bureau's ZCTA gazetteer file into the ZIP centroids
+//! residences are served at (see `redaction`).
+//!
+//! Usage: `us-zip-centroids < 2024_Gaz_zcta_national.txt > us/zip_centroids.csv`
+//!
+//! The gazetteer is a tab-separated file, one row per ZIP Code Tabulation
+//! Area, giving its internal point as `INTPTLAT` and `INTPTLONG` (the last
+//! header padded with spaces). ZCTAs are named after the ZIP code most of
+//! their addresses use, so the internal point stands in for the ZIP's.
+
+use std::collections::BTreeMap;
+use std::io::{self, Read};
+
+#[derive(serde::Serialize)]
+struct ZipCentroid {
+    zip: String,
+    latitude: f64,
+    longitude: f64,
+}
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    if let Some(arg) = std::env::args().nth(1) {
+        return Err(
+            format!("unexpected argument {arg:?}, the gazetteer is read from stdin").into(),
+        );
+    }
+
+    let mut bytes = Vec::new();
+    io::stdin().read_to_end(&mut bytes)?;
+    let mut reader = csv::ReaderBuilder::new()
+        .delimiter(b'\t')
+        .trim(csv::Trim::All)
+        .from_reader(bytes.as_slice());
+    let headers = reader.headers()?.clone();
+    let column = |name: &str| {
+        headers
+            .iter()
+            .position(|header| header.eq_ignore_ascii_case(name))
+            .ok_or_else(|| format!("no {} column, is this the ZCTA gazetteer?", name))
+    };
+    let (zip, latitude, longitude) = (column("GEOID")?, column("INTPTLAT")?, column("INTPTLONG")?);
+
+    let mut centroids = BTreeMap::new();
+    let mut skipped = 0;
+    for record in reader.records() {
+        let record = record?;
+        let cell = |i: usize| record.get(i).unwrap_or_default();
+        let point = (
+            cell(latitude).parse::<f64>(),
+            cell(longitude).parse::<f64>(),
+        );
+        let (Ok(latitude), Ok(longitude)) = point else {
+            skipped += 1;
+            continue;
+        };
+        let code = cell(zip);
+        if code.len() != 5 || !code.chars().all(|c| c.is_ascii_digit()) {
+            skipped += 1;
+            continue;
+        }
+        centroids.insert(
+            code.to_string(),
+            ZipCentroid {
+                zip: code.to_string(),
+                latitude,
+                longitude,
+            },
+        );
+    }
+
+    let mut wtr = csv::Writer::from_writer(io::stdout());
+    let count = centroids.len();
+    for centroid in centroids.into_values() {
+        wtr.serialize(centroid)?;
+    }
+    wtr.flush()?;
+
+    eprintln!(
+        "wrote {} ZIP centroids, skipped {} rows without a ZIP or point",
+        count, skipped
+    );
+
+    heatmap_backend::importers::record_run("us-zip-centroids", count)?;
+
+    Ok(())
+}
@@ -0,0 +1,148 @@
+// Until Every Cage is Empty
+// Copyright (C) 2025 Eli Perez
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+// Contact the developer directly at untileverycageproject@protonmail.com
+use std::hash::{DefaultHasher, Hash, Hasher};
+
+use axum::extract::{Request, State};
+use axum::http::{HeaderValue, Method, StatusCode, header};
+use axum::middleware::Next;
+use axum::response::{IntoResponse, Response};
+
+use crate::AppState;
+
+/// Routes whose responses only change with the dataset, the coordinate
+/// overrides and moderation. A trailing `*` matches any path with that prefix.
+const CACHEABLE: &[&str] = &[
+    "/api/locations*",
+    "/api/search",
+    "/api/autocomplete",
+    "/api/match",
+    "/api/aphis-reports*",
+    "/api/inspection-reports*",
+    "/api/changelog",
+    "/api/activities",
+    "/api/stats",
+    "/api/meta",
+    "/api/choropleth",
+    "/api/heatmap",
+    "/api/facilities*",
+    "/api/aquaculture",
+    "/api/fur-farms",
+    "/api/egg-farms",
+    "/api/cafos",
+    "/api/equine",
+    "/api/citations",
+    "/api/enforcement",
+    "/api/data-quality",
+    "/api/export/*",
+    "/tiles/*",
+    "/sitemap.xml",
+];
+
+const CACHE_CONTROL: &str = "public, max-age=60";
+
+fn cacheable(path: &str) -> bool {
+    CACHEABLE
+        .iter()
+        .any(|pattern| match pattern.strip_suffix('*') {
+            Some(prefix) => path.starts_with(prefix),
+            None => path == *pattern,
+        })
+}
+
+/// A weak validator for the current data and the requested URL and
+/// representation. Weak because the compression layer may re-encode the body.
+fn etag(state: &AppState, revision: u64, request: &Request) -> String {
+    let mut hasher = DefaultHasher::new();
+    state.store.current().content_hash.hash(&mut hasher);
+    revision.hash(&mut hasher);
+    request.uri().hash(&mut hasher);
+    request
+        .headers()
+        .get(header::ACCEPT)
+        .map(|accept| accept.as_bytes())
+        .hash(&mut hasher);
+    format!("W/\"{:016x}\"", hasher.finish())
+}
+
+/// Whether an `If-None-Match` value lists `etag`, compared weakly.
+fn matches_etag(if_none_match: &str, etag: &str) -> bool {
+    let opaque = |tag: &str| tag.trim().trim_start_matches("W/").to_string();
+    if_none_match
+        .split(',')
+        .any(|candidate| candidate.trim() == "*" || opaque(candidate) == opaque(etag))
+}
+
+/// Adds `ETag`, `Last-Modified` and `Cache-Control` to GET responses from
+/// the data routes and answers conditional requests for unchanged data with
+/// 304 Not Modified, without running the handler.
+pub async fn cache_headers(
+    State(state): State<AppState>,
+    request: Request,
+    next: Next,
+) -> Response {
+    if request.method() != Method::GET || !cacheable(request.uri().path()) {
+        return next.run(request).await;
+    }
+
+    // Every reload, coordinate override and moderation change rebuilds the
+    // index, so its
+    // revision and build time date the data.
+    let index = state.location_index();
+    let etag = etag(&state, index.revision, &request);
+    let last_modified = index.built_at;
+
+    let not_modified = match request.headers().get(header::IF_NONE_MATCH) {
+        Some(value) => value.to_str().is_ok_and(|value| matches_etag(value, &etag)),
+        // If-Modified-Since only counts without If-None-Match (RFC 9110).
+        None => request
+            .headers()
+            .get(header::IF_MODIFIED_SINCE)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| chrono::DateTime::parse_from_rfc2822(value).ok())
+            .is_some_and(|since| last_modified.timestamp() <= since.timestamp()),
+    };
+
+    let mut response = if not_modified {
+        StatusCode::NOT_MODIFIED.into_response()
+    } else {
+        next.run(request).await
+    };
+    if !(response.status().is_success() || response.status() == StatusCode::NOT_MODIFIED) {
+        return response;
+    }
+
+    let headers = response.headers_mut();
+    if let Ok(value) = HeaderValue::from_str(&etag) {
+        headers.insert(header::ETAG, value);
+    }
+    if let Ok(value) = HeaderValue::from_str(
+        &last_modified
+            .format("%a, %d %b %Y %H:%M:%S GMT")
+            .to_string(),
+    ) {
+        headers.insert(header::LAST_MODIFIED, value);
+    }
+    headers.insert(
+        header::CACHE_CONTROL,
+        HeaderValue::from_static(CACHE_CONTROL),
+    );
+    headers
+        .entry(header::VARY)
+        .or_insert(HeaderValue::from_static("accept"));
+    response
+}
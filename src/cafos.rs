@@ -0,0 +1,255 @@
+// Until Every Cage is Empty
+// Copyright (C) 2025 Eli Perez
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+// Contact the developer directly at untileverycageproject@protonmail.com
+//! Concentrated animal feeding operations: dairies, feedlots and hog and
+//! poultry barns large enough to need a Clean Water Act (NPDES) permit. Each
+//! country's permits are CSV files in `<country>/cafos/`, one per source,
+//! written by the CAFO importer.
+
+use axum::extract::{Query, State};
+use axum::http::{HeaderMap, StatusCode};
+use axum::response::IntoResponse;
+use serde::{Deserialize, Serialize};
+
+use crate::activities::Activity;
+use crate::geocode;
+use crate::ids;
+use crate::paging::Page;
+use crate::schema;
+use crate::species::{self, Taxon};
+use crate::store::Dataset;
+use crate::{AppState, geojson};
+
+/// A row of a CAFO CSV. Animal counts are the permitted or reported
+/// maximums, left empty where the permit doesn't disclose them.
+#[derive(Serialize, Deserialize, Debug, Default, Clone)]
+#[serde(default)]
+pub struct CafoPermit {
+    /// The NPDES or state permit number.
+    pub permit_id: String,
+    pub name: String,
+    /// The dataset the row came from, e.g. "EPA ICIS-NPDES".
+    pub source: String,
+    /// As the source words it: "Effective", "Expired", "Administratively
+    /// continued"...
+    pub permit_status: String,
+    pub street: String,
+    pub city: String,
+    pub county: String,
+    pub state: String,
+    pub zip: String,
+    /// Animal types as the permit names them, separated by "; ".
+    pub animal_types: String,
+    pub dairy_cattle: Option<u64>,
+    pub beef_cattle: Option<u64>,
+    pub swine: Option<u64>,
+    pub chickens: Option<u64>,
+    pub turkeys: Option<u64>,
+    /// Animals of other types.
+    pub other_animals: Option<u64>,
+    /// EPA animal units, weighting each type by size.
+    pub animal_units: Option<u64>,
+    pub latitude: f64,
+    pub longitude: f64,
+}
+
+impl CafoPermit {
+    /// The counts with the taxon each stands for.
+    fn counts(&self) -> [(Option<u64>, &'static str); 5] {
+        [
+            (self.dairy_cattle, "dairy_cow"),
+            (self.beef_cattle, "cattle"),
+            (self.swine, "pig"),
+            (self.chickens, "chicken"),
+            (self.turkeys, "turkey"),
+        ]
+    }
+
+    /// Animals counted across every type, when the permit gives any count.
+    pub fn total_animals(&self) -> Option<u64> {
+        self.counts()
+            .into_iter()
+            .map(|(count, _)| count)
+            .chain([self.other_animals])
+            .flatten()
+            .reduce(|total, count| total + count)
+    }
+}
+
+/// A permit as served, with the species and activities worked out.
+#[derive(Serialize, Debug, Clone)]
+pub(crate) struct CafoFacility {
+    /// The permit's facility ID (see `ids`).
+    pub(crate) facility_id: String,
+    pub(crate) country: String,
+    #[serde(flatten)]
+    pub(crate) permit: CafoPermit,
+    pub(crate) total_animals: Option<u64>,
+    /// Taxa from the ontology, from the animal types and counts.
+    pub(crate) taxa: Vec<&'static str>,
+    /// Species group IDs from the ontology (`cattle`, `pig`...).
+    pub(crate) species_ids: Vec<&'static str>,
+    /// `cafo`, with the kind of farm where the animals tell it.
+    pub(crate) activities: Vec<Activity>,
+}
+
+impl geojson::Feature for CafoFacility {}
+
+impl CafoFacility {
+    fn new(country: &str, permit: CafoPermit) -> Self {
+        let mut taxa: Vec<&'static str> = species::mentioned_in(&permit.animal_types)
+            .iter()
+            .map(|taxon| taxon.id)
+            .collect();
+        // "Dairy cattle" names cattle as well.
+        if taxa.contains(&"dairy_cow") {
+            taxa.retain(|id| *id != "cattle");
+        }
+        for (count, id) in permit.counts() {
+            if count.is_some_and(|count| count > 0)
+                && !taxa.iter().any(|known| species::is_within(known, id))
+            {
+                taxa.push(id);
+            }
+        }
+
+        let mut activities = vec![Activity::Cafo];
+        let has = |ancestor: &str| taxa.iter().any(|id| species::is_within(id, ancestor));
+        if has("dairy_cow") {
+            activities.push(Activity::DairyFarm);
+        }
+        if has("pig") {
+            activities.push(Activity::PigFarm);
+        }
+        if has("poultry") {
+            activities.push(Activity::PoultryFarm);
+        }
+
+        CafoFacility {
+            facility_id: ids::facility_id("cafos", country, &permit.permit_id),
+            country: geocode::country_code(country),
+            total_animals: permit.total_animals(),
+            species_ids: species::groups(taxa.iter().copied()),
+            taxa,
+            permit,
+            activities,
+        }
+    }
+}
+
+/// Every country's CAFO permits.
+pub(crate) fn read_cafos(
+    dataset: &Dataset,
+) -> Result<Vec<CafoFacility>, Box<dyn std::error::Error>> {
+    let mut facilities = Vec::new();
+    for country in dataset.countries() {
+        for (name, contents) in dataset.files_in(&format!("{}/cafos", country)) {
+            if !name.ends_with(".csv") {
+                continue;
+            }
+            schema::check::<CafoPermit>(&format!("{}/cafos/{}", country, name), contents)?;
+            let mut reader = csv::Reader::from_reader(contents);
+            for result in reader.deserialize() {
+                let permit: CafoPermit =
+                    result.map_err(|e| format!("{}/cafos/{}: {}", country, name, e))?;
+                facilities.push(CafoFacility::new(country, permit));
+            }
+        }
+    }
+    Ok(facilities)
+}
+
+#[derive(Deserialize)]
+pub struct CafoParams {
+    country_code: Option<String>,
+    state: Option<String>,
+    /// Comma-separated species, e.g. `dairy_cow,pig` or `poultry`.
+    species: Option<String>,
+    /// Only permits counting at least this many animals.
+    min_animals: Option<u64>,
+    format: Option<String>,
+    limit: Option<usize>,
+    offset: Option<usize>,
+    fields: Option<String>,
+}
+
+/// GET /api/cafos: permitted dairies, feedlots and hog and poultry
+/// operations.
+pub async fn get_cafos_handler(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Query(params): Query<CafoParams>,
+) -> impl IntoResponse {
+    let parse = || -> Result<(Vec<&'static Taxon>, Page), String> {
+        let species = params
+            .species
+            .as_deref()
+            .unwrap_or_default()
+            .split(',')
+            .map(str::trim)
+            .filter(|term| !term.is_empty())
+            .map(|term| species::lookup(term).ok_or_else(|| format!("Unknown species {}", term)))
+            .collect::<Result<_, _>>()?;
+        let page = Page::parse(params.limit, params.offset, params.fields.as_deref())?;
+        Ok((species, page))
+    };
+    let (species, page) = match parse() {
+        Ok(parsed) => parsed,
+        Err(e) => return (StatusCode::BAD_REQUEST, e).into_response(),
+    };
+
+    let reports = state.reports();
+    if species.is_empty()
+        && params.min_animals.is_none()
+        && params.country_code.is_none()
+        && params.state.is_none()
+    {
+        return geojson::respond_all(&reports.cafos, &headers, params.format.as_deref(), &page);
+    }
+
+    let selected: Vec<&CafoFacility> = reports
+        .cafos
+        .iter()
+        .filter(|facility| {
+            params
+                .country_code
+                .as_ref()
+                .is_none_or(|country| facility.country == geocode::country_code(country))
+        })
+        .filter(|facility| {
+            params
+                .state
+                .as_ref()
+                .is_none_or(|state| facility.permit.state.eq_ignore_ascii_case(state))
+        })
+        .filter(|facility| {
+            params
+                .min_animals
+                .is_none_or(|min| facility.total_animals.is_some_and(|total| total >= min))
+        })
+        .filter(|facility| {
+            species.is_empty()
+                || species.iter().any(|wanted| {
+                    facility
+                        .taxa
+                        .iter()
+                        .any(|id| species::is_within(id, wanted.id))
+                })
+        })
+        .collect();
+    geojson::respond(&selected, &headers, params.format.as_deref(), &page)
+}
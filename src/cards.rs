@@ -0,0 +1,229 @@
+// Until Every Cage is Empty
+// Copyright (C) 2025 Eli Perez
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+// Contact the developer directly at untileverycageproject@protonmail.com
+//! Sharing: GET /sitemap.xml for search engines and
+//! /api/locations/{establishment_id}/card, the Open Graph title, description
+//! and facts the frontend puts on a location's social card.
+//!
+//! Both link to the map as the frontend's own share links do, centered on
+//! the location and searching for its name. `SITE_URL` sets where the
+//! frontend is served (https://untileverycage.org by default).
+
+use axum::Json;
+use axum::extract::{Path, Query, State};
+use axum::http::header;
+use axum::response::IntoResponse;
+use serde::Serialize;
+
+use crate::details::{self, DetailParams};
+use crate::geocode::{address_query, country_name};
+use crate::settings::Settings;
+use crate::species;
+use crate::volume;
+use crate::{AppState, LocationResponse};
+
+const DEFAULT_SITE_URL: &str = "https://untileverycage.org";
+
+const SITE_NAME: &str = "Until Every Cage is Empty";
+
+/// Zoom of the map a location's link opens.
+const LOCATION_ZOOM: u8 = 16;
+
+/// The frontend's pages besides the map.
+const PAGES: &[&str] = &["about", "contribute", "howtouse"];
+
+/// URLs one sitemap may list (sitemaps.org). Locations past it are left out.
+const MAX_SITEMAP_URLS: usize = 50_000;
+
+fn site_url(settings: &Settings) -> String {
+    settings
+        .get("SITE_URL")
+        .filter(|url| !url.trim().is_empty())
+        .unwrap_or(DEFAULT_SITE_URL)
+        .trim()
+        .trim_end_matches('/')
+        .to_string()
+}
+
+/// The map centered on a location and searching for its name.
+fn location_url(site: &str, loc: &LocationResponse) -> String {
+    let params = [
+        ("lat", format!("{:.5}", loc.latitude)),
+        ("lng", format!("{:.5}", loc.longitude)),
+        ("zoom", LOCATION_ZOOM.to_string()),
+        ("search", loc.establishment_name.clone()),
+    ];
+    match reqwest::Url::parse_with_params(&format!("{}/", site), &params) {
+        Ok(url) => url.to_string(),
+        Err(_) => format!("{}/", site),
+    }
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// GET /sitemap.xml: the frontend's pages and a link to every location.
+pub async fn get_sitemap_handler(State(state): State<AppState>) -> impl IntoResponse {
+    let site = site_url(&state.settings);
+    let index = state.location_index();
+    let lastmod = index.built_at.format("%Y-%m-%d").to_string();
+
+    let mut xml = String::from(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+         <urlset xmlns=\"http://www.sitemaps.org/schemas/sitemap/0.9\">\n",
+    );
+    let pages = std::iter::once(format!("{}/", site))
+        .chain(PAGES.iter().map(|page| format!("{}/{}", site, page)));
+    let locations = index
+        .locations
+        .iter()
+        .filter(|loc| !(loc.latitude == 0.0 && loc.longitude == 0.0))
+        .map(|loc| location_url(&site, loc));
+    for url in pages.chain(locations).take(MAX_SITEMAP_URLS) {
+        xml.push_str(&format!(
+            "  <url><loc>{}</loc><lastmod>{}</lastmod></url>\n",
+            escape(&url),
+            lastmod
+        ));
+    }
+    xml.push_str("</urlset>\n");
+    ([(header::CONTENT_TYPE, "application/xml")], xml)
+}
+
+/// What a social card shows for a location.
+#[derive(Serialize)]
+struct Card {
+    /// `og:title`.
+    title: String,
+    /// `og:description`.
+    description: String,
+    /// `og:url`: the map at the location.
+    url: String,
+    /// `og:site_name`.
+    site_name: &'static str,
+    /// The facts the title and description are written from.
+    name: String,
+    city: String,
+    state: String,
+    country: String,
+    /// Names of the species slaughtered, or handled where the source doesn't
+    /// say which are slaughtered.
+    species: Vec<&'static str>,
+    estimated_annual_slaughter_min: Option<u64>,
+    estimated_annual_slaughter_max: Option<u64>,
+}
+
+/// A count with thousands separators ("1,000,000").
+fn thousands(number: u64) -> String {
+    let digits = number.to_string();
+    let mut out = String::new();
+    for (i, digit) in digits.chars().enumerate() {
+        if i > 0 && (digits.len() - i).is_multiple_of(3) {
+            out.push(',');
+        }
+        out.push(digit);
+    }
+    out
+}
+
+/// "Pigs, Cattle and Sheep & Lambs".
+fn list(names: &[&str]) -> String {
+    match names {
+        [] => String::new(),
+        [only] => only.to_string(),
+        [rest @ .., last] => format!("{} and {}", rest.join(", "), last),
+    }
+}
+
+impl Card {
+    fn new(site: &str, loc: &LocationResponse) -> Self {
+        let country = country_name(&loc.country).to_string();
+        let place = address_query(&[&loc.city, &loc.state, &country]);
+        let slaughtered = !loc.species_slaughtered.is_empty();
+        let ids = if slaughtered {
+            &loc.species_slaughtered
+        } else {
+            &loc.species
+        };
+        let species: Vec<&'static str> = ids
+            .iter()
+            .filter_map(|id| species::taxon(id))
+            .map(|taxon| taxon.name)
+            .collect();
+        let estimate = volume::annual_slaughter(&loc.slaughter_volume_category);
+
+        let kind = if loc.slaughter {
+            "Slaughterhouse"
+        } else {
+            "Processing plant"
+        };
+        let mut description = format!("{} in {}.", kind, place);
+        if !species.is_empty() {
+            let verb = if slaughtered { "Slaughters" } else { "Handles" };
+            description.push_str(&format!(" {} {}.", verb, list(&species)));
+        }
+        if let Some(estimate) = estimate {
+            let range = if estimate.min == 0 {
+                format!("Up to {}", thousands(estimate.max))
+            } else if estimate.min >= 1_000_000 {
+                format!("Over {}", thousands(estimate.min))
+            } else {
+                format!("{} to {}", thousands(estimate.min), thousands(estimate.max))
+            };
+            description.push_str(&format!(" {} animals killed a year.", range));
+        }
+        let title = match loc.city.trim() {
+            "" => loc.establishment_name.clone(),
+            city => format!("{}, {}", loc.establishment_name, city),
+        };
+
+        Card {
+            title,
+            description,
+            url: location_url(site, loc),
+            site_name: SITE_NAME,
+            name: loc.establishment_name.clone(),
+            city: loc.city.clone(),
+            state: loc.state.clone(),
+            country,
+            species,
+            estimated_annual_slaughter_min: estimate.map(|estimate| estimate.min),
+            estimated_annual_slaughter_max: estimate.map(|estimate| estimate.max),
+        }
+    }
+}
+
+/// GET /api/locations/{establishment_id}/card
+pub async fn get_location_card_handler(
+    State(state): State<AppState>,
+    Path(establishment_id): Path<String>,
+    Query(params): Query<DetailParams>,
+) -> impl IntoResponse {
+    let index = state.location_index();
+    match details::find_location(
+        &index.locations,
+        &establishment_id,
+        params.country_code.as_deref(),
+    ) {
+        Ok(location) => Json(Card::new(&site_url(&state.settings), location)).into_response(),
+        Err(e) => e.into_response(),
+    }
+}
@@ -0,0 +1,157 @@
+// Until Every Cage is Empty
+// Copyright (C) 2025 Eli Perez
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+// Contact the developer directly at untileverycageproject@protonmail.com
+use std::collections::BTreeSet;
+use std::error::Error;
+
+use axum::extract::State;
+use axum::http::{HeaderValue, header};
+use axum::response::IntoResponse;
+use serde::Serialize;
+
+use crate::AppState;
+use crate::diff::diff_locations;
+use crate::errors::ApiError;
+use crate::location::Location;
+use crate::snapshots::{load_snapshots, snapshot_dates};
+use crate::store::Dataset;
+
+// Keeps entries readable when a refresh touches thousands of rows.
+const MAX_NOTABLE_CHANGES: usize = 10;
+
+#[derive(Serialize, Debug, Clone)]
+pub struct ChangelogEntry {
+    pub dataset: String,
+    pub source: String,
+    pub date: String,
+    pub previous_date: String,
+    pub records_added: usize,
+    pub records_removed: usize,
+    pub records_modified: usize,
+    pub notable_changes: Vec<String>,
+}
+
+fn source_name(country: &str) -> &'static str {
+    match country {
+        "us" => "USDA FSIS Meat, Poultry and Egg Product Inspection Directory",
+        "de" => "BVL approved establishments list",
+        "uk" => "FSA approved food establishments",
+        "es" => "Spanish regional livestock registries",
+        "fr" => "French approved establishments",
+        "dk" => "Fødevarestyrelsen smiley register",
+        "it" => "Ministero della Salute approved establishments",
+        "au" => "DAFF export registered establishments",
+        "br" => "MAPA SIF registered establishments",
+        _ => "Unknown source",
+    }
+}
+
+fn is_slaughterhouse(loc: &Location) -> bool {
+    loc.slaughter || loc.activities.to_lowercase().contains("slaughter")
+}
+
+fn describe(loc: &Location) -> String {
+    let place = [loc.city.as_str(), loc.state.as_str()]
+        .iter()
+        .filter(|part| !part.is_empty())
+        .copied()
+        .collect::<Vec<_>>()
+        .join(", ");
+    if place.is_empty() {
+        loc.establishment_name.clone()
+    } else {
+        format!("{} ({})", loc.establishment_name, place)
+    }
+}
+
+/// One entry per refresh, i.e. per consecutive pair of snapshots, newest first.
+pub fn build_changelog(dataset: &Dataset) -> Result<Vec<ChangelogEntry>, Box<dyn Error>> {
+    let countries: BTreeSet<String> = snapshot_dates(dataset)
+        .into_iter()
+        .map(|(country, _)| country)
+        .collect();
+
+    let mut entries = Vec::new();
+    for country in countries {
+        let snapshots = load_snapshots(dataset, &country)?;
+        for pair in snapshots.windows(2) {
+            let (previous, current) = (&pair[0], &pair[1]);
+            let diff = diff_locations(&previous.locations, &current.locations);
+
+            let mut notable_changes = Vec::new();
+            for loc in diff.added.iter().filter(|loc| is_slaughterhouse(loc)) {
+                notable_changes.push(format!("New slaughterhouse: {}", describe(loc)));
+            }
+            for loc in diff.removed.iter().filter(|loc| is_slaughterhouse(loc)) {
+                notable_changes.push(format!(
+                    "Slaughterhouse no longer listed: {}",
+                    describe(loc)
+                ));
+            }
+            for change in &diff.modified {
+                if change.changed_fields.contains(&"slaughter_volume_category") {
+                    notable_changes.push(format!(
+                        "Slaughter volume category changed from {} to {}: {}",
+                        or_none(&change.old.slaughter_volume_category),
+                        or_none(&change.new.slaughter_volume_category),
+                        describe(change.new)
+                    ));
+                }
+            }
+            let omitted = notable_changes.len().saturating_sub(MAX_NOTABLE_CHANGES);
+            notable_changes.truncate(MAX_NOTABLE_CHANGES);
+            if omitted > 0 {
+                notable_changes.push(format!("...and {} more", omitted));
+            }
+
+            entries.push(ChangelogEntry {
+                dataset: format!("{}-locations", current.country),
+                source: source_name(&current.country).to_string(),
+                date: current.date.clone(),
+                previous_date: previous.date.clone(),
+                records_added: diff.added.len(),
+                records_removed: diff.removed.len(),
+                records_modified: diff.modified.len(),
+                notable_changes,
+            });
+        }
+    }
+
+    entries.sort_by(|a, b| b.date.cmp(&a.date).then(a.dataset.cmp(&b.dataset)));
+    Ok(entries)
+}
+
+fn or_none(value: &str) -> &str {
+    if value.is_empty() { "none" } else { value }
+}
+
+/// GET /api/changelog, as built on the last load.
+pub async fn get_changelog_handler(State(state): State<AppState>) -> impl IntoResponse {
+    match state.reports().changelog.json() {
+        Ok(json) => (
+            [(
+                header::CONTENT_TYPE,
+                HeaderValue::from_static("application/json"),
+            )],
+            json,
+        )
+            .into_response(),
+        Err(e) => {
+            ApiError::internal(format!("Failed to serialize changelog: {}", e)).into_response()
+        }
+    }
+}
@@ -0,0 +1,180 @@
+// Until Every Cage is Empty
+// Copyright (C) 2025 Eli Perez
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+// Contact the developer directly at untileverycageproject@protonmail.com
+//! What changed in the establishment lists since a date: the snapshot in
+//! force then (see `snapshots`) against the list served now.
+
+use axum::extract::{Query, State};
+use axum::{Json, http::StatusCode, response::IntoResponse};
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+
+use crate::AppState;
+use crate::diff::{diff_locations, tracked_fields};
+use crate::errors::ApiError;
+use crate::geocode;
+use crate::location::Location;
+use crate::snapshots::{load_current, load_snapshot, snapshot_dates};
+
+/// The fields of an added or removed establishment worth showing.
+#[derive(Serialize)]
+struct Establishment<'a> {
+    establishment_id: &'a str,
+    establishment_name: &'a str,
+    city: &'a str,
+    state: &'a str,
+    #[serde(rename = "type")]
+    activities: &'a str,
+    grant_date: Option<NaiveDate>,
+}
+
+impl<'a> From<&'a Location> for Establishment<'a> {
+    fn from(loc: &'a Location) -> Self {
+        Establishment {
+            establishment_id: &loc.establishment_id,
+            establishment_name: &loc.establishment_name,
+            city: &loc.city,
+            state: &loc.state,
+            activities: &loc.activities,
+            grant_date: loc.grant_date,
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct FieldChange {
+    field: &'static str,
+    before: String,
+    after: String,
+}
+
+#[derive(Serialize)]
+struct Modification<'a> {
+    #[serde(flatten)]
+    establishment: Establishment<'a>,
+    changes: Vec<FieldChange>,
+}
+
+#[derive(Serialize)]
+struct CountryChanges<'a> {
+    country: String,
+    /// Date of the snapshot compared against: the country's latest on or
+    /// before `since`.
+    from: String,
+    /// Listed now and not then.
+    added: Vec<Establishment<'a>>,
+    /// Listed then and not now: closed, or no longer holding a grant.
+    removed: Vec<Establishment<'a>>,
+    modified: Vec<Modification<'a>>,
+}
+
+#[derive(Deserialize)]
+pub struct ChangesParams {
+    /// `YYYY-MM-DD`.
+    since: String,
+    country_code: Option<String>,
+}
+
+/// GET /api/locations/changes?since=DATE: establishments added, removed and
+/// modified since DATE, per country with a snapshot from on or before DATE.
+/// 404 when no country has one.
+pub async fn get_location_changes_handler(
+    State(state): State<AppState>,
+    Query(params): Query<ChangesParams>,
+) -> impl IntoResponse {
+    if NaiveDate::parse_from_str(&params.since, "%Y-%m-%d").is_err() {
+        return (
+            StatusCode::BAD_REQUEST,
+            format!("since must be a date like 2025-01-31, not {}", params.since),
+        )
+            .into_response();
+    }
+
+    let dataset = state.store.current();
+    let wanted = params.country_code.as_deref().map(geocode::country_dir);
+    let dates: Vec<(String, String)> = snapshot_dates(&dataset)
+        .into_iter()
+        .filter(|(country, _)| wanted.as_ref().is_none_or(|wanted| wanted == country))
+        .collect();
+    let mut baselines: Vec<(String, String)> = Vec::new();
+    for (country, date) in dates.iter().filter(|(_, date)| *date <= params.since) {
+        // Dates are sorted, so this keeps the last one on or before `since`.
+        match baselines.iter_mut().find(|(known, _)| known == country) {
+            Some(baseline) => baseline.1 = date.clone(),
+            None => baselines.push((country.clone(), date.clone())),
+        }
+    }
+    if baselines.is_empty() {
+        let message = match dates.iter().map(|(_, date)| date).min() {
+            Some(earliest) => format!(
+                "No snapshot from on or before {} to compare against, the earliest is from {}",
+                params.since, earliest
+            ),
+            None => "No snapshots to compare against".to_string(),
+        };
+        return (StatusCode::NOT_FOUND, message).into_response();
+    }
+
+    let mut versions = Vec::new();
+    for (country, date) in baselines {
+        let loaded = load_snapshot(&dataset, &country, &date)
+            .and_then(|snapshot| Ok((snapshot, load_current(&dataset, &country)?)));
+        match loaded {
+            Ok((Some(snapshot), current)) => versions.push((snapshot, current)),
+            Ok((None, _)) => {}
+            Err(e) => {
+                return ApiError::internal(format!(
+                    "Failed to read {} snapshot {}: {}",
+                    country, date, e
+                ))
+                .into_response();
+            }
+        }
+    }
+
+    let changes: Vec<CountryChanges> = versions
+        .iter()
+        .map(|(snapshot, current)| {
+            let diff = diff_locations(&snapshot.locations, current);
+            let modified = diff
+                .modified
+                .iter()
+                .map(|change| Modification {
+                    establishment: change.new.into(),
+                    changes: tracked_fields(change.old)
+                        .into_iter()
+                        .zip(tracked_fields(change.new))
+                        .filter(|((field, _), _)| change.changed_fields.contains(field))
+                        .map(|((field, before), (_, after))| FieldChange {
+                            field,
+                            before,
+                            after,
+                        })
+                        .collect(),
+                })
+                .collect();
+            CountryChanges {
+                country: geocode::country_code(&snapshot.country),
+                from: snapshot.date.clone(),
+                added: diff.added.into_iter().map(Establishment::from).collect(),
+                removed: diff.removed.into_iter().map(Establishment::from).collect(),
+                modified,
+            }
+        })
+        .collect();
+    Json(changes).into_response()
+}
@@ -0,0 +1,308 @@
+// Until Every Cage is Empty
+// Copyright (C) 2025 Eli Perez
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+// Contact the developer directly at untileverycageproject@protonmail.com
+//! GET /api/choropleth?by=state|county: counts per region, keyed by the
+//! identifiers boundary files use, for shading regions instead of drawing
+//! points.
+//!
+//! US states are keyed by their two-digit FIPS code and counties by the
+//! five-digit one, as in the Census Bureau's cartographic boundary files.
+//! Regions of other countries are keyed `<country>:<state>`, with the state
+//! as the dataset spells it.
+
+use std::collections::{BTreeMap, HashMap};
+
+use axum::Json;
+use axum::extract::{Query, State};
+use axum::http::StatusCode;
+use axum::response::IntoResponse;
+use serde::{Deserialize, Serialize};
+
+use crate::AppState;
+use crate::filter::LocationFilter;
+use crate::geocode;
+use crate::location::animal_counts;
+
+/// Postal abbreviation and FIPS code of each US state and territory.
+const US_STATES: &[(&str, &str)] = &[
+    ("AL", "01"),
+    ("AK", "02"),
+    ("AZ", "04"),
+    ("AR", "05"),
+    ("CA", "06"),
+    ("CO", "08"),
+    ("CT", "09"),
+    ("DE", "10"),
+    ("DC", "11"),
+    ("FL", "12"),
+    ("GA", "13"),
+    ("HI", "15"),
+    ("ID", "16"),
+    ("IL", "17"),
+    ("IN", "18"),
+    ("IA", "19"),
+    ("KS", "20"),
+    ("KY", "21"),
+    ("LA", "22"),
+    ("ME", "23"),
+    ("MD", "24"),
+    ("MA", "25"),
+    ("MI", "26"),
+    ("MN", "27"),
+    ("MS", "28"),
+    ("MO", "29"),
+    ("MT", "30"),
+    ("NE", "31"),
+    ("NV", "32"),
+    ("NH", "33"),
+    ("NJ", "34"),
+    ("NM", "35"),
+    ("NY", "36"),
+    ("NC", "37"),
+    ("ND", "38"),
+    ("OH", "39"),
+    ("OK", "40"),
+    ("OR", "41"),
+    ("PA", "42"),
+    ("RI", "44"),
+    ("SC", "45"),
+    ("SD", "46"),
+    ("TN", "47"),
+    ("TX", "48"),
+    ("UT", "49"),
+    ("VT", "50"),
+    ("VA", "51"),
+    ("WA", "53"),
+    ("WV", "54"),
+    ("WI", "55"),
+    ("WY", "56"),
+    ("AS", "60"),
+    ("GU", "66"),
+    ("MP", "69"),
+    ("PR", "72"),
+    ("VI", "78"),
+];
+
+fn us_state_fips(state: &str) -> Option<&'static str> {
+    let state = state.trim();
+    US_STATES
+        .iter()
+        .find(|(postal, _)| postal.eq_ignore_ascii_case(state))
+        .map(|(_, fips)| *fips)
+}
+
+/// A county FIPS code as the FSIS directory writes it ("6037.0") in its
+/// five-digit form ("06037"). Empty if it isn't one.
+pub(crate) fn normalize_fips(value: &str) -> String {
+    let digits = value.trim().trim_end_matches(".0");
+    match digits.parse::<u32>() {
+        Ok(code) if (1000..100_000).contains(&code) => format!("{:05}", code),
+        _ => String::new(),
+    }
+}
+
+/// A county name without its kind, for matching "Cache" to "Cache County".
+fn county_key(state: &str, county: &str) -> (String, String) {
+    let mut name = county.trim().to_lowercase();
+    for suffix in [
+        " county",
+        " parish",
+        " borough",
+        " census area",
+        " municipality",
+    ] {
+        if let Some(stripped) = name.strip_suffix(suffix) {
+            name = stripped.to_string();
+        }
+    }
+    (state.trim().to_uppercase(), name)
+}
+
+#[derive(Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+enum Level {
+    State,
+    County,
+}
+
+#[derive(Deserialize)]
+pub struct ChoroplethParams {
+    by: Option<Level>,
+    country_code: Option<String>,
+    species: Option<String>,
+    activity: Option<String>,
+    status: Option<String>,
+}
+
+#[derive(Serialize, Default)]
+struct Region {
+    /// The boundary identifier (see the module documentation).
+    id: String,
+    country: String,
+    state: String,
+    #[serde(skip_serializing_if = "String::is_empty")]
+    county: String,
+    /// Locations matching the filters.
+    locations: usize,
+    /// APHIS research facilities.
+    aphis_reports: usize,
+    /// Animals the APHIS research facilities reported using.
+    aphis_animals: f64,
+}
+
+#[derive(Serialize, Default)]
+struct Unassigned {
+    locations: usize,
+    aphis_reports: usize,
+    aphis_animals: f64,
+}
+
+#[derive(Serialize)]
+struct Choropleth {
+    regions: Vec<Region>,
+    /// Records without a state, or without a county FIPS code.
+    unassigned: Unassigned,
+}
+
+/// GET /api/choropleth
+///
+/// `species`, `activity` and `status` filter the locations as on
+/// /api/locations. County totals cover the US only; APHIS facilities are
+/// placed in a county by matching its name to a location's FIPS code, so
+/// those in counties without a location are unassigned.
+pub async fn get_choropleth_handler(
+    State(state): State<AppState>,
+    Query(params): Query<ChoroplethParams>,
+) -> impl IntoResponse {
+    let level = params.by.unwrap_or(Level::State);
+    let filter = match LocationFilter::parse(
+        None,
+        params.species.as_deref(),
+        params.activity.as_deref(),
+        params.status.as_deref(),
+    ) {
+        Ok(filter) => filter,
+        Err(e) => return (StatusCode::BAD_REQUEST, e).into_response(),
+    };
+    let country_code = params.country_code.as_deref().map(geocode::country_code);
+    let country_code = match level {
+        Level::County => match country_code.as_deref() {
+            None | Some("us") => Some("us"),
+            Some(other) => {
+                return (
+                    StatusCode::BAD_REQUEST,
+                    format!("County totals are only available for us, not {}", other),
+                )
+                    .into_response();
+            }
+        },
+        Level::State => country_code.as_deref(),
+    };
+
+    let index = state.location_index();
+    let mut regions: BTreeMap<String, Region> = BTreeMap::new();
+    let mut unassigned = Unassigned::default();
+    // Counties of the US as FIPS codes, for placing the APHIS facilities.
+    let mut counties: HashMap<(String, String), String> = HashMap::new();
+
+    for loc in index.locations.iter() {
+        if country_code.is_some_and(|country| loc.country != country) {
+            continue;
+        }
+        if loc.country == "us" && !loc.fips_code.is_empty() {
+            counties
+                .entry(county_key(&loc.state, &loc.county))
+                .or_insert_with(|| loc.fips_code.clone());
+        }
+        if !filter.matches(loc) {
+            continue;
+        }
+        let region = match level {
+            Level::State => state_region(&loc.country, &loc.state),
+            Level::County if loc.fips_code.is_empty() => None,
+            Level::County => Some(Region {
+                id: loc.fips_code.clone(),
+                country: loc.country.clone(),
+                state: loc.state.trim().to_string(),
+                county: loc.county.trim().to_string(),
+                ..Default::default()
+            }),
+        };
+        match region {
+            Some(region) => regions.entry(region.id.clone()).or_insert(region).locations += 1,
+            None => unassigned.locations += 1,
+        }
+    }
+
+    // The location filters don't apply to APHIS facilities, so with any of
+    // them only locations are counted.
+    let reports = state.reports();
+    if country_code.is_none_or(|country| country == "us") && filter.is_empty() {
+        for report in reports.aphis.iter() {
+            let animals: f64 = animal_counts(report)
+                .into_iter()
+                .map(|(_, count)| f64::from(count))
+                .sum();
+            let region = match level {
+                Level::State => state_region("us", &report.state),
+                Level::County => counties
+                    .get(&county_key(&report.state, &report.county))
+                    .map(|fips| Region {
+                        id: fips.clone(),
+                        country: "us".to_string(),
+                        state: report.state.clone(),
+                        county: report.county.trim().to_string(),
+                        ..Default::default()
+                    }),
+            };
+            match region {
+                Some(region) => {
+                    let region = regions.entry(region.id.clone()).or_insert(region);
+                    region.aphis_reports += 1;
+                    region.aphis_animals += animals;
+                }
+                None => {
+                    unassigned.aphis_reports += 1;
+                    unassigned.aphis_animals += animals;
+                }
+            }
+        }
+    }
+
+    Json(Choropleth {
+        regions: regions.into_values().collect(),
+        unassigned,
+    })
+    .into_response()
+}
+
+fn state_region(country: &str, state: &str) -> Option<Region> {
+    let state = state.trim();
+    if state.is_empty() {
+        return None;
+    }
+    let id = match (country, us_state_fips(state)) {
+        ("us", Some(fips)) => fips.to_string(),
+        _ => format!("{}:{}", country, state),
+    };
+    Some(Region {
+        id,
+        country: country.to_string(),
+        state: state.to_string(),
+        ..Default::default()
+    })
+}
@@ -0,0 +1,325 @@
+// Until Every Cage is Empty
+// Copyright (C) 2025 Eli Perez
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+// Contact the developer directly at untileverycageproject@protonmail.com
+//! Animal Welfare Act citations from APHIS inspection reports, one CSV row per
+//! citation in `us/citations/` (one file per release), written by the
+//! us-aphis-citations importer. An inspection that found nothing appears as a
+//! row without a `kind`, so clean inspections count towards the history too.
+
+use std::collections::BTreeMap;
+
+use axum::Json;
+use axum::extract::{Query, State};
+use axum::http::StatusCode;
+use axum::response::IntoResponse;
+use serde::{Deserialize, Serialize};
+
+use crate::AppState;
+use crate::schema;
+use crate::store::Dataset;
+
+/// How serious APHIS judged a noncompliance.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum CitationKind {
+    /// Noncompliance with a serious or immediate effect on animal health.
+    Critical,
+    /// Noncompliance that is hurting an animal at the time of inspection.
+    Direct,
+    NonCritical,
+    /// A minor issue fixed on the spot, noted but not cited.
+    TeachableMoment,
+}
+
+impl CitationKind {
+    /// "Critical", "Direct NCI", "Non-Critical", "Teachable Moment" and the
+    /// like, as the reports and their re-publications spell them.
+    pub fn parse(value: &str) -> Option<CitationKind> {
+        let value: String = value
+            .to_lowercase()
+            .chars()
+            .filter(char::is_ascii_alphabetic)
+            .collect();
+        match value.as_str() {
+            "" => None,
+            v if v.starts_with("non") => Some(CitationKind::NonCritical),
+            v if v.starts_with("critical") => Some(CitationKind::Critical),
+            v if v.starts_with("direct") => Some(CitationKind::Direct),
+            v if v.starts_with("teachable") => Some(CitationKind::TeachableMoment),
+            _ => None,
+        }
+    }
+}
+
+/// A row of a citations CSV.
+#[derive(Serialize, Deserialize, Debug, Default, Clone)]
+#[serde(default)]
+pub struct InspectionCitation {
+    pub certificate_number: String,
+    pub customer_number: String,
+    /// The report's identifier, shared by every citation in it.
+    pub inspection_id: String,
+    /// YYYY-MM-DD.
+    pub inspection_date: String,
+    /// "Routine Inspection", "Focused Inspection"...
+    pub inspection_type: String,
+    pub report_url: String,
+    /// The regulation cited, e.g. "2.40(b)(2)".
+    pub code: String,
+    /// None for an inspection that found nothing.
+    pub kind: Option<CitationKind>,
+    /// Whether the same noncompliance was cited at the previous inspection.
+    pub repeat: bool,
+    /// The regulation's title, e.g. "Attending veterinarian and adequate
+    /// veterinary care".
+    pub description: String,
+    pub narrative: String,
+}
+
+/// Citations of each kind.
+#[derive(Serialize, Default, Clone, Copy)]
+struct CitationCounts {
+    critical: usize,
+    direct: usize,
+    non_critical: usize,
+    teachable_moments: usize,
+    repeat: usize,
+}
+
+impl CitationCounts {
+    fn add(&mut self, citation: &InspectionCitation) {
+        match citation.kind {
+            Some(CitationKind::Critical) => self.critical += 1,
+            Some(CitationKind::Direct) => self.direct += 1,
+            Some(CitationKind::NonCritical) => self.non_critical += 1,
+            Some(CitationKind::TeachableMoment) => self.teachable_moments += 1,
+            None => {}
+        }
+        if citation.repeat {
+            self.repeat += 1;
+        }
+    }
+
+    fn sum(&mut self, other: &CitationCounts) {
+        self.critical += other.critical;
+        self.direct += other.direct;
+        self.non_critical += other.non_critical;
+        self.teachable_moments += other.teachable_moments;
+        self.repeat += other.repeat;
+    }
+}
+
+#[derive(Serialize, Clone)]
+struct Citation {
+    code: String,
+    kind: CitationKind,
+    repeat: bool,
+    description: String,
+    narrative: String,
+}
+
+#[derive(Serialize, Clone)]
+struct Inspection {
+    inspection_id: String,
+    date: String,
+    #[serde(rename = "type")]
+    inspection_type: String,
+    report_url: String,
+    counts: CitationCounts,
+    citations: Vec<Citation>,
+}
+
+/// One licensee's inspections, newest first.
+#[derive(Serialize, Clone)]
+struct History {
+    certificate_number: String,
+    customer_number: String,
+    totals: CitationCounts,
+    inspections: Vec<Inspection>,
+}
+
+/// Every licensee's inspection history, built once per load.
+#[derive(Default, Clone)]
+pub(crate) struct Citations {
+    by_certificate: BTreeMap<String, History>,
+}
+
+impl Citations {
+    pub(crate) fn new(rows: Vec<InspectionCitation>) -> Self {
+        let mut inspections: BTreeMap<(String, String), Inspection> = BTreeMap::new();
+        let mut customers: BTreeMap<String, String> = BTreeMap::new();
+        for row in rows {
+            let inspection = inspections
+                .entry((row.certificate_number.clone(), row.inspection_id.clone()))
+                .or_insert_with(|| Inspection {
+                    inspection_id: row.inspection_id.clone(),
+                    date: row.inspection_date.clone(),
+                    inspection_type: row.inspection_type.clone(),
+                    report_url: row.report_url.clone(),
+                    counts: CitationCounts::default(),
+                    citations: Vec::new(),
+                });
+            inspection.counts.add(&row);
+            if !row.customer_number.is_empty() {
+                customers.insert(row.certificate_number.clone(), row.customer_number.clone());
+            }
+            if let Some(kind) = row.kind {
+                inspection.citations.push(Citation {
+                    code: row.code,
+                    kind,
+                    repeat: row.repeat,
+                    description: row.description,
+                    narrative: row.narrative,
+                });
+            }
+        }
+
+        let mut by_certificate: BTreeMap<String, History> = BTreeMap::new();
+        for ((certificate, _), inspection) in inspections {
+            let history = by_certificate
+                .entry(certificate.clone())
+                .or_insert_with(|| History {
+                    customer_number: customers.get(&certificate).cloned().unwrap_or_default(),
+                    certificate_number: certificate,
+                    totals: CitationCounts::default(),
+                    inspections: Vec::new(),
+                });
+            history.totals.sum(&inspection.counts);
+            history.inspections.push(inspection);
+        }
+        for history in by_certificate.values_mut() {
+            history.inspections.sort_by(|a, b| {
+                b.date
+                    .cmp(&a.date)
+                    .then(a.inspection_id.cmp(&b.inspection_id))
+            });
+        }
+        Citations { by_certificate }
+    }
+
+    /// Certificates with at least one inspection.
+    pub(crate) fn certificates(&self) -> usize {
+        self.by_certificate.len()
+    }
+
+    /// The noncompliances cited at `certificate`'s inspections, for its
+    /// priority score. Teachable moments weren't cited.
+    pub(crate) fn cited(&self, certificate: &str) -> usize {
+        self.by_certificate.get(certificate).map_or(0, |history| {
+            let totals = history.totals;
+            totals.critical + totals.direct + totals.non_critical
+        })
+    }
+
+    /// The citations as rows again, by certificate and newest inspection
+    /// first, with a row without a `kind` for an inspection that found
+    /// nothing.
+    pub(crate) fn rows(&self) -> Vec<InspectionCitation> {
+        let mut rows = Vec::new();
+        for history in self.by_certificate.values() {
+            for inspection in &history.inspections {
+                let row = |citation: Option<&Citation>| InspectionCitation {
+                    certificate_number: history.certificate_number.clone(),
+                    customer_number: history.customer_number.clone(),
+                    inspection_id: inspection.inspection_id.clone(),
+                    inspection_date: inspection.date.clone(),
+                    inspection_type: inspection.inspection_type.clone(),
+                    report_url: inspection.report_url.clone(),
+                    code: citation.map(|c| c.code.clone()).unwrap_or_default(),
+                    kind: citation.map(|c| c.kind),
+                    repeat: citation.is_some_and(|c| c.repeat),
+                    description: citation.map(|c| c.description.clone()).unwrap_or_default(),
+                    narrative: citation.map(|c| c.narrative.clone()).unwrap_or_default(),
+                };
+                if inspection.citations.is_empty() {
+                    rows.push(row(None));
+                }
+                rows.extend(inspection.citations.iter().map(|c| row(Some(c))));
+            }
+        }
+        rows
+    }
+}
+
+/// Every CSV in `us/citations/`.
+pub(crate) fn read_citations(
+    dataset: &Dataset,
+) -> Result<Vec<InspectionCitation>, Box<dyn std::error::Error>> {
+    let mut rows = Vec::new();
+    for (name, contents) in dataset.files_in("us/citations") {
+        if !name.ends_with(".csv") {
+            continue;
+        }
+        schema::check::<InspectionCitation>(&format!("us/citations/{}", name), contents)?;
+        let mut reader = csv::Reader::from_reader(contents);
+        for result in reader.deserialize() {
+            let row: InspectionCitation =
+                result.map_err(|e| format!("us/citations/{}: {}", name, e))?;
+            rows.push(row);
+        }
+    }
+    Ok(rows)
+}
+
+/// A licensee's totals, for listing every licensee.
+#[derive(Serialize)]
+struct Summary<'a> {
+    certificate_number: &'a str,
+    customer_number: &'a str,
+    inspections: usize,
+    last_inspection: Option<&'a str>,
+    #[serde(flatten)]
+    totals: CitationCounts,
+}
+
+#[derive(Deserialize)]
+pub struct CitationParams {
+    /// A licensee's certificate number, e.g. `74-C-0123`. Without it the
+    /// response lists every licensee's totals.
+    certificate: Option<String>,
+}
+
+/// GET /api/citations
+pub async fn get_citations_handler(
+    State(state): State<AppState>,
+    Query(params): Query<CitationParams>,
+) -> impl IntoResponse {
+    let reports = state.reports();
+    let by_certificate = &reports.citations.by_certificate;
+    let Some(certificate) = params.certificate.as_deref() else {
+        let summaries: Vec<Summary> = by_certificate
+            .values()
+            .map(|history| Summary {
+                certificate_number: &history.certificate_number,
+                customer_number: &history.customer_number,
+                inspections: history.inspections.len(),
+                last_inspection: history.inspections.first().map(|i| i.date.as_str()),
+                totals: history.totals,
+            })
+            .collect();
+        return Json(summaries).into_response();
+    };
+
+    match by_certificate.get(certificate.trim()) {
+        Some(history) => Json(history).into_response(),
+        None => (
+            StatusCode::NOT_FOUND,
+            format!("No inspections for certificate number {}", certificate),
+        )
+            .into_response(),
+    }
+}
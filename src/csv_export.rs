@@ -0,0 +1,74 @@
+// Until Every Cage is Empty
+// Copyright (C) 2025 Eli Perez
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+// Contact the developer directly at untileverycageproject@protonmail.com
+//! CSV bodies for the list endpoints' `?format=csv`.
+
+use std::collections::HashSet;
+use std::error::Error;
+
+use serde::Serialize;
+use serde_json::{Map, Value};
+
+use crate::geojson::to_object;
+use crate::paging::Page;
+
+/// A cell: scalars as text, lists of scalars joined with "; ", anything else
+/// as JSON.
+pub(crate) fn cell(value: &Value) -> String {
+    match value {
+        Value::Null => String::new(),
+        Value::String(text) => text.clone(),
+        Value::Bool(_) | Value::Number(_) => value.to_string(),
+        Value::Array(values) if values.iter().all(|v| !v.is_array() && !v.is_object()) => {
+            values.iter().map(cell).collect::<Vec<_>>().join("; ")
+        }
+        other => other.to_string(),
+    }
+}
+
+/// `items` as CSV, one row per item with the fields `page` selects. The header
+/// lists every field any item has, in the order they first appear.
+pub(crate) fn to_csv<T: Serialize>(items: &[T], page: &Page) -> Result<Vec<u8>, Box<dyn Error>> {
+    let selected = |item: &T| -> Result<Map<String, Value>, serde_json::Error> {
+        let mut object = to_object(item)?;
+        page.select(&mut object);
+        Ok(object)
+    };
+
+    // Items are serialized twice rather than all held as JSON at once.
+    let mut columns: Vec<String> = Vec::new();
+    let mut seen: HashSet<String> = HashSet::new();
+    for item in items {
+        for key in selected(item)?.keys() {
+            if seen.insert(key.clone()) {
+                columns.push(key.clone());
+            }
+        }
+    }
+
+    let mut writer = csv::Writer::from_writer(Vec::new());
+    writer.write_record(&columns)?;
+    for item in items {
+        let object = selected(item)?;
+        writer.write_record(
+            columns
+                .iter()
+                .map(|column| object.get(column).map(cell).unwrap_or_default()),
+        )?;
+    }
+    Ok(writer.into_inner()?)
+}
@@ -0,0 +1,177 @@
+// Until Every Cage is Empty
+// Copyright (C) 2025 Eli Perez
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+// Contact the developer directly at untileverycageproject@protonmail.com
+//! GET /api/locations/delta?since=VERSION: what a client holding the
+//! locations of an earlier version needs to catch up, instead of downloading
+//! them all again.
+//!
+//! Versions are snapshot dates (see `snapshots`); /api/meta and every delta
+//! give the current one. Records come in the shape /api/locations serves
+//! them, so a client replaces the ones listed as changed by id and drops the
+//! removed ones. Countries without a snapshot as old as the version can't be
+//! diffed and are listed for the client to fetch whole.
+
+use std::collections::HashSet;
+
+use axum::extract::{Query, State};
+use axum::{Json, http::StatusCode, response::IntoResponse};
+use serde::{Deserialize, Serialize};
+
+use crate::diff::diff_locations;
+use crate::errors::ApiError;
+use crate::geocode;
+use crate::snapshots::{load_current, load_snapshot, snapshot_dates};
+use crate::store::Dataset;
+use crate::{AppState, LocationResponse};
+
+#[derive(Serialize)]
+struct CountryDelta<'a> {
+    country: String,
+    /// The snapshot compared against: the country's latest on or before
+    /// `since`.
+    from: String,
+    /// Added or modified since, as served now.
+    changed: Vec<&'a LocationResponse>,
+    /// Establishment ids no longer listed.
+    removed: Vec<String>,
+}
+
+#[derive(Serialize)]
+struct Delta<'a> {
+    since: String,
+    /// The version to ask for the next delta with.
+    version: Option<String>,
+    countries: Vec<CountryDelta<'a>>,
+    /// Countries to fetch whole with /api/locations?country_code=.
+    refetch: Vec<String>,
+}
+
+#[derive(Deserialize)]
+pub struct DeltaParams {
+    /// A snapshot date, `YYYY-MM-DD`.
+    since: String,
+    country_code: Option<String>,
+}
+
+/// The newest snapshot date, which names the version being served.
+pub(crate) fn current_version(dataset: &Dataset) -> Option<String> {
+    snapshot_dates(dataset)
+        .into_iter()
+        .map(|(_, date)| date)
+        .max()
+}
+
+/// GET /api/locations/delta?since=VERSION
+pub async fn get_location_delta_handler(
+    State(state): State<AppState>,
+    Query(params): Query<DeltaParams>,
+) -> impl IntoResponse {
+    let dataset = state.store.current();
+    let dates = snapshot_dates(&dataset);
+    if !dates.iter().any(|(_, date)| *date == params.since) {
+        return (
+            StatusCode::BAD_REQUEST,
+            format!(
+                "Unknown version {}; since takes the version from /api/meta or a previous delta",
+                params.since
+            ),
+        )
+            .into_response();
+    }
+
+    let index = state.location_index();
+    let mut countries: Vec<&str> = Vec::new();
+    for loc in index.locations.iter() {
+        if !countries.contains(&loc.country.as_str())
+            && params
+                .country_code
+                .as_ref()
+                .is_none_or(|wanted| geocode::country_code(wanted) == loc.country)
+        {
+            countries.push(&loc.country);
+        }
+    }
+
+    let mut delta = Delta {
+        since: params.since.clone(),
+        version: current_version(&dataset),
+        countries: Vec::new(),
+        refetch: Vec::new(),
+    };
+    for country in countries {
+        // Snapshots are kept under the country's directory.
+        let directory = geocode::country_dir(country);
+        // Dates are sorted, so this is the snapshot in force at `since`.
+        let baseline = dates
+            .iter()
+            .filter(|(known, date)| *known == directory && *date <= params.since)
+            .map(|(_, date)| date)
+            .next_back();
+        let Some(baseline) = baseline else {
+            delta.refetch.push(country.to_string());
+            continue;
+        };
+
+        let loaded = load_snapshot(&dataset, &directory, baseline)
+            .and_then(|snapshot| Ok((snapshot, load_current(&dataset, &directory)?)));
+        let (snapshot, current) = match loaded {
+            Ok((Some(snapshot), current)) => (snapshot, current),
+            Ok((None, _)) => {
+                delta.refetch.push(country.to_string());
+                continue;
+            }
+            Err(e) => {
+                return ApiError::internal(format!(
+                    "Failed to read {} snapshot {}: {}",
+                    country, baseline, e
+                ))
+                .into_response();
+            }
+        };
+
+        let diff = diff_locations(&snapshot.locations, &current);
+        let changed_ids: HashSet<&str> = diff
+            .added
+            .iter()
+            .map(|loc| loc.establishment_id.as_str())
+            .chain(
+                diff.modified
+                    .iter()
+                    .map(|change| change.new.establishment_id.as_str()),
+            )
+            .collect();
+        let changed = index
+            .locations
+            .iter()
+            .filter(|loc| {
+                loc.country == country && changed_ids.contains(loc.establishment_id.as_str())
+            })
+            .collect();
+        delta.countries.push(CountryDelta {
+            country: country.to_string(),
+            from: snapshot.date,
+            changed,
+            removed: diff
+                .removed
+                .iter()
+                .map(|loc| loc.establishment_id.clone())
+                .collect(),
+        });
+    }
+
+    Json(delta).into_response()
+}
@@ -0,0 +1,114 @@
+// Until Every Cage is Empty
+// Copyright (C) 2025 Eli Perez
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+// Contact the developer directly at untileverycageproject@protonmail.com
+use axum::Json;
+use axum::extract::Request;
+use axum::http::HeaderValue;
+use axum::middleware::Next;
+use axum::response::{IntoResponse, Response};
+use chrono::NaiveDate;
+use serde::Serialize;
+
+/// An announced change to the public API.
+#[derive(Serialize, Debug)]
+pub struct Deprecation {
+    pub id: &'static str,
+    /// Routes affected; a trailing `*` matches any path with that prefix.
+    pub paths: &'static [&'static str],
+    pub description: &'static str,
+    pub replacement: Option<&'static str>,
+    /// `YYYY-MM-DD`
+    pub deprecated_on: &'static str,
+    /// `YYYY-MM-DD` after which the old behavior may be removed.
+    pub sunset_on: Option<&'static str>,
+}
+
+/// Announce changes here before making them. Keep entries until their sunset
+/// has passed and the change has shipped.
+pub const DEPRECATIONS: &[Deprecation] = &[Deprecation {
+    id: "locations-dbas-string",
+    paths: &["/api/locations*", "/api/search"],
+    description: "The `dbas` field holds every trade name in one semicolon-separated \
+        string. It will be removed from location responses.",
+    replacement: Some("dba_names"),
+    deprecated_on: "2026-10-14",
+    sunset_on: Some("2027-04-14"),
+}];
+
+fn matches(pattern: &str, path: &str) -> bool {
+    match pattern.strip_suffix('*') {
+        Some(prefix) => path.starts_with(prefix),
+        None => path == pattern,
+    }
+}
+
+fn parse_date(date: &str) -> Option<NaiveDate> {
+    NaiveDate::parse_from_str(date, "%Y-%m-%d").ok()
+}
+
+/// Adds `Deprecation` (RFC 9745), `Sunset` (RFC 8594) and a `Link` to
+/// /api/deprecations to responses from affected routes. With several
+/// deprecations on one route the earliest dates are reported.
+pub async fn deprecation_headers(request: Request, next: Next) -> Response {
+    let path = request.uri().path().to_string();
+    let mut response = next.run(request).await;
+
+    let applicable: Vec<&Deprecation> = DEPRECATIONS
+        .iter()
+        .filter(|d| d.paths.iter().any(|pattern| matches(pattern, &path)))
+        .collect();
+    if applicable.is_empty() {
+        return response;
+    }
+
+    let midnight = |date: NaiveDate| date.and_hms_opt(0, 0, 0).unwrap().and_utc();
+    let deprecated = applicable
+        .iter()
+        .filter_map(|d| parse_date(d.deprecated_on))
+        .min();
+    let sunset = applicable
+        .iter()
+        .filter_map(|d| d.sunset_on.and_then(parse_date))
+        .min();
+
+    let headers = response.headers_mut();
+    if let Some(date) = deprecated
+        && let Ok(value) = HeaderValue::from_str(&format!("@{}", midnight(date).timestamp()))
+    {
+        headers.insert("deprecation", value);
+    }
+    if let Some(date) = sunset
+        && let Ok(value) = HeaderValue::from_str(
+            &midnight(date)
+                .format("%a, %d %b %Y %H:%M:%S GMT")
+                .to_string(),
+        )
+    {
+        headers.insert("sunset", value);
+    }
+    headers.append(
+        "link",
+        HeaderValue::from_static(
+            r#"</api/deprecations>; rel="deprecation"; type="application/json""#,
+        ),
+    );
+    response
+}
+
+pub async fn get_deprecations_handler() -> impl IntoResponse {
+    Json(DEPRECATIONS)
+}
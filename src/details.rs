@@ -0,0 +1,284 @@
+// Until Every Cage is Empty
+// Copyright (C) 2025 Eli Perez
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+// Contact the developer directly at untileverycageproject@protonmail.com
+//! Single-record endpoints for deep links and share URLs: one location, APHIS
+//! research facility or licensee, with what the other datasets know about it.
+
+use std::collections::HashMap;
+
+use axum::Json;
+use axum::extract::{Path, Query, State};
+use axum::http::StatusCode;
+use axum::response::IntoResponse;
+use serde::{Deserialize, Serialize};
+
+use crate::facilities::{FacilityIndex, RelatedFacility, Source};
+use crate::location::{AphisReport, InspectionReport};
+use crate::served::Reports;
+use crate::{AppState, LocationResponse, geocode};
+
+/// A record with the fields derived from the other datasets.
+#[derive(Serialize)]
+struct Detail<'a, T> {
+    #[serde(flatten)]
+    record: &'a T,
+    /// Species group IDs from the ontology.
+    species: &'a [&'static str],
+    /// Licensee inspection reports under the same customer number or matched
+    /// to the record. Absent on inspection reports themselves.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    inspection_reports: Option<Vec<&'a InspectionReport>>,
+    related_facilities: Vec<RelatedFacility<'a>>,
+}
+
+impl<'a, T> Detail<'a, T> {
+    /// `facility_id` is the record's own, which it serializes itself.
+    fn new(record: &'a T, facility_id: &str, facilities: &'a FacilityIndex) -> Self {
+        Detail {
+            record,
+            species: facilities
+                .get(facility_id)
+                .map(|facility| facility.species.as_slice())
+                .unwrap_or_default(),
+            inspection_reports: None,
+            related_facilities: facilities.related(facility_id),
+        }
+    }
+
+    /// Fills in the inspection reports sharing `customer_number` or matched
+    /// to the record.
+    fn with_inspection_reports(mut self, reports: &'a Reports, customer_number: &str) -> Self {
+        let matched: Vec<&str> = self
+            .related_facilities
+            .iter()
+            .filter(|related| related.facility.source == Source::InspectionReports)
+            .map(|related| related.facility.id.as_str())
+            .collect();
+        let linked = reports
+            .inspections
+            .iter()
+            .filter(|report| {
+                (!customer_number.is_empty() && report.customer_number == customer_number)
+                    || report
+                        .facility_id
+                        .as_deref()
+                        .is_some_and(|id| matched.contains(&id))
+            })
+            .collect();
+        self.inspection_reports = Some(linked);
+        self
+    }
+}
+
+#[derive(Deserialize)]
+pub struct DetailParams {
+    /// Narrows an establishment id used in several countries.
+    pub(crate) country_code: Option<String>,
+}
+
+/// The location with an establishment id, narrowed by country. Not found and
+/// ambiguous ids are errors to respond with.
+pub(crate) fn find_location<'a>(
+    locations: &'a [LocationResponse],
+    establishment_id: &str,
+    country_code: Option<&str>,
+) -> Result<&'a LocationResponse, (StatusCode, String)> {
+    let matches: Vec<&LocationResponse> = locations
+        .iter()
+        .filter(|loc| loc.establishment_id == establishment_id)
+        .filter(|loc| {
+            country_code.is_none_or(|country| loc.country == geocode::country_code(country))
+        })
+        .collect();
+    match matches.as_slice() {
+        [] => Err((
+            StatusCode::NOT_FOUND,
+            format!("No location with establishment id {}", establishment_id),
+        )),
+        [location] => Ok(*location),
+        several => {
+            let slugs: Vec<&str> = several.iter().map(|loc| loc.slug.as_str()).collect();
+            Err((
+                StatusCode::CONFLICT,
+                format!(
+                    "Establishment id {} is used by several locations, pass country_code or \
+                     look one up at /api/locations/slug/{{slug}}: {}",
+                    establishment_id,
+                    slugs.join(", ")
+                ),
+            ))
+        }
+    }
+}
+
+/// GET /api/locations/{establishment_id}
+pub async fn get_location_handler(
+    State(state): State<AppState>,
+    Path(establishment_id): Path<String>,
+    Query(params): Query<DetailParams>,
+) -> impl IntoResponse {
+    let index = state.location_index();
+    let location = match find_location(
+        &index.locations,
+        &establishment_id,
+        params.country_code.as_deref(),
+    ) {
+        Ok(location) => location,
+        Err(e) => return e.into_response(),
+    };
+
+    let facilities = state.facilities();
+    let reports = state.reports();
+    let detail = Detail::new(location, &location.facility_id, &facilities)
+        .with_inspection_reports(&reports, "");
+    Json(detail).into_response()
+}
+
+/// GET /api/aphis-reports/{certificate_number}
+pub async fn get_aphis_report_handler(
+    State(state): State<AppState>,
+    Path(certificate_number): Path<String>,
+) -> impl IntoResponse {
+    let reports = state.reports();
+    let Some(report) = reports
+        .aphis
+        .iter()
+        .find(|report| report.certificate_number == certificate_number)
+    else {
+        return (
+            StatusCode::NOT_FOUND,
+            format!(
+                "No APHIS report with certificate number {}",
+                certificate_number
+            ),
+        )
+            .into_response();
+    };
+
+    let facilities = state.facilities();
+    let detail: Detail<AphisReport> = Detail::new(
+        report,
+        report.facility_id.as_deref().unwrap_or_default(),
+        &facilities,
+    )
+    .with_inspection_reports(&reports, &report.customer_number_x);
+    Json(detail).into_response()
+}
+
+/// The licensee inspection reports of each APHIS certificate: those with the
+/// same certificate number or under the research facility's customer number.
+#[derive(Default)]
+pub(crate) struct InspectionLinks {
+    by_certificate: HashMap<String, Vec<usize>>,
+}
+
+impl InspectionLinks {
+    /// Links the reports and sets their `has_inspections`.
+    pub(crate) fn new(aphis: &mut [AphisReport], inspections: &[InspectionReport]) -> Self {
+        let mut by_number: HashMap<&str, Vec<usize>> = HashMap::new();
+        for (i, report) in inspections.iter().enumerate() {
+            for number in [&report.certificate_number, &report.customer_number] {
+                if !number.is_empty() {
+                    by_number.entry(number).or_default().push(i);
+                }
+            }
+        }
+        let mut by_certificate: HashMap<String, Vec<usize>> = HashMap::new();
+        for report in aphis.iter_mut() {
+            let linked = by_certificate
+                .entry(report.certificate_number.clone())
+                .or_default();
+            for number in [&report.certificate_number, &report.customer_number_x] {
+                if let Some(positions) = by_number.get(number.as_str()) {
+                    linked.extend(positions);
+                }
+            }
+            linked.sort_unstable();
+            linked.dedup();
+            report.has_inspections = !linked.is_empty();
+        }
+        by_certificate.retain(|_, linked| !linked.is_empty());
+        InspectionLinks { by_certificate }
+    }
+
+    /// Positions in `Reports::inspections`, in dataset order.
+    pub(crate) fn of(&self, certificate_number: &str) -> &[usize] {
+        self.by_certificate
+            .get(certificate_number)
+            .map(Vec::as_slice)
+            .unwrap_or_default()
+    }
+}
+
+/// GET /api/aphis-reports/{certificate_number}/inspections
+pub async fn get_aphis_inspections_handler(
+    State(state): State<AppState>,
+    Path(certificate_number): Path<String>,
+) -> impl IntoResponse {
+    let reports = state.reports();
+    if !reports
+        .aphis
+        .iter()
+        .any(|report| report.certificate_number == certificate_number)
+    {
+        return (
+            StatusCode::NOT_FOUND,
+            format!(
+                "No APHIS report with certificate number {}",
+                certificate_number
+            ),
+        )
+            .into_response();
+    }
+    let inspections: Vec<&InspectionReport> = reports
+        .inspection_links
+        .of(&certificate_number)
+        .iter()
+        .map(|&i| &reports.inspections[i])
+        .collect();
+    Json(inspections).into_response()
+}
+
+/// GET /api/inspection-reports/{certificate_number}
+pub async fn get_inspection_report_handler(
+    State(state): State<AppState>,
+    Path(certificate_number): Path<String>,
+) -> impl IntoResponse {
+    let reports = state.reports();
+    let Some(report) = reports
+        .inspections
+        .iter()
+        .find(|report| report.certificate_number == certificate_number)
+    else {
+        return (
+            StatusCode::NOT_FOUND,
+            format!(
+                "No inspection report with certificate number {}",
+                certificate_number
+            ),
+        )
+            .into_response();
+    };
+
+    let facilities = state.facilities();
+    let detail = Detail::new(
+        report,
+        report.facility_id.as_deref().unwrap_or_default(),
+        &facilities,
+    );
+    Json(detail).into_response()
+}
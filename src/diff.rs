@@ -0,0 +1,109 @@
+// Until Every Cage is Empty
+// Copyright (C) 2025 Eli Perez
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+// Contact the developer directly at untileverycageproject@protonmail.com
+use std::collections::HashMap;
+
+use crate::location::Location;
+
+/// A location present in both versions whose tracked fields differ.
+pub struct Modified<'a> {
+    pub old: &'a Location,
+    pub new: &'a Location,
+    pub changed_fields: Vec<&'static str>,
+}
+
+/// Differences between two versions of a location list, matched on establishment ID.
+pub struct LocationDiff<'a> {
+    pub added: Vec<&'a Location>,
+    pub removed: Vec<&'a Location>,
+    pub modified: Vec<Modified<'a>>,
+}
+
+/// Fields compared when deciding whether a location was modified.
+pub fn tracked_fields(loc: &Location) -> [(&'static str, String); 10] {
+    [
+        ("establishment_name", loc.establishment_name.clone()),
+        ("street", loc.street.clone()),
+        ("city", loc.city.clone()),
+        ("state", loc.state.clone()),
+        ("zip", loc.zip.clone()),
+        ("type", loc.activities.clone()),
+        ("dbas", loc.dbas.clone()),
+        (
+            "slaughter_volume_category",
+            loc.slaughter_volume_category.clone(),
+        ),
+        (
+            "processing_volume_category",
+            loc.processing_volume_category.clone(),
+        ),
+        (
+            "coordinates",
+            format!("{:.5},{:.5}", loc.latitude, loc.longitude),
+        ),
+    ]
+}
+
+pub fn diff_locations<'a>(old: &'a [Location], new: &'a [Location]) -> LocationDiff<'a> {
+    let old_by_id: HashMap<&str, &Location> = old
+        .iter()
+        .map(|loc| (loc.establishment_id.as_str(), loc))
+        .collect();
+    let new_by_id: HashMap<&str, &Location> = new
+        .iter()
+        .map(|loc| (loc.establishment_id.as_str(), loc))
+        .collect();
+
+    let mut diff = LocationDiff {
+        added: Vec::new(),
+        removed: Vec::new(),
+        modified: Vec::new(),
+    };
+
+    for loc in new {
+        match old_by_id.get(loc.establishment_id.as_str()) {
+            None => diff.added.push(loc),
+            Some(previous) => {
+                let changed_fields: Vec<&'static str> = tracked_fields(previous)
+                    .into_iter()
+                    .zip(tracked_fields(loc))
+                    // Archived releases have no coordinates, so don't count them as moves.
+                    .filter(|((field, before), (_, after))| {
+                        before != after
+                            && !(*field == "coordinates"
+                                && (before == "0.00000,0.00000" || after == "0.00000,0.00000"))
+                    })
+                    .map(|((field, _), _)| field)
+                    .collect();
+                if !changed_fields.is_empty() {
+                    diff.modified.push(Modified {
+                        old: previous,
+                        new: loc,
+                        changed_fields,
+                    });
+                }
+            }
+        }
+    }
+
+    diff.removed = old
+        .iter()
+        .filter(|loc| !new_by_id.contains_key(loc.establishment_id.as_str()))
+        .collect();
+
+    diff
+}
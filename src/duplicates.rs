@@ -0,0 +1,193 @@
+// Until Every Cage is Empty
+// Copyright (C) 2025 Eli Perez
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+// Contact the developer directly at untileverycageproject@protonmail.com
+//! GET /api/match?name=&city=: the records of any dataset that may already
+//! be a facility someone is about to add, so a new source doesn't duplicate
+//! what another one has.
+//!
+//! Names are compared with Jaro-Winkler after dropping legal suffixes and
+//! word order ("Smith Meats LLC" is "meats smith"), and so are cities. With
+//! `lat` and `lng`, records within a few kilometres score higher. The score
+//! averages whichever of the three could be compared.
+
+use axum::Json;
+use axum::extract::{Query, State};
+use axum::http::StatusCode;
+use axum::response::IntoResponse;
+use serde::{Deserialize, Serialize};
+
+use crate::AppState;
+use crate::facilities::Facility;
+use crate::geocode;
+use crate::matching::{comparable_name, jaro_winkler};
+use crate::search::normalize;
+use crate::spatial::haversine_km;
+
+const DEFAULT_LIMIT: usize = 10;
+const MAX_LIMIT: usize = 50;
+const DEFAULT_MIN_SCORE: f64 = 0.8;
+/// Names less alike than this aren't scored at all.
+const MIN_NAME_SIMILARITY: f64 = 0.7;
+/// Proximity falls from 1 at the point to 0 this far away.
+const NEARBY_KM: f64 = 5.0;
+const NAME_WEIGHT: f64 = 0.6;
+const CITY_WEIGHT: f64 = 0.2;
+const PROXIMITY_WEIGHT: f64 = 0.2;
+
+#[derive(Deserialize)]
+pub struct MatchParams {
+    name: Option<String>,
+    city: Option<String>,
+    #[serde(alias = "country")]
+    country_code: Option<String>,
+    lat: Option<f64>,
+    lng: Option<f64>,
+    /// 0 to 1, 0.8 by default.
+    min_score: Option<f64>,
+    limit: Option<usize>,
+}
+
+/// The checked parameters.
+struct Wanted {
+    /// As `comparable_name` writes it.
+    name: String,
+    point: Option<(f64, f64)>,
+    min_score: f64,
+    limit: usize,
+}
+
+impl MatchParams {
+    fn parse(&self) -> Result<Wanted, String> {
+        let name = comparable_name(self.name.as_deref().unwrap_or_default());
+        if name.is_empty() {
+            return Err("name is required".to_string());
+        }
+        let point = match (self.lat, self.lng) {
+            (None, None) => None,
+            (Some(lat), Some(lng))
+                if (-90.0..=90.0).contains(&lat) && (-180.0..=180.0).contains(&lng) =>
+            {
+                Some((lat, lng))
+            }
+            (Some(_), Some(_)) => {
+                return Err(
+                    "lat must be between -90 and 90 and lng between -180 and 180".to_string(),
+                );
+            }
+            _ => return Err("Pass both lat and lng, or neither".to_string()),
+        };
+        let min_score = self.min_score.unwrap_or(DEFAULT_MIN_SCORE);
+        if !(0.0..=1.0).contains(&min_score) {
+            return Err("min_score must be between 0 and 1".to_string());
+        }
+        let limit = self.limit.unwrap_or(DEFAULT_LIMIT);
+        if limit == 0 || limit > MAX_LIMIT {
+            return Err(format!("limit must be between 1 and {}", MAX_LIMIT));
+        }
+        Ok(Wanted {
+            name,
+            point,
+            min_score,
+            limit,
+        })
+    }
+}
+
+#[derive(Serialize)]
+struct Candidate<'a> {
+    score: f64,
+    name_similarity: f64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    city_similarity: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    distance_km: Option<f64>,
+    #[serde(flatten)]
+    facility: &'a Facility,
+}
+
+fn round(value: f64) -> f64 {
+    (value * 1000.0).round() / 1000.0
+}
+
+/// GET /api/match?name=&city=
+pub async fn get_match_handler(
+    State(state): State<AppState>,
+    Query(params): Query<MatchParams>,
+) -> impl IntoResponse {
+    let wanted = match params.parse() {
+        Ok(wanted) => wanted,
+        Err(e) => return (StatusCode::BAD_REQUEST, e).into_response(),
+    };
+    let city = params
+        .city
+        .as_deref()
+        .map(normalize)
+        .filter(|city| !city.is_empty());
+    let country = params.country_code.as_deref().map(geocode::country_code);
+
+    let facilities = state.facilities();
+    let mut candidates: Vec<Candidate> = facilities
+        .facilities
+        .iter()
+        .filter(|facility| {
+            country
+                .as_deref()
+                .is_none_or(|country| facility.country == country)
+        })
+        .filter_map(|facility| {
+            let name_similarity = jaro_winkler(&wanted.name, &comparable_name(&facility.name));
+            if name_similarity < MIN_NAME_SIMILARITY {
+                return None;
+            }
+            let city_similarity = city
+                .as_deref()
+                .zip(Some(normalize(&facility.city)).filter(|city| !city.is_empty()))
+                .map(|(wanted, theirs)| jaro_winkler(wanted, &theirs));
+            let located = !(facility.latitude == 0.0 && facility.longitude == 0.0);
+            let distance_km = wanted
+                .point
+                .filter(|_| located)
+                .map(|point| haversine_km(point, (facility.latitude, facility.longitude)));
+
+            let mut total = NAME_WEIGHT * name_similarity;
+            let mut weights = NAME_WEIGHT;
+            if let Some(similarity) = city_similarity {
+                total += CITY_WEIGHT * similarity;
+                weights += CITY_WEIGHT;
+            }
+            if let Some(distance) = distance_km {
+                total += PROXIMITY_WEIGHT * (1.0 - distance / NEARBY_KM).clamp(0.0, 1.0);
+                weights += PROXIMITY_WEIGHT;
+            }
+            let score = total / weights;
+            (score >= wanted.min_score).then(|| Candidate {
+                score: round(score),
+                name_similarity: round(name_similarity),
+                city_similarity: city_similarity.map(round),
+                distance_km: distance_km.map(round),
+                facility,
+            })
+        })
+        .collect();
+    candidates.sort_by(|a, b| {
+        b.score
+            .total_cmp(&a.score)
+            .then_with(|| a.facility.id.cmp(&b.facility.id))
+    });
+    candidates.truncate(wanted.limit);
+    Json(candidates).into_response()
+}
@@ -0,0 +1,225 @@
+// Until Every Cage is Empty
+// Copyright (C) 2025 Eli Perez
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+// Contact the developer directly at untileverycageproject@protonmail.com
+//! Egg-laying operations and hatcheries. Layer hens never pass through a
+//! slaughter directory while laying, so these come from producer
+//! registrations and poultry health program lists instead. Each country's
+//! farms are CSV files in `<country>/egg_farms/`, one per source, written by
+//! the egg farm importer.
+
+use axum::extract::{Query, State};
+use axum::http::{HeaderMap, StatusCode};
+use axum::response::IntoResponse;
+use serde::{Deserialize, Serialize};
+
+use crate::activities::Activity;
+use crate::geocode;
+use crate::ids;
+use crate::paging::Page;
+use crate::schema;
+use crate::species::{self, Taxon};
+use crate::store::Dataset;
+use crate::{AppState, geojson};
+
+/// A row of an egg farm CSV.
+#[derive(Serialize, Deserialize, Debug, Default, Clone)]
+#[serde(default)]
+pub struct EggFarm {
+    /// The registration or NPIP number, or an `ids::content_id` when the
+    /// source has none.
+    pub farm_id: String,
+    pub name: String,
+    /// As the source words it: "Shell egg producer", "Hatchery", "Egg-type
+    /// breeding flock"...
+    pub operation: String,
+    /// The dataset the row came from, e.g. "FDA shell egg producer registry".
+    pub source: String,
+    pub street: String,
+    pub city: String,
+    pub county: String,
+    pub state: String,
+    pub zip: String,
+    /// Species as the source names them, separated by "; ".
+    pub species: String,
+    /// Birds housed, or hatching capacity for hatcheries, when the source
+    /// gives it.
+    pub birds: Option<u64>,
+    pub latitude: f64,
+    pub longitude: f64,
+}
+
+/// A farm as served, with the species and activity worked out.
+#[derive(Serialize, Debug, Clone)]
+pub(crate) struct EggFarmFacility {
+    /// The farm's facility ID (see `ids`).
+    pub(crate) facility_id: String,
+    pub(crate) country: String,
+    #[serde(flatten)]
+    pub(crate) farm: EggFarm,
+    /// Taxa from the ontology, as specific as the operation tells: the
+    /// chickens of an egg farm are `laying_hen`.
+    pub(crate) taxa: Vec<&'static str>,
+    /// Species group IDs from the ontology (`chicken`, `duck`...).
+    pub(crate) species_ids: Vec<&'static str>,
+    pub(crate) activity: Activity,
+}
+
+impl geojson::Feature for EggFarmFacility {}
+
+impl EggFarmFacility {
+    fn new(country: &str, farm: EggFarm) -> Self {
+        let activity = if farm.operation.to_lowercase().contains("hatch") {
+            Activity::Hatchery
+        } else {
+            Activity::EggFarm
+        };
+        let mut taxa: Vec<&'static str> = species::mentioned_in(&farm.species)
+            .iter()
+            .map(|taxon| taxon.id)
+            .collect();
+        if activity == Activity::EggFarm {
+            // Registries list "chickens" or nothing at all for layer flocks.
+            taxa.retain(|id| *id != "chicken");
+            if !taxa.iter().any(|id| species::is_within(id, "chicken")) {
+                taxa.insert(0, "laying_hen");
+            }
+        }
+        EggFarmFacility {
+            facility_id: ids::facility_id("egg_farms", country, &farm.farm_id),
+            country: geocode::country_code(country),
+            species_ids: species::groups(taxa.iter().copied()),
+            taxa,
+            farm,
+            activity,
+        }
+    }
+}
+
+/// Every country's egg farms and hatcheries.
+pub(crate) fn read_egg_farms(
+    dataset: &Dataset,
+) -> Result<Vec<EggFarmFacility>, Box<dyn std::error::Error>> {
+    let mut farms = Vec::new();
+    for country in dataset.countries() {
+        for (name, contents) in dataset.files_in(&format!("{}/egg_farms", country)) {
+            if !name.ends_with(".csv") {
+                continue;
+            }
+            schema::check::<EggFarm>(&format!("{}/egg_farms/{}", country, name), contents)?;
+            let mut reader = csv::Reader::from_reader(contents);
+            for result in reader.deserialize() {
+                let farm: EggFarm =
+                    result.map_err(|e| format!("{}/egg_farms/{}: {}", country, name, e))?;
+                farms.push(EggFarmFacility::new(country, farm));
+            }
+        }
+    }
+    Ok(farms)
+}
+
+#[derive(Deserialize)]
+pub struct EggFarmParams {
+    country_code: Option<String>,
+    state: Option<String>,
+    /// Comma-separated species, e.g. `laying_hen,duck` or `poultry`.
+    species: Option<String>,
+    /// `egg_farm` or `hatchery`.
+    activity: Option<String>,
+    format: Option<String>,
+    limit: Option<usize>,
+    offset: Option<usize>,
+    fields: Option<String>,
+}
+
+/// GET /api/egg-farms: egg-laying operations and hatcheries.
+pub async fn get_egg_farms_handler(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Query(params): Query<EggFarmParams>,
+) -> impl IntoResponse {
+    let parse = || -> Result<(Vec<&'static Taxon>, Option<Activity>, Page), String> {
+        let species = params
+            .species
+            .as_deref()
+            .unwrap_or_default()
+            .split(',')
+            .map(str::trim)
+            .filter(|term| !term.is_empty())
+            .map(|term| species::lookup(term).ok_or_else(|| format!("Unknown species {}", term)))
+            .collect::<Result<_, _>>()?;
+        let activity = match params.activity.as_deref() {
+            None => None,
+            Some(value) => match Activity::parse(value) {
+                Some(activity @ (Activity::EggFarm | Activity::Hatchery)) => Some(activity),
+                _ => {
+                    return Err(format!(
+                        "Unknown activity {}, expected egg_farm or hatchery",
+                        value
+                    ));
+                }
+            },
+        };
+        let page = Page::parse(params.limit, params.offset, params.fields.as_deref())?;
+        Ok((species, activity, page))
+    };
+    let (species, activity, page) = match parse() {
+        Ok(parsed) => parsed,
+        Err(e) => return (StatusCode::BAD_REQUEST, e).into_response(),
+    };
+
+    let reports = state.reports();
+    if species.is_empty()
+        && activity.is_none()
+        && params.country_code.is_none()
+        && params.state.is_none()
+    {
+        return geojson::respond_all(
+            &reports.egg_farms,
+            &headers,
+            params.format.as_deref(),
+            &page,
+        );
+    }
+
+    let selected: Vec<&EggFarmFacility> = reports
+        .egg_farms
+        .iter()
+        .filter(|facility| {
+            params
+                .country_code
+                .as_ref()
+                .is_none_or(|country| facility.country == geocode::country_code(country))
+        })
+        .filter(|facility| {
+            params
+                .state
+                .as_ref()
+                .is_none_or(|state| facility.farm.state.eq_ignore_ascii_case(state))
+        })
+        .filter(|facility| activity.is_none_or(|activity| facility.activity == activity))
+        .filter(|facility| {
+            species.is_empty()
+                || species.iter().any(|wanted| {
+                    facility
+                        .taxa
+                        .iter()
+                        .any(|id| species::is_within(id, wanted.id))
+                })
+        })
+        .collect();
+    geojson::respond(&selected, &headers, params.format.as_deref(), &page)
+}
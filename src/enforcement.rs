@@ -0,0 +1,337 @@
+// Until Every Cage is Empty
+// Copyright (C) 2025 Eli Perez
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+// Contact the developer directly at untileverycageproject@protonmail.com
+//! FSIS enforcement actions (from the quarterly enforcement reports) and
+//! noncompliance records, one CSV row per record in `us/enforcement/`,
+//! written by the us-fsis-enforcement importer. Rows are linked to locations
+//! by `establishment_id`, which the importer resolves from the establishment
+//! number FSIS reports them under.
+
+use std::collections::BTreeMap;
+
+use axum::Json;
+use axum::extract::{Path, Query, State};
+use axum::http::StatusCode;
+use axum::response::IntoResponse;
+use serde::{Deserialize, Serialize};
+
+use crate::AppState;
+use crate::schema;
+use crate::store::Dataset;
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum EnforcementKind {
+    /// An inspector's written record of a failure to meet a regulation.
+    NoncomplianceRecord,
+    /// Notice of Intended Enforcement: the establishment has days to respond
+    /// before an action is taken.
+    NoticeOfIntendedEnforcement,
+    /// Inspectors withhold the marks of inspection, halting production.
+    Withholding,
+    /// Inspection is suspended, halting production.
+    Suspension,
+    /// A suspension deferred while the establishment carries out corrective
+    /// actions.
+    SuspensionHeldInAbeyance,
+    Reinstatement,
+    /// The grant of inspection is withdrawn.
+    Withdrawal,
+    #[default]
+    Other,
+}
+
+impl EnforcementKind {
+    /// "NOIE", "Suspension Held in Abeyance", "NR" and the like, as the
+    /// reports spell them.
+    pub fn parse(value: &str) -> Option<EnforcementKind> {
+        let value: String = value
+            .to_lowercase()
+            .chars()
+            .filter(char::is_ascii_alphabetic)
+            .collect();
+        match value.as_str() {
+            "" => None,
+            "nr" | "noncompliancerecord" | "noncompliance" => {
+                Some(EnforcementKind::NoncomplianceRecord)
+            }
+            "noie" => Some(EnforcementKind::NoticeOfIntendedEnforcement),
+            v if v.starts_with("noticeofintended") => {
+                Some(EnforcementKind::NoticeOfIntendedEnforcement)
+            }
+            v if v.contains("abeyance") => Some(EnforcementKind::SuspensionHeldInAbeyance),
+            v if v.starts_with("suspen") || v == "nos" => Some(EnforcementKind::Suspension),
+            v if v.starts_with("withh") => Some(EnforcementKind::Withholding),
+            v if v.starts_with("reinstate") => Some(EnforcementKind::Reinstatement),
+            v if v.starts_with("withdraw") => Some(EnforcementKind::Withdrawal),
+            _ => None,
+        }
+    }
+}
+
+/// A row of an enforcement CSV.
+#[derive(Serialize, Deserialize, Debug, Default, Clone)]
+#[serde(default)]
+pub struct EnforcementRecord {
+    /// The location's `establishment_id`. Empty when the importer couldn't
+    /// find the establishment in the directory.
+    pub establishment_id: String,
+    /// As FSIS reported it, e.g. "M267+P267".
+    pub establishment_number: String,
+    pub establishment_name: String,
+    /// YYYY-MM-DD.
+    pub date: String,
+    pub kind: EnforcementKind,
+    /// Whether the record concerns the humane handling or slaughter of
+    /// animals (9 CFR 313, or good commercial practices for poultry, 9 CFR
+    /// 381.65(b)).
+    pub humane_handling: bool,
+    /// The regulation cited, e.g. "313.2(a)".
+    pub regulation: String,
+    /// Why the action was taken, or what the inspector recorded.
+    pub description: String,
+    pub report_url: String,
+}
+
+/// Records of each kind.
+#[derive(Serialize, Default, Clone, Copy)]
+struct EnforcementCounts {
+    records: usize,
+    humane_handling: usize,
+    noncompliance_records: usize,
+    /// Actions other than noncompliance records.
+    actions: usize,
+    suspensions: usize,
+}
+
+impl EnforcementCounts {
+    fn add(&mut self, record: &EnforcementRecord) {
+        self.records += 1;
+        if record.humane_handling {
+            self.humane_handling += 1;
+        }
+        match record.kind {
+            EnforcementKind::NoncomplianceRecord => self.noncompliance_records += 1,
+            kind => {
+                self.actions += 1;
+                if kind == EnforcementKind::Suspension {
+                    self.suspensions += 1;
+                }
+            }
+        }
+    }
+}
+
+/// One establishment's records, newest first.
+#[derive(Serialize, Clone)]
+struct History {
+    establishment_id: String,
+    establishment_name: String,
+    totals: EnforcementCounts,
+    last_record: String,
+    records: Vec<EnforcementRecord>,
+}
+
+/// Every establishment's enforcement history, built once per load.
+#[derive(Default, Clone)]
+pub(crate) struct Enforcement {
+    by_establishment: BTreeMap<String, History>,
+    /// Records the importer couldn't link to an establishment.
+    unlinked: usize,
+}
+
+impl Enforcement {
+    pub(crate) fn new(rows: Vec<EnforcementRecord>) -> Self {
+        let mut by_establishment: BTreeMap<String, History> = BTreeMap::new();
+        let mut unlinked = 0;
+        for row in rows {
+            if row.establishment_id.is_empty() {
+                unlinked += 1;
+                continue;
+            }
+            let history = by_establishment
+                .entry(row.establishment_id.clone())
+                .or_insert_with(|| History {
+                    establishment_id: row.establishment_id.clone(),
+                    establishment_name: row.establishment_name.clone(),
+                    totals: EnforcementCounts::default(),
+                    last_record: String::new(),
+                    records: Vec::new(),
+                });
+            history.totals.add(&row);
+            history.records.push(row);
+        }
+        for history in by_establishment.values_mut() {
+            history.records.sort_by(|a, b| b.date.cmp(&a.date));
+            history.last_record = history
+                .records
+                .first()
+                .map(|record| record.date.clone())
+                .unwrap_or_default();
+        }
+        Enforcement {
+            by_establishment,
+            unlinked,
+        }
+    }
+
+    /// Establishments with at least one record.
+    pub(crate) fn establishments(&self) -> usize {
+        self.by_establishment.len()
+    }
+
+    /// The records of `establishment_id`, for its priority score.
+    pub(crate) fn count(&self, establishment_id: &str) -> usize {
+        self.by_establishment
+            .get(establishment_id)
+            .map_or(0, |history| history.totals.records)
+    }
+
+    /// The records linked to an establishment, by establishment and newest
+    /// first.
+    pub(crate) fn records(&self) -> impl Iterator<Item = &EnforcementRecord> {
+        self.by_establishment
+            .values()
+            .flat_map(|history| &history.records)
+    }
+}
+
+/// Every CSV in `us/enforcement/`.
+#[tracing::instrument(name = "load", skip_all, fields(dataset = "enforcement"), err)]
+pub(crate) fn read_enforcement(
+    dataset: &Dataset,
+) -> Result<Vec<EnforcementRecord>, Box<dyn std::error::Error>> {
+    let mut rows = Vec::new();
+    for (name, contents) in dataset.files_in("us/enforcement") {
+        if !name.ends_with(".csv") {
+            continue;
+        }
+        schema::check::<EnforcementRecord>(&format!("us/enforcement/{}", name), contents)?;
+        let mut reader = csv::Reader::from_reader(contents);
+        for result in reader.deserialize() {
+            let row: EnforcementRecord =
+                result.map_err(|e| format!("us/enforcement/{}: {}", name, e))?;
+            rows.push(row);
+        }
+    }
+    Ok(rows)
+}
+
+#[derive(Deserialize)]
+pub struct EnforcementParams {
+    /// Only records about the handling of animals.
+    #[serde(default)]
+    humane_handling: bool,
+}
+
+/// An establishment's totals and where it is, for marking it on the map.
+#[derive(Serialize)]
+struct Summary<'a> {
+    establishment_id: &'a str,
+    establishment_name: &'a str,
+    latitude: Option<f64>,
+    longitude: Option<f64>,
+    last_record: &'a str,
+    #[serde(flatten)]
+    totals: EnforcementCounts,
+}
+
+#[derive(Serialize)]
+struct Listing<'a> {
+    establishments: Vec<Summary<'a>>,
+    /// Records that name no establishment in the directory.
+    unlinked: usize,
+}
+
+/// GET /api/enforcement: every establishment with records, with
+/// `humane_handling=true` only those with humane handling records.
+pub async fn get_enforcement_handler(
+    State(state): State<AppState>,
+    Query(params): Query<EnforcementParams>,
+) -> impl IntoResponse {
+    let reports = state.reports();
+    let index = state.location_index();
+    let enforcement = &reports.enforcement;
+    let establishments = enforcement
+        .by_establishment
+        .values()
+        .filter(|history| !params.humane_handling || history.totals.humane_handling > 0)
+        .map(|history| {
+            let location = index.locations.iter().find(|loc| {
+                loc.country == "us" && loc.establishment_id == history.establishment_id
+            });
+            Summary {
+                establishment_id: &history.establishment_id,
+                establishment_name: location
+                    .map_or(&history.establishment_name, |loc| &loc.establishment_name),
+                latitude: location.map(|loc| loc.latitude),
+                longitude: location.map(|loc| loc.longitude),
+                last_record: &history.last_record,
+                totals: history.totals,
+            }
+        })
+        .collect();
+    Json(Listing {
+        establishments,
+        unlinked: enforcement.unlinked,
+    })
+    .into_response()
+}
+
+/// GET /api/locations/{establishment_id}/enforcement: a US location's
+/// enforcement actions and noncompliance records, newest first.
+pub async fn get_location_enforcement_handler(
+    State(state): State<AppState>,
+    Path(establishment_id): Path<String>,
+    Query(params): Query<EnforcementParams>,
+) -> impl IntoResponse {
+    let index = state.location_index();
+    let Some(location) = index
+        .locations
+        .iter()
+        .find(|loc| loc.country == "us" && loc.establishment_id == establishment_id)
+    else {
+        return (
+            StatusCode::NOT_FOUND,
+            format!("No US location with establishment id {}", establishment_id),
+        )
+            .into_response();
+    };
+
+    let reports = state.reports();
+    let records: Vec<&EnforcementRecord> = reports
+        .enforcement
+        .by_establishment
+        .get(&establishment_id)
+        .map(|history| history.records.iter().collect())
+        .unwrap_or_default();
+    let mut totals = EnforcementCounts::default();
+    for record in &records {
+        totals.add(record);
+    }
+    let records: Vec<&EnforcementRecord> = records
+        .into_iter()
+        .filter(|record| !params.humane_handling || record.humane_handling)
+        .collect();
+    Json(serde_json::json!({
+        "establishment_id": location.establishment_id,
+        "establishment_name": location.establishment_name,
+        "totals": totals,
+        "records": records,
+    }))
+    .into_response()
+}
@@ -0,0 +1,277 @@
+// Until Every Cage is Empty
+// Copyright (C) 2025 Eli Perez
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+// Contact the developer directly at untileverycageproject@protonmail.com
+//! The horse slaughter trade. No US plant has slaughtered horses since 2007,
+//! so American horses are gathered at assembly points and export pens and
+//! trucked to plants in Mexico and Canada, none of which the FSIS directory
+//! lists. USDA's export records (approved export facilities and the
+//! owner/shipper certificates of the Slaughter Horse Transport Program) name
+//! every stage. Each country's sites are CSV files in `<country>/equine/`,
+//! one per source, written by the equine importer: the US pens and assembly
+//! points under `us/`, the plants they ship to under `mx/` and `ca/`.
+
+use axum::extract::{Query, State};
+use axum::http::{HeaderMap, StatusCode};
+use axum::response::IntoResponse;
+use serde::{Deserialize, Serialize};
+
+use crate::activities::Activity;
+use crate::geocode;
+use crate::ids;
+use crate::paging::Page;
+use crate::schema;
+use crate::species::{self, Taxon};
+use crate::store::Dataset;
+use crate::{AppState, geojson};
+
+/// A row of an equine CSV.
+#[derive(Serialize, Deserialize, Debug, Default, Clone)]
+#[serde(default)]
+pub struct EquineSite {
+    /// The USDA approval number of a pen, the establishment number of a
+    /// plant (Mexico's TIF, Canada's CFIA number), or an `ids::content_id`
+    /// when the records give none.
+    pub site_id: String,
+    pub name: String,
+    /// As the records word it: "Approved export facility", "Assembly
+    /// point", "Horse slaughter plant"...
+    pub operation: String,
+    /// The dataset the row came from, e.g. "USDA owner/shipper certificates".
+    pub source: String,
+    pub street: String,
+    pub city: String,
+    pub county: String,
+    /// The state, or a foreign plant's province.
+    pub state: String,
+    pub zip: String,
+    /// Species as the records name them, separated by "; ".
+    pub species: String,
+    /// Animals shipped from a US site, or received from the US by a plant,
+    /// over `period`.
+    pub horses: Option<u64>,
+    /// The span the records cover, e.g. "2023" or "2022-2024".
+    pub period: String,
+    /// The border crossing the animals leave the US through, e.g.
+    /// "Eagle Pass, TX".
+    pub port_of_exit: String,
+    pub latitude: f64,
+    pub longitude: f64,
+}
+
+/// Where in the trade a site is.
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum Stage {
+    /// Auctions, feedlots and kill buyers' lots horses are gathered at.
+    Assembly,
+    /// Facilities approved to hold and inspect horses before they cross.
+    ExportPen,
+    /// The plants the horses are slaughtered at.
+    Slaughter,
+}
+
+impl Stage {
+    fn of(operation: &str) -> Stage {
+        let operation = operation.to_lowercase();
+        if operation.contains("slaughter") || operation.contains("plant") {
+            Stage::Slaughter
+        } else if operation.contains("export") || operation.contains("pen") {
+            Stage::ExportPen
+        } else {
+            Stage::Assembly
+        }
+    }
+
+    fn parse(value: &str) -> Option<Stage> {
+        match value
+            .trim()
+            .to_lowercase()
+            .replace([' ', '-'], "_")
+            .as_str()
+        {
+            "assembly" => Some(Stage::Assembly),
+            "export_pen" => Some(Stage::ExportPen),
+            "slaughter" => Some(Stage::Slaughter),
+            _ => None,
+        }
+    }
+}
+
+/// A site as served, with its stage, species and activities worked out.
+#[derive(Serialize, Debug, Clone)]
+pub(crate) struct EquineFacility {
+    /// The site's facility ID (see `ids`).
+    pub(crate) facility_id: String,
+    pub(crate) country: String,
+    #[serde(flatten)]
+    pub(crate) site: EquineSite,
+    pub(crate) stage: Stage,
+    /// Taxa from the ontology; horses where the records name none.
+    pub(crate) taxa: Vec<&'static str>,
+    /// Species group IDs from the ontology (`horse`, `donkey`, `mule`).
+    pub(crate) species_ids: Vec<&'static str>,
+    /// `equine_export`, with `meat_slaughter` for the plants.
+    pub(crate) activities: Vec<Activity>,
+}
+
+impl geojson::Feature for EquineFacility {}
+
+impl EquineFacility {
+    fn new(country: &str, site: EquineSite) -> Self {
+        let stage = Stage::of(&site.operation);
+        let mut taxa: Vec<&'static str> = species::mentioned_in(&site.species)
+            .iter()
+            .map(|taxon| taxon.id)
+            .filter(|id| species::is_within(id, "equine"))
+            .collect();
+        if taxa.is_empty() {
+            taxa.push("horse");
+        }
+        let activities = match stage {
+            Stage::Slaughter => vec![Activity::MeatSlaughter, Activity::EquineExport],
+            Stage::Assembly | Stage::ExportPen => vec![Activity::EquineExport],
+        };
+        EquineFacility {
+            facility_id: ids::facility_id("equine", country, &site.site_id),
+            country: geocode::country_code(country),
+            species_ids: species::groups(taxa.iter().copied()),
+            taxa,
+            site,
+            stage,
+            activities,
+        }
+    }
+}
+
+/// Every country's equine export and slaughter sites. Mexico and Canada have
+/// no locations, so the countries are those with an `equine/` directory
+/// rather than `Dataset::countries`.
+pub(crate) fn read_equine(
+    dataset: &Dataset,
+) -> Result<Vec<EquineFacility>, Box<dyn std::error::Error>> {
+    let mut facilities = Vec::new();
+    for (path, contents) in dataset.files() {
+        let Some((country, name)) = path
+            .split_once('/')
+            .and_then(|(country, rest)| Some((country, rest.strip_prefix("equine/")?)))
+        else {
+            continue;
+        };
+        if name.contains('/') || !name.ends_with(".csv") {
+            continue;
+        }
+        schema::check::<EquineSite>(path, contents)?;
+        let mut reader = csv::Reader::from_reader(contents);
+        for result in reader.deserialize() {
+            let site: EquineSite = result.map_err(|e| format!("{}: {}", path, e))?;
+            facilities.push(EquineFacility::new(country, site));
+        }
+    }
+    Ok(facilities)
+}
+
+#[derive(Deserialize)]
+pub struct EquineParams {
+    country_code: Option<String>,
+    state: Option<String>,
+    /// Comma-separated species, e.g. `donkey,mule` or `equine`.
+    species: Option<String>,
+    /// Comma-separated stages: `assembly`, `export_pen` or `slaughter`.
+    stage: Option<String>,
+    format: Option<String>,
+    limit: Option<usize>,
+    offset: Option<usize>,
+    fields: Option<String>,
+}
+
+/// GET /api/equine: US assembly points and export pens for slaughter
+/// horses, and the foreign plants they are shipped to.
+pub async fn get_equine_handler(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Query(params): Query<EquineParams>,
+) -> impl IntoResponse {
+    let parse = || -> Result<(Vec<&'static Taxon>, Vec<Stage>, Page), String> {
+        let species = params
+            .species
+            .as_deref()
+            .unwrap_or_default()
+            .split(',')
+            .map(str::trim)
+            .filter(|term| !term.is_empty())
+            .map(|term| species::lookup(term).ok_or_else(|| format!("Unknown species {}", term)))
+            .collect::<Result<_, _>>()?;
+        let stages = params
+            .stage
+            .as_deref()
+            .unwrap_or_default()
+            .split(',')
+            .filter(|value| !value.trim().is_empty())
+            .map(|value| {
+                Stage::parse(value).ok_or_else(|| {
+                    format!(
+                        "Unknown stage {}, expected assembly, export_pen or slaughter",
+                        value
+                    )
+                })
+            })
+            .collect::<Result<_, _>>()?;
+        let page = Page::parse(params.limit, params.offset, params.fields.as_deref())?;
+        Ok((species, stages, page))
+    };
+    let (species, stages, page) = match parse() {
+        Ok(parsed) => parsed,
+        Err(e) => return (StatusCode::BAD_REQUEST, e).into_response(),
+    };
+
+    let reports = state.reports();
+    if species.is_empty()
+        && stages.is_empty()
+        && params.country_code.is_none()
+        && params.state.is_none()
+    {
+        return geojson::respond_all(&reports.equine, &headers, params.format.as_deref(), &page);
+    }
+
+    let selected: Vec<&EquineFacility> = reports
+        .equine
+        .iter()
+        .filter(|facility| {
+            params
+                .country_code
+                .as_ref()
+                .is_none_or(|country| facility.country == geocode::country_code(country))
+        })
+        .filter(|facility| {
+            params
+                .state
+                .as_ref()
+                .is_none_or(|state| facility.site.state.eq_ignore_ascii_case(state))
+        })
+        .filter(|facility| stages.is_empty() || stages.contains(&facility.stage))
+        .filter(|facility| {
+            species.is_empty()
+                || species.iter().any(|wanted| {
+                    facility
+                        .taxa
+                        .iter()
+                        .any(|id| species::is_within(id, wanted.id))
+                })
+        })
+        .collect();
+    geojson::respond(&selected, &headers, params.format.as_deref(), &page)
+}
@@ -0,0 +1,234 @@
+// Until Every Cage is Empty
+// Copyright (C) 2025 Eli Perez
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+// Contact the developer directly at untileverycageproject@protonmail.com
+
+//! The section and activity codes EU member states use in their lists of
+//! establishments approved under Regulation (EC) No 853/2004 (the lists
+//! published through TRACES), mapped to our activities.
+
+use crate::activities::Activity;
+
+/// Sections of Annex III of Regulation (EC) No 853/2004, plus section 0 for
+/// the general activities.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Section {
+    General,
+    DomesticUngulates,
+    PoultryAndLagomorphs,
+    FarmedGame,
+    WildGame,
+    MincedMeatAndPreparations,
+    MeatProducts,
+    LiveBivalveMolluscs,
+    FisheryProducts,
+    Milk,
+    Eggs,
+    FrogsLegsAndSnails,
+    RenderedFats,
+    TreatedStomachs,
+    Gelatine,
+    Collagen,
+}
+
+const SECTIONS: &[(Section, &str)] = &[
+    (Section::General, "0"),
+    (Section::DomesticUngulates, "I"),
+    (Section::PoultryAndLagomorphs, "II"),
+    (Section::FarmedGame, "III"),
+    (Section::WildGame, "IV"),
+    (Section::MincedMeatAndPreparations, "V"),
+    (Section::MeatProducts, "VI"),
+    (Section::LiveBivalveMolluscs, "VII"),
+    (Section::FisheryProducts, "VIII"),
+    (Section::Milk, "IX"),
+    (Section::Eggs, "X"),
+    (Section::FrogsLegsAndSnails, "XI"),
+    (Section::RenderedFats, "XII"),
+    (Section::TreatedStomachs, "XIII"),
+    (Section::Gelatine, "XIV"),
+    (Section::Collagen, "XV"),
+];
+
+impl Section {
+    /// The Roman numeral used in the lists ("0" for general activities).
+    pub fn numeral(self) -> &'static str {
+        SECTIONS
+            .iter()
+            .find(|(section, _)| *section == self)
+            .map(|(_, numeral)| *numeral)
+            .unwrap_or_default()
+    }
+
+    /// Finds the section in a numeral or label such as "VIII",
+    /// "Sezione VIII - Prodotti della pesca" or "Section 8".
+    pub fn parse(value: &str) -> Option<Section> {
+        value
+            .split(|c: char| !c.is_ascii_alphanumeric())
+            .find_map(|token| {
+                SECTIONS
+                    .iter()
+                    .enumerate()
+                    .find_map(|(i, (section, numeral))| {
+                        let arabic = token.parse::<usize>().is_ok_and(|n| n == i);
+                        (token.eq_ignore_ascii_case(numeral) || arabic).then_some(*section)
+                    })
+            })
+    }
+}
+
+/// Activity codes from the list legends. Several are only meaningful
+/// together with a section: SH is a red meat slaughterhouse in section I and a
+/// poultry slaughterhouse in section II; PP is a processing plant for whatever
+/// the section covers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum ActivityCode {
+    /// SH
+    Slaughterhouse,
+    /// CP
+    CuttingPlant,
+    /// PP
+    ProcessingPlant,
+    /// CS
+    ColdStore,
+    /// RW
+    Rewrapping,
+    /// WM
+    WholesaleMarket,
+    /// GHE
+    GameHandling,
+    /// MM
+    MincedMeat,
+    /// MP
+    MeatPreparations,
+    /// MSM
+    MechanicallySeparatedMeat,
+    /// DC
+    DispatchCentre,
+    /// PC
+    PurificationCentre,
+    /// FV
+    FactoryVessel,
+    /// ZV
+    FreezerVessel,
+    /// FFPP
+    FreshFisheryProducts,
+    /// AH
+    AuctionHall,
+    /// EPC
+    EggPackingCentre,
+    /// EPP
+    EggProductsPlant,
+    /// LEP
+    LiquidEggPlant,
+    /// CC
+    CollectionCentre,
+}
+
+const CODES: &[(ActivityCode, &str)] = &[
+    (ActivityCode::Slaughterhouse, "SH"),
+    (ActivityCode::CuttingPlant, "CP"),
+    (ActivityCode::ProcessingPlant, "PP"),
+    (ActivityCode::ColdStore, "CS"),
+    (ActivityCode::Rewrapping, "RW"),
+    (ActivityCode::WholesaleMarket, "WM"),
+    (ActivityCode::GameHandling, "GHE"),
+    (ActivityCode::MincedMeat, "MM"),
+    (ActivityCode::MeatPreparations, "MP"),
+    (ActivityCode::MechanicallySeparatedMeat, "MSM"),
+    (ActivityCode::DispatchCentre, "DC"),
+    (ActivityCode::PurificationCentre, "PC"),
+    (ActivityCode::FactoryVessel, "FV"),
+    (ActivityCode::FreezerVessel, "ZV"),
+    (ActivityCode::FreshFisheryProducts, "FFPP"),
+    (ActivityCode::AuctionHall, "AH"),
+    (ActivityCode::EggPackingCentre, "EPC"),
+    (ActivityCode::EggProductsPlant, "EPP"),
+    (ActivityCode::LiquidEggPlant, "LEP"),
+    (ActivityCode::CollectionCentre, "CC"),
+];
+
+impl ActivityCode {
+    pub fn code(self) -> &'static str {
+        CODES
+            .iter()
+            .find(|(code, _)| *code == self)
+            .map(|(_, text)| *text)
+            .unwrap_or_default()
+    }
+
+    pub fn from_code(text: &str) -> Option<ActivityCode> {
+        CODES
+            .iter()
+            .find(|(_, code)| text.eq_ignore_ascii_case(code))
+            .map(|(code, _)| *code)
+    }
+}
+
+/// Every code in a cell like "SH, CP" or "CP/MM". Returns the known codes and
+/// the tokens that aren't codes, so importers can report them.
+pub fn parse_codes(text: &str) -> (Vec<ActivityCode>, Vec<String>) {
+    let mut codes = Vec::new();
+    let mut unknown = Vec::new();
+    for token in text
+        .split(|c: char| !c.is_ascii_alphanumeric())
+        .filter(|token| !token.is_empty())
+    {
+        match ActivityCode::from_code(token) {
+            Some(code) if !codes.contains(&code) => codes.push(code),
+            Some(_) => {}
+            None => unknown.push(token.to_uppercase()),
+        }
+    }
+    (codes, unknown)
+}
+
+/// The activities an approval stands for. Empty for approvals outside the
+/// map's scope, such as dairies or wholesale markets.
+pub fn activities(section: Section, code: ActivityCode) -> &'static [Activity] {
+    use Activity::*;
+    use ActivityCode::*;
+    use Section::*;
+
+    match (section, code) {
+        (General, ColdStore) => &[ColdStorage],
+        (DomesticUngulates | FarmedGame, Slaughterhouse) => &[MeatSlaughter],
+        (PoultryAndLagomorphs, Slaughterhouse) => &[PoultrySlaughter],
+        (PoultryAndLagomorphs, CuttingPlant) => &[PoultryProcessing],
+        (DomesticUngulates | FarmedGame, CuttingPlant) => &[MeatProcessing],
+        (WildGame, GameHandling) => &[HuntingGame],
+        (WildGame, CuttingPlant) => &[MeatProcessing, HuntingGame],
+        (MincedMeatAndPreparations, MincedMeat | MeatPreparations | MechanicallySeparatedMeat) => {
+            &[MeatProcessing]
+        }
+        (MeatProducts, ProcessingPlant) => &[MeatProcessing],
+        (LiveBivalveMolluscs, DispatchCentre | PurificationCentre | ProcessingPlant) => {
+            &[FishProcessing]
+        }
+        (
+            FisheryProducts,
+            FactoryVessel | FreezerVessel | FreshFisheryProducts | ProcessingPlant,
+        ) => &[FishProcessing],
+        (Eggs, EggPackingCentre | EggProductsPlant | LiquidEggPlant) => &[EggProducts],
+        _ => &[],
+    }
+}
+
+/// The `"<section> <code>"` key mapping tables use to override `activities`,
+/// e.g. "II SH".
+pub fn mapping_key(section: Section, code: ActivityCode) -> String {
+    format!("{} {}", section.numeral(), code.code())
+}
@@ -0,0 +1,544 @@
+// Until Every Cage is Empty
+// Copyright (C) 2025 Eli Perez
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+// Contact the developer directly at untileverycageproject@protonmail.com
+use std::collections::HashMap;
+
+use axum::Json;
+use axum::extract::{Path, Query, State};
+use axum::http::{HeaderMap, StatusCode};
+use axum::response::IntoResponse;
+use serde::{Deserialize, Serialize};
+
+use crate::activities::{self, Activity};
+use crate::aquaculture::AquacultureFacility;
+use crate::cafos::CafoFacility;
+use crate::egg_farms::EggFarmFacility;
+use crate::equine::EquineFacility;
+use crate::filter::{ActivityFilter, split_list};
+use crate::fur_farms::FurFarmFacility;
+use crate::geocode;
+use crate::location::{AphisReport, InspectionReport, tested_species};
+use crate::matching::{self, Evidence, Link};
+use crate::paging::Page;
+use crate::served::{Reports, Served};
+use crate::species::{self, Taxon};
+use crate::status::{self, FacilityStatus};
+use crate::{AppState, LocationResponse, geojson, protobuf};
+
+/// The dataset a facility comes from, named like its endpoint.
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Source {
+    Locations,
+    AphisReports,
+    InspectionReports,
+    Aquaculture,
+    FurFarms,
+    EggFarms,
+    Cafos,
+    Equine,
+}
+
+impl Source {
+    fn parse(value: &str) -> Result<Source, String> {
+        match value.trim().to_lowercase().replace('-', "_").as_str() {
+            "locations" => Ok(Source::Locations),
+            "aphis_reports" => Ok(Source::AphisReports),
+            "inspection_reports" => Ok(Source::InspectionReports),
+            "aquaculture" => Ok(Source::Aquaculture),
+            "fur_farms" => Ok(Source::FurFarms),
+            "egg_farms" => Ok(Source::EggFarms),
+            "cafos" => Ok(Source::Cafos),
+            "equine" => Ok(Source::Equine),
+            other => Err(format!("Unknown source {}", other)),
+        }
+    }
+}
+
+/// A record of any dataset in one shape.
+#[derive(Serialize, Debug)]
+pub struct Facility {
+    /// The facility ID (see `ids`), the same across data refreshes.
+    pub id: String,
+    /// The record's id within its dataset: `location:<country>:<establishment_id>`,
+    /// `aphis:<certificate>`, `inspection:<certificate>`,
+    /// `aquaculture:<country>:<site_id>`, `fur_farm:<country>:<farm_id>`,
+    /// `egg_farm:<country>:<farm_id>`, `cafo:<country>:<permit_id>` or
+    /// `equine:<country>:<site_id>`.
+    /// Accepted wherever a facility ID is.
+    pub source_id: String,
+    pub source: Source,
+    pub name: String,
+    pub latitude: f64,
+    pub longitude: f64,
+    pub country: String,
+    pub state: String,
+    pub city: String,
+    pub street: String,
+    pub zip: String,
+    /// The source's own description: activities for locations, registration
+    /// or license type for APHIS records.
+    pub facility_type: String,
+    pub activities: Vec<Activity>,
+    /// Species group IDs from the ontology (`cattle`, `dog`...).
+    pub species: Vec<&'static str>,
+    pub status: FacilityStatus,
+    /// A private residence, served at its ZIP code without a street (see
+    /// `redaction`).
+    pub is_residential: bool,
+}
+
+impl geojson::Feature for Facility {}
+
+fn location_id(loc: &LocationResponse) -> String {
+    format!("location:{}:{}", loc.country, loc.establishment_id)
+}
+
+fn aphis_id(report: &AphisReport) -> String {
+    format!("aphis:{}", report.certificate_number)
+}
+
+fn inspection_id(report: &InspectionReport) -> String {
+    format!("inspection:{}", report.certificate_number)
+}
+
+fn from_location(loc: &LocationResponse) -> Facility {
+    Facility {
+        id: loc.facility_id.clone(),
+        source_id: location_id(loc),
+        source: Source::Locations,
+        name: loc.establishment_name.clone(),
+        latitude: loc.latitude,
+        longitude: loc.longitude,
+        country: loc.country.clone(),
+        state: loc.state.clone(),
+        city: loc.city.clone(),
+        street: loc.street.trim().to_string(),
+        zip: loc.zip.clone(),
+        facility_type: loc.r#type.clone(),
+        activities: loc.activities.clone(),
+        species: species::groups(loc.taxa.iter().copied()),
+        status: loc.status,
+        is_residential: false,
+    }
+}
+
+fn from_aphis_report(report: &AphisReport) -> Facility {
+    Facility {
+        id: report.facility_id.clone().unwrap_or_default(),
+        source_id: aphis_id(report),
+        source: Source::AphisReports,
+        name: report.account_name.clone(),
+        latitude: report.latitude,
+        longitude: report.longitude,
+        country: "us".to_string(),
+        state: report.state.clone(),
+        city: report.city.clone(),
+        street: report.address_line_1.trim().to_string(),
+        zip: report.zip.clone(),
+        facility_type: report.registration_type.clone(),
+        // Every registration class is a research facility.
+        activities: vec![Activity::Laboratory],
+        species: species::groups(tested_species(report)),
+        status: FacilityStatus::from_certificate(&report.certificate_status),
+        is_residential: false,
+    }
+}
+
+fn from_inspection_report(report: &InspectionReport) -> Facility {
+    Facility {
+        id: report.facility_id.clone().unwrap_or_default(),
+        source_id: inspection_id(report),
+        source: Source::InspectionReports,
+        name: report.account_name.clone(),
+        latitude: report.latitude,
+        longitude: report.longitude,
+        country: "us".to_string(),
+        state: report.state.clone(),
+        city: report.city.clone(),
+        street: report.address_line_1.trim().to_string(),
+        zip: report.zip.clone(),
+        facility_type: report.license_type.clone(),
+        activities: activities::parse_description(&report.license_type),
+        species: Vec::new(),
+        status: FacilityStatus::from_certificate(&report.certificate_status),
+        is_residential: report.is_residential,
+    }
+}
+
+fn from_aquaculture(facility: &AquacultureFacility) -> Facility {
+    let site = &facility.site;
+    Facility {
+        id: facility.facility_id.clone(),
+        source_id: format!("aquaculture:{}:{}", facility.country, site.site_id),
+        source: Source::Aquaculture,
+        name: site.name.clone(),
+        latitude: site.latitude,
+        longitude: site.longitude,
+        country: facility.country.clone(),
+        state: site.state.clone(),
+        city: site.city.clone(),
+        street: site.street.trim().to_string(),
+        zip: site.zip.clone(),
+        facility_type: site.operation.clone(),
+        activities: vec![facility.activity],
+        species: facility.species_ids.clone(),
+        status: FacilityStatus::Active,
+        is_residential: false,
+    }
+}
+
+fn from_fur_farm(facility: &FurFarmFacility) -> Facility {
+    let farm = &facility.farm;
+    Facility {
+        id: facility.facility_id.clone(),
+        source_id: format!("fur_farm:{}:{}", facility.country, farm.farm_id),
+        source: Source::FurFarms,
+        name: farm.name.clone(),
+        latitude: farm.latitude,
+        longitude: farm.longitude,
+        country: facility.country.clone(),
+        state: farm.state.clone(),
+        city: farm.city.clone(),
+        street: farm.street.trim().to_string(),
+        zip: farm.zip.clone(),
+        facility_type: "Fur Farm".to_string(),
+        activities: vec![Activity::FurFarm],
+        species: facility.species_ids.clone(),
+        status: FacilityStatus::Active,
+        is_residential: false,
+    }
+}
+
+fn from_egg_farm(facility: &EggFarmFacility) -> Facility {
+    let farm = &facility.farm;
+    Facility {
+        id: facility.facility_id.clone(),
+        source_id: format!("egg_farm:{}:{}", facility.country, farm.farm_id),
+        source: Source::EggFarms,
+        name: farm.name.clone(),
+        latitude: farm.latitude,
+        longitude: farm.longitude,
+        country: facility.country.clone(),
+        state: farm.state.clone(),
+        city: farm.city.clone(),
+        street: farm.street.trim().to_string(),
+        zip: farm.zip.clone(),
+        facility_type: farm.operation.clone(),
+        activities: vec![facility.activity],
+        species: facility.species_ids.clone(),
+        status: FacilityStatus::Active,
+        is_residential: false,
+    }
+}
+
+fn from_cafo(facility: &CafoFacility) -> Facility {
+    let permit = &facility.permit;
+    Facility {
+        id: facility.facility_id.clone(),
+        source_id: format!("cafo:{}:{}", facility.country, permit.permit_id),
+        source: Source::Cafos,
+        name: permit.name.clone(),
+        latitude: permit.latitude,
+        longitude: permit.longitude,
+        country: facility.country.clone(),
+        state: permit.state.clone(),
+        city: permit.city.clone(),
+        street: permit.street.trim().to_string(),
+        zip: permit.zip.clone(),
+        facility_type: "CAFO".to_string(),
+        activities: facility.activities.clone(),
+        species: facility.species_ids.clone(),
+        // Operations keep running under expired permits until they are
+        // renewed, so only a terminated permit means closed.
+        status: if permit.permit_status.to_lowercase().contains("terminated") {
+            FacilityStatus::Closed
+        } else {
+            FacilityStatus::Active
+        },
+        is_residential: false,
+    }
+}
+
+fn from_equine(facility: &EquineFacility) -> Facility {
+    let site = &facility.site;
+    Facility {
+        id: facility.facility_id.clone(),
+        source_id: format!("equine:{}:{}", facility.country, site.site_id),
+        source: Source::Equine,
+        name: site.name.clone(),
+        latitude: site.latitude,
+        longitude: site.longitude,
+        country: facility.country.clone(),
+        state: site.state.clone(),
+        city: site.city.clone(),
+        street: site.street.trim().to_string(),
+        zip: site.zip.clone(),
+        facility_type: site.operation.clone(),
+        activities: facility.activities.clone(),
+        species: facility.species_ids.clone(),
+        status: FacilityStatus::Active,
+        is_residential: false,
+    }
+}
+
+/// Every record of every dataset as a facility, locations first, with the
+/// links between records describing the same site.
+#[derive(Default)]
+pub(crate) struct FacilityIndex {
+    pub(crate) facilities: Served<Facility>,
+    related: Vec<Vec<Link>>,
+    /// Positions by facility ID and by source id.
+    by_id: HashMap<String, usize>,
+}
+
+impl FacilityIndex {
+    pub(crate) fn new(locations: &[LocationResponse], reports: &Reports) -> Self {
+        let facilities: Vec<Facility> = locations
+            .iter()
+            .map(from_location)
+            .chain(reports.aphis.iter().map(from_aphis_report))
+            .chain(reports.inspections.iter().map(from_inspection_report))
+            .chain(reports.aquaculture.iter().map(from_aquaculture))
+            .chain(reports.fur_farms.iter().map(from_fur_farm))
+            .chain(reports.egg_farms.iter().map(from_egg_farm))
+            .chain(reports.cafos.iter().map(from_cafo))
+            .chain(reports.equine.iter().map(from_equine))
+            .collect();
+        let by_id = facilities
+            .iter()
+            .enumerate()
+            .flat_map(|(i, facility)| [(facility.source_id.clone(), i), (facility.id.clone(), i)])
+            .collect();
+        FacilityIndex {
+            related: matching::resolve(&facilities),
+            facilities: Served::new(facilities),
+            by_id,
+        }
+    }
+
+    pub(crate) fn get(&self, id: &str) -> Option<&Facility> {
+        self.by_id.get(id).map(|&i| &self.facilities[i])
+    }
+
+    /// Records of other datasets that probably describe the same site as
+    /// `id`, best first.
+    pub(crate) fn related(&self, id: &str) -> Vec<RelatedFacility<'_>> {
+        let Some(&i) = self.by_id.get(id) else {
+            return Vec::new();
+        };
+        self.related[i]
+            .iter()
+            .map(|link| RelatedFacility {
+                score: link.score,
+                evidence: &link.evidence,
+                facility: &self.facilities[link.facility],
+            })
+            .collect()
+    }
+}
+
+#[derive(Serialize)]
+pub(crate) struct RelatedFacility<'a> {
+    score: f64,
+    evidence: &'a [Evidence],
+    #[serde(flatten)]
+    pub(crate) facility: &'a Facility,
+}
+
+/// The filters /api/facilities and the exports share. Each takes a
+/// comma-separated list, and a facility has to match one value of every
+/// parameter given.
+#[derive(Default)]
+pub(crate) struct FacilityFilter {
+    sources: Vec<Source>,
+    country: Option<String>,
+    states: Vec<String>,
+    species: Vec<&'static Taxon>,
+    activities: Vec<ActivityFilter>,
+    statuses: Vec<FacilityStatus>,
+}
+
+impl FacilityFilter {
+    pub(crate) fn parse(
+        source: Option<&str>,
+        country_code: Option<&str>,
+        state: Option<&str>,
+        species: Option<&str>,
+        activity: Option<&str>,
+        status: Option<&str>,
+    ) -> Result<Self, String> {
+        let sources = split_list(source)
+            .iter()
+            .map(|source| Source::parse(source))
+            .collect::<Result<_, _>>()?;
+        let species = split_list(species)
+            .iter()
+            .map(|term| species::lookup(term).ok_or_else(|| format!("Unknown species {}", term)))
+            .collect::<Result<_, _>>()?;
+        Ok(FacilityFilter {
+            sources,
+            country: country_code.map(geocode::country_code),
+            states: split_list(state),
+            species,
+            activities: split_list(activity)
+                .iter()
+                .map(|term| ActivityFilter::parse(term))
+                .collect(),
+            statuses: status::parse_filter(status)?,
+        })
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.sources.is_empty()
+            && self.country.is_none()
+            && self.states.is_empty()
+            && self.species.is_empty()
+            && self.activities.is_empty()
+            && self.statuses.is_empty()
+    }
+
+    pub(crate) fn matches(&self, facility: &Facility) -> bool {
+        let source_matches = self.sources.is_empty() || self.sources.contains(&facility.source);
+        let country_matches = self
+            .country
+            .as_ref()
+            .is_none_or(|country| facility.country == *country);
+        let state_matches =
+            self.states.is_empty() || self.states.contains(&facility.state.to_lowercase());
+        let species_matches = self.species.is_empty()
+            || self.species.iter().any(|wanted| {
+                facility
+                    .species
+                    .iter()
+                    .any(|id| species::is_within(id, wanted.id))
+            });
+        let activity_matches = self.activities.is_empty() || {
+            let description = facility.facility_type.to_lowercase();
+            self.activities
+                .iter()
+                .any(|wanted| wanted.matches(&facility.activities, &description))
+        };
+        let status_matches = self.statuses.is_empty() || self.statuses.contains(&facility.status);
+        source_matches
+            && country_matches
+            && state_matches
+            && species_matches
+            && activity_matches
+            && status_matches
+    }
+
+    /// The facilities of `index` that match, in index order.
+    pub(crate) fn select<'a>(&self, index: &'a FacilityIndex) -> Vec<&'a Facility> {
+        index
+            .facilities
+            .iter()
+            .filter(|facility| self.matches(facility))
+            .collect()
+    }
+}
+
+#[derive(Deserialize)]
+pub struct FacilityParams {
+    /// Comma-separated sources, e.g. `locations,aphis_reports`.
+    source: Option<String>,
+    country_code: Option<String>,
+    state: Option<String>,
+    species: Option<String>,
+    activity: Option<String>,
+    status: Option<String>,
+    format: Option<String>,
+    limit: Option<usize>,
+    offset: Option<usize>,
+    fields: Option<String>,
+}
+
+/// GET /api/facilities: locations, APHIS research facilities and licensees
+/// in the common `Facility` shape.
+pub async fn get_facilities_handler(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Query(params): Query<FacilityParams>,
+) -> impl IntoResponse {
+    let parse = || -> Result<(FacilityFilter, Page), String> {
+        let filter = FacilityFilter::parse(
+            params.source.as_deref(),
+            params.country_code.as_deref(),
+            params.state.as_deref(),
+            params.species.as_deref(),
+            params.activity.as_deref(),
+            params.status.as_deref(),
+        )?;
+        let page = Page::parse(params.limit, params.offset, params.fields.as_deref())?;
+        Ok((filter, page))
+    };
+    let (filter, page) = match parse() {
+        Ok(parsed) => parsed,
+        Err(e) => return (StatusCode::BAD_REQUEST, e).into_response(),
+    };
+
+    let index = state.facilities();
+    if protobuf::wanted(&headers, params.format.as_deref()) {
+        return if filter.is_empty() {
+            protobuf::respond(&index.facilities, &page)
+        } else {
+            protobuf::respond(&filter.select(&index), &page)
+        };
+    }
+    if filter.is_empty() {
+        return geojson::respond_all(&index.facilities, &headers, params.format.as_deref(), &page);
+    }
+    let selected = filter.select(&index);
+    geojson::respond(&selected, &headers, params.format.as_deref(), &page)
+}
+
+#[derive(Serialize)]
+struct FacilityDetail<'a> {
+    #[serde(flatten)]
+    facility: &'a Facility,
+    related_facilities: Vec<RelatedFacility<'a>>,
+}
+
+/// GET /api/facilities/{id}: the permalink of a record of any dataset, by
+/// facility ID or source id, with the records matched to it.
+pub async fn get_facility_handler(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+) -> impl IntoResponse {
+    let index = state.facilities();
+    let Some(facility) = index.get(&id) else {
+        return (StatusCode::NOT_FOUND, format!("No facility with id {}", id)).into_response();
+    };
+    Json(FacilityDetail {
+        facility,
+        related_facilities: index.related(&id),
+    })
+    .into_response()
+}
+
+/// GET /api/facilities/{id}/related: records of other datasets matched to
+/// this one by name, address and distance.
+pub async fn get_related_facilities_handler(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+) -> impl IntoResponse {
+    let index = state.facilities();
+    if index.get(&id).is_none() {
+        return (StatusCode::NOT_FOUND, format!("No facility with id {}", id)).into_response();
+    }
+    Json(index.related(&id)).into_response()
+}
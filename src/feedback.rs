@@ -0,0 +1,294 @@
+// Until Every Cage is Empty
+// Copyright (C) 2025 Eli Perez
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+// Contact the developer directly at untileverycageproject@protonmail.com
+//! POST /api/feedback?facility_id=: problems people report with a record,
+//! kept for the maintainers and forwarded to `FEEDBACK_WEBHOOK_URL` when set.
+//!
+//! Reports aren't emailed: the server sends no mail, so to get them by email
+//! point the webhook at a webhook-to-email relay, which shows the `text` it
+//! is sent.
+
+use std::error::Error;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::time::Duration;
+
+use axum::Json;
+use axum::extract::{Query, State};
+use axum::http::{HeaderMap, StatusCode};
+use axum::response::IntoResponse;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+use crate::AppState;
+use crate::admin::authorize;
+use crate::errors::ApiError;
+use crate::settings::Settings;
+use crate::submissions::save_json;
+
+/// Longest accepted message or contact.
+const MAX_LENGTH: usize = 2000;
+
+/// Most reports kept at once.
+const MAX_REPORTS: usize = 5000;
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ProblemKind {
+    WrongLocation,
+    WrongSpecies,
+    Closed,
+    Other,
+}
+
+impl ProblemKind {
+    fn id(self) -> &'static str {
+        match self {
+            ProblemKind::WrongLocation => "wrong_location",
+            ProblemKind::WrongSpecies => "wrong_species",
+            ProblemKind::Closed => "closed",
+            ProblemKind::Other => "other",
+        }
+    }
+}
+
+#[derive(Deserialize)]
+pub struct ProblemReport {
+    kind: ProblemKind,
+    #[serde(default)]
+    message: String,
+    /// How to reach the reporter, if they want to be reached.
+    #[serde(default)]
+    contact: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Feedback {
+    pub id: u64,
+    /// An ID from /api/facilities (`f3c9a1e07b2d4f5a6`).
+    pub facility_id: String,
+    pub facility_name: String,
+    pub kind: ProblemKind,
+    pub message: String,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub contact: String,
+    pub reported_at: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub forwarded_at: Option<String>,
+    /// Why forwarding to the webhook failed.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub forward_error: Option<String>,
+}
+
+/// Every report, kept in the JSON file `FEEDBACK_FILE` names. Without it
+/// reports only last until a restart.
+#[derive(Debug, Default)]
+pub struct FeedbackLog {
+    path: Option<PathBuf>,
+    webhook: Option<String>,
+    reports: Mutex<Vec<Feedback>>,
+}
+
+impl FeedbackLog {
+    pub fn from_settings(settings: &Settings) -> Result<Self, Box<dyn Error>> {
+        let webhook = settings
+            .get("FEEDBACK_WEBHOOK_URL")
+            .filter(|url| !url.trim().is_empty())
+            .map(str::to_string);
+        let Some(path) = settings.get("FEEDBACK_FILE") else {
+            return Ok(FeedbackLog {
+                webhook,
+                ..Default::default()
+            });
+        };
+        let path = PathBuf::from(path);
+        let reports = match std::fs::read_to_string(&path) {
+            Ok(source) => serde_json::from_str(&source)
+                .map_err(|e| format!("Failed to parse {}: {}", path.display(), e))?,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Vec::new(),
+            Err(e) => return Err(format!("Failed to read {}: {}", path.display(), e).into()),
+        };
+        Ok(FeedbackLog {
+            path: Some(path),
+            webhook,
+            reports: Mutex::new(reports),
+        })
+    }
+
+    fn save(&self, reports: &[Feedback]) -> Result<(), Box<dyn Error>> {
+        match &self.path {
+            Some(path) => save_json(path, reports),
+            None => Ok(()),
+        }
+    }
+
+    fn add(&self, mut feedback: Feedback) -> Result<Feedback, (StatusCode, String)> {
+        let mut reports = self.reports.lock().unwrap();
+        if reports.len() >= MAX_REPORTS {
+            return Err((
+                StatusCode::SERVICE_UNAVAILABLE,
+                "No more reports can be taken".to_string(),
+            ));
+        }
+        feedback.id = reports.iter().map(|f| f.id).max().unwrap_or_default() + 1;
+        reports.push(feedback.clone());
+        if let Err(e) = self.save(&reports) {
+            reports.pop();
+            return Err((
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("Failed to save report: {}", e),
+            ));
+        }
+        Ok(feedback)
+    }
+
+    /// Notes how forwarding report `id` went. The report itself is already
+    /// saved, so failing to save the note only loses the note.
+    fn forwarded(&self, id: u64, result: Result<(), String>) {
+        let mut reports = self.reports.lock().unwrap();
+        if let Some(feedback) = reports.iter_mut().find(|f| f.id == id) {
+            match result {
+                Ok(()) => feedback.forwarded_at = Some(now()),
+                Err(e) => feedback.forward_error = Some(e),
+            }
+        }
+        let _ = self.save(&reports);
+    }
+
+    fn all(&self) -> Vec<Feedback> {
+        self.reports.lock().unwrap().clone()
+    }
+}
+
+fn now() -> String {
+    chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Secs, true)
+}
+
+/// Posts the report as JSON, with a one-line `text` that chat webhooks and
+/// webhook-to-email relays show as is.
+async fn forward(url: &str, feedback: &Feedback) -> Result<(), String> {
+    let text = format!(
+        "Problem reported with {} ({}): {}. {}",
+        feedback.facility_name,
+        feedback.facility_id,
+        feedback.kind.id(),
+        feedback.message
+    );
+    let mut body = json!({ "text": text.trim() });
+    body["feedback"] = serde_json::to_value(feedback).map_err(|e| e.to_string())?;
+    reqwest::Client::new()
+        .post(url)
+        .timeout(Duration::from_secs(10))
+        .json(&body)
+        .send()
+        .await
+        .and_then(|response| response.error_for_status())
+        .map(|_| ())
+        .map_err(|e| e.to_string())
+}
+
+#[derive(Deserialize)]
+pub struct FeedbackParams {
+    facility_id: Option<String>,
+}
+
+/// POST /api/feedback?facility_id=
+pub async fn post_feedback_handler(
+    State(state): State<AppState>,
+    Query(params): Query<FeedbackParams>,
+    Json(report): Json<ProblemReport>,
+) -> impl IntoResponse {
+    let Some(facility_id) = params.facility_id else {
+        return (
+            StatusCode::BAD_REQUEST,
+            "facility_id is required".to_string(),
+        )
+            .into_response();
+    };
+    if report.message.len() > MAX_LENGTH || report.contact.len() > MAX_LENGTH {
+        return (
+            StatusCode::BAD_REQUEST,
+            format!(
+                "message and contact can't be longer than {} characters",
+                MAX_LENGTH
+            ),
+        )
+            .into_response();
+    }
+    if report.kind == ProblemKind::Other && report.message.trim().is_empty() {
+        return (
+            StatusCode::BAD_REQUEST,
+            "Describe the problem in message".to_string(),
+        )
+            .into_response();
+    }
+    let facilities = state.facilities();
+    let Some(facility) = facilities.get(&facility_id) else {
+        return (
+            StatusCode::NOT_FOUND,
+            format!("No facility with id {}", facility_id),
+        )
+            .into_response();
+    };
+
+    let feedback = Feedback {
+        id: 0,
+        facility_id: facility.id.clone(),
+        facility_name: facility.name.clone(),
+        kind: report.kind,
+        message: report.message.trim().to_string(),
+        contact: report.contact.trim().to_string(),
+        reported_at: now(),
+        forwarded_at: None,
+        forward_error: None,
+    };
+    let feedback = match state.feedback.add(feedback) {
+        Ok(feedback) => feedback,
+        Err((StatusCode::INTERNAL_SERVER_ERROR, e)) => {
+            return ApiError::internal(e).into_response();
+        }
+        Err(e) => return e.into_response(),
+    };
+
+    if let Some(url) = state.feedback.webhook.clone() {
+        let log = state.feedback.clone();
+        let forwarded = feedback.clone();
+        tokio::spawn(async move {
+            let result = forward(&url, &forwarded).await;
+            log.forwarded(forwarded.id, result);
+        });
+    }
+    // The contact stays with the maintainers.
+    (
+        StatusCode::CREATED,
+        Json(json!({ "id": feedback.id, "reported_at": feedback.reported_at })),
+    )
+        .into_response()
+}
+
+/// GET /api/admin/feedback: every report, newest first.
+pub async fn get_feedback_handler(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> impl IntoResponse {
+    if let Err(e) = authorize(&state, &headers) {
+        return e.into_response();
+    }
+    let mut reports = state.feedback.all();
+    reports.reverse();
+    Json(reports).into_response()
+}
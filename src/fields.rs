@@ -0,0 +1,79 @@
+// Until Every Cage is Empty
+// Copyright (C) 2025 Eli Perez
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+// Contact the developer directly at untileverycageproject@protonmail.com
+//! Typed fields out of the source CSVs' loose values: "Yes" flags, counts
+//! written "83.0" or "1,440", and dates in whichever format the release used.
+//!
+//! The readers never fail on a value: what doesn't parse is read as no
+//! value (false, None), and /api/data-quality reports the columns with
+//! unparseable numbers.
+
+use chrono::NaiveDate;
+use serde::{Deserialize, Deserializer};
+
+/// Date formats seen in the releases, tried in order.
+const DATE_FORMATS: &[&str] = &["%Y-%m-%d", "%m/%d/%Y", "%Y/%m/%d", "%d-%m-%Y", "%d.%m.%Y"];
+
+/// Whether a flag column is set: "Yes", "Y", "X", "true" or "1".
+pub fn parse_yes(value: &str) -> bool {
+    matches!(
+        value.trim().to_lowercase().as_str(),
+        "yes" | "y" | "x" | "true" | "1"
+    )
+}
+
+/// A headcount such as "83", "83.0" or "1,440". None for blanks, negative
+/// and fractional values, and anything else that isn't a count.
+pub fn parse_count(value: &str) -> Option<u32> {
+    let value: String = value.trim().chars().filter(|c| *c != ',').collect();
+    let number: f64 = value.parse().ok()?;
+    (number.is_finite() && number >= 0.0 && number.fract() == 0.0 && number <= u32::MAX as f64)
+        .then_some(number as u32)
+}
+
+/// A date such as "2019-03-04", "3/04/2019" or "2019-03-04 00:00:00".
+pub fn parse_date(value: &str) -> Option<NaiveDate> {
+    // Timestamps keep the date.
+    let date = value.trim().split([' ', 'T']).next()?;
+    DATE_FORMATS
+        .iter()
+        .find_map(|format| NaiveDate::parse_from_str(date, format).ok())
+}
+
+/// A flag column, read with `parse_yes` and written back as "Yes" or empty
+/// as the FSIS directory has it.
+pub mod yes {
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(value: &bool, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(if *value { "Yes" } else { "" })
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<bool, D::Error> {
+        Ok(super::parse_yes(&String::deserialize(deserializer)?))
+    }
+}
+
+/// Reads a count column with `parse_count`.
+pub fn count<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<u32>, D::Error> {
+    Ok(parse_count(&String::deserialize(deserializer)?))
+}
+
+/// Reads a date column with `parse_date`.
+pub fn date<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<NaiveDate>, D::Error> {
+    Ok(parse_date(&String::deserialize(deserializer)?))
+}
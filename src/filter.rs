@@ -0,0 +1,271 @@
+// Until Every Cage is Empty
+// Copyright (C) 2025 Eli Perez
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+// Contact the developer directly at untileverycageproject@protonmail.com
+use chrono::NaiveDate;
+use rusqlite::types::Value as SqlValue;
+
+use crate::LocationResponse;
+use crate::activities::Activity;
+use crate::address;
+use crate::species::{self, Taxon};
+use crate::status::{self, FacilityStatus};
+
+/// Server-side filters for /api/locations.
+///
+/// Each parameter takes a comma-separated list; a location has to match one
+/// value of every parameter given (`?state=TX,OK&species=Pigs`).
+#[derive(Default)]
+pub struct LocationFilter {
+    states: Vec<String>,
+    species: Vec<&'static Taxon>,
+    activities: Vec<ActivityFilter>,
+    statuses: Vec<FacilityStatus>,
+    /// Grant dates kept, both ends included.
+    granted_after: Option<NaiveDate>,
+    granted_before: Option<NaiveDate>,
+}
+
+/// An activity ID or label matches the typed activities; anything else is
+/// looked for in the `type` description, as before activities were typed.
+pub(crate) enum ActivityFilter {
+    Kind(Activity),
+    Text(String),
+}
+
+impl ActivityFilter {
+    pub(crate) fn parse(term: &str) -> ActivityFilter {
+        match Activity::parse(term) {
+            Some(activity) => ActivityFilter::Kind(activity),
+            None => ActivityFilter::Text(term.to_lowercase()),
+        }
+    }
+
+    /// `description` is expected in lowercase.
+    pub(crate) fn matches(&self, activities: &[Activity], description: &str) -> bool {
+        match self {
+            ActivityFilter::Kind(activity) => activities.contains(activity),
+            ActivityFilter::Text(text) => description.contains(text.as_str()),
+        }
+    }
+}
+
+/// A `YYYY-MM-DD` query parameter.
+pub(crate) fn date_param(name: &str, value: Option<&str>) -> Result<Option<NaiveDate>, String> {
+    value
+        .map(|value| {
+            NaiveDate::parse_from_str(value.trim(), "%Y-%m-%d")
+                .map_err(|_| format!("{} must be a date like 2025-01-31, not {}", name, value))
+        })
+        .transpose()
+}
+
+pub(crate) fn split_list(value: Option<&str>) -> Vec<String> {
+    value
+        .unwrap_or_default()
+        .split(',')
+        .map(|item| item.trim().to_lowercase())
+        .filter(|item| !item.is_empty())
+        .collect()
+}
+
+impl LocationFilter {
+    /// Fails on species the ontology doesn't know, rather than silently
+    /// returning nothing.
+    pub fn parse(
+        state: Option<&str>,
+        species: Option<&str>,
+        activity: Option<&str>,
+        status: Option<&str>,
+    ) -> Result<Self, String> {
+        let species = split_list(species)
+            .iter()
+            .map(|term| species::lookup(term).ok_or_else(|| format!("Unknown species {}", term)))
+            .collect::<Result<_, _>>()?;
+        Ok(LocationFilter {
+            states: split_list(state),
+            species,
+            activities: split_list(activity)
+                .iter()
+                .map(|term| ActivityFilter::parse(term))
+                .collect(),
+            statuses: status::parse_filter(status)?,
+            ..Default::default()
+        })
+    }
+
+    /// Keeps the locations granted between `after` and `before`, each day
+    /// included. Either bound leaves out the locations without a grant date.
+    pub fn granted(mut self, after: Option<NaiveDate>, before: Option<NaiveDate>) -> Self {
+        self.granted_after = after;
+        self.granted_before = before;
+        self
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.states.is_empty()
+            && self.species.is_empty()
+            && self.activities.is_empty()
+            && self.statuses.is_empty()
+            && self.granted_after.is_none()
+            && self.granted_before.is_none()
+    }
+
+    pub fn matches(&self, loc: &LocationResponse) -> bool {
+        let state_matches =
+            self.states.is_empty() || self.states.contains(&loc.state.to_lowercase());
+
+        // Asking for cattle finds dairy cow slaughterhouses, and asking for
+        // poultry finds all of chickens, turkeys, ducks...
+        let species_matches = self.species.is_empty()
+            || self.species.iter().any(|wanted| {
+                loc.taxa
+                    .iter()
+                    .any(|taxon| species::is_within(taxon, wanted.id))
+            });
+
+        let activity_matches = self.activities.is_empty() || {
+            let description = loc.r#type.to_lowercase();
+            self.activities
+                .iter()
+                .any(|wanted| wanted.matches(&loc.activities, &description))
+        };
+
+        let status_matches = self.statuses.is_empty() || self.statuses.contains(&loc.status);
+
+        let grant_matches = match (self.granted_after, self.granted_before) {
+            (None, None) => true,
+            (after, before) => loc.grant_date.is_some_and(|granted| {
+                after.is_none_or(|after| granted >= after)
+                    && before.is_none_or(|before| granted <= before)
+            }),
+        };
+
+        state_matches && species_matches && activity_matches && status_matches && grant_matches
+    }
+
+    /// `matches` as SQL conditions on the tables `storage::SqliteStorage`
+    /// loads, joined with AND, and their parameters. Kept next to `matches`
+    /// so the two backends answer alike.
+    pub(crate) fn sql(&self) -> (Vec<String>, Vec<SqlValue>) {
+        let mut conditions = Vec::new();
+        let mut params = Vec::new();
+        let placeholders = |count: usize| vec!["?"; count].join(", ");
+
+        if !self.states.is_empty() {
+            conditions.push(format!("state IN ({})", placeholders(self.states.len())));
+            params.extend(self.states.iter().cloned().map(SqlValue::Text));
+        }
+        // The taxa table lists each taxon's lineage, so a parent matches.
+        if !self.species.is_empty() {
+            conditions.push(format!(
+                "id IN (SELECT location_id FROM location_taxa WHERE taxon IN ({}))",
+                placeholders(self.species.len())
+            ));
+            params.extend(
+                self.species
+                    .iter()
+                    .map(|taxon| SqlValue::Text(taxon.id.to_string())),
+            );
+        }
+        if !self.activities.is_empty() {
+            let alternatives: Vec<&str> = self
+                .activities
+                .iter()
+                .map(|wanted| match wanted {
+                    ActivityFilter::Kind(activity) => {
+                        params.push(SqlValue::Text(activity.id().to_string()));
+                        "id IN (SELECT location_id FROM location_activities WHERE activity = ?)"
+                    }
+                    ActivityFilter::Text(text) => {
+                        params.push(SqlValue::Text(text.clone()));
+                        "instr(type, ?) > 0"
+                    }
+                })
+                .collect();
+            conditions.push(format!("({})", alternatives.join(" OR ")));
+        }
+        if !self.statuses.is_empty() {
+            conditions.push(format!("status IN ({})", placeholders(self.statuses.len())));
+            params.extend(
+                self.statuses
+                    .iter()
+                    .map(|status| SqlValue::Text(status.id().to_string())),
+            );
+        }
+        if let Some(after) = self.granted_after {
+            conditions.push("grant_date >= ?".to_string());
+            params.push(SqlValue::Text(after.to_string()));
+        }
+        if let Some(before) = self.granted_before {
+            conditions.push("grant_date <= ?".to_string());
+            params.push(SqlValue::Text(before.to_string()));
+        }
+        (conditions, params)
+    }
+}
+
+/// Filters for the APHIS reports by their checked address (see `address`):
+/// `city` and `state` take comma-separated lists, ignoring case, and `zip` a
+/// prefix, so `zip=841` is the ZIP3 area.
+#[derive(Default)]
+pub(crate) struct AddressFilter {
+    cities: Vec<String>,
+    states: Vec<String>,
+    zip: Option<String>,
+}
+
+impl AddressFilter {
+    /// Fails on a state that isn't a USPS code or a ZIP that isn't digits,
+    /// which could match nothing.
+    pub(crate) fn parse(
+        city: Option<&str>,
+        state: Option<&str>,
+        zip: Option<&str>,
+    ) -> Result<Self, String> {
+        let states = split_list(state);
+        if let Some(unknown) = states.iter().find(|code| address::state(code).is_empty()) {
+            return Err(format!(
+                "Unknown state {}, expected a two-letter USPS code",
+                unknown
+            ));
+        }
+        let zip = zip.map(str::trim).filter(|zip| !zip.is_empty());
+        if let Some(zip) = zip
+            && !zip.chars().all(|c| c.is_ascii_digit() || c == '-')
+        {
+            return Err(format!("Invalid ZIP {}", zip));
+        }
+        Ok(AddressFilter {
+            cities: split_list(city),
+            states,
+            zip: zip.map(str::to_string),
+        })
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.cities.is_empty() && self.states.is_empty() && self.zip.is_none()
+    }
+
+    pub(crate) fn matches(&self, city: &str, state: &str, zip: &str) -> bool {
+        (self.cities.is_empty() || self.cities.contains(&city.to_lowercase()))
+            && (self.states.is_empty() || self.states.contains(&state.to_lowercase()))
+            && self
+                .zip
+                .as_deref()
+                .is_none_or(|prefix| zip.starts_with(prefix))
+    }
+}
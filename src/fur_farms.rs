@@ -0,0 +1,176 @@
+// Until Every Cage is Empty
+// Copyright (C) 2025 Eli Perez
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+// Contact the developer directly at untileverycageproject@protonmail.com
+//! Fur farms, which appear in neither the FSIS nor the APHIS data. Each
+//! country's farms are CSV files in `<country>/fur_farms/`, one per source,
+//! written by the fur farm importer.
+
+use axum::extract::{Query, State};
+use axum::http::{HeaderMap, StatusCode};
+use axum::response::IntoResponse;
+use serde::{Deserialize, Serialize};
+
+use crate::geocode;
+use crate::ids;
+use crate::paging::Page;
+use crate::schema;
+use crate::species::{self, Taxon};
+use crate::store::Dataset;
+use crate::{AppState, geojson};
+
+/// A row of a fur farm CSV.
+#[derive(Serialize, Deserialize, Debug, Default, Clone)]
+#[serde(default)]
+pub struct FurFarm {
+    /// The premises or license number, or an `ids::content_id` when the
+    /// source has none.
+    pub farm_id: String,
+    pub name: String,
+    /// The dataset the row came from, e.g. "USDA mink farm list".
+    pub source: String,
+    pub street: String,
+    pub city: String,
+    pub county: String,
+    pub state: String,
+    pub zip: String,
+    /// Species as the source names them, separated by "; ".
+    pub species: String,
+    /// Animals on the farm when the source gives a count.
+    pub animals: Option<u64>,
+    pub latitude: f64,
+    pub longitude: f64,
+}
+
+/// A farm as served, with its species worked out.
+#[derive(Serialize, Debug, Clone)]
+pub(crate) struct FurFarmFacility {
+    /// The farm's facility ID (see `ids`).
+    pub(crate) facility_id: String,
+    pub(crate) country: String,
+    #[serde(flatten)]
+    pub(crate) farm: FurFarm,
+    /// Species group IDs from the ontology (`mink`, `fox`...).
+    pub(crate) species_ids: Vec<&'static str>,
+}
+
+impl geojson::Feature for FurFarmFacility {}
+
+/// Every country's fur farms.
+pub(crate) fn read_fur_farms(
+    dataset: &Dataset,
+) -> Result<Vec<FurFarmFacility>, Box<dyn std::error::Error>> {
+    let mut farms = Vec::new();
+    for country in dataset.countries() {
+        for (name, contents) in dataset.files_in(&format!("{}/fur_farms", country)) {
+            if !name.ends_with(".csv") {
+                continue;
+            }
+            schema::check::<FurFarm>(&format!("{}/fur_farms/{}", country, name), contents)?;
+            let mut reader = csv::Reader::from_reader(contents);
+            for result in reader.deserialize() {
+                let farm: FurFarm =
+                    result.map_err(|e| format!("{}/fur_farms/{}: {}", country, name, e))?;
+                let species_ids = species::groups(
+                    species::mentioned_in(&farm.species)
+                        .iter()
+                        .map(|taxon| taxon.id),
+                );
+                farms.push(FurFarmFacility {
+                    facility_id: ids::facility_id("fur_farms", country, &farm.farm_id),
+                    country: geocode::country_code(country),
+                    farm,
+                    species_ids,
+                });
+            }
+        }
+    }
+    Ok(farms)
+}
+
+#[derive(Deserialize)]
+pub struct FurFarmParams {
+    country_code: Option<String>,
+    state: Option<String>,
+    /// Comma-separated species, e.g. `mink,fox`.
+    species: Option<String>,
+    format: Option<String>,
+    limit: Option<usize>,
+    offset: Option<usize>,
+    fields: Option<String>,
+}
+
+/// GET /api/fur-farms
+pub async fn get_fur_farms_handler(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Query(params): Query<FurFarmParams>,
+) -> impl IntoResponse {
+    let parse = || -> Result<(Vec<&'static Taxon>, Page), String> {
+        let species = params
+            .species
+            .as_deref()
+            .unwrap_or_default()
+            .split(',')
+            .map(str::trim)
+            .filter(|term| !term.is_empty())
+            .map(|term| species::lookup(term).ok_or_else(|| format!("Unknown species {}", term)))
+            .collect::<Result<_, _>>()?;
+        let page = Page::parse(params.limit, params.offset, params.fields.as_deref())?;
+        Ok((species, page))
+    };
+    let (species, page) = match parse() {
+        Ok(parsed) => parsed,
+        Err(e) => return (StatusCode::BAD_REQUEST, e).into_response(),
+    };
+
+    let reports = state.reports();
+    if species.is_empty() && params.country_code.is_none() && params.state.is_none() {
+        return geojson::respond_all(
+            &reports.fur_farms,
+            &headers,
+            params.format.as_deref(),
+            &page,
+        );
+    }
+
+    let selected: Vec<&FurFarmFacility> = reports
+        .fur_farms
+        .iter()
+        .filter(|facility| {
+            params
+                .country_code
+                .as_ref()
+                .is_none_or(|country| facility.country == geocode::country_code(country))
+        })
+        .filter(|facility| {
+            params
+                .state
+                .as_ref()
+                .is_none_or(|state| facility.farm.state.eq_ignore_ascii_case(state))
+        })
+        .filter(|facility| {
+            species.is_empty()
+                || species.iter().any(|wanted| {
+                    facility
+                        .species_ids
+                        .iter()
+                        .any(|id| species::is_within(id, wanted.id))
+                })
+        })
+        .collect();
+    geojson::respond(&selected, &headers, params.format.as_deref(), &page)
+}
@@ -0,0 +1,380 @@
+// Until Every Cage is Empty
+// Copyright (C) 2025 Eli Perez
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+// Contact the developer directly at untileverycageproject@protonmail.com
+
+//! Geocoding addresses into coordinates, for the datasets that come without
+//! them. The admin geocode job applies results to the served data; the
+//! `geocode` binary writes them back into the CSVs, caching every answer on
+//! disk so reruns don't query the provider again.
+
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::future::Future;
+use std::path::{Path, PathBuf};
+use std::pin::Pin;
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+use crate::settings::Settings;
+
+pub type GeocodeResult = Result<Option<(f64, f64)>, Box<dyn Error + Send + Sync>>;
+pub type GeocodeFuture<'a> = Pin<Box<dyn Future<Output = GeocodeResult> + Send + 'a>>;
+
+/// A geocoding service that turns a free-text address into `(latitude, longitude)`.
+pub trait Geocoder: Send + Sync {
+    fn name(&self) -> &'static str;
+
+    /// Minimum pause between two requests, to stay within the provider's usage policy.
+    fn min_interval(&self) -> Duration;
+
+    fn geocode<'a>(&'a self, address: &'a str) -> GeocodeFuture<'a>;
+}
+
+/// Picks the provider from the environment.
+pub fn provider_from_env() -> Result<Box<dyn Geocoder>, String> {
+    provider_from_settings(&Settings::from_env())
+}
+
+/// Picks the provider from `settings`.
+///
+/// `GEOCODER` selects it (`nominatim` by default, `photon`, or `geocodio`
+/// which also needs `GEOCODIO_API_KEY`).
+pub fn provider_from_settings(settings: &Settings) -> Result<Box<dyn Geocoder>, String> {
+    provider(settings.get("GEOCODER").unwrap_or("nominatim"), settings)
+}
+
+/// The provider called `name`. `NOMINATIM_URL` and `PHOTON_URL` point
+/// Nominatim and Photon at a self-hosted instance.
+pub fn provider(name: &str, settings: &Settings) -> Result<Box<dyn Geocoder>, String> {
+    match name {
+        "nominatim" => Ok(Box::new(Nominatim::new(
+            settings
+                .get("NOMINATIM_URL")
+                .unwrap_or("https://nominatim.openstreetmap.org")
+                .to_string(),
+        ))),
+        "photon" => Ok(Box::new(Photon::new(
+            settings
+                .get("PHOTON_URL")
+                .unwrap_or("https://photon.komoot.io")
+                .to_string(),
+        ))),
+        "geocodio" => {
+            let api_key = settings
+                .get("GEOCODIO_API_KEY")
+                .ok_or("GEOCODIO_API_KEY must be set to use geocodio")?
+                .to_string();
+            Ok(Box::new(Geocodio::new(api_key)))
+        }
+        other => Err(format!("Unknown geocoding provider {}", other)),
+    }
+}
+
+/// The English name of a country code or directory, for address queries.
+pub fn country_name(code: &str) -> &str {
+    match code {
+        "us" => "United States",
+        "au" => "Australia",
+        "br" => "Brazil",
+        "de" => "Germany",
+        "dk" => "Denmark",
+        "es" => "Spain",
+        "fr" => "France",
+        "it" => "Italy",
+        "gb" | "uk" => "United Kingdom",
+        other => other,
+    }
+}
+
+/// The ISO 3166-1 alpha-2 code the API serves, lowercase, for a code or a
+/// country directory in either case: the United Kingdom's directory `uk` is
+/// `gb`, so filters take either.
+pub fn country_code(code: &str) -> String {
+    match code.trim().to_ascii_lowercase().as_str() {
+        "uk" => "gb".to_string(),
+        code => code.to_string(),
+    }
+}
+
+/// The country directory under static_data for a code `country_code`
+/// accepts, `gb` being `uk`.
+pub fn country_dir(code: &str) -> String {
+    match country_code(code).as_str() {
+        "gb" => "uk".to_string(),
+        code => code.to_string(),
+    }
+}
+
+/// Joins the non-empty address parts into a single query string.
+pub fn address_query(parts: &[&str]) -> String {
+    parts
+        .iter()
+        .map(|part| part.trim())
+        .filter(|part| !part.is_empty())
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+fn http_client() -> reqwest::Client {
+    reqwest::Client::builder()
+        .user_agent("UntilEveryCage/0.1 (untileverycageproject@protonmail.com)")
+        .timeout(Duration::from_secs(30))
+        .build()
+        .unwrap_or_default()
+}
+
+pub struct Nominatim {
+    base_url: String,
+    client: reqwest::Client,
+}
+
+impl Nominatim {
+    pub fn new(base_url: String) -> Self {
+        Nominatim {
+            base_url,
+            client: http_client(),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct NominatimPlace {
+    lat: String,
+    lon: String,
+}
+
+impl Geocoder for Nominatim {
+    fn name(&self) -> &'static str {
+        "nominatim"
+    }
+
+    fn min_interval(&self) -> Duration {
+        // https://operations.osmfoundation.org/policies/nominatim/
+        Duration::from_secs(1)
+    }
+
+    fn geocode<'a>(&'a self, address: &'a str) -> GeocodeFuture<'a> {
+        Box::pin(async move {
+            let places: Vec<NominatimPlace> = self
+                .client
+                .get(format!("{}/search", self.base_url))
+                .query(&[("q", address), ("format", "json"), ("limit", "1")])
+                .send()
+                .await?
+                .error_for_status()?
+                .json()
+                .await?;
+
+            Ok(places
+                .first()
+                .and_then(|place| Some((place.lat.parse().ok()?, place.lon.parse().ok()?))))
+        })
+    }
+}
+
+/// Komoot's Photon, an OpenStreetMap geocoder without Nominatim's strict
+/// rate limit.
+pub struct Photon {
+    base_url: String,
+    client: reqwest::Client,
+}
+
+impl Photon {
+    pub fn new(base_url: String) -> Self {
+        Photon {
+            base_url,
+            client: http_client(),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct PhotonResponse {
+    features: Vec<PhotonFeature>,
+}
+
+#[derive(Deserialize)]
+struct PhotonFeature {
+    geometry: PhotonGeometry,
+}
+
+#[derive(Deserialize)]
+struct PhotonGeometry {
+    /// `[longitude, latitude]`.
+    coordinates: (f64, f64),
+}
+
+impl Geocoder for Photon {
+    fn name(&self) -> &'static str {
+        "photon"
+    }
+
+    fn min_interval(&self) -> Duration {
+        Duration::from_millis(200)
+    }
+
+    fn geocode<'a>(&'a self, address: &'a str) -> GeocodeFuture<'a> {
+        Box::pin(async move {
+            let response: PhotonResponse = self
+                .client
+                .get(format!("{}/api", self.base_url))
+                .query(&[("q", address), ("limit", "1")])
+                .send()
+                .await?
+                .error_for_status()?
+                .json()
+                .await?;
+
+            Ok(response.features.first().map(|feature| {
+                let (longitude, latitude) = feature.geometry.coordinates;
+                (latitude, longitude)
+            }))
+        })
+    }
+}
+
+pub struct Geocodio {
+    api_key: String,
+    client: reqwest::Client,
+}
+
+impl Geocodio {
+    pub fn new(api_key: String) -> Self {
+        Geocodio {
+            api_key,
+            client: http_client(),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct GeocodioResponse {
+    results: Vec<GeocodioResult>,
+}
+
+#[derive(Deserialize)]
+struct GeocodioResult {
+    location: GeocodioLocation,
+}
+
+#[derive(Deserialize)]
+struct GeocodioLocation {
+    lat: f64,
+    lng: f64,
+}
+
+impl Geocoder for Geocodio {
+    fn name(&self) -> &'static str {
+        "geocodio"
+    }
+
+    fn min_interval(&self) -> Duration {
+        Duration::from_millis(100)
+    }
+
+    fn geocode<'a>(&'a self, address: &'a str) -> GeocodeFuture<'a> {
+        Box::pin(async move {
+            let response: GeocodioResponse = self
+                .client
+                .get("https://api.geocod.io/v1.7/geocode")
+                .query(&[("q", address), ("api_key", self.api_key.as_str())])
+                .send()
+                .await?
+                .error_for_status()?
+                .json()
+                .await?;
+
+            Ok(response
+                .results
+                .first()
+                .map(|result| (result.location.lat, result.location.lng)))
+        })
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct CacheRow {
+    provider: String,
+    query: String,
+    latitude: Option<f64>,
+    longitude: Option<f64>,
+}
+
+/// Every answer a provider gave, kept in a CSV file between runs. Addresses
+/// the provider couldn't place are cached too, so they aren't retried.
+#[derive(Default)]
+pub struct GeocodeCache {
+    path: PathBuf,
+    answers: BTreeMap<(String, String), Option<(f64, f64)>>,
+}
+
+impl GeocodeCache {
+    /// The cache at `path`, empty if the file doesn't exist yet.
+    pub fn load(path: &Path) -> Result<Self, Box<dyn Error>> {
+        let mut cache = GeocodeCache {
+            path: path.to_path_buf(),
+            answers: BTreeMap::new(),
+        };
+        if !path.exists() {
+            return Ok(cache);
+        }
+        let mut reader = csv::Reader::from_path(path)
+            .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+        for result in reader.deserialize() {
+            let row: CacheRow = result?;
+            let coordinates = row.latitude.zip(row.longitude);
+            cache.answers.insert((row.provider, row.query), coordinates);
+        }
+        Ok(cache)
+    }
+
+    /// The cached answer, `Some(None)` when the provider found nothing.
+    pub fn get(&self, provider: &str, query: &str) -> Option<Option<(f64, f64)>> {
+        self.answers
+            .get(&(provider.to_string(), query.to_string()))
+            .copied()
+    }
+
+    pub fn insert(&mut self, provider: &str, query: &str, coordinates: Option<(f64, f64)>) {
+        self.answers
+            .insert((provider.to_string(), query.to_string()), coordinates);
+    }
+
+    pub fn len(&self) -> usize {
+        self.answers.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.answers.is_empty()
+    }
+
+    pub fn save(&self) -> Result<(), Box<dyn Error>> {
+        let mut wtr = csv::Writer::from_path(&self.path)
+            .map_err(|e| format!("Failed to write {}: {}", self.path.display(), e))?;
+        for ((provider, query), coordinates) in &self.answers {
+            wtr.serialize(CacheRow {
+                provider: provider.clone(),
+                query: query.clone(),
+                latitude: coordinates.map(|(latitude, _)| latitude),
+                longitude: coordinates.map(|(_, longitude)| longitude),
+            })?;
+        }
+        wtr.flush()?;
+        Ok(())
+    }
+}
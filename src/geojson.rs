@@ -0,0 +1,221 @@
+// Until Every Cage is Empty
+// Copyright (C) 2025 Eli Perez
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+// Contact the developer directly at untileverycageproject@protonmail.com
+use std::borrow::Cow;
+
+use axum::Json;
+use axum::http::{HeaderMap, HeaderValue, StatusCode, header};
+use axum::response::{IntoResponse, Response};
+use serde::Serialize;
+use serde_json::{Map, Value, json};
+
+use crate::csv_export;
+use crate::errors::ApiError;
+use crate::paging::Page;
+use crate::served::Served;
+
+pub const GEOJSON_CONTENT_TYPE: &str = "application/geo+json";
+const CSV_CONTENT_TYPE: &str = "text/csv";
+
+/// A record with a point location that can become a GeoJSON Feature.
+pub(crate) trait Feature: Serialize {
+    /// The serialized names of the latitude and longitude fields.
+    const COORDINATE_FIELDS: [&'static str; 2] = ["latitude", "longitude"];
+}
+
+impl<T: Feature> Feature for &T {
+    const COORDINATE_FIELDS: [&'static str; 2] = T::COORDINATE_FIELDS;
+}
+
+impl<T: Feature + Clone> Feature for Cow<'_, T> {
+    const COORDINATE_FIELDS: [&'static str; 2] = T::COORDINATE_FIELDS;
+}
+
+/// What a list endpoint responds with.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Format {
+    Json,
+    GeoJson,
+    Csv,
+}
+
+/// What the client asked for with `?format=json|geojson|csv`, or with
+/// `Accept: application/geo+json` or `text/csv`. An explicit `format` wins
+/// over the header.
+pub(crate) fn negotiate(headers: &HeaderMap, format: Option<&str>) -> Result<Format, String> {
+    match format {
+        Some("geojson") => Ok(Format::GeoJson),
+        Some("json") => Ok(Format::Json),
+        Some("csv") => Ok(Format::Csv),
+        Some(other) => Err(format!("Unsupported format {}", other)),
+        None => {
+            let accept = headers
+                .get(header::ACCEPT)
+                .and_then(|value| value.to_str().ok())
+                .unwrap_or_default();
+            Ok(if accept.contains(GEOJSON_CONTENT_TYPE) {
+                Format::GeoJson
+            } else if accept.contains(CSV_CONTENT_TYPE) {
+                Format::Csv
+            } else {
+                Format::Json
+            })
+        }
+    }
+}
+
+pub(crate) fn to_object<T: Serialize>(item: &T) -> Result<Map<String, Value>, serde_json::Error> {
+    Ok(match serde_json::to_value(item)? {
+        Value::Object(map) => map,
+        other => Map::from_iter([("value".to_string(), other)]),
+    })
+}
+
+/// A FeatureCollection with a Point per item and every other field (or those
+/// `page` selects) as a property. Items without coordinates (0, 0) get a null
+/// geometry.
+pub(crate) fn feature_collection<T: Feature>(
+    items: &[T],
+    page: &Page,
+) -> Result<Value, serde_json::Error> {
+    let [lat_field, lng_field] = T::COORDINATE_FIELDS;
+    let mut features = Vec::with_capacity(items.len());
+
+    for item in items {
+        let mut properties = to_object(item)?;
+        let latitude = properties.shift_remove(lat_field).and_then(|v| v.as_f64());
+        let longitude = properties.shift_remove(lng_field).and_then(|v| v.as_f64());
+        page.select(&mut properties);
+
+        let geometry = match (latitude, longitude) {
+            (Some(lat), Some(lng)) if !(lat == 0.0 && lng == 0.0) => json!({
+                "type": "Point",
+                "coordinates": [lng, lat],
+            }),
+            _ => Value::Null,
+        };
+        features.push(json!({
+            "type": "Feature",
+            "geometry": geometry,
+            "properties": properties,
+        }));
+    }
+
+    Ok(json!({
+        "type": "FeatureCollection",
+        "features": features,
+    }))
+}
+
+/// The page of `items` as JSON, or as GeoJSON or CSV when the client asked
+/// for it, with the total count in `X-Total-Count`.
+pub(crate) fn respond<T: Feature>(
+    items: &[T],
+    headers: &HeaderMap,
+    format: Option<&str>,
+    page: &Page,
+) -> Response {
+    let serialize_error = |e: serde_json::Error| {
+        ApiError::internal(format!("Failed to serialize response: {}", e)).into_response()
+    };
+    if page.has_fields()
+        && let Some(first) = items.first()
+    {
+        match to_object(first) {
+            Ok(sample) => {
+                if let Err(e) = page.check_fields(&sample) {
+                    return (StatusCode::BAD_REQUEST, e).into_response();
+                }
+            }
+            Err(e) => return serialize_error(e),
+        }
+    }
+
+    // The body depends on Accept, so caches must key on it.
+    let extra_headers = [
+        (header::VARY, HeaderValue::from_static("accept")),
+        Page::total_count_header(items.len()),
+    ];
+    let window = page.window(items);
+    match negotiate(headers, format) {
+        Ok(Format::Json) if !page.has_fields() => (extra_headers, Json(window)).into_response(),
+        Ok(Format::Json) => {
+            let objects: Result<Vec<_>, _> = window
+                .iter()
+                .map(|item| {
+                    to_object(item).map(|mut object| {
+                        page.select(&mut object);
+                        object
+                    })
+                })
+                .collect();
+            match objects {
+                Ok(objects) => (extra_headers, Json(objects)).into_response(),
+                Err(e) => serialize_error(e),
+            }
+        }
+        Ok(Format::Csv) => match csv_export::to_csv(window, page) {
+            Ok(body) => (
+                extra_headers,
+                [(header::CONTENT_TYPE, "text/csv; charset=utf-8")],
+                body,
+            )
+                .into_response(),
+            Err(e) => ApiError::internal(format!("Failed to build CSV: {}", e)).into_response(),
+        },
+        Ok(Format::GeoJson) => match feature_collection(window, page) {
+            Ok(collection) => (
+                extra_headers,
+                [(header::CONTENT_TYPE, GEOJSON_CONTENT_TYPE)],
+                collection.to_string(),
+            )
+                .into_response(),
+            Err(e) => ApiError::internal(format!("Failed to build GeoJSON: {}", e)).into_response(),
+        },
+        Err(e) => (StatusCode::BAD_REQUEST, e).into_response(),
+    }
+}
+
+/// Like `respond` for a whole served list, reusing its JSON encoding when the
+/// client wants all of it as JSON.
+pub(crate) fn respond_all<T: Feature>(
+    served: &Served<T>,
+    headers: &HeaderMap,
+    format: Option<&str>,
+    page: &Page,
+) -> Response {
+    if !page.is_whole() || negotiate(headers, format) != Ok(Format::Json) {
+        return respond(served, headers, format, page);
+    }
+    match served.json() {
+        Ok(json) => (
+            [
+                (header::VARY, HeaderValue::from_static("accept")),
+                Page::total_count_header(served.len()),
+                (
+                    header::CONTENT_TYPE,
+                    HeaderValue::from_static("application/json"),
+                ),
+            ],
+            json,
+        )
+            .into_response(),
+        Err(e) => {
+            ApiError::internal(format!("Failed to serialize response: {}", e)).into_response()
+        }
+    }
+}
@@ -0,0 +1,946 @@
+// Until Every Cage is Empty
+// Copyright (C) 2025 Eli Perez
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+// Contact the developer directly at untileverycageproject@protonmail.com
+//! /api/graphql: facilities, APHIS reports and inspection reports with their
+//! relations, in one round trip and with only the fields asked for.
+//!
+//! Queries take the GraphQL query language's operations, fields, aliases,
+//! arguments and variables. Fragments, directives, mutations and
+//! introspection aren't supported. Field names are those of the REST
+//! responses, so `{ facilities(country_code: "us", limit: 5) { name related
+//! { id score } } }` selects from what /api/facilities returns. Names that
+//! aren't GraphQL names, like the APHIS reports' "Certificate Number", are
+//! written in snake_case (`certificate_number`). A field selected twice under
+//! the same name or alias is merged into one, and refused when the two ask
+//! for different fields or arguments.
+//!
+//! Root fields:
+//!
+//! - `facilities(source, country_code, state, species, activity, status,
+//!   limit, offset)`, filtered as on /api/facilities
+//! - `facility(id)`
+//! - `aphis_reports(certificate, limit, offset)`
+//! - `inspection_reports(certificate, limit, offset)`
+//!
+//! Relations: a facility's `related` facilities, `aphis_report` and
+//! `inspection_reports`; an APHIS report's `facility` and
+//! `inspection_reports`; an inspection report's `facility` and
+//! `aphis_report`.
+
+use axum::Json;
+use axum::extract::{Query, State};
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use serde::Deserialize;
+use serde_json::{Map, Value, json};
+
+use crate::AppState;
+use crate::facilities::{Facility, FacilityFilter, FacilityIndex, Source};
+use crate::location::{AphisReport, InspectionReport};
+use crate::paging::Page;
+use crate::served::Reports;
+
+/// How deeply selections, list and object values, and list types may nest,
+/// so relations can't be followed forever and parsing can't run out of stack.
+const MAX_DEPTH: usize = 8;
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Punct(char),
+    Spread,
+    Name(String),
+    Int(i64),
+    Float(f64),
+    Str(String),
+}
+
+fn tokenize(source: &str) -> Result<Vec<Token>, String> {
+    let chars: Vec<char> = source.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        match c {
+            // Commas are insignificant, like whitespace.
+            c if c.is_whitespace() || c == ',' || c == '\u{feff}' => i += 1,
+            '#' => {
+                while i < chars.len() && chars[i] != '\n' {
+                    i += 1;
+                }
+            }
+            '.' if chars[i..].starts_with(&['.', '.', '.']) => {
+                tokens.push(Token::Spread);
+                i += 3;
+            }
+            '{' | '}' | '(' | ')' | '[' | ']' | ':' | '=' | '$' | '!' | '@' | '|' => {
+                tokens.push(Token::Punct(c));
+                i += 1;
+            }
+            '"' => {
+                if chars[i..].starts_with(&['"', '"', '"']) {
+                    return Err("Block strings are not supported".to_string());
+                }
+                i += 1;
+                let mut value = String::new();
+                loop {
+                    match chars.get(i) {
+                        None | Some('\n') => return Err("Unterminated string".to_string()),
+                        Some('"') => break,
+                        Some('\\') => {
+                            let escaped = match chars.get(i + 1) {
+                                Some('n') => '\n',
+                                Some('t') => '\t',
+                                Some('r') => '\r',
+                                Some('b') => '\u{8}',
+                                Some('f') => '\u{c}',
+                                Some('u') => {
+                                    let hex: String = chars.iter().skip(i + 2).take(4).collect();
+                                    i += 4;
+                                    u32::from_str_radix(&hex, 16)
+                                        .ok()
+                                        .and_then(char::from_u32)
+                                        .ok_or_else(|| format!("Bad escape \\u{}", hex))?
+                                }
+                                Some(&other) => other,
+                                None => return Err("Unterminated string".to_string()),
+                            };
+                            value.push(escaped);
+                            i += 2;
+                        }
+                        Some(&other) => {
+                            value.push(other);
+                            i += 1;
+                        }
+                    }
+                }
+                tokens.push(Token::Str(value));
+                i += 1;
+            }
+            c if c == '-' || c.is_ascii_digit() => {
+                let start = i;
+                i += 1;
+                while i < chars.len()
+                    && (chars[i].is_ascii_digit()
+                        || matches!(chars[i], '.' | 'e' | 'E')
+                        || (matches!(chars[i], '+' | '-') && matches!(chars[i - 1], 'e' | 'E')))
+                {
+                    i += 1;
+                }
+                let text: String = chars[start..i].iter().collect();
+                let token = match text.parse::<i64>() {
+                    Ok(value) => Token::Int(value),
+                    Err(_) => {
+                        Token::Float(text.parse().map_err(|_| format!("Bad number {}", text))?)
+                    }
+                };
+                tokens.push(token);
+            }
+            c if c == '_' || c.is_ascii_alphabetic() => {
+                let start = i;
+                while i < chars.len() && (chars[i] == '_' || chars[i].is_ascii_alphanumeric()) {
+                    i += 1;
+                }
+                tokens.push(Token::Name(chars[start..i].iter().collect()));
+            }
+            other => return Err(format!("Unexpected character {:?}", other)),
+        }
+    }
+    Ok(tokens)
+}
+
+/// An argument or default value as written in the query.
+#[derive(Debug, Clone, PartialEq)]
+enum Argument {
+    Literal(Value),
+    Variable(String),
+    List(Vec<Argument>),
+    Object(Vec<(String, Argument)>),
+}
+
+impl Argument {
+    fn resolve(&self, variables: &Map<String, Value>) -> Value {
+        match self {
+            Argument::Literal(value) => value.clone(),
+            Argument::Variable(name) => variables.get(name).cloned().unwrap_or(Value::Null),
+            Argument::List(items) => items.iter().map(|item| item.resolve(variables)).collect(),
+            Argument::Object(fields) => Value::Object(
+                fields
+                    .iter()
+                    .map(|(name, value)| (name.clone(), value.resolve(variables)))
+                    .collect(),
+            ),
+        }
+    }
+}
+
+#[derive(Debug)]
+struct Field {
+    alias: Option<String>,
+    name: String,
+    arguments: Vec<(String, Argument)>,
+    selection: Vec<Field>,
+}
+
+impl Field {
+    /// The key the field's value goes under.
+    fn key(&self) -> &str {
+        self.alias.as_deref().unwrap_or(&self.name)
+    }
+}
+
+#[derive(Debug)]
+struct Operation {
+    name: Option<String>,
+    /// Declared variables and their defaults.
+    variables: Vec<(String, Option<Argument>)>,
+    selection: Vec<Field>,
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        token
+    }
+
+    fn at(&self, c: char) -> bool {
+        self.peek() == Some(&Token::Punct(c))
+    }
+
+    fn expect(&mut self, c: char) -> Result<(), String> {
+        match self.next() {
+            Some(Token::Punct(found)) if found == c => Ok(()),
+            other => Err(format!(
+                "Expected {:?}, found {}",
+                c,
+                describe(other.as_ref())
+            )),
+        }
+    }
+
+    fn name(&mut self) -> Result<String, String> {
+        match self.next() {
+            Some(Token::Name(name)) => Ok(name),
+            other => Err(format!(
+                "Expected a name, found {}",
+                describe(other.as_ref())
+            )),
+        }
+    }
+
+    fn document(&mut self) -> Result<Vec<Operation>, String> {
+        let mut operations = Vec::new();
+        while let Some(token) = self.peek() {
+            let operation = match token {
+                Token::Punct('{') => Operation {
+                    name: None,
+                    variables: Vec::new(),
+                    selection: self.selection_set(1)?,
+                },
+                Token::Name(keyword) if keyword == "query" => {
+                    self.pos += 1;
+                    let name = match self.peek() {
+                        Some(Token::Name(_)) => Some(self.name()?),
+                        _ => None,
+                    };
+                    let variables = if self.at('(') {
+                        self.variable_definitions()?
+                    } else {
+                        Vec::new()
+                    };
+                    if self.at('@') {
+                        return Err("Directives are not supported".to_string());
+                    }
+                    Operation {
+                        name,
+                        variables,
+                        selection: self.selection_set(1)?,
+                    }
+                }
+                Token::Name(keyword) if keyword == "mutation" || keyword == "subscription" => {
+                    return Err("Only queries are supported".to_string());
+                }
+                Token::Name(keyword) if keyword == "fragment" => {
+                    return Err("Fragments are not supported".to_string());
+                }
+                other => return Err(format!("Unexpected {}", describe(Some(other)))),
+            };
+            operations.push(operation);
+        }
+        if operations.is_empty() {
+            return Err("The query has no operation".to_string());
+        }
+        Ok(operations)
+    }
+
+    fn variable_definitions(&mut self) -> Result<Vec<(String, Option<Argument>)>, String> {
+        self.expect('(')?;
+        let mut variables = Vec::new();
+        while !self.at(')') {
+            self.expect('$')?;
+            let name = self.name()?;
+            self.expect(':')?;
+            self.skip_type(0)?;
+            let default = if self.at('=') {
+                self.pos += 1;
+                Some(self.value(0)?)
+            } else {
+                None
+            };
+            variables.push((name, default));
+        }
+        self.expect(')')?;
+        Ok(variables)
+    }
+
+    /// Types are only checked by the resolvers, so they are read and dropped.
+    fn skip_type(&mut self, depth: usize) -> Result<(), String> {
+        if depth > MAX_DEPTH {
+            return Err(format!("List types can nest at most {} deep", MAX_DEPTH));
+        }
+        if self.at('[') {
+            self.pos += 1;
+            self.skip_type(depth + 1)?;
+            self.expect(']')?;
+        } else {
+            self.name()?;
+        }
+        if self.at('!') {
+            self.pos += 1;
+        }
+        Ok(())
+    }
+
+    fn selection_set(&mut self, depth: usize) -> Result<Vec<Field>, String> {
+        if depth > MAX_DEPTH {
+            return Err(format!("Selections can nest at most {} deep", MAX_DEPTH));
+        }
+        self.expect('{')?;
+        let mut fields = Vec::new();
+        while !self.at('}') {
+            if self.peek() == Some(&Token::Spread) {
+                return Err("Fragments are not supported".to_string());
+            }
+            let mut name = self.name()?;
+            let mut alias = None;
+            if self.at(':') {
+                self.pos += 1;
+                alias = Some(name);
+                name = self.name()?;
+            }
+            let mut arguments = Vec::new();
+            if self.at('(') {
+                self.pos += 1;
+                while !self.at(')') {
+                    let argument = self.name()?;
+                    self.expect(':')?;
+                    arguments.push((argument, self.value(0)?));
+                }
+                self.expect(')')?;
+            }
+            if self.at('@') {
+                return Err("Directives are not supported".to_string());
+            }
+            let selection = if self.at('{') {
+                self.selection_set(depth + 1)?
+            } else {
+                Vec::new()
+            };
+            fields.push(Field {
+                alias,
+                name,
+                arguments,
+                selection,
+            });
+        }
+        self.expect('}')?;
+        if fields.is_empty() {
+            return Err("Empty selection".to_string());
+        }
+        merge_fields(fields)
+    }
+
+    fn value(&mut self, depth: usize) -> Result<Argument, String> {
+        if depth > MAX_DEPTH {
+            return Err(format!("Values can nest at most {} deep", MAX_DEPTH));
+        }
+        match self.next() {
+            Some(Token::Punct('$')) => Ok(Argument::Variable(self.name()?)),
+            Some(Token::Int(value)) => Ok(Argument::Literal(json!(value))),
+            Some(Token::Float(value)) => Ok(Argument::Literal(json!(value))),
+            Some(Token::Str(value)) => Ok(Argument::Literal(Value::String(value))),
+            Some(Token::Name(name)) => Ok(Argument::Literal(match name.as_str() {
+                "true" => Value::Bool(true),
+                "false" => Value::Bool(false),
+                "null" => Value::Null,
+                // Enum values are taken as their names.
+                _ => Value::String(name),
+            })),
+            Some(Token::Punct('[')) => {
+                let mut items = Vec::new();
+                while !self.at(']') {
+                    items.push(self.value(depth + 1)?);
+                }
+                self.pos += 1;
+                Ok(Argument::List(items))
+            }
+            Some(Token::Punct('{')) => {
+                let mut fields = Vec::new();
+                while !self.at('}') {
+                    let name = self.name()?;
+                    self.expect(':')?;
+                    fields.push((name, self.value(depth + 1)?));
+                }
+                self.pos += 1;
+                Ok(Argument::Object(fields))
+            }
+            other => Err(format!(
+                "Expected a value, found {}",
+                describe(other.as_ref())
+            )),
+        }
+    }
+}
+
+/// Folds fields under the same response key into one, as GraphQL merges
+/// them: they must be the same field with the same arguments, and their
+/// selections are combined, so `{ a { x } a { y } }` is `{ a { x y } }`.
+fn merge_fields(fields: Vec<Field>) -> Result<Vec<Field>, String> {
+    let mut merged: Vec<Field> = Vec::new();
+    for field in fields {
+        let Some(known) = merged.iter_mut().find(|known| known.key() == field.key()) else {
+            merged.push(field);
+            continue;
+        };
+        if known.name != field.name {
+            return Err(format!(
+                "Fields {} conflict because {} and {} are different fields; use different aliases",
+                field.key(),
+                known.name,
+                field.name
+            ));
+        }
+        let same_arguments = known.arguments.len() == field.arguments.len()
+            && known
+                .arguments
+                .iter()
+                .all(|argument| field.arguments.contains(argument));
+        if !same_arguments {
+            return Err(format!(
+                "Fields {} conflict because they have different arguments; use different aliases",
+                field.key()
+            ));
+        }
+        let selection = std::mem::take(&mut known.selection)
+            .into_iter()
+            .chain(field.selection)
+            .collect();
+        known.selection = merge_fields(selection)?;
+    }
+    Ok(merged)
+}
+
+fn describe(token: Option<&Token>) -> String {
+    match token {
+        None => "the end of the query".to_string(),
+        Some(Token::Punct(c)) => format!("{:?}", c),
+        Some(Token::Spread) => "\"...\"".to_string(),
+        Some(Token::Name(name)) => name.clone(),
+        Some(Token::Int(value)) => value.to_string(),
+        Some(Token::Float(value)) => value.to_string(),
+        Some(Token::Str(value)) => format!("{:?}", value),
+    }
+}
+
+/// What the resolvers read, as of the request.
+struct Context<'a> {
+    facilities: &'a FacilityIndex,
+    reports: &'a Reports,
+    variables: Map<String, Value>,
+}
+
+impl Context<'_> {
+    fn argument(&self, field: &Field, name: &str) -> Option<Value> {
+        field
+            .arguments
+            .iter()
+            .find(|(argument, _)| argument == name)
+            .map(|(_, value)| value.resolve(&self.variables))
+            .filter(|value| !value.is_null())
+    }
+
+    /// A string argument. Lists are joined with commas, as the REST
+    /// parameters take them.
+    fn string(&self, field: &Field, name: &str) -> Result<Option<String>, String> {
+        match self.argument(field, name) {
+            None => Ok(None),
+            Some(Value::String(value)) => Ok(Some(value)),
+            Some(Value::Array(items)) => items
+                .iter()
+                .map(|item| {
+                    item.as_str()
+                        .map(str::to_string)
+                        .ok_or_else(|| format!("{} takes strings", name))
+                })
+                .collect::<Result<Vec<_>, _>>()
+                .map(|items| Some(items.join(","))),
+            Some(_) => Err(format!("{} takes a string", name)),
+        }
+    }
+
+    fn page(&self, field: &Field) -> Result<Page, String> {
+        let number = |name: &str| -> Result<Option<usize>, String> {
+            match self.argument(field, name) {
+                None => Ok(None),
+                Some(value) => value
+                    .as_u64()
+                    .map(|n| Some(n as usize))
+                    .ok_or_else(|| format!("{} takes a non-negative integer", name)),
+            }
+        };
+        Page::parse(number("limit")?, number("offset")?, None)
+    }
+
+    /// The facility ID of `id`, which may be a source id.
+    fn facility_id<'a>(&'a self, id: &'a str) -> &'a str {
+        self.facilities.get(id).map_or(id, |facility| &facility.id)
+    }
+
+    fn aphis_by_facility(&self, id: &str) -> Option<&AphisReport> {
+        let id = self.facility_id(id);
+        self.reports
+            .aphis
+            .iter()
+            .find(|report| report.facility_id.as_deref() == Some(id))
+    }
+
+    fn inspection_by_facility(&self, id: &str) -> Option<&InspectionReport> {
+        let id = self.facility_id(id);
+        self.reports
+            .inspections
+            .iter()
+            .find(|report| report.facility_id.as_deref() == Some(id))
+    }
+
+    /// The APHIS report of a facility, or of the best matched APHIS facility.
+    fn aphis_for(&self, facility_id: &str, customer_number: &str) -> Option<&AphisReport> {
+        if let Some(report) = self.aphis_by_facility(facility_id) {
+            return Some(report);
+        }
+        if !customer_number.is_empty()
+            && let Some(report) = self
+                .reports
+                .aphis
+                .iter()
+                .find(|report| report.customer_number_x == customer_number)
+        {
+            return Some(report);
+        }
+        self.facilities
+            .related(facility_id)
+            .iter()
+            .filter(|related| related.facility.source == Source::AphisReports)
+            .find_map(|related| self.aphis_by_facility(&related.facility.id))
+    }
+
+    /// Inspection reports under `customer_number` or matched to the facility,
+    /// as on the detail endpoints.
+    fn inspections_for(&self, facility_id: &str, customer_number: &str) -> Vec<&InspectionReport> {
+        let matched: Vec<&str> = self
+            .facilities
+            .related(facility_id)
+            .iter()
+            .filter(|related| related.facility.source == Source::InspectionReports)
+            .map(|related| related.facility.id.as_str())
+            .collect();
+        self.reports
+            .inspections
+            .iter()
+            .filter(|report| {
+                (!customer_number.is_empty() && report.customer_number == customer_number)
+                    || report
+                        .facility_id
+                        .as_deref()
+                        .is_some_and(|id| matched.contains(&id))
+            })
+            .collect()
+    }
+
+    fn query(&self, fields: &[Field]) -> Result<Value, String> {
+        let mut data = Map::new();
+        for field in fields {
+            let value = match field.name.as_str() {
+                "__typename" => json!("Query"),
+                "facilities" => {
+                    let filter = FacilityFilter::parse(
+                        self.string(field, "source")?.as_deref(),
+                        self.string(field, "country_code")?.as_deref(),
+                        self.string(field, "state")?.as_deref(),
+                        self.string(field, "species")?.as_deref(),
+                        self.string(field, "activity")?.as_deref(),
+                        self.string(field, "status")?.as_deref(),
+                    )?;
+                    let selected = filter.select(self.facilities);
+                    let page = self.page(field)?;
+                    page.window(&selected)
+                        .iter()
+                        .map(|facility| self.facility(facility, &field.selection))
+                        .collect::<Result<_, _>>()?
+                }
+                "facility" => {
+                    let id = self
+                        .string(field, "id")?
+                        .ok_or("facility needs an id argument")?;
+                    match self.facilities.get(&id) {
+                        Some(facility) => self.facility(facility, &field.selection)?,
+                        None => Value::Null,
+                    }
+                }
+                "aphis_reports" => {
+                    let certificate = self.string(field, "certificate")?;
+                    let reports: Vec<&AphisReport> = self
+                        .reports
+                        .aphis
+                        .iter()
+                        .filter(|report| {
+                            certificate
+                                .as_ref()
+                                .is_none_or(|wanted| report.certificate_number == *wanted)
+                        })
+                        .collect();
+                    let page = self.page(field)?;
+                    page.window(&reports)
+                        .iter()
+                        .map(|report| self.aphis_report(report, &field.selection))
+                        .collect::<Result<_, _>>()?
+                }
+                "inspection_reports" => {
+                    let certificate = self.string(field, "certificate")?;
+                    let reports: Vec<&InspectionReport> = self
+                        .reports
+                        .inspections
+                        .iter()
+                        .filter(|report| {
+                            certificate
+                                .as_ref()
+                                .is_none_or(|wanted| report.certificate_number == *wanted)
+                        })
+                        .collect();
+                    let page = self.page(field)?;
+                    page.window(&reports)
+                        .iter()
+                        .map(|report| self.inspection_report(report, &field.selection))
+                        .collect::<Result<_, _>>()?
+                }
+                other => return Err(format!("Cannot query field {} on type Query", other)),
+            };
+            data.insert(field.key().to_string(), value);
+        }
+        Ok(Value::Object(data))
+    }
+
+    fn facility(&self, facility: &Facility, fields: &[Field]) -> Result<Value, String> {
+        let value = serde_json::to_value(facility).map_err(|e| e.to_string())?;
+        self.facility_value("Facility", &facility.id, value, fields)
+    }
+
+    fn facility_value(
+        &self,
+        type_name: &str,
+        id: &str,
+        value: Value,
+        fields: &[Field],
+    ) -> Result<Value, String> {
+        select(type_name, &value, fields, |field| {
+            Ok(Some(match field.name.as_str() {
+                "related" => self
+                    .facilities
+                    .related(id)
+                    .iter()
+                    .map(|related| {
+                        let value = serde_json::to_value(related).map_err(|e| e.to_string())?;
+                        self.facility_value(
+                            "RelatedFacility",
+                            &related.facility.id,
+                            value,
+                            &field.selection,
+                        )
+                    })
+                    .collect::<Result<_, _>>()?,
+                "aphis_report" => match self.aphis_for(id, "") {
+                    Some(report) => self.aphis_report(report, &field.selection)?,
+                    None => Value::Null,
+                },
+                "inspection_reports" => {
+                    let own = self.inspection_by_facility(id);
+                    let reports = match own {
+                        Some(report) => vec![report],
+                        None => {
+                            let customer = self
+                                .aphis_by_facility(id)
+                                .map_or("", |report| report.customer_number_x.as_str());
+                            self.inspections_for(id, customer)
+                        }
+                    };
+                    reports
+                        .into_iter()
+                        .map(|report| self.inspection_report(report, &field.selection))
+                        .collect::<Result<_, _>>()?
+                }
+                _ => return Ok(None),
+            }))
+        })
+    }
+
+    fn aphis_report(&self, report: &AphisReport, fields: &[Field]) -> Result<Value, String> {
+        let value = serde_json::to_value(report).map_err(|e| e.to_string())?;
+        let id = report.facility_id.clone().unwrap_or_default();
+        select("AphisReport", &value, fields, |field| {
+            Ok(Some(match field.name.as_str() {
+                "facility" => match self.facilities.get(&id) {
+                    Some(facility) => self.facility(facility, &field.selection)?,
+                    None => Value::Null,
+                },
+                "inspection_reports" => self
+                    .inspections_for(&id, &report.customer_number_x)
+                    .into_iter()
+                    .map(|report| self.inspection_report(report, &field.selection))
+                    .collect::<Result<_, _>>()?,
+                _ => return Ok(None),
+            }))
+        })
+    }
+
+    fn inspection_report(
+        &self,
+        report: &InspectionReport,
+        fields: &[Field],
+    ) -> Result<Value, String> {
+        let value = serde_json::to_value(report).map_err(|e| e.to_string())?;
+        let id = report.facility_id.clone().unwrap_or_default();
+        select("InspectionReport", &value, fields, |field| {
+            Ok(Some(match field.name.as_str() {
+                "facility" => match self.facilities.get(&id) {
+                    Some(facility) => self.facility(facility, &field.selection)?,
+                    None => Value::Null,
+                },
+                "aphis_report" => match self.aphis_for(&id, &report.customer_number) {
+                    Some(report) => self.aphis_report(report, &field.selection)?,
+                    None => Value::Null,
+                },
+                _ => return Ok(None),
+            }))
+        })
+    }
+}
+
+/// Selects `fields` from a record, resolving those `relation` knows and
+/// taking the rest from the record's JSON.
+fn select(
+    type_name: &str,
+    value: &Value,
+    fields: &[Field],
+    mut relation: impl FnMut(&Field) -> Result<Option<Value>, String>,
+) -> Result<Value, String> {
+    if fields.is_empty() {
+        return Err(format!(
+            "A selection of fields is required on {}",
+            type_name
+        ));
+    }
+    let mut selected = Map::new();
+    for field in fields {
+        let resolved = match field.name.as_str() {
+            "__typename" => json!(type_name),
+            _ => match relation(field)? {
+                Some(resolved) => resolved,
+                None => {
+                    let Some(scalar) = lookup(value, &field.name) else {
+                        return Err(format!(
+                            "Cannot query field {} on type {}",
+                            field.name, type_name
+                        ));
+                    };
+                    project(scalar, &field.selection, &field.name)?
+                }
+            },
+        };
+        selected.insert(field.key().to_string(), resolved);
+    }
+    Ok(Value::Object(selected))
+}
+
+/// The key `name` in a JSON object, or the one that is `name` in snake_case.
+fn lookup<'a>(value: &'a Value, name: &str) -> Option<&'a Value> {
+    let object = value.as_object()?;
+    object.get(name).or_else(|| {
+        object.iter().find_map(|(key, value)| {
+            let snake: String = key
+                .trim()
+                .to_lowercase()
+                .chars()
+                .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+                .collect();
+            (snake == name).then_some(value)
+        })
+    })
+}
+
+/// Narrows a plain JSON value (e.g. a list of objects) to `fields`, or
+/// returns it whole without a selection.
+fn project(value: &Value, fields: &[Field], name: &str) -> Result<Value, String> {
+    if fields.is_empty() {
+        return Ok(value.clone());
+    }
+    match value {
+        Value::Array(items) => items
+            .iter()
+            .map(|item| project(item, fields, name))
+            .collect(),
+        Value::Object(_) => {
+            let mut selected = Map::new();
+            for field in fields {
+                let inner = lookup(value, &field.name)
+                    .ok_or_else(|| format!("Cannot query field {} on {}", field.name, name))?;
+                selected.insert(
+                    field.key().to_string(),
+                    project(inner, &field.selection, &field.name)?,
+                );
+            }
+            Ok(Value::Object(selected))
+        }
+        Value::Null => Ok(Value::Null),
+        _ => Err(format!("Field {} has no subfields to select", name)),
+    }
+}
+
+#[derive(Deserialize, Default)]
+pub struct GraphQlRequest {
+    query: String,
+    #[serde(default)]
+    variables: Option<Map<String, Value>>,
+    #[serde(default, rename = "operationName")]
+    operation_name: Option<String>,
+}
+
+/// The query string form, with `variables` as JSON.
+#[derive(Deserialize)]
+pub struct GraphQlParams {
+    query: Option<String>,
+    variables: Option<String>,
+    #[serde(rename = "operationName")]
+    operation_name: Option<String>,
+}
+
+fn errors(status: StatusCode, message: String) -> Response {
+    (status, Json(json!({ "errors": [{ "message": message }] }))).into_response()
+}
+
+fn execute(state: &AppState, request: GraphQlRequest) -> Response {
+    let operations =
+        match tokenize(&request.query).and_then(|tokens| Parser { tokens, pos: 0 }.document()) {
+            Ok(operations) => operations,
+            Err(e) => return errors(StatusCode::BAD_REQUEST, e),
+        };
+    let operation = match (&request.operation_name, operations.as_slice()) {
+        (None, [operation]) => operation,
+        (None, _) => {
+            return errors(
+                StatusCode::BAD_REQUEST,
+                "operationName is required with several operations".to_string(),
+            );
+        }
+        (Some(name), _) => match operations
+            .iter()
+            .find(|operation| operation.name.as_deref() == Some(name))
+        {
+            Some(operation) => operation,
+            None => {
+                return errors(
+                    StatusCode::BAD_REQUEST,
+                    format!("No operation named {}", name),
+                );
+            }
+        },
+    };
+
+    let mut variables = request.variables.unwrap_or_default();
+    for (name, default) in &operation.variables {
+        if let Some(default) = default
+            && !variables.contains_key(name)
+        {
+            let value = default.resolve(&Map::new());
+            variables.insert(name.clone(), value);
+        }
+    }
+    let facilities = state.facilities();
+    let reports = state.reports();
+    let context = Context {
+        facilities: &facilities,
+        reports: &reports,
+        variables,
+    };
+    match context.query(&operation.selection) {
+        Ok(data) => Json(json!({ "data": data })).into_response(),
+        Err(e) => errors(StatusCode::OK, e),
+    }
+}
+
+/// POST /api/graphql with `{"query", "variables", "operationName"}`.
+pub async fn post_graphql_handler(
+    State(state): State<AppState>,
+    Json(request): Json<GraphQlRequest>,
+) -> impl IntoResponse {
+    execute(&state, request)
+}
+
+/// GET /api/graphql?query=...&variables=...
+pub async fn get_graphql_handler(
+    State(state): State<AppState>,
+    Query(params): Query<GraphQlParams>,
+) -> impl IntoResponse {
+    let Some(query) = params.query else {
+        return errors(StatusCode::BAD_REQUEST, "query is required".to_string());
+    };
+    let variables = match params.variables.as_deref().map(serde_json::from_str) {
+        None => None,
+        Some(Ok(variables)) => Some(variables),
+        Some(Err(e)) => {
+            return errors(
+                StatusCode::BAD_REQUEST,
+                format!("variables isn't a JSON object: {}", e),
+            );
+        }
+    };
+    execute(
+        &state,
+        GraphQlRequest {
+            query,
+            variables,
+            operation_name: params.operation_name,
+        },
+    )
+}
@@ -0,0 +1,136 @@
+// Until Every Cage is Empty
+// Copyright (C) 2025 Eli Perez
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+// Contact the developer directly at untileverycageproject@protonmail.com
+//! GET /healthz and /readyz, for uptime checks and the deployment's
+//! monitoring, and the `Warning` header on responses while a dataset that
+//! failed to load is served from its last good copy.
+
+use std::collections::BTreeMap;
+
+use axum::Json;
+use axum::extract::{Request, State};
+use axum::http::{HeaderValue, StatusCode, header};
+use axum::middleware::Next;
+use axum::response::{IntoResponse, Response};
+use serde::Serialize;
+
+use crate::AppState;
+use crate::importers;
+
+#[derive(Serialize)]
+struct DatasetStatus {
+    dataset: &'static str,
+    loaded: bool,
+    /// Records in memory, as of the last successful load.
+    records: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+#[derive(Serialize)]
+struct Readiness {
+    ready: bool,
+    /// When the data being served was loaded.
+    loaded_at: String,
+    /// When an importer last refreshed any of it.
+    last_import: Option<String>,
+    datasets: Vec<DatasetStatus>,
+}
+
+#[derive(Serialize)]
+struct Health {
+    /// `ok`, or `degraded` while a dataset is served from its last good copy.
+    status: &'static str,
+    /// Why each such dataset failed to load.
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    load_errors: BTreeMap<&'static str, String>,
+}
+
+/// GET /healthz: the process is up and answering, with the datasets that
+/// failed their last load. Always 200; /readyz is the check that fails.
+pub async fn get_healthz_handler(State(state): State<AppState>) -> impl IntoResponse {
+    let load_errors = state.load_errors.read().unwrap().clone();
+    let status = if load_errors.is_empty() {
+        "ok"
+    } else {
+        "degraded"
+    };
+    Json(Health {
+        status,
+        load_errors,
+    })
+}
+
+/// Adds `Warning: 110` ("Response is Stale", RFC 7234) naming the datasets
+/// that failed their last load and are served as they were before it.
+pub async fn load_warning(State(state): State<AppState>, request: Request, next: Next) -> Response {
+    let mut response = next.run(request).await;
+    let failed: Vec<&str> = state.load_errors.read().unwrap().keys().copied().collect();
+    if !failed.is_empty()
+        && let Ok(value) = HeaderValue::from_str(&format!(
+            "110 - \"{} failed to load; serving the last good copy\"",
+            failed.join(", ")
+        ))
+    {
+        response.headers_mut().insert(header::WARNING, value);
+    }
+    response
+}
+
+/// GET /readyz: whether every dataset loaded, with its record count. 503 when
+/// one failed on the last load or reload, or when no locations are loaded.
+pub async fn get_readyz_handler(State(state): State<AppState>) -> impl IntoResponse {
+    let reports = state.reports();
+    let index = state.location_index();
+    let errors = state.load_errors.read().unwrap().clone();
+    let datasets: Vec<DatasetStatus> = [
+        ("locations", index.locations.len()),
+        ("aphis_reports", reports.aphis.len()),
+        ("aphis_history", reports.aphis_years.reports()),
+        ("inspection_reports", reports.inspections.len()),
+        ("aquaculture", reports.aquaculture.len()),
+        ("fur_farms", reports.fur_farms.len()),
+        ("egg_farms", reports.egg_farms.len()),
+        ("cafos", reports.cafos.len()),
+        ("equine", reports.equine.len()),
+        ("citations", reports.citations.certificates()),
+        ("enforcement", reports.enforcement.establishments()),
+    ]
+    .into_iter()
+    .map(|(dataset, records)| DatasetStatus {
+        dataset,
+        loaded: !errors.contains_key(dataset),
+        records,
+        error: errors.get(dataset).cloned(),
+    })
+    .collect();
+
+    let ready = datasets.iter().all(|status| status.loaded) && !index.locations.is_empty();
+    let dataset = state.store.current();
+    let readiness = Readiness {
+        ready,
+        loaded_at: dataset.loaded_at.clone(),
+        last_import: importers::latest_run(&dataset),
+        datasets,
+    };
+    let status = if ready {
+        StatusCode::OK
+    } else {
+        StatusCode::SERVICE_UNAVAILABLE
+    };
+    (status, Json(readiness))
+}
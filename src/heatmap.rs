@@ -0,0 +1,171 @@
+// Until Every Cage is Empty
+// Copyright (C) 2025 Eli Perez
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+// Contact the developer directly at untileverycageproject@protonmail.com
+//! GET /api/heatmap?weight=volume|count: a weighted point per location and
+//! APHIS research facility, so a heat layer shows how many animals pass
+//! through a region rather than how many facilities are in it.
+//!
+//! - `volume` (the default) is the 0 to 1 scale the priority score's volume
+//!   component uses: the FSIS volume category over 5 (over 10 for
+//!   processing-only plants), and APHIS's animal count log-scaled to reach 1
+//!   at 10,000. It compares the two datasets on one scale.
+//! - `count` is animals a year: the typical head count of the FSIS slaughter
+//!   category (see `volume::typical_annual_slaughter`) and the animals an
+//!   APHIS facility reported using. Processing-only plants slaughter none.
+//!
+//! Records a weight can't be given to, such as locations without a volume
+//! category, are left out; /api/locations still has them for density.
+
+use axum::extract::{Query, State};
+use axum::http::{HeaderMap, StatusCode};
+use axum::response::IntoResponse;
+use serde::{Deserialize, Serialize};
+
+use crate::filter::LocationFilter;
+use crate::location::animal_counts;
+use crate::paging::Page;
+use crate::{AppState, LocationResponse, geocode, geojson, scoring, volume};
+
+#[derive(Deserialize, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "lowercase")]
+enum Weight {
+    #[default]
+    Volume,
+    Count,
+}
+
+impl Weight {
+    fn of_location(self, loc: &LocationResponse) -> Option<f64> {
+        let weight = match self {
+            Weight::Volume => scoring::fsis_volume(
+                &loc.slaughter_volume_category,
+                &loc.processing_volume_category,
+            ),
+            Weight::Count => volume::typical_annual_slaughter(&loc.slaughter_volume_category)?,
+        };
+        self.round(weight)
+    }
+
+    fn of_animals(self, animals: f64) -> Option<f64> {
+        let weight = match self {
+            Weight::Volume => scoring::animal_count_volume(animals),
+            Weight::Count => animals,
+        };
+        self.round(weight)
+    }
+
+    /// Three decimals of a scale, whole animals of a count; None for 0.
+    fn round(self, weight: f64) -> Option<f64> {
+        let rounded = match self {
+            Weight::Volume => (weight * 1000.0).round() / 1000.0,
+            Weight::Count => weight.round(),
+        };
+        (rounded > 0.0).then_some(rounded)
+    }
+}
+
+#[derive(Deserialize)]
+pub struct HeatmapParams {
+    weight: Option<Weight>,
+    #[serde(alias = "country")]
+    country_code: Option<String>,
+    species: Option<String>,
+    activity: Option<String>,
+    status: Option<String>,
+    /// `json` (the default), `geojson` or `csv`.
+    format: Option<String>,
+}
+
+#[derive(Serialize)]
+struct HeatPoint<'a> {
+    facility_id: &'a str,
+    /// The dataset, named like its endpoint.
+    source: &'static str,
+    latitude: f64,
+    longitude: f64,
+    weight: f64,
+}
+
+impl geojson::Feature for HeatPoint<'_> {}
+
+/// GET /api/heatmap
+///
+/// `country_code`, `species`, `activity` and `status` filter the locations
+/// as on /api/locations. As on /api/choropleth, the APHIS facilities are
+/// left out with any of the last three, or a `country_code` other than us.
+pub async fn get_heatmap_handler(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Query(params): Query<HeatmapParams>,
+) -> impl IntoResponse {
+    let weight = params.weight.unwrap_or_default();
+    let filter = match LocationFilter::parse(
+        None,
+        params.species.as_deref(),
+        params.activity.as_deref(),
+        params.status.as_deref(),
+    ) {
+        Ok(filter) => filter,
+        Err(e) => return (StatusCode::BAD_REQUEST, e).into_response(),
+    };
+    let country = params.country_code.as_deref().map(geocode::country_code);
+
+    let index = state.location_index();
+    let mut points: Vec<HeatPoint> = index
+        .locations
+        .iter()
+        .filter(|loc| {
+            country
+                .as_deref()
+                .is_none_or(|country| loc.country == country)
+        })
+        .filter(|loc| filter.matches(loc))
+        .filter_map(|loc| {
+            Some(HeatPoint {
+                facility_id: &loc.facility_id,
+                source: "locations",
+                latitude: loc.latitude,
+                longitude: loc.longitude,
+                weight: weight.of_location(loc)?,
+            })
+        })
+        .collect();
+
+    let reports = state.reports();
+    if country.as_deref().is_none_or(|country| country == "us") && filter.is_empty() {
+        points.extend(reports.aphis.iter().filter_map(|report| {
+            let animals: f64 = animal_counts(report)
+                .into_iter()
+                .map(|(_, count)| f64::from(count))
+                .sum();
+            Some(HeatPoint {
+                facility_id: report.facility_id.as_deref().unwrap_or_default(),
+                source: "aphis_reports",
+                latitude: report.latitude,
+                longitude: report.longitude,
+                weight: weight.of_animals(animals)?,
+            })
+        }));
+    }
+
+    geojson::respond(
+        &points,
+        &headers,
+        params.format.as_deref(),
+        &Page::default(),
+    )
+}
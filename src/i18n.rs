@@ -0,0 +1,95 @@
+// Until Every Cage is Empty
+// Copyright (C) 2025 Eli Perez
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+// Contact the developer directly at untileverycageproject@protonmail.com
+//! GET /api/i18n/{lang}: display names for the language-neutral codes the
+//! API serves (`species`, `activities`, `status`), so clients can show them
+//! in the visitor's language. The English summaries such as
+//! `animals_slaughtered` stay as they are.
+
+use std::collections::BTreeMap;
+
+use axum::Json;
+use axum::extract::Path;
+use axum::http::{StatusCode, header};
+use axum::response::IntoResponse;
+use serde::Serialize;
+
+use crate::activities::Activity;
+use crate::species::TAXA;
+use crate::status::FacilityStatus;
+
+/// Languages with translations, English first.
+pub const LANGUAGES: &[&str] = &["en", "es", "de", "fr", "it", "da"];
+
+/// The language `lang` asks for, English when not given.
+pub(crate) fn language(lang: Option<&str>) -> Result<&'static str, String> {
+    let Some(lang) = lang else {
+        return Ok(LANGUAGES[0]);
+    };
+    let lang = lang.trim().to_lowercase();
+    LANGUAGES
+        .iter()
+        .find(|code| **code == lang)
+        .copied()
+        .ok_or_else(|| {
+            format!(
+                "Unsupported language {}, expected one of {}",
+                lang,
+                LANGUAGES.join(", ")
+            )
+        })
+}
+
+#[derive(Serialize)]
+struct Dictionary {
+    lang: &'static str,
+    /// Taxon ID to name. Narrow regulatory categories may only have English
+    /// names.
+    species: BTreeMap<&'static str, &'static str>,
+    activities: BTreeMap<&'static str, &'static str>,
+    statuses: BTreeMap<&'static str, &'static str>,
+}
+
+/// GET /api/i18n/{lang}
+pub async fn get_i18n_handler(Path(lang): Path<String>) -> impl IntoResponse {
+    let lang = match language(Some(&lang)) {
+        Ok(lang) => lang,
+        Err(e) => return (StatusCode::NOT_FOUND, e).into_response(),
+    };
+    let statuses = [
+        FacilityStatus::Active,
+        FacilityStatus::Closed,
+        FacilityStatus::Suspended,
+    ];
+    // The dictionary only changes with a release.
+    let cache = [(header::CACHE_CONTROL, "public, max-age=3600")];
+    let dictionary = Json(Dictionary {
+        lang,
+        species: TAXA
+            .iter()
+            .map(|taxon| (taxon.id, taxon.name_in(lang)))
+            .collect(),
+        activities: Activity::all()
+            .map(|activity| (activity.id(), activity.label_in(lang)))
+            .collect(),
+        statuses: statuses
+            .iter()
+            .map(|status| (status.id(), status.label_in(lang)))
+            .collect(),
+    });
+    (cache, dictionary).into_response()
+}
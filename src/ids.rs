@@ -0,0 +1,66 @@
+// Until Every Cage is Empty
+// Copyright (C) 2025 Eli Perez
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+// Contact the developer directly at untileverycageproject@protonmail.com
+//! Identifiers that survive data refreshes.
+//!
+//! Every served record has a facility ID hashed from its dataset, country
+//! and the id its source gives it (FSIS establishment number, APHIS
+//! certificate, permit number...), so it is the same on every load and
+//! never shared between datasets. It is the ID of /api/facilities/{id},
+//! the `facility_id` of every record and what feedback and bookmarks refer
+//! to.
+//!
+//! Importers whose source has no id of its own use `content_id` rather than
+//! the row number, which would renumber the records whenever the source
+//! adds or drops a row.
+
+use sha1::{Digest, Sha1};
+
+/// Hex digits of the hash kept in a facility ID: 64 bits, so collisions are
+/// out of the question at the scale of every farm and slaughterhouse listed.
+const FACILITY_ID_DIGITS: usize = 16;
+
+/// Hex digits of a content ID, unique within one source file.
+const CONTENT_ID_DIGITS: usize = 12;
+
+fn digest(parts: &[&str]) -> String {
+    let mut hasher = Sha1::new();
+    for part in parts {
+        hasher.update(part.as_bytes());
+        hasher.update([0x1f]);
+    }
+    format!("{:x}", hasher.finalize())
+}
+
+/// The facility ID of a record: `f` and 16 hex digits, from the dataset it is
+/// served in (the facilities `source`, e.g. `locations`), the country and the
+/// source's id for it.
+pub fn facility_id(source: &str, country: &str, source_id: &str) -> String {
+    let hash = digest(&[source, &country.to_lowercase(), source_id.trim()]);
+    format!("f{}", &hash[..FACILITY_ID_DIGITS])
+}
+
+/// An id for a record whose source gives none, from what identifies it
+/// there (name, address...). Case, accents and spacing don't change it.
+pub fn content_id(parts: &[&str]) -> String {
+    let normalized: Vec<String> = parts
+        .iter()
+        .map(|part| crate::importer::normalize(part))
+        .collect();
+    let normalized: Vec<&str> = normalized.iter().map(String::as_str).collect();
+    digest(&normalized)[..CONTENT_ID_DIGITS].to_string()
+}
@@ -0,0 +1,306 @@
+// Until Every Cage is Empty
+// Copyright (C) 2025 Eli Perez
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+// Contact the developer directly at untileverycageproject@protonmail.com
+//! What the country importers share: a source is read into rows (parse),
+//! each row becomes a location (normalize) and is checked (validate), and
+//! the locations are deduplicated and written as a locations CSV (emit).
+//!
+//! A new country implements `SourceAdapter` for its export and calls `run`
+//! from its binary, which handles the rest: coordinates, merging rows for
+//! the same establishment, writing the CSV, the summary on stderr and
+//! recording the run. The helpers below cover the exports seen so far
+//! (headers spelled differently per release, `;` or `,` delimited CSV).
+
+use std::collections::{BTreeMap, HashMap};
+use std::error::Error;
+use std::io::Write;
+
+use crate::Location;
+use crate::importers;
+
+/// One country's export.
+pub trait SourceAdapter {
+    /// A record as the source publishes it.
+    type Row;
+
+    /// The importer's name in `IMPORTERS`, recorded with each run.
+    fn name(&self) -> &'static str;
+
+    /// Reads the whole export into rows.
+    fn parse(&mut self, input: &[u8]) -> Result<Vec<Self::Row>, Box<dyn Error>>;
+
+    /// The location a row describes, or None when the row is out of scope,
+    /// noting why in `report`.
+    fn normalize(&mut self, row: Self::Row, report: &mut Report) -> Option<Location>;
+
+    /// Whether a normalized location can be written, or why not. Coordinates
+    /// are checked by `run` after this.
+    fn validate(&self, location: &Location) -> Result<(), String> {
+        if location.establishment_id.is_empty() {
+            return Err("no establishment id".to_string());
+        }
+        if location.establishment_name.is_empty() {
+            return Err("no name".to_string());
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy)]
+pub struct Options {
+    /// Leave out rows without usable coordinates instead of keeping them at
+    /// 0, 0 for the geocode job.
+    pub skip_missing_coordinates: bool,
+}
+
+/// What a run did, printed to stderr at the end.
+#[derive(Debug, Default)]
+pub struct Report {
+    pub written: usize,
+    /// Rows merged into an earlier row with the same establishment id.
+    pub merged: usize,
+    pub missing_coordinates: usize,
+    /// Rows left out, by reason.
+    pub skipped: BTreeMap<String, usize>,
+    /// Source values without a mapping (industries, codes...), by rows.
+    pub unmapped: BTreeMap<String, usize>,
+}
+
+impl Report {
+    pub fn skip(&mut self, reason: impl Into<String>) {
+        *self.skipped.entry(reason.into()).or_default() += 1;
+    }
+
+    pub fn unmapped(&mut self, value: impl Into<String>) {
+        *self.unmapped.entry(value.into()).or_default() += 1;
+    }
+
+    fn print(&self, options: &Options) {
+        eprintln!(
+            "wrote {} establishments ({} rows merged), {} without coordinates ({}), {} rows skipped, {} need manual mapping",
+            self.written,
+            self.merged,
+            self.missing_coordinates,
+            if options.skip_missing_coordinates {
+                "skipped"
+            } else {
+                "kept at 0, 0 for geocoding"
+            },
+            self.skipped.values().sum::<usize>(),
+            self.unmapped.values().sum::<usize>(),
+        );
+        for (reason, rows) in &self.skipped {
+            eprintln!("  skipped {} rows: {}", rows, reason);
+        }
+        for (value, rows) in &self.unmapped {
+            eprintln!("  unmapped {:?} on {} rows", value, rows);
+        }
+    }
+}
+
+/// Parses, normalizes, validates and deduplicates `input`, writes the
+/// locations CSV to `output` and records the run.
+pub fn run<A: SourceAdapter>(
+    adapter: &mut A,
+    input: &[u8],
+    output: impl Write,
+    options: &Options,
+) -> Result<Report, Box<dyn Error>> {
+    let report = convert(adapter, &[input], output, options)?;
+    importers::record_run(adapter.name(), report.written)?;
+    Ok(report)
+}
+
+/// `run` over several exports of the same source, written as one locations
+/// CSV with the establishments they share merged, without recording a run.
+pub fn convert<A: SourceAdapter>(
+    adapter: &mut A,
+    inputs: &[&[u8]],
+    output: impl Write,
+    options: &Options,
+) -> Result<Report, Box<dyn Error>> {
+    let mut report = Report::default();
+    let mut locations: Vec<Location> = Vec::new();
+    let mut positions: HashMap<String, usize> = HashMap::new();
+
+    let mut rows = Vec::new();
+    for input in inputs {
+        rows.extend(adapter.parse(input)?);
+    }
+    for row in rows {
+        let Some(mut location) = adapter.normalize(row, &mut report) else {
+            continue;
+        };
+        if let Err(reason) = adapter.validate(&location) {
+            report.skip(reason);
+            continue;
+        }
+        if coordinates(location.latitude, location.longitude).is_none() {
+            report.missing_coordinates += 1;
+            if options.skip_missing_coordinates {
+                continue;
+            }
+            location.latitude = 0.0;
+            location.longitude = 0.0;
+        }
+        match positions.get(&location.establishment_id) {
+            Some(&i) => {
+                merge(&mut locations[i], location);
+                report.merged += 1;
+            }
+            None => {
+                positions.insert(location.establishment_id.clone(), locations.len());
+                locations.push(location);
+            }
+        }
+    }
+
+    let mut wtr = csv::Writer::from_writer(output);
+    for location in &locations {
+        wtr.serialize(location)?;
+    }
+    wtr.flush()?;
+    report.written = locations.len();
+
+    report.print(options);
+    Ok(report)
+}
+
+/// Folds a later row for the same establishment into the first: a facility
+/// listed once per approved activity keeps every activity, and every
+/// species its description names.
+fn merge(into: &mut Location, other: Location) {
+    let (mut activities, mut species) = split_description(&into.activities);
+    let (other_activities, other_species) = split_description(&other.activities);
+    for activity in other_activities {
+        if !activities.contains(&activity) {
+            activities.push(activity);
+        }
+    }
+    for name in other_species {
+        if !species.contains(&name) {
+            species.push(name);
+        }
+    }
+    into.activities = describe(activities, &species);
+    if other.slaughter {
+        into.slaughter = true;
+    }
+    if into.latitude == 0.0 && into.longitude == 0.0 {
+        into.latitude = other.latitude;
+        into.longitude = other.longitude;
+    }
+}
+
+/// The activities of a `type` column value.
+pub fn split_activities(activities: &str) -> Vec<String> {
+    activities
+        .split("; ")
+        .filter(|activity| !activity.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+/// A `type` column value: the activities sorted and `; `-separated, then
+/// the species in parentheses when there are any, as the other countries'
+/// descriptions record them ("Meat Slaughter (Cattle, Sheep)").
+pub fn describe(mut activities: Vec<String>, species: &[String]) -> String {
+    activities.sort();
+    let activities = activities.join("; ");
+    if species.is_empty() {
+        activities
+    } else {
+        format!("{} ({})", activities, species.join(", "))
+    }
+}
+
+/// The activities and species of a `type` column value `describe` wrote.
+fn split_description(description: &str) -> (Vec<String>, Vec<String>) {
+    match description
+        .strip_suffix(')')
+        .and_then(|rest| rest.rsplit_once(" ("))
+    {
+        Some((activities, species)) => (
+            split_activities(activities),
+            species.split(", ").map(str::to_string).collect(),
+        ),
+        None => (split_activities(description), Vec::new()),
+    }
+}
+
+/// Lowercase ASCII words, for matching header spellings and category names
+/// across releases.
+pub fn normalize(text: &str) -> String {
+    deunicode::deunicode(text)
+        .to_lowercase()
+        .split(|c: char| !c.is_ascii_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Column positions by canonical name, from a table of the normalized
+/// spellings each column has been seen under.
+pub fn map_columns(
+    aliases: &[(&'static str, &[&str])],
+    headers: &[String],
+) -> BTreeMap<&'static str, usize> {
+    let mut columns = BTreeMap::new();
+    for (i, header) in headers.iter().enumerate() {
+        let header = normalize(header);
+        if let Some((name, _)) = aliases
+            .iter()
+            .find(|(_, spellings)| spellings.contains(&header.as_str()))
+        {
+            columns.entry(*name).or_insert(i);
+        }
+    }
+    columns
+}
+
+/// Rows of a CSV export, which uses semicolons when saved from Excel in
+/// most European locales and commas otherwise. The first lines decide, as
+/// exports can start with a title line.
+pub fn csv_rows(text: &str) -> Result<Vec<Vec<String>>, csv::Error> {
+    let head: String = text.lines().take(5).collect();
+    let delimiter = if head.matches(';').count() > head.matches(',').count() {
+        b';'
+    } else {
+        b','
+    };
+    csv::ReaderBuilder::new()
+        .delimiter(delimiter)
+        .has_headers(false)
+        .flexible(true)
+        .from_reader(text.as_bytes())
+        .records()
+        .map(|record| record.map(|r| r.iter().map(|cell| cell.trim().to_string()).collect()))
+        .collect()
+}
+
+/// A coordinate written with a decimal point or comma.
+pub fn parse_coordinate(value: &str) -> Option<f64> {
+    value.trim().replace(',', ".").parse().ok()
+}
+
+/// `(latitude, longitude)`, or None when out of range or 0, 0.
+pub fn coordinates(latitude: f64, longitude: f64) -> Option<(f64, f64)> {
+    let valid = (-90.0..=90.0).contains(&latitude)
+        && (-180.0..=180.0).contains(&longitude)
+        && !(latitude == 0.0 && longitude == 0.0);
+    valid.then_some((latitude, longitude))
+}
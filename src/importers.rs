@@ -0,0 +1,366 @@
+// Until Every Cage is Empty
+// Copyright (C) 2025 Eli Perez
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+// Contact the developer directly at untileverycageproject@protonmail.com
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::path::PathBuf;
+
+use axum::extract::State;
+use axum::{Json, response::IntoResponse};
+use serde::{Deserialize, Serialize};
+
+use crate::AppState;
+use crate::store::Dataset;
+
+/// A tool that produces one of the datasets under static_data.
+#[derive(Serialize, Debug)]
+pub struct DatasetImporter {
+    pub name: &'static str,
+    /// The binary (`cargo run --bin ...`) or script that does the import.
+    pub tool: &'static str,
+    pub country: &'static str,
+    pub source_url: &'static str,
+    /// The terms the publisher releases the data under, empty where we
+    /// haven't confirmed them; check with the source before reuse.
+    pub license: &'static str,
+    /// File or directory under static_data the importer writes.
+    pub output: &'static str,
+}
+
+/// Every importer, Rust or legacy script. Add new importers here.
+pub const IMPORTERS: &[DatasetImporter] = &[
+    DatasetImporter {
+        name: "usda-fsis-mpi",
+        tool: "Old scripts/compiler.py",
+        country: "us",
+        source_url: "https://www.fsis.usda.gov/inspection/establishments/meat-poultry-and-egg-product-inspection-directory",
+        license: "Public domain (US Government work)",
+        output: "us/locations.csv",
+    },
+    DatasetImporter {
+        name: "us-fsis-archive",
+        tool: "us-fsis-archive",
+        country: "us",
+        source_url: "https://web.archive.org/",
+        license: "Public domain (US Government work)",
+        output: "us/snapshots",
+    },
+    DatasetImporter {
+        name: "aphis-annual-reports",
+        tool: "Old scripts/aphis_data_complier.py",
+        country: "us",
+        source_url: "https://aphis.my.site.com/PublicSearchTool/s/annual-reports",
+        license: "Public domain (US Government work)",
+        output: "us/aphis_data_final.csv",
+    },
+    DatasetImporter {
+        name: "aphis-inspection-reports",
+        tool: "Old scripts/data_cleaner_inspection_reports.py",
+        country: "us",
+        source_url: "https://efile.aphis.usda.gov/PublicSearchTool/s/inspection-reports",
+        license: "Public domain (US Government work)",
+        output: "us/inspection_reports.csv",
+    },
+    DatasetImporter {
+        name: "us-aquaculture",
+        tool: "us-aquaculture",
+        country: "us",
+        source_url: "https://www.fisheries.noaa.gov/",
+        license: "Public domain (US Government work)",
+        output: "us/aquaculture",
+    },
+    DatasetImporter {
+        name: "us-fur-farms",
+        tool: "us-fur-farms",
+        country: "us",
+        source_url: "https://www.aphis.usda.gov/",
+        license: "Public domain (US Government work)",
+        output: "us/fur_farms",
+    },
+    DatasetImporter {
+        name: "us-egg-farms-fda",
+        tool: "us-egg-farms",
+        country: "us",
+        source_url: "https://www.fda.gov/food/egg-guidance-regulation-and-other-information/egg-safety-final-rule",
+        license: "Public domain (US Government work)",
+        output: "us/egg_farms",
+    },
+    DatasetImporter {
+        name: "us-egg-farms-npip",
+        tool: "us-egg-farms",
+        country: "us",
+        source_url: "https://www.poultryimprovement.org/",
+        license: "Public domain (US Government work)",
+        output: "us/egg_farms",
+    },
+    DatasetImporter {
+        name: "us-cafos-epa",
+        tool: "us-cafos",
+        country: "us",
+        source_url: "https://echo.epa.gov/facilities/facility-search",
+        license: "Public domain (US Government work)",
+        output: "us/cafos",
+    },
+    DatasetImporter {
+        name: "us-cafos-state",
+        tool: "us-cafos",
+        country: "us",
+        source_url: "",
+        license: "",
+        output: "us/cafos",
+    },
+    DatasetImporter {
+        name: "us-equine-pens",
+        tool: "us-equine",
+        country: "us",
+        source_url: "https://www.aphis.usda.gov/live-animal-import-export/export",
+        license: "Public domain (US Government work)",
+        output: "us/equine",
+    },
+    DatasetImporter {
+        name: "us-equine-shipments",
+        tool: "us-equine",
+        country: "us",
+        source_url: "https://www.aphis.usda.gov/livestock-poultry-disease/equine/slaughter-horse-transport",
+        license: "Public domain (US Government work)",
+        output: "us/equine",
+    },
+    DatasetImporter {
+        name: "mx-equine-shipments",
+        tool: "us-equine",
+        country: "mx",
+        source_url: "https://www.aphis.usda.gov/livestock-poultry-disease/equine/slaughter-horse-transport",
+        license: "Public domain (US Government work)",
+        output: "mx/equine",
+    },
+    DatasetImporter {
+        name: "ca-equine-shipments",
+        tool: "us-equine",
+        country: "ca",
+        source_url: "https://www.aphis.usda.gov/livestock-poultry-disease/equine/slaughter-horse-transport",
+        license: "Public domain (US Government work)",
+        output: "ca/equine",
+    },
+    DatasetImporter {
+        name: "us-aphis-pain-categories",
+        tool: "us-aphis-pain-categories",
+        country: "us",
+        source_url: "https://aphis.my.site.com/PublicSearchTool/s/annual-reports",
+        license: "Public domain (US Government work)",
+        output: "us/aphis_pain_categories.csv",
+    },
+    DatasetImporter {
+        name: "us-aphis-citations",
+        tool: "us-aphis-citations",
+        country: "us",
+        source_url: "https://aphis.my.site.com/PublicSearchTool/s/inspection-reports",
+        license: "Public domain (US Government work)",
+        output: "us/citations",
+    },
+    DatasetImporter {
+        name: "us-fsis-enforcement",
+        tool: "us-fsis-enforcement",
+        country: "us",
+        source_url: "https://www.fsis.usda.gov/science-data/data-sets-visualizations/quarterly-enforcement-reports",
+        license: "Public domain (US Government work)",
+        output: "us/enforcement",
+    },
+    DatasetImporter {
+        name: "us-zip-centroids",
+        tool: "us-zip-centroids",
+        country: "us",
+        source_url: "https://www.census.gov/geographies/reference-files/time-series/geo/gazetteer-files.html",
+        license: "Public domain (US Government work)",
+        output: "us/zip_centroids.csv",
+    },
+    DatasetImporter {
+        name: "de-bvl",
+        tool: "static_data/de/migrate_data.py",
+        country: "de",
+        source_url: "https://bltu.bvl.bund.de/bltu/app/process/bvl-btl_p_veroeffentlichung",
+        license: "",
+        output: "de/locations.csv",
+    },
+    DatasetImporter {
+        name: "da-foedevarestyrelsen",
+        tool: "da-foedevarestyrelsen",
+        country: "dk",
+        source_url: "https://www.findsmiley.dk/",
+        license: "",
+        output: "dk/locations.csv",
+    },
+    DatasetImporter {
+        name: "it-salute",
+        tool: "it-salute",
+        country: "it",
+        source_url: "https://www.salute.gov.it/",
+        license: "",
+        output: "it/locations.csv",
+    },
+    DatasetImporter {
+        name: "uk-fsa",
+        tool: "Old scripts/uk_data_converter.py",
+        country: "gb",
+        source_url: "https://www.food.gov.uk/business-guidance/approved-food-establishments",
+        license: "Open Government Licence v3.0",
+        output: "uk/locations.csv",
+    },
+    DatasetImporter {
+        name: "es-rega",
+        tool: "Old scripts/spain_data_converter.py",
+        country: "es",
+        source_url: "https://www.mapa.gob.es/es/ganaderia/temas/trazabilidad-animal/registro/",
+        license: "",
+        output: "es/locations.csv",
+    },
+    DatasetImporter {
+        name: "es-rgseaa",
+        tool: "es-rgseaa",
+        country: "es",
+        source_url: "https://www.aesan.gob.es/AECOSAN/web/seguridad_alimentaria/subseccion/registro_general_sanitario.htm",
+        license: "",
+        output: "es/locations.csv",
+    },
+    DatasetImporter {
+        name: "au-daff",
+        tool: "au-daff",
+        country: "au",
+        source_url: "https://www.agriculture.gov.au/biosecurity-trade/export/controlled-goods",
+        license: "CC BY 4.0",
+        output: "au/locations.csv",
+    },
+    DatasetImporter {
+        name: "br-sif",
+        tool: "br-sif",
+        country: "br",
+        source_url: "https://www.gov.br/agricultura/pt-br/assuntos/inspecao/produtos-animal/empresas-registradas",
+        license: "",
+        output: "br/locations.csv",
+    },
+    DatasetImporter {
+        name: "fr-kml",
+        tool: "Old scripts/convert_kml_to_csv_improved.py",
+        country: "fr",
+        source_url: "",
+        license: "",
+        output: "fr/locations.csv",
+    },
+];
+
+const RUNS_FILE: &str = "import_runs.toml";
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ImportRun {
+    pub last_successful_run: String,
+    pub records: usize,
+}
+
+/// Records a successful run in static_data/import_runs.toml, so it ships with
+/// the data it describes. Does nothing outside a checkout of the repository
+/// unless `IMPORT_RUNS_FILE` points somewhere else.
+pub fn record_run(name: &str, records: usize) -> Result<(), Box<dyn Error>> {
+    let path = match std::env::var("IMPORT_RUNS_FILE") {
+        Ok(path) => PathBuf::from(path),
+        Err(_) if PathBuf::from("static_data").is_dir() => {
+            PathBuf::from("static_data").join(RUNS_FILE)
+        }
+        Err(_) => return Ok(()),
+    };
+
+    let mut runs: BTreeMap<String, ImportRun> = match std::fs::read_to_string(&path) {
+        Ok(source) => toml::from_str(&source)?,
+        Err(_) => BTreeMap::new(),
+    };
+    runs.insert(
+        name.to_string(),
+        ImportRun {
+            last_successful_run: chrono::Utc::now()
+                .to_rfc3339_opts(chrono::SecondsFormat::Secs, true),
+            records,
+        },
+    );
+    std::fs::write(&path, toml::to_string(&runs)?)?;
+    Ok(())
+}
+
+fn recorded_runs(dataset: &Dataset) -> BTreeMap<String, ImportRun> {
+    dataset
+        .get(RUNS_FILE)
+        .and_then(|contents| std::str::from_utf8(contents).ok())
+        .and_then(|source| toml::from_str(source).ok())
+        .unwrap_or_default()
+}
+
+/// The latest recorded run of any importer.
+pub(crate) fn latest_run(dataset: &Dataset) -> Option<String> {
+    recorded_runs(dataset)
+        .into_values()
+        .map(|run| run.last_successful_run)
+        .max()
+}
+
+/// The latest recorded run of the importers writing `output`.
+pub(crate) fn last_run(dataset: &Dataset, output: &str) -> Option<String> {
+    let runs = recorded_runs(dataset);
+    IMPORTERS
+        .iter()
+        .filter(|importer| importer.output == output)
+        .filter_map(|importer| runs.get(importer.name))
+        .map(|run| run.last_successful_run.clone())
+        .max()
+}
+
+/// Rows in a CSV file, or in every CSV file of a directory.
+pub(crate) fn count_records(dataset: &Dataset, output: &str) -> Option<usize> {
+    let count = |contents: &[u8]| csv::Reader::from_reader(contents).records().count();
+
+    if let Some(contents) = dataset.get(output) {
+        return Some(count(contents));
+    }
+    let files: Vec<&[u8]> = dataset
+        .files_in(output)
+        .map(|(_, contents)| contents)
+        .collect();
+    if files.is_empty() {
+        return None;
+    }
+    Some(files.into_iter().map(count).sum())
+}
+
+#[derive(Serialize)]
+struct ImporterStatus {
+    #[serde(flatten)]
+    importer: &'static DatasetImporter,
+    last_successful_run: Option<String>,
+    record_count: Option<usize>,
+}
+
+pub async fn get_importers_handler(State(state): State<AppState>) -> impl IntoResponse {
+    let dataset = state.store.current();
+    let runs = recorded_runs(&dataset);
+    let statuses: Vec<ImporterStatus> = IMPORTERS
+        .iter()
+        .map(|importer| ImporterStatus {
+            importer,
+            last_successful_run: runs
+                .get(importer.name)
+                .map(|run| run.last_successful_run.clone()),
+            record_count: count_records(&dataset, importer.output),
+        })
+        .collect();
+    Json(statuses)
+}
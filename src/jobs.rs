@@ -0,0 +1,106 @@
+// Until Every Cage is Empty
+// Copyright (C) 2025 Eli Perez
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+// Contact the developer directly at untileverycageproject@protonmail.com
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde::Serialize;
+
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum JobState {
+    Queued,
+    Running,
+    Completed,
+    Failed,
+}
+
+/// Progress of a background job, as reported by /api/jobs.
+#[derive(Serialize, Debug, Clone)]
+pub struct Job {
+    pub id: u64,
+    pub kind: String,
+    pub state: JobState,
+    pub total: usize,
+    pub processed: usize,
+    pub succeeded: usize,
+    pub failed: usize,
+    pub created_at: u64,
+    pub finished_at: Option<u64>,
+    pub message: Option<String>,
+}
+
+/// In-memory registry of background jobs. Jobs don't survive a restart.
+#[derive(Clone, Default)]
+pub struct JobRegistry {
+    next_id: Arc<AtomicU64>,
+    jobs: Arc<Mutex<HashMap<u64, Job>>>,
+}
+
+impl JobRegistry {
+    pub fn create(&self, kind: &str, total: usize) -> Job {
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed) + 1;
+        let job = Job {
+            id,
+            kind: kind.to_string(),
+            state: JobState::Queued,
+            total,
+            processed: 0,
+            succeeded: 0,
+            failed: 0,
+            created_at: unix_now(),
+            finished_at: None,
+            message: None,
+        };
+        self.jobs.lock().unwrap().insert(id, job.clone());
+        job
+    }
+
+    pub fn get(&self, id: u64) -> Option<Job> {
+        self.jobs.lock().unwrap().get(&id).cloned()
+    }
+
+    pub fn list(&self) -> Vec<Job> {
+        let mut jobs: Vec<Job> = self.jobs.lock().unwrap().values().cloned().collect();
+        jobs.sort_by_key(|job| job.id);
+        jobs
+    }
+
+    /// Applies `f` to the job if it still exists.
+    pub fn update(&self, id: u64, f: impl FnOnce(&mut Job)) {
+        if let Some(job) = self.jobs.lock().unwrap().get_mut(&id) {
+            f(job);
+        }
+    }
+
+    pub fn finish(&self, id: u64, state: JobState, message: Option<String>) {
+        self.update(id, |job| {
+            job.state = state;
+            job.message = message;
+            job.finished_at = Some(unix_now());
+        });
+    }
+}
+
+fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default()
+}
@@ -0,0 +1,243 @@
+// Until Every Cage is Empty
+// Copyright (C) 2025 Eli Perez
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+// Contact the developer directly at untileverycageproject@protonmail.com
+//! GET /api/export/kml and /api/export/kmz: facilities as a KML document for
+//! Google Earth, with a folder and a pin color per activity. They take the
+//! filters of /api/facilities.
+
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::fmt::Write as _;
+use std::io::{Cursor, Write as _};
+
+use axum::extract::{Query, State};
+use axum::http::{StatusCode, header};
+use axum::response::IntoResponse;
+use serde::Deserialize;
+
+use crate::AppState;
+use crate::activities::Activity;
+use crate::errors::ApiError;
+use crate::facilities::{Facility, FacilityFilter};
+
+const KML_CONTENT_TYPE: &str = "application/vnd.google-earth.kml+xml";
+const KMZ_CONTENT_TYPE: &str = "application/vnd.google-earth.kmz";
+
+const ICON: &str = "https://maps.google.com/mapfiles/kml/paddle/wht-blank.png";
+
+/// Pin colors (KML's aabbggrr), following the map legend: red for slaughter,
+/// grey for processing, violet for laboratories, yellow for breeders, orange
+/// for dealers and green for exhibitors. Everything else is blue.
+const COLORS: &[(Activity, &str)] = &[
+    (Activity::MeatSlaughter, "ff2f2fd6"),
+    (Activity::PoultrySlaughter, "ff2f2fd6"),
+    (Activity::MeatProcessing, "ff8c8c8c"),
+    (Activity::PoultryProcessing, "ff8c8c8c"),
+    (Activity::EggProducts, "ff8c8c8c"),
+    (Activity::FishProcessing, "ff8c8c8c"),
+    (Activity::DairyProcessing, "ff8c8c8c"),
+    (Activity::ColdStorage, "ff8c8c8c"),
+    (Activity::Laboratory, "ffcb2f9c"),
+    (Activity::Breeder, "ff2fd6f0"),
+    (Activity::Dealer, "ff1f8cf0"),
+    (Activity::Exhibition, "ff3fb83f"),
+];
+const DEFAULT_COLOR: &str = "ffd67f2f";
+
+fn color(activity: Activity) -> &'static str {
+    COLORS
+        .iter()
+        .find(|(known, _)| *known == activity)
+        .map(|(_, color)| *color)
+        .unwrap_or(DEFAULT_COLOR)
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// The activity a facility is filed and styled under: its first one.
+fn primary_activity(facility: &Facility) -> Activity {
+    facility
+        .activities
+        .first()
+        .copied()
+        .unwrap_or(Activity::Other)
+}
+
+fn placemark(kml: &mut String, facility: &Facility, activity: Activity) {
+    let address = [
+        facility.street.as_str(),
+        facility.city.as_str(),
+        &format!("{} {}", facility.state, facility.zip),
+    ]
+    .iter()
+    .map(|part| part.trim())
+    .filter(|part| !part.is_empty())
+    .collect::<Vec<_>>()
+    .join(", ");
+
+    let _ = writeln!(kml, "      <Placemark>");
+    let _ = writeln!(kml, "        <name>{}</name>", escape(&facility.name));
+    let _ = writeln!(
+        kml,
+        "        <description>{}</description>",
+        escape(&format!("{}\n{}", facility.facility_type, address))
+    );
+    let _ = writeln!(kml, "        <styleUrl>#{}</styleUrl>", activity.id());
+    let _ = writeln!(kml, "        <ExtendedData>");
+    for (name, value) in [
+        ("id", facility.id.as_str()),
+        ("country", facility.country.as_str()),
+        ("type", facility.facility_type.as_str()),
+        ("address", address.as_str()),
+    ] {
+        let _ = writeln!(
+            kml,
+            "          <Data name=\"{}\"><value>{}</value></Data>",
+            name,
+            escape(value)
+        );
+    }
+    let _ = writeln!(kml, "        </ExtendedData>");
+    let _ = writeln!(
+        kml,
+        "        <Point><coordinates>{},{}</coordinates></Point>",
+        facility.longitude, facility.latitude
+    );
+    let _ = writeln!(kml, "      </Placemark>");
+}
+
+/// A KML document with a folder per activity. Facilities without
+/// coordinates are left out, since Google Earth would put them at 0, 0.
+fn document(facilities: &[&Facility]) -> String {
+    let mut by_activity: BTreeMap<Activity, Vec<&Facility>> = BTreeMap::new();
+    for facility in facilities {
+        if facility.latitude == 0.0 && facility.longitude == 0.0 {
+            continue;
+        }
+        by_activity
+            .entry(primary_activity(facility))
+            .or_default()
+            .push(facility);
+    }
+
+    let mut kml = String::from(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+         <kml xmlns=\"http://www.opengis.net/kml/2.2\">\n  <Document>\n    \
+         <name>Until Every Cage is Empty</name>\n",
+    );
+    for activity in by_activity.keys() {
+        let _ = writeln!(
+            kml,
+            "    <Style id=\"{}\"><IconStyle><color>{}</color><Icon><href>{}</href></Icon></IconStyle></Style>",
+            activity.id(),
+            color(*activity),
+            ICON
+        );
+    }
+    for (activity, facilities) in &by_activity {
+        let _ = writeln!(kml, "    <Folder>");
+        let _ = writeln!(kml, "      <name>{}</name>", escape(activity.label()));
+        for facility in facilities {
+            placemark(&mut kml, facility, *activity);
+        }
+        let _ = writeln!(kml, "    </Folder>");
+    }
+    kml.push_str("  </Document>\n</kml>\n");
+    kml
+}
+
+fn kmz(kml: &str) -> Result<Vec<u8>, Box<dyn Error>> {
+    let mut archive = zip::ZipWriter::new(Cursor::new(Vec::new()));
+    archive.start_file("doc.kml", zip::write::SimpleFileOptions::default())?;
+    archive.write_all(kml.as_bytes())?;
+    Ok(archive.finish()?.into_inner())
+}
+
+#[derive(Deserialize)]
+pub struct ExportParams {
+    source: Option<String>,
+    country_code: Option<String>,
+    state: Option<String>,
+    species: Option<String>,
+    activity: Option<String>,
+    status: Option<String>,
+}
+
+/// The KML document, or why the parameters are wrong.
+fn export(state: &AppState, params: &ExportParams) -> Result<String, String> {
+    let filter = FacilityFilter::parse(
+        params.source.as_deref(),
+        params.country_code.as_deref(),
+        params.state.as_deref(),
+        params.species.as_deref(),
+        params.activity.as_deref(),
+        params.status.as_deref(),
+    )?;
+    let index = state.facilities();
+    Ok(document(&filter.select(&index)))
+}
+
+/// GET /api/export/kml
+pub async fn get_kml_export_handler(
+    State(state): State<AppState>,
+    Query(params): Query<ExportParams>,
+) -> impl IntoResponse {
+    match export(&state, &params) {
+        Ok(kml) => (
+            [
+                (header::CONTENT_TYPE, KML_CONTENT_TYPE),
+                (
+                    header::CONTENT_DISPOSITION,
+                    "attachment; filename=\"facilities.kml\"",
+                ),
+            ],
+            kml,
+        )
+            .into_response(),
+        Err(e) => (StatusCode::BAD_REQUEST, e).into_response(),
+    }
+}
+
+/// GET /api/export/kmz: the KML document zipped, as Google Earth shares it.
+pub async fn get_kmz_export_handler(
+    State(state): State<AppState>,
+    Query(params): Query<ExportParams>,
+) -> impl IntoResponse {
+    let kml = match export(&state, &params) {
+        Ok(kml) => kml,
+        Err(e) => return (StatusCode::BAD_REQUEST, e).into_response(),
+    };
+    match kmz(&kml) {
+        Ok(bytes) => (
+            [
+                (header::CONTENT_TYPE, KMZ_CONTENT_TYPE),
+                (
+                    header::CONTENT_DISPOSITION,
+                    "attachment; filename=\"facilities.kmz\"",
+                ),
+            ],
+            bytes,
+        )
+            .into_response(),
+        Err(e) => ApiError::internal(format!("Failed to build KMZ: {}", e)).into_response(),
+    }
+}
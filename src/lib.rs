@@ -15,131 +15,1259 @@
 // along with this program. If not, see <https://www.gnu.org/licenses/>.
 
 // Contact the developer directly at untileverycageproject@protonmail.com
-use axum::extract::Query;
+use axum::extract::{Path, Query, State};
+use axum::http::HeaderMap;
 use axum::{Json, http::StatusCode, response::IntoResponse};
+use chrono::NaiveDate;
 use include_dir::{Dir, include_dir};
 use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::error::Error;
+use std::sync::{Arc, RwLock};
 
+mod access;
+pub mod activities;
+mod address;
+mod admin;
+pub mod app;
+mod aquaculture;
+pub mod archive;
+mod batch;
+mod caching;
+mod cafos;
+mod cards;
+mod changelog;
+mod changes;
+mod choropleth;
+mod citations;
+mod csv_export;
+mod delta;
+mod deprecations;
+mod details;
+mod diff;
+mod duplicates;
+mod egg_farms;
+mod enforcement;
+mod equine;
+mod errors;
+pub mod eu_codes;
+mod facilities;
+mod feedback;
+pub mod fields;
+mod filter;
+mod fur_farms;
+pub mod geocode;
+mod geojson;
+mod graphql;
+mod health;
+mod heatmap;
+mod i18n;
+pub mod ids;
+pub mod importer;
+pub mod importers;
+mod jobs;
+mod kml;
 mod location;
+pub mod mapping;
+mod matching;
+mod meta;
+mod moderation;
+mod openapi;
+mod paging;
+pub mod pdf;
+mod postal;
+mod precompressed;
+mod protobuf;
+mod quality;
+mod redaction;
+mod regions;
+mod schema;
+pub mod scoring;
+mod search;
+mod served;
+pub mod settings;
+pub mod slug;
+pub mod smiley;
+pub mod snapshots;
+mod spatial;
+pub mod species;
+mod sqlite_export;
+mod stats;
+mod status;
+mod storage;
+pub mod store;
+mod submissions;
+mod subscriptions;
+pub mod telemetry;
+mod tiles;
+mod timeseries;
+mod validation;
+mod volume;
+use crate::activities::Activity;
+use crate::address::CityStateZip;
+use crate::aquaculture::AquacultureFacility;
+use crate::cafos::CafoFacility;
+use crate::citations::Citations;
+use crate::details::InspectionLinks;
+use crate::egg_farms::EggFarmFacility;
+use crate::enforcement::Enforcement;
+use crate::equine::EquineFacility;
+use crate::facilities::FacilityIndex;
+use crate::feedback::FeedbackLog;
+use crate::filter::{AddressFilter, LocationFilter};
+use crate::fur_farms::FurFarmFacility;
 use crate::location::*;
+use crate::moderation::{Flag, Moderation};
+use crate::paging::Page;
+use crate::regions::RegionFilter;
+use crate::scoring::{ScoreComponent, ScoreInputs, ScoringConfig};
+use crate::served::{Reports, Served};
+use crate::settings::Settings;
+use crate::spatial::LocationIndex;
+use crate::status::FacilityStatus;
+use crate::storage::{Backend, LocationQuery, StoredLocation};
+use crate::store::{DataSource, DataStore, Dataset};
+use crate::submissions::{Submission, SubmissionQueue, SubmissionStatus};
+use crate::subscriptions::Subscriptions;
+use crate::timeseries::AphisYears;
+use crate::validation::{CoordinateReport, Policy};
 
-pub use location::Location;
+pub use access::{Access, rate_limit};
+pub use activities::get_activities_handler;
+pub use admin::{post_geocode_handler, post_reload_handler};
+pub use aquaculture::{AquacultureSite, get_aquaculture_handler};
+pub use batch::post_batch_handler;
+pub use caching::cache_headers;
+pub use cafos::{CafoPermit, get_cafos_handler};
+pub use cards::{get_location_card_handler, get_sitemap_handler};
+pub use changelog::get_changelog_handler;
+pub use changes::get_location_changes_handler;
+pub use choropleth::get_choropleth_handler;
+pub use citations::{CitationKind, InspectionCitation, get_citations_handler};
+pub use delta::get_location_delta_handler;
+pub use deprecations::{deprecation_headers, get_deprecations_handler};
+pub use details::{
+    get_aphis_inspections_handler, get_aphis_report_handler, get_inspection_report_handler,
+    get_location_handler,
+};
+pub use duplicates::get_match_handler;
+pub use egg_farms::{EggFarm, get_egg_farms_handler};
+pub use enforcement::{
+    EnforcementKind, EnforcementRecord, get_enforcement_handler, get_location_enforcement_handler,
+};
+pub use equine::{EquineSite, get_equine_handler};
+pub use errors::ApiError;
+pub use facilities::{
+    get_facilities_handler, get_facility_handler, get_related_facilities_handler,
+};
+pub use feedback::{get_feedback_handler, post_feedback_handler};
+pub use fur_farms::{FurFarm, get_fur_farms_handler};
+pub use graphql::{get_graphql_handler, post_graphql_handler};
+pub use health::{get_healthz_handler, get_readyz_handler, load_warning};
+pub use heatmap::get_heatmap_handler;
+pub use i18n::get_i18n_handler;
+pub use importers::get_importers_handler;
+pub use kml::{get_kml_export_handler, get_kmz_export_handler};
+pub use location::{
+    AphisReport, FacilityType, InspectionReport, Location, PainCategories, SlaughterClass,
+    get_slaughtered_animals, get_tested_animals,
+};
+pub use meta::get_meta_handler;
+pub use moderation::{
+    delete_flag_handler, get_audit_handler, post_review_handler, put_flag_handler,
+    put_submission_handler,
+};
+pub use openapi::{get_docs_handler, get_openapi_handler};
+pub use paging::TOTAL_COUNT_HEADER;
+pub use postal::{get_locations_by_fips_handler, get_locations_by_zip_handler};
+pub use precompressed::precompress;
+pub use quality::get_data_quality_handler;
+pub use redaction::{delete_residence_handler, get_residences_handler, put_residence_handler};
+pub use search::{get_autocomplete_handler, get_search_handler};
+pub use spatial::{
+    get_locations_along_route_handler, get_locations_in_bbox_handler, get_locations_near_handler,
+    post_locations_nearest_handler,
+};
+pub use sqlite_export::get_sqlite_export_handler;
+pub use stats::get_stats_handler;
+pub use submissions::{get_pending_submissions_handler, post_submission_handler};
+pub use subscriptions::{
+    delete_subscription_handler, get_subscriptions_handler, post_subscription_handler,
+};
+pub use tiles::get_tile_handler;
+pub use timeseries::get_aphis_timeseries_handler;
 
 const DATA_DIR: Dir = include_dir!("./static_data");
 
-pub async fn get_locations_handler(Query(params): Query<LocationParams>) -> impl IntoResponse {
-    match read_locations_from_csv(params.country_code).await {
-        Ok(locations) => Json(locations).into_response(),
-        Err(e) => (
-            StatusCode::INTERNAL_SERVER_ERROR,
-            format!("Failed to read location data: {}", e),
-        )
-            .into_response(),
+/// Coordinates fixed at runtime (e.g. by an admin geocoding job), keyed by
+/// `(country, establishment_id)`. They take precedence over the CSV values.
+pub type CoordinateOverrides = Arc<RwLock<HashMap<(String, String), (f64, f64)>>>;
+
+#[derive(Clone, Default)]
+pub struct AppState {
+    jobs: jobs::JobRegistry,
+    coordinate_overrides: CoordinateOverrides,
+    scoring: Arc<ScoringConfig>,
+    regions: Arc<RegionFilter>,
+    validation: Policy,
+    location_index: Arc<RwLock<Arc<LocationIndex>>>,
+    /// What answers location queries (see `storage`).
+    storage: Arc<Backend>,
+    reports: Arc<RwLock<Arc<Reports>>>,
+    facilities: Arc<RwLock<Arc<FacilityIndex>>>,
+    store: Arc<DataStore>,
+    tiles: tiles::TileCache,
+    precompressed: precompressed::PrecompressedCache,
+    database: sqlite_export::DatabaseCache,
+    submissions: Arc<SubmissionQueue>,
+    moderation: Arc<Moderation>,
+    feedback: Arc<FeedbackLog>,
+    access: Arc<Access>,
+    subscriptions: Arc<Subscriptions>,
+    /// Why each dataset failed to load in the last rebuild, for /readyz.
+    load_errors: Arc<RwLock<BTreeMap<&'static str, String>>>,
+    /// What the state was built from, for what's read per request (admin
+    /// tokens, `SITE_URL`, the geocoder).
+    settings: Arc<Settings>,
+}
+
+impl AppState {
+    /// `from_settings` with the environment's settings.
+    pub async fn from_env() -> Result<Self, Box<dyn Error>> {
+        Self::from_settings(Settings::from_env()).await
+    }
+
+    /// Builds the state from `settings` (see `ScoringConfig::from_settings`,
+    /// `RegionFilter::from_settings` and `DataSource::from_settings`), loads
+    /// the data and prepares it for serving.
+    pub async fn from_settings(settings: Settings) -> Result<Self, Box<dyn Error>> {
+        let source = DataSource::from_settings(&settings);
+        Self::load(settings, source).await
+    }
+
+    /// Like `from_env`, with the data from `source` whatever `DATA_SOURCE`
+    /// says, e.g. a fixture directory in the tests.
+    pub async fn from_source(source: DataSource) -> Result<Self, Box<dyn Error>> {
+        Self::load(Settings::from_env(), source).await
+    }
+
+    /// `settings` with the data from `source`.
+    pub async fn load(settings: Settings, source: DataSource) -> Result<Self, Box<dyn Error>> {
+        let scoring = ScoringConfig::from_settings(&settings)?;
+        let regions = RegionFilter::from_settings(&settings)?;
+        let validation = Policy::from_settings(&settings)?;
+        let submissions = SubmissionQueue::from_settings(&settings)?;
+        let moderation = Moderation::from_settings(&settings)?;
+        let feedback = FeedbackLog::from_settings(&settings)?;
+        let access = Access::from_settings(&settings)?;
+        let subscriptions = Subscriptions::from_settings(&settings)?;
+        let storage = Backend::from_settings(&settings)?;
+        let store = DataStore::load(source).await.map_err(|e| e.to_string())?;
+        let state = AppState {
+            scoring: Arc::new(scoring),
+            regions: Arc::new(regions),
+            validation,
+            submissions: Arc::new(submissions),
+            moderation: Arc::new(moderation),
+            feedback: Arc::new(feedback),
+            access: Arc::new(access),
+            subscriptions: Arc::new(subscriptions),
+            storage: Arc::new(storage),
+            store: Arc::new(store),
+            settings: Arc::new(settings),
+            ..Default::default()
+        };
+        state.rebuild().await;
+        Ok(state)
+    }
+
+    /// Prepares the current dataset for serving, after startup or a reload.
+    /// Handlers serve from what this builds instead of parsing per request.
+    /// A dataset that fails to load keeps its last good copy (none at
+    /// startup), with the error reported by /healthz, /readyz and a
+    /// `Warning` header until a reload succeeds.
+    #[tracing::instrument(skip_all)]
+    async fn rebuild(&self) {
+        let previous = self.reports();
+        let mut coordinates = CoordinateReport::default();
+        let mut aphis = self.or_last_good(
+            "aphis_reports",
+            self.read_aphis_reports(&mut coordinates).await,
+            || previous.aphis.to_vec(),
+        );
+        let inspections = self.or_last_good(
+            "inspection_reports",
+            self.read_inspection_reports(&mut coordinates).await,
+            || previous.inspections.to_vec(),
+        );
+        let aphis_years = self.or_last_good(
+            "aphis_history",
+            self.read_aphis_history()
+                .await
+                .map(|history| AphisYears::new(&aphis, &history)),
+            || previous.aphis_years.clone(),
+        );
+        let aquaculture = self.or_last_good(
+            "aquaculture",
+            self.read_aquaculture(&mut coordinates),
+            || previous.aquaculture.to_vec(),
+        );
+        let fur_farms =
+            self.or_last_good("fur_farms", self.read_fur_farms(&mut coordinates), || {
+                previous.fur_farms.to_vec()
+            });
+        let egg_farms =
+            self.or_last_good("egg_farms", self.read_egg_farms(&mut coordinates), || {
+                previous.egg_farms.to_vec()
+            });
+        let cafos = self.or_last_good("cafos", self.read_cafos(&mut coordinates), || {
+            previous.cafos.to_vec()
+        });
+        let equine = self.or_last_good("equine", self.read_equine(&mut coordinates), || {
+            previous.equine.to_vec()
+        });
+        let citations = self.or_last_good(
+            "citations",
+            self.read_citations(&inspections).map(Citations::new),
+            || previous.citations.clone(),
+        );
+        let enforcement = self.or_last_good(
+            "enforcement",
+            enforcement::read_enforcement(&self.store.current()).map(Enforcement::new),
+            || previous.enforcement.clone(),
+        );
+        let changelog = self.or_last_good(
+            "changelog",
+            changelog::build_changelog(&self.store.current()),
+            || previous.changelog.to_vec(),
+        );
+        drop(previous);
+        self.score_aphis_reports(&mut aphis, &citations);
+        let inspection_links = InspectionLinks::new(&mut aphis, &inspections);
+        let reports = Reports {
+            aphis_years,
+            aphis: Served::new(aphis),
+            inspections: Served::new(inspections),
+            inspection_links,
+            aquaculture: Served::new(aquaculture),
+            fur_farms: Served::new(fur_farms),
+            egg_farms: Served::new(egg_farms),
+            cafos: Served::new(cafos),
+            equine: Served::new(equine),
+            citations,
+            enforcement,
+            changelog: Served::new(changelog),
+            coordinates,
+        };
+        *self.reports.write().unwrap() = Arc::new(reports);
+        // A failure keeps the last good locations, and is noted by `track`.
+        let _ = self.rebuild_location_index().await;
+    }
+
+    /// Notes whether `dataset` loaded, for /readyz, and passes the result on.
+    fn track<T>(
+        &self,
+        dataset: &'static str,
+        result: Result<T, Box<dyn Error>>,
+    ) -> Result<T, Box<dyn Error>> {
+        let mut errors = self.load_errors.write().unwrap();
+        match &result {
+            Ok(_) => errors.remove(dataset),
+            Err(e) => errors.insert(dataset, e.to_string()),
+        };
+        result
+    }
+
+    /// What `dataset` loaded, or `last_good` when it failed to (see `track`).
+    fn or_last_good<T>(
+        &self,
+        dataset: &'static str,
+        result: Result<T, Box<dyn Error>>,
+        last_good: impl FnOnce() -> T,
+    ) -> T {
+        self.track(dataset, result).unwrap_or_else(|_| last_good())
+    }
+
+    fn reports(&self) -> Arc<Reports> {
+        self.reports.read().unwrap().clone()
+    }
+
+    /// Every dataset in the common facility shape, as of the last rebuild.
+    fn facilities(&self) -> Arc<FacilityIndex> {
+        self.facilities.read().unwrap().clone()
+    }
+
+    /// The spatial index as of the last rebuild.
+    fn location_index(&self) -> Arc<LocationIndex> {
+        self.location_index.read().unwrap().clone()
+    }
+
+    /// Re-reads the locations into the spatial index, e.g. after coordinates
+    /// were overridden, and rebuilds and re-links the facilities.
+    #[tracing::instrument(skip_all)]
+    async fn rebuild_location_index(&self) -> Result<(), Box<dyn Error>> {
+        let mut coordinates = CoordinateReport::default();
+        let locations = match self.track(
+            "locations",
+            self.read_locations(None, &mut coordinates).await,
+        ) {
+            Ok(locations) => locations,
+            Err(e) => {
+                // The last good locations stay, linked to the reports as
+                // they are now.
+                let facilities =
+                    FacilityIndex::new(&self.location_index().locations, &self.reports());
+                *self.facilities.write().unwrap() = Arc::new(facilities);
+                return Err(e);
+            }
+        };
+        let mut index = LocationIndex::new(locations);
+        index.coordinates = coordinates;
+        if let Backend::Sqlite(database) = &*self.storage {
+            // The database keeps its last good rows, reported like a
+            // dataset that failed to load.
+            let _ = self.track("storage", database.load(&index.locations));
+        }
+        let facilities = FacilityIndex::new(&index.locations, &self.reports());
+        let mut current = self.location_index.write().unwrap();
+        index.revision = current.revision + 1;
+        let previous = std::mem::replace(&mut *current, Arc::new(index));
+        let index = current.clone();
+        drop(current);
+        // The first build has nothing to compare with.
+        if previous.revision > 0 {
+            self.subscriptions.notify(&previous, &index);
+        }
+        *self.facilities.write().unwrap() = Arc::new(facilities);
+        self.tiles.write().unwrap().clear();
+        self.precompressed.write().unwrap().clear();
+        Ok(())
+    }
+
+    #[tracing::instrument(name = "load", skip_all, fields(dataset = "locations"), err)]
+    async fn read_locations(
+        &self,
+        country: Option<String>,
+        coordinates: &mut CoordinateReport,
+    ) -> Result<Vec<LocationResponse>, Box<dyn Error>> {
+        let approved = self.submissions.with_status(SubmissionStatus::Approved);
+        let mut locations =
+            read_locations_from_csv(&self.store.current(), country, &approved).await?;
+
+        let overrides = self.coordinate_overrides.read().unwrap();
+        if !overrides.is_empty() {
+            for loc in &mut locations {
+                let key = (loc.country.clone(), loc.establishment_id.clone());
+                if let Some((latitude, longitude)) = overrides.get(&key) {
+                    loc.latitude = *latitude;
+                    loc.longitude = *longitude;
+                }
+            }
+        }
+        drop(overrides);
+
+        // A moderator's flag is newer than the status file it overrides.
+        let statuses = status::read_statuses(&self.store.current())?;
+        let flags = self.moderation.flags();
+        if !statuses.is_empty() || !flags.is_empty() {
+            for loc in &mut locations {
+                let key = (loc.country.clone(), loc.establishment_id.clone());
+                if let Some(entry) = statuses.get(&key) {
+                    loc.status = entry.status;
+                    loc.status_date = entry.date.clone();
+                }
+                loc.flag = flags.get(&key).cloned();
+                if let Some(status) = loc.flag.as_ref().and_then(Flag::status) {
+                    loc.status = status;
+                    loc.status_date = loc.flag.as_ref().map_or_else(String::new, Flag::date);
+                }
+            }
+        }
+
+        coordinates.validate("locations", &mut locations, self.validation);
+        if !self.regions.is_empty() {
+            locations.retain(|loc| self.regions.allows(loc.latitude, loc.longitude));
+        }
+
+        let reports = self.reports();
+        for loc in &mut locations {
+            let species = [&loc.animals_slaughtered, &loc.animals_processed]
+                .iter()
+                .flat_map(|animals| animals.split(", "))
+                .filter(|animal| !animal.is_empty() && *animal != "N/A")
+                .collect::<HashSet<_>>()
+                .len();
+            let priority = self.scoring.score(&ScoreInputs {
+                volume: scoring::fsis_volume(
+                    &loc.slaughter_volume_category,
+                    &loc.processing_volume_category,
+                ),
+                species,
+                // Enforcement records are FSIS's, linked by establishment ID.
+                violations: if loc.country == "us" {
+                    reports.enforcement.count(&loc.establishment_id) as u32
+                } else {
+                    0
+                },
+                license_active: loc.status == FacilityStatus::Active,
+            });
+            loc.priority_score = priority.score;
+            loc.priority_breakdown = priority.breakdown;
+        }
+        Ok(locations)
+    }
+
+    #[tracing::instrument(name = "load", skip_all, fields(dataset = "aphis_reports"), err)]
+    async fn read_aphis_reports(
+        &self,
+        coordinates: &mut CoordinateReport,
+    ) -> Result<Vec<AphisReport>, Box<dyn Error>> {
+        let mut reports = read_aphis_reports_from_csv(&self.store.current()).await?;
+        coordinates.validate("aphis_reports", &mut reports, self.validation);
+        if !self.regions.is_empty() {
+            reports.retain(|report| self.regions.allows(report.latitude, report.longitude));
+        }
+        Ok(reports)
+    }
+
+    /// Scores the APHIS reports, once the citations they're scored on are
+    /// read.
+    fn score_aphis_reports(&self, reports: &mut [AphisReport], citations: &Citations) {
+        for report in reports {
+            let counts: Vec<f64> = animal_counts(report)
+                .into_iter()
+                .map(|(_, count)| f64::from(count))
+                .collect();
+
+            let priority = self.scoring.score(&ScoreInputs {
+                volume: scoring::animal_count_volume(counts.iter().sum()),
+                species: counts.len(),
+                violations: citations.cited(&report.certificate_number) as u32,
+                license_active: FacilityStatus::from_certificate(&report.certificate_status)
+                    == FacilityStatus::Active,
+            });
+            report.priority_score = Some(priority.score);
+            report.priority_breakdown = Some(priority.breakdown);
+        }
+    }
+
+    /// Earlier annual reports, for the time series only.
+    #[tracing::instrument(name = "load", skip_all, fields(dataset = "aphis_history"), err)]
+    async fn read_aphis_history(&self) -> Result<Vec<AphisReport>, Box<dyn Error>> {
+        let mut reports = read_aphis_history_from_csv(&self.store.current()).await?;
+        if !self.regions.is_empty() {
+            reports.retain(|report| self.regions.allows(report.latitude, report.longitude));
+        }
+        Ok(reports)
+    }
+
+    #[tracing::instrument(name = "load", skip_all, fields(dataset = "aquaculture"), err)]
+    fn read_aquaculture(
+        &self,
+        coordinates: &mut CoordinateReport,
+    ) -> Result<Vec<AquacultureFacility>, Box<dyn Error>> {
+        let mut facilities = aquaculture::read_aquaculture(&self.store.current())?;
+        coordinates.validate("aquaculture", &mut facilities, self.validation);
+        if !self.regions.is_empty() {
+            facilities.retain(|facility| {
+                self.regions
+                    .allows(facility.site.latitude, facility.site.longitude)
+            });
+        }
+        Ok(facilities)
+    }
+
+    #[tracing::instrument(name = "load", skip_all, fields(dataset = "fur_farms"), err)]
+    fn read_fur_farms(
+        &self,
+        coordinates: &mut CoordinateReport,
+    ) -> Result<Vec<FurFarmFacility>, Box<dyn Error>> {
+        let mut farms = fur_farms::read_fur_farms(&self.store.current())?;
+        coordinates.validate("fur_farms", &mut farms, self.validation);
+        if !self.regions.is_empty() {
+            farms.retain(|facility| {
+                self.regions
+                    .allows(facility.farm.latitude, facility.farm.longitude)
+            });
+        }
+        Ok(farms)
+    }
+
+    #[tracing::instrument(name = "load", skip_all, fields(dataset = "egg_farms"), err)]
+    fn read_egg_farms(
+        &self,
+        coordinates: &mut CoordinateReport,
+    ) -> Result<Vec<EggFarmFacility>, Box<dyn Error>> {
+        let mut farms = egg_farms::read_egg_farms(&self.store.current())?;
+        coordinates.validate("egg_farms", &mut farms, self.validation);
+        if !self.regions.is_empty() {
+            farms.retain(|facility| {
+                self.regions
+                    .allows(facility.farm.latitude, facility.farm.longitude)
+            });
+        }
+        Ok(farms)
+    }
+
+    #[tracing::instrument(name = "load", skip_all, fields(dataset = "cafos"), err)]
+    fn read_cafos(
+        &self,
+        coordinates: &mut CoordinateReport,
+    ) -> Result<Vec<CafoFacility>, Box<dyn Error>> {
+        let mut facilities = cafos::read_cafos(&self.store.current())?;
+        coordinates.validate("cafos", &mut facilities, self.validation);
+        if !self.regions.is_empty() {
+            facilities.retain(|facility| {
+                self.regions
+                    .allows(facility.permit.latitude, facility.permit.longitude)
+            });
+        }
+        Ok(facilities)
+    }
+
+    #[tracing::instrument(name = "load", skip_all, fields(dataset = "equine"), err)]
+    fn read_equine(
+        &self,
+        coordinates: &mut CoordinateReport,
+    ) -> Result<Vec<EquineFacility>, Box<dyn Error>> {
+        let mut facilities = equine::read_equine(&self.store.current())?;
+        coordinates.validate("equine", &mut facilities, self.validation);
+        if !self.regions.is_empty() {
+            facilities.retain(|facility| {
+                self.regions
+                    .allows(facility.site.latitude, facility.site.longitude)
+            });
+        }
+        Ok(facilities)
+    }
+
+    /// Citations carry no coordinates, so with a region filter they are kept
+    /// for the licensees whose inspection report is kept.
+    #[tracing::instrument(name = "load", skip_all, fields(dataset = "citations"), err)]
+    fn read_citations(
+        &self,
+        inspections: &[InspectionReport],
+    ) -> Result<Vec<InspectionCitation>, Box<dyn Error>> {
+        let mut rows = citations::read_citations(&self.store.current())?;
+        if !self.regions.is_empty() {
+            let kept: HashSet<&str> = inspections
+                .iter()
+                .map(|report| report.certificate_number.as_str())
+                .collect();
+            rows.retain(|row| kept.contains(row.certificate_number.as_str()));
+        }
+        Ok(rows)
+    }
+
+    #[tracing::instrument(name = "load", skip_all, fields(dataset = "inspection_reports"), err)]
+    async fn read_inspection_reports(
+        &self,
+        coordinates: &mut CoordinateReport,
+    ) -> Result<Vec<InspectionReport>, Box<dyn Error>> {
+        let mut reports = read_inspection_reports_from_csv(&self.store.current()).await?;
+        coordinates.validate("inspection_reports", &mut reports, self.validation);
+        let centroids = redaction::ZipCentroids::read(&self.store.current())?;
+        redaction::redact(&mut reports, &self.moderation.residences(), &centroids);
+        if !self.regions.is_empty() {
+            reports.retain(|report| self.regions.allows(report.latitude, report.longitude));
+        }
+        Ok(reports)
+    }
+}
+
+/// Sorts `items` by `sort=priority_score`, highest first unless `order=asc`.
+fn apply_sort<T>(
+    items: &mut [T],
+    sort: Option<&str>,
+    order: Option<&str>,
+    priority_score: impl Fn(&T) -> f64,
+) -> Result<(), String> {
+    match sort {
+        None => return Ok(()),
+        Some("priority_score") => {
+            items.sort_by(|a, b| priority_score(b).total_cmp(&priority_score(a)));
+        }
+        Some(other) => return Err(format!("Unsupported sort field {}", other)),
+    }
+    match order {
+        None | Some("desc") => Ok(()),
+        Some("asc") => {
+            items.reverse();
+            Ok(())
+        }
+        Some(other) => Err(format!("Unsupported sort order {}", other)),
+    }
+}
+
+pub async fn get_locations_handler(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Query(params): Query<LocationParams>,
+) -> impl IntoResponse {
+    let parse = || -> Result<(LocationFilter, Page), String> {
+        let filter = LocationFilter::parse(
+            params.state.as_deref(),
+            params.species.as_deref(),
+            params.activity.as_deref(),
+            params.status.as_deref(),
+        )?
+        .granted(
+            filter::date_param("granted_after", params.granted_after.as_deref())?,
+            filter::date_param("granted_before", params.granted_before.as_deref())?,
+        );
+        let page = Page::parse(params.limit, params.offset, params.fields.as_deref())?;
+        Ok((filter, page))
+    };
+    let (filter, page) = match parse() {
+        Ok(parsed) => parsed,
+        Err(e) => return (StatusCode::BAD_REQUEST, e).into_response(),
+    };
+
+    let index = state.location_index();
+    let country = params.country_code.as_deref().map(geocode::country_code);
+    if country.is_none() && filter.is_empty() && params.sort.is_none() {
+        return geojson::respond_all(&index.locations, &headers, params.format.as_deref(), &page);
+    }
+
+    let query = LocationQuery {
+        country_code: country.as_deref(),
+        filter: Some(&filter),
+        ..Default::default()
+    };
+    let mut locations = match state.storage.with(&index).locations(&query) {
+        Ok(locations) => locations,
+        Err(e) => return ApiError::internal(e).into_response(),
+    };
+    let sorted = apply_sort(
+        &mut locations,
+        params.sort.as_deref(),
+        params.order.as_deref(),
+        StoredLocation::priority_score,
+    );
+    match sorted {
+        Ok(()) => geojson::respond(&locations, &headers, params.format.as_deref(), &page),
+        Err(e) => (StatusCode::BAD_REQUEST, e).into_response(),
     }
 }
 
-pub async fn get_aphis_reports_handler() -> impl IntoResponse {
-    match read_aphis_reports_from_csv().await {
-        Ok(reports) => Json(reports).into_response(),
-        Err(e) => (
-            StatusCode::INTERNAL_SERVER_ERROR,
-            format!("Failed to read APHIS data: {}", e),
+pub async fn get_location_by_slug_handler(
+    State(state): State<AppState>,
+    Path(slug): Path<String>,
+) -> impl IntoResponse {
+    let index = state.location_index();
+    match index.locations.iter().find(|loc| loc.slug == slug) {
+        Some(location) => Json(location).into_response(),
+        None => (
+            StatusCode::NOT_FOUND,
+            format!("No location found for slug {}", slug),
         )
             .into_response(),
     }
 }
 
-pub async fn get_inspection_reports_handler() -> impl IntoResponse {
-    match read_inspection_reports_from_csv().await {
-        Ok(reports) => Json(reports).into_response(),
-        Err(e) => (
-            StatusCode::INTERNAL_SERVER_ERROR,
-            format!("Failed to read inspection reports data: {}", e),
+pub async fn get_aphis_reports_handler(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Query(params): Query<AphisParams>,
+) -> impl IntoResponse {
+    let parse = || -> Result<(Option<NaiveDate>, AddressFilter, Page), String> {
+        let since = filter::date_param("status_since", params.status_since.as_deref())?;
+        let address = AddressFilter::parse(
+            params.city.as_deref(),
+            params.state.as_deref(),
+            params.zip.as_deref(),
+        )?;
+        let page = Page::parse(params.limit, params.offset, params.fields.as_deref())?;
+        Ok((since, address, page))
+    };
+    let (since, address, page) = match parse() {
+        Ok(parsed) => parsed,
+        Err(e) => return (StatusCode::BAD_REQUEST, e).into_response(),
+    };
+
+    let reports = state.reports();
+    if params.sort.is_none() && since.is_none() && address.is_empty() {
+        return geojson::respond_all(&reports.aphis, &headers, params.format.as_deref(), &page);
+    }
+
+    let mut sorted_reports: Vec<&AphisReport> = reports
+        .aphis
+        .iter()
+        .filter(|report| status_since(report.status_date, since))
+        .filter(|report| address.matches(&report.city, &report.state, &report.zip))
+        .collect();
+    let sorted = apply_sort(
+        &mut sorted_reports,
+        params.sort.as_deref(),
+        params.order.as_deref(),
+        |report| report.priority_score.unwrap_or_default(),
+    );
+    match sorted {
+        Ok(()) => geojson::respond(&sorted_reports, &headers, params.format.as_deref(), &page),
+        Err(e) => (StatusCode::BAD_REQUEST, e).into_response(),
+    }
+}
+
+pub async fn get_inspection_reports_handler(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Query(params): Query<InspectionParams>,
+) -> impl IntoResponse {
+    let parse = || -> Result<(Option<NaiveDate>, AddressFilter, Page), String> {
+        let since = filter::date_param("status_since", params.status_since.as_deref())?;
+        let address = AddressFilter::parse(
+            params.city.as_deref(),
+            params.state.as_deref(),
+            params.zip.as_deref(),
+        )?;
+        let page = Page::parse(params.limit, params.offset, params.fields.as_deref())?;
+        Ok((since, address, page))
+    };
+    let (since, address, page) = match parse() {
+        Ok(parsed) => parsed,
+        Err(e) => return (StatusCode::BAD_REQUEST, e).into_response(),
+    };
+    let wanted: Vec<FacilityType> = match params
+        .license_type
+        .as_deref()
+        .unwrap_or_default()
+        .split(',')
+        .map(str::trim)
+        .filter(|term| !term.is_empty())
+        .map(|term| {
+            FacilityType::parse(term).ok_or_else(|| {
+                format!(
+                    "Unknown license type {}, expected A, B, C or R (or breeder, dealer, exhibitor, research)",
+                    term
+                )
+            })
+        })
+        .collect()
+    {
+        Ok(wanted) => wanted,
+        Err(e) => return (StatusCode::BAD_REQUEST, e).into_response(),
+    };
+
+    let reports = state.reports();
+    if wanted.is_empty() && since.is_none() && address.is_empty() {
+        return geojson::respond_all(
+            &reports.inspections,
+            &headers,
+            params.format.as_deref(),
+            &page,
+        );
+    }
+    let selected: Vec<&InspectionReport> = reports
+        .inspections
+        .iter()
+        .filter(|report| {
+            wanted.is_empty()
+                || report
+                    .facility_type
+                    .is_some_and(|facility_type| wanted.contains(&facility_type))
+        })
+        .filter(|report| status_since(report.status_date, since))
+        .filter(|report| address.matches(&report.city, &report.state, &report.zip))
+        .collect();
+    geojson::respond(&selected, &headers, params.format.as_deref(), &page)
+}
+
+/// Whether a report's status changed on or after `since`. Reports without a
+/// status date are left out once `since` is given.
+fn status_since(status_date: Option<NaiveDate>, since: Option<NaiveDate>) -> bool {
+    since.is_none_or(|since| status_date.is_some_and(|date| date >= since))
+}
+
+pub async fn get_jobs_handler(State(state): State<AppState>) -> impl IntoResponse {
+    Json(state.jobs.list())
+}
+
+pub async fn get_job_handler(
+    State(state): State<AppState>,
+    Path(id): Path<u64>,
+) -> impl IntoResponse {
+    match state.jobs.get(id) {
+        Some(job) => Json(job).into_response(),
+        None => (StatusCode::NOT_FOUND, format!("No job with id {}", id)).into_response(),
+    }
+}
+
+/// Species a location handles: the FSIS class columns where present, plus any
+/// named in the activity description (how the other countries record them).
+fn species_of(
+    record: &Location,
+    mentioned: &mut HashMap<String, Vec<&'static str>>,
+) -> Vec<&'static str> {
+    let mut taxa: Vec<&'static str> = slaughter_classes(record)
+        .into_iter()
+        .chain(processing_classes(record))
+        .filter(|(_, value)| *value)
+        .map(|(id, _)| id)
+        .collect();
+    // Few distinct activity strings repeat across thousands of rows.
+    let named = mentioned
+        .entry(record.activities.clone())
+        .or_insert_with(|| {
+            species::mentioned_in(&record.activities)
+                .iter()
+                .map(|taxon| taxon.id)
+                .collect()
+        });
+    for id in named.iter() {
+        if !taxa.contains(id) {
+            taxa.push(id);
+        }
+    }
+    taxa
+}
+
+/// An approved submission as a location. Only what the submitter gave is
+/// known, so the FSIS columns are left empty.
+fn location_from_submission(submission: &Submission) -> LocationResponse {
+    let facility = &submission.facility;
+    let activities: Vec<Activity> = Activity::parse(&facility.activity).into_iter().collect();
+    let taxa: Vec<&'static str> = species::mentioned_in(&facility.notes)
+        .iter()
+        .map(|taxon| taxon.id)
+        .collect();
+    LocationResponse {
+        facility_id: ids::facility_id(
+            "locations",
+            &geocode::country_dir(&facility.country),
+            &submission.establishment_id(),
+        ),
+        country: geocode::country_code(&facility.country),
+        establishment_id: submission.establishment_id(),
+        establishment_name: facility.name.clone(),
+        latitude: facility.latitude.unwrap_or_default(),
+        longitude: facility.longitude.unwrap_or_default(),
+        r#type: activities
+            .iter()
+            .map(|activity| activity.label())
+            .collect::<Vec<_>>()
+            .join("; "),
+        activities,
+        state: facility.state.clone(),
+        county: String::new(),
+        fips_code: String::new(),
+        city: facility.city.clone(),
+        street: facility.street.clone(),
+        zip: facility.zip.clone(),
+        slaughter: false,
+        animals_slaughtered: String::new(),
+        animals_processed: String::new(),
+        species_slaughtered: Vec::new(),
+        slaughter_classes: Vec::new(),
+        species_processed: Vec::new(),
+        species: species::groups(taxa.iter().copied()),
+        taxa,
+        slaughter_volume_category: String::new(),
+        processing_volume_category: String::new(),
+        estimated_annual_slaughter_min: None,
+        estimated_annual_slaughter_max: None,
+        dbas: String::new(),
+        dba_names: Vec::new(),
+        phone: String::new(),
+        grant_date: None,
+        slug: String::new(),
+        priority_score: 0.0,
+        priority_breakdown: Vec::new(),
+        status: FacilityStatus::Active,
+        status_date: String::new(),
+        flag: None,
+    }
+}
+
+/// The rows of a locations.csv, one per establishment. Some sources list an
+/// establishment once per approval section (the German register has a row
+/// for its slaughter approval and another for its cutting plant), which
+/// would serve it twice under one facility ID. Rows with the same country
+/// and establishment ID are merged where the first was seen; an empty field
+/// takes the other row's value (see `merge_field` for differing ones).
+fn merge_sections<R: std::io::Read>(
+    reader: &mut csv::Reader<R>,
+) -> Result<Vec<csv::StringRecord>, csv::Error> {
+    let headers = reader.headers()?.clone();
+    let column = |name: &str| headers.iter().position(|header| header == name);
+    let (id, country) = (column("establishment_id"), column("country"));
+    let mut rows: Vec<Vec<String>> = Vec::new();
+    let mut seen = HashMap::new();
+    for record in reader.records() {
+        let record = record?;
+        let field = |index: Option<usize>| index.and_then(|i| record.get(i)).unwrap_or_default();
+        let key = (
+            field(country).trim().to_lowercase(),
+            field(id).trim().to_string(),
+        );
+        match seen.get(&key).filter(|_| !key.1.is_empty()) {
+            Some(&index) => {
+                let merged: &mut Vec<String> = &mut rows[index];
+                for (value, other) in merged.iter_mut().zip(record.iter()) {
+                    merge_field(value, other);
+                }
+            }
+            None => {
+                seen.insert(key, rows.len());
+                rows.push(record.iter().map(str::to_string).collect());
+            }
+        }
+    }
+    Ok(rows.into_iter().map(csv::StringRecord::from).collect())
+}
+
+/// Merges another section's `other` into `value`. Yes/no flags are set if
+/// either section sets them, numbers and dates (coordinates, grant dates)
+/// keep the first section's, and any other text lists both, `; `-separated
+/// as `type` already is.
+fn merge_field(value: &mut String, other: &str) {
+    let other = other.trim();
+    if other.is_empty() || value.trim() == other {
+        return;
+    }
+    if value.trim().is_empty() {
+        *value = other.to_string();
+        return;
+    }
+    let is_flag = |value: &str| {
+        matches!(
+            value.trim().to_lowercase().as_str(),
+            "yes" | "y" | "x" | "true" | "1" | "no" | "n" | "false" | "0"
         )
-            .into_response(),
+    };
+    let is_scalar =
+        |value: &str| value.trim().parse::<f64>().is_ok() || fields::parse_date(value).is_some();
+    if is_flag(value) && is_flag(other) {
+        if !fields::parse_yes(value) && fields::parse_yes(other) {
+            *value = other.to_string();
+        }
+        return;
+    }
+    if is_scalar(value) && is_scalar(other) {
+        return;
+    }
+    for part in other.split(';').map(str::trim).filter(|p| !p.is_empty()) {
+        if !value.split(';').any(|existing| existing.trim() == part) {
+            value.push_str("; ");
+            value.push_str(part);
+        }
     }
 }
 
 async fn read_locations_from_csv(
+    dataset: &Dataset,
     country: Option<String>,
+    approved: &[Submission],
 ) -> Result<Vec<LocationResponse>, Box<dyn Error>> {
     let mut locations = Vec::new();
+    let mut mentioned = HashMap::new();
 
-    // Process each country directory
-    for locale_dir in DATA_DIR.dirs() {
-        let dir_name = locale_dir
-            .path()
-            .file_name()
-            .unwrap_or_default()
-            .to_string_lossy()
-            .to_string();
-
-        // Skip the country directory if it doesn't match the country code
-        if let Some(country) = &country {
-            if dir_name != *country {
-                continue;
-            }
-        }
-
+    // Process each country directory. Every country is read even when filtering
+    // so that slugs are assigned against the whole dataset and stay stable.
+    for dir_name in dataset.countries() {
         // Read the CSV file for this country
-        let csv_path = format!("{}/locations.csv", dir_name.clone());
-        if let Some(csv_data) = locale_dir.get_file(csv_path) {
-            let mut reader = csv::Reader::from_reader(csv_data.contents());
+        let csv_path = format!("{}/locations.csv", dir_name);
+        if let Some(csv_data) = dataset.get(&csv_path) {
+            schema::check::<Location>(&csv_path, csv_data)?;
+            let mut reader = csv::Reader::from_reader(csv_data);
+            let headers = reader.headers()?.clone();
 
-            for result in reader.deserialize() {
-                let record: Location = result?;
+            for row in merge_sections(&mut reader)? {
+                let record: Location = row.deserialize(Some(&headers))?;
                 let animals_slaughtered = get_slaughtered_animals(&record);
                 let animals_processed = get_processed_animals(&record);
+                let dba_names = search::parse_dba_names(&record.dbas, &record.establishment_name);
+                let taxa = species_of(&record, &mut mentioned);
+                let yes = |classes: &[(&'static str, bool)]| {
+                    let ids = classes.iter().filter(|(_, value)| *value);
+                    species::groups(ids.map(|(id, _)| *id))
+                };
+                let species_slaughtered = yes(&slaughter_classes(&record));
+                let slaughter_classes = get_slaughter_classes(&record);
+                let species_processed = yes(&processing_classes(&record));
+                let estimate = volume::annual_slaughter(&record.slaughter_volume_category);
+                let country = match record.country.trim() {
+                    "" => geocode::country_code(dir_name),
+                    code => geocode::country_code(code),
+                };
+                // Hashed with the directory, as before `gb` was served for
+                // `uk`, so the IDs stay the same.
+                let directory = geocode::country_dir(&country);
                 locations.push(LocationResponse {
-                    country: dir_name.clone(),
+                    facility_id: ids::facility_id(
+                        "locations",
+                        &directory,
+                        &record.establishment_id,
+                    ),
+                    country,
                     establishment_id: record.establishment_id,
                     establishment_name: record.establishment_name,
                     latitude: record.latitude,
                     longitude: record.longitude,
+                    activities: activities::parse_description(&record.activities),
                     r#type: record.activities,
                     state: record.state,
+                    fips_code: choropleth::normalize_fips(&record.fips_code),
+                    county: record.county,
                     city: record.city,
                     street: record.street,
                     zip: record.zip,
                     slaughter: record.slaughter,
                     animals_slaughtered,
+                    species_slaughtered,
+                    slaughter_classes,
+                    species: species::groups(taxa.iter().copied()),
+                    dba_names,
+                    taxa,
                     dbas: record.dbas,
                     phone: record.phone,
                     slaughter_volume_category: record.slaughter_volume_category,
                     processing_volume_category: record.processing_volume_category,
+                    estimated_annual_slaughter_min: estimate.map(|e| e.min),
+                    estimated_annual_slaughter_max: estimate.map(|e| e.max),
                     animals_processed,
+                    species_processed,
                     grant_date: record.grant_date,
+                    slug: String::new(),
+                    priority_score: 0.0,
+                    priority_breakdown: Vec::new(),
+                    status: FacilityStatus::Active,
+                    status_date: String::new(),
+                    flag: None,
                 });
             }
         }
     }
+    locations.extend(approved.iter().map(location_from_submission));
+
+    let bases: Vec<_> = locations
+        .iter()
+        .map(|loc| {
+            (
+                slug::facility_slug(&loc.establishment_name, &loc.city, &loc.state),
+                loc.country.as_str(),
+                loc.establishment_id.as_str(),
+            )
+        })
+        .collect();
+    let slugs = slug::assign_slugs(&bases);
+    for (loc, slug) in locations.iter_mut().zip(slugs) {
+        loc.slug = slug;
+    }
+
+    // Skip the locations that don't match the country code
+    if let Some(country) = &country {
+        locations.retain(|loc| loc.country == *country);
+    }
+
     Ok(locations)
 }
 
-pub async fn read_aphis_reports_from_csv() -> Result<Vec<AphisReport>, Box<dyn Error>> {
-    // Embed the APHIS data at compile time
-    let csv_data = include_str!("../static_data/us/aphis_data_final.csv");
-
-    // Read directly from the string data
-    let mut reader = csv::Reader::from_reader(csv_data.as_bytes());
+pub async fn read_aphis_reports_from_csv(
+    dataset: &Dataset,
+) -> Result<Vec<AphisReport>, Box<dyn Error>> {
+    // Deployments without US data serve no reports.
+    let Some(csv_data) = dataset.get("us/aphis_data_final.csv") else {
+        return Ok(Vec::new());
+    };
+    schema::check::<AphisReport>("us/aphis_data_final.csv", csv_data)?;
+    let mut reader = csv::Reader::from_reader(csv_data);
 
+    let mut pain_categories = read_pain_categories(dataset)?;
     let mut reports = Vec::new();
-    for mut record in reader.deserialize::<AphisReport>().flatten() {
+    for record in reader.deserialize::<AphisReport>() {
+        let mut record = record.map_err(|e| format!("us/aphis_data_final.csv: {}", e))?;
+        record.facility_id = Some(aphis_facility_id(&record));
+        let address = CityStateZip::parse(&record.city_state_zip);
+        (record.city, record.state, record.zip) = (address.city, address.state, address.zip);
         record.animals_tested = Some(get_tested_animals(&record));
+        record.species_tested = Some(species::groups(tested_species(&record)));
+        if let Some(lines) =
+            pain_categories.remove(&(record.certificate_number.clone(), record.year.clone()))
+        {
+            record.set_pain_categories(lines);
+        }
         reports.push(record);
     }
     Ok(reports)
 }
 
-pub async fn read_inspection_reports_from_csv() -> Result<Vec<InspectionReport>, Box<dyn Error>> {
-    let csv_data = include_str!("../static_data/us/inspection_reports.csv");
+/// An APHIS report's facility ID, from its certificate number so every year
+/// of the registration shares it.
+fn aphis_facility_id(report: &AphisReport) -> String {
+    ids::facility_id("aphis_reports", "us", &report.certificate_number)
+}
+
+/// Pain category lines by certificate number and year.
+type PainCategoryLines = HashMap<(String, String), Vec<PainCategories>>;
+
+/// The pain category lines parsed from the annual report PDFs.
+fn read_pain_categories(dataset: &Dataset) -> Result<PainCategoryLines, Box<dyn Error>> {
+    let mut lines = PainCategoryLines::new();
+    let Some(csv_data) = dataset.get("us/aphis_pain_categories.csv") else {
+        return Ok(lines);
+    };
+    schema::check::<PainCategories>("us/aphis_pain_categories.csv", csv_data)?;
+    let mut reader = csv::Reader::from_reader(csv_data);
+    for line in reader.deserialize::<PainCategories>() {
+        let line = line.map_err(|e| format!("us/aphis_pain_categories.csv: {}", e))?;
+        lines
+            .entry((line.certificate_number.clone(), line.year.clone()))
+            .or_default()
+            .push(line);
+    }
+    Ok(lines)
+}
+
+pub async fn read_aphis_history_from_csv(
+    dataset: &Dataset,
+) -> Result<Vec<AphisReport>, Box<dyn Error>> {
+    let mut reports = Vec::new();
+    for (name, csv_data) in dataset.files_in("us/aphis_history") {
+        if !name.ends_with(".csv") {
+            continue;
+        }
+        let file = format!("us/aphis_history/{}", name);
+        schema::check::<AphisReport>(&file, csv_data)?;
+        let mut reader = csv::Reader::from_reader(csv_data);
+        for report in reader.deserialize::<AphisReport>() {
+            reports.push(report.map_err(|e| format!("{}: {}", file, e))?);
+        }
+    }
+    for report in &mut reports {
+        report.facility_id = Some(aphis_facility_id(report));
+    }
+    let mut pain_categories = read_pain_categories(dataset)?;
+    for report in &mut reports {
+        if let Some(lines) =
+            pain_categories.remove(&(report.certificate_number.clone(), report.year.clone()))
+        {
+            report.set_pain_categories(lines);
+        }
+    }
+    Ok(reports)
+}
 
-    let mut reader = csv::Reader::from_reader(csv_data.as_bytes());
+pub async fn read_inspection_reports_from_csv(
+    dataset: &Dataset,
+) -> Result<Vec<InspectionReport>, Box<dyn Error>> {
+    let Some(csv_data) = dataset.get("us/inspection_reports.csv") else {
+        return Ok(Vec::new());
+    };
+    schema::check::<InspectionReport>("us/inspection_reports.csv", csv_data)?;
+    let mut reader = csv::Reader::from_reader(csv_data);
 
     let mut reports = Vec::new();
     for result in reader.deserialize() {
-        let record: InspectionReport = result?;
+        let mut record: InspectionReport = result?;
+        record.facility_id = Some(ids::facility_id(
+            "inspection_reports",
+            "us",
+            &record.certificate_number,
+        ));
+        record.facility_type = FacilityType::parse(&record.license_type);
+        let address = CityStateZip::checked(&record.city, &record.state, &record.zip)
+            .or(CityStateZip::parse(&record.city_state_zip));
+        (record.city, record.state, record.zip) = (address.city, address.state, address.zip);
         reports.push(record);
     }
     Ok(reports)
@@ -147,11 +1275,66 @@ pub async fn read_inspection_reports_from_csv() -> Result<Vec<InspectionReport>,
 
 #[derive(Deserialize)]
 pub struct LocationParams {
+    /// A country directory or ISO 3166-1 alpha-2 code, in either case.
+    #[serde(alias = "country")]
     country_code: Option<String>,
+    state: Option<String>,
+    species: Option<String>,
+    activity: Option<String>,
+    /// Comma-separated statuses: `active`, `closed` or `suspended`.
+    status: Option<String>,
+    /// `YYYY-MM-DD`: only locations granted on or after this day.
+    granted_after: Option<String>,
+    /// `YYYY-MM-DD`: only locations granted on or before this day.
+    granted_before: Option<String>,
+    sort: Option<String>,
+    order: Option<String>,
+    /// `json` (the default) or `geojson`.
+    format: Option<String>,
+    limit: Option<usize>,
+    offset: Option<usize>,
+    /// Comma-separated fields to return, e.g. `slug,latitude,longitude`.
+    fields: Option<String>,
+}
+
+#[derive(Deserialize)]
+pub struct AphisParams {
+    /// `YYYY-MM-DD`: only reports whose status changed on or after this day.
+    status_since: Option<String>,
+    /// Comma-separated cities and USPS state codes, and a ZIP prefix (see
+    /// `AddressFilter`).
+    city: Option<String>,
+    state: Option<String>,
+    zip: Option<String>,
+    sort: Option<String>,
+    order: Option<String>,
+    format: Option<String>,
+    limit: Option<usize>,
+    offset: Option<usize>,
+    /// Comma-separated fields to return, e.g. `slug,latitude,longitude`.
+    fields: Option<String>,
 }
 
-#[derive(Serialize, Debug)]
+#[derive(Deserialize)]
+pub struct InspectionParams {
+    /// Comma-separated license classes (`C`) or facility types
+    /// (`exhibitor`).
+    license_type: Option<String>,
+    /// `YYYY-MM-DD`: only licenses whose status changed on or after this day.
+    status_since: Option<String>,
+    city: Option<String>,
+    state: Option<String>,
+    zip: Option<String>,
+    format: Option<String>,
+    limit: Option<usize>,
+    offset: Option<usize>,
+    fields: Option<String>,
+}
+
+#[derive(Serialize, Debug, Clone)]
 struct LocationResponse {
+    /// The location's facility ID (see `ids`).
+    facility_id: String,
     country: String,
     establishment_id: String,
     establishment_name: String,
@@ -159,16 +1342,52 @@ struct LocationResponse {
     longitude: f64,
     #[serde(rename = "type")]
     r#type: String,
+    /// `type` as activity IDs (`["meat_processing", "meat_slaughter"]`).
+    activities: Vec<Activity>,
     state: String,
+    county: String,
+    /// Five-digit county FIPS code, US only.
+    fips_code: String,
     city: String,
     street: String,
     zip: String,
-    slaughter: String,
+    slaughter: bool,
     animals_slaughtered: String,
     animals_processed: String,
+    /// Group IDs of `animals_slaughtered` (`["cattle", "pig"]`).
+    species_slaughtered: Vec<&'static str>,
+    /// The FSIS classes behind `species_slaughtered`, e.g. bob veal and
+    /// dairy cows rather than cattle.
+    slaughter_classes: Vec<SlaughterClass>,
+    /// Group IDs of `animals_processed`.
+    species_processed: Vec<&'static str>,
+    /// Group IDs of every species handled, including those only named in
+    /// the activities, as most countries record them.
+    species: Vec<&'static str>,
     slaughter_volume_category: String,
     processing_volume_category: String,
+    /// Head slaughtered per year, bounding `slaughter_volume_category` (see
+    /// `volume`).
+    estimated_annual_slaughter_min: Option<u64>,
+    estimated_annual_slaughter_max: Option<u64>,
     dbas: String,
+    /// `dbas` split into individual trade names.
+    dba_names: Vec<String>,
     phone: String,
-    grant_date: String,
+    /// ISO date, or null where the source gives none.
+    grant_date: Option<NaiveDate>,
+    /// Species taxon IDs, used for filtering.
+    #[serde(skip)]
+    taxa: Vec<&'static str>,
+    slug: String,
+    priority_score: f64,
+    priority_breakdown: Vec<ScoreComponent>,
+    status: FacilityStatus,
+    /// When `status` took effect, if known (YYYY-MM-DD).
+    status_date: String,
+    /// Set when a moderator flagged the record as closed or incorrect.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    flag: Option<Flag>,
 }
+
+impl geojson::Feature for LocationResponse {}
@@ -15,10 +15,15 @@
 // along with this program. If not, see <https://www.gnu.org/licenses/>.
 
 // Contact the developer directly at untileverycageproject@protonmail.com
+use chrono::NaiveDate;
 use serde::Deserialize;
 use serde::Serialize;
 
-#[derive(Serialize, Deserialize, Debug, Default)]
+use crate::fields;
+use crate::scoring::ScoreComponent;
+use crate::species;
+
+#[derive(Serialize, Deserialize, Debug, Default, Clone)]
 pub struct Location {
     pub establishment_id: String,
     pub establishment_number: String,
@@ -29,7 +34,8 @@ pub struct Location {
     pub state: String,
     pub zip: String,
     pub phone: String,
-    pub grant_date: String,
+    #[serde(deserialize_with = "fields::date")]
+    pub grant_date: Option<NaiveDate>,
     #[serde(rename = "type")]
     pub activities: String,
     pub dbas: String,
@@ -39,60 +45,117 @@ pub struct Location {
     pub latitude: f64,
     pub longitude: f64,
     pub county: String,
+    /// ISO 3166-1 alpha-2 code. Left blank, the location is in the country of
+    /// the directory its file is in, so one file can merge several countries.
+    #[serde(default)]
+    pub country: String,
     pub fips_code: String,
-    pub meat_exemption_custom_slaughter: String,
-    pub poultry_exemption_custom_slaughter: String,
-    pub slaughter: String,
-    pub meat_slaughter: String,
-    pub beef_cow_slaughter: String,
-    pub steer_slaughter: String,
-    pub heifer_slaughter: String,
-    pub bull_stag_slaughter: String,
-    pub dairy_cow_slaughter: String,
-    pub heavy_calf_slaughter: String,
-    pub bob_veal_slaughter: String,
-    pub formula_fed_veal_slaughter: String,
-    pub non_formula_fed_veal_slaughter: String,
-    pub market_swine_slaughter: String,
-    pub sow_slaughter: String,
-    pub roaster_swine_slaughter: String,
-    pub boar_stag_swine_slaughter: String,
-    pub stag_swine_slaughter: String,
-    pub feral_swine_slaughter: String,
-    pub goat_slaughter: String,
-    pub young_goat_slaughter: String,
-    pub adult_goat_slaughter: String,
-    pub sheep_slaughter: String,
-    pub lamb_slaughter: String,
-    pub deer_reindeer_slaughter: String,
-    pub antelope_slaughter: String,
-    pub elk_slaughter: String,
-    pub bison_slaughter: String,
-    pub buffalo_slaughter: String,
-    pub water_buffalo_slaughter: String,
-    pub cattalo_slaughter: String,
-    pub yak_slaughter: String,
-    pub other_voluntary_livestock_slaughter: String,
-    pub rabbit_slaughter: String,
-    pub poultry_slaughter: String,
-    pub young_chicken_slaughter: String,
-    pub light_fowl_slaughter: String,
-    pub heavy_fowl_slaughter: String,
-    pub capon_slaughter: String,
-    pub young_turkey_slaughter: String,
-    pub young_breeder_turkey_slaughter: String,
-    pub old_breeder_turkey_slaughter: String,
-    pub fryer_roaster_turkey_slaughter: String,
-    pub duck_slaughter: String,
-    pub goose_slaughter: String,
-    pub pheasant_slaughter: String,
-    pub quail_slaughter: String,
-    pub guinea_slaughter: String,
-    pub ostrich_slaughter: String,
-    pub emu_slaughter: String,
-    pub rhea_slaughter: String,
-    pub squab_slaughter: String,
-    pub other_voluntary_poultry_slaughter: String,
+    #[serde(with = "fields::yes")]
+    pub meat_exemption_custom_slaughter: bool,
+    #[serde(with = "fields::yes")]
+    pub poultry_exemption_custom_slaughter: bool,
+    #[serde(with = "fields::yes")]
+    pub slaughter: bool,
+    #[serde(with = "fields::yes")]
+    pub meat_slaughter: bool,
+    #[serde(with = "fields::yes")]
+    pub beef_cow_slaughter: bool,
+    #[serde(with = "fields::yes")]
+    pub steer_slaughter: bool,
+    #[serde(with = "fields::yes")]
+    pub heifer_slaughter: bool,
+    #[serde(with = "fields::yes")]
+    pub bull_stag_slaughter: bool,
+    #[serde(with = "fields::yes")]
+    pub dairy_cow_slaughter: bool,
+    #[serde(with = "fields::yes")]
+    pub heavy_calf_slaughter: bool,
+    #[serde(with = "fields::yes")]
+    pub bob_veal_slaughter: bool,
+    #[serde(with = "fields::yes")]
+    pub formula_fed_veal_slaughter: bool,
+    #[serde(with = "fields::yes")]
+    pub non_formula_fed_veal_slaughter: bool,
+    #[serde(with = "fields::yes")]
+    pub market_swine_slaughter: bool,
+    #[serde(with = "fields::yes")]
+    pub sow_slaughter: bool,
+    #[serde(with = "fields::yes")]
+    pub roaster_swine_slaughter: bool,
+    #[serde(with = "fields::yes")]
+    pub boar_stag_swine_slaughter: bool,
+    #[serde(with = "fields::yes")]
+    pub stag_swine_slaughter: bool,
+    #[serde(with = "fields::yes")]
+    pub feral_swine_slaughter: bool,
+    #[serde(with = "fields::yes")]
+    pub goat_slaughter: bool,
+    #[serde(with = "fields::yes")]
+    pub young_goat_slaughter: bool,
+    #[serde(with = "fields::yes")]
+    pub adult_goat_slaughter: bool,
+    #[serde(with = "fields::yes")]
+    pub sheep_slaughter: bool,
+    #[serde(with = "fields::yes")]
+    pub lamb_slaughter: bool,
+    #[serde(with = "fields::yes")]
+    pub deer_reindeer_slaughter: bool,
+    #[serde(with = "fields::yes")]
+    pub antelope_slaughter: bool,
+    #[serde(with = "fields::yes")]
+    pub elk_slaughter: bool,
+    #[serde(with = "fields::yes")]
+    pub bison_slaughter: bool,
+    #[serde(with = "fields::yes")]
+    pub buffalo_slaughter: bool,
+    #[serde(with = "fields::yes")]
+    pub water_buffalo_slaughter: bool,
+    #[serde(with = "fields::yes")]
+    pub cattalo_slaughter: bool,
+    #[serde(with = "fields::yes")]
+    pub yak_slaughter: bool,
+    #[serde(with = "fields::yes")]
+    pub other_voluntary_livestock_slaughter: bool,
+    #[serde(with = "fields::yes")]
+    pub rabbit_slaughter: bool,
+    #[serde(with = "fields::yes")]
+    pub poultry_slaughter: bool,
+    #[serde(with = "fields::yes")]
+    pub young_chicken_slaughter: bool,
+    #[serde(with = "fields::yes")]
+    pub light_fowl_slaughter: bool,
+    #[serde(with = "fields::yes")]
+    pub heavy_fowl_slaughter: bool,
+    #[serde(with = "fields::yes")]
+    pub capon_slaughter: bool,
+    #[serde(with = "fields::yes")]
+    pub young_turkey_slaughter: bool,
+    #[serde(with = "fields::yes")]
+    pub young_breeder_turkey_slaughter: bool,
+    #[serde(with = "fields::yes")]
+    pub old_breeder_turkey_slaughter: bool,
+    #[serde(with = "fields::yes")]
+    pub fryer_roaster_turkey_slaughter: bool,
+    #[serde(with = "fields::yes")]
+    pub duck_slaughter: bool,
+    #[serde(with = "fields::yes")]
+    pub goose_slaughter: bool,
+    #[serde(with = "fields::yes")]
+    pub pheasant_slaughter: bool,
+    #[serde(with = "fields::yes")]
+    pub quail_slaughter: bool,
+    #[serde(with = "fields::yes")]
+    pub guinea_slaughter: bool,
+    #[serde(with = "fields::yes")]
+    pub ostrich_slaughter: bool,
+    #[serde(with = "fields::yes")]
+    pub emu_slaughter: bool,
+    #[serde(with = "fields::yes")]
+    pub rhea_slaughter: bool,
+    #[serde(with = "fields::yes")]
+    pub squab_slaughter: bool,
+    #[serde(with = "fields::yes")]
+    pub other_voluntary_poultry_slaughter: bool,
     pub slaughter_or_processing_only: String,
     pub slaughter_only_class: String,
     pub slaughter_only_species: String,
@@ -102,70 +165,153 @@ pub struct Location {
     pub processing_volume_category: String,
 
     // --- PROCESSING FIELDS ---
-    pub beef_processing: String,
-    pub pork_processing: String,
-    pub antelope_processing: String,
-    pub bison_processing: String,
-    pub buffalo_processing: String,
-    pub deer_processing: String,
-    pub elk_processing: String,
-    pub goat_processing: String,
-    pub other_voluntary_livestock_processing: String,
-    pub rabbit_processing: String,
-    pub reindeer_processing: String,
-    pub sheep_processing: String,
-    pub yak_processing: String,
-    pub chicken_processing: String,
-    pub duck_processing: String,
-    pub goose_processing: String,
-    pub pigeon_processing: String,
-    pub ratite_processing: String,
-    pub turkey_processing: String,
-    pub exotic_poultry_processing: String,
-    pub other_voluntary_poultry_processing: String,
+    #[serde(with = "fields::yes")]
+    pub beef_processing: bool,
+    #[serde(with = "fields::yes")]
+    pub pork_processing: bool,
+    #[serde(with = "fields::yes")]
+    pub antelope_processing: bool,
+    #[serde(with = "fields::yes")]
+    pub bison_processing: bool,
+    #[serde(with = "fields::yes")]
+    pub buffalo_processing: bool,
+    #[serde(with = "fields::yes")]
+    pub deer_processing: bool,
+    #[serde(with = "fields::yes")]
+    pub elk_processing: bool,
+    #[serde(with = "fields::yes")]
+    pub goat_processing: bool,
+    #[serde(with = "fields::yes")]
+    pub other_voluntary_livestock_processing: bool,
+    #[serde(with = "fields::yes")]
+    pub rabbit_processing: bool,
+    #[serde(with = "fields::yes")]
+    pub reindeer_processing: bool,
+    #[serde(with = "fields::yes")]
+    pub sheep_processing: bool,
+    #[serde(with = "fields::yes")]
+    pub yak_processing: bool,
+    #[serde(with = "fields::yes")]
+    pub chicken_processing: bool,
+    #[serde(with = "fields::yes")]
+    pub duck_processing: bool,
+    #[serde(with = "fields::yes")]
+    pub goose_processing: bool,
+    #[serde(with = "fields::yes")]
+    pub pigeon_processing: bool,
+    #[serde(with = "fields::yes")]
+    pub ratite_processing: bool,
+    #[serde(with = "fields::yes")]
+    pub turkey_processing: bool,
+    #[serde(with = "fields::yes")]
+    pub exotic_poultry_processing: bool,
+    #[serde(with = "fields::yes")]
+    pub other_voluntary_poultry_processing: bool,
 }
 
-// --- NEW HELPER FUNCTION FOR PROCESSED ANIMALS ---
-pub fn get_processed_animals(location: &Location) -> String {
-    let mut processed_animals: Vec<&str> = Vec::new();
-
-    // Helper closure to check the Option<String> fields safely
-    let mut add_if_processed = |field: &str, name: &'static str| {
-        if field == "Yes" {
-            processed_animals.push(name);
-        }
-    };
+/// Each FSIS slaughter class column with the species taxon it covers.
+pub fn slaughter_classes(location: &Location) -> [(&'static str, bool); 48] {
+    [
+        ("beef_cow", location.beef_cow_slaughter),
+        ("steer", location.steer_slaughter),
+        ("heifer", location.heifer_slaughter),
+        ("bull_stag", location.bull_stag_slaughter),
+        ("dairy_cow", location.dairy_cow_slaughter),
+        ("heavy_calf", location.heavy_calf_slaughter),
+        ("bob_veal", location.bob_veal_slaughter),
+        ("formula_fed_veal", location.formula_fed_veal_slaughter),
+        (
+            "non_formula_fed_veal",
+            location.non_formula_fed_veal_slaughter,
+        ),
+        ("market_swine", location.market_swine_slaughter),
+        ("sow", location.sow_slaughter),
+        ("roaster_swine", location.roaster_swine_slaughter),
+        ("boar_stag_swine", location.boar_stag_swine_slaughter),
+        ("stag_swine", location.stag_swine_slaughter),
+        ("feral_swine", location.feral_swine_slaughter),
+        ("goat", location.goat_slaughter),
+        ("young_goat", location.young_goat_slaughter),
+        ("adult_goat", location.adult_goat_slaughter),
+        ("sheep", location.sheep_slaughter),
+        ("lamb", location.lamb_slaughter),
+        ("deer_reindeer", location.deer_reindeer_slaughter),
+        ("antelope", location.antelope_slaughter),
+        ("elk", location.elk_slaughter),
+        ("bison", location.bison_slaughter),
+        ("buffalo", location.buffalo_slaughter),
+        ("water_buffalo", location.water_buffalo_slaughter),
+        ("cattalo", location.cattalo_slaughter),
+        ("yak", location.yak_slaughter),
+        (
+            "other_livestock",
+            location.other_voluntary_livestock_slaughter,
+        ),
+        ("rabbit", location.rabbit_slaughter),
+        ("young_chicken", location.young_chicken_slaughter),
+        ("light_fowl", location.light_fowl_slaughter),
+        ("heavy_fowl", location.heavy_fowl_slaughter),
+        ("capon", location.capon_slaughter),
+        ("young_turkey", location.young_turkey_slaughter),
+        (
+            "young_breeder_turkey",
+            location.young_breeder_turkey_slaughter,
+        ),
+        ("old_breeder_turkey", location.old_breeder_turkey_slaughter),
+        (
+            "fryer_roaster_turkey",
+            location.fryer_roaster_turkey_slaughter,
+        ),
+        ("duck", location.duck_slaughter),
+        ("goose", location.goose_slaughter),
+        ("pheasant", location.pheasant_slaughter),
+        ("quail", location.quail_slaughter),
+        ("guinea_fowl", location.guinea_slaughter),
+        ("ostrich", location.ostrich_slaughter),
+        ("emu", location.emu_slaughter),
+        ("rhea", location.rhea_slaughter),
+        ("pigeon", location.squab_slaughter),
+        ("other_poultry", location.other_voluntary_poultry_slaughter),
+    ]
+}
 
-    // --- Livestock Processing ---
-    add_if_processed(&location.beef_processing, "Beef");
-    add_if_processed(&location.pork_processing, "Pork");
-    add_if_processed(&location.antelope_processing, "Antelope");
-    add_if_processed(&location.bison_processing, "Bison");
-    add_if_processed(&location.buffalo_processing, "Buffalo");
-    add_if_processed(&location.deer_processing, "Deer");
-    add_if_processed(&location.elk_processing, "Elk");
-    add_if_processed(&location.goat_processing, "Goat");
-    add_if_processed(
-        &location.other_voluntary_livestock_processing,
-        "Other Voluntary Livestock",
-    );
-    add_if_processed(&location.rabbit_processing, "Rabbit");
-    add_if_processed(&location.reindeer_processing, "Reindeer");
-    add_if_processed(&location.sheep_processing, "Sheep");
-    add_if_processed(&location.yak_processing, "Yak");
+/// Each FSIS processing column with the species taxon it covers.
+pub fn processing_classes(location: &Location) -> [(&'static str, bool); 21] {
+    [
+        ("cattle", location.beef_processing),
+        ("pig", location.pork_processing),
+        ("antelope", location.antelope_processing),
+        ("bison", location.bison_processing),
+        ("buffalo", location.buffalo_processing),
+        ("deer", location.deer_processing),
+        ("elk", location.elk_processing),
+        ("goat", location.goat_processing),
+        (
+            "other_livestock",
+            location.other_voluntary_livestock_processing,
+        ),
+        ("rabbit", location.rabbit_processing),
+        ("reindeer", location.reindeer_processing),
+        ("sheep", location.sheep_processing),
+        ("yak", location.yak_processing),
+        ("chicken", location.chicken_processing),
+        ("duck", location.duck_processing),
+        ("goose", location.goose_processing),
+        ("pigeon", location.pigeon_processing),
+        ("ratite", location.ratite_processing),
+        ("turkey", location.turkey_processing),
+        ("exotic_poultry", location.exotic_poultry_processing),
+        ("other_poultry", location.other_voluntary_poultry_processing),
+    ]
+}
 
-    // --- Poultry Processing ---
-    add_if_processed(&location.chicken_processing, "Chicken");
-    add_if_processed(&location.duck_processing, "Duck");
-    add_if_processed(&location.goose_processing, "Goose");
-    add_if_processed(&location.pigeon_processing, "Pigeon");
-    add_if_processed(&location.ratite_processing, "Ratite (Ostrich/Emu)");
-    add_if_processed(&location.turkey_processing, "Turkey");
-    add_if_processed(&location.exotic_poultry_processing, "Exotic Poultry");
-    add_if_processed(
-        &location.other_voluntary_poultry_processing,
-        "Other Voluntary Poultry",
-    );
+pub fn get_processed_animals(location: &Location) -> String {
+    let processed_animals: Vec<&str> = processing_classes(location)
+        .into_iter()
+        .filter(|(_, handled)| *handled)
+        .filter_map(|(id, _)| species::taxon(id))
+        .map(|taxon| taxon.product_name())
+        .collect();
 
     if processed_animals.is_empty() {
         "N/A".to_string()
@@ -174,118 +320,58 @@ pub fn get_processed_animals(location: &Location) -> String {
     }
 }
 
-// --- UPDATED to use more common names ---
-pub fn get_slaughtered_animals(location: &Location) -> String {
-    let mut killed_animals: Vec<&str> = Vec::new();
+/// An FSIS slaughter class a location is marked for.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct SlaughterClass {
+    /// The class's taxon ID (`bob_veal`).
+    pub class: &'static str,
+    /// Its display name ("Bob Veal").
+    pub name: &'static str,
+    /// The species group it is summarized to (`cattle`), as in
+    /// `species_slaughtered`.
+    pub category: &'static str,
+}
 
-    if location.beef_cow_slaughter == "Yes"
-        || location.steer_slaughter == "Yes"
-        || location.heifer_slaughter == "Yes"
-        || location.bull_stag_slaughter == "Yes"
-        || location.dairy_cow_slaughter == "Yes"
-    {
-        killed_animals.push("Cattle (Cows, Bulls)");
-    }
-    if location.heavy_calf_slaughter == "Yes"
-        || location.bob_veal_slaughter == "Yes"
-        || location.formula_fed_veal_slaughter == "Yes"
-        || location.non_formula_fed_veal_slaughter == "Yes"
-    {
-        killed_animals.push("Calves (Veal)");
-    }
-    if location.market_swine_slaughter == "Yes"
-        || location.sow_slaughter == "Yes"
-        || location.roaster_swine_slaughter == "Yes"
-        || location.boar_stag_swine_slaughter == "Yes"
-        || location.stag_swine_slaughter == "Yes"
-        || location.feral_swine_slaughter == "Yes"
-    {
-        killed_animals.push("Pigs");
-    }
-    if location.goat_slaughter == "Yes"
-        || location.young_goat_slaughter == "Yes"
-        || location.adult_goat_slaughter == "Yes"
-    {
-        killed_animals.push("Goats");
-    }
-    if location.sheep_slaughter == "Yes" || location.lamb_slaughter == "Yes" {
-        killed_animals.push("Sheep & Lambs");
-    }
-    if location.deer_reindeer_slaughter == "Yes" {
-        killed_animals.push("Deer & Reindeer");
-    }
-    if location.antelope_slaughter == "Yes" {
-        killed_animals.push("Antelope");
-    }
-    if location.elk_slaughter == "Yes" {
-        killed_animals.push("Elk");
-    }
-    if location.bison_slaughter == "Yes"
-        || location.buffalo_slaughter == "Yes"
-        || location.water_buffalo_slaughter == "Yes"
-        || location.cattalo_slaughter == "Yes"
-    {
-        killed_animals.push("Bison & Buffalo");
-    }
-    if location.yak_slaughter == "Yes" {
-        killed_animals.push("Yak");
-    }
-    if location.other_voluntary_livestock_slaughter == "Yes" {
-        killed_animals.push("Other Livestock");
-    }
-    if location.rabbit_slaughter == "Yes" {
-        killed_animals.push("Rabbits");
-    }
+/// The slaughter classes marked, in column order, unsummarized.
+pub fn get_slaughter_classes(location: &Location) -> Vec<SlaughterClass> {
+    slaughter_classes(location)
+        .into_iter()
+        .filter(|(_, slaughtered)| *slaughtered)
+        .filter_map(|(id, _)| {
+            let taxon = species::taxon(id)?;
+            Some(SlaughterClass {
+                class: taxon.id,
+                name: taxon.name,
+                category: species::group_of(id).map_or(taxon.id, |group| group.id),
+            })
+        })
+        .collect()
+}
 
-    // --- Poultry ---
-    if location.young_chicken_slaughter == "Yes"
-        || location.light_fowl_slaughter == "Yes"
-        || location.heavy_fowl_slaughter == "Yes"
-        || location.capon_slaughter == "Yes"
-    {
-        killed_animals.push("Chickens");
-    }
-    if location.young_turkey_slaughter == "Yes"
-        || location.young_breeder_turkey_slaughter == "Yes"
-        || location.old_breeder_turkey_slaughter == "Yes"
-        || location.fryer_roaster_turkey_slaughter == "Yes"
-    {
-        killed_animals.push("Turkeys");
-    }
-    if location.duck_slaughter == "Yes" {
-        killed_animals.push("Ducks");
-    }
-    if location.goose_slaughter == "Yes" {
-        killed_animals.push("Geese");
-    }
-    if location.pheasant_slaughter == "Yes" {
-        killed_animals.push("Pheasants");
-    }
-    if location.quail_slaughter == "Yes" {
-        killed_animals.push("Quail");
-    }
-    if location.guinea_slaughter == "Yes" {
-        killed_animals.push("Guinea Fowl");
-    }
-    if location.ostrich_slaughter == "Yes"
-        || location.emu_slaughter == "Yes"
-        || location.rhea_slaughter == "Yes"
-    {
-        killed_animals.push("Ratites (Ostrich, Emu, etc.)");
-    }
-    if location.squab_slaughter == "Yes" {
-        killed_animals.push("Pigeons (Squab)");
-    }
-    if location.other_voluntary_poultry_slaughter == "Yes" {
-        killed_animals.push("Other Poultry");
-    }
+/// Slaughtered species summarized to their groups, e.g. any of the five
+/// cattle classes gives "Cattle (Cows, Bulls)".
+pub fn get_slaughtered_animals(location: &Location) -> String {
+    let groups: Vec<&str> = slaughter_classes(location)
+        .into_iter()
+        .filter(|(_, slaughtered)| *slaughtered)
+        .filter_map(|(id, _)| species::group_of(id))
+        .map(|group| group.id)
+        .collect();
 
-    // Join the collected names with a comma and space
-    killed_animals.join(", ")
+    // The ontology lists groups in display order.
+    species::TAXA
+        .iter()
+        .filter(|taxon| groups.contains(&taxon.id))
+        .map(|taxon| taxon.name)
+        .collect::<Vec<_>>()
+        .join(", ")
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct AphisReport {
+    /// The report's facility ID (see `ids`), the same for every year's report.
+    #[serde(default, skip_deserializing, skip_serializing_if = "Option::is_none")]
+    pub facility_id: Option<String>,
     #[serde(rename = "Account Name")]
     pub account_name: String,
     #[serde(rename = "Customer Number_x")]
@@ -296,44 +382,151 @@ pub struct AphisReport {
     pub registration_type: String,
     #[serde(rename = "Certificate Status")]
     pub certificate_status: String,
-    #[serde(rename = "Status Date")]
-    pub status_date: String,
+    #[serde(rename = "Status Date", deserialize_with = "fields::date")]
+    pub status_date: Option<NaiveDate>,
     #[serde(rename = "Address Line 1")]
     pub address_line_1: String,
     #[serde(rename = "Address Line 2")]
     pub address_line_2: String,
     #[serde(rename = "City-State-Zip")]
     pub city_state_zip: String,
+    /// `City-State-Zip` split and checked (see `address`).
+    #[serde(rename = "City", default, skip_deserializing)]
+    pub city: String,
+    #[serde(rename = "State", default, skip_deserializing)]
+    pub state: String,
+    #[serde(rename = "Zip", default, skip_deserializing)]
+    pub zip: String,
     #[serde(rename = "County")]
     pub county: String,
     #[serde(rename = "Customer Number_y")]
     pub customer_number_y: String,
     #[serde(rename = "Year")]
     pub year: String,
-    #[serde(rename = "Dogs")]
-    pub dogs: String,
-    #[serde(rename = "Cats")]
-    pub cats: String,
-    #[serde(rename = "Guinea Pigs")]
-    pub guinea_pigs: String,
-    #[serde(rename = "Hamsters")]
-    pub hamsters: String,
-    #[serde(rename = "Rabbits")]
-    pub rabbits: String,
-    #[serde(rename = "Non-Human Primates")]
-    pub non_human_primates: String,
-    #[serde(rename = "Sheep")]
-    pub sheep: String,
-    #[serde(rename = "Pigs")]
-    pub pigs: String,
-    #[serde(rename = "Other Farm Animals")]
-    pub other_farm_animals: String,
-    #[serde(rename = "All Other Animals")]
-    pub all_other_animals: String,
+    #[serde(rename = "Dogs", deserialize_with = "fields::count")]
+    pub dogs: Option<u32>,
+    #[serde(rename = "Cats", deserialize_with = "fields::count")]
+    pub cats: Option<u32>,
+    #[serde(rename = "Guinea Pigs", deserialize_with = "fields::count")]
+    pub guinea_pigs: Option<u32>,
+    #[serde(rename = "Hamsters", deserialize_with = "fields::count")]
+    pub hamsters: Option<u32>,
+    #[serde(rename = "Rabbits", deserialize_with = "fields::count")]
+    pub rabbits: Option<u32>,
+    #[serde(rename = "Non-Human Primates", deserialize_with = "fields::count")]
+    pub non_human_primates: Option<u32>,
+    #[serde(rename = "Sheep", deserialize_with = "fields::count")]
+    pub sheep: Option<u32>,
+    #[serde(rename = "Pigs", deserialize_with = "fields::count")]
+    pub pigs: Option<u32>,
+    #[serde(rename = "Other Farm Animals", deserialize_with = "fields::count")]
+    pub other_farm_animals: Option<u32>,
+    #[serde(rename = "All Other Animals", deserialize_with = "fields::count")]
+    pub all_other_animals: Option<u32>,
     pub latitude: f64,
     pub longitude: f64,
-    #[serde(rename = "Animals Tested On")]
+    /// Worked out from the counts as the reports are read, not a column.
+    #[serde(rename = "Animals Tested On", default, skip_deserializing)]
     pub animals_tested: Option<String>,
+    /// Species group IDs tested on, the structured form of `animals_tested`.
+    #[serde(default, skip_deserializing, skip_serializing_if = "Option::is_none")]
+    pub species_tested: Option<Vec<&'static str>>,
+    /// Whether licensee inspection reports share the certificate or customer
+    /// number (see /api/aphis-reports/{certificate_number}/inspections).
+    #[serde(default, skip_deserializing)]
+    pub has_inspections: bool,
+    #[serde(default, skip_deserializing, skip_serializing_if = "Option::is_none")]
+    pub priority_score: Option<f64>,
+    #[serde(default, skip_deserializing, skip_serializing_if = "Option::is_none")]
+    pub priority_breakdown: Option<Vec<ScoreComponent>>,
+    /// Animals held but not used, summed over the report's pain category
+    /// lines. The category counts are absent where the annual report PDF
+    /// hasn't been parsed.
+    #[serde(default, skip_deserializing, skip_serializing_if = "Option::is_none")]
+    pub column_b: Option<u64>,
+    /// Animals used without pain.
+    #[serde(default, skip_deserializing, skip_serializing_if = "Option::is_none")]
+    pub column_c: Option<u64>,
+    /// Animals used with pain, relieved by drugs.
+    #[serde(default, skip_deserializing, skip_serializing_if = "Option::is_none")]
+    pub column_d: Option<u64>,
+    /// Animals used with pain left unrelieved.
+    #[serde(default, skip_deserializing, skip_serializing_if = "Option::is_none")]
+    pub column_e: Option<u64>,
+    /// The pain category counts species by species.
+    #[serde(default, skip_deserializing, skip_serializing_if = "Option::is_none")]
+    pub pain_categories: Option<Vec<PainCategories>>,
+}
+
+/// A species' line of an APHIS annual report (form 7023), by the form's
+/// pain category columns. Column F, the total used, is C + D + E.
+#[derive(Serialize, Deserialize, Debug, Default, Clone)]
+#[serde(default)]
+pub struct PainCategories {
+    pub certificate_number: String,
+    pub year: String,
+    /// The `animal_count_columns` key of the line: `dog`, `guinea_pig`,
+    /// `other_animals`...
+    pub species: String,
+    pub column_b: Option<u64>,
+    pub column_c: Option<u64>,
+    pub column_d: Option<u64>,
+    pub column_e: Option<u64>,
+    /// The PDF the line was read from.
+    pub source_file: String,
+}
+
+impl AphisReport {
+    /// Fills the pain category fields from the report's lines.
+    pub(crate) fn set_pain_categories(&mut self, lines: Vec<PainCategories>) {
+        let sum = |column: fn(&PainCategories) -> Option<u64>| {
+            lines
+                .iter()
+                .filter_map(column)
+                .reduce(|total, count| total + count)
+        };
+        self.column_b = sum(|line| line.column_b);
+        self.column_c = sum(|line| line.column_c);
+        self.column_d = sum(|line| line.column_d);
+        self.column_e = sum(|line| line.column_e);
+        self.pain_categories = Some(lines);
+    }
+}
+
+/// Each APHIS count column with the species it counts. "All Other Animals"
+/// is `other_animals`, which isn't a taxon.
+pub fn animal_count_columns(report: &AphisReport) -> [(&'static str, Option<u32>); 10] {
+    [
+        ("dog", report.dogs),
+        ("cat", report.cats),
+        ("guinea_pig", report.guinea_pigs),
+        ("hamster", report.hamsters),
+        ("rabbit", report.rabbits),
+        ("non_human_primate", report.non_human_primates),
+        ("sheep", report.sheep),
+        ("pig", report.pigs),
+        ("other_livestock", report.other_farm_animals),
+        ("other_animals", report.all_other_animals),
+    ]
+}
+
+/// The positive counts of the report, by `animal_count_columns` key.
+pub fn animal_counts(report: &AphisReport) -> Vec<(&'static str, u32)> {
+    animal_count_columns(report)
+        .into_iter()
+        .filter_map(|(id, count)| Some((id, count?)))
+        .filter(|(_, count)| *count > 0)
+        .collect()
+}
+
+/// Species with a positive count in the report, leaving out "All Other
+/// Animals".
+pub fn tested_species(report: &AphisReport) -> Vec<&'static str> {
+    animal_counts(report)
+        .into_iter()
+        .map(|(id, _)| id)
+        .filter(|id| species::taxon(id).is_some())
+        .collect()
 }
 
 // This function takes a reference to an AphisReport and returns the formatted string.
@@ -342,27 +535,26 @@ pub fn get_tested_animals(report: &AphisReport) -> String {
 
     // Helper closure to reduce code repetition.
     // It takes the count string and the animal name, and if valid, adds the formatted string to the list.
-    let mut add_if_tested = |count_str: &str, name: &str| {
-        // Attempt to parse the string into an integer.
-        // If it succeeds and the number is > 0, format it and push to the vector.
-        if let Ok(num) = count_str.parse::<f32>() {
-            if num > 0.0 {
-                tested_animals.push(format!("{} {}", num as i32, name));
-            }
+    let mut add_if_tested = |count: Option<u32>, name: &str| {
+        // If there is a count and it is > 0, format it and push to the vector.
+        if let Some(num) = count
+            && num > 0
+        {
+            tested_animals.push(format!("{} {}", num, name));
         }
     };
 
     // Call the helper for each animal type
-    add_if_tested(&report.dogs, "Dogs");
-    add_if_tested(&report.cats, "Cats");
-    add_if_tested(&report.guinea_pigs, "Guinea Pigs");
-    add_if_tested(&report.hamsters, "Hamsters");
-    add_if_tested(&report.rabbits, "Rabbits");
-    add_if_tested(&report.non_human_primates, "Non-Human Primates");
-    add_if_tested(&report.sheep, "Sheep");
-    add_if_tested(&report.pigs, "Pigs");
-    add_if_tested(&report.other_farm_animals, "Other Farm Animals");
-    add_if_tested(&report.all_other_animals, "All Other Animals");
+    add_if_tested(report.dogs, "Dogs");
+    add_if_tested(report.cats, "Cats");
+    add_if_tested(report.guinea_pigs, "Guinea Pigs");
+    add_if_tested(report.hamsters, "Hamsters");
+    add_if_tested(report.rabbits, "Rabbits");
+    add_if_tested(report.non_human_primates, "Non-Human Primates");
+    add_if_tested(report.sheep, "Sheep");
+    add_if_tested(report.pigs, "Pigs");
+    add_if_tested(report.other_farm_animals, "Other Farm Animals");
+    add_if_tested(report.all_other_animals, "All Other Animals");
 
     // If no animals were found, return "N/A". Otherwise, join the list.
     if tested_animals.is_empty() {
@@ -373,8 +565,11 @@ pub fn get_tested_animals(report: &AphisReport) -> String {
 }
 
 // --- NEW STRUCT for Inspection Reports ---
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct InspectionReport {
+    /// The report's facility ID (see `ids`).
+    #[serde(default, skip_deserializing, skip_serializing_if = "Option::is_none")]
+    pub facility_id: Option<String>,
     #[serde(rename = "Account Name")]
     pub account_name: String,
     #[serde(rename = "Customer Number")]
@@ -385,8 +580,8 @@ pub struct InspectionReport {
     pub license_type: String,
     #[serde(rename = "Certificate Status")]
     pub certificate_status: String,
-    #[serde(rename = "Status Date")]
-    pub status_date: String,
+    #[serde(rename = "Status Date", deserialize_with = "fields::date")]
+    pub status_date: Option<NaiveDate>,
     #[serde(rename = "Address Line 1")]
     pub address_line_1: String,
     #[serde(rename = "Address Line 2")]
@@ -395,6 +590,8 @@ pub struct InspectionReport {
     pub city_state_zip: String,
     #[serde(rename = "County")]
     pub county: String,
+    /// The geocoder's city, state and ZIP, checked and completed from
+    /// `City-State-Zip` where it found none (see `address`).
     #[serde(rename = "City")]
     pub city: String,
     #[serde(rename = "State")]
@@ -405,4 +602,76 @@ pub struct InspectionReport {
     pub latitude: f64,
     #[serde(rename = "Geocodio Longitude")]
     pub longitude: f64,
+    /// What the license is for, derived from `license_type`.
+    #[serde(default, skip_deserializing, skip_serializing_if = "Option::is_none")]
+    pub facility_type: Option<FacilityType>,
+    /// Whether the licensee is a private residence, whose street address is
+    /// withheld and point moved to its ZIP code (see `redaction`).
+    #[serde(default, skip_deserializing)]
+    pub is_residential: bool,
+}
+
+/// The kinds of Animal Welfare Act license and registration: Class A
+/// breeders, Class B dealers, Class C exhibitors (zoos, aquariums, circuses)
+/// and Class R research facilities.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum FacilityType {
+    Breeder,
+    Dealer,
+    Exhibitor,
+    Research,
+}
+
+const FACILITY_TYPES: &[(FacilityType, &str)] = &[
+    (FacilityType::Breeder, "A"),
+    (FacilityType::Dealer, "B"),
+    (FacilityType::Exhibitor, "C"),
+    (FacilityType::Research, "R"),
+];
+
+impl FacilityType {
+    /// The license class letter.
+    pub fn class(self) -> &'static str {
+        FACILITY_TYPES
+            .iter()
+            .find(|(facility_type, _)| *facility_type == self)
+            .map(|(_, class)| *class)
+            .unwrap_or_default()
+    }
+
+    /// A license type as in the dataset ("Class C - Exhibitor"), or as a
+    /// client writes it: a class letter ("C", "class c") or the type name
+    /// ("exhibitor", "registrant" for research).
+    pub fn parse(value: &str) -> Option<FacilityType> {
+        let words: Vec<String> = value
+            .split(|c: char| !c.is_ascii_alphanumeric())
+            .filter(|word| !word.is_empty())
+            .map(str::to_lowercase)
+            .collect();
+        let named = words.iter().find_map(|word| match word.as_str() {
+            "breeder" | "breeders" => Some(FacilityType::Breeder),
+            "dealer" | "dealers" => Some(FacilityType::Dealer),
+            "exhibitor" | "exhibitors" => Some(FacilityType::Exhibitor),
+            "research" | "registrant" | "registrants" => Some(FacilityType::Research),
+            _ => None,
+        });
+        let class = || {
+            let rest = match words.as_slice() {
+                [first, rest @ ..] if first == "class" => rest,
+                all => all,
+            };
+            let letter = rest.first()?;
+            FACILITY_TYPES
+                .iter()
+                .find(|(_, class)| letter.eq_ignore_ascii_case(class))
+                .map(|(facility_type, _)| *facility_type)
+        };
+        named.or_else(class)
+    }
+}
+
+impl crate::geojson::Feature for AphisReport {}
+
+impl crate::geojson::Feature for InspectionReport {
+    const COORDINATE_FIELDS: [&'static str; 2] = ["Geocodio Latitude", "Geocodio Longitude"];
 }
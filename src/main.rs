@@ -15,30 +15,17 @@
 // along with this program. If not, see <https://www.gnu.org/licenses/>.
 
 // Contact the developer directly at untileverycageproject@protonmail.com
-use axum::{Router, routing::get};
-use tower_http::compression::CompressionLayer;
-use tower_http::cors::CorsLayer;
-
-mod location;
-
 #[shuttle_runtime::main]
-async fn main() -> shuttle_axum::ShuttleAxum {
-    let cors = CorsLayer::very_permissive();
-    let app = Router::new()
-        .route(
-            "/api/locations",
-            get(heatmap_backend::get_locations_handler),
-        )
-        .route(
-            "/api/aphis-reports",
-            get(heatmap_backend::get_aphis_reports_handler),
-        )
-        .route(
-            "/api/inspection-reports",
-            get(heatmap_backend::get_inspection_reports_handler),
-        )
-        .layer(CompressionLayer::new().gzip(true))
-        .layer(cors);
-
-    Ok(app.into())
+async fn main(
+    #[shuttle_runtime::Secrets] secrets: shuttle_runtime::SecretStore,
+) -> shuttle_axum::ShuttleAxum {
+    // Shuttle has no environment to configure, so Secrets.toml stands in for
+    // it (`CORS_ORIGINS`, `API_KEYS`, `ADMIN_TOKEN`...). Variables already
+    // set win.
+    let settings = heatmap_backend::settings::Settings::from_env().or(secrets);
+    let cors = heatmap_backend::app::cors(&settings).map_err(shuttle_runtime::CustomError::msg)?;
+    let state = heatmap_backend::AppState::from_settings(settings)
+        .await
+        .map_err(|e| shuttle_runtime::CustomError::msg(e.to_string()))?;
+    Ok(heatmap_backend::app::router(state, cors).into())
 }
@@ -0,0 +1,99 @@
+// Until Every Cage is Empty
+// Copyright (C) 2025 Eli Perez
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+// Contact the developer directly at untileverycageproject@protonmail.com
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::path::Path;
+
+use serde::Deserialize;
+
+use crate::activities::Activity;
+
+/// A table mapping a source dataset's category strings to activity labels
+/// (see `Activity::label`).
+///
+/// Contributors can extend a country's table without touching Rust by
+/// passing a TOML file like this one to the importer:
+///
+/// ```toml
+/// [mappings]
+/// "Slagterier" = ["Meat Processing", "Meat Slaughter"]
+/// "Fremstilling af animalske produkter - Æg" = ["Egg Product"]
+/// ```
+#[derive(Deserialize, Debug, Default, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct MappingTable {
+    #[serde(default)]
+    pub mappings: BTreeMap<String, Vec<String>>,
+}
+
+impl MappingTable {
+    /// Parses and validates a table. `origin` names the file in error messages.
+    pub fn parse(toml_source: &str, origin: &str) -> Result<Self, Box<dyn Error>> {
+        let table: MappingTable =
+            toml::from_str(toml_source).map_err(|e| format!("{}: {}", origin, e))?;
+
+        let problems = table.validate();
+        if !problems.is_empty() {
+            return Err(format!("{}:\n  {}", origin, problems.join("\n  ")).into());
+        }
+        Ok(table)
+    }
+
+    pub fn load(path: &Path) -> Result<Self, Box<dyn Error>> {
+        let source = std::fs::read_to_string(path)
+            .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+        Self::parse(&source, &path.display().to_string())
+    }
+
+    /// Every problem found in the table, so contributors can fix them in one go.
+    pub fn validate(&self) -> Vec<String> {
+        let mut problems = Vec::new();
+        for (category, activities) in &self.mappings {
+            if category.trim().is_empty() {
+                problems.push("empty category name".to_string());
+            }
+            if activities.is_empty() {
+                problems.push(format!("{:?} maps to no activities", category));
+            }
+            for activity in activities {
+                if Activity::from_label(activity).is_none() {
+                    let labels: Vec<&str> = Activity::all().map(Activity::label).collect();
+                    problems.push(format!(
+                        "{:?} maps to unknown activity {:?} (expected one of: {})",
+                        category,
+                        activity,
+                        labels.join(", ")
+                    ));
+                }
+            }
+        }
+        problems
+    }
+
+    /// Adds `other`'s entries, replacing any category both tables define.
+    pub fn merge(&mut self, other: MappingTable) {
+        self.mappings.extend(other.mappings);
+    }
+
+    /// The activities for `category`, joined the way the `type` column expects.
+    pub fn activities(&self, category: &str) -> Option<String> {
+        self.mappings
+            .get(category)
+            .map(|activities| activities.join("; "))
+    }
+}
@@ -0,0 +1,317 @@
+// Until Every Cage is Empty
+// Copyright (C) 2025 Eli Perez
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+// Contact the developer directly at untileverycageproject@protonmail.com
+//! Links records of different datasets that describe the same site, e.g. an
+//! FSIS establishment that is also an APHIS licensee.
+
+use std::collections::{HashMap, HashSet};
+
+use rstar::primitives::GeomWithData;
+use rstar::{AABB, RTree};
+use serde::Serialize;
+
+use crate::facilities::Facility;
+use crate::search::normalize;
+use crate::slug::LEGAL_SUFFIXES;
+use crate::spatial::haversine_km;
+
+/// Candidates further apart than this are only linked on name and ZIP.
+const MAX_DISTANCE_KM: f64 = 1.0;
+const MIN_SCORE: f64 = 0.6;
+const NAME_WEIGHT: f64 = 0.5;
+const ADDRESS_WEIGHT: f64 = 0.3;
+const PROXIMITY_WEIGHT: f64 = 0.2;
+
+const STOPWORDS: &[&str] = &["the", "of", "and", "at"];
+
+/// Street words spelled out in one dataset and abbreviated in another.
+const STREET_ABBREVIATIONS: &[(&str, &str)] = &[
+    ("street", "st"),
+    ("road", "rd"),
+    ("avenue", "ave"),
+    ("drive", "dr"),
+    ("highway", "hwy"),
+    ("boulevard", "blvd"),
+    ("lane", "ln"),
+    ("route", "rt"),
+    ("parkway", "pkwy"),
+    ("north", "n"),
+    ("south", "s"),
+    ("east", "e"),
+    ("west", "w"),
+];
+
+/// Why two records were linked.
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Evidence {
+    Name,
+    Address,
+    Proximity,
+}
+
+/// A record in another dataset that is probably the same facility.
+#[derive(Debug)]
+pub(crate) struct Link {
+    /// Position in the facility list.
+    pub(crate) facility: usize,
+    /// 0 to 1.
+    pub(crate) score: f64,
+    pub(crate) evidence: Vec<Evidence>,
+}
+
+fn name_tokens(name: &str) -> HashSet<String> {
+    normalize(name)
+        .split(' ')
+        .filter(|word| !word.is_empty())
+        .filter(|word| !LEGAL_SUFFIXES.contains(word) && !STOPWORDS.contains(word))
+        .map(str::to_string)
+        .collect()
+}
+
+fn street_tokens(street: &str) -> HashSet<String> {
+    normalize(street)
+        .split(' ')
+        .filter(|word| !word.is_empty())
+        .map(|word| {
+            STREET_ABBREVIATIONS
+                .iter()
+                .find(|(long, _)| *long == word)
+                .map_or(word, |(_, short)| short)
+                .to_string()
+        })
+        .collect()
+}
+
+fn jaccard(a: &HashSet<String>, b: &HashSet<String>) -> f64 {
+    if a.is_empty() || b.is_empty() {
+        return 0.0;
+    }
+    a.intersection(b).count() as f64 / a.union(b).count() as f64
+}
+
+/// A name for fuzzy comparison: its words without legal suffixes and
+/// stopwords, sorted so that their order doesn't count.
+pub(crate) fn comparable_name(name: &str) -> String {
+    let mut words: Vec<String> = name_tokens(name).into_iter().collect();
+    words.sort_unstable();
+    words.join(" ")
+}
+
+fn jaro(a: &[char], b: &[char]) -> f64 {
+    if a.is_empty() || b.is_empty() {
+        return if a.is_empty() && b.is_empty() {
+            1.0
+        } else {
+            0.0
+        };
+    }
+    let window = (a.len().max(b.len()) / 2).saturating_sub(1);
+    let mut b_matched = vec![false; b.len()];
+    let mut a_matches = Vec::new();
+    for (i, c) in a.iter().enumerate() {
+        let end = (i + window + 1).min(b.len());
+        for j in i.saturating_sub(window)..end {
+            if !b_matched[j] && b[j] == *c {
+                b_matched[j] = true;
+                a_matches.push(*c);
+                break;
+            }
+        }
+    }
+    if a_matches.is_empty() {
+        return 0.0;
+    }
+    let b_matches = b
+        .iter()
+        .zip(&b_matched)
+        .filter(|(_, matched)| **matched)
+        .map(|(c, _)| c);
+    let transpositions = a_matches
+        .iter()
+        .zip(b_matches)
+        .filter(|(x, y)| x != y)
+        .count()
+        / 2;
+    let matches = a_matches.len() as f64;
+    (matches / a.len() as f64
+        + matches / b.len() as f64
+        + (matches - transpositions as f64) / matches)
+        / 3.0
+}
+
+/// Jaro-Winkler similarity, 0 to 1: close to 1 for typos, and closer for
+/// strings that agree on their first letters.
+pub(crate) fn jaro_winkler(a: &str, b: &str) -> f64 {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+    let similarity = jaro(&a, &b);
+    let prefix = a.iter().zip(&b).take(4).take_while(|(x, y)| x == y).count();
+    similarity + prefix as f64 * 0.1 * (1.0 - similarity)
+}
+
+fn has_coordinates(facility: &Facility) -> bool {
+    !(facility.latitude == 0.0 && facility.longitude == 0.0)
+}
+
+/// The five-digit ZIP or the whole postal code.
+fn postal_code(facility: &Facility) -> String {
+    let zip = facility.zip.trim();
+    zip.get(..5)
+        .filter(|prefix| facility.country == "us" && prefix.chars().all(|c| c.is_ascii_digit()))
+        .unwrap_or(zip)
+        .to_lowercase()
+}
+
+struct Prepared {
+    name: HashSet<String>,
+    street: HashSet<String>,
+    postal_code: String,
+}
+
+fn prepare(facility: &Facility) -> Prepared {
+    Prepared {
+        name: name_tokens(&facility.name),
+        street: street_tokens(&facility.street),
+        postal_code: postal_code(facility),
+    }
+}
+
+/// The score of a candidate pair and what supports it.
+fn score(a: &Prepared, b: &Prepared, distance_km: Option<f64>) -> (f64, Vec<Evidence>) {
+    let name = jaccard(&a.name, &b.name);
+    let zips_conflict =
+        !a.postal_code.is_empty() && !b.postal_code.is_empty() && a.postal_code != b.postal_code;
+    let address = if zips_conflict {
+        0.0
+    } else {
+        jaccard(&a.street, &b.street)
+    };
+    let proximity = distance_km.map_or(0.0, |distance| {
+        (1.0 - distance / MAX_DISTANCE_KM).clamp(0.0, 1.0)
+    });
+
+    let mut evidence = Vec::new();
+    if name >= 0.5 {
+        evidence.push(Evidence::Name);
+    }
+    if address >= 0.5 {
+        evidence.push(Evidence::Address);
+    }
+    if proximity > 0.0 {
+        evidence.push(Evidence::Proximity);
+    }
+    let total = NAME_WEIGHT * name + ADDRESS_WEIGHT * address + PROXIMITY_WEIGHT * proximity;
+    (total, evidence)
+}
+
+type IndexedPoint = GeomWithData<[f64; 2], usize>;
+
+/// Candidate pairs `(i, j)` with `i < j` from different sources: neighbours
+/// within `MAX_DISTANCE_KM`, plus records sharing name and postal code
+/// wherever they were geocoded.
+fn candidates(
+    facilities: &[Facility],
+    prepared: &[Prepared],
+) -> HashMap<(usize, usize), Option<f64>> {
+    let mut pairs = HashMap::new();
+
+    let tree = RTree::bulk_load(
+        facilities
+            .iter()
+            .enumerate()
+            .filter(|(_, facility)| has_coordinates(facility))
+            .map(|(i, facility)| IndexedPoint::new([facility.longitude, facility.latitude], i))
+            .collect(),
+    );
+    let d_lat = (MAX_DISTANCE_KM / 6371.0).to_degrees();
+    for point in tree.iter() {
+        let i = point.data;
+        let [lng, lat] = *point.geom();
+        let d_lng = d_lat / lat.to_radians().cos().max(0.01);
+        let envelope = AABB::from_corners([lng - d_lng, lat - d_lat], [lng + d_lng, lat + d_lat]);
+        for neighbour in tree.locate_in_envelope(&envelope) {
+            let j = neighbour.data;
+            if i >= j || facilities[i].source == facilities[j].source {
+                continue;
+            }
+            let [other_lng, other_lat] = *neighbour.geom();
+            let distance = haversine_km((lat, lng), (other_lat, other_lng));
+            if distance <= MAX_DISTANCE_KM {
+                pairs.insert((i, j), Some(distance));
+            }
+        }
+    }
+
+    let mut by_key: HashMap<(Vec<&str>, &str), Vec<usize>> = HashMap::new();
+    for (i, prepared) in prepared.iter().enumerate() {
+        if prepared.name.is_empty() || prepared.postal_code.is_empty() {
+            continue;
+        }
+        let mut name: Vec<&str> = prepared.name.iter().map(String::as_str).collect();
+        name.sort_unstable();
+        by_key
+            .entry((name, prepared.postal_code.as_str()))
+            .or_default()
+            .push(i);
+    }
+    for group in by_key.values() {
+        for (n, &i) in group.iter().enumerate() {
+            for &j in &group[n + 1..] {
+                if facilities[i].source != facilities[j].source {
+                    pairs.entry((i, j)).or_insert(None);
+                }
+            }
+        }
+    }
+    pairs
+}
+
+/// For every facility, the records of other datasets that probably describe
+/// the same site, best match first.
+pub(crate) fn resolve(facilities: &[Facility]) -> Vec<Vec<Link>> {
+    let prepared: Vec<Prepared> = facilities.iter().map(prepare).collect();
+    let mut links: Vec<Vec<Link>> = facilities.iter().map(|_| Vec::new()).collect();
+
+    for ((i, j), distance) in candidates(facilities, &prepared) {
+        let (score, evidence) = score(&prepared[i], &prepared[j], distance);
+        // Two records at the same spot are only linked if their names agree
+        // too; several businesses often share an address.
+        if score < MIN_SCORE || !evidence.contains(&Evidence::Name) {
+            continue;
+        }
+        let score = (score * 1000.0).round() / 1000.0;
+        links[i].push(Link {
+            facility: j,
+            score,
+            evidence: evidence.clone(),
+        });
+        links[j].push(Link {
+            facility: i,
+            score,
+            evidence,
+        });
+    }
+    for list in &mut links {
+        list.sort_by(|a, b| {
+            b.score
+                .total_cmp(&a.score)
+                .then(a.facility.cmp(&b.facility))
+        });
+    }
+    links
+}
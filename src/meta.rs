@@ -0,0 +1,132 @@
+// Until Every Cage is Empty
+// Copyright (C) 2025 Eli Perez
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+// Contact the developer directly at untileverycageproject@protonmail.com
+//! GET /api/meta: where each dataset being served comes from, when it was
+//! retrieved, under what terms and which version of it this is, for anyone
+//! citing the data and for the frontend's "data as of".
+
+use axum::Json;
+use axum::extract::State;
+use axum::response::IntoResponse;
+use serde::Serialize;
+use sha1::{Digest, Sha1};
+
+use crate::AppState;
+use crate::delta;
+use crate::importers::{self, IMPORTERS};
+use crate::store::Dataset;
+
+#[derive(Serialize)]
+struct DatasetMeta {
+    /// File or directory in the data, such as "us/locations.csv".
+    dataset: &'static str,
+    country: &'static str,
+    /// Where the importers writing the dataset take it from.
+    source_urls: Vec<&'static str>,
+    /// The latest recorded importer run, if any.
+    retrieved_at: Option<String>,
+    /// Empty where the terms aren't confirmed.
+    licenses: Vec<&'static str>,
+    records: usize,
+    /// SHA-1 of the dataset's files, changing only when their contents do.
+    version: String,
+}
+
+#[derive(Serialize)]
+struct Meta {
+    /// SHA-1 of every file being served.
+    version: String,
+    /// When the data was last retrieved from upstream, if an importer
+    /// recorded it.
+    data_as_of: Option<String>,
+    /// The newest location snapshot, to sync from with
+    /// /api/locations/delta?since=.
+    snapshot_version: Option<String>,
+    loaded_at: String,
+    datasets: Vec<DatasetMeta>,
+}
+
+/// Hashes `(path, contents)` pairs in the order given.
+fn version<'a>(files: impl Iterator<Item = (String, &'a [u8])>) -> String {
+    let mut hasher = Sha1::new();
+    for (path, contents) in files {
+        hasher.update(path.as_bytes());
+        hasher.update([0]);
+        hasher.update((contents.len() as u64).to_be_bytes());
+        hasher.update(contents);
+    }
+    format!("{:x}", hasher.finalize())
+}
+
+/// The file at `output`, or the files directly inside the directory.
+fn files<'a>(dataset: &'a Dataset, output: &str) -> Vec<(String, &'a [u8])> {
+    match dataset.get(output) {
+        Some(contents) => vec![(output.to_string(), contents)],
+        None => dataset
+            .files_in(output)
+            .map(|(name, contents)| (format!("{}/{}", output, name), contents))
+            .collect(),
+    }
+}
+
+/// GET /api/meta
+pub async fn get_meta_handler(State(state): State<AppState>) -> impl IntoResponse {
+    let dataset = state.store.current();
+    let mut datasets: Vec<DatasetMeta> = Vec::new();
+    for importer in IMPORTERS {
+        if let Some(meta) = datasets
+            .iter_mut()
+            .find(|meta| meta.dataset == importer.output)
+        {
+            if !meta.source_urls.contains(&importer.source_url) {
+                meta.source_urls.push(importer.source_url);
+            }
+            if !meta.licenses.contains(&importer.license) {
+                meta.licenses.push(importer.license);
+            }
+            continue;
+        }
+        let Some(records) = importers::count_records(&dataset, importer.output) else {
+            continue;
+        };
+        datasets.push(DatasetMeta {
+            dataset: importer.output,
+            country: importer.country,
+            source_urls: vec![importer.source_url],
+            retrieved_at: importers::last_run(&dataset, importer.output),
+            licenses: vec![importer.license],
+            records,
+            version: version(files(&dataset, importer.output).into_iter()),
+        });
+    }
+    for meta in &mut datasets {
+        meta.source_urls.retain(|url| !url.is_empty());
+        meta.licenses.retain(|license| !license.is_empty());
+    }
+
+    Json(Meta {
+        version: version(
+            dataset
+                .files()
+                .map(|(path, contents)| (path.to_string(), contents)),
+        ),
+        data_as_of: importers::latest_run(&dataset),
+        snapshot_version: delta::current_version(&dataset),
+        loaded_at: dataset.loaded_at.clone(),
+        datasets,
+    })
+}
@@ -0,0 +1,596 @@
+// Until Every Cage is Empty
+// Copyright (C) 2025 Eli Perez
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+// Contact the developer directly at untileverycageproject@protonmail.com
+//! Admin routes for curating the data: reviewing community submissions,
+//! flagging records as closed, suspended or incorrect, and the audit log of
+//! both. Residences marked for redaction (see `redaction`) are kept here too.
+
+use std::collections::HashMap;
+use std::error::Error;
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+use axum::Json;
+use axum::extract::{Path, State};
+use axum::http::{HeaderMap, StatusCode};
+use axum::response::{IntoResponse, Response};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::AppState;
+use crate::admin::authorize;
+use crate::errors::ApiError;
+use crate::geocode;
+use crate::redaction::Residence;
+use crate::settings::Settings;
+use crate::status::FacilityStatus;
+use crate::submissions::{ProposedFacility, SubmissionStatus, UpdateError, save_json};
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum FlagKind {
+    /// The facility no longer operates.
+    Closed,
+    /// The license or grant is suspended or revoked.
+    Suspended,
+    /// The record is wrong: the wrong place, the wrong activity, or no
+    /// such facility.
+    Incorrect,
+}
+
+/// A moderator's note on an existing record, served with it as `flag`.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Flag {
+    pub kind: FlagKind,
+    #[serde(default)]
+    pub note: String,
+    pub flagged_by: String,
+    pub flagged_at: String,
+}
+
+impl Flag {
+    /// The status the flag gives the record, if it changes it.
+    pub fn status(&self) -> Option<FacilityStatus> {
+        match self.kind {
+            FlagKind::Closed => Some(FacilityStatus::Closed),
+            FlagKind::Suspended => Some(FacilityStatus::Suspended),
+            FlagKind::Incorrect => None,
+        }
+    }
+
+    /// The day the flag was set, as YYYY-MM-DD.
+    pub fn date(&self) -> String {
+        self.flagged_at.chars().take(10).collect()
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+struct FlaggedRecord {
+    country: String,
+    establishment_id: String,
+    #[serde(flatten)]
+    flag: Flag,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+struct ResidentialRecord {
+    facility_id: String,
+    #[serde(flatten)]
+    residence: Residence,
+}
+
+/// One field a moderator changed.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Change {
+    pub field: String,
+    pub from: Value,
+    pub to: Value,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct AuditEntry {
+    pub at: String,
+    pub by: String,
+    /// `approve`, `reject`, `merge`, `edit`, `flag`, `unflag`, `redact` or
+    /// `unredact`.
+    pub action: String,
+    /// `submission/<id>`, `<country>/<establishment_id>` or
+    /// `facility/<facility_id>`.
+    pub target: String,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub changes: Vec<Change>,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub note: String,
+}
+
+#[derive(Serialize, Deserialize, Default)]
+struct ModerationFile {
+    flags: Vec<FlaggedRecord>,
+    #[serde(default)]
+    residences: Vec<ResidentialRecord>,
+    audit: Vec<AuditEntry>,
+}
+
+/// Flags, residences and the audit log, kept in the JSON file
+/// `MODERATION_FILE` names.
+/// Without it they only last until a restart.
+#[derive(Default)]
+pub struct Moderation {
+    path: Option<PathBuf>,
+    state: Mutex<ModerationFile>,
+}
+
+impl Moderation {
+    pub fn from_settings(settings: &Settings) -> Result<Self, Box<dyn Error>> {
+        let Some(path) = settings.get("MODERATION_FILE") else {
+            return Ok(Moderation::default());
+        };
+        let path = PathBuf::from(path);
+        let state = match std::fs::read_to_string(&path) {
+            Ok(source) => serde_json::from_str(&source)
+                .map_err(|e| format!("Failed to parse {}: {}", path.display(), e))?,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => ModerationFile::default(),
+            Err(e) => return Err(format!("Failed to read {}: {}", path.display(), e).into()),
+        };
+        Ok(Moderation {
+            path: Some(path),
+            state: Mutex::new(state),
+        })
+    }
+
+    fn save(&self, state: &ModerationFile) -> Result<(), Box<dyn Error>> {
+        match &self.path {
+            Some(path) => save_json(path, state),
+            None => Ok(()),
+        }
+    }
+
+    /// Appends to the audit log. The change it records has already been
+    /// made, so a failure to save is only reported.
+    fn record(&self, entry: AuditEntry) -> Result<(), Box<dyn Error>> {
+        let mut state = self.state.lock().unwrap();
+        state.audit.push(entry);
+        self.save(&state)
+    }
+
+    /// Sets or, with `None`, clears the flag on a record and logs `entry`
+    /// with what changed. Returns the previous flag; clearing a record that
+    /// has none changes and logs nothing.
+    fn set_flag(
+        &self,
+        country: &str,
+        establishment_id: &str,
+        flag: Option<Flag>,
+        mut entry: AuditEntry,
+    ) -> Result<Option<Flag>, Box<dyn Error>> {
+        let mut state = self.state.lock().unwrap();
+        let position = state.flags.iter().position(|record| {
+            geocode::country_code(&record.country) == country
+                && record.establishment_id == establishment_id
+        });
+        if position.is_none() && flag.is_none() {
+            return Ok(None);
+        }
+
+        let saved = state.flags.clone();
+        let previous = position.map(|position| state.flags.remove(position).flag);
+        let kind = |flag: Option<&Flag>| json_value(flag.map(|flag| flag.kind));
+        entry.changes = vec![Change {
+            field: "flag".to_string(),
+            from: kind(previous.as_ref()),
+            to: kind(flag.as_ref()),
+        }];
+        if let Some(flag) = flag {
+            state.flags.push(FlaggedRecord {
+                country: country.to_string(),
+                establishment_id: establishment_id.to_string(),
+                flag,
+            });
+        }
+        state.audit.push(entry);
+        if let Err(e) = self.save(&state) {
+            state.flags = saved;
+            state.audit.pop();
+            return Err(e);
+        }
+        Ok(previous)
+    }
+
+    /// Every flag, keyed by `(country, establishment_id)`.
+    pub fn flags(&self) -> HashMap<(String, String), Flag> {
+        self.state
+            .lock()
+            .unwrap()
+            .flags
+            .iter()
+            .map(|record| {
+                (
+                    (
+                        geocode::country_code(&record.country),
+                        record.establishment_id.clone(),
+                    ),
+                    record.flag.clone(),
+                )
+            })
+            .collect()
+    }
+
+    /// Marks or, with `None`, unmarks a facility as a residence and logs
+    /// `entry`, like `set_flag`. Returns the previous mark.
+    pub(crate) fn set_residence(
+        &self,
+        facility_id: &str,
+        residence: Option<Residence>,
+        mut entry: AuditEntry,
+    ) -> Result<Option<Residence>, Box<dyn Error>> {
+        let mut state = self.state.lock().unwrap();
+        let position = state
+            .residences
+            .iter()
+            .position(|record| record.facility_id == facility_id);
+        if position.is_none() && residence.is_none() {
+            return Ok(None);
+        }
+
+        let saved = state.residences.clone();
+        let previous = position.map(|position| state.residences.remove(position).residence);
+        entry.changes = vec![Change {
+            field: "is_residential".to_string(),
+            from: Value::Bool(previous.is_some()),
+            to: Value::Bool(residence.is_some()),
+        }];
+        if let Some(residence) = residence {
+            state.residences.push(ResidentialRecord {
+                facility_id: facility_id.to_string(),
+                residence,
+            });
+        }
+        state.audit.push(entry);
+        if let Err(e) = self.save(&state) {
+            state.residences = saved;
+            state.audit.pop();
+            return Err(e);
+        }
+        Ok(previous)
+    }
+
+    /// Every residence, keyed by facility ID.
+    pub(crate) fn residences(&self) -> HashMap<String, Residence> {
+        self.state
+            .lock()
+            .unwrap()
+            .residences
+            .iter()
+            .map(|record| (record.facility_id.clone(), record.residence.clone()))
+            .collect()
+    }
+
+    fn audit(&self) -> Vec<AuditEntry> {
+        self.state.lock().unwrap().audit.clone()
+    }
+}
+
+pub(crate) fn now() -> String {
+    chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Secs, true)
+}
+
+/// The fields that differ between two versions of the same thing.
+fn changes<T: Serialize>(before: &T, after: &T) -> Vec<Change> {
+    let (Ok(Value::Object(before)), Ok(Value::Object(mut after))) =
+        (serde_json::to_value(before), serde_json::to_value(after))
+    else {
+        return Vec::new();
+    };
+    let mut changes: Vec<Change> = before
+        .into_iter()
+        .filter_map(|(field, from)| {
+            let to = after.remove(&field).unwrap_or(Value::Null);
+            (from != to).then_some(Change { field, from, to })
+        })
+        .collect();
+    changes.extend(after.into_iter().map(|(field, to)| Change {
+        field,
+        from: Value::Null,
+        to,
+    }));
+    changes
+}
+
+/// Records `entry` in the audit log and, when the served locations changed,
+/// rebuilds them, before answering with `body`.
+async fn finish<T: Serialize>(
+    state: &AppState,
+    entry: AuditEntry,
+    reindex: bool,
+    body: T,
+) -> Response {
+    let logged = state.moderation.record(entry).map_err(|e| e.to_string());
+    if reindex && let Err(e) = state.rebuild_location_index().await {
+        return ApiError::internal(format!("Saved the change but failed to reindex: {}", e))
+            .into_response();
+    }
+    if let Err(e) = logged {
+        return ApiError::internal(format!("Saved the change but not the audit log: {}", e))
+            .into_response();
+    }
+    Json(body).into_response()
+}
+
+fn update_error(id: u64, error: UpdateError) -> Response {
+    match error {
+        UpdateError::NotFound => (
+            StatusCode::NOT_FOUND,
+            format!("No submission with id {}", id),
+        )
+            .into_response(),
+        UpdateError::Invalid(e) => (StatusCode::CONFLICT, e).into_response(),
+        UpdateError::Save(e) => {
+            ApiError::internal(format!("Failed to save submission: {}", e)).into_response()
+        }
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(tag = "action", rename_all = "snake_case")]
+pub enum Decision {
+    /// Serve the submission as a location.
+    Approve,
+    Reject,
+    /// The facility is already in the data as this record.
+    Merge {
+        country: String,
+        establishment_id: String,
+    },
+}
+
+#[derive(Deserialize)]
+pub struct ReviewRequest {
+    #[serde(flatten)]
+    decision: Decision,
+    #[serde(default)]
+    note: String,
+}
+
+/// POST /api/admin/submissions/{id}/review
+///
+/// Approves, rejects or merges a submission. Decisions can be revisited:
+/// rejecting an approved submission takes it off the map again.
+pub async fn post_review_handler(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(id): Path<u64>,
+    Json(request): Json<ReviewRequest>,
+) -> impl IntoResponse {
+    let moderator = match authorize(&state, &headers) {
+        Ok(moderator) => moderator,
+        Err(e) => return e.into_response(),
+    };
+
+    let (action, status, merged_into) = match &request.decision {
+        Decision::Approve => ("approve", SubmissionStatus::Approved, None),
+        Decision::Reject => ("reject", SubmissionStatus::Rejected, None),
+        Decision::Merge {
+            country,
+            establishment_id,
+        } => {
+            let index = state.location_index();
+            let exists = index.locations.iter().any(|loc| {
+                loc.country == geocode::country_code(country)
+                    && loc.establishment_id == *establishment_id
+            });
+            if !exists {
+                return (
+                    StatusCode::BAD_REQUEST,
+                    format!("No location {}/{} to merge into", country, establishment_id),
+                )
+                    .into_response();
+            }
+            let target = format!("{}/{}", country, establishment_id);
+            ("merge", SubmissionStatus::Merged, Some(target))
+        }
+    };
+
+    let reviewed_at = now();
+    let updated = state.submissions.update(id, |submission| {
+        if merged_into.as_deref() == Some(&submission.establishment_id()) {
+            return Err("A submission can't be merged into itself".to_string());
+        }
+        submission.status = status;
+        submission.merged_into = merged_into.clone();
+        submission.reviewed_by = Some(moderator.clone());
+        submission.reviewed_at = Some(reviewed_at.clone());
+        Ok(())
+    });
+    let (before, after) = match updated {
+        Ok(updated) => updated,
+        Err(e) => return update_error(id, e),
+    };
+
+    let reindex =
+        before.status == SubmissionStatus::Approved || status == SubmissionStatus::Approved;
+    let entry = AuditEntry {
+        at: reviewed_at,
+        by: moderator,
+        action: action.to_string(),
+        target: format!("submission/{}", id),
+        changes: changes(&before, &after),
+        note: request.note,
+    };
+    finish(&state, entry, reindex, after).await
+}
+
+/// PUT /api/admin/submissions/{id}
+///
+/// Replaces what a submission proposes, e.g. to fix a typo before approving
+/// it. Approved submissions change on the map straight away.
+pub async fn put_submission_handler(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(id): Path<u64>,
+    Json(facility): Json<ProposedFacility>,
+) -> impl IntoResponse {
+    let moderator = match authorize(&state, &headers) {
+        Ok(moderator) => moderator,
+        Err(e) => return e.into_response(),
+    };
+    let facility = match facility.validate() {
+        Ok(facility) => facility,
+        Err(e) => return (StatusCode::BAD_REQUEST, e).into_response(),
+    };
+
+    let (before, after) = match state.submissions.update(id, |submission| {
+        submission.facility = facility;
+        Ok(())
+    }) {
+        Ok(updated) => updated,
+        Err(e) => return update_error(id, e),
+    };
+
+    let entry = AuditEntry {
+        at: now(),
+        by: moderator,
+        action: "edit".to_string(),
+        target: format!("submission/{}", id),
+        changes: changes(&before.facility, &after.facility),
+        note: String::new(),
+    };
+    let reindex = after.status == SubmissionStatus::Approved;
+    finish(&state, entry, reindex, after).await
+}
+
+#[derive(Deserialize)]
+pub struct FlagRequest {
+    kind: FlagKind,
+    #[serde(default)]
+    note: String,
+}
+
+/// PUT /api/admin/flags/{country}/{establishment_id}
+///
+/// Flags a location as closed, suspended or incorrect. The flag is served with the
+/// location; the record itself is left as its source has it.
+pub async fn put_flag_handler(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path((country, establishment_id)): Path<(String, String)>,
+    Json(request): Json<FlagRequest>,
+) -> impl IntoResponse {
+    let moderator = match authorize(&state, &headers) {
+        Ok(moderator) => moderator,
+        Err(e) => return e.into_response(),
+    };
+    // `uk` and `GB` name the same country.
+    let country = geocode::country_code(&country);
+    let index = state.location_index();
+    if !index
+        .locations
+        .iter()
+        .any(|loc| loc.country == country && loc.establishment_id == establishment_id)
+    {
+        return (
+            StatusCode::NOT_FOUND,
+            format!("No location {}/{}", country, establishment_id),
+        )
+            .into_response();
+    }
+
+    let flag = Flag {
+        kind: request.kind,
+        note: request.note.clone(),
+        flagged_by: moderator.clone(),
+        flagged_at: now(),
+    };
+    let entry = AuditEntry {
+        at: flag.flagged_at.clone(),
+        by: moderator,
+        action: "flag".to_string(),
+        target: format!("{}/{}", country, establishment_id),
+        changes: Vec::new(),
+        note: request.note,
+    };
+    set_flag(&state, &country, &establishment_id, Some(flag), entry).await
+}
+
+/// DELETE /api/admin/flags/{country}/{establishment_id}
+pub async fn delete_flag_handler(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path((country, establishment_id)): Path<(String, String)>,
+) -> impl IntoResponse {
+    let moderator = match authorize(&state, &headers) {
+        Ok(moderator) => moderator,
+        Err(e) => return e.into_response(),
+    };
+    let country = geocode::country_code(&country);
+    let entry = AuditEntry {
+        at: now(),
+        by: moderator,
+        action: "unflag".to_string(),
+        target: format!("{}/{}", country, establishment_id),
+        changes: Vec::new(),
+        note: String::new(),
+    };
+    set_flag(&state, &country, &establishment_id, None, entry).await
+}
+
+async fn set_flag(
+    state: &AppState,
+    country: &str,
+    establishment_id: &str,
+    flag: Option<Flag>,
+    entry: AuditEntry,
+) -> Response {
+    let set = state
+        .moderation
+        .set_flag(country, establishment_id, flag.clone(), entry)
+        .map_err(|e| e.to_string());
+    match set {
+        Ok(None) if flag.is_none() => {
+            return (
+                StatusCode::NOT_FOUND,
+                format!("{}/{} is not flagged", country, establishment_id),
+            )
+                .into_response();
+        }
+        Ok(_) => {}
+        Err(e) => {
+            return ApiError::internal(format!("Failed to save flag: {}", e)).into_response();
+        }
+    }
+    if let Err(e) = state.rebuild_location_index().await {
+        return ApiError::internal(format!("Saved the flag but failed to reindex: {}", e))
+            .into_response();
+    }
+    Json(flag).into_response()
+}
+
+fn json_value<T: Serialize>(value: T) -> Value {
+    serde_json::to_value(value).unwrap_or(Value::Null)
+}
+
+/// GET /api/admin/audit: every moderation action, newest first.
+pub async fn get_audit_handler(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> impl IntoResponse {
+    if let Err(e) = authorize(&state, &headers) {
+        return e.into_response();
+    }
+    let mut audit = state.moderation.audit();
+    audit.reverse();
+    Json(audit).into_response()
+}
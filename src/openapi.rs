@@ -0,0 +1,1039 @@
+// Until Every Cage is Empty
+// Copyright (C) 2025 Eli Perez
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+// Contact the developer directly at untileverycageproject@protonmail.com
+//! GET /api/openapi.json and /api/docs: an OpenAPI 3.1 description of the
+//! API and a Swagger UI to try it in.
+//!
+//! The operations are listed by hand in `OPERATIONS`; add one there with
+//! every new route in `app::routes`. The API tests check the two agree.
+
+use axum::Json;
+use axum::http::header;
+use axum::response::{Html, IntoResponse};
+use serde_json::{Map, Value, json};
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum In {
+    Query,
+    Path,
+}
+
+#[derive(Clone, Copy)]
+struct Parameter {
+    name: &'static str,
+    location: In,
+    /// JSON Schema type: "string", "integer", "number" or "boolean".
+    kind: &'static str,
+    required: bool,
+    description: &'static str,
+}
+
+const fn query(name: &'static str, kind: &'static str, description: &'static str) -> Parameter {
+    Parameter {
+        name,
+        location: In::Query,
+        kind,
+        required: false,
+        description,
+    }
+}
+
+const fn required(parameter: Parameter) -> Parameter {
+    Parameter {
+        required: true,
+        ..parameter
+    }
+}
+
+const fn path(name: &'static str, description: &'static str) -> Parameter {
+    Parameter {
+        name,
+        location: In::Path,
+        kind: "string",
+        required: true,
+        description,
+    }
+}
+
+const COUNTRY_CODE: Parameter = query(
+    "country_code",
+    "string",
+    "Two-letter country directory, e.g. us or de.",
+);
+const COUNTRY: Parameter = query(
+    "country",
+    "string",
+    "ISO 3166-1 alpha-2 code in either case, e.g. US or GB; same as country_code.",
+);
+const STATE: Parameter = query("state", "string", "Comma-separated states or regions.");
+const CITY: Parameter = query("city", "string", "Comma-separated cities.");
+const USPS_STATE: Parameter = query("state", "string", "Comma-separated USPS state codes.");
+const ZIP: Parameter = query(
+    "zip",
+    "string",
+    "ZIP code or prefix, e.g. 841 for the ZIP3 area.",
+);
+const SPECIES: Parameter = query(
+    "species",
+    "string",
+    "Comma-separated species or groups from /api/activities, e.g. Pigs,poultry.",
+);
+const ACTIVITY: Parameter = query(
+    "activity",
+    "string",
+    "Comma-separated activity IDs or labels, e.g. meat_slaughter.",
+);
+const STATUS: Parameter = query(
+    "status",
+    "string",
+    "Comma-separated statuses: active, closed or suspended.",
+);
+const STATUS_SINCE: Parameter = query(
+    "status_since",
+    "string",
+    "YYYY-MM-DD: only records whose status changed on or after this day.",
+);
+const LANG: Parameter = query("lang", "string", "en (default), es, de, fr, it or da.");
+const SOURCE: Parameter = query(
+    "source",
+    "string",
+    "Comma-separated datasets: locations, aphis_reports, inspection_reports, aquaculture, fur_farms, egg_farms, cafos, equine.",
+);
+const SORT: Parameter = query("sort", "string", "Field to sort by.");
+const ORDER: Parameter = query("order", "string", "asc or desc.");
+const FORMAT: Parameter = query("format", "string", "json (default), geojson or csv.");
+const FACILITY_FORMAT: Parameter = query(
+    "format",
+    "string",
+    "json (default), geojson, csv or pb (Protocol Buffers, see proto/facilities.proto).",
+);
+const LIMIT: Parameter = query("limit", "integer", "Maximum number of items.");
+const OFFSET: Parameter = query("offset", "integer", "Items to skip.");
+const FIELDS: Parameter = query("fields", "string", "Comma-separated fields to keep.");
+const SEARCH: Parameter = required(query("q", "string", "Search text."));
+const HUMANE_HANDLING: Parameter = query(
+    "humane_handling",
+    "boolean",
+    "Only records about the handling of animals.",
+);
+const CERTIFICATE: Parameter = query("certificate", "string", "An APHIS certificate number.");
+
+/// What a 200 response carries besides its description.
+#[derive(Clone, Copy)]
+enum Body {
+    Json,
+    /// JSON, GeoJSON or CSV, paged, with the total in X-Total-Count.
+    List,
+    Binary(&'static str),
+}
+
+struct Operation {
+    method: &'static str,
+    path: &'static str,
+    tag: &'static str,
+    summary: &'static str,
+    parameters: &'static [Parameter],
+    body: Body,
+    /// Needs `Authorization: Bearer <token>` with a token from `ADMIN_TOKEN`
+    /// or `ADMIN_TOKENS`.
+    admin: bool,
+}
+
+const fn get(
+    path: &'static str,
+    tag: &'static str,
+    summary: &'static str,
+    parameters: &'static [Parameter],
+    body: Body,
+) -> Operation {
+    Operation {
+        method: "get",
+        path,
+        tag,
+        summary,
+        parameters,
+        body,
+        admin: false,
+    }
+}
+
+const OPERATIONS: &[Operation] = &[
+    get(
+        "/api/locations",
+        "locations",
+        "Slaughterhouses and processing plants",
+        &[
+            COUNTRY_CODE,
+            COUNTRY,
+            STATE,
+            SPECIES,
+            ACTIVITY,
+            STATUS,
+            query(
+                "granted_after",
+                "string",
+                "YYYY-MM-DD: only locations granted on or after this day.",
+            ),
+            query(
+                "granted_before",
+                "string",
+                "YYYY-MM-DD: only locations granted on or before this day.",
+            ),
+            SORT,
+            ORDER,
+            FORMAT,
+            LIMIT,
+            OFFSET,
+            FIELDS,
+        ],
+        Body::List,
+    ),
+    get(
+        "/api/locations/bbox",
+        "locations",
+        "Locations inside a bounding box",
+        &[
+            required(query("min_lat", "number", "")),
+            required(query("min_lng", "number", "")),
+            required(query("max_lat", "number", "")),
+            required(query("max_lng", "number", "")),
+            COUNTRY_CODE,
+        ],
+        Body::Json,
+    ),
+    get(
+        "/api/locations/near",
+        "locations",
+        "Locations within a radius, nearest first",
+        &[
+            required(query("lat", "number", "")),
+            required(query("lng", "number", "")),
+            required(query("radius_km", "number", "")),
+            COUNTRY_CODE,
+            LIMIT,
+        ],
+        Body::Json,
+    ),
+    get(
+        "/api/locations/along-route",
+        "locations",
+        "Locations within a corridor along a route, in route order",
+        &[
+            query("polyline", "string", "An encoded polyline (precision 5)."),
+            query(
+                "waypoints",
+                "string",
+                "lat,lng;lat,lng;... when no polyline is given.",
+            ),
+            required(query(
+                "width_km",
+                "number",
+                "How far from the route a location may be, at most 100.",
+            )),
+            COUNTRY_CODE,
+            LIMIT,
+        ],
+        Body::Json,
+    ),
+    get(
+        "/api/locations/by-fips/{code}",
+        "locations",
+        "Locations in a US county",
+        &[
+            path("code", "Five-digit county FIPS code, e.g. 42101."),
+            FORMAT,
+            LIMIT,
+            OFFSET,
+            FIELDS,
+        ],
+        Body::Json,
+    ),
+    get(
+        "/api/locations/by-zip/{zip}",
+        "locations",
+        "Locations whose postal code starts with a prefix",
+        &[
+            path(
+                "zip",
+                "A postal code or a prefix of at least three characters, e.g. 191.",
+            ),
+            COUNTRY_CODE,
+            FORMAT,
+            LIMIT,
+            OFFSET,
+            FIELDS,
+        ],
+        Body::Json,
+    ),
+    get(
+        "/api/locations/slug/{slug}",
+        "locations",
+        "A location by its URL slug",
+        &[path("slug", "")],
+        Body::Json,
+    ),
+    get(
+        "/api/locations/changes",
+        "locations",
+        "Establishments added, removed and modified since a date",
+        &[
+            required(query("since", "string", "YYYY-MM-DD.")),
+            COUNTRY_CODE,
+        ],
+        Body::Json,
+    ),
+    get(
+        "/api/locations/delta",
+        "locations",
+        "Locations changed and removed since a snapshot version, for clients syncing offline copies",
+        &[
+            required(query(
+                "since",
+                "string",
+                "Version from /api/meta or a previous delta.",
+            )),
+            COUNTRY_CODE,
+        ],
+        Body::Json,
+    ),
+    get(
+        "/api/locations/{establishment_id}",
+        "locations",
+        "A location with its related records",
+        &[path("establishment_id", ""), COUNTRY_CODE],
+        Body::Json,
+    ),
+    get(
+        "/api/locations/{establishment_id}/card",
+        "locations",
+        "Open Graph title, description and facts for a location's share card",
+        &[path("establishment_id", ""), COUNTRY_CODE],
+        Body::Json,
+    ),
+    get(
+        "/api/locations/{establishment_id}/enforcement",
+        "locations",
+        "A US location's FSIS enforcement actions and noncompliance records",
+        &[path("establishment_id", ""), HUMANE_HANDLING],
+        Body::Json,
+    ),
+    get(
+        "/api/enforcement",
+        "locations",
+        "Establishments with FSIS enforcement records",
+        &[HUMANE_HANDLING],
+        Body::Json,
+    ),
+    get(
+        "/tiles/{z}/{x}/{y}",
+        "locations",
+        "Locations as a Mapbox Vector Tile",
+        &[
+            path("z", ""),
+            path("x", ""),
+            path("y", "The row with .mvt, e.g. 12.mvt."),
+        ],
+        Body::Binary("application/vnd.mapbox-vector-tile"),
+    ),
+    get(
+        "/api/facilities",
+        "facilities",
+        "Records of every dataset in one shape",
+        &[
+            SOURCE,
+            COUNTRY_CODE,
+            STATE,
+            SPECIES,
+            ACTIVITY,
+            STATUS,
+            FACILITY_FORMAT,
+            LIMIT,
+            OFFSET,
+            FIELDS,
+        ],
+        Body::List,
+    ),
+    get(
+        "/api/facilities/{id}",
+        "facilities",
+        "A record of any dataset by its permanent facility ID, with related records",
+        &[path(
+            "id",
+            "A facility ID such as f3a9c04e1b27d865, or a source id such as location:us:123.",
+        )],
+        Body::Json,
+    ),
+    get(
+        "/api/facilities/{id}/related",
+        "facilities",
+        "Records of other datasets describing the same site",
+        &[path(
+            "id",
+            "A facility ID such as f3a9c04e1b27d865, or a source id such as location:us:123.",
+        )],
+        Body::Json,
+    ),
+    get(
+        "/api/graphql",
+        "facilities",
+        "Facilities, APHIS reports and inspections with their relations, as GraphQL",
+        &[
+            required(query("query", "string", "A GraphQL query.")),
+            query(
+                "variables",
+                "string",
+                "The query's variables as a JSON object.",
+            ),
+            query("operationName", "string", "The operation to run."),
+        ],
+        Body::Json,
+    ),
+    Operation {
+        method: "post",
+        path: "/api/graphql",
+        tag: "facilities",
+        summary: "The same, with {\"query\", \"variables\", \"operationName\"} as the body",
+        parameters: &[],
+        body: Body::Json,
+        admin: false,
+    },
+    get(
+        "/api/aquaculture",
+        "facilities",
+        "Aquaculture sites",
+        &[COUNTRY_CODE, STATE, SPECIES, FORMAT, LIMIT, OFFSET, FIELDS],
+        Body::List,
+    ),
+    get(
+        "/api/fur-farms",
+        "facilities",
+        "Fur farms",
+        &[COUNTRY_CODE, STATE, SPECIES, FORMAT, LIMIT, OFFSET, FIELDS],
+        Body::List,
+    ),
+    get(
+        "/api/egg-farms",
+        "facilities",
+        "Egg-laying operations and hatcheries",
+        &[
+            COUNTRY_CODE,
+            STATE,
+            SPECIES,
+            query("activity", "string", "egg_farm or hatchery."),
+            FORMAT,
+            LIMIT,
+            OFFSET,
+            FIELDS,
+        ],
+        Body::List,
+    ),
+    get(
+        "/api/cafos",
+        "facilities",
+        "Concentrated animal feeding operations with NPDES permits, with animal counts where disclosed",
+        &[
+            COUNTRY_CODE,
+            STATE,
+            SPECIES,
+            query(
+                "min_animals",
+                "integer",
+                "Only permits counting at least this many animals.",
+            ),
+            FORMAT,
+            LIMIT,
+            OFFSET,
+            FIELDS,
+        ],
+        Body::List,
+    ),
+    get(
+        "/api/equine",
+        "facilities",
+        "US assembly points and export pens for slaughter horses, and the foreign plants they ship to",
+        &[
+            COUNTRY_CODE,
+            STATE,
+            SPECIES,
+            query(
+                "stage",
+                "string",
+                "Comma-separated stages: assembly, export_pen or slaughter.",
+            ),
+            FORMAT,
+            LIMIT,
+            OFFSET,
+            FIELDS,
+        ],
+        Body::List,
+    ),
+    get(
+        "/api/export/kml",
+        "exports",
+        "Facilities as KML for Google Earth",
+        &[SOURCE, COUNTRY_CODE, STATE, SPECIES, ACTIVITY, STATUS],
+        Body::Binary("application/vnd.google-earth.kml+xml"),
+    ),
+    get(
+        "/api/export/kmz",
+        "exports",
+        "Facilities as zipped KML",
+        &[SOURCE, COUNTRY_CODE, STATE, SPECIES, ACTIVITY, STATUS],
+        Body::Binary("application/vnd.google-earth.kmz"),
+    ),
+    get(
+        "/api/export/sqlite",
+        "exports",
+        "Every dataset as a SQLite database",
+        &[],
+        Body::Binary("application/vnd.sqlite3"),
+    ),
+    get(
+        "/api/search",
+        "search",
+        "Full-text search over locations",
+        &[SEARCH, COUNTRY_CODE, LIMIT],
+        Body::Json,
+    ),
+    get(
+        "/api/autocomplete",
+        "search",
+        "Name suggestions",
+        &[SEARCH, COUNTRY_CODE, LIMIT],
+        Body::Json,
+    ),
+    get(
+        "/api/match",
+        "search",
+        "Records of any dataset that may be the same facility, best match first",
+        &[
+            required(query("name", "string", "The facility's name.")),
+            query("city", "string", ""),
+            COUNTRY_CODE,
+            query("lat", "number", "With lng, scores nearby records higher."),
+            query("lng", "number", ""),
+            query("min_score", "number", "0 to 1, 0.8 by default."),
+            LIMIT,
+        ],
+        Body::Json,
+    ),
+    get(
+        "/api/aphis-reports",
+        "aphis",
+        "APHIS annual reports of research facilities",
+        &[
+            STATUS_SINCE,
+            CITY,
+            USPS_STATE,
+            ZIP,
+            SORT,
+            ORDER,
+            FORMAT,
+            LIMIT,
+            OFFSET,
+            FIELDS,
+        ],
+        Body::List,
+    ),
+    get(
+        "/api/aphis-reports/timeseries",
+        "aphis",
+        "Animals used per year",
+        &[CERTIFICATE],
+        Body::Json,
+    ),
+    get(
+        "/api/aphis-reports/{certificate_number}",
+        "aphis",
+        "One research facility",
+        &[path("certificate_number", "")],
+        Body::Json,
+    ),
+    get(
+        "/api/aphis-reports/{certificate_number}/inspections",
+        "aphis",
+        "A research facility's licensee inspection reports",
+        &[path("certificate_number", "")],
+        Body::Json,
+    ),
+    get(
+        "/api/inspection-reports",
+        "aphis",
+        "APHIS licensees and registrants",
+        &[
+            query(
+                "license_type",
+                "string",
+                "Comma-separated classes A, B, C or R, or breeder, dealer, exhibitor, research.",
+            ),
+            STATUS_SINCE,
+            CITY,
+            USPS_STATE,
+            ZIP,
+            FORMAT,
+            LIMIT,
+            OFFSET,
+            FIELDS,
+        ],
+        Body::List,
+    ),
+    get(
+        "/api/inspection-reports/{certificate_number}",
+        "aphis",
+        "One licensee",
+        &[path("certificate_number", "")],
+        Body::Json,
+    ),
+    get(
+        "/api/citations",
+        "aphis",
+        "Inspection histories with their citations",
+        &[query(
+            "certificate",
+            "string",
+            "A certificate number. Without it, a summary per certificate.",
+        )],
+        Body::Json,
+    ),
+    get(
+        "/api/stats",
+        "metadata",
+        "Counts per country, state and activity, and records per country",
+        &[COUNTRY_CODE, COUNTRY],
+        Body::Json,
+    ),
+    get(
+        "/api/choropleth",
+        "metadata",
+        "Counts per state or county, keyed by boundary identifiers",
+        &[
+            query("by", "string", "state (default) or county."),
+            COUNTRY_CODE,
+            SPECIES,
+            ACTIVITY,
+            STATUS,
+        ],
+        Body::Json,
+    ),
+    get(
+        "/api/heatmap",
+        "metadata",
+        "Locations and APHIS facilities weighted by animals, for heat layers",
+        &[
+            query(
+                "weight",
+                "string",
+                "volume (default, 0 to 1) or count (animals a year).",
+            ),
+            COUNTRY_CODE,
+            SPECIES,
+            ACTIVITY,
+            STATUS,
+            FORMAT,
+        ],
+        Body::List,
+    ),
+    get(
+        "/api/data-quality",
+        "metadata",
+        "What contributors could clean up in the data",
+        &[],
+        Body::Json,
+    ),
+    get(
+        "/api/activities",
+        "metadata",
+        "Activities and species",
+        &[LANG],
+        Body::Json,
+    ),
+    get(
+        "/api/i18n/{lang}",
+        "metadata",
+        "Names of species, activities and statuses in a language",
+        &[path("lang", "en, es, de, fr, it or da.")],
+        Body::Json,
+    ),
+    get(
+        "/api/changelog",
+        "metadata",
+        "Data updates",
+        &[],
+        Body::Json,
+    ),
+    get(
+        "/api/importers",
+        "metadata",
+        "Importers and when they last ran",
+        &[],
+        Body::Json,
+    ),
+    get(
+        "/api/meta",
+        "metadata",
+        "Each dataset's source, retrieval date, license and version",
+        &[],
+        Body::Json,
+    ),
+    get(
+        "/api/deprecations",
+        "metadata",
+        "Deprecated routes and parameters",
+        &[],
+        Body::Json,
+    ),
+    get(
+        "/api/openapi.json",
+        "metadata",
+        "This OpenAPI description",
+        &[],
+        Body::Json,
+    ),
+    get(
+        "/api/docs",
+        "metadata",
+        "Swagger UI for this description",
+        &[],
+        Body::Binary("text/html"),
+    ),
+    get(
+        "/sitemap.xml",
+        "locations",
+        "Sitemap of the frontend's pages and every location",
+        &[],
+        Body::Binary("application/xml"),
+    ),
+    get("/healthz", "health", "The process is up", &[], Body::Json),
+    get(
+        "/readyz",
+        "health",
+        "Whether every dataset loaded, with record counts (503 if not)",
+        &[],
+        Body::Json,
+    ),
+    Operation {
+        method: "post",
+        path: "/api/submissions",
+        tag: "community",
+        summary: "Proposes a facility missing from the data",
+        parameters: &[],
+        body: Body::Json,
+        admin: false,
+    },
+    Operation {
+        method: "get",
+        path: "/api/submissions/pending",
+        tag: "community",
+        summary: "Submissions waiting for moderation",
+        parameters: &[],
+        body: Body::Json,
+        admin: true,
+    },
+    Operation {
+        method: "post",
+        path: "/api/feedback",
+        tag: "community",
+        summary: "Reports a problem with a facility",
+        parameters: &[required(query(
+            "facility_id",
+            "string",
+            "An ID from /api/facilities, e.g. location:us:6407.",
+        ))],
+        body: Body::Json,
+        admin: false,
+    },
+    Operation {
+        method: "post",
+        path: "/api/locations/batch",
+        tag: "locations",
+        summary: "Records of every dataset by facility ID, establishment id or certificate number, \
+            from a JSON array of up to 1000 ids",
+        parameters: &[],
+        body: Body::Json,
+        admin: false,
+    },
+    Operation {
+        method: "post",
+        path: "/api/locations/nearest",
+        tag: "locations",
+        summary: "The n nearest locations to each of up to 1000 points, from a JSON body of \
+            points and the filters of /api/locations",
+        parameters: &[],
+        body: Body::Json,
+        admin: false,
+    },
+    Operation {
+        method: "post",
+        path: "/api/subscriptions",
+        tag: "community",
+        summary: "Subscribes a webhook to new and changed locations matching a filter",
+        parameters: &[],
+        body: Body::Json,
+        admin: false,
+    },
+    Operation {
+        method: "delete",
+        path: "/api/subscriptions/{id}",
+        tag: "community",
+        summary: "Unsubscribes, with the subscription's token as bearer token",
+        parameters: &[path("id", "")],
+        body: Body::Json,
+        admin: false,
+    },
+    Operation {
+        method: "get",
+        path: "/api/admin/subscriptions",
+        tag: "community",
+        summary: "Webhook subscriptions and how their last notification went",
+        parameters: &[],
+        body: Body::Json,
+        admin: true,
+    },
+    Operation {
+        method: "get",
+        path: "/api/admin/feedback",
+        tag: "community",
+        summary: "Reported problems, newest first",
+        parameters: &[],
+        body: Body::Json,
+        admin: true,
+    },
+    Operation {
+        method: "put",
+        path: "/api/admin/submissions/{id}",
+        tag: "community",
+        summary: "Edits a submission",
+        parameters: &[path("id", "")],
+        body: Body::Json,
+        admin: true,
+    },
+    Operation {
+        method: "post",
+        path: "/api/admin/submissions/{id}/review",
+        tag: "community",
+        summary: "Approves, rejects or merges a submission",
+        parameters: &[path("id", "")],
+        body: Body::Json,
+        admin: true,
+    },
+    Operation {
+        method: "put",
+        path: "/api/admin/flags/{country}/{establishment_id}",
+        tag: "community",
+        summary: "Flags a location as closed, suspended or incorrect",
+        parameters: &[path("country", ""), path("establishment_id", "")],
+        body: Body::Json,
+        admin: true,
+    },
+    Operation {
+        method: "delete",
+        path: "/api/admin/flags/{country}/{establishment_id}",
+        tag: "community",
+        summary: "Clears a location's flag",
+        parameters: &[path("country", ""), path("establishment_id", "")],
+        body: Body::Json,
+        admin: true,
+    },
+    Operation {
+        method: "get",
+        path: "/api/admin/residences",
+        tag: "community",
+        summary: "APHIS licensees marked as private residences",
+        parameters: &[],
+        body: Body::Json,
+        admin: true,
+    },
+    Operation {
+        method: "put",
+        path: "/api/admin/residences/{facility_id}",
+        tag: "community",
+        summary: "Marks a licensee as a residence, served at its ZIP code without a street",
+        parameters: &[path("facility_id", "")],
+        body: Body::Json,
+        admin: true,
+    },
+    Operation {
+        method: "delete",
+        path: "/api/admin/residences/{facility_id}",
+        tag: "community",
+        summary: "Unmarks a residence",
+        parameters: &[path("facility_id", "")],
+        body: Body::Json,
+        admin: true,
+    },
+    Operation {
+        method: "get",
+        path: "/api/admin/audit",
+        tag: "community",
+        summary: "Moderation actions, newest first",
+        parameters: &[],
+        body: Body::Json,
+        admin: true,
+    },
+    get("/api/jobs", "admin", "Background jobs", &[], Body::Json),
+    get(
+        "/api/jobs/{id}",
+        "admin",
+        "One background job",
+        &[path("id", "")],
+        Body::Json,
+    ),
+    Operation {
+        method: "post",
+        path: "/api/admin/reload",
+        tag: "admin",
+        summary: "Snapshots each changed location list and loads the data source again",
+        parameters: &[],
+        body: Body::Json,
+        admin: true,
+    },
+    Operation {
+        method: "post",
+        path: "/admin/geocode",
+        tag: "admin",
+        summary: "Starts a geocoding job for locations without coordinates",
+        parameters: &[],
+        body: Body::Json,
+        admin: true,
+    },
+];
+
+fn parameter(parameter: &Parameter) -> Value {
+    let mut value = json!({
+        "name": parameter.name,
+        "in": if parameter.location == In::Path { "path" } else { "query" },
+        "required": parameter.required,
+        "schema": { "type": parameter.kind },
+    });
+    if !parameter.description.is_empty() {
+        value["description"] = json!(parameter.description);
+    }
+    value
+}
+
+fn operation(op: &Operation) -> Value {
+    let content = match op.body {
+        Body::Json => json!({ "application/json": {} }),
+        Body::List => json!({
+            "application/json": {},
+            "application/geo+json": {},
+            "text/csv": {},
+        }),
+        Body::Binary(content_type) => json!({ content_type: {} }),
+    };
+    let mut ok = json!({ "description": "OK", "content": content });
+    if let Body::List = op.body {
+        ok["headers"] = json!({
+            "X-Total-Count": {
+                "description": "Items matching before paging.",
+                "schema": { "type": "integer" },
+            }
+        });
+    }
+    let mut value = json!({
+        "tags": [op.tag],
+        "summary": op.summary,
+        "parameters": op.parameters.iter().map(parameter).collect::<Vec<_>>(),
+        "responses": {
+            "200": ok,
+            "4XX": {
+                "description": "A problem with the request, explained in plain text.",
+                "content": { "text/plain": {} },
+            },
+            "500": {
+                "description": "A failure on our side.",
+                "content": {
+                    "application/json": {
+                        "schema": { "$ref": "#/components/schemas/ApiError" }
+                    }
+                },
+            },
+        },
+    });
+    if op.admin {
+        value["security"] = json!([{ "admin": [] }]);
+    }
+    value
+}
+
+fn spec() -> Value {
+    let mut paths = Map::new();
+    for op in OPERATIONS {
+        let item = paths
+            .entry(op.path.to_string())
+            .or_insert_with(|| json!({}));
+        item[op.method] = operation(op);
+    }
+    json!({
+        "openapi": "3.1.0",
+        "info": {
+            "title": "Until Every Cage is Empty",
+            "version": env!("CARGO_PKG_VERSION"),
+            "license": {
+                "name": "AGPL-3.0-or-later",
+                "identifier": "AGPL-3.0-or-later",
+            },
+        },
+        "paths": paths,
+        "components": {
+            "schemas": {
+                "ApiError": {
+                    "type": "object",
+                    "required": ["status", "error", "message"],
+                    "properties": {
+                        "status": { "type": "integer" },
+                        "error": { "type": "string", "examples": ["internal"] },
+                        "message": { "type": "string" },
+                    },
+                },
+            },
+            "securitySchemes": {
+                "admin": { "type": "http", "scheme": "bearer" },
+            },
+        },
+    })
+}
+
+/// GET /api/openapi.json
+pub async fn get_openapi_handler() -> impl IntoResponse {
+    Json(spec())
+}
+
+const SWAGGER_UI: &str = r##"<!DOCTYPE html>
+<html lang="en">
+<head>
+  <meta charset="utf-8">
+  <title>Until Every Cage is Empty API</title>
+  <link rel="stylesheet" href="https://unpkg.com/swagger-ui-dist@5/swagger-ui.css">
+</head>
+<body>
+  <div id="swagger-ui"></div>
+  <script src="https://unpkg.com/swagger-ui-dist@5/swagger-ui-bundle.js"></script>
+  <script>
+    SwaggerUIBundle({ url: "/api/openapi.json", dom_id: "#swagger-ui" });
+  </script>
+</body>
+</html>
+"##;
+
+/// GET /api/docs: Swagger UI for the specification.
+pub async fn get_docs_handler() -> impl IntoResponse {
+    (
+        [(header::CACHE_CONTROL, "public, max-age=3600")],
+        Html(SWAGGER_UI),
+    )
+}
@@ -0,0 +1,106 @@
+// Until Every Cage is Empty
+// Copyright (C) 2025 Eli Perez
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+// Contact the developer directly at untileverycageproject@protonmail.com
+use axum::http::{HeaderName, HeaderValue};
+use serde_json::{Map, Value};
+
+/// Total number of matching items before `limit`/`offset` were applied.
+pub const TOTAL_COUNT_HEADER: HeaderName = HeaderName::from_static("x-total-count");
+
+/// The `limit`, `offset` and `fields` query parameters of a list endpoint.
+/// Without them the whole list is returned, as before they existed.
+#[derive(Debug, Default)]
+pub(crate) struct Page {
+    offset: usize,
+    limit: Option<usize>,
+    fields: Option<Vec<String>>,
+}
+
+impl Page {
+    /// `fields` is a comma-separated list of the fields to keep.
+    pub(crate) fn parse(
+        limit: Option<usize>,
+        offset: Option<usize>,
+        fields: Option<&str>,
+    ) -> Result<Self, String> {
+        if limit == Some(0) {
+            return Err("limit must be at least 1".to_string());
+        }
+        let fields = fields.map(|fields| {
+            fields
+                .split(',')
+                .map(|field| field.trim().to_string())
+                .filter(|field| !field.is_empty())
+                .collect::<Vec<_>>()
+        });
+        if fields.as_ref().is_some_and(|fields| fields.is_empty()) {
+            return Err("fields must name at least one field".to_string());
+        }
+        Ok(Page {
+            offset: offset.unwrap_or(0),
+            limit,
+            fields,
+        })
+    }
+
+    /// The requested slice of `items`, empty past the end.
+    pub(crate) fn window<'a, T>(&self, items: &'a [T]) -> &'a [T] {
+        let start = self.offset.min(items.len());
+        let end = match self.limit {
+            Some(limit) => start.saturating_add(limit).min(items.len()),
+            None => items.len(),
+        };
+        &items[start..end]
+    }
+
+    /// Whether every item is returned with every field.
+    pub(crate) fn is_whole(&self) -> bool {
+        self.offset == 0 && self.limit.is_none() && self.fields.is_none()
+    }
+
+    pub(crate) fn has_fields(&self) -> bool {
+        self.fields.is_some()
+    }
+
+    /// Fails on a field `sample` (a serialized item) doesn't have, so typos
+    /// don't silently return empty objects.
+    pub(crate) fn check_fields(&self, sample: &Map<String, Value>) -> Result<(), String> {
+        let unknown: Vec<&str> = self
+            .fields
+            .iter()
+            .flatten()
+            .filter(|field| !sample.contains_key(field.as_str()))
+            .map(String::as_str)
+            .collect();
+        if unknown.is_empty() {
+            Ok(())
+        } else {
+            Err(format!("Unknown fields {}", unknown.join(", ")))
+        }
+    }
+
+    /// Drops the fields that weren't asked for.
+    pub(crate) fn select(&self, object: &mut Map<String, Value>) {
+        if let Some(fields) = &self.fields {
+            object.retain(|key, _| fields.contains(key));
+        }
+    }
+
+    pub(crate) fn total_count_header(total: usize) -> (HeaderName, HeaderValue) {
+        (TOTAL_COUNT_HEADER, HeaderValue::from(total))
+    }
+}
@@ -0,0 +1,779 @@
+// Until Every Cage is Empty
+// Copyright (C) 2025 Eli Perez
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+// Contact the developer directly at untileverycageproject@protonmail.com
+//! Text out of the PDFs agencies publish their reports as, line by line in
+//! the order it is drawn.
+//!
+//! Enough of PDF for generated reports: objects and object streams,
+//! FlateDecode, the page tree, form XObjects, the text operators of content
+//! streams and fonts' ToUnicode maps. Pages are read in the order the page
+//! tree lists them, each with the fonts its own resources name. Fonts
+//! without a ToUnicode map are read as single-byte Latin-1. Encrypted files
+//! and scanned pages have no text to give.
+
+use std::collections::{HashMap, HashSet};
+use std::error::Error;
+use std::io::Read;
+use std::rc::Rc;
+
+/// A TJ adjustment at least this wide (thousandths of an em) is a space.
+const SPACE_ADJUSTMENT: f64 = 200.0;
+
+/// Form XObjects drawn inside form XObjects deeper than this are left out,
+/// which also ends forms that draw themselves.
+const MAX_FORM_DEPTH: usize = 8;
+
+/// One `N G obj ... endobj`: the dictionary, and the decoded stream if any.
+struct Object {
+    dict: String,
+    stream: Option<Vec<u8>>,
+}
+
+/// The lines of text in the document.
+pub fn extract_text(bytes: &[u8]) -> Result<Vec<String>, Box<dyn Error>> {
+    if find(bytes, b"/Encrypt", 0).is_some() {
+        return Err("the PDF is encrypted".into());
+    }
+    let objects = objects(bytes);
+    let pages = pages(bytes, &objects);
+    if pages.is_empty() {
+        return Err("the PDF has no page tree".into());
+    }
+
+    let mut reader = TextReader {
+        objects: &objects,
+        fonts: HashMap::new(),
+    };
+    let mut lines = Vec::new();
+    for page in pages {
+        // A page's content streams are one stream split up, so an operator
+        // may start in one and end in the next.
+        let mut content = Vec::new();
+        for number in page.contents {
+            if let Some(stream) = objects
+                .get(&number)
+                .and_then(|object| object.stream.as_ref())
+            {
+                content.extend_from_slice(stream);
+                content.push(b'\n');
+            }
+        }
+        lines.extend(reader.text(&content, page.resources, 0));
+    }
+    Ok(lines)
+}
+
+fn find(haystack: &[u8], needle: &[u8], from: usize) -> Option<usize> {
+    haystack
+        .get(from..)?
+        .windows(needle.len())
+        .position(|window| window == needle)
+        .map(|i| from + i)
+}
+
+/// Every object in the file, those in object streams included.
+fn objects(bytes: &[u8]) -> HashMap<u32, Object> {
+    let mut objects = HashMap::new();
+    let mut at = 0;
+    while let Some(start) = find(bytes, b" obj", at) {
+        at = start + 4;
+        let Some(number) = object_number(bytes, start) else {
+            continue;
+        };
+        let end = find(bytes, b"endobj", at).unwrap_or(bytes.len());
+        let body = &bytes[at..end];
+        let object = match find(body, b"stream", 0) {
+            Some(stream_start) if !body[..stream_start].ends_with(b"end") => {
+                let dict = String::from_utf8_lossy(&body[..stream_start]).to_string();
+                let mut data_start = stream_start + b"stream".len();
+                if body.get(data_start) == Some(&b'\r') {
+                    data_start += 1;
+                }
+                if body.get(data_start) == Some(&b'\n') {
+                    data_start += 1;
+                }
+                let data_end = find(body, b"endstream", data_start).unwrap_or(body.len());
+                let stream = decode_stream(&dict, &body[data_start..data_end]);
+                Object { dict, stream }
+            }
+            _ => Object {
+                dict: String::from_utf8_lossy(body).to_string(),
+                stream: None,
+            },
+        };
+        if object.dict.contains("/ObjStm")
+            && let Some(stream) = &object.stream
+        {
+            unpack_object_stream(&object.dict, stream, &mut objects);
+        }
+        objects.insert(number, object);
+        at = end;
+    }
+    objects
+}
+
+/// The object number of the `N G obj` whose " obj" is at `at`.
+fn object_number(bytes: &[u8], at: usize) -> Option<u32> {
+    let before = &bytes[at.saturating_sub(24)..at];
+    let text = String::from_utf8_lossy(before);
+    let mut words = text.split_whitespace().rev();
+    let generation = words.next()?;
+    let number = words.next()?;
+    generation.parse::<u32>().ok()?;
+    number.parse().ok()
+}
+
+fn decode_stream(dict: &str, data: &[u8]) -> Option<Vec<u8>> {
+    let data = data.strip_suffix(b"\n").unwrap_or(data);
+    let data = data.strip_suffix(b"\r").unwrap_or(data);
+    if dict.contains("/FlateDecode") {
+        let mut decoded = Vec::new();
+        // Truncated streams still give what was inflated before the error.
+        let _ = flate2::read::ZlibDecoder::new(data).read_to_end(&mut decoded);
+        (!decoded.is_empty()).then_some(decoded)
+    } else if dict.contains("/Filter") {
+        // Image and font encodings carry no text.
+        None
+    } else {
+        Some(data.to_vec())
+    }
+}
+
+/// The integer following `/key` in a dictionary.
+fn dict_int(dict: &str, key: &str) -> Option<usize> {
+    let start = dict.find(key)? + key.len();
+    dict[start..]
+        .split(|c: char| !c.is_ascii_digit() && !c.is_whitespace())
+        .next()?
+        .trim()
+        .parse()
+        .ok()
+}
+
+/// The length of the `open` ... `close` span `text` starts with, nested
+/// spans included.
+fn balanced(text: &str, open: &[u8], close: &[u8]) -> usize {
+    let bytes = text.as_bytes();
+    let mut depth = 0;
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i..].starts_with(open) {
+            depth += 1;
+            i += open.len();
+        } else if bytes[i..].starts_with(close) {
+            depth -= 1;
+            i += close.len();
+            if depth == 0 {
+                return i;
+            }
+        } else {
+            i += 1;
+        }
+    }
+    bytes.len()
+}
+
+/// The value starting at or after `at` in a dictionary's text, as written
+/// (`<< ... >>`, `[ ... ]`, `N G R`, a name or a number), and where it ends.
+fn value_at(dict: &str, at: usize) -> (&str, usize) {
+    let rest = &dict[at..];
+    let start = at + (rest.len() - rest.trim_start().len());
+    let rest = &dict[start..];
+    let word = |text: &str| text.bytes().position(is_delimiter).unwrap_or(text.len());
+    let length = if rest.starts_with("<<") {
+        balanced(rest, b"<<", b">>")
+    } else if rest.starts_with('[') {
+        balanced(rest, b"[", b"]")
+    } else if rest.starts_with('(') {
+        literal_string(rest.as_bytes(), 0).1
+    } else if rest.starts_with('<') {
+        rest.find('>').map_or(rest.len(), |end| end + 1)
+    } else if let Some(name) = rest.strip_prefix('/') {
+        1 + word(name)
+    } else {
+        reference_length(rest).unwrap_or_else(|| word(rest))
+    };
+    (&dict[start..start + length], start + length)
+}
+
+/// The length of the `N G R` `text` starts with, if it does.
+fn reference_length(text: &str) -> Option<usize> {
+    let word = |at: usize| {
+        text[at..]
+            .bytes()
+            .position(is_delimiter)
+            .unwrap_or(text.len() - at)
+    };
+    let mut at = 0;
+    for _ in 0..2 {
+        let length = word(at);
+        text[at..at + length].parse::<u32>().ok()?;
+        at += length;
+        let spaces = text[at..].len() - text[at..].trim_start().len();
+        if spaces == 0 {
+            return None;
+        }
+        at += spaces;
+    }
+    (word(at) == 1 && text[at..].starts_with('R')).then_some(at + 1)
+}
+
+/// The entries of the dictionary `dict` starts with, keys without their `/`.
+fn dict_entries(dict: &str) -> Vec<(&str, &str)> {
+    let mut entries = Vec::new();
+    let Some(open) = dict.find("<<") else {
+        return entries;
+    };
+    let mut at = open + 2;
+    loop {
+        let rest = dict[at..].trim_start();
+        if !rest.starts_with('/') {
+            break;
+        }
+        let key_start = dict.len() - rest.len() + 1;
+        let key_end = key_start
+            + dict[key_start..]
+                .bytes()
+                .position(is_delimiter)
+                .unwrap_or(dict.len() - key_start);
+        let (value, end) = value_at(dict, key_end);
+        entries.push((&dict[key_start..key_end], value));
+        if end == key_end {
+            break;
+        }
+        at = end;
+    }
+    entries
+}
+
+/// The value of `key` in the dictionary `dict` starts with.
+fn dict_get<'a>(dict: &'a str, key: &str) -> Option<&'a str> {
+    dict_entries(dict)
+        .into_iter()
+        .find_map(|(name, value)| (name == key).then_some(value))
+}
+
+/// The objects a value such as `5 0 R` or `[5 0 R 8 0 R]` refers to.
+fn refs(value: &str) -> Vec<u32> {
+    let value = value.replace(['[', ']'], " ");
+    let words: Vec<&str> = value.split_whitespace().collect();
+    words
+        .windows(3)
+        .filter(|window| window[2] == "R" && window[1].parse::<u32>().is_ok())
+        .filter_map(|window| window[0].parse().ok())
+        .collect()
+}
+
+/// The dictionary a value is, or refers to.
+fn resolve<'a>(objects: &'a HashMap<u32, Object>, value: &'a str) -> Option<&'a str> {
+    if value.trim_start().starts_with("<<") {
+        return Some(value);
+    }
+    match refs(value).as_slice() {
+        [number] => objects.get(number).map(|object| object.dict.as_str()),
+        _ => None,
+    }
+}
+
+fn unpack_object_stream(dict: &str, stream: &[u8], objects: &mut HashMap<u32, Object>) {
+    let (Some(count), Some(first)) = (dict_int(dict, "/N"), dict_int(dict, "/First")) else {
+        return;
+    };
+    let Some(header) = stream.get(..first) else {
+        return;
+    };
+    let header = String::from_utf8_lossy(header);
+    let numbers: Vec<usize> = header
+        .split_whitespace()
+        .filter_map(|word| word.parse().ok())
+        .collect();
+    let entries: Vec<(u32, usize)> = numbers
+        .chunks(2)
+        .take(count)
+        .filter_map(|pair| Some((*pair.first()? as u32, first + pair.get(1)?)))
+        .collect();
+    for (i, (number, start)) in entries.iter().enumerate() {
+        let end = entries
+            .get(i + 1)
+            .map_or(stream.len(), |(_, next)| *next)
+            .min(stream.len());
+        if let Some(body) = stream.get(*start..end) {
+            objects.entry(*number).or_insert(Object {
+                dict: String::from_utf8_lossy(body).to_string(),
+                stream: None,
+            });
+        }
+    }
+}
+
+/// How to turn a font's string bytes into text.
+#[derive(Default)]
+struct Font {
+    /// Code bytes to text, from the ToUnicode map.
+    map: HashMap<Vec<u8>, String>,
+    /// Bytes per code: 2 for composite fonts.
+    code_length: usize,
+}
+
+impl Font {
+    fn decode(&self, bytes: &[u8]) -> String {
+        if self.map.is_empty() {
+            return bytes
+                .iter()
+                .filter(|byte| **byte != 0)
+                .map(|byte| *byte as char)
+                .collect();
+        }
+        bytes
+            .chunks(self.code_length.max(1))
+            .map(|code| match self.map.get(code) {
+                Some(text) => text.clone(),
+                None if code.len() == 1 => (code[0] as char).to_string(),
+                None => String::new(),
+            })
+            .collect()
+    }
+}
+
+/// A page: its content streams, and the resources it names fonts and forms
+/// from, its own or those it inherits from the page tree.
+struct Page<'a> {
+    contents: Vec<u32>,
+    resources: Option<&'a str>,
+}
+
+/// The pages in the order the page tree lists them.
+fn pages<'a>(bytes: &[u8], objects: &'a HashMap<u32, Object>) -> Vec<Page<'a>> {
+    // The last trailer (or cross-reference stream) is the latest update's.
+    let trailer_root = bytes
+        .windows(b"/Root".len())
+        .rposition(|window| window == b"/Root")
+        .and_then(|at| {
+            let end = (at + 40).min(bytes.len());
+            let trailer = String::from_utf8_lossy(&bytes[at + b"/Root".len()..end]).to_string();
+            refs(&trailer).first().copied()
+        });
+    let catalog = trailer_root
+        .and_then(|number| objects.get(&number))
+        .map(|object| object.dict.as_str())
+        .or_else(|| {
+            objects
+                .values()
+                .map(|object| object.dict.as_str())
+                .find(|dict| dict_get(dict, "Type") == Some("/Catalog"))
+        });
+    let mut pages = Vec::new();
+    if let Some(root) = catalog.and_then(|catalog| dict_get(catalog, "Pages")) {
+        let mut seen = HashSet::new();
+        for number in refs(root) {
+            page_tree(objects, number, None, &mut seen, &mut pages);
+        }
+    }
+    pages
+}
+
+/// Adds the pages under the page tree node `number` to `pages`.
+fn page_tree<'a>(
+    objects: &'a HashMap<u32, Object>,
+    number: u32,
+    inherited: Option<&'a str>,
+    seen: &mut HashSet<u32>,
+    pages: &mut Vec<Page<'a>>,
+) {
+    if !seen.insert(number) {
+        return;
+    }
+    let Some(node) = objects.get(&number) else {
+        return;
+    };
+    let resources = dict_get(&node.dict, "Resources").or(inherited);
+    match dict_get(&node.dict, "Kids") {
+        Some(kids) => {
+            for kid in refs(kids) {
+                page_tree(objects, kid, resources, seen, pages);
+            }
+        }
+        None => pages.push(Page {
+            contents: dict_get(&node.dict, "Contents")
+                .map(refs)
+                .unwrap_or_default(),
+            resources,
+        }),
+    }
+}
+
+/// Reads content streams' text, keeping the fonts it has read by object.
+struct TextReader<'a> {
+    objects: &'a HashMap<u32, Object>,
+    fonts: HashMap<u32, Rc<Font>>,
+}
+
+impl<'a> TextReader<'a> {
+    /// The entries of a `/Font` or `/XObject` resource dictionary, as
+    /// resource names and the objects they refer to.
+    fn resources(&self, resources: Option<&'a str>, kind: &str) -> Vec<(&'a str, u32)> {
+        let objects = self.objects;
+        resources
+            .and_then(|resources| resolve(objects, resources))
+            .and_then(|resources| dict_get(resources, kind))
+            .and_then(|named| resolve(objects, named))
+            .map(|named| {
+                dict_entries(named)
+                    .into_iter()
+                    .filter_map(|(name, value)| Some((name, *refs(value).first()?)))
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    /// The fonts resources name, by resource name (`F1`).
+    fn fonts(&mut self, resources: Option<&'a str>) -> HashMap<String, Rc<Font>> {
+        let objects = self.objects;
+        self.resources(resources, "Font")
+            .into_iter()
+            .map(|(name, number)| {
+                let font = self.fonts.entry(number).or_insert_with(|| {
+                    let font = objects
+                        .get(&number)
+                        .and_then(|font| dict_get(&font.dict, "ToUnicode"))
+                        .and_then(|cmap| objects.get(refs(cmap).first()?)?.stream.as_deref())
+                        .map(parse_cmap)
+                        .unwrap_or_default();
+                    Rc::new(font)
+                });
+                (name.to_string(), Rc::clone(font))
+            })
+            .collect()
+    }
+
+    /// The text a content stream draws, a line per positioning operator,
+    /// with the fonts and forms of `resources`.
+    fn text(&mut self, stream: &[u8], resources: Option<&'a str>, depth: usize) -> Vec<String> {
+        let fonts = self.fonts(resources);
+        let forms = self.resources(resources, "XObject");
+        let fallback = Rc::new(Font::default());
+        let mut font = Rc::clone(&fallback);
+        let mut lines = Vec::new();
+        let mut line = String::new();
+        let mut operands: Vec<Token> = Vec::new();
+        let mut at = 0;
+
+        let break_line = |line: &mut String, lines: &mut Vec<String>| {
+            let text = line.split_whitespace().collect::<Vec<_>>().join(" ");
+            if !text.is_empty() {
+                lines.push(text);
+            }
+            line.clear();
+        };
+
+        while let Some((token, next)) = next_token(stream, at) {
+            at = next;
+            let Token::Operator(operator) = token else {
+                operands.push(token);
+                continue;
+            };
+            match operator.as_str() {
+                "Tf" => {
+                    if let Some(Token::Name(name)) = operands.first() {
+                        font = Rc::clone(fonts.get(name).unwrap_or(&fallback));
+                    }
+                }
+                "Tj" => {
+                    if let Some(Token::Bytes(bytes)) = operands.last() {
+                        line.push_str(&font.decode(bytes));
+                    }
+                }
+                "'" | "\"" => {
+                    break_line(&mut line, &mut lines);
+                    if let Some(Token::Bytes(bytes)) = operands.last() {
+                        line.push_str(&font.decode(bytes));
+                    }
+                }
+                "TJ" => {
+                    if let Some(Token::Array(items)) = operands.last() {
+                        for item in items {
+                            match item {
+                                Token::Bytes(bytes) => line.push_str(&font.decode(bytes)),
+                                Token::Number(adjustment) if -adjustment >= SPACE_ADJUSTMENT => {
+                                    line.push(' ')
+                                }
+                                _ => {}
+                            }
+                        }
+                    }
+                }
+                "Td" | "TD" | "Tm" | "T*" | "ET" => break_line(&mut line, &mut lines),
+                "Do" => {
+                    let form = match operands.first() {
+                        Some(Token::Name(name)) if depth < MAX_FORM_DEPTH => forms
+                            .iter()
+                            .find(|(form, _)| form == name)
+                            .and_then(|(_, number)| self.objects.get(number)),
+                        _ => None,
+                    };
+                    if let Some(form) = form
+                        && dict_get(&form.dict, "Subtype") == Some("/Form")
+                        && let Some(content) = &form.stream
+                    {
+                        // A form without resources of its own uses the page's.
+                        let form_resources = dict_get(&form.dict, "Resources").or(resources);
+                        break_line(&mut line, &mut lines);
+                        lines.extend(self.text(content, form_resources, depth + 1));
+                    }
+                }
+                "BI" => {
+                    // Inline image data runs to EI.
+                    at = find(stream, b"EI", at).map_or(stream.len(), |end| end + 2);
+                }
+                _ => {}
+            }
+            operands.clear();
+        }
+        break_line(&mut line, &mut lines);
+        lines
+    }
+}
+
+fn hex_bytes(hex: &str) -> Vec<u8> {
+    let digits: Vec<u8> = hex.bytes().filter(u8::is_ascii_hexdigit).collect();
+    digits
+        .chunks(2)
+        .map(|pair| {
+            let text = std::str::from_utf8(pair).unwrap_or("0");
+            u8::from_str_radix(&format!("{:0<2}", text), 16).unwrap_or(0)
+        })
+        .collect()
+}
+
+fn utf16_text(bytes: &[u8]) -> String {
+    let units: Vec<u16> = bytes
+        .chunks(2)
+        .map(|pair| u16::from_be_bytes([pair[0], *pair.get(1).unwrap_or(&0)]))
+        .collect();
+    String::from_utf16_lossy(&units)
+}
+
+/// A ToUnicode CMap's `bfchar` and `bfrange` entries.
+fn parse_cmap(stream: &[u8]) -> Font {
+    let text = String::from_utf8_lossy(stream);
+    let hexes = |section: &str| -> Vec<Vec<u8>> {
+        section
+            .split('<')
+            .skip(1)
+            .filter_map(|part| part.split_once('>'))
+            .map(|(hex, _)| hex_bytes(hex))
+            .collect()
+    };
+    let mut font = Font {
+        code_length: 1,
+        ..Default::default()
+    };
+    for block in text.split("beginbfchar").skip(1) {
+        let block = block.split("endbfchar").next().unwrap_or_default();
+        for pair in hexes(block).chunks(2) {
+            if let [code, unicode] = pair {
+                font.code_length = font.code_length.max(code.len());
+                font.map.insert(code.clone(), utf16_text(unicode));
+            }
+        }
+    }
+    for block in text.split("beginbfrange").skip(1) {
+        let block = block.split("endbfrange").next().unwrap_or_default();
+        for line in block.lines() {
+            // `<lo> <hi> <start>`; ranges mapping to arrays are rare in
+            // generated reports and skipped.
+            let values = hexes(line);
+            let [lo, hi, start] = values.as_slice() else {
+                continue;
+            };
+            if line.contains('[') || lo.len() != hi.len() || lo.is_empty() {
+                continue;
+            }
+            font.code_length = font.code_length.max(lo.len());
+            let as_number = |bytes: &[u8]| bytes.iter().fold(0u32, |n, b| (n << 8) | *b as u32);
+            let (lo_n, hi_n) = (as_number(lo), as_number(hi));
+            let start_units: Vec<u16> = start
+                .chunks(2)
+                .map(|pair| u16::from_be_bytes([pair[0], *pair.get(1).unwrap_or(&0)]))
+                .collect();
+            for (offset, code) in (lo_n..=hi_n.min(lo_n + 0xFFFF)).enumerate() {
+                let key = code.to_be_bytes()[4 - lo.len()..].to_vec();
+                let mut units = start_units.clone();
+                if let Some(last) = units.last_mut() {
+                    *last = last.wrapping_add(offset as u16);
+                }
+                font.map.insert(key, String::from_utf16_lossy(&units));
+            }
+        }
+    }
+    font
+}
+
+enum Token {
+    Number(f64),
+    Name(String),
+    Bytes(Vec<u8>),
+    Array(Vec<Token>),
+    Operator(String),
+}
+
+/// Reads a literal string whose `(` is at `at`, returning it and the
+/// position after its `)`.
+fn literal_string(stream: &[u8], at: usize) -> (Vec<u8>, usize) {
+    let mut bytes = Vec::new();
+    let mut depth = 0;
+    let mut i = at;
+    while i < stream.len() {
+        let byte = stream[i];
+        match byte {
+            b'(' => {
+                if depth > 0 {
+                    bytes.push(byte);
+                }
+                depth += 1;
+            }
+            b')' => {
+                depth -= 1;
+                if depth == 0 {
+                    return (bytes, i + 1);
+                }
+                bytes.push(byte);
+            }
+            b'\\' => {
+                i += 1;
+                let Some(&escaped) = stream.get(i) else {
+                    break;
+                };
+                match escaped {
+                    b'n' => bytes.push(b'\n'),
+                    b'r' => bytes.push(b'\r'),
+                    b't' => bytes.push(b'\t'),
+                    b'b' => bytes.push(8),
+                    b'f' => bytes.push(12),
+                    b'0'..=b'7' => {
+                        let mut value = 0u32;
+                        let mut digits = 0;
+                        while digits < 3 && matches!(stream.get(i), Some(b'0'..=b'7')) {
+                            value = value * 8 + (stream[i] - b'0') as u32;
+                            i += 1;
+                            digits += 1;
+                        }
+                        bytes.push(value as u8);
+                        continue;
+                    }
+                    b'\r' | b'\n' => {}
+                    other => bytes.push(other),
+                }
+            }
+            _ => bytes.push(byte),
+        }
+        i += 1;
+    }
+    (bytes, stream.len())
+}
+
+fn is_delimiter(byte: u8) -> bool {
+    byte.is_ascii_whitespace() || b"()<>[]{}/%".contains(&byte)
+}
+
+/// The next token starting at or after `at`, and where the one after starts.
+fn next_token(stream: &[u8], mut at: usize) -> Option<(Token, usize)> {
+    loop {
+        while at < stream.len() && stream[at].is_ascii_whitespace() {
+            at += 1;
+        }
+        if stream.get(at) == Some(&b'%') {
+            while at < stream.len() && stream[at] != b'\n' && stream[at] != b'\r' {
+                at += 1;
+            }
+            continue;
+        }
+        break;
+    }
+    let byte = *stream.get(at)?;
+    let word_end = |from: usize| {
+        let mut end = from;
+        while end < stream.len() && !is_delimiter(stream[end]) {
+            end += 1;
+        }
+        end
+    };
+    match byte {
+        b'(' => {
+            let (bytes, end) = literal_string(stream, at);
+            Some((Token::Bytes(bytes), end))
+        }
+        b'<' if stream.get(at + 1) == Some(&b'<') => {
+            // Dictionaries only appear as marked-content properties.
+            let mut depth = 0;
+            let mut i = at;
+            while i + 1 < stream.len() {
+                if &stream[i..i + 2] == b"<<" {
+                    depth += 1;
+                    i += 2;
+                } else if &stream[i..i + 2] == b">>" {
+                    depth -= 1;
+                    i += 2;
+                    if depth == 0 {
+                        break;
+                    }
+                } else {
+                    i += 1;
+                }
+            }
+            Some((Token::Operator(String::new()), i))
+        }
+        b'<' => {
+            let end = find(stream, b">", at).unwrap_or(stream.len());
+            let hex = String::from_utf8_lossy(&stream[at + 1..end]);
+            Some((Token::Bytes(hex_bytes(&hex)), end + 1))
+        }
+        b'[' => {
+            let mut items = Vec::new();
+            let mut i = at + 1;
+            loop {
+                let mut j = i;
+                while j < stream.len() && stream[j].is_ascii_whitespace() {
+                    j += 1;
+                }
+                if j >= stream.len() {
+                    return Some((Token::Array(items), j));
+                }
+                if stream[j] == b']' {
+                    return Some((Token::Array(items), j + 1));
+                }
+                let (item, next) = next_token(stream, j)?;
+                items.push(item);
+                i = next;
+            }
+        }
+        b'/' => {
+            let end = word_end(at + 1);
+            let name = String::from_utf8_lossy(&stream[at + 1..end]).to_string();
+            Some((Token::Name(name), end))
+        }
+        b']' | b')' | b'>' | b'{' | b'}' => Some((Token::Operator(String::new()), at + 1)),
+        _ => {
+            let end = word_end(at).max(at + 1);
+            let word = String::from_utf8_lossy(&stream[at..end]).to_string();
+            match word.parse::<f64>() {
+                Ok(number) => Some((Token::Number(number), end)),
+                Err(_) => Some((Token::Operator(word), end)),
+            }
+        }
+    }
+}
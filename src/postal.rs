@@ -0,0 +1,172 @@
+// Until Every Cage is Empty
+// Copyright (C) 2025 Eli Perez
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+// Contact the developer directly at untileverycageproject@protonmail.com
+//! Locations by county FIPS code and by postal code, for organizers who
+//! split their work by county or postal area:
+//! /api/locations/by-fips/{code} and /api/locations/by-zip/{zip}.
+//!
+//! A postal code matches as a prefix, so `by-zip/191` is the ZIP3 area and
+//! `by-zip/19104` also finds ZIP+4 codes. Spaces and dashes are ignored
+//! ("SW1A 1AA" is "SW1A1AA").
+
+use std::collections::{BTreeMap, HashMap};
+
+use axum::extract::{Path, Query, State};
+use axum::http::{HeaderMap, StatusCode};
+use axum::response::IntoResponse;
+use serde::Deserialize;
+
+use crate::choropleth;
+use crate::geocode;
+use crate::geojson;
+use crate::paging::Page;
+use crate::{AppState, LocationResponse};
+
+/// Shortest postal code prefix looked up, a US ZIP3.
+const MIN_ZIP_PREFIX: usize = 3;
+
+/// Positions in `LocationIndex::locations` by county FIPS code and postal
+/// code, built with the index.
+#[derive(Default)]
+pub(crate) struct PostalIndex {
+    fips: HashMap<String, Vec<usize>>,
+    zips: BTreeMap<String, Vec<usize>>,
+}
+
+/// A postal code uppercased without spaces or dashes.
+fn zip_key(zip: &str) -> String {
+    zip.chars()
+        .filter(|c| !c.is_whitespace() && *c != '-')
+        .map(|c| c.to_ascii_uppercase())
+        .collect()
+}
+
+impl PostalIndex {
+    pub(crate) fn build(locations: &[LocationResponse]) -> Self {
+        let mut index = PostalIndex::default();
+        for (i, loc) in locations.iter().enumerate() {
+            if !loc.fips_code.is_empty() {
+                index.fips.entry(loc.fips_code.clone()).or_default().push(i);
+            }
+            let zip = zip_key(&loc.zip);
+            if !zip.is_empty() {
+                index.zips.entry(zip).or_default().push(i);
+            }
+        }
+        index
+    }
+
+    /// Positions of the locations in the county, in dataset order.
+    pub(crate) fn in_county(&self, fips: &str) -> &[usize] {
+        self.fips.get(fips).map(Vec::as_slice).unwrap_or_default()
+    }
+
+    /// Positions of the locations whose postal code starts with `prefix`, in
+    /// dataset order.
+    pub(crate) fn with_zip_prefix(&self, prefix: &str) -> Vec<usize> {
+        let mut found: Vec<usize> = self
+            .zips
+            .range(prefix.to_string()..)
+            .take_while(|(zip, _)| zip.starts_with(prefix))
+            .flat_map(|(_, positions)| positions.iter().copied())
+            .collect();
+        found.sort_unstable();
+        found
+    }
+}
+
+#[derive(Deserialize)]
+pub struct AreaParams {
+    #[serde(alias = "country")]
+    country_code: Option<String>,
+    /// `json` (the default) or `geojson`.
+    format: Option<String>,
+    limit: Option<usize>,
+    offset: Option<usize>,
+    fields: Option<String>,
+}
+
+fn respond(
+    state: &AppState,
+    positions: impl Fn(&PostalIndex) -> Vec<usize>,
+    headers: &HeaderMap,
+    params: &AreaParams,
+) -> axum::response::Response {
+    let page = match Page::parse(params.limit, params.offset, params.fields.as_deref()) {
+        Ok(page) => page,
+        Err(e) => return (StatusCode::BAD_REQUEST, e).into_response(),
+    };
+    let country = params.country_code.as_deref().map(geocode::country_code);
+    let index = state.location_index();
+    let locations: Vec<&LocationResponse> = positions(&index.postal)
+        .into_iter()
+        .map(|i| &index.locations[i])
+        .filter(|loc| {
+            country
+                .as_deref()
+                .is_none_or(|country| loc.country == country)
+        })
+        .collect();
+    geojson::respond(&locations, headers, params.format.as_deref(), &page)
+}
+
+/// GET /api/locations/by-fips/{code}: the locations in a US county.
+pub async fn get_locations_by_fips_handler(
+    State(state): State<AppState>,
+    Path(code): Path<String>,
+    headers: HeaderMap,
+    Query(params): Query<AreaParams>,
+) -> impl IntoResponse {
+    let fips = choropleth::normalize_fips(&code);
+    if fips.is_empty() {
+        return (
+            StatusCode::BAD_REQUEST,
+            format!("{} is not a five-digit county FIPS code", code),
+        )
+            .into_response();
+    }
+    respond(
+        &state,
+        |postal| postal.in_county(&fips).to_vec(),
+        &headers,
+        &params,
+    )
+}
+
+/// GET /api/locations/by-zip/{zip}: the locations whose postal code starts
+/// with `zip`.
+pub async fn get_locations_by_zip_handler(
+    State(state): State<AppState>,
+    Path(zip): Path<String>,
+    headers: HeaderMap,
+    Query(params): Query<AreaParams>,
+) -> impl IntoResponse {
+    let prefix = zip_key(&zip);
+    if prefix.chars().count() < MIN_ZIP_PREFIX {
+        return (
+            StatusCode::BAD_REQUEST,
+            format!("A postal code needs at least {} characters", MIN_ZIP_PREFIX),
+        )
+            .into_response();
+    }
+    respond(
+        &state,
+        |postal| postal.with_zip_prefix(&prefix),
+        &headers,
+        &params,
+    )
+}
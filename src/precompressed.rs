@@ -0,0 +1,215 @@
+// Until Every Cage is Empty
+// Copyright (C) 2025 Eli Perez
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+// Contact the developer directly at untileverycageproject@protonmail.com
+//! The full-dataset responses, compressed once per data revision instead of
+//! on every request.
+//!
+//! They are the largest responses and the ones every map load asks for, so
+//! they are compressed harder than the compression layer does per request
+//! and kept until the index is rebuilt. Everything else, and these routes
+//! with a query string, goes through the compression layer as usual, which
+//! skips responses that already have a `Content-Encoding`.
+
+use std::collections::HashMap;
+use std::io::Write;
+use std::sync::{Arc, RwLock};
+
+use axum::body::{Body, Bytes};
+use axum::extract::{Request, State};
+use axum::http::{HeaderMap, HeaderValue, Method, StatusCode, header};
+use axum::middleware::Next;
+use axum::response::{IntoResponse, Response};
+
+use crate::AppState;
+
+/// Routes whose unfiltered response is the whole dataset.
+const PRECOMPRESSED: &[&str] = &[
+    "/api/locations",
+    "/api/facilities",
+    "/api/aphis-reports",
+    "/api/inspection-reports",
+    "/api/aquaculture",
+    "/api/fur-farms",
+    "/api/egg-farms",
+    "/api/cafos",
+    "/api/equine",
+];
+
+/// Entries kept before the cache starts over, as `Accept` varies the key.
+const CACHE_CAPACITY: usize = 64;
+
+const BROTLI_QUALITY: u32 = 9;
+const BROTLI_WINDOW: u32 = 22;
+const ZSTD_LEVEL: i32 = 15;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub(crate) enum Encoding {
+    Brotli,
+    Zstd,
+    Gzip,
+}
+
+impl Encoding {
+    /// In order of preference when a client accepts several equally.
+    const ALL: [Encoding; 3] = [Encoding::Brotli, Encoding::Zstd, Encoding::Gzip];
+
+    fn name(self) -> &'static str {
+        match self {
+            Encoding::Brotli => "br",
+            Encoding::Zstd => "zstd",
+            Encoding::Gzip => "gzip",
+        }
+    }
+
+    /// The encoding to use for an `Accept-Encoding` value, by q-value.
+    fn negotiate(accept_encoding: &str) -> Option<Encoding> {
+        let mut best: Option<(Encoding, f32)> = None;
+        for encoding in Encoding::ALL {
+            let q = accept_encoding
+                .split(',')
+                .filter_map(|item| {
+                    let mut parts = item.split(';');
+                    let name = parts.next()?.trim();
+                    if !name.eq_ignore_ascii_case(encoding.name()) {
+                        return None;
+                    }
+                    Some(
+                        parts
+                            .find_map(|param| param.trim().strip_prefix("q="))
+                            .map_or(1.0, |q| q.trim().parse().unwrap_or(0.0)),
+                    )
+                })
+                .next()
+                .unwrap_or(0.0);
+            if q > 0.0 && best.is_none_or(|(_, best_q)| q > best_q) {
+                best = Some((encoding, q));
+            }
+        }
+        best.map(|(encoding, _)| encoding)
+    }
+
+    fn compress(self, body: &[u8]) -> std::io::Result<Vec<u8>> {
+        match self {
+            Encoding::Brotli => {
+                let mut out = Vec::new();
+                {
+                    let mut writer = brotli::CompressorWriter::new(
+                        &mut out,
+                        4096,
+                        BROTLI_QUALITY,
+                        BROTLI_WINDOW,
+                    );
+                    writer.write_all(body)?;
+                }
+                Ok(out)
+            }
+            Encoding::Zstd => zstd::encode_all(body, ZSTD_LEVEL),
+            Encoding::Gzip => {
+                let mut encoder =
+                    flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::best());
+                encoder.write_all(body)?;
+                encoder.finish()
+            }
+        }
+    }
+}
+
+#[derive(Clone)]
+pub(crate) struct Compressed {
+    headers: HeaderMap,
+    body: Bytes,
+}
+
+/// Compressed responses keyed by path, `Accept` and encoding, emptied
+/// whenever the index is rebuilt.
+pub(crate) type PrecompressedCache =
+    Arc<RwLock<HashMap<(String, Option<HeaderValue>, Encoding), Compressed>>>;
+
+fn respond(compressed: Compressed) -> Response {
+    let mut response = (StatusCode::OK, compressed.body).into_response();
+    *response.headers_mut() = compressed.headers;
+    response
+}
+
+/// Serves the full-dataset routes from the cache, compressing and storing
+/// the handler's response on a miss.
+pub async fn precompress(State(state): State<AppState>, request: Request, next: Next) -> Response {
+    let path = request.uri().path().to_string();
+    let encoding = request
+        .headers()
+        .get(header::ACCEPT_ENCODING)
+        .and_then(|value| value.to_str().ok())
+        .and_then(Encoding::negotiate);
+    let (Some(encoding), Method::GET, None, true) = (
+        encoding,
+        request.method().clone(),
+        request.uri().query(),
+        PRECOMPRESSED.contains(&path.as_str()),
+    ) else {
+        return next.run(request).await;
+    };
+    let key = (
+        path,
+        request.headers().get(header::ACCEPT).cloned(),
+        encoding,
+    );
+
+    let cached = state.precompressed.read().unwrap().get(&key).cloned();
+    if let Some(compressed) = cached {
+        return respond(compressed);
+    }
+
+    let response = next.run(request).await;
+    if response.status() != StatusCode::OK
+        || response.headers().contains_key(header::CONTENT_ENCODING)
+    {
+        return response;
+    }
+    let (parts, body) = response.into_parts();
+    let Ok(bytes) = axum::body::to_bytes(body, usize::MAX).await else {
+        return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+    };
+    let original = bytes.clone();
+    let compressed =
+        tokio::task::spawn_blocking(move || encoding.compress(&bytes).map(Bytes::from))
+            .await
+            .ok()
+            .and_then(Result::ok);
+    let Some(body) = compressed else {
+        // Served uncompressed rather than failing the request.
+        return Response::from_parts(parts, Body::from(original));
+    };
+
+    let mut headers = parts.headers;
+    headers.insert(
+        header::CONTENT_ENCODING,
+        HeaderValue::from_static(encoding.name()),
+    );
+    headers.remove(header::CONTENT_LENGTH);
+    headers.insert(
+        header::VARY,
+        HeaderValue::from_static("accept, accept-encoding"),
+    );
+    let compressed = Compressed { headers, body };
+    let mut cache = state.precompressed.write().unwrap();
+    if cache.len() >= CACHE_CAPACITY {
+        cache.clear();
+    }
+    cache.insert(key, compressed.clone());
+    drop(cache);
+    respond(compressed)
+}
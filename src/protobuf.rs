@@ -0,0 +1,205 @@
+// Until Every Cage is Empty
+// Copyright (C) 2025 Eli Perez
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+// Contact the developer directly at untileverycageproject@protonmail.com
+//! The facility list as Protocol Buffers (`?format=pb`), for the mobile
+//! client: a fraction of the JSON's size and quicker to parse on a phone.
+//!
+//! The messages follow `proto/facilities.proto`, which clients generate
+//! their code from; a field added there is added here with the same number.
+
+use std::borrow::Borrow;
+
+use axum::http::{HeaderMap, HeaderValue, StatusCode, header};
+use axum::response::{IntoResponse, Response};
+use prost::Message;
+
+use crate::facilities::{Facility, Source};
+use crate::paging::Page;
+use crate::status::FacilityStatus;
+
+pub(crate) const PROTOBUF_CONTENT_TYPE: &str = "application/x-protobuf";
+
+#[derive(Clone, PartialEq, Message)]
+struct FacilityList {
+    #[prost(message, repeated, tag = "1")]
+    facilities: Vec<FacilityMessage>,
+    #[prost(uint64, tag = "2")]
+    total: u64,
+}
+
+#[derive(Clone, PartialEq, Message)]
+struct FacilityMessage {
+    #[prost(string, tag = "1")]
+    id: String,
+    #[prost(string, tag = "2")]
+    source_id: String,
+    #[prost(enumeration = "SourceMessage", tag = "3")]
+    source: i32,
+    #[prost(string, tag = "4")]
+    name: String,
+    #[prost(sint32, tag = "5")]
+    latitude_e6: i32,
+    #[prost(sint32, tag = "6")]
+    longitude_e6: i32,
+    #[prost(string, tag = "7")]
+    country: String,
+    #[prost(string, tag = "8")]
+    state: String,
+    #[prost(string, tag = "9")]
+    city: String,
+    #[prost(string, tag = "10")]
+    street: String,
+    #[prost(string, tag = "11")]
+    zip: String,
+    #[prost(string, tag = "12")]
+    facility_type: String,
+    #[prost(string, repeated, tag = "13")]
+    activities: Vec<String>,
+    #[prost(string, repeated, tag = "14")]
+    species: Vec<String>,
+    #[prost(enumeration = "StatusMessage", tag = "15")]
+    status: i32,
+    #[prost(bool, tag = "16")]
+    is_residential: bool,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, prost::Enumeration)]
+#[repr(i32)]
+enum SourceMessage {
+    Unspecified = 0,
+    Locations = 1,
+    AphisReports = 2,
+    InspectionReports = 3,
+    Aquaculture = 4,
+    FurFarms = 5,
+    EggFarms = 6,
+    Cafos = 7,
+    Equine = 8,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, prost::Enumeration)]
+#[repr(i32)]
+enum StatusMessage {
+    Unspecified = 0,
+    Active = 1,
+    Closed = 2,
+    Suspended = 3,
+}
+
+impl From<Source> for SourceMessage {
+    fn from(source: Source) -> Self {
+        match source {
+            Source::Locations => SourceMessage::Locations,
+            Source::AphisReports => SourceMessage::AphisReports,
+            Source::InspectionReports => SourceMessage::InspectionReports,
+            Source::Aquaculture => SourceMessage::Aquaculture,
+            Source::FurFarms => SourceMessage::FurFarms,
+            Source::EggFarms => SourceMessage::EggFarms,
+            Source::Cafos => SourceMessage::Cafos,
+            Source::Equine => SourceMessage::Equine,
+        }
+    }
+}
+
+impl From<FacilityStatus> for StatusMessage {
+    fn from(status: FacilityStatus) -> Self {
+        match status {
+            FacilityStatus::Active => StatusMessage::Active,
+            FacilityStatus::Closed => StatusMessage::Closed,
+            FacilityStatus::Suspended => StatusMessage::Suspended,
+        }
+    }
+}
+
+/// Degrees in millionths, about 11 cm.
+fn micro_degrees(degrees: f64) -> i32 {
+    (degrees * 1e6).round() as i32
+}
+
+impl From<&Facility> for FacilityMessage {
+    fn from(facility: &Facility) -> Self {
+        FacilityMessage {
+            id: facility.id.clone(),
+            source_id: facility.source_id.clone(),
+            source: SourceMessage::from(facility.source).into(),
+            name: facility.name.clone(),
+            latitude_e6: micro_degrees(facility.latitude),
+            longitude_e6: micro_degrees(facility.longitude),
+            country: facility.country.clone(),
+            state: facility.state.clone(),
+            city: facility.city.clone(),
+            street: facility.street.clone(),
+            zip: facility.zip.clone(),
+            facility_type: facility.facility_type.clone(),
+            activities: facility
+                .activities
+                .iter()
+                .map(|activity| activity.id().to_string())
+                .collect(),
+            species: facility
+                .species
+                .iter()
+                .map(|species| species.to_string())
+                .collect(),
+            status: StatusMessage::from(facility.status).into(),
+            is_residential: facility.is_residential,
+        }
+    }
+}
+
+/// Whether the client asked for Protocol Buffers with `?format=pb` or
+/// `Accept: application/x-protobuf`. Other formats are left to `geojson`.
+pub(crate) fn wanted(headers: &HeaderMap, format: Option<&str>) -> bool {
+    match format {
+        Some(format) => format == "pb",
+        None => headers
+            .get(header::ACCEPT)
+            .and_then(|value| value.to_str().ok())
+            .is_some_and(|accept| accept.contains(PROTOBUF_CONTENT_TYPE)),
+    }
+}
+
+/// The page of `facilities` as a `FacilityList`.
+pub(crate) fn respond<T: Borrow<Facility>>(facilities: &[T], page: &Page) -> Response {
+    if page.has_fields() {
+        return (
+            StatusCode::BAD_REQUEST,
+            "fields can't be chosen with format=pb".to_string(),
+        )
+            .into_response();
+    }
+    let list = FacilityList {
+        facilities: page
+            .window(facilities)
+            .iter()
+            .map(|facility| FacilityMessage::from(facility.borrow()))
+            .collect(),
+        total: facilities.len() as u64,
+    };
+    (
+        [
+            (header::VARY, HeaderValue::from_static("accept")),
+            Page::total_count_header(facilities.len()),
+            (
+                header::CONTENT_TYPE,
+                HeaderValue::from_static(PROTOBUF_CONTENT_TYPE),
+            ),
+        ],
+        list.encode_to_vec(),
+    )
+        .into_response()
+}
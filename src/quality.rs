@@ -0,0 +1,325 @@
+// Until Every Cage is Empty
+// Copyright (C) 2025 Eli Perez
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+// Contact the developer directly at untileverycageproject@protonmail.com
+//! GET /api/data-quality: what contributors could clean up in the data.
+//!
+//! Besides the coordinate checks done at load time, every CSV dataset is
+//! scanned as it ships: rows, rows without coordinates, values that should be
+//! numbers but aren't, ids used more than once, columns missing or not read,
+//! and when its importer last ran.
+
+use std::collections::{BTreeMap, HashMap};
+
+use axum::Json;
+use axum::extract::State;
+use axum::response::IntoResponse;
+use serde::Serialize;
+
+use crate::aquaculture::AquacultureSite;
+use crate::importers;
+use crate::location::{AphisReport, InspectionReport, Location, PainCategories};
+use crate::schema::{self, Mismatch, Schema};
+use crate::store::Dataset;
+use crate::validation::{CoordinateReport, Policy};
+use crate::{
+    AppState, CafoPermit, EggFarm, EnforcementRecord, EquineSite, FurFarm, InspectionCitation,
+};
+
+/// How many of the duplicated ids a dataset lists.
+const DUPLICATE_EXAMPLES: usize = 20;
+
+const APHIS_COUNTS: &[&str] = &[
+    "Dogs",
+    "Cats",
+    "Guinea Pigs",
+    "Hamsters",
+    "Rabbits",
+    "Non-Human Primates",
+    "Sheep",
+    "Pigs",
+    "Other Farm Animals",
+    "All Other Animals",
+];
+
+/// A CSV dataset in a country directory: a file, or a directory of CSV files
+/// read together.
+struct Checked {
+    path: &'static str,
+    /// Columns that together identify a row. Empty when rows have no id.
+    id_columns: &'static [&'static str],
+    /// Latitude and longitude columns.
+    coordinates: Option<[&'static str; 2]>,
+    /// Columns besides the coordinates that should hold numbers.
+    numbers: &'static [&'static str],
+    /// The columns the server reads from it.
+    schema: fn() -> Schema,
+}
+
+const CHECKED: &[Checked] = &[
+    Checked {
+        path: "locations.csv",
+        id_columns: &["establishment_id"],
+        coordinates: Some(["latitude", "longitude"]),
+        numbers: &[],
+        schema: schema::of::<Location>,
+    },
+    Checked {
+        path: "aphis_data_final.csv",
+        id_columns: &["Certificate Number"],
+        coordinates: Some(["latitude", "longitude"]),
+        numbers: APHIS_COUNTS,
+        schema: schema::of::<AphisReport>,
+    },
+    Checked {
+        path: "aphis_history",
+        id_columns: &["Certificate Number", "Year"],
+        coordinates: Some(["latitude", "longitude"]),
+        numbers: APHIS_COUNTS,
+        schema: schema::of::<AphisReport>,
+    },
+    Checked {
+        path: "aphis_pain_categories.csv",
+        id_columns: &["certificate_number", "year", "species"],
+        coordinates: None,
+        numbers: &["column_b", "column_c", "column_d", "column_e"],
+        schema: schema::of::<PainCategories>,
+    },
+    Checked {
+        path: "inspection_reports.csv",
+        id_columns: &["Certificate Number"],
+        coordinates: Some(["Geocodio Latitude", "Geocodio Longitude"]),
+        numbers: &[],
+        schema: schema::of::<InspectionReport>,
+    },
+    Checked {
+        path: "aquaculture",
+        id_columns: &["site_id"],
+        coordinates: Some(["latitude", "longitude"]),
+        numbers: &[],
+        schema: schema::of::<AquacultureSite>,
+    },
+    Checked {
+        path: "fur_farms",
+        id_columns: &["farm_id"],
+        coordinates: Some(["latitude", "longitude"]),
+        numbers: &["animals"],
+        schema: schema::of::<FurFarm>,
+    },
+    Checked {
+        path: "egg_farms",
+        id_columns: &["farm_id"],
+        coordinates: Some(["latitude", "longitude"]),
+        numbers: &["birds"],
+        schema: schema::of::<EggFarm>,
+    },
+    Checked {
+        path: "cafos",
+        id_columns: &["permit_id"],
+        coordinates: Some(["latitude", "longitude"]),
+        numbers: &[
+            "dairy_cattle",
+            "beef_cattle",
+            "swine",
+            "chickens",
+            "turkeys",
+            "other_animals",
+            "animal_units",
+        ],
+        schema: schema::of::<CafoPermit>,
+    },
+    Checked {
+        path: "equine",
+        id_columns: &["site_id"],
+        coordinates: Some(["latitude", "longitude"]),
+        numbers: &["horses"],
+        schema: schema::of::<EquineSite>,
+    },
+    Checked {
+        path: "citations",
+        id_columns: &[],
+        coordinates: None,
+        numbers: &[],
+        schema: schema::of::<InspectionCitation>,
+    },
+    Checked {
+        path: "enforcement",
+        id_columns: &[],
+        coordinates: None,
+        numbers: &[],
+        schema: schema::of::<EnforcementRecord>,
+    },
+];
+
+#[derive(Serialize)]
+struct DatasetQuality {
+    /// Path in the data directory, such as "us/locations.csv".
+    dataset: String,
+    rows: usize,
+    /// Rows the CSV reader could not read at all.
+    unreadable_rows: usize,
+    /// Columns read by the server that a file lacks, and columns it has that
+    /// nothing reads, e.g. after a rename upstream.
+    missing_columns: Vec<String>,
+    unexpected_columns: Vec<String>,
+    /// Rows with an empty, unparseable or 0, 0 coordinate pair.
+    missing_coordinates: Option<usize>,
+    /// Per column, rows holding something other than a number.
+    unparseable_numbers: BTreeMap<String, usize>,
+    /// Ids used by more than one row.
+    duplicate_ids: usize,
+    duplicate_examples: Vec<String>,
+    /// When an importer last wrote the dataset, if it recorded the run.
+    last_updated: Option<String>,
+}
+
+impl DatasetQuality {
+    fn scan(dataset: &Dataset, country: &str, checked: &Checked) -> Option<DatasetQuality> {
+        let path = format!("{}/{}", country, checked.path);
+        let files: Vec<&[u8]> = match dataset.get(&path) {
+            Some(contents) => vec![contents],
+            None => dataset
+                .files_in(&path)
+                .filter(|(name, _)| name.ends_with(".csv"))
+                .map(|(_, contents)| contents)
+                .collect(),
+        };
+        if files.is_empty() {
+            return None;
+        }
+
+        let mut quality = DatasetQuality {
+            last_updated: importers::last_run(dataset, &path),
+            dataset: path,
+            rows: 0,
+            unreadable_rows: 0,
+            missing_columns: Vec::new(),
+            unexpected_columns: Vec::new(),
+            missing_coordinates: checked.coordinates.map(|_| 0),
+            unparseable_numbers: BTreeMap::new(),
+            duplicate_ids: 0,
+            duplicate_examples: Vec::new(),
+        };
+        let mut ids: HashMap<String, usize> = HashMap::new();
+
+        for contents in files {
+            let mut reader = csv::Reader::from_reader(contents);
+            let Ok(headers) = reader.headers().cloned() else {
+                continue;
+            };
+            let mismatch = Mismatch::new((checked.schema)(), &headers);
+            for (found, all) in [
+                (mismatch.missing, &mut quality.missing_columns),
+                (mismatch.unexpected, &mut quality.unexpected_columns),
+            ] {
+                for column in found {
+                    if !all.contains(&column) {
+                        all.push(column);
+                    }
+                }
+            }
+            let column = |name: &str| headers.iter().position(|header| header.trim() == name);
+            let id_columns: Option<Vec<usize>> =
+                checked.id_columns.iter().map(|name| column(name)).collect();
+            let coordinates = checked
+                .coordinates
+                .and_then(|[lat, lng]| Some((column(lat)?, column(lng)?)));
+            let numbers: Vec<(&str, usize)> = checked
+                .numbers
+                .iter()
+                .chain(checked.coordinates.iter().flatten())
+                .filter_map(|name| Some((*name, column(name)?)))
+                .collect();
+
+            for record in reader.records() {
+                let Ok(record) = record else {
+                    quality.unreadable_rows += 1;
+                    continue;
+                };
+                quality.rows += 1;
+                let field = |i: usize| record.get(i).unwrap_or_default().trim();
+
+                for (name, i) in &numbers {
+                    if !field(*i).is_empty() && field(*i).parse::<f64>().is_err() {
+                        *quality
+                            .unparseable_numbers
+                            .entry(name.to_string())
+                            .or_default() += 1;
+                    }
+                }
+                if let (Some(missing), Some((lat, lng))) =
+                    (quality.missing_coordinates.as_mut(), coordinates)
+                {
+                    match (field(lat).parse::<f64>(), field(lng).parse::<f64>()) {
+                        (Ok(lat), Ok(lng)) if !(lat == 0.0 && lng == 0.0) => {}
+                        _ => *missing += 1,
+                    }
+                }
+                if let Some(id_columns) = &id_columns
+                    && !id_columns.is_empty()
+                {
+                    let id: Vec<&str> = id_columns.iter().map(|i| field(*i)).collect();
+                    if id.iter().all(|part| !part.is_empty()) {
+                        *ids.entry(id.join(" ")).or_default() += 1;
+                    }
+                }
+            }
+        }
+
+        let mut duplicates: Vec<String> = ids
+            .into_iter()
+            .filter(|(_, rows)| *rows > 1)
+            .map(|(id, _)| id)
+            .collect();
+        duplicates.sort();
+        quality.duplicate_ids = duplicates.len();
+        duplicates.truncate(DUPLICATE_EXAMPLES);
+        quality.duplicate_examples = duplicates;
+        Some(quality)
+    }
+}
+
+#[derive(Serialize)]
+struct DataQuality {
+    loaded_at: String,
+    datasets: Vec<DatasetQuality>,
+    /// What was done with records whose coordinates are wrong.
+    coordinate_validation: Policy,
+    coordinates: CoordinateReport,
+}
+
+/// GET /api/data-quality
+pub async fn get_data_quality_handler(State(state): State<AppState>) -> impl IntoResponse {
+    let dataset = state.store.current();
+    let datasets = dataset
+        .countries()
+        .into_iter()
+        .flat_map(|country| {
+            CHECKED
+                .iter()
+                .filter_map(|checked| DatasetQuality::scan(&dataset, country, checked))
+        })
+        .collect();
+
+    let mut coordinates = state.location_index().coordinates.clone();
+    coordinates.merge(&state.reports().coordinates);
+    Json(DataQuality {
+        loaded_at: dataset.loaded_at.clone(),
+        datasets,
+        coordinate_validation: state.validation,
+        coordinates,
+    })
+}
@@ -0,0 +1,262 @@
+// Until Every Cage is Empty
+// Copyright (C) 2025 Eli Perez
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+// Contact the developer directly at untileverycageproject@protonmail.com
+//! Private residences among the APHIS licensees. Many Class A breeders are
+//! licensed at home, so an admin can mark a licensee as a residence: it is
+//! served with `is_residential`, without its street address, and at the
+//! centroid of its ZIP code instead of its own coordinates. The records are
+//! redacted as they load, so every endpoint and export built from them
+//! agrees.
+//!
+//! Centroids come from `us/zip_centroids.csv` (`zip,latitude,longitude`) in
+//! the data, which the `us-zip-centroids` importer writes from the Census
+//! Bureau's ZCTA gazetteer file. A ZIP it doesn't have falls back to the
+//! middle of the 0.1° grid cell the record is in, about 11 km across, so a
+//! residence is never served where it is.
+//!
+//! The marks are kept and audited with the moderation flags.
+
+use std::collections::HashMap;
+use std::error::Error;
+
+use axum::Json;
+use axum::extract::{Path, State};
+use axum::http::{HeaderMap, StatusCode};
+use axum::response::{IntoResponse, Response};
+use serde::{Deserialize, Serialize};
+
+use crate::AppState;
+use crate::admin::authorize;
+use crate::errors::ApiError;
+use crate::location::InspectionReport;
+use crate::moderation::{AuditEntry, now};
+use crate::schema;
+use crate::store::Dataset;
+
+const CENTROIDS_PATH: &str = "us/zip_centroids.csv";
+/// Degrees of the grid a residence without a known ZIP centroid snaps to.
+const GRID: f64 = 0.1;
+
+/// An admin's mark on a licensee, like a moderation `Flag`.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Residence {
+    #[serde(default)]
+    pub note: String,
+    pub flagged_by: String,
+    pub flagged_at: String,
+}
+
+#[derive(Deserialize)]
+struct ZipCentroid {
+    zip: String,
+    latitude: f64,
+    longitude: f64,
+}
+
+/// The first five digits of a ZIP or ZIP+4.
+fn zip5(zip: &str) -> String {
+    zip.trim().chars().take(5).collect()
+}
+
+/// Points by five-digit ZIP.
+#[derive(Default)]
+pub(crate) struct ZipCentroids(HashMap<String, (f64, f64)>);
+
+impl ZipCentroids {
+    /// The centroid table of `dataset`, empty without one.
+    pub(crate) fn read(dataset: &Dataset) -> Result<Self, Box<dyn Error>> {
+        let Some(csv_data) = dataset.get(CENTROIDS_PATH) else {
+            return Ok(ZipCentroids::default());
+        };
+        schema::check::<ZipCentroid>(CENTROIDS_PATH, csv_data)?;
+        let mut centroids = HashMap::new();
+        for result in csv::Reader::from_reader(csv_data).deserialize() {
+            let row: ZipCentroid = result.map_err(|e| format!("{}: {}", CENTROIDS_PATH, e))?;
+            // Spreadsheets drop the leading zeros of New England ZIPs.
+            let zip = format!("{:0>5}", row.zip.trim());
+            centroids.insert(zip, (row.latitude, row.longitude));
+        }
+        Ok(ZipCentroids(centroids))
+    }
+
+    /// Where a residence at `point` in `zip` is shown.
+    fn snap(&self, zip: &str, point: (f64, f64)) -> (f64, f64) {
+        if let Some(centroid) = self.0.get(&zip5(zip)) {
+            return *centroid;
+        }
+        // A record without coordinates has nothing to give away.
+        if point == (0.0, 0.0) {
+            return point;
+        }
+        let middle = |degrees: f64| {
+            let snapped = ((degrees / GRID).floor() + 0.5) * GRID;
+            (snapped * 1e4).round() / 1e4
+        };
+        (middle(point.0), middle(point.1))
+    }
+}
+
+/// Marks the `residences` among `reports`, withholding their street address
+/// and moving them to their ZIP code.
+pub(crate) fn redact(
+    reports: &mut [InspectionReport],
+    residences: &HashMap<String, Residence>,
+    centroids: &ZipCentroids,
+) {
+    if residences.is_empty() {
+        return;
+    }
+    for report in reports {
+        let residential = report
+            .facility_id
+            .as_ref()
+            .is_some_and(|id| residences.contains_key(id));
+        if !residential {
+            continue;
+        }
+        report.is_residential = true;
+        report.address_line_1.clear();
+        report.address_line_2.clear();
+        (report.latitude, report.longitude) =
+            centroids.snap(&report.zip, (report.latitude, report.longitude));
+    }
+}
+
+#[derive(Serialize)]
+struct ResidenceEntry {
+    facility_id: String,
+    #[serde(flatten)]
+    residence: Residence,
+}
+
+/// GET /api/admin/residences: every licensee marked as a residence.
+pub async fn get_residences_handler(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> impl IntoResponse {
+    if let Err(e) = authorize(&state, &headers) {
+        return e.into_response();
+    }
+    let mut entries: Vec<ResidenceEntry> = state
+        .moderation
+        .residences()
+        .into_iter()
+        .map(|(facility_id, residence)| ResidenceEntry {
+            facility_id,
+            residence,
+        })
+        .collect();
+    entries.sort_by(|a, b| a.facility_id.cmp(&b.facility_id));
+    Json(entries).into_response()
+}
+
+#[derive(Deserialize)]
+pub struct ResidenceRequest {
+    #[serde(default)]
+    note: String,
+}
+
+/// PUT /api/admin/residences/{facility_id}
+///
+/// Marks an APHIS licensee, by facility ID, as a private residence.
+pub async fn put_residence_handler(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(facility_id): Path<String>,
+    Json(request): Json<ResidenceRequest>,
+) -> impl IntoResponse {
+    let moderator = match authorize(&state, &headers) {
+        Ok(moderator) => moderator,
+        Err(e) => return e.into_response(),
+    };
+    let licensed = state
+        .reports()
+        .inspections
+        .iter()
+        .any(|report| report.facility_id.as_deref() == Some(facility_id.as_str()));
+    if !licensed {
+        return (
+            StatusCode::NOT_FOUND,
+            format!("No APHIS licensee with facility ID {}", facility_id),
+        )
+            .into_response();
+    }
+
+    let residence = Residence {
+        note: request.note.clone(),
+        flagged_by: moderator.clone(),
+        flagged_at: now(),
+    };
+    let entry = AuditEntry {
+        at: residence.flagged_at.clone(),
+        by: moderator,
+        action: "redact".to_string(),
+        target: format!("facility/{}", facility_id),
+        changes: Vec::new(),
+        note: request.note,
+    };
+    set_residence(&state, &facility_id, Some(residence), entry).await
+}
+
+/// DELETE /api/admin/residences/{facility_id}
+pub async fn delete_residence_handler(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(facility_id): Path<String>,
+) -> impl IntoResponse {
+    let moderator = match authorize(&state, &headers) {
+        Ok(moderator) => moderator,
+        Err(e) => return e.into_response(),
+    };
+    let entry = AuditEntry {
+        at: now(),
+        by: moderator,
+        action: "unredact".to_string(),
+        target: format!("facility/{}", facility_id),
+        changes: Vec::new(),
+        note: String::new(),
+    };
+    set_residence(&state, &facility_id, None, entry).await
+}
+
+async fn set_residence(
+    state: &AppState,
+    facility_id: &str,
+    residence: Option<Residence>,
+    entry: AuditEntry,
+) -> Response {
+    let set = state
+        .moderation
+        .set_residence(facility_id, residence.clone(), entry)
+        .map_err(|e| e.to_string());
+    match set {
+        Ok(None) if residence.is_none() => {
+            return (
+                StatusCode::NOT_FOUND,
+                format!("{} is not marked as a residence", facility_id),
+            )
+                .into_response();
+        }
+        Ok(_) => {}
+        Err(e) => {
+            return ApiError::internal(format!("Failed to save residence: {}", e)).into_response();
+        }
+    }
+    // The licensees are re-read, and everything built from them rebuilt.
+    state.rebuild().await;
+    Json(residence).into_response()
+}
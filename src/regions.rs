@@ -0,0 +1,145 @@
+// Until Every Cage is Empty
+// Copyright (C) 2025 Eli Perez
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+// Contact the developer directly at untileverycageproject@protonmail.com
+use std::error::Error;
+
+use serde::Deserialize;
+
+use crate::settings::Settings;
+
+/// A ring of `[longitude, latitude]` points, GeoJSON style.
+type Ring = Vec<[f64; 2]>;
+
+/// Outer ring followed by any holes.
+type Polygon = Vec<Ring>;
+
+#[derive(Deserialize)]
+struct FeatureCollection {
+    features: Vec<Feature>,
+}
+
+#[derive(Deserialize)]
+struct Feature {
+    #[serde(default)]
+    properties: Properties,
+    geometry: Geometry,
+}
+
+#[derive(Deserialize, Default)]
+struct Properties {
+    #[serde(default)]
+    mode: Mode,
+}
+
+#[derive(Deserialize, Default, PartialEq, Clone, Copy)]
+#[serde(rename_all = "lowercase")]
+enum Mode {
+    #[default]
+    Include,
+    Exclude,
+}
+
+#[derive(Deserialize)]
+#[serde(tag = "type", content = "coordinates")]
+enum Geometry {
+    Polygon(Polygon),
+    MultiPolygon(Vec<Polygon>),
+}
+
+/// Inclusion/exclusion zones limiting which facilities a deployment serves,
+/// e.g. only those inside Italy for an Italy-only instance.
+///
+/// Loaded from the GeoJSON FeatureCollection named by `DEPLOYMENT_REGIONS`.
+/// Each Polygon or MultiPolygon feature has a `mode` property of `include`
+/// (the default) or `exclude`. With any include zones a facility has to lie in
+/// one of them; facilities in an exclude zone are always dropped.
+#[derive(Default)]
+pub struct RegionFilter {
+    include: Vec<Polygon>,
+    exclude: Vec<Polygon>,
+}
+
+impl RegionFilter {
+    pub fn from_settings(settings: &Settings) -> Result<Self, Box<dyn Error>> {
+        match settings.get("DEPLOYMENT_REGIONS") {
+            Some(path) => {
+                let source = std::fs::read_to_string(path)
+                    .map_err(|e| format!("Failed to read {}: {}", path, e))?;
+                Self::from_geojson(&source).map_err(|e| format!("{}: {}", path, e).into())
+            }
+            None => Ok(RegionFilter::default()),
+        }
+    }
+
+    pub fn from_geojson(source: &str) -> Result<Self, Box<dyn Error>> {
+        let collection: FeatureCollection = serde_json::from_str(source)?;
+        let mut filter = RegionFilter::default();
+
+        for feature in collection.features {
+            let polygons = match feature.geometry {
+                Geometry::Polygon(polygon) => vec![polygon],
+                Geometry::MultiPolygon(polygons) => polygons,
+            };
+            if polygons.iter().any(|polygon| polygon.is_empty()) {
+                return Err("polygon without an outer ring".into());
+            }
+            match feature.properties.mode {
+                Mode::Include => filter.include.extend(polygons),
+                Mode::Exclude => filter.exclude.extend(polygons),
+            }
+        }
+        Ok(filter)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.include.is_empty() && self.exclude.is_empty()
+    }
+
+    pub fn allows(&self, latitude: f64, longitude: f64) -> bool {
+        let point = [longitude, latitude];
+        let included = self.include.is_empty()
+            || self
+                .include
+                .iter()
+                .any(|polygon| polygon_contains(polygon, point));
+        included
+            && !self
+                .exclude
+                .iter()
+                .any(|polygon| polygon_contains(polygon, point))
+    }
+}
+
+fn polygon_contains(polygon: &Polygon, point: [f64; 2]) -> bool {
+    let (outer, holes) = polygon.split_first().expect("validated on load");
+    ring_contains(outer, point) && !holes.iter().any(|hole| ring_contains(hole, point))
+}
+
+/// Even-odd ray casting.
+fn ring_contains(ring: &Ring, [x, y]: [f64; 2]) -> bool {
+    let mut inside = false;
+    let mut j = ring.len().wrapping_sub(1);
+    for i in 0..ring.len() {
+        let [xi, yi] = ring[i];
+        let [xj, yj] = ring[j];
+        if (yi > y) != (yj > y) && x < (xj - xi) * (y - yi) / (yj - yi) + xi {
+            inside = !inside;
+        }
+        j = i;
+    }
+    inside
+}
@@ -0,0 +1,163 @@
+// Until Every Cage is Empty
+// Copyright (C) 2025 Eli Perez
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+// Contact the developer directly at untileverycageproject@protonmail.com
+//! Checks a CSV's header against the columns its row struct reads, so a
+//! column renamed upstream is reported by name instead of as a serde error on
+//! some row, or as rows quietly skipped or left empty.
+//!
+//! The columns come from the struct's `Deserialize` impl, so they can't drift
+//! from what is actually read.
+
+use std::cell::Cell;
+use std::error::Error;
+use std::fmt;
+
+use serde::Serialize;
+use serde::de::{self, DeserializeOwned, Deserializer, Visitor};
+
+/// The columns a row struct reads.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Schema {
+    pub(crate) name: &'static str,
+    pub(crate) columns: &'static [&'static str],
+}
+
+/// A deserializer that only records the fields a struct asks for.
+struct Introspect<'a>(&'a Cell<Option<Schema>>);
+
+impl<'de> Deserializer<'de> for Introspect<'_> {
+    type Error = de::value::Error;
+
+    fn deserialize_any<V: Visitor<'de>>(self, _visitor: V) -> Result<V::Value, Self::Error> {
+        Err(de::Error::custom("not a struct"))
+    }
+
+    fn deserialize_struct<V: Visitor<'de>>(
+        self,
+        name: &'static str,
+        fields: &'static [&'static str],
+        _visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        self.0.set(Some(Schema {
+            name,
+            columns: fields,
+        }));
+        Err(de::Error::custom("introspected"))
+    }
+
+    serde::forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+        bytes byte_buf option unit unit_struct newtype_struct seq tuple
+        tuple_struct map enum identifier ignored_any
+    }
+}
+
+/// The columns `T` reads. Structs with a flattened field don't list theirs,
+/// and give no columns.
+pub(crate) fn of<T: DeserializeOwned>() -> Schema {
+    let schema = Cell::new(None);
+    let _ = T::deserialize(Introspect(&schema));
+    schema.get().unwrap_or(Schema {
+        name: std::any::type_name::<T>(),
+        columns: &[],
+    })
+}
+
+/// How a header differs from a schema.
+#[derive(Serialize, Debug, Default, Clone)]
+pub(crate) struct Mismatch {
+    /// Columns the struct reads that the file doesn't have.
+    pub(crate) missing: Vec<String>,
+    /// Columns the file has that nothing reads.
+    pub(crate) unexpected: Vec<String>,
+}
+
+impl Mismatch {
+    pub(crate) fn new(schema: Schema, headers: &csv::StringRecord) -> Mismatch {
+        if schema.columns.is_empty() {
+            return Mismatch::default();
+        }
+        Mismatch {
+            missing: schema
+                .columns
+                .iter()
+                .filter(|column| !headers.iter().any(|header| header == **column))
+                .map(|column| column.to_string())
+                .collect(),
+            unexpected: headers
+                .iter()
+                .filter(|header| !schema.columns.contains(header))
+                .map(str::to_string)
+                .collect(),
+        }
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.missing.is_empty() && self.unexpected.is_empty()
+    }
+}
+
+impl fmt::Display for Mismatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let list = |columns: &[String]| {
+            columns
+                .iter()
+                .map(|column| format!("{:?}", column))
+                .collect::<Vec<_>>()
+                .join(", ")
+        };
+        match (self.missing.is_empty(), self.unexpected.is_empty()) {
+            (false, false) => write!(
+                f,
+                "missing columns {}; unexpected columns {}",
+                list(&self.missing),
+                list(&self.unexpected)
+            ),
+            (false, true) => write!(f, "missing columns {}", list(&self.missing)),
+            (true, false) => write!(f, "unexpected columns {}", list(&self.unexpected)),
+            (true, true) => Ok(()),
+        }
+    }
+}
+
+/// Fails when `contents` can't be read as `T` because of its columns, naming
+/// the ones missing and unexpected. Columns the struct can do without (with
+/// a default) only show up in /api/data-quality.
+pub(crate) fn check<T: DeserializeOwned>(
+    path: &str,
+    contents: &[u8],
+) -> Result<(), Box<dyn Error>> {
+    let mut reader = csv::Reader::from_reader(contents);
+    let headers = reader
+        .headers()
+        .map_err(|e| format!("{}: unreadable header: {}", path, e))?
+        .clone();
+    let schema = of::<T>();
+    let mismatch = Mismatch::new(schema, &headers);
+    if mismatch.is_empty() {
+        return Ok(());
+    }
+    // Whether the missing columns are needed shows on the first row.
+    match reader.deserialize::<T>().next() {
+        Some(Err(e)) => Err(format!(
+            "{}: the columns don't match {}: {} (first row: {})",
+            path, schema.name, mismatch, e
+        )
+        .into()),
+        _ => Ok(()),
+    }
+}
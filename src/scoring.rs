@@ -0,0 +1,165 @@
+// Until Every Cage is Empty
+// Copyright (C) 2025 Eli Perez
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+// Contact the developer directly at untileverycageproject@protonmail.com
+use std::error::Error;
+
+use serde::{Deserialize, Serialize};
+
+use crate::settings::Settings;
+
+/// Relative weights of the priority score components.
+///
+/// Loaded from the TOML file named by `PRIORITY_SCORING_CONFIG`, e.g.
+///
+/// ```toml
+/// volume = 0.5
+/// species = 0.2
+/// violations = 0.2
+/// license = 0.1
+/// ```
+#[derive(Deserialize, Debug, Clone)]
+#[serde(default, deny_unknown_fields)]
+pub struct ScoringConfig {
+    pub volume: f64,
+    pub species: f64,
+    pub violations: f64,
+    pub license: f64,
+}
+
+impl Default for ScoringConfig {
+    fn default() -> Self {
+        ScoringConfig {
+            volume: 0.4,
+            species: 0.3,
+            violations: 0.2,
+            license: 0.1,
+        }
+    }
+}
+
+impl ScoringConfig {
+    pub fn from_settings(settings: &Settings) -> Result<Self, Box<dyn Error>> {
+        let Some(path) = settings.get("PRIORITY_SCORING_CONFIG") else {
+            return Ok(ScoringConfig::default());
+        };
+
+        let config: ScoringConfig = toml::from_str(&std::fs::read_to_string(path)?)
+            .map_err(|e| format!("Invalid scoring config {}: {}", path, e))?;
+
+        let weights = [
+            config.volume,
+            config.species,
+            config.violations,
+            config.license,
+        ];
+        if weights.iter().any(|w| !w.is_finite() || *w < 0.0) || weights.iter().sum::<f64>() == 0.0
+        {
+            return Err(format!(
+                "Invalid scoring config {}: weights must be non-negative and not all zero",
+                path
+            )
+            .into());
+        }
+        Ok(config)
+    }
+
+    pub fn score(&self, inputs: &ScoreInputs) -> PriorityScore {
+        // Each component is normalized to 0..=1 before weighting.
+        let components = [
+            ("volume", self.volume, inputs.volume.clamp(0.0, 1.0)),
+            (
+                "species",
+                self.species,
+                (inputs.species as f64 / 5.0).min(1.0),
+            ),
+            (
+                "violations",
+                self.violations,
+                (inputs.violations as f64 / 10.0).min(1.0),
+            ),
+            (
+                "license",
+                self.license,
+                if inputs.license_active { 1.0 } else { 0.0 },
+            ),
+        ];
+
+        let total_weight: f64 = components.iter().map(|(_, weight, _)| weight).sum();
+        let breakdown: Vec<ScoreComponent> = components
+            .iter()
+            .map(|(name, weight, value)| ScoreComponent {
+                component: name.to_string(),
+                value: *value,
+                weight: *weight,
+                contribution: round2(100.0 * weight * value / total_weight),
+            })
+            .collect();
+
+        PriorityScore {
+            score: round2(breakdown.iter().map(|c| c.contribution).sum()),
+            breakdown,
+        }
+    }
+}
+
+/// Facility facts the priority score is computed from.
+#[derive(Debug, Default)]
+pub struct ScoreInputs {
+    /// Scale of the operation, already normalized to 0..=1.
+    pub volume: f64,
+    /// Number of distinct species killed, processed or used.
+    pub species: usize,
+    /// FSIS enforcement records of a location, or the Animal Welfare Act
+    /// citations of a licensee.
+    pub violations: u32,
+    pub license_active: bool,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ScoreComponent {
+    pub component: String,
+    pub value: f64,
+    pub weight: f64,
+    pub contribution: f64,
+}
+
+/// A 0-100 score, with how much each component contributed to it.
+#[derive(Debug, Clone)]
+pub struct PriorityScore {
+    pub score: f64,
+    pub breakdown: Vec<ScoreComponent>,
+}
+
+fn round2(value: f64) -> f64 {
+    (value * 100.0).round() / 100.0
+}
+
+/// FSIS volume categories run from 1 (smallest) to 5 (largest).
+pub fn fsis_volume(slaughter_category: &str, processing_category: &str) -> f64 {
+    let category = |value: &str| value.trim().parse::<f64>().ok().filter(|c| *c > 0.0);
+    match (category(slaughter_category), category(processing_category)) {
+        (Some(slaughter), _) => slaughter / 5.0,
+        // Processing-only plants rank below slaughter plants of the same size.
+        (None, Some(processing)) => processing / 10.0,
+        (None, None) => 0.0,
+    }
+}
+
+/// Log-scaled animal count, reaching 1.0 at 10,000 animals.
+pub fn animal_count_volume(count: f64) -> f64 {
+    ((1.0 + count.max(0.0)).ln() / (1.0f64 + 10_000.0).ln()).min(1.0)
+}
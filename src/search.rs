@@ -0,0 +1,228 @@
+// Until Every Cage is Empty
+// Copyright (C) 2025 Eli Perez
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+// Contact the developer directly at untileverycageproject@protonmail.com
+use std::collections::HashSet;
+
+use axum::extract::{Query, State};
+use axum::{Json, response::IntoResponse};
+use serde::{Deserialize, Serialize};
+
+use crate::{AppState, LocationResponse, geocode};
+
+const DEFAULT_SEARCH_LIMIT: usize = 50;
+const DEFAULT_AUTOCOMPLETE_LIMIT: usize = 10;
+const MAX_LIMIT: usize = 500;
+
+/// Splits the `dbas` column into individual trade names.
+///
+/// FSIS separates names with semicolons. Commas are left alone because they
+/// also appear inside names ("Deli Brands of America, Saval Foods Corp.").
+/// Names repeating the establishment name are dropped.
+pub fn parse_dba_names(dbas: &str, establishment_name: &str) -> Vec<String> {
+    let mut seen = HashSet::from([normalize(establishment_name)]);
+    dbas.split(';')
+        .map(|name| name.split_whitespace().collect::<Vec<_>>().join(" "))
+        .filter(|name| !name.is_empty() && name != "N/A")
+        .filter(|name| seen.insert(normalize(name)))
+        .collect()
+}
+
+/// Lowercase ASCII words, so "Godshall's" matches "godshalls" and "Fødevarer"
+/// matches "fodevarer".
+pub(crate) fn normalize(text: &str) -> String {
+    deunicode::deunicode(text)
+        .to_lowercase()
+        .replace('\'', "")
+        .split(|c: char| !c.is_ascii_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+#[derive(Serialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum NameKind {
+    EstablishmentName,
+    Dba,
+}
+
+struct Entry {
+    normalized: String,
+    name: String,
+    kind: NameKind,
+    location: usize,
+}
+
+/// Every establishment and trade name of a set of locations.
+#[derive(Default)]
+pub(crate) struct SearchIndex {
+    entries: Vec<Entry>,
+}
+
+/// How well a name matches a query; lower is better.
+fn match_rank(normalized: &str, query: &str, query_words: &[&str]) -> Option<u8> {
+    if normalized == query {
+        return Some(0);
+    }
+    if normalized.starts_with(query) {
+        return Some(1);
+    }
+    let words: Vec<&str> = normalized.split(' ').collect();
+    let all_words_match = query_words
+        .iter()
+        .all(|q| words.iter().any(|word| word.starts_with(q)));
+    if all_words_match {
+        Some(2)
+    } else if normalized.contains(query) {
+        Some(3)
+    } else {
+        None
+    }
+}
+
+impl SearchIndex {
+    pub(crate) fn build(locations: &[LocationResponse]) -> Self {
+        let mut entries = Vec::new();
+        for (i, loc) in locations.iter().enumerate() {
+            entries.push(Entry {
+                normalized: normalize(&loc.establishment_name),
+                name: loc.establishment_name.clone(),
+                kind: NameKind::EstablishmentName,
+                location: i,
+            });
+            for dba in &loc.dba_names {
+                entries.push(Entry {
+                    normalized: normalize(dba),
+                    name: dba.clone(),
+                    kind: NameKind::Dba,
+                    location: i,
+                });
+            }
+        }
+        SearchIndex { entries }
+    }
+
+    /// Matching entries, best first.
+    fn matches(&self, query: &str) -> Vec<&Entry> {
+        let query = normalize(query);
+        if query.is_empty() {
+            return Vec::new();
+        }
+        let query_words: Vec<&str> = query.split(' ').collect();
+
+        let mut ranked: Vec<(u8, &Entry)> = self
+            .entries
+            .iter()
+            .filter_map(|entry| {
+                match_rank(&entry.normalized, &query, &query_words).map(|rank| (rank, entry))
+            })
+            .collect();
+        ranked.sort_by(|(rank_a, a), (rank_b, b)| {
+            rank_a
+                .cmp(rank_b)
+                .then(a.normalized.len().cmp(&b.normalized.len()))
+                .then(a.normalized.cmp(&b.normalized))
+        });
+        ranked.into_iter().map(|(_, entry)| entry).collect()
+    }
+}
+
+fn in_country(loc: &LocationResponse, country: Option<&str>) -> bool {
+    country.is_none_or(|country| loc.country == geocode::country_code(country))
+}
+
+#[derive(Deserialize)]
+pub struct SearchParams {
+    q: String,
+    country_code: Option<String>,
+    limit: Option<usize>,
+}
+
+#[derive(Serialize)]
+struct SearchResult<'a> {
+    /// The name that matched, which may be a trade name.
+    matched_name: String,
+    matched_kind: NameKind,
+    #[serde(flatten)]
+    location: &'a LocationResponse,
+}
+
+/// Locations whose establishment name or any DBA name matches `q`.
+pub async fn get_search_handler(
+    State(state): State<AppState>,
+    Query(params): Query<SearchParams>,
+) -> impl IntoResponse {
+    let limit = params.limit.unwrap_or(DEFAULT_SEARCH_LIMIT).min(MAX_LIMIT);
+    let index = state.location_index();
+    let mut seen = HashSet::new();
+    let results: Vec<SearchResult> = index
+        .search
+        .matches(&params.q)
+        .into_iter()
+        .map(|entry| (entry, &index.locations[entry.location]))
+        .filter(|(_, loc)| in_country(loc, params.country_code.as_deref()))
+        // A location matching on several names is listed once, by its best.
+        .filter(|(entry, _)| seen.insert(entry.location))
+        .take(limit)
+        .map(|(entry, location)| SearchResult {
+            matched_name: entry.name.clone(),
+            matched_kind: entry.kind,
+            location,
+        })
+        .collect();
+    Json(results).into_response()
+}
+
+#[derive(Serialize)]
+struct Suggestion {
+    name: String,
+    kind: NameKind,
+    establishment_name: String,
+    slug: String,
+    country: String,
+}
+
+/// Name suggestions for a partially typed query, including trade names.
+pub async fn get_autocomplete_handler(
+    State(state): State<AppState>,
+    Query(params): Query<SearchParams>,
+) -> impl IntoResponse {
+    let limit = params
+        .limit
+        .unwrap_or(DEFAULT_AUTOCOMPLETE_LIMIT)
+        .min(MAX_LIMIT);
+    let index = state.location_index();
+    let mut seen = HashSet::new();
+    let suggestions: Vec<Suggestion> = index
+        .search
+        .matches(&params.q)
+        .into_iter()
+        .map(|entry| (entry, &index.locations[entry.location]))
+        .filter(|(_, loc)| in_country(loc, params.country_code.as_deref()))
+        // Chains share trade names; suggest each one once.
+        .filter(|(entry, _)| seen.insert(entry.normalized.as_str()))
+        .take(limit)
+        .map(|(entry, loc)| Suggestion {
+            name: entry.name.clone(),
+            kind: entry.kind,
+            establishment_name: loc.establishment_name.clone(),
+            slug: loc.slug.clone(),
+            country: loc.country.clone(),
+        })
+        .collect();
+    Json(suggestions)
+}
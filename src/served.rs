@@ -0,0 +1,98 @@
+// Until Every Cage is Empty
+// Copyright (C) 2025 Eli Perez
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+// Contact the developer directly at untileverycageproject@protonmail.com
+use std::ops::Deref;
+use std::sync::OnceLock;
+
+use axum::body::Bytes;
+use serde::Serialize;
+
+use crate::aquaculture::AquacultureFacility;
+use crate::cafos::CafoFacility;
+use crate::changelog::ChangelogEntry;
+use crate::citations::Citations;
+use crate::details::InspectionLinks;
+use crate::egg_farms::EggFarmFacility;
+use crate::enforcement::Enforcement;
+use crate::equine::EquineFacility;
+use crate::fur_farms::FurFarmFacility;
+use crate::location::{AphisReport, InspectionReport};
+use crate::timeseries::AphisYears;
+use crate::validation::CoordinateReport;
+
+/// A list built once per load and shared by every request, with its JSON
+/// encoding made on first use.
+pub(crate) struct Served<T> {
+    items: Vec<T>,
+    json: OnceLock<Bytes>,
+}
+
+impl<T> Default for Served<T> {
+    fn default() -> Self {
+        Served {
+            items: Vec::new(),
+            json: OnceLock::new(),
+        }
+    }
+}
+
+impl<T: Serialize> Served<T> {
+    pub(crate) fn new(items: Vec<T>) -> Self {
+        Served {
+            items,
+            json: OnceLock::new(),
+        }
+    }
+
+    /// The whole list as a JSON array.
+    pub(crate) fn json(&self) -> Result<Bytes, serde_json::Error> {
+        if let Some(json) = self.json.get() {
+            return Ok(json.clone());
+        }
+        let json = Bytes::from(serde_json::to_vec(&self.items)?);
+        Ok(self.json.get_or_init(|| json).clone())
+    }
+}
+
+impl<T> Deref for Served<T> {
+    type Target = [T];
+
+    fn deref(&self) -> &[T] {
+        &self.items
+    }
+}
+
+/// The datasets other than the locations, as of the last load.
+#[derive(Default)]
+pub(crate) struct Reports {
+    pub(crate) aphis: Served<AphisReport>,
+    pub(crate) inspections: Served<InspectionReport>,
+    /// APHIS certificates' inspection reports.
+    pub(crate) inspection_links: InspectionLinks,
+    pub(crate) aphis_years: AphisYears,
+    pub(crate) aquaculture: Served<AquacultureFacility>,
+    pub(crate) fur_farms: Served<FurFarmFacility>,
+    pub(crate) egg_farms: Served<EggFarmFacility>,
+    pub(crate) cafos: Served<CafoFacility>,
+    pub(crate) equine: Served<EquineFacility>,
+    pub(crate) citations: Citations,
+    pub(crate) enforcement: Enforcement,
+    /// One entry per pair of consecutive snapshots (see `changelog`).
+    pub(crate) changelog: Served<ChangelogEntry>,
+    /// Coordinate problems found loading the datasets above.
+    pub(crate) coordinates: CoordinateReport,
+}
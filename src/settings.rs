@@ -0,0 +1,59 @@
+// Until Every Cage is Empty
+// Copyright (C) 2025 Eli Perez
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+// Contact the developer directly at untileverycageproject@protonmail.com
+//! The server's configuration (`CORS_ORIGINS`, `API_KEYS`, `ADMIN_TOKEN`...),
+//! read once at startup and handed to what needs it rather than looked up
+//! in the process environment as it runs.
+//!
+//! The self-hosted `serve` binary takes it from the environment. Shuttle has
+//! no environment to configure, so the deployment adds Secrets.toml under
+//! it: a variable set in the environment wins over a secret of that name.
+
+use std::collections::HashMap;
+
+#[derive(Debug, Default, Clone)]
+pub struct Settings {
+    values: HashMap<String, String>,
+}
+
+impl Settings {
+    /// The process environment, as it is now.
+    pub fn from_env() -> Self {
+        Settings {
+            values: std::env::vars().collect(),
+        }
+    }
+
+    /// `self`, with `fallback`'s value for each name `self` doesn't set.
+    pub fn or(mut self, fallback: impl IntoIterator<Item = (String, String)>) -> Self {
+        for (name, value) in fallback {
+            self.values.entry(name).or_insert(value);
+        }
+        self
+    }
+
+    /// `self` with `name` set to `value`, e.g. in the tests.
+    pub fn with(mut self, name: &str, value: &str) -> Self {
+        self.values.insert(name.to_string(), value.to_string());
+        self
+    }
+
+    /// The value of `name`, if set.
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.values.get(name).map(String::as_str)
+    }
+}
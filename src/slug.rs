@@ -0,0 +1,111 @@
+// Until Every Cage is Empty
+// Copyright (C) 2025 Eli Perez
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+// Contact the developer directly at untileverycageproject@protonmail.com
+use std::collections::HashSet;
+
+// Trailing legal-form words that add nothing to a URL ("Smithfield Foods, Inc.").
+pub(crate) const LEGAL_SUFFIXES: &[&str] = &[
+    "inc",
+    "llc",
+    "ltd",
+    "limited",
+    "corp",
+    "corporation",
+    "co",
+    "company",
+    "gmbh",
+    "kg",
+    "ag",
+    "sa",
+    "sl",
+    "slu",
+    "sas",
+    "sarl",
+    "as",
+    "aps",
+    "plc",
+    "lp",
+    "llp",
+];
+
+/// Lowercases, transliterates and hyphenates a string ("Tar Heel" -> "tar-heel").
+pub fn slugify(text: &str) -> String {
+    let ascii = deunicode::deunicode(text).to_lowercase();
+    let mut slug = String::with_capacity(ascii.len());
+
+    for c in ascii.chars() {
+        if c.is_ascii_alphanumeric() {
+            slug.push(c);
+        } else if c == '\'' {
+            // "Godshall's" -> "godshalls", not "godshall-s"
+            continue;
+        } else if !slug.is_empty() && !slug.ends_with('-') {
+            slug.push('-');
+        }
+    }
+
+    slug.trim_end_matches('-').to_string()
+}
+
+/// Base slug for a facility: name without legal suffixes, then city and state.
+pub fn facility_slug(name: &str, city: &str, state: &str) -> String {
+    let name = slugify(name);
+    let mut words: Vec<&str> = name.split('-').collect();
+    while words.len() > 1 && LEGAL_SUFFIXES.contains(words.last().unwrap_or(&"")) {
+        words.pop();
+    }
+
+    let parts = [words.join("-"), slugify(city), slugify(state)];
+    parts
+        .iter()
+        .filter(|part| !part.is_empty())
+        .cloned()
+        .collect::<Vec<_>>()
+        .join("-")
+}
+
+/// Gives each facility its slug: its base slug with its country and ID
+/// appended, so namesakes never collide.
+///
+/// `facilities` is a list of `(base_slug, country, id)`. Every slug carries
+/// the suffix, not just those whose base is shared, so a slug changes only
+/// with the facility's own name, place or ID: a namesake turning up later
+/// can't move a published URL. Exact duplicates (same country and ID), and
+/// suffixed slugs that happen to equal another one, fall back to a numeric
+/// suffix.
+pub fn assign_slugs(facilities: &[(String, &str, &str)]) -> Vec<String> {
+    let mut used = HashSet::new();
+    facilities
+        .iter()
+        .map(|(base, country, id)| {
+            let suffix = slugify(&format!("{country} {id}"));
+            let slug = if base.is_empty() {
+                suffix
+            } else {
+                format!("{base}-{suffix}")
+            };
+
+            let mut unique = slug.clone();
+            let mut n = 2;
+            while !used.insert(unique.clone()) {
+                unique = format!("{slug}-{n}");
+                n += 1;
+            }
+            unique
+        })
+        .collect()
+}
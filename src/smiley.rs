@@ -0,0 +1,144 @@
+// Until Every Cage is Empty
+// Copyright (C) 2025 Eli Perez
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+// Contact the developer directly at untileverycageproject@protonmail.com
+//! Fødevarestyrelsen's smiley export (Smiley_xml.xml), read into locations
+//! by the da-foedevarestyrelsen importer and by ingest-archive.
+//!
+//! The export lists every food business the agency inspects; only animal
+//! product industries are kept, and those without an entry in the mapping
+//! table are reported as unmapped.
+
+use std::error::Error;
+
+use serde::{Deserialize, Serialize};
+
+use crate::Location;
+use crate::ids;
+use crate::importer::{self, Report, SourceAdapter};
+use crate::mapping::MappingTable;
+
+/// The industries mapped so far, which `--mapping` files add to.
+const DEFAULT_INDUSTRIES: &str = include_str!("bin/da-foedevarestyrelsen/industries.toml");
+
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+struct Document(Vec<Row>);
+
+// Every field is a defaulted string so a row with a missing or malformed
+// element is reported rather than failing the whole document.
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+pub struct Row {
+    #[serde(default)]
+    navnelbnr: String,
+    #[serde(default)]
+    cvrnr: String,
+    #[serde(default)]
+    pnr: String,
+    // region: (),
+    #[serde(rename = "brancheKode", default)]
+    industry_code: String,
+    #[serde(rename = "branche", default)]
+    industry: String,
+    #[serde(rename = "virksomhedstype", default)]
+    company_type: String,
+    #[serde(rename = "navn1", default)]
+    name: String,
+    #[serde(rename = "adresse1", default)]
+    address: String,
+    #[serde(rename = "postnr", default)]
+    zip: String,
+    #[serde(rename = "By", default)]
+    city: String,
+    // <seneste_kontrol>1</seneste_kontrol>
+    // <seneste_kontrol_dato>27-11-2024 00:00:00</seneste_kontrol_dato>
+    // <naestseneste_kontrol>1</naestseneste_kontrol>
+    // <naestseneste_kontrol_dato>01-11-2023 00:00:00</naestseneste_kontrol_dato>
+    // <tredjeseneste_kontrol>1</tredjeseneste_kontrol>
+    // <tredjeseneste_kontrol_dato>02-11-2022 00:00:00</tredjeseneste_kontrol_dato>
+    // <fjerdeseneste_kontrol>1</fjerdeseneste_kontrol>
+    // <fjerdeseneste_kontrol_dato>09-08-2022 00:00:00</fjerdeseneste_kontrol_dato>
+    // <URL>http://www.findsmiley.dk/da-DK/Searching/DetailsView.htm?virk=921228</URL>
+    // <reklame_beskyttelse>0</reklame_beskyttelse>
+    // <Elite_Smiley>0</Elite_Smiley>
+    // <Kaedenavn></Kaedenavn>
+    #[serde(rename = "Geo_Lng", default)]
+    lng: String,
+    #[serde(rename = "Geo_Lat", default)]
+    lat: String,
+    // <Pixibranche>Fiske- og vildtforretninger, fiskeafdelinger</Pixibranche>
+}
+
+impl Row {
+    fn is_animal_product(&self) -> bool {
+        let industry = self.industry.to_lowercase();
+        industry.starts_with("fremstilling af animalske produkter") || industry.contains("slagter")
+    }
+}
+
+/// The adapter for the smiley export, mapping industries (`branche`) to
+/// activities with `industries`.
+pub struct Smiley {
+    pub industries: MappingTable,
+}
+
+impl Smiley {
+    /// The adapter with the industries mapped so far.
+    pub fn new() -> Result<Self, Box<dyn Error>> {
+        Ok(Smiley {
+            industries: MappingTable::parse(DEFAULT_INDUSTRIES, "industries.toml")?,
+        })
+    }
+}
+
+impl SourceAdapter for Smiley {
+    type Row = Row;
+
+    fn name(&self) -> &'static str {
+        "da-foedevarestyrelsen"
+    }
+
+    fn parse(&mut self, input: &[u8]) -> Result<Vec<Row>, Box<dyn Error>> {
+        let doc: Document = serde_xml_rs::from_reader(input)
+            .map_err(|e| format!("Failed to parse smiley XML: {}", e))?;
+        Ok(doc.0.into_iter().filter(Row::is_animal_product).collect())
+    }
+
+    fn normalize(&mut self, row: Row, report: &mut Report) -> Option<Location> {
+        // navnelbnr is the smiley registry's number for the business, kept
+        // from one export to the next.
+        let id = match row.navnelbnr.trim() {
+            "" => ids::content_id(&[&row.name, &row.address, &row.zip]),
+            number => number.to_string(),
+        };
+        let Some(activities) = self.industries.activities(&row.industry) else {
+            report.unmapped(row.industry);
+            return None;
+        };
+        let coordinate = |value: &str| importer::parse_coordinate(value).unwrap_or(0.0);
+        Some(Location {
+            country: "DK".to_string(),
+            establishment_id: id,
+            establishment_name: row.name,
+            city: row.city,
+            street: row.address,
+            zip: row.zip.trim().to_string(),
+            activities,
+            latitude: coordinate(&row.lat),
+            longitude: coordinate(&row.lng),
+            ..Default::default()
+        })
+    }
+}
@@ -0,0 +1,132 @@
+// Until Every Cage is Empty
+// Copyright (C) 2025 Eli Perez
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+// Contact the developer directly at untileverycageproject@protonmail.com
+use std::error::Error;
+use std::fs;
+use std::path::Path;
+
+use crate::location::Location;
+use crate::store::Dataset;
+
+/// A dated copy of a country's location list, stored as
+/// `static_data/<country>/snapshots/<YYYY-MM-DD>.csv`. A snapshot is saved on
+/// every data refresh, by fetch-data and /api/admin/reload (see
+/// `save_snapshots`). Older FSIS releases can be converted into snapshots with
+/// us-fsis-archive, though none are shipped yet.
+pub struct Snapshot {
+    pub country: String,
+    pub date: String,
+    pub locations: Vec<Location>,
+}
+
+/// Dates of the available snapshots per country, oldest first.
+pub fn snapshot_dates(dataset: &Dataset) -> Vec<(String, String)> {
+    let mut dates = Vec::new();
+    for country in dataset.countries() {
+        for (name, _) in dataset.files_in(&format!("{}/snapshots", country)) {
+            if let Some(date) = name.strip_suffix(".csv") {
+                dates.push((country.to_string(), date.to_string()));
+            }
+        }
+    }
+    dates.sort();
+    dates
+}
+
+fn parse_locations(contents: &[u8]) -> Result<Vec<Location>, Box<dyn Error>> {
+    let mut reader = csv::Reader::from_reader(contents);
+    let mut locations = Vec::new();
+    for result in reader.deserialize() {
+        let record: Location = result?;
+        locations.push(record);
+    }
+    Ok(locations)
+}
+
+/// Copies each country's `locations.csv` under the data directory `root` to
+/// `<country>/snapshots/<date>.csv`, unless it is the same as the country's
+/// latest snapshot. Returns the countries snapshotted.
+pub fn save_snapshots(root: &Path, date: &str) -> Result<Vec<String>, Box<dyn Error>> {
+    let mut countries: Vec<_> = fs::read_dir(root)?
+        .filter_map(Result::ok)
+        .filter(|entry| entry.path().join("locations.csv").is_file())
+        .filter_map(|entry| entry.file_name().into_string().ok())
+        .collect();
+    countries.sort();
+
+    let mut saved = Vec::new();
+    for country in countries {
+        let current = fs::read(root.join(&country).join("locations.csv"))?;
+        let dir = root.join(&country).join("snapshots");
+        let mut dates: Vec<String> = match fs::read_dir(&dir) {
+            Ok(entries) => entries
+                .filter_map(Result::ok)
+                .filter_map(|entry| entry.file_name().into_string().ok())
+                .filter(|name| name.ends_with(".csv"))
+                .collect(),
+            Err(_) => Vec::new(),
+        };
+        dates.sort();
+        if let Some(latest) = dates.last()
+            && fs::read(dir.join(latest))? == current
+        {
+            continue;
+        }
+        fs::create_dir_all(&dir)?;
+        fs::write(dir.join(format!("{}.csv", date)), current)
+            .map_err(|e| format!("Failed to save the {} snapshot: {}", country, e))?;
+        saved.push(country);
+    }
+    Ok(saved)
+}
+
+/// The list currently served for `country`, to compare snapshots against.
+pub fn load_current(dataset: &Dataset, country: &str) -> Result<Vec<Location>, Box<dyn Error>> {
+    match dataset.get(&format!("{}/locations.csv", country)) {
+        Some(contents) => parse_locations(contents),
+        None => Ok(Vec::new()),
+    }
+}
+
+pub fn load_snapshot(
+    dataset: &Dataset,
+    country: &str,
+    date: &str,
+) -> Result<Option<Snapshot>, Box<dyn Error>> {
+    let Some(contents) = dataset.get(&format!("{}/snapshots/{}.csv", country, date)) else {
+        return Ok(None);
+    };
+
+    Ok(Some(Snapshot {
+        country: country.to_string(),
+        date: date.to_string(),
+        locations: parse_locations(contents)?,
+    }))
+}
+
+/// Every snapshot of `country`, oldest first.
+pub fn load_snapshots(dataset: &Dataset, country: &str) -> Result<Vec<Snapshot>, Box<dyn Error>> {
+    let mut snapshots = Vec::new();
+    for (snapshot_country, date) in snapshot_dates(dataset) {
+        if snapshot_country == country
+            && let Some(snapshot) = load_snapshot(dataset, country, &date)?
+        {
+            snapshots.push(snapshot);
+        }
+    }
+    Ok(snapshots)
+}
@@ -0,0 +1,674 @@
+// Until Every Cage is Empty
+// Copyright (C) 2025 Eli Perez
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+// Contact the developer directly at untileverycageproject@protonmail.com
+use std::collections::HashMap;
+
+use axum::extract::{Query, State};
+use axum::{Json, http::StatusCode, response::IntoResponse};
+use rstar::primitives::GeomWithData;
+use rstar::{AABB, RTree};
+use serde::{Deserialize, Serialize};
+
+use crate::errors::ApiError;
+use crate::filter::LocationFilter;
+use crate::geocode;
+use crate::postal::PostalIndex;
+use crate::search::SearchIndex;
+use crate::served::Served;
+use crate::storage::LocationQuery;
+use crate::validation::CoordinateReport;
+use crate::{AppState, LocationResponse};
+
+const EARTH_RADIUS_KM: f64 = 6371.0088;
+const DEFAULT_NEAR_LIMIT: usize = 100;
+const MAX_RADIUS_KM: f64 = 1000.0;
+const DEFAULT_ROUTE_LIMIT: usize = 500;
+const MAX_CORRIDOR_KM: f64 = 100.0;
+/// Most points in a route, after decoding.
+const MAX_ROUTE_POINTS: usize = 10_000;
+const DEFAULT_NEAREST: usize = 5;
+const MAX_NEAREST: usize = 50;
+/// Most points in one nearest lookup.
+const MAX_NEAREST_POINTS: usize = 1000;
+/// The first radius searched for the nearest locations, doubled until
+/// enough are found.
+const NEAREST_START_KM: f64 = 25.0;
+
+/// Great-circle distance between two `(latitude, longitude)` points in km.
+pub(crate) fn haversine_km(from: (f64, f64), to: (f64, f64)) -> f64 {
+    let (lat1, lat2) = (from.0.to_radians(), to.0.to_radians());
+    let d_lat = lat2 - lat1;
+    let d_lng = (to.1 - from.1).to_radians();
+    let a = (d_lat / 2.0).sin().powi(2) + lat1.cos() * lat2.cos() * (d_lng / 2.0).sin().powi(2);
+    2.0 * EARTH_RADIUS_KM * a.sqrt().asin()
+}
+
+/// `[longitude, latitude]` with the position of the location in `locations`.
+type IndexedPoint = GeomWithData<[f64; 2], usize>;
+
+/// The served locations with an R-tree over their coordinates and their
+/// names indexed for search.
+#[derive(Default)]
+pub(crate) struct LocationIndex {
+    pub(crate) locations: Served<LocationResponse>,
+    tree: RTree<IndexedPoint>,
+    pub(crate) search: SearchIndex,
+    /// Positions by county FIPS code and postal code.
+    pub(crate) postal: PostalIndex,
+    /// Counts rebuilds since startup.
+    pub(crate) revision: u64,
+    pub(crate) built_at: chrono::DateTime<chrono::Utc>,
+    /// Coordinate problems found loading the locations.
+    pub(crate) coordinates: CoordinateReport,
+}
+
+impl LocationIndex {
+    pub(crate) fn new(locations: Vec<LocationResponse>) -> Self {
+        let points = locations
+            .iter()
+            .enumerate()
+            .map(|(i, loc)| IndexedPoint::new([loc.longitude, loc.latitude], i))
+            .collect();
+        LocationIndex {
+            tree: RTree::bulk_load(points),
+            search: SearchIndex::build(&locations),
+            postal: PostalIndex::build(&locations),
+            locations: Served::new(locations),
+            revision: 0,
+            built_at: chrono::Utc::now(),
+            coordinates: CoordinateReport::default(),
+        }
+    }
+
+    /// Locations inside the box, in dataset order. A box with
+    /// `min_lng > max_lng` crosses the antimeridian.
+    pub(crate) fn in_bbox(&self, bbox: &BoundingBox) -> Vec<&LocationResponse> {
+        self.positions_in_bbox(bbox)
+            .into_iter()
+            .map(|i| &self.locations[i])
+            .collect()
+    }
+
+    /// The positions in `locations` of `in_bbox`.
+    fn positions_in_bbox(&self, bbox: &BoundingBox) -> Vec<usize> {
+        let boxes = if bbox.min_lng <= bbox.max_lng {
+            vec![(bbox.min_lng, bbox.max_lng)]
+        } else {
+            vec![(bbox.min_lng, 180.0), (-180.0, bbox.max_lng)]
+        };
+
+        let mut found: Vec<usize> = boxes
+            .into_iter()
+            .flat_map(|(min_lng, max_lng)| {
+                let envelope = AABB::from_corners([min_lng, bbox.min_lat], [max_lng, bbox.max_lat]);
+                self.tree
+                    .locate_in_envelope(&envelope)
+                    .map(|point| point.data)
+                    .collect::<Vec<_>>()
+            })
+            .collect();
+        found.sort_unstable();
+        found.dedup();
+        found
+    }
+
+    /// Locations within `radius_km` of `(lat, lng)` with their distance,
+    /// nearest first. Locations without coordinates (0, 0) are left out.
+    pub(crate) fn near(&self, lat: f64, lng: f64, radius_km: f64) -> Vec<(&LocationResponse, f64)> {
+        // Candidates come from the box enclosing the circle, which spans every
+        // longitude when the circle reaches a pole.
+        let angular = radius_km / EARTH_RADIUS_KM;
+        let d_lat = angular.to_degrees();
+        let (min_lat, max_lat) = (lat - d_lat, lat + d_lat);
+        let spread = angular.sin() / lat.to_radians().cos();
+        let d_lng = if min_lat <= -90.0 || max_lat >= 90.0 || spread >= 1.0 {
+            180.0
+        } else {
+            spread.asin().to_degrees()
+        };
+        let wrap = |lng: f64| {
+            if lng > 180.0 {
+                lng - 360.0
+            } else if lng < -180.0 {
+                lng + 360.0
+            } else {
+                lng
+            }
+        };
+        let (min_lng, max_lng) = if d_lng >= 180.0 {
+            (-180.0, 180.0)
+        } else {
+            (wrap(lng - d_lng), wrap(lng + d_lng))
+        };
+        let bbox = BoundingBox::new(min_lat.max(-90.0), min_lng, max_lat.min(90.0), max_lng);
+
+        let mut found: Vec<(&LocationResponse, f64)> = self
+            .in_bbox(&bbox)
+            .into_iter()
+            .filter(|loc| !(loc.latitude == 0.0 && loc.longitude == 0.0))
+            .map(|loc| (loc, haversine_km((lat, lng), (loc.latitude, loc.longitude))))
+            .filter(|(_, distance)| *distance <= radius_km)
+            .collect();
+        found.sort_by(|a, b| a.1.total_cmp(&b.1));
+        found
+    }
+
+    /// The `n` locations nearest `(lat, lng)` that `keep` accepts, up to
+    /// `max_km` away, with their distance, nearest first. The search widens
+    /// from `NEAREST_START_KM` until it finds them.
+    pub(crate) fn nearest(
+        &self,
+        (lat, lng): (f64, f64),
+        n: usize,
+        max_km: f64,
+        keep: impl Fn(&LocationResponse) -> bool,
+    ) -> Vec<(&LocationResponse, f64)> {
+        let mut radius_km = NEAREST_START_KM.min(max_km);
+        loop {
+            let mut found: Vec<(&LocationResponse, f64)> = self
+                .near(lat, lng, radius_km)
+                .into_iter()
+                .filter(|(loc, _)| keep(loc))
+                .collect();
+            if found.len() >= n || radius_km >= max_km {
+                found.truncate(n);
+                return found;
+            }
+            radius_km = (radius_km * 2.0).min(max_km);
+        }
+    }
+
+    /// Locations within `width_km` of the route through `points`, with how
+    /// far along the route they are and how far off it, in route order.
+    /// Each is placed at the nearest point of the route.
+    pub(crate) fn along_route(
+        &self,
+        points: &[(f64, f64)],
+        width_km: f64,
+    ) -> Vec<(&LocationResponse, RoutePosition)> {
+        // By position in `locations`.
+        let mut found: HashMap<usize, RoutePosition> = HashMap::new();
+        let mut travelled = 0.0;
+        for segment in points.windows(2) {
+            let (from, to) = (segment[0], segment[1]);
+            let length = haversine_km(from, to);
+            // The segment's box, widened by the corridor.
+            let d_lat = (width_km / EARTH_RADIUS_KM).to_degrees();
+            let widest = from.0.abs().max(to.0.abs()) + d_lat;
+            let d_lng = if widest >= 90.0 {
+                180.0
+            } else {
+                d_lat / widest.to_radians().cos()
+            };
+            let bbox = BoundingBox::new(
+                (from.0.min(to.0) - d_lat).max(-90.0),
+                (from.1.min(to.1) - d_lng).max(-180.0),
+                (from.0.max(to.0) + d_lat).min(90.0),
+                (from.1.max(to.1) + d_lng).min(180.0),
+            );
+            for i in self.positions_in_bbox(&bbox) {
+                let loc = &self.locations[i];
+                if loc.latitude == 0.0 && loc.longitude == 0.0 {
+                    continue;
+                }
+                let (along, off) = project((loc.latitude, loc.longitude), from, to);
+                if off > width_km {
+                    continue;
+                }
+                let position = RoutePosition {
+                    progress_km: travelled + along.min(length),
+                    distance_km: off,
+                };
+                found
+                    .entry(i)
+                    .and_modify(|best| {
+                        if position.distance_km < best.distance_km {
+                            *best = position;
+                        }
+                    })
+                    .or_insert(position);
+            }
+            travelled += length;
+        }
+        let mut found: Vec<(&LocationResponse, RoutePosition)> = found
+            .into_iter()
+            .map(|(i, position)| (&self.locations[i], position))
+            .collect();
+        found.sort_by(|a, b| {
+            a.1.progress_km
+                .total_cmp(&b.1.progress_km)
+                .then(a.1.distance_km.total_cmp(&b.1.distance_km))
+        });
+        found
+    }
+}
+
+/// Where a location is relative to a route.
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct RoutePosition {
+    /// Distance along the route to the point nearest the location.
+    pub(crate) progress_km: f64,
+    /// Distance from that point to the location.
+    pub(crate) distance_km: f64,
+}
+
+/// How far along the segment from `from` to `to` the point nearest `point`
+/// is, and how far `point` is from it, in km. Uses a flat projection centered
+/// on `point`, close enough over a corridor's width.
+fn project(point: (f64, f64), from: (f64, f64), to: (f64, f64)) -> (f64, f64) {
+    let km_per_degree = EARTH_RADIUS_KM.to_radians();
+    let scale = point.0.to_radians().cos();
+    let flat = |(lat, lng): (f64, f64)| {
+        (
+            (lng - point.1) * scale * km_per_degree,
+            (lat - point.0) * km_per_degree,
+        )
+    };
+    let (ax, ay) = flat(from);
+    let (bx, by) = flat(to);
+    let (dx, dy) = (bx - ax, by - ay);
+    let length_squared = dx * dx + dy * dy;
+    let t = if length_squared == 0.0 {
+        0.0
+    } else {
+        (-(ax * dx + ay * dy) / length_squared).clamp(0.0, 1.0)
+    };
+    let (nx, ny) = (ax + t * dx, ay + t * dy);
+    (t * length_squared.sqrt(), (nx * nx + ny * ny).sqrt())
+}
+
+/// Decodes an encoded polyline (the format of Google, OSRM and Valhalla,
+/// five decimal places) into `(latitude, longitude)` points.
+fn decode_polyline(encoded: &str) -> Result<Vec<(f64, f64)>, String> {
+    let invalid = || "polyline is not a valid encoded polyline".to_string();
+    let mut values = Vec::new();
+    let (mut value, mut shift) = (0i64, 0u32);
+    for byte in encoded.trim().bytes() {
+        if !(63..=126).contains(&byte) || shift > 30 {
+            return Err(invalid());
+        }
+        let chunk = i64::from(byte - 63);
+        value |= (chunk & 0x1f) << shift;
+        shift += 5;
+        if chunk < 0x20 {
+            values.push(if value & 1 == 1 {
+                !(value >> 1)
+            } else {
+                value >> 1
+            });
+            (value, shift) = (0, 0);
+        }
+    }
+    if shift != 0 || values.len() % 2 != 0 {
+        return Err(invalid());
+    }
+    let (mut lat, mut lng) = (0i64, 0i64);
+    Ok(values
+        .chunks(2)
+        .map(|delta| {
+            lat += delta[0];
+            lng += delta[1];
+            (lat as f64 / 1e5, lng as f64 / 1e5)
+        })
+        .collect())
+}
+
+/// `lat,lng;lat,lng;...` points.
+fn parse_waypoints(waypoints: &str) -> Result<Vec<(f64, f64)>, String> {
+    waypoints
+        .split(';')
+        .map(str::trim)
+        .filter(|point| !point.is_empty())
+        .map(|point| {
+            let parsed = point
+                .split_once(',')
+                .and_then(|(lat, lng)| Some((lat.trim().parse().ok()?, lng.trim().parse().ok()?)));
+            parsed
+                .ok_or_else(|| format!("Waypoints are lat,lng pairs separated by ;, not {}", point))
+        })
+        .collect()
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct BoundingBox {
+    pub(crate) min_lat: f64,
+    pub(crate) min_lng: f64,
+    pub(crate) max_lat: f64,
+    pub(crate) max_lng: f64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    country_code: Option<String>,
+}
+
+impl BoundingBox {
+    pub(crate) fn new(min_lat: f64, min_lng: f64, max_lat: f64, max_lng: f64) -> Self {
+        BoundingBox {
+            min_lat,
+            min_lng,
+            max_lat,
+            max_lng,
+            country_code: None,
+        }
+    }
+
+    /// Whether a point is inside, the box crossing the antimeridian when
+    /// `min_lng > max_lng`.
+    pub(crate) fn contains(&self, lat: f64, lng: f64) -> bool {
+        let lng_inside = if self.min_lng <= self.max_lng {
+            (self.min_lng..=self.max_lng).contains(&lng)
+        } else {
+            lng >= self.min_lng || lng <= self.max_lng
+        };
+        lng_inside && (self.min_lat..=self.max_lat).contains(&lat)
+    }
+
+    pub(crate) fn validate(&self) -> Result<(), String> {
+        let in_range = |value: f64, limit: f64| (-limit..=limit).contains(&value);
+        if !in_range(self.min_lat, 90.0) || !in_range(self.max_lat, 90.0) {
+            return Err("Latitudes must be between -90 and 90".to_string());
+        }
+        if !in_range(self.min_lng, 180.0) || !in_range(self.max_lng, 180.0) {
+            return Err("Longitudes must be between -180 and 180".to_string());
+        }
+        if self.min_lat > self.max_lat {
+            return Err("min_lat must not be greater than max_lat".to_string());
+        }
+        Ok(())
+    }
+}
+
+/// GET /api/locations/bbox?min_lat=&min_lng=&max_lat=&max_lng=
+pub async fn get_locations_in_bbox_handler(
+    State(state): State<AppState>,
+    Query(bbox): Query<BoundingBox>,
+) -> impl IntoResponse {
+    if let Err(e) = bbox.validate() {
+        return (StatusCode::BAD_REQUEST, e).into_response();
+    }
+
+    let index = state.location_index();
+    let country = bbox.country_code.as_deref().map(geocode::country_code);
+    let query = LocationQuery {
+        country_code: country.as_deref(),
+        bbox: Some(&bbox),
+        ..Default::default()
+    };
+    match state.storage.with(&index).locations(&query) {
+        Ok(locations) => Json(locations).into_response(),
+        Err(e) => ApiError::internal(e).into_response(),
+    }
+}
+
+#[derive(Deserialize)]
+pub struct NearParams {
+    lat: f64,
+    lng: f64,
+    radius_km: f64,
+    country_code: Option<String>,
+    limit: Option<usize>,
+}
+
+impl NearParams {
+    fn validate(&self) -> Result<(), String> {
+        if !(-90.0..=90.0).contains(&self.lat) {
+            return Err("lat must be between -90 and 90".to_string());
+        }
+        if !(-180.0..=180.0).contains(&self.lng) {
+            return Err("lng must be between -180 and 180".to_string());
+        }
+        if !(self.radius_km > 0.0 && self.radius_km <= MAX_RADIUS_KM) {
+            return Err(format!(
+                "radius_km must be greater than 0 and at most {}",
+                MAX_RADIUS_KM
+            ));
+        }
+        Ok(())
+    }
+}
+
+#[derive(Serialize)]
+struct NearbyLocation<'a> {
+    distance_km: f64,
+    #[serde(flatten)]
+    location: &'a LocationResponse,
+}
+
+/// GET /api/locations/near?lat=&lng=&radius_km=
+pub async fn get_locations_near_handler(
+    State(state): State<AppState>,
+    Query(params): Query<NearParams>,
+) -> impl IntoResponse {
+    if let Err(e) = params.validate() {
+        return (StatusCode::BAD_REQUEST, e).into_response();
+    }
+
+    let index = state.location_index();
+    let locations: Vec<NearbyLocation> = index
+        .near(params.lat, params.lng, params.radius_km)
+        .into_iter()
+        .filter(|(loc, _)| {
+            params
+                .country_code
+                .as_ref()
+                .is_none_or(|country| loc.country == geocode::country_code(country))
+        })
+        .take(params.limit.unwrap_or(DEFAULT_NEAR_LIMIT))
+        .map(|(location, distance_km)| NearbyLocation {
+            distance_km: (distance_km * 1000.0).round() / 1000.0,
+            location,
+        })
+        .collect();
+    Json(locations).into_response()
+}
+
+#[derive(Deserialize)]
+pub struct RouteParams {
+    /// An encoded polyline.
+    polyline: Option<String>,
+    /// `lat,lng;lat,lng;...`, when no polyline is given.
+    waypoints: Option<String>,
+    /// How far from the route a location may be.
+    width_km: f64,
+    country_code: Option<String>,
+    limit: Option<usize>,
+}
+
+impl RouteParams {
+    fn points(&self) -> Result<Vec<(f64, f64)>, String> {
+        let points = match (&self.polyline, &self.waypoints) {
+            (Some(polyline), None) => decode_polyline(polyline)?,
+            (None, Some(waypoints)) => parse_waypoints(waypoints)?,
+            _ => return Err("Pass either polyline or waypoints".to_string()),
+        };
+        if points.len() < 2 {
+            return Err("A route needs at least two points".to_string());
+        }
+        if points.len() > MAX_ROUTE_POINTS {
+            return Err(format!(
+                "A route can have at most {} points",
+                MAX_ROUTE_POINTS
+            ));
+        }
+        if points
+            .iter()
+            .any(|(lat, lng)| !(-90.0..=90.0).contains(lat) || !(-180.0..=180.0).contains(lng))
+        {
+            return Err(
+                "Route points must have latitudes between -90 and 90 and longitudes \
+                 between -180 and 180"
+                    .to_string(),
+            );
+        }
+        if !(self.width_km > 0.0 && self.width_km <= MAX_CORRIDOR_KM) {
+            return Err(format!(
+                "width_km must be greater than 0 and at most {}",
+                MAX_CORRIDOR_KM
+            ));
+        }
+        Ok(points)
+    }
+}
+
+#[derive(Serialize)]
+struct RouteLocation<'a> {
+    progress_km: f64,
+    distance_km: f64,
+    #[serde(flatten)]
+    location: &'a LocationResponse,
+}
+
+/// GET /api/locations/along-route?polyline=&width_km= (or `waypoints=`):
+/// locations in the corridor, in the order the route passes them.
+pub async fn get_locations_along_route_handler(
+    State(state): State<AppState>,
+    Query(params): Query<RouteParams>,
+) -> impl IntoResponse {
+    let points = match params.points() {
+        Ok(points) => points,
+        Err(e) => return (StatusCode::BAD_REQUEST, e).into_response(),
+    };
+
+    let index = state.location_index();
+    let round = |km: f64| (km * 1000.0).round() / 1000.0;
+    let locations: Vec<RouteLocation> = index
+        .along_route(&points, params.width_km)
+        .into_iter()
+        .filter(|(loc, _)| {
+            params
+                .country_code
+                .as_ref()
+                .is_none_or(|country| loc.country == geocode::country_code(country))
+        })
+        .take(params.limit.unwrap_or(DEFAULT_ROUTE_LIMIT))
+        .map(|(location, position)| RouteLocation {
+            progress_km: round(position.progress_km),
+            distance_km: round(position.distance_km),
+            location,
+        })
+        .collect();
+    Json(locations).into_response()
+}
+
+#[derive(Deserialize)]
+pub struct NearestPoint {
+    lat: f64,
+    lng: f64,
+    /// Echoed back, e.g. the city's name.
+    label: Option<String>,
+}
+
+#[derive(Deserialize)]
+pub struct NearestRequest {
+    points: Vec<NearestPoint>,
+    /// Locations per point, 5 by default.
+    n: Option<usize>,
+    /// How far to look, `MAX_RADIUS_KM` at most and by default.
+    max_distance_km: Option<f64>,
+    #[serde(alias = "country")]
+    country_code: Option<String>,
+    /// Comma-separated, as in /api/locations.
+    state: Option<String>,
+    species: Option<String>,
+    activity: Option<String>,
+    status: Option<String>,
+}
+
+impl NearestRequest {
+    fn validate(&self) -> Result<(usize, f64), String> {
+        if self.points.is_empty() || self.points.len() > MAX_NEAREST_POINTS {
+            return Err(format!("Pass between 1 and {} points", MAX_NEAREST_POINTS));
+        }
+        if self.points.iter().any(|point| {
+            !(-90.0..=90.0).contains(&point.lat) || !(-180.0..=180.0).contains(&point.lng)
+        }) {
+            return Err(
+                "Points must have latitudes between -90 and 90 and longitudes between -180 \
+                 and 180"
+                    .to_string(),
+            );
+        }
+        let n = self.n.unwrap_or(DEFAULT_NEAREST);
+        if n == 0 || n > MAX_NEAREST {
+            return Err(format!("n must be between 1 and {}", MAX_NEAREST));
+        }
+        let max_km = self.max_distance_km.unwrap_or(MAX_RADIUS_KM);
+        if !(max_km > 0.0 && max_km <= MAX_RADIUS_KM) {
+            return Err(format!(
+                "max_distance_km must be greater than 0 and at most {}",
+                MAX_RADIUS_KM
+            ));
+        }
+        Ok((n, max_km))
+    }
+}
+
+#[derive(Serialize)]
+struct Nearest<'a> {
+    lat: f64,
+    lng: f64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    label: Option<&'a str>,
+    nearest: Vec<NearbyLocation<'a>>,
+}
+
+/// POST /api/locations/nearest with a JSON body of `points` and filters: the
+/// `n` nearest matching locations to each point, in the order given.
+pub async fn post_locations_nearest_handler(
+    State(state): State<AppState>,
+    Json(request): Json<NearestRequest>,
+) -> impl IntoResponse {
+    let parse = || -> Result<(usize, f64, LocationFilter), String> {
+        let (n, max_km) = request.validate()?;
+        let filter = LocationFilter::parse(
+            request.state.as_deref(),
+            request.species.as_deref(),
+            request.activity.as_deref(),
+            request.status.as_deref(),
+        )?;
+        Ok((n, max_km, filter))
+    };
+    let (n, max_km, filter) = match parse() {
+        Ok(parsed) => parsed,
+        Err(e) => return (StatusCode::BAD_REQUEST, e).into_response(),
+    };
+    let country = request.country_code.as_deref().map(geocode::country_code);
+
+    let index = state.location_index();
+    let keep = |loc: &LocationResponse| {
+        country
+            .as_deref()
+            .is_none_or(|country| loc.country == country)
+            && filter.matches(loc)
+    };
+    let results: Vec<Nearest> = request
+        .points
+        .iter()
+        .map(|point| Nearest {
+            lat: point.lat,
+            lng: point.lng,
+            label: point.label.as_deref(),
+            nearest: index
+                .nearest((point.lat, point.lng), n, max_km, keep)
+                .into_iter()
+                .map(|(location, distance_km)| NearbyLocation {
+                    distance_km: (distance_km * 1000.0).round() / 1000.0,
+                    location,
+                })
+                .collect(),
+        })
+        .collect();
+    Json(results).into_response()
+}
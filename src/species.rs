@@ -0,0 +1,1390 @@
+// Until Every Cage is Empty
+// Copyright (C) 2025 Eli Perez
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+// Contact the developer directly at untileverycageproject@protonmail.com
+use serde::Serialize;
+
+/// Level of a taxon in the hierarchy, from broadest to narrowest.
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "snake_case")]
+pub enum Rank {
+    /// Mammals, birds, fish.
+    Class,
+    /// Bovines, poultry, cervids...
+    Family,
+    /// The level shown to users: cattle, pigs, chickens...
+    Group,
+    /// Regulatory classes within a group, e.g. FSIS's dairy cow or bob veal.
+    Category,
+}
+
+/// One node of the species ontology.
+#[derive(Serialize, Debug)]
+pub struct Taxon {
+    pub id: &'static str,
+    pub parent: Option<&'static str>,
+    pub rank: Rank,
+    /// English display name, as used in summaries ("Sheep & Lambs").
+    pub name: &'static str,
+    /// Singular English name ("Sheep").
+    pub singular: &'static str,
+    /// English name of the meat where it differs from the animal ("Beef").
+    pub meat: Option<&'static str>,
+    /// Other terms users, datasets or importers use for this taxon.
+    pub synonyms: &'static [&'static str],
+    /// `(language, name)` pairs.
+    pub translations: &'static [(&'static str, &'static str)],
+}
+
+impl Taxon {
+    /// Name for a language code, falling back to English.
+    pub fn name_in(&self, lang: &str) -> &'static str {
+        self.translations
+            .iter()
+            .find(|(code, _)| *code == lang)
+            .map(|(_, name)| *name)
+            .unwrap_or(self.name)
+    }
+
+    /// Label used for processing lists: the meat name, else the singular name.
+    pub fn product_name(&self) -> &'static str {
+        self.meat.unwrap_or(self.singular)
+    }
+
+    pub fn parent(&self) -> Option<&'static Taxon> {
+        self.parent.and_then(taxon)
+    }
+}
+
+const BASE: Taxon = Taxon {
+    id: "",
+    parent: None,
+    rank: Rank::Category,
+    name: "",
+    singular: "",
+    meat: None,
+    synonyms: &[],
+    translations: &[],
+};
+
+/// The ontology. Groups are listed in the order summaries display them.
+pub static TAXA: &[Taxon] = &[
+    // --- Classes ---
+    Taxon {
+        id: "mammal",
+        rank: Rank::Class,
+        name: "Mammals",
+        singular: "Mammal",
+        translations: &[
+            ("es", "Mamíferos"),
+            ("de", "Säugetiere"),
+            ("fr", "Mammifères"),
+            ("it", "Mammiferi"),
+            ("da", "Pattedyr"),
+        ],
+        ..BASE
+    },
+    Taxon {
+        id: "bird",
+        rank: Rank::Class,
+        name: "Birds",
+        singular: "Bird",
+        synonyms: &["fowl"],
+        translations: &[
+            ("es", "Aves"),
+            ("de", "Vögel"),
+            ("fr", "Oiseaux"),
+            ("it", "Uccelli"),
+            ("da", "Fugle"),
+        ],
+        ..BASE
+    },
+    Taxon {
+        id: "fish",
+        rank: Rank::Class,
+        name: "Fish",
+        singular: "Fish",
+        synonyms: &["aquaculture", "aquatic"],
+        translations: &[
+            ("es", "Peces"),
+            ("de", "Fische"),
+            ("fr", "Poissons"),
+            ("it", "Pesci"),
+            ("da", "Fisk"),
+        ],
+        ..BASE
+    },
+    Taxon {
+        id: "crustacean",
+        rank: Rank::Class,
+        name: "Crustaceans",
+        singular: "Crustacean",
+        synonyms: &["shellfish"],
+        translations: &[
+            ("es", "Crustáceos"),
+            ("de", "Krebstiere"),
+            ("fr", "Crustacés"),
+            ("it", "Crostacei"),
+            ("da", "Krebsdyr"),
+        ],
+        ..BASE
+    },
+    Taxon {
+        id: "mollusc",
+        rank: Rank::Class,
+        name: "Molluscs",
+        singular: "Mollusc",
+        synonyms: &["mollusk", "mollusks", "bivalve", "bivalves"],
+        translations: &[
+            ("es", "Moluscos"),
+            ("de", "Weichtiere"),
+            ("fr", "Mollusques"),
+            ("it", "Molluschi"),
+            ("da", "Bløddyr"),
+        ],
+        ..BASE
+    },
+    // --- Families ---
+    Taxon {
+        id: "bovine",
+        parent: Some("mammal"),
+        rank: Rank::Family,
+        name: "Bovines",
+        singular: "Bovine",
+        translations: &[
+            ("es", "Bovinos"),
+            ("de", "Rinder"),
+            ("fr", "Bovins"),
+            ("it", "Bovini"),
+            ("da", "Kvæg"),
+        ],
+        ..BASE
+    },
+    Taxon {
+        id: "porcine",
+        parent: Some("mammal"),
+        rank: Rank::Family,
+        name: "Swine",
+        singular: "Swine",
+        translations: &[
+            ("es", "Porcinos"),
+            ("de", "Schweine"),
+            ("fr", "Porcins"),
+            ("it", "Suini"),
+            ("da", "Svin"),
+        ],
+        ..BASE
+    },
+    Taxon {
+        id: "caprine",
+        parent: Some("mammal"),
+        rank: Rank::Family,
+        name: "Caprines",
+        singular: "Caprine",
+        translations: &[
+            ("es", "Caprinos"),
+            ("de", "Ziegenartige"),
+            ("fr", "Caprins"),
+            ("it", "Caprini"),
+            ("da", "Geder"),
+        ],
+        ..BASE
+    },
+    Taxon {
+        id: "ovine",
+        parent: Some("mammal"),
+        rank: Rank::Family,
+        name: "Ovines",
+        singular: "Ovine",
+        translations: &[
+            ("es", "Ovinos"),
+            ("de", "Schafartige"),
+            ("fr", "Ovins"),
+            ("it", "Ovini"),
+            ("da", "Får"),
+        ],
+        ..BASE
+    },
+    Taxon {
+        id: "cervid",
+        parent: Some("mammal"),
+        rank: Rank::Family,
+        name: "Deer Family",
+        singular: "Cervid",
+        translations: &[
+            ("es", "Cérvidos"),
+            ("de", "Hirsche"),
+            ("fr", "Cervidés"),
+            ("it", "Cervidi"),
+            ("da", "Hjortedyr"),
+        ],
+        ..BASE
+    },
+    Taxon {
+        id: "equine",
+        parent: Some("mammal"),
+        rank: Rank::Family,
+        name: "Equines",
+        singular: "Equine",
+        synonyms: &["equines", "equids"],
+        translations: &[
+            ("es", "Équidos"),
+            ("de", "Equiden"),
+            ("fr", "Équidés"),
+            ("it", "Equini"),
+            ("da", "Hestedyr"),
+        ],
+        ..BASE
+    },
+    Taxon {
+        id: "poultry",
+        parent: Some("bird"),
+        rank: Rank::Family,
+        name: "Poultry",
+        singular: "Poultry",
+        translations: &[
+            ("es", "Aves de corral"),
+            ("de", "Geflügel"),
+            ("fr", "Volailles"),
+            ("it", "Pollame"),
+            ("da", "Fjerkræ"),
+        ],
+        ..BASE
+    },
+    // --- Groups, in summary order ---
+    Taxon {
+        id: "cattle",
+        parent: Some("bovine"),
+        rank: Rank::Group,
+        name: "Cattle (Cows, Bulls)",
+        singular: "Cattle",
+        meat: Some("Beef"),
+        synonyms: &["cow", "cows", "bull", "bulls", "beef", "cattle"],
+        translations: &[
+            ("es", "Ganado vacuno (vacas, toros)"),
+            ("de", "Rinder (Kühe, Bullen)"),
+            ("fr", "Bovins (vaches, taureaux)"),
+            ("it", "Bovini (vacche, tori)"),
+            ("da", "Kvæg (køer, tyre)"),
+        ],
+    },
+    Taxon {
+        id: "calf",
+        parent: Some("bovine"),
+        rank: Rank::Group,
+        name: "Calves (Veal)",
+        singular: "Calf",
+        meat: Some("Veal"),
+        synonyms: &["calves", "veal"],
+        translations: &[
+            ("es", "Terneros (ternera)"),
+            ("de", "Kälber (Kalbfleisch)"),
+            ("fr", "Veaux"),
+            ("it", "Vitelli"),
+            ("da", "Kalve (kalvekød)"),
+        ],
+    },
+    Taxon {
+        id: "pig",
+        parent: Some("porcine"),
+        rank: Rank::Group,
+        name: "Pigs",
+        singular: "Pig",
+        meat: Some("Pork"),
+        synonyms: &["swine", "hog", "hogs", "sow", "sows", "pork"],
+        translations: &[
+            ("es", "Cerdos"),
+            ("de", "Schweine"),
+            ("fr", "Porcs"),
+            ("it", "Maiali"),
+            ("da", "Grise"),
+        ],
+    },
+    Taxon {
+        id: "goat",
+        parent: Some("caprine"),
+        rank: Rank::Group,
+        name: "Goats",
+        singular: "Goat",
+        synonyms: &["kid", "kids", "chevon"],
+        translations: &[
+            ("es", "Cabras"),
+            ("de", "Ziegen"),
+            ("fr", "Chèvres"),
+            ("it", "Capre"),
+            ("da", "Geder"),
+        ],
+        ..BASE
+    },
+    Taxon {
+        id: "sheep",
+        parent: Some("ovine"),
+        rank: Rank::Group,
+        name: "Sheep & Lambs",
+        singular: "Sheep",
+        synonyms: &["lamb", "lambs", "mutton", "ewe", "ewes"],
+        translations: &[
+            ("es", "Ovejas y corderos"),
+            ("de", "Schafe und Lämmer"),
+            ("fr", "Moutons et agneaux"),
+            ("it", "Pecore e agnelli"),
+            ("da", "Får og lam"),
+        ],
+        ..BASE
+    },
+    Taxon {
+        id: "deer_reindeer",
+        parent: Some("cervid"),
+        rank: Rank::Group,
+        name: "Deer & Reindeer",
+        singular: "Deer",
+        synonyms: &["venison"],
+        translations: &[
+            ("es", "Ciervos y renos"),
+            ("de", "Hirsche und Rentiere"),
+            ("fr", "Cerfs et rennes"),
+            ("it", "Cervi e renne"),
+            ("da", "Hjorte og rensdyr"),
+        ],
+        ..BASE
+    },
+    Taxon {
+        id: "antelope",
+        parent: Some("mammal"),
+        rank: Rank::Group,
+        name: "Antelope",
+        singular: "Antelope",
+        translations: &[
+            ("es", "Antílopes"),
+            ("de", "Antilopen"),
+            ("fr", "Antilopes"),
+            ("it", "Antilopi"),
+            ("da", "Antiloper"),
+        ],
+        ..BASE
+    },
+    Taxon {
+        id: "elk",
+        parent: Some("cervid"),
+        rank: Rank::Group,
+        name: "Elk",
+        singular: "Elk",
+        synonyms: &["wapiti"],
+        translations: &[
+            ("es", "Uapitíes"),
+            ("de", "Wapitis"),
+            ("fr", "Wapitis"),
+            ("it", "Wapiti"),
+            ("da", "Wapitihjorte"),
+        ],
+        ..BASE
+    },
+    Taxon {
+        id: "bison_buffalo",
+        parent: Some("bovine"),
+        rank: Rank::Group,
+        name: "Bison & Buffalo",
+        singular: "Bison",
+        translations: &[
+            ("es", "Bisontes y búfalos"),
+            ("de", "Bisons und Büffel"),
+            ("fr", "Bisons et buffles"),
+            ("it", "Bisonti e bufali"),
+            ("da", "Bisoner og bøfler"),
+        ],
+        ..BASE
+    },
+    Taxon {
+        id: "yak",
+        parent: Some("bovine"),
+        rank: Rank::Group,
+        name: "Yak",
+        singular: "Yak",
+        translations: &[
+            ("es", "Yaks"),
+            ("de", "Yaks"),
+            ("fr", "Yacks"),
+            ("it", "Yak"),
+            ("da", "Yakokser"),
+        ],
+        ..BASE
+    },
+    Taxon {
+        id: "other_livestock",
+        parent: Some("mammal"),
+        rank: Rank::Group,
+        name: "Other Livestock",
+        singular: "Other Voluntary Livestock",
+        translations: &[
+            ("es", "Otro ganado"),
+            ("de", "Sonstiges Vieh"),
+            ("fr", "Autre bétail"),
+            ("it", "Altro bestiame"),
+            ("da", "Andre husdyr"),
+        ],
+        ..BASE
+    },
+    Taxon {
+        id: "rabbit",
+        parent: Some("mammal"),
+        rank: Rank::Group,
+        name: "Rabbits",
+        singular: "Rabbit",
+        synonyms: &["hare", "hares"],
+        translations: &[
+            ("es", "Conejos"),
+            ("de", "Kaninchen"),
+            ("fr", "Lapins"),
+            ("it", "Conigli"),
+            ("da", "Kaniner"),
+        ],
+        ..BASE
+    },
+    Taxon {
+        id: "horse",
+        parent: Some("equine"),
+        rank: Rank::Group,
+        name: "Horses",
+        singular: "Horse",
+        synonyms: &["pony", "ponies", "caballo"],
+        translations: &[
+            ("es", "Caballos"),
+            ("de", "Pferde"),
+            ("fr", "Chevaux"),
+            ("it", "Cavalli"),
+            ("da", "Heste"),
+        ],
+        ..BASE
+    },
+    Taxon {
+        id: "donkey",
+        parent: Some("equine"),
+        rank: Rank::Group,
+        name: "Donkeys",
+        singular: "Donkey",
+        synonyms: &["burro", "burros", "asno", "asnos"],
+        translations: &[
+            ("es", "Burros"),
+            ("de", "Esel"),
+            ("fr", "Ânes"),
+            ("it", "Asini"),
+            ("da", "Æsler"),
+        ],
+        ..BASE
+    },
+    Taxon {
+        id: "mule",
+        parent: Some("equine"),
+        rank: Rank::Group,
+        name: "Mules",
+        singular: "Mule",
+        synonyms: &["hinny", "hinnies", "mula", "mulas"],
+        translations: &[
+            ("es", "Mulas"),
+            ("de", "Maultiere"),
+            ("fr", "Mulets"),
+            ("it", "Muli"),
+            ("da", "Muldyr"),
+        ],
+        ..BASE
+    },
+    Taxon {
+        id: "chicken",
+        parent: Some("poultry"),
+        rank: Rank::Group,
+        name: "Chickens",
+        singular: "Chicken",
+        synonyms: &["broiler", "broilers", "hen", "hens", "rooster", "fowl"],
+        translations: &[
+            ("es", "Pollos"),
+            ("de", "Hühner"),
+            ("fr", "Poulets"),
+            ("it", "Polli"),
+            ("da", "Kyllinger"),
+        ],
+        ..BASE
+    },
+    Taxon {
+        id: "turkey",
+        parent: Some("poultry"),
+        rank: Rank::Group,
+        name: "Turkeys",
+        singular: "Turkey",
+        translations: &[
+            ("es", "Pavos"),
+            ("de", "Puten"),
+            ("fr", "Dindes"),
+            ("it", "Tacchini"),
+            ("da", "Kalkuner"),
+        ],
+        ..BASE
+    },
+    Taxon {
+        id: "duck",
+        parent: Some("poultry"),
+        rank: Rank::Group,
+        name: "Ducks",
+        singular: "Duck",
+        translations: &[
+            ("es", "Patos"),
+            ("de", "Enten"),
+            ("fr", "Canards"),
+            ("it", "Anatre"),
+            ("da", "Ænder"),
+        ],
+        ..BASE
+    },
+    Taxon {
+        id: "goose",
+        parent: Some("poultry"),
+        rank: Rank::Group,
+        name: "Geese",
+        singular: "Goose",
+        translations: &[
+            ("es", "Gansos"),
+            ("de", "Gänse"),
+            ("fr", "Oies"),
+            ("it", "Oche"),
+            ("da", "Gæs"),
+        ],
+        ..BASE
+    },
+    Taxon {
+        id: "pheasant",
+        parent: Some("poultry"),
+        rank: Rank::Group,
+        name: "Pheasants",
+        singular: "Pheasant",
+        translations: &[
+            ("es", "Faisanes"),
+            ("de", "Fasane"),
+            ("fr", "Faisans"),
+            ("it", "Fagiani"),
+            ("da", "Fasaner"),
+        ],
+        ..BASE
+    },
+    Taxon {
+        id: "quail",
+        parent: Some("poultry"),
+        rank: Rank::Group,
+        name: "Quail",
+        singular: "Quail",
+        translations: &[
+            ("es", "Codornices"),
+            ("de", "Wachteln"),
+            ("fr", "Cailles"),
+            ("it", "Quaglie"),
+            ("da", "Vagtler"),
+        ],
+        ..BASE
+    },
+    Taxon {
+        id: "guinea_fowl",
+        parent: Some("poultry"),
+        rank: Rank::Group,
+        name: "Guinea Fowl",
+        singular: "Guinea Fowl",
+        synonyms: &["guinea"],
+        translations: &[
+            ("es", "Pintadas"),
+            ("de", "Perlhühner"),
+            ("fr", "Pintades"),
+            ("it", "Faraone"),
+            ("da", "Perlehøns"),
+        ],
+        ..BASE
+    },
+    Taxon {
+        id: "ratite",
+        parent: Some("poultry"),
+        rank: Rank::Group,
+        name: "Ratites (Ostrich, Emu, etc.)",
+        singular: "Ratite (Ostrich/Emu)",
+        synonyms: &["ratites"],
+        translations: &[
+            ("es", "Ratites (avestruz, emú, etc.)"),
+            ("de", "Laufvögel (Strauß, Emu usw.)"),
+            ("fr", "Ratites (autruche, émeu, etc.)"),
+            ("it", "Ratiti (struzzo, emù, ecc.)"),
+            ("da", "Strudsefugle (struds, emu osv.)"),
+        ],
+        ..BASE
+    },
+    Taxon {
+        id: "pigeon",
+        parent: Some("poultry"),
+        rank: Rank::Group,
+        name: "Pigeons (Squab)",
+        singular: "Pigeon",
+        synonyms: &["squab", "dove", "doves"],
+        translations: &[
+            ("es", "Palomas (pichones)"),
+            ("de", "Tauben"),
+            ("fr", "Pigeons"),
+            ("it", "Piccioni"),
+            ("da", "Duer"),
+        ],
+        ..BASE
+    },
+    Taxon {
+        id: "exotic_poultry",
+        parent: Some("poultry"),
+        rank: Rank::Group,
+        name: "Exotic Poultry",
+        singular: "Exotic Poultry",
+        translations: &[
+            ("es", "Aves exóticas"),
+            ("de", "Exotisches Geflügel"),
+            ("fr", "Volailles exotiques"),
+            ("it", "Pollame esotico"),
+            ("da", "Eksotisk fjerkræ"),
+        ],
+        ..BASE
+    },
+    Taxon {
+        id: "other_poultry",
+        parent: Some("poultry"),
+        rank: Rank::Group,
+        name: "Other Poultry",
+        singular: "Other Voluntary Poultry",
+        translations: &[
+            ("es", "Otras aves de corral"),
+            ("de", "Sonstiges Geflügel"),
+            ("fr", "Autres volailles"),
+            ("it", "Altro pollame"),
+            ("da", "Andet fjerkræ"),
+        ],
+        ..BASE
+    },
+    Taxon {
+        id: "catfish",
+        parent: Some("fish"),
+        rank: Rank::Group,
+        name: "Catfish (Siluriformes)",
+        singular: "Catfish",
+        synonyms: &["siluriformes"],
+        translations: &[
+            ("es", "Bagres"),
+            ("de", "Welse"),
+            ("fr", "Poissons-chats"),
+            ("it", "Pesci gatto"),
+            ("da", "Malle"),
+        ],
+        ..BASE
+    },
+    // Animals farmed for fur.
+    Taxon {
+        id: "mink",
+        parent: Some("mammal"),
+        rank: Rank::Group,
+        name: "Mink",
+        singular: "Mink",
+        synonyms: &["minks"],
+        translations: &[
+            ("es", "Visones"),
+            ("de", "Nerze"),
+            ("fr", "Visons"),
+            ("it", "Visoni"),
+            ("da", "Mink"),
+        ],
+        ..BASE
+    },
+    Taxon {
+        id: "fox",
+        parent: Some("mammal"),
+        rank: Rank::Group,
+        name: "Foxes",
+        singular: "Fox",
+        synonyms: &["foxes"],
+        translations: &[
+            ("es", "Zorros"),
+            ("de", "Füchse"),
+            ("fr", "Renards"),
+            ("it", "Volpi"),
+            ("da", "Ræve"),
+        ],
+        ..BASE
+    },
+    Taxon {
+        id: "raccoon_dog",
+        parent: Some("mammal"),
+        rank: Rank::Group,
+        name: "Raccoon Dogs",
+        singular: "Raccoon Dog",
+        synonyms: &["finnraccoon"],
+        translations: &[
+            ("es", "Perros mapache"),
+            ("de", "Marderhunde"),
+            ("fr", "Chiens viverrins"),
+            ("it", "Cani procioni"),
+            ("da", "Mårhunde"),
+        ],
+        ..BASE
+    },
+    Taxon {
+        id: "chinchilla",
+        parent: Some("mammal"),
+        rank: Rank::Group,
+        name: "Chinchillas",
+        singular: "Chinchilla",
+        synonyms: &["chinchillas"],
+        translations: &[
+            ("es", "Chinchillas"),
+            ("de", "Chinchillas"),
+            ("fr", "Chinchillas"),
+            ("it", "Cincillà"),
+            ("da", "Chinchillaer"),
+        ],
+        ..BASE
+    },
+    // Farmed and processed aquatic animals.
+    Taxon {
+        id: "salmon",
+        parent: Some("fish"),
+        rank: Rank::Group,
+        name: "Salmon",
+        singular: "Salmon",
+        synonyms: &["salmonids"],
+        translations: &[
+            ("es", "Salmones"),
+            ("de", "Lachse"),
+            ("fr", "Saumons"),
+            ("it", "Salmoni"),
+            ("da", "Laks"),
+        ],
+        ..BASE
+    },
+    Taxon {
+        id: "trout",
+        parent: Some("fish"),
+        rank: Rank::Group,
+        name: "Trout",
+        singular: "Trout",
+        synonyms: &["trouts"],
+        translations: &[
+            ("es", "Truchas"),
+            ("de", "Forellen"),
+            ("fr", "Truites"),
+            ("it", "Trote"),
+            ("da", "Ørreder"),
+        ],
+        ..BASE
+    },
+    Taxon {
+        id: "tilapia",
+        parent: Some("fish"),
+        rank: Rank::Group,
+        name: "Tilapia",
+        singular: "Tilapia",
+        synonyms: &["tilapias"],
+        translations: &[
+            ("es", "Tilapias"),
+            ("de", "Tilapien"),
+            ("fr", "Tilapias"),
+            ("it", "Tilapie"),
+            ("da", "Tilapia"),
+        ],
+        ..BASE
+    },
+    Taxon {
+        id: "carp",
+        parent: Some("fish"),
+        rank: Rank::Group,
+        name: "Carp",
+        singular: "Carp",
+        synonyms: &["carps"],
+        translations: &[
+            ("es", "Carpas"),
+            ("de", "Karpfen"),
+            ("fr", "Carpes"),
+            ("it", "Carpe"),
+            ("da", "Karper"),
+        ],
+        ..BASE
+    },
+    Taxon {
+        id: "sturgeon",
+        parent: Some("fish"),
+        rank: Rank::Group,
+        name: "Sturgeon",
+        singular: "Sturgeon",
+        synonyms: &["sturgeons", "caviar"],
+        translations: &[
+            ("es", "Esturiones"),
+            ("de", "Störe"),
+            ("fr", "Esturgeons"),
+            ("it", "Storioni"),
+            ("da", "Stør"),
+        ],
+        ..BASE
+    },
+    Taxon {
+        id: "shrimp",
+        parent: Some("crustacean"),
+        rank: Rank::Group,
+        name: "Shrimp",
+        singular: "Shrimp",
+        synonyms: &["shrimps", "prawn", "prawns"],
+        translations: &[
+            ("es", "Camarones"),
+            ("de", "Garnelen"),
+            ("fr", "Crevettes"),
+            ("it", "Gamberi"),
+            ("da", "Rejer"),
+        ],
+        ..BASE
+    },
+    Taxon {
+        id: "crab",
+        parent: Some("crustacean"),
+        rank: Rank::Group,
+        name: "Crabs",
+        singular: "Crab",
+        synonyms: &["crabs"],
+        translations: &[
+            ("es", "Cangrejos"),
+            ("de", "Krabben"),
+            ("fr", "Crabes"),
+            ("it", "Granchi"),
+            ("da", "Krabber"),
+        ],
+        ..BASE
+    },
+    Taxon {
+        id: "crayfish",
+        parent: Some("crustacean"),
+        rank: Rank::Group,
+        name: "Crayfish",
+        singular: "Crayfish",
+        synonyms: &["crawfish", "crawdads"],
+        translations: &[
+            ("es", "Cangrejos de río"),
+            ("de", "Flusskrebse"),
+            ("fr", "Écrevisses"),
+            ("it", "Gamberi di fiume"),
+            ("da", "Krebs"),
+        ],
+        ..BASE
+    },
+    Taxon {
+        id: "lobster",
+        parent: Some("crustacean"),
+        rank: Rank::Group,
+        name: "Lobsters",
+        singular: "Lobster",
+        synonyms: &["lobsters"],
+        translations: &[
+            ("es", "Langostas"),
+            ("de", "Hummer"),
+            ("fr", "Homards"),
+            ("it", "Astici"),
+            ("da", "Hummere"),
+        ],
+        ..BASE
+    },
+    Taxon {
+        id: "oyster",
+        parent: Some("mollusc"),
+        rank: Rank::Group,
+        name: "Oysters",
+        singular: "Oyster",
+        synonyms: &["oysters"],
+        translations: &[
+            ("es", "Ostras"),
+            ("de", "Austern"),
+            ("fr", "Huîtres"),
+            ("it", "Ostriche"),
+            ("da", "Østers"),
+        ],
+        ..BASE
+    },
+    Taxon {
+        id: "mussel",
+        parent: Some("mollusc"),
+        rank: Rank::Group,
+        name: "Mussels",
+        singular: "Mussel",
+        synonyms: &["mussels"],
+        translations: &[
+            ("es", "Mejillones"),
+            ("de", "Miesmuscheln"),
+            ("fr", "Moules"),
+            ("it", "Cozze"),
+            ("da", "Blåmuslinger"),
+        ],
+        ..BASE
+    },
+    Taxon {
+        id: "clam",
+        parent: Some("mollusc"),
+        rank: Rank::Group,
+        name: "Clams",
+        singular: "Clam",
+        synonyms: &["clams"],
+        translations: &[
+            ("es", "Almejas"),
+            ("de", "Venusmuscheln"),
+            ("fr", "Palourdes"),
+            ("it", "Vongole"),
+            ("da", "Hjertemuslinger"),
+        ],
+        ..BASE
+    },
+    Taxon {
+        id: "scallop",
+        parent: Some("mollusc"),
+        rank: Rank::Group,
+        name: "Scallops",
+        singular: "Scallop",
+        synonyms: &["scallops"],
+        translations: &[
+            ("es", "Vieiras"),
+            ("de", "Jakobsmuscheln"),
+            ("fr", "Coquilles Saint-Jacques"),
+            ("it", "Capesante"),
+            ("da", "Kammuslinger"),
+        ],
+        ..BASE
+    },
+    // Animals counted in research facility reports.
+    Taxon {
+        id: "dog",
+        parent: Some("mammal"),
+        rank: Rank::Group,
+        name: "Dogs",
+        singular: "Dog",
+        synonyms: &["canine", "canines", "beagle", "beagles"],
+        translations: &[
+            ("es", "Perros"),
+            ("de", "Hunde"),
+            ("fr", "Chiens"),
+            ("it", "Cani"),
+            ("da", "Hunde"),
+        ],
+        ..BASE
+    },
+    Taxon {
+        id: "cat",
+        parent: Some("mammal"),
+        rank: Rank::Group,
+        name: "Cats",
+        singular: "Cat",
+        synonyms: &["feline", "felines"],
+        translations: &[
+            ("es", "Gatos"),
+            ("de", "Katzen"),
+            ("fr", "Chats"),
+            ("it", "Gatti"),
+            ("da", "Katte"),
+        ],
+        ..BASE
+    },
+    Taxon {
+        id: "guinea_pig",
+        parent: Some("mammal"),
+        rank: Rank::Group,
+        name: "Guinea Pigs",
+        singular: "Guinea Pig",
+        synonyms: &["cavy", "cavies"],
+        translations: &[
+            ("es", "Cobayas"),
+            ("de", "Meerschweinchen"),
+            ("fr", "Cochons d'Inde"),
+            ("it", "Cavie"),
+            ("da", "Marsvin"),
+        ],
+        ..BASE
+    },
+    Taxon {
+        id: "hamster",
+        parent: Some("mammal"),
+        rank: Rank::Group,
+        name: "Hamsters",
+        singular: "Hamster",
+        translations: &[
+            ("es", "Hámsteres"),
+            ("de", "Hamster"),
+            ("fr", "Hamsters"),
+            ("it", "Criceti"),
+            ("da", "Hamstere"),
+        ],
+        ..BASE
+    },
+    Taxon {
+        id: "non_human_primate",
+        parent: Some("mammal"),
+        rank: Rank::Group,
+        name: "Non-Human Primates",
+        singular: "Non-Human Primate",
+        synonyms: &[
+            "primate", "primates", "monkey", "monkeys", "macaque", "macaques",
+        ],
+        translations: &[
+            ("es", "Primates no humanos"),
+            ("de", "Nichtmenschliche Primaten"),
+            ("fr", "Primates non humains"),
+            ("it", "Primati non umani"),
+            ("da", "Ikke-menneskelige primater"),
+        ],
+        ..BASE
+    },
+    // --- FSIS slaughter classes ---
+    Taxon {
+        id: "beef_cow",
+        parent: Some("cattle"),
+        name: "Beef Cows",
+        singular: "Beef Cow",
+        ..BASE
+    },
+    Taxon {
+        id: "steer",
+        parent: Some("cattle"),
+        name: "Steers",
+        singular: "Steer",
+        ..BASE
+    },
+    Taxon {
+        id: "heifer",
+        parent: Some("cattle"),
+        name: "Heifers",
+        singular: "Heifer",
+        ..BASE
+    },
+    Taxon {
+        id: "bull_stag",
+        parent: Some("cattle"),
+        name: "Bulls & Stags",
+        singular: "Bull/Stag",
+        ..BASE
+    },
+    Taxon {
+        id: "dairy_cow",
+        parent: Some("cattle"),
+        name: "Dairy Cows",
+        singular: "Dairy Cow",
+        synonyms: &["dairy", "milk cow", "milk cows"],
+        translations: &[
+            ("es", "Vacas lecheras"),
+            ("de", "Milchkühe"),
+            ("fr", "Vaches laitières"),
+            ("it", "Vacche da latte"),
+            ("da", "Malkekøer"),
+        ],
+        ..BASE
+    },
+    Taxon {
+        id: "heavy_calf",
+        parent: Some("calf"),
+        name: "Heavy Calves",
+        singular: "Heavy Calf",
+        ..BASE
+    },
+    Taxon {
+        id: "bob_veal",
+        parent: Some("calf"),
+        name: "Bob Veal Calves",
+        singular: "Bob Veal Calf",
+        ..BASE
+    },
+    Taxon {
+        id: "formula_fed_veal",
+        parent: Some("calf"),
+        name: "Formula-Fed Veal Calves",
+        singular: "Formula-Fed Veal Calf",
+        ..BASE
+    },
+    Taxon {
+        id: "non_formula_fed_veal",
+        parent: Some("calf"),
+        name: "Non-Formula-Fed Veal Calves",
+        singular: "Non-Formula-Fed Veal Calf",
+        ..BASE
+    },
+    Taxon {
+        id: "market_swine",
+        parent: Some("pig"),
+        name: "Market Pigs",
+        singular: "Market Pig",
+        ..BASE
+    },
+    Taxon {
+        id: "sow",
+        parent: Some("pig"),
+        name: "Sows",
+        singular: "Sow",
+        ..BASE
+    },
+    Taxon {
+        id: "roaster_swine",
+        parent: Some("pig"),
+        name: "Roaster Pigs",
+        singular: "Roaster Pig",
+        ..BASE
+    },
+    Taxon {
+        id: "boar_stag_swine",
+        parent: Some("pig"),
+        name: "Boars & Stags",
+        singular: "Boar/Stag",
+        ..BASE
+    },
+    Taxon {
+        id: "stag_swine",
+        parent: Some("pig"),
+        name: "Stag Pigs",
+        singular: "Stag Pig",
+        ..BASE
+    },
+    Taxon {
+        id: "feral_swine",
+        parent: Some("pig"),
+        name: "Feral Pigs",
+        singular: "Feral Pig",
+        synonyms: &["wild boar", "wild pig", "wild pigs"],
+        ..BASE
+    },
+    Taxon {
+        id: "young_goat",
+        parent: Some("goat"),
+        name: "Young Goats",
+        singular: "Young Goat",
+        ..BASE
+    },
+    Taxon {
+        id: "adult_goat",
+        parent: Some("goat"),
+        name: "Adult Goats",
+        singular: "Adult Goat",
+        ..BASE
+    },
+    Taxon {
+        id: "lamb",
+        parent: Some("sheep"),
+        name: "Lambs",
+        singular: "Lamb",
+        ..BASE
+    },
+    Taxon {
+        id: "deer",
+        parent: Some("deer_reindeer"),
+        name: "Deer",
+        singular: "Deer",
+        ..BASE
+    },
+    Taxon {
+        id: "reindeer",
+        parent: Some("deer_reindeer"),
+        name: "Reindeer",
+        singular: "Reindeer",
+        synonyms: &["caribou"],
+        ..BASE
+    },
+    Taxon {
+        id: "bison",
+        parent: Some("bison_buffalo"),
+        name: "Bison",
+        singular: "Bison",
+        ..BASE
+    },
+    Taxon {
+        id: "buffalo",
+        parent: Some("bison_buffalo"),
+        name: "Buffalo",
+        singular: "Buffalo",
+        ..BASE
+    },
+    Taxon {
+        id: "water_buffalo",
+        parent: Some("bison_buffalo"),
+        name: "Water Buffalo",
+        singular: "Water Buffalo",
+        ..BASE
+    },
+    Taxon {
+        id: "cattalo",
+        parent: Some("bison_buffalo"),
+        name: "Cattalo",
+        singular: "Cattalo",
+        synonyms: &["beefalo"],
+        ..BASE
+    },
+    Taxon {
+        id: "young_chicken",
+        parent: Some("chicken"),
+        name: "Young Chickens",
+        singular: "Young Chicken",
+        ..BASE
+    },
+    Taxon {
+        id: "light_fowl",
+        parent: Some("chicken"),
+        name: "Light Fowl",
+        singular: "Light Fowl",
+        ..BASE
+    },
+    Taxon {
+        id: "heavy_fowl",
+        parent: Some("chicken"),
+        name: "Heavy Fowl",
+        singular: "Heavy Fowl",
+        ..BASE
+    },
+    Taxon {
+        id: "capon",
+        parent: Some("chicken"),
+        name: "Capons",
+        singular: "Capon",
+        ..BASE
+    },
+    Taxon {
+        id: "laying_hen",
+        parent: Some("chicken"),
+        name: "Laying Hens",
+        singular: "Laying Hen",
+        synonyms: &[
+            "layer",
+            "layers",
+            "laying hens",
+            "egg-laying hens",
+            "pullet",
+            "pullets",
+        ],
+        ..BASE
+    },
+    Taxon {
+        id: "young_turkey",
+        parent: Some("turkey"),
+        name: "Young Turkeys",
+        singular: "Young Turkey",
+        ..BASE
+    },
+    Taxon {
+        id: "young_breeder_turkey",
+        parent: Some("turkey"),
+        name: "Young Breeder Turkeys",
+        singular: "Young Breeder Turkey",
+        ..BASE
+    },
+    Taxon {
+        id: "old_breeder_turkey",
+        parent: Some("turkey"),
+        name: "Old Breeder Turkeys",
+        singular: "Old Breeder Turkey",
+        ..BASE
+    },
+    Taxon {
+        id: "fryer_roaster_turkey",
+        parent: Some("turkey"),
+        name: "Fryer-Roaster Turkeys",
+        singular: "Fryer-Roaster Turkey",
+        ..BASE
+    },
+    Taxon {
+        id: "ostrich",
+        parent: Some("ratite"),
+        name: "Ostriches",
+        singular: "Ostrich",
+        ..BASE
+    },
+    Taxon {
+        id: "emu",
+        parent: Some("ratite"),
+        name: "Emus",
+        singular: "Emu",
+        ..BASE
+    },
+    Taxon {
+        id: "rhea",
+        parent: Some("ratite"),
+        name: "Rheas",
+        singular: "Rhea",
+        ..BASE
+    },
+];
+
+pub fn taxon(id: &str) -> Option<&'static Taxon> {
+    TAXA.iter().find(|taxon| taxon.id == id)
+}
+
+/// Finds a taxon by ID, English name, synonym or translated name,
+/// ignoring case ("Pigs", "pork", "Schweine" and "pig" all find pigs).
+/// Groups win over other ranks sharing a term.
+pub fn lookup(term: &str) -> Option<&'static Taxon> {
+    let term = term.trim().to_lowercase();
+    if term.is_empty() {
+        return None;
+    }
+
+    let id = term.replace([' ', '-'], "_");
+    let same = |name: &str| name.chars().flat_map(char::to_lowercase).eq(term.chars());
+    let matches = |taxon: &&Taxon| {
+        taxon.id == id
+            || same(taxon.name)
+            || same(taxon.singular)
+            || taxon.meat.is_some_and(same)
+            || taxon.synonyms.iter().any(|s| same(s))
+            || taxon.translations.iter().any(|(_, name)| same(name))
+    };
+
+    TAXA.iter()
+        .filter(|taxon| taxon.rank == Rank::Group)
+        .find(matches)
+        .or_else(|| TAXA.iter().find(matches))
+}
+
+/// The taxon itself followed by its parents up to the class.
+pub fn lineage(id: &str) -> impl Iterator<Item = &'static Taxon> {
+    std::iter::successors(taxon(id), |taxon| taxon.parent())
+}
+
+/// Whether `id` is `ancestor` or lies below it in the hierarchy.
+pub fn is_within(id: &str, ancestor: &str) -> bool {
+    lineage(id).any(|taxon| taxon.id == ancestor)
+}
+
+/// The group a taxon is summarized under, e.g. "dairy_cow" -> cattle.
+pub fn group_of(id: &str) -> Option<&'static Taxon> {
+    lineage(id).find(|taxon| taxon.rank == Rank::Group)
+}
+
+/// Group IDs for taxa, deduplicated and in display order. Broader taxa with
+/// no group, such as "poultry", are kept as they are.
+pub fn groups(ids: impl IntoIterator<Item = &'static str>) -> Vec<&'static str> {
+    let mut groups: Vec<&'static str> = Vec::new();
+    for id in ids {
+        let group = group_of(id).map_or(id, |taxon| taxon.id);
+        if !groups.contains(&group) {
+            groups.push(group);
+        }
+    }
+    groups.sort_by_key(|id| TAXA.iter().position(|taxon| taxon.id == *id));
+    groups
+}
+
+/// Taxa named by the words of free text such as an activity description
+/// ("Mixed Slaughterhouse (Cattle Slaughterhouse, Pig Slaughterhouse)").
+pub fn mentioned_in(text: &str) -> Vec<&'static Taxon> {
+    let mut found: Vec<&'static Taxon> = Vec::new();
+    for word in text.split(|c: char| !c.is_alphabetic()) {
+        if let Some(taxon) = lookup(word)
+            && !found.iter().any(|t| t.id == taxon.id)
+        {
+            found.push(taxon);
+        }
+    }
+    found
+}
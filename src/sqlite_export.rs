@@ -0,0 +1,334 @@
+// Until Every Cage is Empty
+// Copyright (C) 2025 Eli Perez
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+// Contact the developer directly at untileverycageproject@protonmail.com
+//! GET /api/export/sqlite: every dataset in one SQLite database, for
+//! researchers' own SQL and the mobile app's offline mode.
+//!
+//! A table per dataset with the fields its endpoint serves as columns (lists
+//! joined with "; " as in CSV), indexes on the columns records are looked up
+//! by, and a `metadata` table with when the data was built. A dataset with no
+//! records is left out. The file is built once per data revision, in memory
+//! and then written out with `VACUUM INTO`.
+
+use std::collections::HashMap;
+use std::error::Error;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, RwLock};
+
+use axum::body::Bytes;
+use axum::extract::State;
+use axum::http::header;
+use axum::response::IntoResponse;
+use rusqlite::types::Value;
+use rusqlite::{Connection, params_from_iter};
+use serde::Serialize;
+use serde_json::Value as Json;
+
+use crate::AppState;
+use crate::csv_export::cell;
+use crate::errors::ApiError;
+use crate::geojson::to_object;
+
+const CONTENT_TYPE: &str = "application/vnd.sqlite3";
+
+/// The last database built, with the location index revision it was built
+/// from.
+pub(crate) type DatabaseCache = Arc<RwLock<Option<(u64, Bytes)>>>;
+
+#[derive(Clone, Copy, PartialEq, PartialOrd)]
+enum Kind {
+    Integer,
+    Real,
+    Text,
+}
+
+impl Kind {
+    fn of(value: &Json) -> Option<Kind> {
+        match value {
+            Json::Null => None,
+            Json::Bool(_) => Some(Kind::Integer),
+            Json::Number(n) if n.as_i64().is_some() => Some(Kind::Integer),
+            Json::Number(_) => Some(Kind::Real),
+            _ => Some(Kind::Text),
+        }
+    }
+
+    fn declared(self) -> &'static str {
+        match self {
+            Kind::Integer => "INTEGER",
+            Kind::Real => "REAL",
+            Kind::Text => "TEXT",
+        }
+    }
+
+    /// `value` as a column of this kind holds it.
+    fn value(self, value: &Json) -> Value {
+        match (value, self) {
+            (Json::Null, _) => Value::Null,
+            (Json::Bool(b), Kind::Integer | Kind::Real) => Value::Integer(i64::from(*b)),
+            (Json::Number(n), Kind::Integer) => n
+                .as_i64()
+                .map(Value::Integer)
+                .unwrap_or_else(|| Value::Real(n.as_f64().unwrap_or_default())),
+            (Json::Number(n), Kind::Real) => Value::Real(n.as_f64().unwrap_or_default()),
+            (other, _) => Value::Text(cell(other)),
+        }
+    }
+}
+
+struct Table {
+    name: String,
+    /// Names and declared types, e.g. ("city", "TEXT").
+    columns: Vec<(String, &'static str)>,
+    rows: Vec<Vec<Value>>,
+    /// Columns indexed, each on its own as `<table>_<column>`.
+    indexes: Vec<String>,
+}
+
+/// A double-quoted identifier.
+fn quote(name: &str) -> String {
+    format!("\"{}\"", name.replace('"', "\"\""))
+}
+
+/// `tables` as a SQLite database file.
+fn write(tables: &[Table]) -> Result<Vec<u8>, Box<dyn Error + Send + Sync>> {
+    static FILES: AtomicUsize = AtomicUsize::new(0);
+    let mut connection = Connection::open_in_memory()?;
+    let transaction = connection.transaction()?;
+    for table in tables {
+        let columns: Vec<String> = table
+            .columns
+            .iter()
+            .map(|(name, kind)| format!("{} {}", quote(name), kind))
+            .collect();
+        transaction.execute(
+            &format!(
+                "CREATE TABLE {} ({})",
+                quote(&table.name),
+                columns.join(", ")
+            ),
+            [],
+        )?;
+        let placeholders = vec!["?"; table.columns.len()].join(", ");
+        let mut insert = transaction.prepare(&format!(
+            "INSERT INTO {} VALUES ({})",
+            quote(&table.name),
+            placeholders
+        ))?;
+        for row in &table.rows {
+            insert.execute(params_from_iter(row))?;
+        }
+        for column in &table.indexes {
+            if !table.columns.iter().any(|(name, _)| name == column) {
+                continue;
+            }
+            transaction.execute(
+                &format!(
+                    "CREATE INDEX {} ON {} ({})",
+                    quote(&format!("{}_{}", table.name, column)),
+                    quote(&table.name),
+                    quote(column)
+                ),
+                [],
+            )?;
+        }
+    }
+    transaction.commit()?;
+
+    let path = std::env::temp_dir().join(format!(
+        "sqlite-export-{}-{}.sqlite",
+        std::process::id(),
+        FILES.fetch_add(1, Ordering::Relaxed)
+    ));
+    let _ = std::fs::remove_file(&path);
+    connection.execute("VACUUM INTO ?1", [path.to_string_lossy()])?;
+    let bytes = std::fs::read(&path);
+    let _ = std::fs::remove_file(&path);
+    Ok(bytes?)
+}
+
+/// A table of `items`, with a column per field any of them has in the order
+/// they first appear, typed by the values in it. `indexed` columns that
+/// exist are indexed.
+fn table<T: Serialize>(
+    name: &str,
+    items: impl IntoIterator<Item = T>,
+    indexed: &[&str],
+) -> Result<Table, serde_json::Error> {
+    let objects = items
+        .into_iter()
+        .map(|item| to_object(&item))
+        .collect::<Result<Vec<_>, _>>()?;
+    let mut columns: Vec<(String, Option<Kind>)> = Vec::new();
+    let mut positions: HashMap<String, usize> = HashMap::new();
+    for object in &objects {
+        for (key, value) in object {
+            let position = *positions.entry(key.clone()).or_insert_with(|| {
+                columns.push((key.clone(), None));
+                columns.len() - 1
+            });
+            let kind = &mut columns[position].1;
+            *kind = match (*kind, Kind::of(value)) {
+                (Some(a), Some(b)) if b > a => Some(b),
+                (None, b) => b,
+                (a, _) => a,
+            };
+        }
+    }
+    let kinds: Vec<Kind> = columns
+        .iter()
+        .map(|(_, kind)| kind.unwrap_or(Kind::Text))
+        .collect();
+    let rows = objects
+        .iter()
+        .map(|object| {
+            columns
+                .iter()
+                .zip(&kinds)
+                .map(|((key, _), kind)| object.get(key).map_or(Value::Null, |v| kind.value(v)))
+                .collect()
+        })
+        .collect();
+    Ok(Table {
+        name: name.to_string(),
+        columns: columns
+            .into_iter()
+            .zip(kinds)
+            .map(|((key, _), kind)| (key, kind.declared()))
+            .collect(),
+        rows,
+        indexes: indexed.iter().map(|column| column.to_string()).collect(),
+    })
+}
+
+/// The database file for the current data.
+fn database(state: &AppState) -> Result<Vec<u8>, Box<dyn Error + Send + Sync>> {
+    let index = state.location_index();
+    let reports = state.reports();
+    let facilities = state.facilities();
+    let tables = vec![
+        table(
+            "locations",
+            index.locations.iter(),
+            &[
+                "facility_id",
+                "establishment_id",
+                "country",
+                "state",
+                "fips_code",
+                "zip",
+            ],
+        )?,
+        table(
+            "facilities",
+            facilities.facilities.iter(),
+            &["id", "source_id", "country", "state"],
+        )?,
+        table(
+            "aphis_reports",
+            reports.aphis.iter(),
+            &["facility_id", "Certificate Number", "Customer Number_x"],
+        )?,
+        table(
+            "inspection_reports",
+            reports.inspections.iter(),
+            &[
+                "facility_id",
+                "Certificate Number",
+                "Customer Number",
+                "State",
+            ],
+        )?,
+        table(
+            "aquaculture",
+            reports.aquaculture.iter(),
+            &["facility_id", "state"],
+        )?,
+        table(
+            "fur_farms",
+            reports.fur_farms.iter(),
+            &["facility_id", "state"],
+        )?,
+        table(
+            "egg_farms",
+            reports.egg_farms.iter(),
+            &["facility_id", "state"],
+        )?,
+        table("cafos", reports.cafos.iter(), &["facility_id", "state"])?,
+        table("equine", reports.equine.iter(), &["facility_id", "state"])?,
+        table(
+            "citations",
+            reports.citations.rows(),
+            &["certificate_number", "customer_number", "inspection_id"],
+        )?,
+        table(
+            "enforcement",
+            reports.enforcement.records(),
+            &["establishment_id", "date"],
+        )?,
+        table(
+            "metadata",
+            [("built_at", index.built_at.to_rfc3339())]
+                .map(|(key, value)| serde_json::json!({ "key": key, "value": value })),
+            &[],
+        )?,
+    ];
+    // A dataset without records has no columns to make a table of.
+    let tables: Vec<Table> = tables
+        .into_iter()
+        .filter(|table| !table.columns.is_empty())
+        .collect();
+    write(&tables)
+}
+
+/// GET /api/export/sqlite
+pub async fn get_sqlite_export_handler(State(state): State<AppState>) -> impl IntoResponse {
+    let revision = state.location_index().revision;
+    let cached = state.database.read().unwrap().clone();
+    let bytes = match cached {
+        Some((built, bytes)) if built == revision => bytes,
+        _ => {
+            let building = state.clone();
+            let built = tokio::task::spawn_blocking(move || database(&building))
+                .await
+                .map_err(|e| e.to_string())
+                .and_then(|result| result.map_err(|e| e.to_string()));
+            match built {
+                Ok(bytes) => {
+                    let bytes = Bytes::from(bytes);
+                    *state.database.write().unwrap() = Some((revision, bytes.clone()));
+                    bytes
+                }
+                Err(e) => {
+                    return ApiError::internal(format!("Failed to build the database: {}", e))
+                        .into_response();
+                }
+            }
+        }
+    };
+    (
+        [
+            (header::CONTENT_TYPE, CONTENT_TYPE),
+            (
+                header::CONTENT_DISPOSITION,
+                "attachment; filename=\"untileverycage.sqlite\"",
+            ),
+        ],
+        bytes,
+    )
+        .into_response()
+}
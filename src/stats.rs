@@ -0,0 +1,195 @@
+// Until Every Cage is Empty
+// Copyright (C) 2025 Eli Perez
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+// Contact the developer directly at untileverycageproject@protonmail.com
+//! Aggregate counts for dashboards and infographics ("X slaughterhouses in
+//! your state"), so they don't need the raw datasets.
+
+use std::collections::BTreeMap;
+
+use axum::Json;
+use axum::extract::{Query, State};
+use axum::response::IntoResponse;
+use serde::{Deserialize, Serialize};
+
+use crate::AppState;
+use crate::geocode;
+use crate::timeseries::YearTotals;
+use crate::volume::{self, Totals};
+
+#[derive(Serialize, Default)]
+struct LocationStats {
+    total: usize,
+    by_country: BTreeMap<String, usize>,
+    /// Per country, then per state as the dataset spells it.
+    by_state: BTreeMap<String, BTreeMap<String, usize>>,
+    /// By activity ID.
+    by_activity: BTreeMap<&'static str, usize>,
+    /// By species group ID.
+    by_species: BTreeMap<&'static str, usize>,
+    by_slaughter_volume_category: BTreeMap<String, usize>,
+    by_processing_volume_category: BTreeMap<String, usize>,
+    /// Head slaughtered per year, from the volume categories.
+    estimated_annual_slaughter: Totals,
+}
+
+#[derive(Serialize, Default)]
+struct AphisStats {
+    total: usize,
+    /// Including earlier years' reports (see `timeseries`).
+    by_year: BTreeMap<String, YearTotals>,
+}
+
+#[derive(Serialize, Default)]
+struct InspectionStats {
+    total: usize,
+    by_license_type: BTreeMap<String, usize>,
+}
+
+/// One country's records in each dataset.
+#[derive(Serialize, Default)]
+struct CountryCounts {
+    locations: usize,
+    aphis_reports: usize,
+    inspection_reports: usize,
+    aquaculture: usize,
+    fur_farms: usize,
+    egg_farms: usize,
+    cafos: usize,
+    equine: usize,
+}
+
+#[derive(Serialize)]
+struct Stats {
+    locations: LocationStats,
+    aphis_reports: AphisStats,
+    inspection_reports: InspectionStats,
+    /// Records per country, across every dataset.
+    by_country: BTreeMap<String, CountryCounts>,
+}
+
+#[derive(Deserialize)]
+pub struct StatsParams {
+    /// Limits the counts to one country, by directory or ISO 3166-1 alpha-2
+    /// code. APHIS and inspection reports are only counted for `us`.
+    #[serde(alias = "country")]
+    country_code: Option<String>,
+}
+
+/// Adds one to `key`'s count, leaving blank values out.
+fn count<K: Ord>(counts: &mut BTreeMap<K, usize>, key: K, blank: bool) {
+    if !blank {
+        *counts.entry(key).or_default() += 1;
+    }
+}
+
+/// GET /api/stats
+pub async fn get_stats_handler(
+    State(state): State<AppState>,
+    Query(params): Query<StatsParams>,
+) -> impl IntoResponse {
+    let wanted = params.country_code.as_deref().map(geocode::country_code);
+    let in_country = |country: &str| wanted.as_deref().is_none_or(|wanted| wanted == country);
+    let mut by_country: BTreeMap<String, CountryCounts> = BTreeMap::new();
+
+    let index = state.location_index();
+    let mut locations = LocationStats::default();
+    for loc in index
+        .locations
+        .iter()
+        .filter(|loc| in_country(&loc.country))
+    {
+        locations.total += 1;
+        by_country.entry(loc.country.clone()).or_default().locations += 1;
+        count(&mut locations.by_country, loc.country.clone(), false);
+        let state = loc.state.trim();
+        count(
+            locations.by_state.entry(loc.country.clone()).or_default(),
+            state.to_string(),
+            state.is_empty(),
+        );
+        for activity in &loc.activities {
+            count(&mut locations.by_activity, activity.id(), false);
+        }
+        for species in &loc.species {
+            count(&mut locations.by_species, species, false);
+        }
+        count(
+            &mut locations.by_slaughter_volume_category,
+            loc.slaughter_volume_category.clone(),
+            loc.slaughter_volume_category.is_empty(),
+        );
+        count(
+            &mut locations.by_processing_volume_category,
+            loc.processing_volume_category.clone(),
+            loc.processing_volume_category.is_empty(),
+        );
+        if let Some(estimate) = volume::annual_slaughter(&loc.slaughter_volume_category) {
+            locations
+                .estimated_annual_slaughter
+                .add(estimate, &loc.species_slaughtered);
+        }
+    }
+    locations.by_state.retain(|_, states| !states.is_empty());
+
+    let reports = state.reports();
+    let mut aphis_reports = AphisStats::default();
+    let mut inspection_reports = InspectionStats::default();
+    if in_country("us") {
+        aphis_reports.total = reports.aphis.len();
+        aphis_reports.by_year = reports.aphis_years.aggregate();
+        for report in reports.inspections.iter() {
+            inspection_reports.total += 1;
+            count(
+                &mut inspection_reports.by_license_type,
+                report.license_type.clone(),
+                report.license_type.is_empty(),
+            );
+        }
+        if aphis_reports.total + inspection_reports.total > 0 {
+            let us = by_country.entry("us".to_string()).or_default();
+            us.aphis_reports = aphis_reports.total;
+            us.inspection_reports = inspection_reports.total;
+        }
+    }
+    let mut tally = |country: &str, dataset: fn(&mut CountryCounts) -> &mut usize| {
+        if in_country(country) {
+            *dataset(by_country.entry(country.to_string()).or_default()) += 1;
+        }
+    };
+    for site in reports.aquaculture.iter() {
+        tally(&site.country, |counts| &mut counts.aquaculture);
+    }
+    for farm in reports.fur_farms.iter() {
+        tally(&farm.country, |counts| &mut counts.fur_farms);
+    }
+    for farm in reports.egg_farms.iter() {
+        tally(&farm.country, |counts| &mut counts.egg_farms);
+    }
+    for permit in reports.cafos.iter() {
+        tally(&permit.country, |counts| &mut counts.cafos);
+    }
+    for site in reports.equine.iter() {
+        tally(&site.country, |counts| &mut counts.equine);
+    }
+
+    Json(Stats {
+        locations,
+        aphis_reports,
+        inspection_reports,
+        by_country,
+    })
+}
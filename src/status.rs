@@ -0,0 +1,159 @@
+// Until Every Cage is Empty
+// Copyright (C) 2025 Eli Perez
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+// Contact the developer directly at untileverycageproject@protonmail.com
+//! Whether a facility still operates. Directories only list establishments
+//! holding a grant, so locations are active unless `<country>/status.csv`
+//! or a moderator's flag says otherwise; APHIS records carry their own
+//! certificate status.
+
+use std::collections::HashMap;
+use std::error::Error;
+
+use serde::{Deserialize, Serialize};
+
+use crate::filter::split_list;
+use crate::schema;
+use crate::store::Dataset;
+
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum FacilityStatus {
+    #[default]
+    Active,
+    Closed,
+    /// The license or grant is suspended or revoked.
+    Suspended,
+}
+
+impl FacilityStatus {
+    /// Reads a status as sources and people write it: APHIS's "Cancelled",
+    /// FSIS's "Withdrawn", or the IDs themselves.
+    pub fn parse(value: &str) -> Option<FacilityStatus> {
+        match value.trim().to_lowercase().as_str() {
+            "active" | "open" | "licensed" => Some(FacilityStatus::Active),
+            "closed" | "cancelled" | "canceled" | "inactive" | "expired" | "withdrawn" => {
+                Some(FacilityStatus::Closed)
+            }
+            "suspended" | "revoked" => Some(FacilityStatus::Suspended),
+            _ => None,
+        }
+    }
+
+    pub fn id(self) -> &'static str {
+        match self {
+            FacilityStatus::Active => "active",
+            FacilityStatus::Closed => "closed",
+            FacilityStatus::Suspended => "suspended",
+        }
+    }
+
+    /// Display name in a language code, falling back to English.
+    pub fn label_in(self, lang: &str) -> &'static str {
+        match (self, lang) {
+            (FacilityStatus::Active, "es") => "Activo",
+            (FacilityStatus::Active, "de" | "da") => "Aktiv",
+            (FacilityStatus::Active, "fr") => "Actif",
+            (FacilityStatus::Active, "it") => "Attivo",
+            (FacilityStatus::Active, _) => "Active",
+            (FacilityStatus::Closed, "es") => "Cerrado",
+            (FacilityStatus::Closed, "de") => "Geschlossen",
+            (FacilityStatus::Closed, "fr") => "Fermé",
+            (FacilityStatus::Closed, "it") => "Chiuso",
+            (FacilityStatus::Closed, "da") => "Lukket",
+            (FacilityStatus::Closed, _) => "Closed",
+            (FacilityStatus::Suspended, "es") => "Suspendido",
+            (FacilityStatus::Suspended, "de") => "Ausgesetzt",
+            (FacilityStatus::Suspended, "fr") => "Suspendu",
+            (FacilityStatus::Suspended, "it") => "Sospeso",
+            (FacilityStatus::Suspended, "da") => "Suspenderet",
+            (FacilityStatus::Suspended, _) => "Suspended",
+        }
+    }
+
+    /// The status of an APHIS certificate. Anything unrecognized counts as
+    /// active, as the certificate is still listed.
+    pub fn from_certificate(status: &str) -> FacilityStatus {
+        FacilityStatus::parse(status).unwrap_or_default()
+    }
+}
+
+/// A row of `<country>/status.csv`: `establishment_id,status,date`. Other
+/// columns, such as where the status comes from, are for maintainers.
+#[derive(Deserialize)]
+struct StatusRow {
+    establishment_id: String,
+    status: String,
+    #[serde(default)]
+    date: String,
+}
+
+#[derive(Debug, Clone)]
+pub struct StatusEntry {
+    pub status: FacilityStatus,
+    /// When the status took effect, as YYYY-MM-DD.
+    pub date: String,
+}
+
+/// Every country's status.csv, keyed by `(country, establishment_id)`.
+pub fn read_statuses(
+    dataset: &Dataset,
+) -> Result<HashMap<(String, String), StatusEntry>, Box<dyn Error>> {
+    let mut statuses = HashMap::new();
+    for country in dataset.countries() {
+        let path = format!("{}/status.csv", country);
+        let Some(csv_data) = dataset.get(&path) else {
+            continue;
+        };
+        schema::check::<StatusRow>(&path, csv_data)?;
+        let mut reader = csv::Reader::from_reader(csv_data);
+        for (row, result) in reader.deserialize::<StatusRow>().enumerate() {
+            let record = result?;
+            let status = FacilityStatus::parse(&record.status).ok_or_else(|| {
+                format!(
+                    "{} row {}: unknown status {:?}, expected active, closed or suspended",
+                    path,
+                    row + 2,
+                    record.status
+                )
+            })?;
+            let key = (country.to_string(), record.establishment_id);
+            statuses.insert(
+                key,
+                StatusEntry {
+                    status,
+                    date: record.date,
+                },
+            );
+        }
+    }
+    Ok(statuses)
+}
+
+/// Parses `?status=active,suspended`. Empty means any status.
+pub(crate) fn parse_filter(value: Option<&str>) -> Result<Vec<FacilityStatus>, String> {
+    split_list(value)
+        .iter()
+        .map(|term| {
+            FacilityStatus::parse(term).ok_or_else(|| {
+                format!(
+                    "Unknown status {}, expected active, closed or suspended",
+                    term
+                )
+            })
+        })
+        .collect()
+}
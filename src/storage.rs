@@ -0,0 +1,294 @@
+// Until Every Cage is Empty
+// Copyright (C) 2025 Eli Perez
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+// Contact the developer directly at untileverycageproject@protonmail.com
+//! Where location queries are answered. `Storage` is the seam between the
+//! location list handlers and the data, with two backends chosen by
+//! `STORAGE`:
+//!
+//! - `memory` (the default): the in-memory `LocationIndex`.
+//! - `sqlite`: a SQLite database at `STORAGE_PATH` (in memory when unset)
+//!   that every rebuild loads the locations into, filtered with indexes and
+//!   an R*Tree for bounding boxes, and returning each row as the JSON it
+//!   was stored as.
+//!
+//! The other endpoints read the in-memory index either way.
+
+use std::error::Error;
+use std::sync::Mutex;
+
+use rusqlite::types::Value as SqlValue;
+use rusqlite::{Connection, params, params_from_iter};
+use serde::{Serialize, Serializer};
+
+use crate::LocationResponse;
+use crate::filter::LocationFilter;
+use crate::geojson;
+use crate::settings::Settings;
+use crate::spatial::{BoundingBox, LocationIndex};
+use crate::species;
+
+/// What to select. Every part is optional; an empty query selects everything.
+#[derive(Default)]
+pub(crate) struct LocationQuery<'a> {
+    pub(crate) country_code: Option<&'a str>,
+    pub(crate) filter: Option<&'a LocationFilter>,
+    pub(crate) bbox: Option<&'a BoundingBox>,
+}
+
+impl LocationQuery<'_> {
+    fn matches(&self, loc: &LocationResponse) -> bool {
+        self.country_code
+            .is_none_or(|country| loc.country == country)
+            && self.filter.is_none_or(|filter| filter.matches(loc))
+    }
+}
+
+/// A location as a backend returns it: borrowed from the index, or the row
+/// a database stored.
+pub(crate) enum StoredLocation<'a> {
+    Indexed(&'a LocationResponse),
+    Row(serde_json::Value),
+}
+
+impl StoredLocation<'_> {
+    pub(crate) fn priority_score(&self) -> f64 {
+        match self {
+            StoredLocation::Indexed(loc) => loc.priority_score,
+            StoredLocation::Row(row) => row["priority_score"].as_f64().unwrap_or_default(),
+        }
+    }
+}
+
+impl Serialize for StoredLocation<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            StoredLocation::Indexed(loc) => loc.serialize(serializer),
+            StoredLocation::Row(row) => row.serialize(serializer),
+        }
+    }
+}
+
+impl geojson::Feature for StoredLocation<'_> {}
+
+pub(crate) trait Storage {
+    /// The locations matching `query`, in storage order.
+    fn locations<'a>(&'a self, query: &LocationQuery) -> Result<Vec<StoredLocation<'a>>, String>;
+}
+
+impl Storage for LocationIndex {
+    fn locations<'a>(&'a self, query: &LocationQuery) -> Result<Vec<StoredLocation<'a>>, String> {
+        let candidates: Vec<&LocationResponse> = match query.bbox {
+            Some(bbox) => self.in_bbox(bbox),
+            None => self.locations.iter().collect(),
+        };
+        Ok(candidates
+            .into_iter()
+            .filter(|loc| query.matches(loc))
+            .map(StoredLocation::Indexed)
+            .collect())
+    }
+}
+
+/// The backend `STORAGE` chose.
+#[derive(Default)]
+pub(crate) enum Backend {
+    #[default]
+    Memory,
+    Sqlite(SqliteStorage),
+}
+
+impl Backend {
+    pub(crate) fn from_settings(settings: &Settings) -> Result<Self, Box<dyn Error>> {
+        match settings.get("STORAGE") {
+            None | Some("") | Some("memory") => Ok(Backend::Memory),
+            Some("sqlite") => {
+                let path = settings.get("STORAGE_PATH").unwrap_or(":memory:");
+                Ok(Backend::Sqlite(SqliteStorage::open(path)?))
+            }
+            Some(other) => Err(format!("STORAGE must be memory or sqlite, not {}", other).into()),
+        }
+    }
+
+    /// The backend to query, given the index as of the last rebuild.
+    pub(crate) fn with<'a>(&'a self, index: &'a LocationIndex) -> &'a dyn Storage {
+        match self {
+            Backend::Memory => index,
+            Backend::Sqlite(database) => database,
+        }
+    }
+}
+
+const SCHEMA: &str = "
+    CREATE TABLE IF NOT EXISTS locations (
+        id INTEGER PRIMARY KEY,
+        country TEXT NOT NULL,
+        state TEXT NOT NULL,
+        type TEXT NOT NULL,
+        status TEXT NOT NULL,
+        grant_date TEXT,
+        latitude REAL NOT NULL,
+        longitude REAL NOT NULL,
+        body TEXT NOT NULL
+    );
+    CREATE INDEX IF NOT EXISTS locations_by_place ON locations (country, state);
+    CREATE INDEX IF NOT EXISTS locations_by_grant_date ON locations (grant_date);
+    CREATE TABLE IF NOT EXISTS location_taxa (
+        taxon TEXT NOT NULL,
+        location_id INTEGER NOT NULL,
+        PRIMARY KEY (taxon, location_id)
+    ) WITHOUT ROWID;
+    CREATE TABLE IF NOT EXISTS location_activities (
+        activity TEXT NOT NULL,
+        location_id INTEGER NOT NULL,
+        PRIMARY KEY (activity, location_id)
+    ) WITHOUT ROWID;
+    CREATE VIRTUAL TABLE IF NOT EXISTS location_points
+        USING rtree(id, min_lat, max_lat, min_lng, max_lng);
+";
+
+/// Locations in SQLite. Rows keep the order they were loaded in as their
+/// id; `state` and `type` are lowercased for filtering, and the taxa table
+/// lists each taxon with its parents, so `cattle` finds dairy cows.
+pub(crate) struct SqliteStorage {
+    connection: Mutex<Connection>,
+}
+
+impl SqliteStorage {
+    pub(crate) fn open(path: &str) -> Result<Self, Box<dyn Error>> {
+        let connection =
+            Connection::open(path).map_err(|e| format!("Failed to open {}: {}", path, e))?;
+        connection.execute_batch(SCHEMA)?;
+        Ok(SqliteStorage {
+            connection: Mutex::new(connection),
+        })
+    }
+
+    /// Replaces what's stored with `locations`, in one transaction so
+    /// queries see either the old set or the new one.
+    pub(crate) fn load(&self, locations: &[LocationResponse]) -> Result<(), Box<dyn Error>> {
+        let mut connection = self.connection.lock().unwrap();
+        let transaction = connection.transaction()?;
+        transaction.execute_batch(
+            "DELETE FROM locations; DELETE FROM location_taxa;
+             DELETE FROM location_activities; DELETE FROM location_points;",
+        )?;
+        {
+            let mut insert_location = transaction.prepare(
+                "INSERT INTO locations
+                     (id, country, state, type, status, grant_date, latitude, longitude, body)
+                 VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)",
+            )?;
+            let mut insert_taxon = transaction.prepare(
+                "INSERT OR IGNORE INTO location_taxa (taxon, location_id) VALUES (?, ?)",
+            )?;
+            let mut insert_activity = transaction.prepare(
+                "INSERT OR IGNORE INTO location_activities (activity, location_id) VALUES (?, ?)",
+            )?;
+            let mut insert_point = transaction.prepare(
+                "INSERT INTO location_points (id, min_lat, max_lat, min_lng, max_lng)
+                 VALUES (?, ?, ?, ?, ?)",
+            )?;
+            for (id, loc) in locations.iter().enumerate() {
+                let id = id as i64;
+                insert_location.execute(params![
+                    id,
+                    loc.country,
+                    loc.state.to_lowercase(),
+                    loc.r#type.to_lowercase(),
+                    loc.status.id(),
+                    loc.grant_date.map(|date| date.to_string()),
+                    loc.latitude,
+                    loc.longitude,
+                    serde_json::to_string(loc)?,
+                ])?;
+                for taxon in loc.taxa.iter().flat_map(|id| species::lineage(id)) {
+                    insert_taxon.execute(params![taxon.id, id])?;
+                }
+                for activity in &loc.activities {
+                    insert_activity.execute(params![activity.id(), id])?;
+                }
+                insert_point.execute(params![
+                    id,
+                    loc.latitude,
+                    loc.latitude,
+                    loc.longitude,
+                    loc.longitude
+                ])?;
+            }
+        }
+        transaction.commit()?;
+        Ok(())
+    }
+}
+
+impl Storage for SqliteStorage {
+    fn locations<'a>(&'a self, query: &LocationQuery) -> Result<Vec<StoredLocation<'a>>, String> {
+        let (mut conditions, mut params) =
+            query.filter.map(LocationFilter::sql).unwrap_or_default();
+        if let Some(country) = query.country_code {
+            conditions.push("country = ?".to_string());
+            params.push(SqlValue::Text(country.to_string()));
+        }
+        if let Some(bbox) = query.bbox {
+            // The R*Tree keeps its boxes as 32-bit floats, rounded outwards,
+            // so it narrows the rows down and the stored coordinates decide.
+            // A box across the antimeridian is the two either side of it.
+            let (rtree_lng, lng) = if bbox.min_lng <= bbox.max_lng {
+                (
+                    "max_lng >= ? AND min_lng <= ?",
+                    "longitude >= ? AND longitude <= ?",
+                )
+            } else {
+                (
+                    "(max_lng >= ? OR min_lng <= ?)",
+                    "(longitude >= ? OR longitude <= ?)",
+                )
+            };
+            conditions.push(format!(
+                "id IN (SELECT id FROM location_points
+                        WHERE max_lat >= ? AND min_lat <= ? AND {})",
+                rtree_lng
+            ));
+            conditions.push(format!("latitude >= ? AND latitude <= ? AND {}", lng));
+            for _ in 0..2 {
+                params.extend(
+                    [bbox.min_lat, bbox.max_lat, bbox.min_lng, bbox.max_lng].map(SqlValue::Real),
+                );
+            }
+        }
+        let mut sql = "SELECT body FROM locations".to_string();
+        if !conditions.is_empty() {
+            sql.push_str(" WHERE ");
+            sql.push_str(&conditions.join(" AND "));
+        }
+        sql.push_str(" ORDER BY id");
+
+        let connection = self.connection.lock().unwrap();
+        let query_error = |e: rusqlite::Error| format!("Storage query failed: {}", e);
+        let mut statement = connection.prepare(&sql).map_err(query_error)?;
+        let rows = statement
+            .query_map(params_from_iter(params), |row| row.get::<_, String>(0))
+            .map_err(query_error)?;
+        rows.map(|body| {
+            let body = body.map_err(query_error)?;
+            serde_json::from_str(&body)
+                .map(StoredLocation::Row)
+                .map_err(|e| format!("Stored location is not JSON: {}", e))
+        })
+        .collect()
+    }
+}
@@ -0,0 +1,288 @@
+// Until Every Cage is Empty
+// Copyright (C) 2025 Eli Perez
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+// Contact the developer directly at untileverycageproject@protonmail.com
+use std::borrow::Cow;
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::hash::{DefaultHasher, Hash, Hasher};
+use std::io::{Cursor, Read};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, RwLock};
+
+use include_dir::Dir;
+use serde::Serialize;
+
+use crate::DATA_DIR;
+use crate::settings::Settings;
+
+/// Where the datasets come from, set with `DATA_SOURCE`:
+///
+/// - unset: the static_data directory embedded in the binary at build time
+/// - a path: a directory laid out like static_data
+/// - an `http(s)://` URL: a ZIP archive containing such a directory, possibly
+///   nested (a GitHub archive of the whole repository works)
+#[derive(Debug, Clone, Default)]
+pub enum DataSource {
+    #[default]
+    Embedded,
+    Directory(PathBuf),
+    Archive(String),
+}
+
+impl DataSource {
+    pub fn from_settings(settings: &Settings) -> Self {
+        match settings.get("DATA_SOURCE") {
+            Some(source) if source.starts_with("http://") || source.starts_with("https://") => {
+                DataSource::Archive(source.to_string())
+            }
+            Some(source) if !source.is_empty() => DataSource::Directory(PathBuf::from(source)),
+            _ => DataSource::Embedded,
+        }
+    }
+
+    fn describe(&self) -> String {
+        match self {
+            DataSource::Embedded => "embedded".to_string(),
+            DataSource::Directory(path) => path.display().to_string(),
+            DataSource::Archive(url) => url.clone(),
+        }
+    }
+}
+
+/// Every file of one load, keyed by its path relative to the data root
+/// (`us/locations.csv`).
+#[derive(Debug, Default)]
+pub struct Dataset {
+    files: BTreeMap<String, Cow<'static, [u8]>>,
+    pub loaded_at: String,
+    /// Hash of every path and file, for cache validators.
+    pub content_hash: u64,
+}
+
+impl Dataset {
+    pub fn embedded() -> Self {
+        fn collect(dir: &'static Dir<'static>, files: &mut BTreeMap<String, Cow<'static, [u8]>>) {
+            for file in dir.files() {
+                files.insert(
+                    file.path().to_string_lossy().replace('\\', "/"),
+                    Cow::Borrowed(file.contents()),
+                );
+            }
+            for child in dir.dirs() {
+                collect(child, files);
+            }
+        }
+
+        let mut files = BTreeMap::new();
+        collect(&DATA_DIR, &mut files);
+        Dataset::new(files)
+    }
+
+    fn new(files: BTreeMap<String, Cow<'static, [u8]>>) -> Self {
+        let mut hasher = DefaultHasher::new();
+        files.hash(&mut hasher);
+        Dataset {
+            content_hash: hasher.finish(),
+            files,
+            loaded_at: chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Secs, true),
+        }
+    }
+
+    pub fn from_directory(root: &Path) -> Result<Self, Box<dyn Error>> {
+        fn collect(
+            root: &Path,
+            dir: &Path,
+            files: &mut BTreeMap<String, Cow<'static, [u8]>>,
+        ) -> Result<(), Box<dyn Error>> {
+            for entry in std::fs::read_dir(dir)? {
+                let path = entry?.path();
+                if path
+                    .file_name()
+                    .is_some_and(|name| name.to_string_lossy().starts_with('.'))
+                {
+                    continue;
+                }
+                if path.is_dir() {
+                    collect(root, &path, files)?;
+                } else {
+                    let relative = path
+                        .strip_prefix(root)?
+                        .to_string_lossy()
+                        .replace('\\', "/");
+                    files.insert(relative, Cow::Owned(std::fs::read(&path)?));
+                }
+            }
+            Ok(())
+        }
+
+        let mut files = BTreeMap::new();
+        collect(root, root, &mut files)
+            .map_err(|e| format!("Failed to read {}: {}", root.display(), e))?;
+        Ok(Dataset::new(files))
+    }
+
+    pub fn from_archive(bytes: &[u8]) -> Result<Self, Box<dyn Error>> {
+        let mut archive = zip::ZipArchive::new(Cursor::new(bytes))?;
+        let mut entries = BTreeMap::new();
+        for i in 0..archive.len() {
+            let mut file = archive.by_index(i)?;
+            if file.is_dir() {
+                continue;
+            }
+            let mut contents = Vec::new();
+            file.read_to_end(&mut contents)?;
+            entries.insert(file.name().replace('\\', "/"), contents);
+        }
+
+        // The data root is the directory holding the country directories,
+        // found from the shallowest locations.csv.
+        let root = entries
+            .keys()
+            .filter(|name| name.ends_with("/locations.csv"))
+            .min_by_key(|name| name.matches('/').count())
+            .and_then(|name| name.rsplitn(3, '/').nth(2).map(|root| format!("{}/", root)))
+            .unwrap_or_default();
+
+        let files = entries
+            .into_iter()
+            .filter_map(|(name, contents)| {
+                name.strip_prefix(&root)
+                    .map(|relative| (relative.to_string(), Cow::Owned(contents)))
+            })
+            .collect();
+        Ok(Dataset::new(files))
+    }
+
+    pub async fn load(source: &DataSource) -> Result<Self, Box<dyn Error + Send + Sync>> {
+        let to_send = |e: Box<dyn Error>| -> Box<dyn Error + Send + Sync> { e.to_string().into() };
+        let dataset = match source {
+            DataSource::Embedded => Dataset::embedded(),
+            DataSource::Directory(path) => Dataset::from_directory(path).map_err(to_send)?,
+            DataSource::Archive(url) => {
+                let bytes = reqwest::get(url).await?.error_for_status()?.bytes().await?;
+                Dataset::from_archive(&bytes)
+                    .map_err(|e| format!("Failed to unpack {}: {}", url, e))?
+            }
+        };
+        if dataset.countries().is_empty() {
+            return Err(
+                format!("No <country>/locations.csv found in {}", source.describe()).into(),
+            );
+        }
+        Ok(dataset)
+    }
+
+    pub fn get(&self, path: &str) -> Option<&[u8]> {
+        self.files.get(path).map(|contents| contents.as_ref())
+    }
+
+    /// Country directories with a locations.csv, sorted.
+    pub fn countries(&self) -> Vec<&str> {
+        self.files
+            .keys()
+            .filter_map(|path| path.strip_suffix("/locations.csv"))
+            .filter(|country| !country.contains('/'))
+            .collect()
+    }
+
+    /// Files directly inside `dir`, sorted by name, as `(name, contents)`.
+    pub fn files_in<'a>(&'a self, dir: &str) -> impl Iterator<Item = (&'a str, &'a [u8])> {
+        let prefix = format!("{}/", dir.trim_end_matches('/'));
+        self.files.iter().filter_map(move |(path, contents)| {
+            path.strip_prefix(&prefix)
+                .filter(|name| !name.contains('/'))
+                .map(|name| (name, contents.as_ref()))
+        })
+    }
+
+    /// Every file, sorted by path, as `(path, contents)`.
+    pub fn files(&self) -> impl Iterator<Item = (&str, &[u8])> {
+        self.files
+            .iter()
+            .map(|(path, contents)| (path.as_str(), contents.as_ref()))
+    }
+
+    pub fn len(&self) -> usize {
+        self.files.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.files.is_empty()
+    }
+}
+
+/// The dataset currently served, replaceable at runtime.
+#[derive(Debug)]
+pub struct DataStore {
+    source: DataSource,
+    current: RwLock<Arc<Dataset>>,
+}
+
+impl Default for DataStore {
+    fn default() -> Self {
+        DataStore {
+            source: DataSource::Embedded,
+            current: RwLock::new(Arc::new(Dataset::embedded())),
+        }
+    }
+}
+
+#[derive(Serialize)]
+pub struct ReloadSummary {
+    pub source: String,
+    pub files: usize,
+    pub countries: Vec<String>,
+    /// Countries whose list changed and was saved as a snapshot first.
+    pub snapshots: Vec<String>,
+    pub loaded_at: String,
+}
+
+impl DataStore {
+    pub async fn load(source: DataSource) -> Result<Self, Box<dyn Error + Send + Sync>> {
+        let dataset = Dataset::load(&source).await?;
+        Ok(DataStore {
+            source,
+            current: RwLock::new(Arc::new(dataset)),
+        })
+    }
+
+    pub fn current(&self) -> Arc<Dataset> {
+        self.current.read().unwrap().clone()
+    }
+
+    /// Loads the source again and swaps it in. The old dataset keeps being
+    /// served if loading fails. A directory source first gets a snapshot of
+    /// each location list that changed (see `snapshots::save_snapshots`).
+    pub async fn reload(&self) -> Result<ReloadSummary, Box<dyn Error + Send + Sync>> {
+        let mut snapshots = Vec::new();
+        if let DataSource::Directory(root) = &self.source {
+            let today = chrono::Utc::now().format("%Y-%m-%d").to_string();
+            snapshots =
+                crate::snapshots::save_snapshots(root, &today).map_err(|e| e.to_string())?;
+        }
+        let dataset = Dataset::load(&self.source).await?;
+        let summary = ReloadSummary {
+            source: self.source.describe(),
+            files: dataset.len(),
+            countries: dataset.countries().iter().map(|c| c.to_string()).collect(),
+            snapshots,
+            loaded_at: dataset.loaded_at.clone(),
+        };
+        *self.current.write().unwrap() = Arc::new(dataset);
+        Ok(summary)
+    }
+}
@@ -0,0 +1,311 @@
+// Until Every Cage is Empty
+// Copyright (C) 2025 Eli Perez
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+// Contact the developer directly at untileverycageproject@protonmail.com
+//! POST /api/submissions and GET /api/submissions/pending: facilities people
+//! know of that no government list has, queued for a moderator to check.
+//! Approved submissions are served with the other locations (see
+//! `moderation`).
+
+use std::error::Error;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use axum::Json;
+use axum::extract::State;
+use axum::http::{HeaderMap, StatusCode};
+use axum::response::IntoResponse;
+use serde::{Deserialize, Serialize};
+
+use crate::AppState;
+use crate::activities::Activity;
+use crate::admin::authorize;
+use crate::errors::ApiError;
+use crate::geocode;
+use crate::settings::Settings;
+use crate::validation::{self, CoordinateIssue};
+
+/// Most submissions waiting for a moderator at once.
+const MAX_PENDING: usize = 1000;
+
+/// Longest accepted value of any text field.
+const MAX_LENGTH: usize = 1000;
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum SubmissionStatus {
+    Pending,
+    /// Served as a location with the ID `submission-<id>`.
+    Approved,
+    Rejected,
+    /// Already in the data as the record in `merged_into`.
+    Merged,
+}
+
+/// A facility as proposed, in the shape the API accepts.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ProposedFacility {
+    pub name: String,
+    #[serde(default)]
+    pub street: String,
+    #[serde(default)]
+    pub city: String,
+    #[serde(default)]
+    pub state: String,
+    #[serde(default)]
+    pub zip: String,
+    /// Two-letter country code, as in the data directories.
+    pub country: String,
+    pub latitude: Option<f64>,
+    pub longitude: Option<f64>,
+    /// An activity ID or label from /api/activities.
+    pub activity: String,
+    /// Where the facility can be seen to exist: a news story, a permit, a
+    /// company page.
+    pub evidence_url: String,
+    #[serde(default)]
+    pub notes: String,
+}
+
+impl ProposedFacility {
+    /// Checks the proposal and normalizes its activity and country.
+    pub(crate) fn validate(mut self) -> Result<ProposedFacility, String> {
+        let texts = [
+            ("name", &self.name),
+            ("street", &self.street),
+            ("city", &self.city),
+            ("state", &self.state),
+            ("zip", &self.zip),
+            ("evidence_url", &self.evidence_url),
+            ("notes", &self.notes),
+        ];
+        if let Some((field, _)) = texts.iter().find(|(_, text)| text.len() > MAX_LENGTH) {
+            return Err(format!(
+                "{} is longer than {} characters",
+                field, MAX_LENGTH
+            ));
+        }
+        if self.name.trim().is_empty() {
+            return Err("name is required".to_string());
+        }
+
+        self.country = geocode::country_code(&self.country);
+        if self.country.len() != 2 || !self.country.chars().all(|c| c.is_ascii_lowercase()) {
+            return Err(format!("Invalid country code {}", self.country));
+        }
+        let activity = Activity::parse(&self.activity)
+            .ok_or_else(|| format!("Unknown activity {}", self.activity))?;
+        self.activity = activity.id().to_string();
+
+        let evidence = self.evidence_url.trim();
+        if !(evidence.starts_with("https://") || evidence.starts_with("http://")) {
+            return Err("evidence_url must be an http(s) URL".to_string());
+        }
+
+        match (self.latitude, self.longitude) {
+            (Some(latitude), Some(longitude)) => {
+                match validation::check(&self.country, latitude, longitude) {
+                    None => {}
+                    Some(CoordinateIssue::Swapped) => {
+                        return Err("latitude and longitude look swapped".to_string());
+                    }
+                    Some(_) => {
+                        return Err(format!(
+                            "Coordinates {}, {} are not in {}",
+                            latitude, longitude, self.country
+                        ));
+                    }
+                }
+            }
+            (None, None) if self.street.trim().is_empty() && self.city.trim().is_empty() => {
+                return Err("Provide coordinates or an address".to_string());
+            }
+            (None, None) => {}
+            _ => return Err("Provide both latitude and longitude".to_string()),
+        }
+        Ok(self)
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Submission {
+    pub id: u64,
+    pub status: SubmissionStatus,
+    pub submitted_at: String,
+    #[serde(flatten)]
+    pub facility: ProposedFacility,
+    /// The moderator who last approved, rejected or merged it, and when.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reviewed_by: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reviewed_at: Option<String>,
+    /// `<country>/<establishment_id>` of the record a merged submission
+    /// duplicates.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub merged_into: Option<String>,
+}
+
+impl Submission {
+    /// The establishment ID an approved submission is served under.
+    pub fn establishment_id(&self) -> String {
+        format!("submission-{}", self.id)
+    }
+}
+
+/// Writes `value` to `path` as JSON, through a temporary file so a crash
+/// can't leave it half written.
+pub(crate) fn save_json<T: Serialize + ?Sized>(
+    path: &Path,
+    value: &T,
+) -> Result<(), Box<dyn Error>> {
+    let tmp = path.with_extension("tmp");
+    std::fs::write(&tmp, serde_json::to_string_pretty(value)?)?;
+    std::fs::rename(&tmp, path)?;
+    Ok(())
+}
+
+/// Every submission, kept in the JSON file `SUBMISSIONS_FILE` names. Without
+/// it submissions only last until a restart.
+#[derive(Debug, Default)]
+pub struct SubmissionQueue {
+    path: Option<PathBuf>,
+    submissions: Mutex<Vec<Submission>>,
+}
+
+impl SubmissionQueue {
+    pub fn from_settings(settings: &Settings) -> Result<Self, Box<dyn Error>> {
+        let Some(path) = settings.get("SUBMISSIONS_FILE") else {
+            return Ok(SubmissionQueue::default());
+        };
+        let path = PathBuf::from(path);
+        let submissions = match std::fs::read_to_string(&path) {
+            Ok(source) => serde_json::from_str(&source)
+                .map_err(|e| format!("Failed to parse {}: {}", path.display(), e))?,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Vec::new(),
+            Err(e) => return Err(format!("Failed to read {}: {}", path.display(), e).into()),
+        };
+        Ok(SubmissionQueue {
+            path: Some(path),
+            submissions: Mutex::new(submissions),
+        })
+    }
+
+    /// Writes the queue out whole.
+    fn save(&self, submissions: &[Submission]) -> Result<(), Box<dyn Error>> {
+        match &self.path {
+            Some(path) => save_json(path, submissions),
+            None => Ok(()),
+        }
+    }
+
+    fn add(&self, facility: ProposedFacility) -> Result<Submission, (StatusCode, String)> {
+        let mut submissions = self.submissions.lock().unwrap();
+        let pending = submissions
+            .iter()
+            .filter(|s| s.status == SubmissionStatus::Pending)
+            .count();
+        if pending >= MAX_PENDING {
+            return Err((
+                StatusCode::SERVICE_UNAVAILABLE,
+                "No more submissions can be taken until the queue is moderated".to_string(),
+            ));
+        }
+        let submission = Submission {
+            id: submissions.iter().map(|s| s.id).max().unwrap_or_default() + 1,
+            status: SubmissionStatus::Pending,
+            submitted_at: chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Secs, true),
+            facility,
+            reviewed_by: None,
+            reviewed_at: None,
+            merged_into: None,
+        };
+        submissions.push(submission.clone());
+        if let Err(e) = self.save(&submissions) {
+            submissions.pop();
+            return Err((
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("Failed to save submission: {}", e),
+            ));
+        }
+        Ok(submission)
+    }
+
+    /// Changes submission `id` with `change` and saves the queue, returning
+    /// the submission before and after. Nothing changes if `change` fails or
+    /// the queue can't be saved.
+    pub(crate) fn update(
+        &self,
+        id: u64,
+        change: impl FnOnce(&mut Submission) -> Result<(), String>,
+    ) -> Result<(Submission, Submission), UpdateError> {
+        let mut submissions = self.submissions.lock().unwrap();
+        let Some(position) = submissions.iter().position(|s| s.id == id) else {
+            return Err(UpdateError::NotFound);
+        };
+        let before = submissions[position].clone();
+        change(&mut submissions[position]).map_err(UpdateError::Invalid)?;
+        let after = submissions[position].clone();
+        if let Err(e) = self.save(&submissions) {
+            submissions[position] = before;
+            return Err(UpdateError::Save(e.to_string()));
+        }
+        Ok((before, after))
+    }
+
+    pub(crate) fn with_status(&self, status: SubmissionStatus) -> Vec<Submission> {
+        self.submissions
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|submission| submission.status == status)
+            .cloned()
+            .collect()
+    }
+}
+
+pub(crate) enum UpdateError {
+    NotFound,
+    /// The change doesn't apply to the submission as it is.
+    Invalid(String),
+    Save(String),
+}
+
+/// POST /api/submissions
+pub async fn post_submission_handler(
+    State(state): State<AppState>,
+    Json(facility): Json<ProposedFacility>,
+) -> impl IntoResponse {
+    let facility = match facility.validate() {
+        Ok(facility) => facility,
+        Err(e) => return (StatusCode::BAD_REQUEST, e).into_response(),
+    };
+    match state.submissions.add(facility) {
+        Ok(submission) => (StatusCode::CREATED, Json(submission)).into_response(),
+        Err((StatusCode::INTERNAL_SERVER_ERROR, e)) => ApiError::internal(e).into_response(),
+        Err(e) => e.into_response(),
+    }
+}
+
+/// GET /api/submissions/pending: the moderation queue, oldest first.
+pub async fn get_pending_submissions_handler(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> impl IntoResponse {
+    if let Err(e) = authorize(&state, &headers) {
+        return e.into_response();
+    }
+    Json(state.submissions.with_status(SubmissionStatus::Pending)).into_response()
+}
@@ -0,0 +1,592 @@
+// Until Every Cage is Empty
+// Copyright (C) 2025 Eli Perez
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+// Contact the developer directly at untileverycageproject@protonmail.com
+//! POST /api/subscriptions: webhooks told when locations in an area appear
+//! or change.
+//!
+//! Every rebuild of the location index after the first (a data reload, an
+//! approved submission, a moderation flag...) is compared with the one it
+//! replaces, and each subscription whose filter matches an added or changed
+//! location gets them POSTed to its webhook. Subscriptions are kept in the
+//! JSON file `SUBSCRIPTIONS_FILE` names; without it they only last until a
+//! restart.
+//!
+//! Webhooks are only sent to the public internet: a URL whose host resolves
+//! to a loopback, private, link-local (cloud metadata) or otherwise reserved
+//! address is refused when subscribing, and checked again before each
+//! notification, which then connects to the address checked and follows no
+//! redirects. Each client (an API key, or an address; see `access`) keeps
+//! at most `SUBSCRIPTIONS_PER_CLIENT` (10 by default), and
+//! `SUBSCRIPTIONS_REQUIRE_API_KEY=1` leaves subscribing to key holders.
+
+use std::collections::HashMap;
+use std::error::Error;
+use std::net::{IpAddr, SocketAddr};
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use axum::extract::{Path, State};
+use axum::http::{HeaderMap, StatusCode, header};
+use axum::response::IntoResponse;
+use axum::{Extension, Json};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+use crate::access::Client;
+use crate::admin::authorize;
+use crate::errors::ApiError;
+use crate::settings::Settings;
+use crate::spatial::{BoundingBox, LocationIndex};
+use crate::species;
+use crate::submissions::save_json;
+use crate::{AppState, LocationResponse};
+
+/// Most subscriptions kept at once.
+const MAX_SUBSCRIPTIONS: usize = 1000;
+
+/// Most subscriptions one client keeps, unless `SUBSCRIPTIONS_PER_CLIENT`
+/// says otherwise.
+const DEFAULT_PER_CLIENT: usize = 10;
+
+/// Most locations of each kind in one notification; the counts say how many
+/// there were in all.
+const MAX_NOTIFIED: usize = 500;
+
+/// Which locations a subscription is about. Empty parts match everything,
+/// so an empty filter hears of every change.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct SubscriptionFilter {
+    /// States or regions, e.g. `["TX", "OK"]`.
+    #[serde(default)]
+    pub states: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub bbox: Option<BoundingBox>,
+    /// Species or groups, as /api/locations takes them.
+    #[serde(default)]
+    pub species: Vec<String>,
+}
+
+impl SubscriptionFilter {
+    fn validate(&self) -> Result<(), String> {
+        if let Some(bbox) = &self.bbox {
+            bbox.validate()?;
+        }
+        for term in &self.species {
+            species::lookup(term).ok_or_else(|| format!("Unknown species {}", term))?;
+        }
+        Ok(())
+    }
+
+    fn matches(&self, loc: &LocationResponse) -> bool {
+        let state_matches = self.states.is_empty()
+            || self
+                .states
+                .iter()
+                .any(|state| state.trim().eq_ignore_ascii_case(loc.state.trim()));
+        let bbox_matches = self
+            .bbox
+            .as_ref()
+            .is_none_or(|bbox| bbox.contains(loc.latitude, loc.longitude));
+        let species_matches = self.species.is_empty()
+            || self
+                .species
+                .iter()
+                .filter_map(|term| species::lookup(term))
+                .any(|wanted| {
+                    loc.taxa
+                        .iter()
+                        .any(|taxon| species::is_within(taxon, wanted.id))
+                });
+        state_matches && bbox_matches && species_matches
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Subscription {
+    pub id: u64,
+    /// Needed to delete the subscription. Only shown when it is created.
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub token: String,
+    pub webhook_url: String,
+    pub filter: SubscriptionFilter,
+    /// Who subscribed (see `access::Client::id`).
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub client: String,
+    pub created_at: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_notified_at: Option<String>,
+    /// Why the last notification failed.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_error: Option<String>,
+}
+
+#[derive(Deserialize)]
+pub struct NewSubscription {
+    webhook_url: String,
+    #[serde(default)]
+    filter: SubscriptionFilter,
+}
+
+#[derive(Debug)]
+pub struct Subscriptions {
+    path: Option<PathBuf>,
+    per_client: usize,
+    require_api_key: bool,
+    subscriptions: Mutex<Vec<Subscription>>,
+}
+
+impl Default for Subscriptions {
+    fn default() -> Self {
+        Subscriptions {
+            path: None,
+            per_client: DEFAULT_PER_CLIENT,
+            require_api_key: false,
+            subscriptions: Mutex::default(),
+        }
+    }
+}
+
+impl Subscriptions {
+    pub fn from_settings(settings: &Settings) -> Result<Self, Box<dyn Error>> {
+        let per_client = match settings.get("SUBSCRIPTIONS_PER_CLIENT") {
+            Some(value) if !value.trim().is_empty() => value.trim().parse().map_err(|_| {
+                format!(
+                    "Invalid SUBSCRIPTIONS_PER_CLIENT {:?}, expected a number",
+                    value
+                )
+            })?,
+            _ => DEFAULT_PER_CLIENT,
+        };
+        let require_api_key = matches!(
+            settings.get("SUBSCRIPTIONS_REQUIRE_API_KEY"),
+            Some("1") | Some("true")
+        );
+        let Some(path) = settings.get("SUBSCRIPTIONS_FILE") else {
+            return Ok(Subscriptions {
+                per_client,
+                require_api_key,
+                ..Default::default()
+            });
+        };
+        let path = PathBuf::from(path);
+        let subscriptions = match std::fs::read_to_string(&path) {
+            Ok(source) => serde_json::from_str(&source)
+                .map_err(|e| format!("Failed to parse {}: {}", path.display(), e))?,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Vec::new(),
+            Err(e) => return Err(format!("Failed to read {}: {}", path.display(), e).into()),
+        };
+        Ok(Subscriptions {
+            path: Some(path),
+            per_client,
+            require_api_key,
+            subscriptions: Mutex::new(subscriptions),
+        })
+    }
+
+    fn save(&self, subscriptions: &[Subscription]) -> Result<(), Box<dyn Error>> {
+        match &self.path {
+            Some(path) => save_json(path, subscriptions),
+            None => Ok(()),
+        }
+    }
+
+    fn add(&self, mut subscription: Subscription) -> Result<Subscription, (StatusCode, String)> {
+        let mut subscriptions = self.subscriptions.lock().unwrap();
+        if subscriptions.len() >= MAX_SUBSCRIPTIONS {
+            return Err((
+                StatusCode::SERVICE_UNAVAILABLE,
+                "No more subscriptions can be taken".to_string(),
+            ));
+        }
+        let own = subscriptions
+            .iter()
+            .filter(|s| s.client == subscription.client)
+            .count();
+        if own >= self.per_client {
+            return Err((
+                StatusCode::TOO_MANY_REQUESTS,
+                format!(
+                    "A client keeps at most {} subscriptions, delete one first",
+                    self.per_client
+                ),
+            ));
+        }
+        subscription.id = subscriptions.iter().map(|s| s.id).max().unwrap_or_default() + 1;
+        subscriptions.push(subscription.clone());
+        if let Err(e) = self.save(&subscriptions) {
+            subscriptions.pop();
+            return Err((
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("Failed to save subscription: {}", e),
+            ));
+        }
+        Ok(subscription)
+    }
+
+    /// Removes subscription `id` if `token` is its token.
+    fn remove(&self, id: u64, token: &str) -> Result<(), (StatusCode, String)> {
+        let mut subscriptions = self.subscriptions.lock().unwrap();
+        let Some(i) = subscriptions.iter().position(|s| s.id == id) else {
+            return Err((StatusCode::NOT_FOUND, format!("No subscription {}", id)));
+        };
+        if token.is_empty() || subscriptions[i].token != token {
+            return Err((
+                StatusCode::UNAUTHORIZED,
+                "Pass the subscription's token as Authorization: Bearer <token>".to_string(),
+            ));
+        }
+        let removed = subscriptions.remove(i);
+        if let Err(e) = self.save(&subscriptions) {
+            subscriptions.insert(i, removed);
+            return Err((
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("Failed to save subscriptions: {}", e),
+            ));
+        }
+        Ok(())
+    }
+
+    /// Notes how notifying subscription `id` went. Failing to save the note
+    /// only loses the note.
+    fn notified(&self, id: u64, result: Result<(), String>) {
+        let mut subscriptions = self.subscriptions.lock().unwrap();
+        if let Some(subscription) = subscriptions.iter_mut().find(|s| s.id == id) {
+            match result {
+                Ok(()) => {
+                    subscription.last_notified_at = Some(now());
+                    subscription.last_error = None;
+                }
+                Err(e) => subscription.last_error = Some(e),
+            }
+        }
+        let _ = self.save(&subscriptions);
+    }
+
+    fn all(&self) -> Vec<Subscription> {
+        self.subscriptions.lock().unwrap().clone()
+    }
+
+    /// Sends each subscription the locations of `new` it matches that `old`
+    /// didn't have or had otherwise.
+    pub(crate) fn notify(self: &Arc<Self>, old: &LocationIndex, new: &LocationIndex) {
+        let subscriptions = self.all();
+        if subscriptions.is_empty() {
+            return;
+        }
+        let changes = compare(&old.locations, &new.locations);
+        if changes.is_empty() {
+            return;
+        }
+        for subscription in subscriptions {
+            let added: Vec<&LocationResponse> = changes
+                .added
+                .iter()
+                .copied()
+                .filter(|loc| subscription.filter.matches(loc))
+                .collect();
+            let changed: Vec<&Change> = changes
+                .changed
+                .iter()
+                .filter(|change| subscription.filter.matches(change.location))
+                .collect();
+            if added.is_empty() && changed.is_empty() {
+                continue;
+            }
+            let text = format!(
+                "{} new and {} changed locations matching subscription {}",
+                added.len(),
+                changed.len(),
+                subscription.id
+            );
+            let body = json!({
+                "text": text,
+                "subscription_id": subscription.id,
+                "revision": new.revision,
+                "added_count": added.len(),
+                "changed_count": changed.len(),
+                "added": &added[..added.len().min(MAX_NOTIFIED)],
+                "changed": &changed[..changed.len().min(MAX_NOTIFIED)],
+            });
+            let log = self.clone();
+            tokio::spawn(async move {
+                let result = post(&subscription.webhook_url, &body).await;
+                log.notified(subscription.id, result);
+            });
+        }
+    }
+}
+
+fn now() -> String {
+    chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Secs, true)
+}
+
+/// 128 bits from the operating system's random number generator, in hex.
+fn new_token() -> Result<String, getrandom::Error> {
+    let mut bytes = [0u8; 16];
+    getrandom::fill(&mut bytes)?;
+    Ok(bytes.iter().map(|byte| format!("{:02x}", byte)).collect())
+}
+
+/// Whether a webhook may be sent to `ip`: not loopback, private,
+/// link-local (where cloud metadata services answer), shared, multicast or
+/// otherwise reserved.
+fn is_public(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => {
+            let [a, b, c, _] = ip.octets();
+            !(ip.is_unspecified()
+                || ip.is_loopback()
+                || ip.is_private()
+                || ip.is_link_local()
+                || ip.is_broadcast()
+                || ip.is_documentation()
+                || ip.is_multicast()
+                || a == 0
+                // Carrier-grade NAT.
+                || (a == 100 && (64..128).contains(&b))
+                // IETF protocol assignments.
+                || (a == 192 && b == 0 && c == 0)
+                // Benchmarking.
+                || (a == 198 && (18..20).contains(&b))
+                || a >= 240)
+        }
+        IpAddr::V6(ip) => {
+            if let Some(ip) = ip.to_ipv4_mapped() {
+                return is_public(IpAddr::V4(ip));
+            }
+            let segments = ip.segments();
+            !(ip.is_unspecified()
+                || ip.is_loopback()
+                || ip.is_multicast()
+                // IPv4-compatible and other embeddings of IPv4.
+                || segments[..6].iter().all(|&segment| segment == 0)
+                // NAT64, which reaches IPv4 addresses.
+                || (segments[0] == 0x64 && segments[1] == 0xff9b)
+                // Unique local.
+                || (segments[0] & 0xfe00) == 0xfc00
+                // Link-local.
+                || (segments[0] & 0xffc0) == 0xfe80
+                // Documentation.
+                || (segments[0] == 0x2001 && segments[1] == 0x0db8))
+        }
+    }
+}
+
+/// The host of `url` and the addresses it resolves to, refused unless the
+/// URL is http(s) and every address is public.
+async fn resolve_webhook(url: &str) -> Result<(String, Vec<SocketAddr>), String> {
+    let url = match reqwest::Url::parse(url) {
+        Ok(url) if matches!(url.scheme(), "http" | "https") => url,
+        _ => {
+            return Err(format!(
+                "webhook_url must be an http or https URL, not {}",
+                url
+            ));
+        }
+    };
+    let host = url.host_str().unwrap_or_default();
+    let port = url.port_or_known_default().unwrap_or(80);
+    let bare_host = host.trim_start_matches('[').trim_end_matches(']');
+    let addrs: Vec<SocketAddr> = tokio::net::lookup_host((bare_host, port))
+        .await
+        .map_err(|e| format!("webhook_url host {} doesn't resolve: {}", host, e))?
+        .collect();
+    if addrs.is_empty() {
+        return Err(format!("webhook_url host {} doesn't resolve", host));
+    }
+    if let Some(addr) = addrs.iter().find(|addr| !is_public(addr.ip())) {
+        return Err(format!(
+            "webhook_url must not point at a local or private address ({} is {})",
+            host,
+            addr.ip()
+        ));
+    }
+    Ok((bare_host.to_string(), addrs))
+}
+
+async fn post(url: &str, body: &serde_json::Value) -> Result<(), String> {
+    // Checked again as the host may resolve elsewhere by now, and pinned to
+    // the addresses checked so it can't change between check and connect.
+    let (host, addrs) = resolve_webhook(url).await?;
+    reqwest::Client::builder()
+        .redirect(reqwest::redirect::Policy::none())
+        .resolve_to_addrs(&host, &addrs)
+        .timeout(Duration::from_secs(10))
+        .build()
+        .map_err(|e| e.to_string())?
+        .post(url)
+        .json(body)
+        .send()
+        .await
+        .and_then(|response| response.error_for_status())
+        .map(|_| ())
+        .map_err(|e| e.to_string())
+}
+
+#[derive(Serialize)]
+struct FieldChange {
+    field: &'static str,
+    before: String,
+    after: String,
+}
+
+#[derive(Serialize)]
+struct Change<'a> {
+    location: &'a LocationResponse,
+    changes: Vec<FieldChange>,
+}
+
+#[derive(Default)]
+struct Changes<'a> {
+    added: Vec<&'a LocationResponse>,
+    changed: Vec<Change<'a>>,
+}
+
+impl Changes<'_> {
+    fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.changed.is_empty()
+    }
+}
+
+/// What subscribers hear about when it changes.
+fn tracked_fields(loc: &LocationResponse) -> [(&'static str, String); 9] {
+    [
+        ("establishment_name", loc.establishment_name.clone()),
+        ("street", loc.street.clone()),
+        ("city", loc.city.clone()),
+        ("state", loc.state.clone()),
+        ("zip", loc.zip.clone()),
+        ("type", loc.r#type.clone()),
+        ("species", loc.species.join(", ")),
+        ("status", loc.status.id().to_string()),
+        (
+            "coordinates",
+            format!("{:.5},{:.5}", loc.latitude, loc.longitude),
+        ),
+    ]
+}
+
+/// Locations of `new` missing from `old` or changed since, by facility ID.
+fn compare<'a>(old: &[LocationResponse], new: &'a [LocationResponse]) -> Changes<'a> {
+    let old_by_id: HashMap<&str, &LocationResponse> = old
+        .iter()
+        .map(|loc| (loc.facility_id.as_str(), loc))
+        .collect();
+    let mut changes = Changes::default();
+    for loc in new {
+        let Some(before) = old_by_id.get(loc.facility_id.as_str()) else {
+            changes.added.push(loc);
+            continue;
+        };
+        let fields: Vec<FieldChange> = tracked_fields(before)
+            .into_iter()
+            .zip(tracked_fields(loc))
+            .filter(|((_, before), (_, after))| before != after)
+            .map(|((field, before), (_, after))| FieldChange {
+                field,
+                before,
+                after,
+            })
+            .collect();
+        if !fields.is_empty() {
+            changes.changed.push(Change {
+                location: loc,
+                changes: fields,
+            });
+        }
+    }
+    changes
+}
+
+/// POST /api/subscriptions: `{"webhook_url": "https://...", "filter":
+/// {"states": ["TX"], "species": ["pigs"], "bbox": {...}}}`.
+pub async fn post_subscription_handler(
+    State(state): State<AppState>,
+    client: Option<Extension<Client>>,
+    Json(new): Json<NewSubscription>,
+) -> impl IntoResponse {
+    let client = client.map(|Extension(client)| client);
+    if state.subscriptions.require_api_key && !matches!(client, Some(Client::Key(_))) {
+        return (
+            StatusCode::UNAUTHORIZED,
+            "Subscribing needs an API key in X-API-Key".to_string(),
+        )
+            .into_response();
+    }
+    let webhook_url = new.webhook_url.trim().to_string();
+    if let Err(e) = resolve_webhook(&webhook_url).await {
+        return (StatusCode::BAD_REQUEST, e).into_response();
+    }
+    if let Err(e) = new.filter.validate() {
+        return (StatusCode::BAD_REQUEST, e).into_response();
+    }
+
+    let token = match new_token() {
+        Ok(token) => token,
+        Err(e) => {
+            return ApiError::internal(format!("Failed to make a token: {}", e)).into_response();
+        }
+    };
+    let subscription = Subscription {
+        id: 0,
+        token,
+        webhook_url,
+        filter: new.filter,
+        client: client.as_ref().map(Client::id).unwrap_or_default(),
+        created_at: now(),
+        last_notified_at: None,
+        last_error: None,
+    };
+    match state.subscriptions.add(subscription) {
+        Ok(subscription) => (StatusCode::CREATED, Json(subscription)).into_response(),
+        Err((StatusCode::INTERNAL_SERVER_ERROR, e)) => ApiError::internal(e).into_response(),
+        Err(e) => e.into_response(),
+    }
+}
+
+/// DELETE /api/subscriptions/{id} with `Authorization: Bearer <token>`.
+pub async fn delete_subscription_handler(
+    State(state): State<AppState>,
+    Path(id): Path<u64>,
+    headers: HeaderMap,
+) -> impl IntoResponse {
+    let token = headers
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .unwrap_or_default();
+    match state.subscriptions.remove(id, token) {
+        Ok(()) => StatusCode::NO_CONTENT.into_response(),
+        Err(e) => e.into_response(),
+    }
+}
+
+/// GET /api/admin/subscriptions: every subscription, without its token.
+pub async fn get_subscriptions_handler(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> impl IntoResponse {
+    if let Err(e) = authorize(&state, &headers) {
+        return e.into_response();
+    }
+    let mut subscriptions = state.subscriptions.all();
+    for subscription in &mut subscriptions {
+        subscription.token.clear();
+    }
+    Json(subscriptions).into_response()
+}
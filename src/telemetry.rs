@@ -0,0 +1,120 @@
+// Until Every Cage is Empty
+// Copyright (C) 2025 Eli Perez
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+// Contact the developer directly at untileverycageproject@protonmail.com
+//! Logging. Every request gets a `request` span with its route, query,
+//! status and response size, and every dataset load a `load` span; a span's
+//! duration is logged when it closes.
+//!
+//! `RUST_LOG` sets what is logged (`info` by default, e.g.
+//! `heatmap_backend=debug,tower_http=warn`) and `LOG_FORMAT=json` writes one
+//! JSON object per line instead of text.
+
+use std::io::IsTerminal;
+use std::time::Duration;
+
+use axum::body::{Body, HttpBody};
+use axum::extract::{MatchedPath, Request};
+use axum::http::header;
+use axum::response::Response;
+use tower_http::classify::{ServerErrorsAsFailures, SharedClassifier};
+use tower_http::trace::{MakeSpan, OnResponse, TraceLayer};
+use tracing::Span;
+use tracing::field::Empty;
+use tracing_subscriber::EnvFilter;
+use tracing_subscriber::fmt::format::FmtSpan;
+
+const DEFAULT_FILTER: &str = "info";
+
+/// Installs the global subscriber from `RUST_LOG` and `LOG_FORMAT`.
+pub fn init_from_env() -> Result<(), String> {
+    let filter = match std::env::var("RUST_LOG") {
+        Ok(directives) if !directives.trim().is_empty() => EnvFilter::try_new(directives.trim())
+            .map_err(|e| format!("Invalid RUST_LOG {:?}: {}", directives, e))?,
+        _ => EnvFilter::new(DEFAULT_FILTER),
+    };
+    let builder = tracing_subscriber::fmt()
+        .with_env_filter(filter)
+        .with_span_events(FmtSpan::CLOSE)
+        .with_ansi(std::io::stderr().is_terminal())
+        .with_writer(std::io::stderr);
+    let installed = match std::env::var("LOG_FORMAT").as_deref() {
+        Ok("json") => builder.json().with_current_span(true).try_init(),
+        Ok("text") | Ok("") | Err(_) => builder.try_init(),
+        Ok(other) => {
+            return Err(format!(
+                "Unknown LOG_FORMAT {}, expected text or json",
+                other
+            ));
+        }
+    };
+    installed.map_err(|e| format!("Failed to install the logger: {}", e))
+}
+
+/// Opens a request's span, named by its route pattern so requests for
+/// different ids group together.
+#[derive(Clone)]
+pub(crate) struct RequestSpan;
+
+impl MakeSpan<Body> for RequestSpan {
+    fn make_span(&mut self, request: &Request) -> Span {
+        let route = request
+            .extensions()
+            .get::<MatchedPath>()
+            .map(|path| path.as_str())
+            .unwrap_or_else(|| request.uri().path());
+        tracing::info_span!(
+            "request",
+            method = %request.method(),
+            route,
+            path = request.uri().path(),
+            params = request.uri().query().unwrap_or_default(),
+            status = Empty,
+            size = Empty,
+        )
+    }
+}
+
+/// Records the status and, when known up front, the body's size in bytes.
+#[derive(Clone)]
+pub(crate) struct RecordResponse;
+
+impl<B: HttpBody> OnResponse<B> for RecordResponse {
+    fn on_response(self, response: &Response<B>, _latency: Duration, span: &Span) {
+        span.record("status", response.status().as_u16());
+        let size = response.body().size_hint().exact().or_else(|| {
+            response
+                .headers()
+                .get(header::CONTENT_LENGTH)
+                .and_then(|length| length.to_str().ok())
+                .and_then(|length| length.parse().ok())
+        });
+        if let Some(size) = size {
+            span.record("size", size);
+        }
+    }
+}
+
+pub(crate) type RequestTrace =
+    TraceLayer<SharedClassifier<ServerErrorsAsFailures>, RequestSpan, (), RecordResponse>;
+
+/// The request tracing layer. Server errors are also logged as they happen.
+pub(crate) fn trace_layer() -> RequestTrace {
+    TraceLayer::new_for_http()
+        .make_span_with(RequestSpan)
+        .on_request(())
+        .on_response(RecordResponse)
+}
@@ -0,0 +1,150 @@
+// Until Every Cage is Empty
+// Copyright (C) 2025 Eli Perez
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+// Contact the developer directly at untileverycageproject@protonmail.com
+use std::collections::HashMap;
+use std::f64::consts::PI;
+use std::sync::{Arc, RwLock};
+
+use axum::body::Bytes;
+use axum::extract::{Path, State};
+use axum::http::{StatusCode, header};
+use axum::response::IntoResponse;
+use mvt::{GeomEncoder, GeomType, Tile};
+
+use crate::AppState;
+use crate::errors::ApiError;
+use crate::spatial::{BoundingBox, LocationIndex};
+
+pub const MVT_CONTENT_TYPE: &str = "application/vnd.mapbox-vector-tile";
+const LAYER_NAME: &str = "locations";
+const EXTENT: u32 = 4096;
+/// Points this far outside the tile (in tile units) are still included, so
+/// symbols on the edge aren't cut off.
+const BUFFER: f64 = 64.0;
+const MAX_ZOOM: u8 = 22;
+const CACHE_CAPACITY: usize = 4096;
+/// Web Mercator stops here.
+const MAX_LATITUDE: f64 = 85.051_128_78;
+
+/// Encoded tiles keyed by `(z, x, y)`, emptied whenever the index is rebuilt.
+pub(crate) type TileCache = Arc<RwLock<HashMap<(u8, u32, u32), Bytes>>>;
+
+/// `(x, y)` of a point in tiles at zoom `z`, fractional.
+fn project(latitude: f64, longitude: f64, z: u8) -> (f64, f64) {
+    let n = f64::from(1u32 << z);
+    let lat = latitude.clamp(-MAX_LATITUDE, MAX_LATITUDE).to_radians();
+    let x = (longitude + 180.0) / 360.0 * n;
+    let y = (1.0 - (lat.tan() + 1.0 / lat.cos()).ln() / PI) / 2.0 * n;
+    (x, y)
+}
+
+/// `(latitude, longitude)` of a fractional tile position at zoom `z`.
+fn unproject(x: f64, y: f64, z: u8) -> (f64, f64) {
+    let n = f64::from(1u32 << z);
+    let longitude = x / n * 360.0 - 180.0;
+    let latitude = (PI * (1.0 - 2.0 * y / n)).sinh().atan().to_degrees();
+    (latitude, longitude)
+}
+
+fn encode_tile(index: &LocationIndex, z: u8, x: u32, y: u32) -> Result<Vec<u8>, mvt::Error> {
+    let margin = BUFFER / f64::from(EXTENT);
+    let (max_lat, min_lng) = unproject(f64::from(x) - margin, f64::from(y) - margin, z);
+    let (min_lat, max_lng) = unproject(f64::from(x + 1) + margin, f64::from(y + 1) + margin, z);
+    let bbox = BoundingBox::new(
+        min_lat.max(-90.0),
+        min_lng.max(-180.0),
+        max_lat.min(90.0),
+        max_lng.min(180.0),
+    );
+
+    let mut tile = Tile::new(EXTENT);
+    let mut layer = tile.create_layer(LAYER_NAME);
+    for (id, loc) in index.in_bbox(&bbox).into_iter().enumerate() {
+        if loc.latitude == 0.0 && loc.longitude == 0.0 {
+            continue;
+        }
+        let (px, py) = project(loc.latitude, loc.longitude, z);
+        let geometry = GeomEncoder::new(GeomType::Point)
+            .point(
+                ((px - f64::from(x)) * f64::from(EXTENT)).round(),
+                ((py - f64::from(y)) * f64::from(EXTENT)).round(),
+            )?
+            .encode()?;
+
+        let mut feature = layer.into_feature(geometry);
+        feature.set_id(id as u64);
+        feature.add_tag_string("slug", &loc.slug);
+        feature.add_tag_string("establishment_name", &loc.establishment_name);
+        feature.add_tag_string("country", &loc.country);
+        feature.add_tag_string("type", &loc.r#type);
+        let activities: Vec<&str> = loc
+            .activities
+            .iter()
+            .map(|activity| activity.id())
+            .collect();
+        feature.add_tag_string("activities", &activities.join(","));
+        feature.add_tag_string("species", &loc.taxa.join(","));
+        feature.add_tag_double("priority_score", loc.priority_score);
+        layer = feature.into_layer();
+    }
+    if layer.num_features() > 0 {
+        tile.add_layer(layer)?;
+    }
+    tile.to_bytes()
+}
+
+/// GET /tiles/{z}/{x}/{y}.mvt: the locations as a Mapbox Vector Tile with a
+/// single `locations` layer of points.
+pub async fn get_tile_handler(
+    State(state): State<AppState>,
+    Path((z, x, y)): Path<(u8, u32, String)>,
+) -> impl IntoResponse {
+    let Some(y) = y.strip_suffix(".mvt").and_then(|y| y.parse::<u32>().ok()) else {
+        return (
+            StatusCode::NOT_FOUND,
+            "Tiles are served as {y}.mvt".to_string(),
+        )
+            .into_response();
+    };
+    if z > MAX_ZOOM || x >= 1 << z || y >= 1 << z {
+        return (
+            StatusCode::BAD_REQUEST,
+            format!("Tile {}/{}/{} does not exist", z, x, y),
+        )
+            .into_response();
+    }
+
+    let cached = state.tiles.read().unwrap().get(&(z, x, y)).cloned();
+    let bytes = match cached {
+        Some(bytes) => bytes,
+        None => match encode_tile(&state.location_index(), z, x, y) {
+            Ok(bytes) => {
+                let bytes = Bytes::from(bytes);
+                let mut tiles = state.tiles.write().unwrap();
+                if tiles.len() >= CACHE_CAPACITY {
+                    tiles.clear();
+                }
+                tiles.insert((z, x, y), bytes.clone());
+                bytes
+            }
+            Err(e) => {
+                return ApiError::internal(format!("Failed to encode tile: {}", e)).into_response();
+            }
+        },
+    };
+    ([(header::CONTENT_TYPE, MVT_CONTENT_TYPE)], bytes).into_response()
+}
@@ -0,0 +1,188 @@
+// Until Every Cage is Empty
+// Copyright (C) 2025 Eli Perez
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+// Contact the developer directly at untileverycageproject@protonmail.com
+//! APHIS animal-use numbers over the years, per research facility and in
+//! total. `us/aphis_data_final.csv` holds each facility's latest annual
+//! report; earlier reports in the same format go in `us/aphis_history/`, one
+//! CSV per year or release.
+
+use std::collections::BTreeMap;
+
+use axum::Json;
+use axum::extract::{Query, State};
+use axum::http::StatusCode;
+use axum::response::IntoResponse;
+use serde::{Deserialize, Serialize};
+
+use crate::AppState;
+use crate::location::{AphisReport, animal_counts};
+
+/// One year of reports, for one facility or summed over all of them.
+#[derive(Serialize, Default, Clone)]
+pub(crate) struct YearTotals {
+    pub(crate) facilities: usize,
+    /// Animals by species (see `animal_count_columns`).
+    pub(crate) animals: BTreeMap<&'static str, u64>,
+    pub(crate) total_animals: u64,
+}
+
+impl YearTotals {
+    fn add(&mut self, counts: &[(&'static str, u32)]) {
+        self.facilities += 1;
+        for &(species, animals) in counts {
+            *self.animals.entry(species).or_default() += u64::from(animals);
+            self.total_animals += u64::from(animals);
+        }
+    }
+}
+
+#[derive(Clone)]
+struct FacilityYears {
+    account_name: String,
+    /// Counts by report year.
+    years: BTreeMap<String, Vec<(&'static str, u32)>>,
+}
+
+/// Every facility's counts by year, built once per load.
+#[derive(Default, Clone)]
+pub(crate) struct AphisYears {
+    by_certificate: BTreeMap<String, FacilityYears>,
+}
+
+impl AphisYears {
+    /// `history` first, so a year in both keeps the current file's numbers.
+    pub(crate) fn new(current: &[AphisReport], history: &[AphisReport]) -> Self {
+        let mut by_certificate: BTreeMap<String, FacilityYears> = BTreeMap::new();
+        for report in history.iter().chain(current) {
+            let year = report.year.trim();
+            if year.is_empty() {
+                continue;
+            }
+            let facility = by_certificate
+                .entry(report.certificate_number.clone())
+                .or_insert_with(|| FacilityYears {
+                    account_name: String::new(),
+                    years: BTreeMap::new(),
+                });
+            facility.account_name = report.account_name.clone();
+            facility
+                .years
+                .insert(year.to_string(), animal_counts(report));
+        }
+        AphisYears { by_certificate }
+    }
+
+    /// Yearly reports held, current and earlier.
+    pub(crate) fn reports(&self) -> usize {
+        self.by_certificate
+            .values()
+            .map(|facility| facility.years.len())
+            .sum()
+    }
+
+    /// Every facility's reports summed per year.
+    pub(crate) fn aggregate(&self) -> BTreeMap<String, YearTotals> {
+        let mut totals: BTreeMap<String, YearTotals> = BTreeMap::new();
+        for facility in self.by_certificate.values() {
+            for (year, counts) in &facility.years {
+                totals.entry(year.clone()).or_default().add(counts);
+            }
+        }
+        totals
+    }
+}
+
+#[derive(Serialize)]
+struct YearPoint {
+    year: String,
+    #[serde(flatten)]
+    totals: YearTotals,
+    /// Change in `total_animals` since the previous year reported.
+    change: Option<i64>,
+}
+
+#[derive(Serialize)]
+struct Timeseries<'a> {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    certificate_number: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    account_name: Option<&'a str>,
+    years: Vec<YearPoint>,
+}
+
+fn points(years: BTreeMap<String, YearTotals>) -> Vec<YearPoint> {
+    let mut previous: Option<i64> = None;
+    years
+        .into_iter()
+        .map(|(year, totals)| {
+            let total = totals.total_animals as i64;
+            let change = previous.map(|previous| total - previous);
+            previous = Some(total);
+            YearPoint {
+                year,
+                totals,
+                change,
+            }
+        })
+        .collect()
+}
+
+#[derive(Deserialize)]
+pub struct TimeseriesParams {
+    /// A research facility's certificate number. Without it the series sums
+    /// every facility.
+    certificate: Option<String>,
+}
+
+/// GET /api/aphis-reports/timeseries
+pub async fn get_aphis_timeseries_handler(
+    State(state): State<AppState>,
+    Query(params): Query<TimeseriesParams>,
+) -> impl IntoResponse {
+    let reports = state.reports();
+    let Some(certificate) = params.certificate.as_deref() else {
+        return Json(Timeseries {
+            certificate_number: None,
+            account_name: None,
+            years: points(reports.aphis_years.aggregate()),
+        })
+        .into_response();
+    };
+
+    let Some(facility) = reports.aphis_years.by_certificate.get(certificate) else {
+        return (
+            StatusCode::NOT_FOUND,
+            format!("No APHIS reports for certificate number {}", certificate),
+        )
+            .into_response();
+    };
+    let years = facility
+        .years
+        .iter()
+        .map(|(year, counts)| {
+            let mut totals = YearTotals::default();
+            totals.add(counts);
+            (year.clone(), totals)
+        })
+        .collect();
+    Json(Timeseries {
+        certificate_number: Some(certificate),
+        account_name: Some(&facility.account_name),
+        years: points(years),
+    })
+    .into_response()
+}
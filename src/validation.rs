@@ -0,0 +1,346 @@
+// Until Every Cage is Empty
+// Copyright (C) 2025 Eli Perez
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+// Contact the developer directly at untileverycageproject@protonmail.com
+//! Checks every record's coordinates when the data is loaded: missing (0, 0),
+//! out of range, latitude and longitude swapped, or outside the country the
+//! record belongs to. What happens to a bad record is set with
+//! `COORDINATE_VALIDATION`; the findings are served at /api/data-quality.
+
+use std::collections::BTreeMap;
+
+use serde::Serialize;
+
+use crate::LocationResponse;
+use crate::aquaculture::AquacultureFacility;
+use crate::cafos::CafoFacility;
+use crate::egg_farms::EggFarmFacility;
+use crate::equine::EquineFacility;
+use crate::fur_farms::FurFarmFacility;
+use crate::location::{AphisReport, InspectionReport};
+use crate::settings::Settings;
+
+/// Rough bounding boxes, `[min_lat, min_lng, max_lat, max_lng]`, of each
+/// country with its islands and territories. Loose enough that border
+/// facilities stay in, tight enough to catch a sign error or a point in the
+/// wrong country.
+const COUNTRY_BOUNDS: &[(&str, &[[f64; 4]])] = &[
+    (
+        "us",
+        &[
+            [24.0, -125.0, 49.5, -66.5],
+            [51.0, -180.0, 71.5, -129.0],
+            [18.8, -160.5, 22.5, -154.5],
+            [17.6, -67.5, 18.6, -64.5],
+            [13.0, 144.5, 20.7, 146.2],
+            [-14.6, -171.1, -14.1, -169.4],
+        ],
+    ),
+    ("au", &[[-43.8, 112.9, -10.0, 153.7]]),
+    ("br", &[[-33.8, -74.0, 5.3, -34.7]]),
+    ("de", &[[47.2, 5.8, 55.1, 15.1]]),
+    ("dk", &[[54.5, 8.0, 57.8, 15.2]]),
+    ("es", &[[35.1, -9.4, 43.9, 4.4], [27.6, -18.2, 29.5, -13.3]]),
+    (
+        "fr",
+        &[
+            [41.3, -5.3, 51.2, 9.6],
+            [-21.4, 55.2, -20.8, 55.9],
+            [15.8, -61.9, 16.6, -61.0],
+            [14.3, -61.3, 14.9, -60.8],
+            [2.1, -54.6, 5.8, -51.6],
+            [-13.1, 45.0, -12.6, 45.3],
+        ],
+    ),
+    ("it", &[[35.4, 6.6, 47.1, 18.6]]),
+    ("gb", &[[49.1, -8.7, 60.9, 1.8]]),
+];
+
+/// What is wrong with a record's coordinates.
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum CoordinateIssue {
+    /// 0, 0 or not a number: the source had none, and geocoding hasn't
+    /// filled them in yet.
+    Missing,
+    /// Latitude beyond ±90 or longitude beyond ±180.
+    OutOfRange,
+    /// Inside the country once latitude and longitude are swapped.
+    Swapped,
+    OutsideCountry,
+}
+
+fn inside(country: &str, latitude: f64, longitude: f64) -> Option<bool> {
+    let (_, boxes) = COUNTRY_BOUNDS.iter().find(|(code, _)| *code == country)?;
+    Some(boxes.iter().any(|[min_lat, min_lng, max_lat, max_lng]| {
+        (*min_lat..=*max_lat).contains(&latitude) && (*min_lng..=*max_lng).contains(&longitude)
+    }))
+}
+
+/// The problem with a point in `country`, if any. Countries without bounds
+/// are only checked for range.
+pub(crate) fn check(country: &str, latitude: f64, longitude: f64) -> Option<CoordinateIssue> {
+    if !latitude.is_finite() || !longitude.is_finite() || (latitude == 0.0 && longitude == 0.0) {
+        return Some(CoordinateIssue::Missing);
+    }
+    let in_range = (-90.0..=90.0).contains(&latitude) && (-180.0..=180.0).contains(&longitude);
+    match inside(country, latitude, longitude) {
+        Some(true) => None,
+        _ if inside(country, longitude, latitude) == Some(true) => Some(CoordinateIssue::Swapped),
+        _ if !in_range => Some(CoordinateIssue::OutOfRange),
+        Some(false) => Some(CoordinateIssue::OutsideCountry),
+        None => None,
+    }
+}
+
+/// What to do with records whose coordinates are wrong (not merely
+/// missing), set with `COORDINATE_VALIDATION`.
+#[derive(Serialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Policy {
+    /// Report them and serve them as they are.
+    Flag,
+    /// Serve them without coordinates (0, 0) so they don't show up in the
+    /// wrong place, and list them for fixing. The geocode job picks them up.
+    #[default]
+    Quarantine,
+    /// Leave them out of the served data.
+    Drop,
+}
+
+impl Policy {
+    pub fn from_settings(settings: &Settings) -> Result<Self, String> {
+        match settings.get("COORDINATE_VALIDATION") {
+            None | Some("") | Some("quarantine") => Ok(Policy::Quarantine),
+            Some("flag") => Ok(Policy::Flag),
+            Some("drop") => Ok(Policy::Drop),
+            Some(other) => Err(format!(
+                "COORDINATE_VALIDATION must be flag, quarantine or drop, not {}",
+                other
+            )),
+        }
+    }
+}
+
+/// A record with a point location to validate.
+pub(crate) trait Located {
+    fn country(&self) -> &str;
+    fn record_id(&self) -> String;
+    fn record_name(&self) -> &str;
+    fn coordinates(&mut self) -> (&mut f64, &mut f64);
+}
+
+impl Located for LocationResponse {
+    fn country(&self) -> &str {
+        &self.country
+    }
+    fn record_id(&self) -> String {
+        self.establishment_id.clone()
+    }
+    fn record_name(&self) -> &str {
+        &self.establishment_name
+    }
+    fn coordinates(&mut self) -> (&mut f64, &mut f64) {
+        (&mut self.latitude, &mut self.longitude)
+    }
+}
+
+impl Located for AphisReport {
+    fn country(&self) -> &str {
+        "us"
+    }
+    fn record_id(&self) -> String {
+        self.certificate_number.clone()
+    }
+    fn record_name(&self) -> &str {
+        &self.account_name
+    }
+    fn coordinates(&mut self) -> (&mut f64, &mut f64) {
+        (&mut self.latitude, &mut self.longitude)
+    }
+}
+
+impl Located for InspectionReport {
+    fn country(&self) -> &str {
+        "us"
+    }
+    fn record_id(&self) -> String {
+        self.certificate_number.clone()
+    }
+    fn record_name(&self) -> &str {
+        &self.account_name
+    }
+    fn coordinates(&mut self) -> (&mut f64, &mut f64) {
+        (&mut self.latitude, &mut self.longitude)
+    }
+}
+
+impl Located for AquacultureFacility {
+    fn country(&self) -> &str {
+        &self.country
+    }
+    fn record_id(&self) -> String {
+        self.site.site_id.clone()
+    }
+    fn record_name(&self) -> &str {
+        &self.site.name
+    }
+    fn coordinates(&mut self) -> (&mut f64, &mut f64) {
+        (&mut self.site.latitude, &mut self.site.longitude)
+    }
+}
+
+impl Located for CafoFacility {
+    fn country(&self) -> &str {
+        &self.country
+    }
+    fn record_id(&self) -> String {
+        self.permit.permit_id.clone()
+    }
+    fn record_name(&self) -> &str {
+        &self.permit.name
+    }
+    fn coordinates(&mut self) -> (&mut f64, &mut f64) {
+        (&mut self.permit.latitude, &mut self.permit.longitude)
+    }
+}
+
+impl Located for EggFarmFacility {
+    fn country(&self) -> &str {
+        &self.country
+    }
+    fn record_id(&self) -> String {
+        self.farm.farm_id.clone()
+    }
+    fn record_name(&self) -> &str {
+        &self.farm.name
+    }
+    fn coordinates(&mut self) -> (&mut f64, &mut f64) {
+        (&mut self.farm.latitude, &mut self.farm.longitude)
+    }
+}
+
+impl Located for EquineFacility {
+    fn country(&self) -> &str {
+        &self.country
+    }
+    fn record_id(&self) -> String {
+        self.site.site_id.clone()
+    }
+    fn record_name(&self) -> &str {
+        &self.site.name
+    }
+    fn coordinates(&mut self) -> (&mut f64, &mut f64) {
+        (&mut self.site.latitude, &mut self.site.longitude)
+    }
+}
+
+impl Located for FurFarmFacility {
+    fn country(&self) -> &str {
+        &self.country
+    }
+    fn record_id(&self) -> String {
+        self.farm.farm_id.clone()
+    }
+    fn record_name(&self) -> &str {
+        &self.farm.name
+    }
+    fn coordinates(&mut self) -> (&mut f64, &mut f64) {
+        (&mut self.farm.latitude, &mut self.farm.longitude)
+    }
+}
+
+/// Counts of each issue in one dataset.
+#[derive(Serialize, Default, Clone)]
+pub(crate) struct IssueCounts {
+    pub(crate) checked: usize,
+    pub(crate) missing: usize,
+    pub(crate) out_of_range: usize,
+    pub(crate) swapped: usize,
+    pub(crate) outside_country: usize,
+}
+
+/// A record whose coordinates were found wrong, with what it had.
+#[derive(Serialize, Clone)]
+pub(crate) struct Flagged {
+    pub(crate) dataset: &'static str,
+    pub(crate) country: String,
+    pub(crate) id: String,
+    pub(crate) name: String,
+    pub(crate) latitude: f64,
+    pub(crate) longitude: f64,
+    pub(crate) issue: CoordinateIssue,
+}
+
+/// The findings of validating some datasets.
+#[derive(Serialize, Default, Clone)]
+pub(crate) struct CoordinateReport {
+    pub(crate) datasets: BTreeMap<&'static str, IssueCounts>,
+    /// Every record with wrong coordinates, whatever the policy did with it.
+    pub(crate) flagged: Vec<Flagged>,
+}
+
+impl CoordinateReport {
+    /// Checks `records` of `dataset`, applying `policy` to the wrong ones.
+    pub(crate) fn validate<T: Located>(
+        &mut self,
+        dataset: &'static str,
+        records: &mut Vec<T>,
+        policy: Policy,
+    ) {
+        let counts = self.datasets.entry(dataset).or_default();
+        let flagged = &mut self.flagged;
+        records.retain_mut(|record| {
+            counts.checked += 1;
+            let country = record.country().to_string();
+            let (latitude, longitude) = record.coordinates();
+            let (lat, lng) = (*latitude, *longitude);
+            let Some(issue) = check(&country, lat, lng) else {
+                return true;
+            };
+            match issue {
+                CoordinateIssue::Missing => {
+                    counts.missing += 1;
+                    return true;
+                }
+                CoordinateIssue::OutOfRange => counts.out_of_range += 1,
+                CoordinateIssue::Swapped => counts.swapped += 1,
+                CoordinateIssue::OutsideCountry => counts.outside_country += 1,
+            }
+            if policy == Policy::Quarantine {
+                *latitude = 0.0;
+                *longitude = 0.0;
+            }
+            flagged.push(Flagged {
+                dataset,
+                country,
+                id: record.record_id(),
+                name: record.record_name().to_string(),
+                latitude: lat,
+                longitude: lng,
+                issue,
+            });
+            policy != Policy::Drop
+        });
+    }
+
+    pub(crate) fn merge(&mut self, other: &CoordinateReport) {
+        for (dataset, counts) in &other.datasets {
+            self.datasets.insert(dataset, counts.clone());
+        }
+        self.flagged.extend(other.flagged.iter().cloned());
+    }
+}
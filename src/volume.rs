@@ -0,0 +1,116 @@
+// Until Every Cage is Empty
+// Copyright (C) 2025 Eli Perez
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+// Contact the developer directly at untileverycageproject@protonmail.com
+//! Head counts behind FSIS's `slaughter_volume_category`.
+//!
+//! The MPI Directory's establishment demographic data groups establishments
+//! by annual slaughter volume in steps of a factor of ten, 1 being the
+//! smallest and 5 the largest:
+//! <https://www.fsis.usda.gov/inspection/establishments/meat-poultry-and-egg-product-inspection-directory>
+//!
+//! | Category | Head per year           |
+//! |----------|-------------------------|
+//! | 1        | fewer than 1,000        |
+//! | 2        | 1,000 to 10,000         |
+//! | 3        | 10,000 to 100,000       |
+//! | 4        | 100,000 to 1,000,000    |
+//! | 5        | 1,000,000 or more       |
+//!
+//! Category 5 is open-ended. So that totals stay finite its upper bound is
+//! taken as `CATEGORY_5_CEILING`, our assumption rather than FSIS's: the
+//! largest US poultry plants kill around two million birds a week.
+
+use std::collections::BTreeMap;
+
+use serde::Serialize;
+
+/// Assumed most head any one establishment slaughters in a year.
+pub const CATEGORY_5_CEILING: u64 = 100_000_000;
+
+/// `(category, min, max)` head per year.
+const SLAUGHTER_CATEGORIES: &[(u8, u64, u64)] = &[
+    (1, 0, 1_000),
+    (2, 1_000, 10_000),
+    (3, 10_000, 100_000),
+    (4, 100_000, 1_000_000),
+    (5, 1_000_000, CATEGORY_5_CEILING),
+];
+
+/// A range of head slaughtered per year.
+#[derive(Serialize, Debug, Clone, Copy, Default, PartialEq)]
+pub struct Estimate {
+    pub min: u64,
+    pub max: u64,
+}
+
+impl Estimate {
+    fn add(&mut self, other: Estimate) {
+        self.min += other.min;
+        self.max += other.max;
+    }
+}
+
+/// The annual slaughter range of an FSIS category ("3.0"), if it is one.
+pub fn annual_slaughter(category: &str) -> Option<Estimate> {
+    let category = category.trim().parse::<f64>().ok()?;
+    SLAUGHTER_CATEGORIES
+        .iter()
+        .find(|(known, _, _)| f64::from(*known) == category)
+        .map(|&(_, min, max)| Estimate { min, max })
+}
+
+/// One head count standing for an FSIS category, for weighing establishments
+/// against each other: the geometric middle of its factor of ten, from 316
+/// head a year for category 1 to 3,162,278 for category 5. The open ends of
+/// categories 1 and 5 are taken as a factor of ten like the others.
+pub fn typical_annual_slaughter(category: &str) -> Option<f64> {
+    let category = category.trim().parse::<f64>().ok()?;
+    SLAUGHTER_CATEGORIES
+        .iter()
+        .any(|(known, _, _)| f64::from(*known) == category)
+        .then(|| 10f64.powf(category + 1.5))
+}
+
+/// National totals of the estimates.
+#[derive(Serialize, Default)]
+pub struct Totals {
+    /// Establishments with a volume category.
+    pub establishments: usize,
+    pub total: Estimate,
+    /// By species group. The category counts every species an
+    /// establishment slaughters together, so an establishment only adds to
+    /// a group's minimum when that group is all it slaughters, and adds its
+    /// whole maximum to each of its groups.
+    pub by_species: BTreeMap<&'static str, Estimate>,
+}
+
+impl Totals {
+    pub fn add(&mut self, estimate: Estimate, species: &[&'static str]) {
+        self.establishments += 1;
+        self.total.add(estimate);
+        for group in species {
+            let share = match species.len() {
+                1 => estimate,
+                _ => Estimate {
+                    min: 0,
+                    max: estimate.max,
+                },
+            };
+            self.by_species.entry(group).or_default().add(share);
+        }
+    }
+}
@@ -127,7 +127,7 @@ export function getSelectedCountryForLocation(location) {
     if (location.country === 'de') return 'DE';
     if (location.country === 'es') return 'ES';
     if (location.country === 'fr') return 'FR';
-    if (location.country === 'uk') return 'UK';
+    if (location.country === 'gb') return 'UK';
     return 'all';
 }
 
@@ -0,0 +1,1274 @@
+//! Serves the small dataset in `tests/fixtures/api/` (laid out like
+//! static_data) through the full router and checks what the endpoints
+//! answer, plus the summaries computed from single records.
+//!
+//! The fixture has four US locations (Lebanon PA, Buffalo Lake MN,
+//! Gainesville GA, Fresno CA), one German and one British one, two APHIS
+//! research facilities and two licensees, with FSIS enforcement records
+//! against Buffalo Lake, citations from two inspections and the centroid of
+//! one licensee's ZIP, and an older snapshot of the German list. Tests that
+//! count records count these.
+
+use std::collections::BTreeSet;
+use std::path::Path;
+
+use axum::Router;
+use axum::body::Body;
+use axum::http::{HeaderMap, Request, StatusCode, header};
+use heatmap_backend::settings::Settings;
+use heatmap_backend::slug;
+use heatmap_backend::store::{DataSource, Dataset};
+use heatmap_backend::{
+    AphisReport, AppState, Location, TOTAL_COUNT_HEADER, app, get_slaughtered_animals,
+    get_tested_animals, read_aphis_reports_from_csv,
+};
+use serde_json::Value;
+use tower::ServiceExt;
+
+fn fixture(name: &str) -> std::path::PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/fixtures/api")
+        .join(name)
+}
+
+async fn app() -> Router {
+    let state = AppState::from_source(DataSource::Directory(fixture("")))
+        .await
+        .unwrap();
+    app::router(state, app::cors_from_env().unwrap())
+}
+
+struct Response {
+    status: StatusCode,
+    headers: HeaderMap,
+    body: Vec<u8>,
+}
+
+impl Response {
+    fn json(&self) -> Value {
+        serde_json::from_slice(&self.body).unwrap_or_else(|e| {
+            panic!("{}: {}", e, String::from_utf8_lossy(&self.body));
+        })
+    }
+
+    fn text(&self) -> String {
+        String::from_utf8_lossy(&self.body).into_owned()
+    }
+
+    fn total_count(&self) -> usize {
+        self.headers[TOTAL_COUNT_HEADER]
+            .to_str()
+            .unwrap()
+            .parse()
+            .unwrap()
+    }
+}
+
+async fn get(app: &Router, uri: &str) -> Response {
+    let response = app
+        .clone()
+        .oneshot(Request::get(uri).body(Body::empty()).unwrap())
+        .await
+        .unwrap();
+    let status = response.status();
+    let headers = response.headers().clone();
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .unwrap();
+    Response {
+        status,
+        headers,
+        body: body.to_vec(),
+    }
+}
+
+async fn post_json(app: &Router, uri: &str, body: Value, headers: &[(&str, &str)]) -> Response {
+    send_json(app, Request::post(uri), body, headers).await
+}
+
+async fn send_json(
+    app: &Router,
+    request: axum::http::request::Builder,
+    body: Value,
+    headers: &[(&str, &str)],
+) -> Response {
+    let mut request = request.header(header::CONTENT_TYPE, "application/json");
+    for (name, value) in headers {
+        request = request.header(*name, *value);
+    }
+    let response = app
+        .clone()
+        .oneshot(request.body(Body::from(body.to_string())).unwrap())
+        .await
+        .unwrap();
+    let status = response.status();
+    let headers = response.headers().clone();
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .unwrap();
+    Response {
+        status,
+        headers,
+        body: body.to_vec(),
+    }
+}
+
+/// The `field` of each item of a JSON list response, in order.
+fn column(response: &Response, field: &str) -> Vec<String> {
+    response
+        .json()
+        .as_array()
+        .unwrap_or_else(|| panic!("not a list: {}", response.text()))
+        .iter()
+        .map(|item| item[field].as_str().unwrap_or_default().to_string())
+        .collect()
+}
+
+fn read_csv<T: serde::de::DeserializeOwned>(name: &str) -> Vec<T> {
+    csv::Reader::from_path(fixture(name))
+        .unwrap()
+        .deserialize()
+        .collect::<Result<_, _>>()
+        .unwrap()
+}
+
+#[tokio::test]
+async fn locations_of_every_country() {
+    let app = app().await;
+    let response = get(&app, "/api/locations").await;
+    assert_eq!(response.status, StatusCode::OK);
+    assert_eq!(
+        column(&response, "establishment_id"),
+        ["BY 20581", "121", "6407", "6165469", "5001", "7002"]
+    );
+    assert_eq!(response.total_count(), 6);
+    assert_eq!(
+        column(&response, "country"),
+        ["de", "gb", "us", "us", "us", "us"]
+    );
+}
+
+#[tokio::test]
+async fn locations_filtered() {
+    let app = app().await;
+    let ids = |response: Response| column(&response, "establishment_id");
+    assert_eq!(
+        ids(get(&app, "/api/locations?country_code=US&state=pa").await),
+        ["6407"]
+    );
+    assert_eq!(
+        ids(get(&app, "/api/locations?country=de").await),
+        ["BY 20581"]
+    );
+    assert_eq!(
+        ids(get(&app, "/api/locations?species=cattle").await),
+        ["BY 20581", "121", "6407", "6165469"]
+    );
+    assert_eq!(
+        ids(get(&app, "/api/locations?species=chicken").await),
+        ["5001"]
+    );
+    assert_eq!(
+        ids(get(&app, "/api/locations?granted_after=2020-01-01").await),
+        ["6407", "6165469"]
+    );
+    let response = get(&app, "/api/locations?country_code=us&species=pig").await;
+    assert_eq!(response.total_count(), 1);
+}
+
+#[tokio::test]
+async fn establishments_listed_per_section_are_one_facility() {
+    // The German fixture lists BY 20581 twice, once per approval section,
+    // and only the second row flags cattle.
+    let app = app().await;
+    let response = get(&app, "/api/locations?country=de").await;
+    assert_eq!(column(&response, "establishment_id"), ["BY 20581"]);
+    let location = &response.json()[0];
+    assert!(
+        location["animals_slaughtered"]
+            .as_str()
+            .unwrap()
+            .contains("Cattle"),
+        "{}",
+        location
+    );
+
+    let response = get(&app, "/api/locations").await;
+    let ids = column(&response, "facility_id");
+    let unique: std::collections::HashSet<&String> = ids.iter().collect();
+    assert_eq!(unique.len(), ids.len(), "{:?}", ids);
+}
+
+#[tokio::test]
+async fn united_kingdom_is_served_as_gb() {
+    // The fixture's uk/locations.csv names the country GB in its own column.
+    let app = app().await;
+    for country in ["gb", "GB", "uk", "UK"] {
+        let response = get(&app, &format!("/api/locations?country={}", country)).await;
+        assert_eq!(
+            column(&response, "establishment_id"),
+            ["121"],
+            "{}",
+            country
+        );
+        assert_eq!(column(&response, "country"), ["gb"], "{}", country);
+    }
+
+    let response = get(&app, "/api/facilities?country_code=uk").await;
+    assert_eq!(column(&response, "country"), ["gb"]);
+    let response = get(&app, "/api/equine?country_code=MX").await;
+    assert_eq!(column(&response, "site_id"), ["TIF 103"]);
+}
+
+#[tokio::test]
+async fn changes_need_a_snapshot_from_before_since() {
+    // The German list has one snapshot, from 2025-01-01, when BY 20581 went
+    // by another name.
+    let app = app().await;
+    let response = get(&app, "/api/locations/changes?since=2024-06-01").await;
+    assert_eq!(response.status, StatusCode::NOT_FOUND);
+    assert!(
+        response.text().contains("2025-01-01"),
+        "{}",
+        response.text()
+    );
+    let response = get(
+        &app,
+        "/api/locations/changes?since=2025-03-01&country_code=us",
+    )
+    .await;
+    assert_eq!(response.status, StatusCode::NOT_FOUND);
+
+    let response = get(&app, "/api/locations/changes?since=2025-03-01").await;
+    assert_eq!(response.status, StatusCode::OK);
+    let changes = response.json();
+    assert_eq!(column(&response, "country"), ["de"]);
+    assert_eq!(changes[0]["from"], "2025-01-01");
+    let renamed = &changes[0]["modified"][0];
+    assert_eq!(renamed["establishment_id"], "BY 20581");
+    assert!(
+        renamed["changes"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .any(|change| change["before"] == "Rosenstingl Metzgerei"),
+        "{}",
+        renamed
+    );
+}
+
+#[tokio::test]
+async fn locations_paged() {
+    let app = app().await;
+    let response = get(&app, "/api/locations?limit=2&offset=4").await;
+    assert_eq!(column(&response, "establishment_id"), ["5001", "7002"]);
+    // The total is of every match, not of the page.
+    assert_eq!(response.total_count(), 6);
+
+    let response = get(&app, "/api/locations?limit=2&offset=5").await;
+    assert_eq!(column(&response, "establishment_id"), ["7002"]);
+
+    let response = get(&app, "/api/locations?offset=10").await;
+    assert_eq!(response.status, StatusCode::OK);
+    assert_eq!(response.json(), serde_json::json!([]));
+}
+
+#[tokio::test]
+async fn locations_with_chosen_fields() {
+    let app = app().await;
+    let response = get(
+        &app,
+        "/api/locations?country_code=us&state=GA&fields=establishment_id,latitude",
+    )
+    .await;
+    assert_eq!(
+        response.json(),
+        serde_json::json!([{ "establishment_id": "5001", "latitude": 34.297884 }])
+    );
+}
+
+#[tokio::test]
+async fn locations_as_geojson_and_csv() {
+    let app = app().await;
+    let response = get(&app, "/api/locations?country=de&format=geojson").await;
+    let collection = response.json();
+    assert_eq!(collection["type"], "FeatureCollection");
+    assert_eq!(
+        collection["features"][0]["geometry"]["coordinates"],
+        serde_json::json!([13.3107354, 48.40372])
+    );
+
+    let response = get(&app, "/api/locations?state=MN&format=csv").await;
+    assert_eq!(response.status, StatusCode::OK);
+    assert!(
+        response.headers[header::CONTENT_TYPE]
+            .to_str()
+            .unwrap()
+            .starts_with("text/csv")
+    );
+    let csv = response.text();
+    assert!(csv.starts_with("facility_id,"), "{}", csv);
+    assert_eq!(csv.lines().count(), 2);
+    assert!(csv.contains("Buffalo Lake Beef"));
+}
+
+#[tokio::test]
+async fn location_by_establishment_id() {
+    let app = app().await;
+    let response = get(&app, "/api/locations/6407").await;
+    assert_eq!(response.status, StatusCode::OK);
+    let location = response.json();
+    assert_eq!(location["establishment_name"], "Lebanon Meats");
+    assert_eq!(location["grant_date"], "2021-11-02");
+    assert_eq!(
+        location["species_slaughtered"],
+        serde_json::json!(["cattle", "pig"])
+    );
+
+    let response = get(&app, "/api/locations/nope").await;
+    assert_eq!(response.status, StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn bad_parameters_are_refused() {
+    let app = app().await;
+    for (uri, message) in [
+        ("/api/locations?limit=abc", "limit"),
+        ("/api/locations?species=unicorn", "Unknown species unicorn"),
+        ("/api/locations?status=gone", "Unknown status gone"),
+        ("/api/locations?fields=nope", "Unknown fields nope"),
+        ("/api/locations?sort=name", "Unsupported sort field name"),
+        ("/api/locations?format=xml", "Unsupported format xml"),
+        ("/api/locations?granted_after=yesterday", "granted_after"),
+    ] {
+        let response = get(&app, uri).await;
+        assert_eq!(response.status, StatusCode::BAD_REQUEST, "{}", uri);
+        assert!(
+            response.text().contains(message),
+            "{}: {}",
+            uri,
+            response.text()
+        );
+    }
+    assert_eq!(get(&app, "/api/nope").await.status, StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn aphis_reports_read_loose_counts() {
+    let app = app().await;
+    let response = get(&app, "/api/aphis-reports").await;
+    assert_eq!(response.status, StatusCode::OK);
+    let reports = response.json();
+    let utah = &reports[0];
+    assert_eq!(utah["Certificate Number"], "87-R-0002");
+    // "83.0" and "1,440" in the CSV.
+    assert_eq!(utah["Guinea Pigs"], 83);
+    assert_eq!(utah["Hamsters"], 1440);
+    assert_eq!(utah["Dogs"], Value::Null);
+    assert_eq!(
+        utah["Animals Tested On"],
+        "83 Guinea Pigs, 1440 Hamsters, 230 Sheep, 22 Pigs, 88 Other Farm Animals"
+    );
+    assert_eq!(reports[1]["Animals Tested On"], "Unknown");
+
+    let response = get(&app, "/api/aphis-reports?limit=1&fields=Account%20Name").await;
+    assert_eq!(
+        response.json(),
+        serde_json::json!([{ "Account Name": "Utah State University" }])
+    );
+    assert_eq!(response.total_count(), 2);
+}
+
+#[tokio::test]
+async fn aphis_rows_that_dont_read_fail_the_load() {
+    let dir = std::env::temp_dir().join(format!("aphis-rows-{}", std::process::id()));
+    std::fs::create_dir_all(dir.join("us")).unwrap();
+    let reports = std::fs::read_to_string(fixture("us/aphis_data_final.csv")).unwrap();
+    std::fs::write(
+        dir.join("us/aphis_data_final.csv"),
+        reports.replacen("41.641189", "north", 1),
+    )
+    .unwrap();
+    let dataset = Dataset::from_directory(&dir).unwrap();
+    let error = read_aphis_reports_from_csv(&dataset).await.map(|_| ());
+    std::fs::remove_dir_all(&dir).unwrap();
+    let error = error.unwrap_err().to_string();
+    assert!(error.starts_with("us/aphis_data_final.csv"), "{}", error);
+}
+
+#[tokio::test]
+async fn derived_columns_are_not_reported_missing() {
+    let app = app().await;
+    let response = get(&app, "/api/data-quality").await;
+    let datasets = response.json()["datasets"].as_array().unwrap().clone();
+    let reports = datasets
+        .iter()
+        .find(|dataset| dataset["dataset"] == "us/aphis_data_final.csv")
+        .unwrap();
+    assert_eq!(reports["missing_columns"], serde_json::json!([]));
+}
+
+#[tokio::test]
+async fn reports_by_split_address() {
+    let app = app().await;
+    let response = get(&app, "/api/aphis-reports?fields=City,State,Zip").await;
+    assert_eq!(
+        response.json(),
+        serde_json::json!([
+            { "City": "Logan", "State": "UT", "Zip": "84322" },
+            { "City": "Provo", "State": "UT", "Zip": "84602-1231" },
+        ])
+    );
+    let response = get(&app, "/api/aphis-reports?city=provo&state=ut").await;
+    assert_eq!(column(&response, "Certificate Number"), ["87-R-0003"]);
+    let response = get(&app, "/api/aphis-reports?zip=843").await;
+    assert_eq!(column(&response, "Certificate Number"), ["87-R-0002"]);
+
+    // Filled in from City-State-Zip where the geocoder left them empty.
+    let response = get(&app, "/api/inspection-reports?state=AL&zip=35260").await;
+    assert_eq!(column(&response, "Certificate Number"), ["64-B-0063"]);
+    assert_eq!(column(&response, "City"), ["Birmingham"]);
+
+    let response = get(&app, "/api/inspection-reports?state=Alabama").await;
+    assert_eq!(response.status, StatusCode::BAD_REQUEST);
+    let response = get(&app, "/api/aphis-reports?zip=ab1").await;
+    assert_eq!(response.status, StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+async fn inspection_reports_by_license_type() {
+    let app = app().await;
+    let response = get(&app, "/api/inspection-reports?license_type=C").await;
+    assert_eq!(response.status, StatusCode::OK);
+    assert_eq!(column(&response, "Certificate Number"), ["64-C-0258"]);
+
+    let response = get(&app, "/api/inspection-reports").await;
+    assert_eq!(response.total_count(), 2);
+}
+
+#[tokio::test]
+async fn facilities_across_datasets() {
+    let app = app().await;
+    let response = get(&app, "/api/facilities").await;
+    assert_eq!(response.total_count(), 13);
+
+    let response = get(
+        &app,
+        "/api/facilities?source=aphis_reports,inspection_reports",
+    )
+    .await;
+    assert_eq!(
+        column(&response, "source"),
+        [
+            "aphis_reports",
+            "aphis_reports",
+            "inspection_reports",
+            "inspection_reports"
+        ]
+    );
+
+    let response = get(&app, "/api/facilities?source=zoos").await;
+    assert_eq!(response.status, StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+async fn equine_export_and_foreign_plants() {
+    let app = app().await;
+    let response = get(&app, "/api/equine").await;
+    assert_eq!(response.status, StatusCode::OK);
+    // Mexico has no locations, but its plants are read all the same.
+    assert_eq!(column(&response, "country"), ["mx", "us", "us"]);
+
+    let response = get(&app, "/api/equine?stage=slaughter").await;
+    let plants = response.json();
+    assert_eq!(plants[0]["site_id"], "TIF 103");
+    assert_eq!(plants[0]["horses"], 412);
+    assert_eq!(
+        plants[0]["activities"],
+        serde_json::json!(["meat_slaughter", "equine_export"])
+    );
+
+    let response = get(&app, "/api/equine?species=donkey").await;
+    assert_eq!(column(&response, "stage"), ["export_pen"]);
+    let response = get(&app, "/api/equine?species=equine").await;
+    assert_eq!(response.total_count(), 3);
+
+    let response = get(&app, "/api/equine?stage=auction").await;
+    assert_eq!(response.status, StatusCode::BAD_REQUEST);
+}
+
+/// The `component` entry of a record's `priority_breakdown`.
+fn score_component(record: &Value, component: &str) -> Value {
+    record["priority_breakdown"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|entry| entry["component"] == component)
+        .unwrap_or_else(|| panic!("no {} in {}", component, record))
+        .clone()
+}
+
+#[tokio::test]
+async fn priority_scores_count_violations_and_license_status() {
+    let app = app().await;
+    let lebanon = get(&app, "/api/locations/6407").await.json();
+    let buffalo_lake = get(&app, "/api/locations/6165469").await.json();
+    // Five FSIS enforcement records against none.
+    assert_eq!(score_component(&buffalo_lake, "violations")["value"], 0.5);
+    assert_eq!(score_component(&lebanon, "violations")["value"], 0.0);
+    assert_eq!(score_component(&lebanon, "license")["value"], 1.0);
+    // Lebanon kills more species at the same volume, but the violations
+    // outweigh that.
+    assert!(
+        buffalo_lake["priority_score"].as_f64() > lebanon["priority_score"].as_f64(),
+        "{} vs {}",
+        buffalo_lake["priority_score"],
+        lebanon["priority_score"]
+    );
+
+    let response = get(&app, "/api/aphis-reports?sort=priority_score").await;
+    let reports = response.json();
+    let byu = reports
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|report| report["Certificate Number"] == "87-R-0003")
+        .unwrap();
+    // One direct citation; the teachable moment wasn't cited.
+    assert_eq!(score_component(byu, "violations")["value"], 0.1);
+}
+
+#[tokio::test]
+async fn graphql_refuses_deeply_nested_values() {
+    let app = app().await;
+    for query in [
+        format!("{{ facilities(limit: {}1) {{ id }} }}", "[".repeat(100_000)),
+        format!(
+            "{{ facilities(limit: {}) {{ id }} }}",
+            "{a: ".repeat(100_000)
+        ),
+        format!(
+            "query ($limit: {}Int) {{ facilities {{ id }} }}",
+            "[".repeat(100_000)
+        ),
+    ] {
+        let response = post_json(
+            &app,
+            "/api/graphql",
+            serde_json::json!({ "query": query }),
+            &[],
+        )
+        .await;
+        assert_eq!(response.status, StatusCode::BAD_REQUEST);
+        assert!(
+            response.text().contains("nest at most"),
+            "{}",
+            response.text()
+        );
+    }
+    // Nesting within the limit still parses.
+    let response = post_json(
+        &app,
+        "/api/graphql",
+        serde_json::json!({ "query": "{ facilities(source: [[\"locations\"]]) { id } }" }),
+        &[],
+    )
+    .await;
+    assert_ne!(response.status, StatusCode::INTERNAL_SERVER_ERROR);
+    assert!(
+        !response.text().contains("nest at most"),
+        "{}",
+        response.text()
+    );
+}
+
+#[tokio::test]
+async fn openapi_describes_every_route() {
+    let response = get(&app().await, "/api/openapi.json").await;
+    let documented: BTreeSet<(String, String)> = response.json()["paths"]
+        .as_object()
+        .unwrap()
+        .iter()
+        .flat_map(|(path, operations)| {
+            operations
+                .as_object()
+                .unwrap()
+                .keys()
+                .map(move |method| (method.to_uppercase(), path.clone()))
+        })
+        .collect();
+    let routed: BTreeSet<(String, String)> = app::routes()
+        .iter()
+        .map(|route| (route.method.to_string(), route.path.to_string()))
+        .collect();
+    assert_eq!(
+        routed.difference(&documented).collect::<Vec<_>>(),
+        Vec::<&(String, String)>::new(),
+        "routes missing from openapi.rs"
+    );
+    assert_eq!(
+        documented.difference(&routed).collect::<Vec<_>>(),
+        Vec::<&(String, String)>::new(),
+        "operations in openapi.rs with no route"
+    );
+}
+
+#[tokio::test]
+async fn graphql_merges_fields_under_the_same_key() {
+    let app = app().await;
+    let response = get(&app, "/api/facilities?source=aphis_reports").await;
+    let id = column(&response, "id")[0].clone();
+    let query = format!(
+        r#"{{
+            facility(id: "{id}") {{ aphis_report {{ year }} aphis_report {{ certificate_number }} }}
+            first: facilities(limit: 1) {{ id }}
+            first: facilities(limit: 1) {{ name }}
+        }}"#
+    );
+    let response = post_json(
+        &app,
+        "/api/graphql",
+        serde_json::json!({ "query": query }),
+        &[],
+    )
+    .await;
+    assert_eq!(response.status, StatusCode::OK);
+    let data = &response.json()["data"];
+    let report = data["facility"]["aphis_report"].as_object().unwrap();
+    assert_eq!(
+        report.keys().collect::<Vec<_>>(),
+        ["year", "certificate_number"]
+    );
+    let first = data["first"][0].as_object().unwrap();
+    assert_eq!(first.keys().collect::<Vec<_>>(), ["id", "name"]);
+
+    for (query, message) in [
+        (
+            "{ a: facilities(limit: 1) { id } a: aphis_reports { year } }",
+            "facilities and aphis_reports are different fields",
+        ),
+        (
+            "{ facilities(limit: 1) { id } facilities(limit: 2) { id } }",
+            "different arguments",
+        ),
+        (
+            "{ facilities { id: name id } }",
+            "name and id are different fields",
+        ),
+    ] {
+        let response = post_json(
+            &app,
+            "/api/graphql",
+            serde_json::json!({ "query": query }),
+            &[],
+        )
+        .await;
+        assert_eq!(response.status, StatusCode::BAD_REQUEST, "{}", query);
+        assert!(response.text().contains(message), "{}", response.text());
+    }
+}
+
+#[tokio::test]
+async fn heatmap_weights() {
+    let app = app().await;
+    let weights = |response: Response| -> Vec<(String, f64)> {
+        response
+            .json()
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|point| {
+                (
+                    point["source"].as_str().unwrap().to_string(),
+                    point["weight"].as_f64().unwrap(),
+                )
+            })
+            .collect()
+    };
+    let point = |source: &str, weight: f64| (source.to_string(), weight);
+
+    // Volume categories 3, 3, 5 and processing-only 2; the German location
+    // has none and the second APHIS facility no counts.
+    assert_eq!(
+        weights(get(&app, "/api/heatmap").await),
+        [
+            point("locations", 0.6),
+            point("locations", 0.6),
+            point("locations", 1.0),
+            point("locations", 0.2),
+            point("aphis_reports", 0.818),
+        ]
+    );
+    assert_eq!(
+        weights(get(&app, "/api/heatmap?weight=count").await),
+        [
+            point("locations", 31623.0),
+            point("locations", 31623.0),
+            point("locations", 3162278.0),
+            point("aphis_reports", 1863.0),
+        ]
+    );
+    assert_eq!(
+        weights(get(&app, "/api/heatmap?weight=count&species=chicken").await),
+        [point("locations", 3162278.0)]
+    );
+    assert_eq!(
+        get(&app, "/api/heatmap?weight=mass").await.status,
+        StatusCode::BAD_REQUEST
+    );
+}
+
+#[tokio::test]
+async fn subscriptions_refuse_local_webhooks_and_cap_clients() {
+    let settings = Settings::default()
+        .with("SUBSCRIPTIONS_PER_CLIENT", "2")
+        .with("API_KEYS", "map:fixture-key");
+    let state = AppState::load(settings, DataSource::Directory(fixture("")))
+        .await
+        .unwrap();
+    let app = app::router(state, app::cors_from_env().unwrap());
+    let subscribe = |url: &str| serde_json::json!({ "webhook_url": url, "filter": {} });
+
+    for url in [
+        "http://localhost/hook",
+        "http://127.0.0.1:8000/hook",
+        "http://10.1.2.3/hook",
+        "http://169.254.169.254/latest/meta-data/",
+        "http://[::1]/hook",
+        "http://[::ffff:192.168.0.1]/hook",
+        "http://[fd00:ec2::254]/hook",
+        "ftp://93.184.215.14/hook",
+    ] {
+        let response = post_json(&app, "/api/subscriptions", subscribe(url), &[]).await;
+        assert_eq!(response.status, StatusCode::BAD_REQUEST, "{}", url);
+    }
+
+    let public = subscribe("https://93.184.215.14/hook");
+    let mut tokens = BTreeSet::new();
+    for _ in 0..2 {
+        let response = post_json(&app, "/api/subscriptions", public.clone(), &[]).await;
+        assert_eq!(response.status, StatusCode::CREATED, "{}", response.text());
+        let token = response.json()["token"].as_str().unwrap().to_string();
+        assert!(
+            token.len() == 32 && token.chars().all(|c| c.is_ascii_hexdigit()),
+            "{}",
+            token
+        );
+        tokens.insert(token);
+    }
+    assert_eq!(tokens.len(), 2);
+    let response = post_json(&app, "/api/subscriptions", public.clone(), &[]).await;
+    assert_eq!(response.status, StatusCode::TOO_MANY_REQUESTS);
+    // A key is a client of its own.
+    let response = post_json(
+        &app,
+        "/api/subscriptions",
+        public,
+        &[("x-api-key", "fixture-key")],
+    )
+    .await;
+    assert_eq!(response.status, StatusCode::CREATED);
+    assert_eq!(response.json()["client"], "key:map");
+}
+
+#[tokio::test]
+async fn full_queues_refuse_submissions_and_reports() {
+    let dir = std::env::temp_dir().join(format!("full-queues-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let facility = serde_json::json!({
+        "name": "Fixture Farm",
+        "country": "us",
+        "latitude": 40.3,
+        "longitude": -76.4,
+        "activity": "meat_slaughter",
+        "evidence_url": "https://example.org/permit",
+    });
+    let submissions: Vec<Value> = (1..=1000)
+        .map(|id| {
+            let mut submission = facility.clone();
+            submission["id"] = id.into();
+            submission["status"] = "pending".into();
+            submission["submitted_at"] = "2026-01-01T00:00:00Z".into();
+            submission
+        })
+        .collect();
+    let reports: Vec<Value> = (1..=5000)
+        .map(|id| {
+            serde_json::json!({
+                "id": id,
+                "facility_id": "f0",
+                "facility_name": "Fixture Farm",
+                "kind": "closed",
+                "message": "",
+                "reported_at": "2026-01-01T00:00:00Z",
+            })
+        })
+        .collect();
+    std::fs::write(
+        dir.join("submissions.json"),
+        Value::from(submissions).to_string(),
+    )
+    .unwrap();
+    std::fs::write(dir.join("feedback.json"), Value::from(reports).to_string()).unwrap();
+
+    let settings = Settings::default()
+        .with(
+            "SUBMISSIONS_FILE",
+            dir.join("submissions.json").to_str().unwrap(),
+        )
+        .with("FEEDBACK_FILE", dir.join("feedback.json").to_str().unwrap());
+    let state = AppState::load(settings, DataSource::Directory(fixture("")))
+        .await
+        .unwrap();
+    let app = app::router(state, app::cors_from_env().unwrap());
+
+    let response = post_json(&app, "/api/submissions", facility, &[]).await;
+    assert_eq!(
+        response.status,
+        StatusCode::SERVICE_UNAVAILABLE,
+        "{}",
+        response.text()
+    );
+    let id = column(&get(&app, "/api/facilities").await, "id").remove(0);
+    let report = serde_json::json!({ "kind": "closed" });
+    let uri = format!("/api/feedback?facility_id={}", id);
+    let response = post_json(&app, &uri, report, &[]).await;
+    assert_eq!(
+        response.status,
+        StatusCode::SERVICE_UNAVAILABLE,
+        "{}",
+        response.text()
+    );
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[tokio::test]
+async fn subscriptions_can_require_a_key() {
+    let settings = Settings::default().with("SUBSCRIPTIONS_REQUIRE_API_KEY", "1");
+    let state = AppState::load(settings, DataSource::Directory(fixture("")))
+        .await
+        .unwrap();
+    let app = app::router(state, app::cors_from_env().unwrap());
+    let body = serde_json::json!({ "webhook_url": "https://93.184.215.14/hook" });
+    let response = post_json(&app, "/api/subscriptions", body, &[]).await;
+    assert_eq!(response.status, StatusCode::UNAUTHORIZED);
+}
+
+#[tokio::test]
+async fn sqlite_storage_answers_like_memory() {
+    let memory = app().await;
+    let settings = Settings::default().with("STORAGE", "sqlite");
+    let state = AppState::load(settings, DataSource::Directory(fixture("")))
+        .await
+        .unwrap();
+    let sqlite = app::router(state, app::cors_from_env().unwrap());
+    for uri in [
+        "/api/locations?country_code=us",
+        "/api/locations?state=PA,mn",
+        "/api/locations?species=cattle",
+        "/api/locations?species=poultry&format=geojson",
+        "/api/locations?activity=meat_slaughter",
+        "/api/locations?activity=processing&format=csv",
+        "/api/locations?status=active&granted_after=2020-01-01",
+        "/api/locations?granted_before=2020-01-01",
+        "/api/locations?sort=priority_score&order=asc&limit=2&fields=establishment_id",
+        "/api/locations/bbox?min_lat=40&min_lng=-100&max_lat=50&max_lng=-70",
+        "/api/locations/bbox?min_lat=-90&min_lng=10&max_lat=90&max_lng=-170",
+    ] {
+        let expected = get(&memory, uri).await;
+        let actual = get(&sqlite, uri).await;
+        assert_eq!(actual.status, StatusCode::OK, "{}: {}", uri, actual.text());
+        assert_eq!(actual.text(), expected.text(), "{}", uri);
+    }
+    assert_eq!(
+        column(
+            &get(&sqlite, "/api/locations?species=cattle").await,
+            "establishment_id"
+        ),
+        ["BY 20581", "121", "6407", "6165469"]
+    );
+}
+
+#[tokio::test]
+async fn sqlite_export_opens_with_every_record() {
+    let app = app().await;
+    let response = get(&app, "/api/export/sqlite").await;
+    assert_eq!(response.status, StatusCode::OK);
+    let path = std::env::temp_dir().join(format!("export-{}.sqlite", std::process::id()));
+    std::fs::write(&path, &response.body).unwrap();
+    let database = rusqlite::Connection::open(&path).unwrap();
+    let count = |table: &str| -> i64 {
+        database
+            .query_row(&format!("SELECT count(*) FROM \"{}\"", table), [], |row| {
+                row.get(0)
+            })
+            .unwrap()
+    };
+    let (locations, facilities) = (count("locations"), count("facilities"));
+    let us: Vec<String> = database
+        .prepare("SELECT establishment_id FROM locations WHERE country = 'us' ORDER BY rowid")
+        .unwrap()
+        .query_map([], |row| row.get(0))
+        .unwrap()
+        .collect::<Result<_, _>>()
+        .unwrap();
+    let indexes: i64 = database
+        .query_row(
+            "SELECT count(*) FROM sqlite_master WHERE type = 'index' AND tbl_name = 'locations'",
+            [],
+            |row| row.get(0),
+        )
+        .unwrap();
+    drop(database);
+    std::fs::remove_file(&path).unwrap();
+
+    assert_eq!(locations, 6);
+    assert_eq!(facilities, 13);
+    assert_eq!(us.len(), 4, "{:?}", us);
+    assert_eq!(indexes, 6);
+}
+
+#[tokio::test]
+async fn sections_merge_by_the_kind_of_column() {
+    // One establishment listed once per approval section.
+    let sections = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/sections");
+    let state = AppState::from_source(DataSource::Directory(sections))
+        .await
+        .unwrap();
+    let app = app::router(state, app::cors_from_env().unwrap());
+    let response = get(&app, "/api/locations").await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.text());
+    let locations = response.json();
+    assert_eq!(locations.as_array().unwrap().len(), 1, "{}", locations);
+    let location = &locations[0];
+    // Text lists both sections'.
+    assert_eq!(location["type"], "Meat Slaughter; Meat Processing");
+    // A flag set in either section is set, whatever the other says.
+    assert_eq!(location["slaughter"], true);
+    let classes: Vec<&Value> = location["slaughter_classes"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|class| &class["class"])
+        .collect();
+    assert_eq!(classes, ["beef_cow", "steer"]);
+    // Numbers and dates are the first section's.
+    assert_eq!(location["latitude"], 48.40372);
+    assert_eq!(location["longitude"], 13.3107354);
+    assert_eq!(location["grant_date"], "2020-01-01");
+    assert_eq!(location["slaughter_volume_category"], "3.0");
+}
+
+fn copy_dir(from: &Path, to: &Path) {
+    std::fs::create_dir_all(to).unwrap();
+    for entry in std::fs::read_dir(from).unwrap() {
+        let entry = entry.unwrap();
+        if entry.file_type().unwrap().is_dir() {
+            copy_dir(&entry.path(), &to.join(entry.file_name()));
+        } else {
+            std::fs::copy(entry.path(), to.join(entry.file_name())).unwrap();
+        }
+    }
+}
+
+#[tokio::test]
+async fn reloads_snapshot_changed_lists_for_the_changelog() {
+    let dir = std::env::temp_dir().join(format!("reload-snapshots-{}", std::process::id()));
+    copy_dir(&fixture(""), &dir);
+    let settings = Settings::default().with("ADMIN_TOKEN", "fixture-token");
+    let state = AppState::load(settings, DataSource::Directory(dir.clone()))
+        .await
+        .unwrap();
+    let app = app::router(state, app::cors_from_env().unwrap());
+    let reload = || {
+        post_json(
+            &app,
+            "/api/admin/reload",
+            serde_json::json!({}),
+            &[("authorization", "Bearer fixture-token")],
+        )
+    };
+
+    // The German list has one snapshot, so nothing to compare yet.
+    assert_eq!(
+        get(&app, "/api/changelog").await.json(),
+        serde_json::json!([])
+    );
+
+    let first = reload().await;
+    let second = reload().await;
+    let changelog = get(&app, "/api/changelog").await;
+    let today = chrono::Utc::now().format("%Y-%m-%d").to_string();
+    let saved = dir.join("de/snapshots").join(format!("{}.csv", today));
+    let saved_matches =
+        std::fs::read(&saved).ok() == std::fs::read(dir.join("de/locations.csv")).ok();
+    std::fs::remove_dir_all(&dir).unwrap();
+
+    assert_eq!(first.status, StatusCode::OK, "{}", first.text());
+    assert_eq!(
+        first.json()["snapshots"],
+        serde_json::json!(["de", "uk", "us"])
+    );
+    assert!(saved_matches);
+    // Nothing changed since, so nothing more is saved.
+    assert_eq!(second.json()["snapshots"], serde_json::json!([]));
+    let entries = changelog.json();
+    assert_eq!(entries.as_array().unwrap().len(), 1, "{}", entries);
+    assert_eq!(entries[0]["dataset"], "de-locations");
+    assert_eq!(entries[0]["date"], today.as_str());
+    assert_eq!(entries[0]["previous_date"], "2025-01-01");
+    // Both of the renamed establishment's sections.
+    assert_eq!(entries[0]["records_modified"], 2);
+}
+
+#[tokio::test]
+async fn settings_are_passed_not_read_from_the_environment() {
+    let settings = Settings::default()
+        .with("ADMIN_TOKEN", "fixture-token")
+        .with("CORS_ORIGINS", "https://example.org");
+    let cors = app::cors(&settings).unwrap();
+    let state = AppState::load(settings, DataSource::Directory(fixture("")))
+        .await
+        .unwrap();
+    let app = app::router(state, cors);
+    let audit = |token: &'static str| {
+        Request::get("/api/admin/audit")
+            .header(header::AUTHORIZATION, format!("Bearer {}", token))
+            .header(header::ORIGIN, "https://example.org")
+            .body(Body::empty())
+            .unwrap()
+    };
+    let response = app.clone().oneshot(audit("fixture-token")).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(
+        response.headers()[header::ACCESS_CONTROL_ALLOW_ORIGIN],
+        "https://example.org"
+    );
+    let response = app.oneshot(audit("wrong")).await.unwrap();
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    assert!(std::env::var_os("ADMIN_TOKEN").is_none());
+}
+
+/// The fields of `proto/facilities.proto`'s `Facility` the residence test
+/// reads; prost skips the others.
+#[derive(Clone, PartialEq, prost::Message)]
+struct ProtoFacility {
+    #[prost(string, tag = "1")]
+    id: String,
+    #[prost(sint32, tag = "5")]
+    latitude_e6: i32,
+    #[prost(sint32, tag = "6")]
+    longitude_e6: i32,
+    #[prost(string, tag = "10")]
+    street: String,
+    #[prost(bool, tag = "16")]
+    is_residential: bool,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+struct ProtoFacilityList {
+    #[prost(message, repeated, tag = "1")]
+    facilities: Vec<ProtoFacility>,
+}
+
+#[tokio::test]
+async fn residences_are_served_at_their_zip_centroid_in_every_format() {
+    let settings = Settings::default().with("ADMIN_TOKEN", "fixture-token");
+    let state = AppState::load(settings, DataSource::Directory(fixture("")))
+        .await
+        .unwrap();
+    let app = app::router(state, app::cors_from_env().unwrap());
+
+    // Auburn University, licensed at 32.592036, -85.518913 in ZIP 36849,
+    // whose centroid is in us/zip_centroids.csv.
+    let id = heatmap_backend::ids::facility_id("inspection_reports", "us", "64-C-0258");
+    let (latitude, longitude) = (32.60258, -85.488209);
+    let uri = format!("/api/admin/residences/{}", id);
+    let body = serde_json::json!({ "note": "licensed at home" });
+    let response = send_json(
+        &app,
+        Request::put(uri.as_str()),
+        body,
+        &[("authorization", "Bearer fixture-token")],
+    )
+    .await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.text());
+
+    let source = "source=inspection_reports";
+    let response = get(&app, &format!("/api/facilities?{}", source)).await;
+    let facility = response
+        .json()
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|facility| facility["id"] == id.as_str())
+        .cloned()
+        .unwrap();
+    assert_eq!(facility["latitude"], latitude);
+    assert_eq!(facility["longitude"], longitude);
+    assert_eq!(facility["street"], "");
+    assert_eq!(facility["is_residential"], true);
+
+    let response = get(&app, &format!("/api/facilities?{}&format=geojson", source)).await;
+    let features = response.json()["features"].as_array().unwrap().clone();
+    let feature = features
+        .iter()
+        .find(|feature| feature["properties"]["id"] == id.as_str())
+        .unwrap();
+    assert_eq!(
+        feature["geometry"]["coordinates"],
+        serde_json::json!([longitude, latitude])
+    );
+    assert_eq!(feature["properties"]["street"], "");
+
+    let response = get(&app, &format!("/api/facilities?{}&format=csv", source)).await;
+    let mut reader = csv::Reader::from_reader(response.body.as_slice());
+    let headers = reader.headers().unwrap().clone();
+    let row = reader
+        .records()
+        .map(Result::unwrap)
+        .find(|row| row.iter().any(|value| value == id))
+        .unwrap();
+    let cell = |name: &str| row[headers.iter().position(|h| h == name).unwrap()].to_string();
+    assert_eq!(cell("latitude"), latitude.to_string());
+    assert_eq!(cell("longitude"), longitude.to_string());
+    assert_eq!(cell("street"), "");
+
+    let response = get(&app, &format!("/api/export/kml?{}", source)).await;
+    let kml = response.text();
+    assert!(
+        kml.contains(&format!(
+            "<coordinates>{},{}</coordinates>",
+            longitude, latitude
+        )),
+        "{}",
+        kml
+    );
+    assert!(!kml.contains("Samford Hall") && !kml.contains("-85.518913"));
+
+    let response = get(&app, &format!("/api/facilities?{}&format=pb", source)).await;
+    let list = <ProtoFacilityList as prost::Message>::decode(response.body.as_slice()).unwrap();
+    let facility = list.facilities.iter().find(|f| f.id == id).unwrap();
+    assert_eq!(
+        (facility.latitude_e6, facility.longitude_e6),
+        (32_602_580, -85_488_209)
+    );
+    assert_eq!(facility.street, "");
+    assert!(facility.is_residential);
+
+    // The tiles only draw the slaughterhouse locations, so neither the home
+    // nor its centroid shows the licensee.
+    for tile in ["/tiles/14/4299/6621.mvt", "/tiles/14/4301/6620.mvt"] {
+        let response = get(&app, tile).await;
+        assert_eq!(response.status, StatusCode::OK);
+        assert!(!response.text().contains("Auburn University"), "{}", tile);
+    }
+
+    let response = get(&app, "/api/inspection-reports/64-C-0258").await;
+    assert_eq!(response.status, StatusCode::OK);
+    let text = response.text();
+    assert!(text.contains("Auburn University"), "{}", text);
+    assert!(
+        !text.contains("Samford Hall") && !text.contains("-85.518913"),
+        "{}",
+        text
+    );
+}
+
+#[test]
+fn slaughtered_animals_from_mixed_case_flags() {
+    let locations: Vec<Location> = read_csv("us/locations.csv");
+    let animals: Vec<String> = locations.iter().map(get_slaughtered_animals).collect();
+    assert_eq!(
+        animals,
+        [
+            // Steers "Yes", market pigs "YES".
+            "Cattle (Cows, Bulls), Pigs",
+            // Beef cows "yes", heifers "Yes".
+            "Cattle (Cows, Bulls)",
+            "Chickens",
+            // Processing only.
+            "",
+        ]
+    );
+}
+
+#[test]
+fn slaughtered_animals_follow_display_order() {
+    let location = Location {
+        young_turkey_slaughter: true,
+        goat_slaughter: true,
+        steer_slaughter: true,
+        heifer_slaughter: true,
+        ..Default::default()
+    };
+    assert_eq!(
+        get_slaughtered_animals(&location),
+        "Cattle (Cows, Bulls), Goats, Turkeys"
+    );
+    assert_eq!(get_slaughtered_animals(&Location::default()), "");
+}
+
+#[test]
+fn tested_animals_from_decimal_counts() {
+    let reports: Vec<AphisReport> = read_csv("us/aphis_data_final.csv");
+    assert_eq!(
+        get_tested_animals(&reports[0]),
+        "83 Guinea Pigs, 1440 Hamsters, 230 Sheep, 22 Pigs, 88 Other Farm Animals"
+    );
+    // No counts at all.
+    assert_eq!(get_tested_animals(&reports[1]), "Unknown");
+}
+
+#[test]
+fn tested_animals_skip_what_isnt_a_count() {
+    let mut report: AphisReport = read_csv("us/aphis_data_final.csv").remove(0);
+    report.guinea_pigs = Some(0);
+    report.hamsters = None;
+    report.sheep = None;
+    report.pigs = None;
+    report.other_farm_animals = None;
+    report.dogs = Some(12);
+    assert_eq!(get_tested_animals(&report), "12 Dogs");
+
+    let row = "Customer Number_x,Account Name,Certificate Number,Registration Type,\
+        Certificate Status,Status Date,Address Line 1,Address Line 2,City-State-Zip,County,\
+        Customer Number_y,Year,Dogs,Cats,Guinea Pigs,Hamsters,Rabbits,Non-Human Primates,\
+        Sheep,Pigs,Other Farm Animals,All Other Animals,latitude,longitude\n\
+        9,Lab,87-R-0009,Class R,Active,,,,,,9,2024,12.5,-3, 7 ,0.0,,,,,,N/A,40.0,-111.0\n";
+    let report: AphisReport = csv::Reader::from_reader(row.as_bytes())
+        .deserialize()
+        .next()
+        .unwrap()
+        .unwrap();
+    // Fractional and negative counts and "N/A" are no counts.
+    assert_eq!(get_tested_animals(&report), "7 Guinea Pigs");
+}
+
+#[test]
+fn slugs_dont_change_when_a_namesake_is_added() {
+    let facility =
+        |name: &str, id: &'static str| (slug::facility_slug(name, "Tar Heel", "NC"), "us", id);
+    let facilities = vec![
+        facility("Smithfield Foods, Inc.", "M100"),
+        facility("Smithfield Packing", "M200"),
+    ];
+    let slugs = slug::assign_slugs(&facilities);
+    assert_eq!(
+        slugs,
+        [
+            "smithfield-foods-tar-heel-nc-us-m100",
+            "smithfield-packing-tar-heel-nc-us-m200",
+        ]
+    );
+
+    // M100's base was its own; namesakes listed before and after it take
+    // nothing from it.
+    let mut added = vec![facility("Smithfield Foods LLC", "M001")];
+    added.extend(facilities.iter().cloned());
+    added.push(facility("Smithfield Foods", "M300"));
+    let after = slug::assign_slugs(&added);
+    assert_eq!(after[0], "smithfield-foods-tar-heel-nc-us-m001");
+    assert_eq!(after[1..3], slugs[..]);
+    assert_eq!(after[3], "smithfield-foods-tar-heel-nc-us-m300");
+}
@@ -0,0 +1,370 @@
+//! Runs each importer built on `heatmap_backend::importer`, and the other
+//! importers with a fixture, over the sample export in
+//! `tests/fixtures/<importer>/` and compares the CSV it writes with
+//! `expected.csv` there.
+//!
+//! After changing an importer on purpose, regenerate the expected output
+//! with e.g. `cargo run --bin it-salute < tests/fixtures/it-salute/export.csv
+//! > tests/fixtures/it-salute/expected.csv`.
+
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output, Stdio};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+fn fixture(importer: &str, name: &str) -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/fixtures")
+        .join(importer)
+        .join(name)
+}
+
+/// Runs `binary` with `input`, if any, on stdin, recording the run outside
+/// the data.
+fn import(binary: &str, args: &[&str], input: Option<&Path>) -> Output {
+    static RUNS: AtomicUsize = AtomicUsize::new(0);
+    let runs = std::env::temp_dir().join(format!(
+        "import_runs-{}-{}.toml",
+        std::process::id(),
+        RUNS.fetch_add(1, Ordering::Relaxed)
+    ));
+    let mut child = Command::new(binary)
+        .args(args)
+        .env("IMPORT_RUNS_FILE", &runs)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    let mut stdin = child.stdin.take().unwrap();
+    if let Some(input) = input {
+        stdin.write_all(&std::fs::read(input).unwrap()).unwrap();
+    }
+    drop(stdin);
+    let output = child.wait_with_output().unwrap();
+    assert!(
+        output.status.success(),
+        "{} failed: {}",
+        binary,
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let recorded = std::fs::read_to_string(&runs).unwrap_or_default();
+    let _ = std::fs::remove_file(&runs);
+    assert!(recorded.contains("records"), "{} recorded no run", binary);
+    output
+}
+
+fn assert_matches_expected(importer: &str, output: &Output) {
+    let expected = std::fs::read_to_string(fixture(importer, "expected.csv")).unwrap();
+    let actual = String::from_utf8(output.stdout.clone()).unwrap();
+    assert_eq!(actual, expected, "{} output changed", importer);
+}
+
+/// The establishment ids of a locations CSV, in order.
+fn ids(output: &Output) -> Vec<String> {
+    csv::Reader::from_reader(output.stdout.as_slice())
+        .records()
+        .map(|record| record.unwrap()[0].to_string())
+        .collect()
+}
+
+#[test]
+fn da_foedevarestyrelsen() {
+    let input = fixture("da-foedevarestyrelsen", "Smiley_xml.xml");
+    let output = import(
+        env!("CARGO_BIN_EXE_da-foedevarestyrelsen"),
+        &[],
+        Some(&input),
+    );
+    assert_matches_expected("da-foedevarestyrelsen", &output);
+
+    // Ids are the smiley registry numbers, which survive re-exports.
+    assert_eq!(ids(&output), ["921228", "921230", "921232"]);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("Insekter"), "unmapped industry not listed");
+}
+
+#[test]
+fn da_foedevarestyrelsen_skips_missing_coordinates() {
+    let input = fixture("da-foedevarestyrelsen", "Smiley_xml.xml");
+    let output = import(
+        env!("CARGO_BIN_EXE_da-foedevarestyrelsen"),
+        &["--skip-missing-coordinates"],
+        Some(&input),
+    );
+    assert_eq!(ids(&output), ["921228", "921232"]);
+}
+
+#[test]
+fn it_salute() {
+    let input = fixture("it-salute", "export.csv");
+    let output = import(env!("CARGO_BIN_EXE_it-salute"), &[], Some(&input));
+    assert_matches_expected("it-salute", &output);
+
+    // The slaughterhouse approved under two codes is listed once, and the
+    // dairy outside the map's scope is left out.
+    assert_eq!(ids(&output), ["IT 123 M CE", "IT 789 P CE"]);
+}
+
+#[test]
+fn es_rgseaa() {
+    let input = fixture("es-rgseaa", "rgseaa.csv");
+    let output = import(env!("CARGO_BIN_EXE_es-rgseaa"), &[], Some(&input));
+    assert_matches_expected("es-rgseaa", &output);
+
+    // The slaughterhouse registered for two categories is listed once, and
+    // the bakery outside the mapped sectors is left out.
+    assert_eq!(ids(&output), ["10.012345/M", "11.000777/C"]);
+}
+
+#[test]
+fn au_daff() {
+    let input = fixture("au-daff", "establishments.csv");
+    let output = import(env!("CARGO_BIN_EXE_au-daff"), &[], Some(&input));
+    assert_matches_expected("au-daff", &output);
+
+    // The abattoir's two rows are merged with the species of both, and the
+    // honey commodity, which has no mapping, is listed for mapping.
+    assert_eq!(ids(&output), ["123", "456"]);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains("commodity Honey"),
+        "unmapped commodity not listed"
+    );
+}
+
+#[test]
+fn br_sif() {
+    let input = fixture("br-sif", "sif.csv");
+    let output = import(env!("CARGO_BIN_EXE_br-sif"), &[], Some(&input));
+    assert_matches_expected("br-sif", &output);
+
+    // The abatedouro's poultry row adds poultry slaughter to its cattle and
+    // pig slaughter, and the honey warehouse matches no class.
+    assert_eq!(ids(&output), ["1234", "5678"]);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains("class Entreposto de Mel"),
+        "unmapped class not listed"
+    );
+}
+
+#[test]
+fn us_zip_centroids() {
+    let input = fixture("us-zip-centroids", "2024_Gaz_zcta_national.txt");
+    let output = import(env!("CARGO_BIN_EXE_us-zip-centroids"), &[], Some(&input));
+    assert_matches_expected("us-zip-centroids", &output);
+
+    // Sorted by ZIP, with the leading zeros kept; the ZCTA without an
+    // internal point is left out.
+    assert_eq!(ids(&output), ["00601", "02108", "36849"]);
+}
+
+#[test]
+fn us_aphis_pain_categories() {
+    let report = fixture("us-aphis-pain-categories", "87-R-0002.pdf");
+    let output = import(
+        env!("CARGO_BIN_EXE_us-aphis-pain-categories"),
+        &[report.to_str().unwrap()],
+        None,
+    );
+    // Cats' four counts add up, so they are C to F; Dogs' five are B to F.
+    assert_matches_expected("us-aphis-pain-categories", &output);
+}
+
+#[test]
+fn pdf_text_in_page_order_with_each_pages_fonts() {
+    // The fixture's second page comes first in the file, and both pages
+    // call their font F1: a Latin-1 font on the first page and its header
+    // form, a two-byte font with a ToUnicode map on the second.
+    let bytes = std::fs::read(fixture("us-aphis-pain-categories", "87-R-0002.pdf")).unwrap();
+    let lines = heatmap_backend::pdf::extract_text(&bytes).unwrap();
+    assert_eq!(
+        lines,
+        [
+            "Annual Report of Research Facility",
+            "Registration Number: 87-R-0002",
+            "Fiscal Year 2023",
+            "Dogs 12 30 4 0 46",
+            "Cats 5 7 2 14",
+            "Rabbits 1,440 210 36 8 1,694",
+            "Guinea Pigs 60 0 0 0 60",
+        ]
+    );
+}
+
+#[test]
+fn ingest_archive_reads_every_smiley_export() {
+    // Two smiley exports in one bundle, the second re-encoded as
+    // Windows-1252 and listing 921232 as 921240 instead.
+    let export =
+        std::fs::read_to_string(fixture("da-foedevarestyrelsen", "Smiley_xml.xml")).unwrap();
+    let renumbered = export.replace("921232", "921240");
+    let (second, _, _) = encoding_rs::WINDOWS_1252.encode(&renumbered);
+    let dir = std::env::temp_dir().join(format!("ingest-archive-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let bundle = dir.join("bundle.zip");
+    let mut zip = zip::ZipWriter::new(std::fs::File::create(&bundle).unwrap());
+    let options = zip::write::SimpleFileOptions::default();
+    zip.start_file("Smiley_xml.xml", options).unwrap();
+    zip.write_all(export.as_bytes()).unwrap();
+    zip.start_file("Smiley_xml_2.xml", options).unwrap();
+    zip.write_all(&second).unwrap();
+    zip.finish().unwrap();
+
+    let out = dir.join("out");
+    let output = Command::new(env!("CARGO_BIN_EXE_ingest-archive"))
+        .args([&bundle, &out])
+        .output()
+        .unwrap();
+    let written = std::fs::read_to_string(out.join("dk/locations.csv"));
+    std::fs::remove_dir_all(&dir).unwrap();
+    assert!(
+        output.status.success(),
+        "ingest-archive failed: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    // The establishments both list are merged, and the first export's come
+    // out as the Danish importer writes them.
+    let written = written.unwrap();
+    let ids: Vec<String> = csv::Reader::from_reader(written.as_bytes())
+        .records()
+        .map(|record| record.unwrap()[0].to_string())
+        .collect();
+    assert_eq!(ids, ["921228", "921230", "921232", "921240"]);
+    let expected =
+        std::fs::read_to_string(fixture("da-foedevarestyrelsen", "expected.csv")).unwrap();
+    assert!(written.starts_with(&expected), "{}", written);
+}
+
+#[test]
+fn us_fsis_archive_reads_windows_1252_releases() {
+    // An old release in Windows-1252, matched against the api fixture's
+    // current directory for its IDs and coordinates.
+    let release = fixture(
+        "us-fsis-archive",
+        "MPI_Directory_by_Establishment_Number.csv",
+    );
+    let current = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/api/us/locations.csv");
+    let out = std::env::temp_dir().join(format!("us-fsis-archive-{}", std::process::id()));
+    import(
+        env!("CARGO_BIN_EXE_us-fsis-archive"),
+        &[
+            "--out",
+            out.to_str().unwrap(),
+            "--current",
+            current.to_str().unwrap(),
+            &format!("2019-03-01={}", release.display()),
+        ],
+        None,
+    );
+    let written = std::fs::read_to_string(out.join("2019-03-01.csv"));
+    std::fs::remove_dir_all(&out).unwrap();
+    let expected = std::fs::read_to_string(fixture("us-fsis-archive", "expected.csv")).unwrap();
+    assert_eq!(written.unwrap(), expected);
+    assert!(expected.contains("Carnicería La Peña"));
+}
+
+#[test]
+fn us_fsis_archive_keeps_the_current_columns() {
+    // Releases have no species or volume columns, nor the directory's
+    // columns `Location` doesn't know; those come from the current row.
+    let release = fixture(
+        "us-fsis-archive",
+        "MPI_Directory_by_Establishment_Number.csv",
+    );
+    let out = std::env::temp_dir().join(format!("us-fsis-keep-columns-{}", std::process::id()));
+    let run = |current: &Path| {
+        Command::new(env!("CARGO_BIN_EXE_us-fsis-archive"))
+            .args(["--out", out.to_str().unwrap(), "--current"])
+            .arg(current)
+            .arg("--keep-columns")
+            .arg(format!("2025-06-01={}", release.display()))
+            .env("IMPORT_RUNS_FILE", out.join("runs.toml"))
+            .output()
+            .unwrap()
+    };
+
+    let output = run(&fixture("us-fsis-archive", "current.csv"));
+    let written = std::fs::read_to_string(out.join("2025-06-01.csv"));
+    std::fs::remove_dir_all(&out).unwrap();
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let written = written.unwrap();
+    let mut reader = csv::Reader::from_reader(written.as_bytes());
+    let headers = reader.headers().unwrap().clone();
+    let row = reader.records().next().unwrap().unwrap();
+    let value = |column: &str| &row[headers.iter().position(|h| h == column).unwrap()];
+    assert_eq!(value("establishment_id"), "6407");
+    assert_eq!(value("grant_date"), "2011-11-02");
+    assert_eq!(value("steer_slaughter"), "Yes");
+    assert_eq!(value("slaughter_volume_category"), "3.0");
+    assert_eq!(value("processing"), "Yes");
+
+    // The api fixture's other establishments fill columns this release's
+    // two would leave blank throughout, so nothing is written.
+    let current = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/api/us/locations.csv");
+    let output = run(&current);
+    let written = out.join("2025-06-01.csv").exists();
+    std::fs::remove_dir_all(&out).unwrap();
+    assert!(!output.status.success());
+    assert!(!written);
+    assert!(
+        String::from_utf8_lossy(&output.stderr).contains("would be blank in every row"),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+}
+
+#[test]
+fn ingest_archive_writes_a_data_directory() {
+    let api = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/api/us");
+    let dir = std::env::temp_dir().join(format!("ingest-layout-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let bundle = dir.join("bundle.zip");
+    let mut zip = zip::ZipWriter::new(std::fs::File::create(&bundle).unwrap());
+    let options = zip::write::SimpleFileOptions::default();
+    for (name, path) in [
+        ("MPI.csv", api.join("locations.csv")),
+        ("annual.csv", api.join("aphis_data_final.csv")),
+        ("inspections.csv", api.join("inspection_reports.csv")),
+        (
+            "Smiley_xml.xml",
+            fixture("da-foedevarestyrelsen", "Smiley_xml.xml"),
+        ),
+    ] {
+        zip.start_file(name, options).unwrap();
+        zip.write_all(&std::fs::read(path).unwrap()).unwrap();
+    }
+    zip.finish().unwrap();
+
+    let out = dir.join("out");
+    let output = Command::new(env!("CARGO_BIN_EXE_ingest-archive"))
+        .args([&bundle, &out])
+        .output()
+        .unwrap();
+    let dataset = heatmap_backend::store::Dataset::from_directory(&out);
+    std::fs::remove_dir_all(&dir).unwrap();
+    assert!(
+        output.status.success(),
+        "ingest-archive failed: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    let dataset = dataset.unwrap();
+    let paths: Vec<&str> = dataset.files().map(|(path, _)| path).collect();
+    assert_eq!(
+        paths,
+        [
+            "dk/locations.csv",
+            "us/aphis_data_final.csv",
+            "us/inspection_reports.csv",
+            "us/locations.csv",
+        ]
+    );
+    assert_eq!(dataset.countries(), ["dk", "us"]);
+}